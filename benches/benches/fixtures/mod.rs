@@ -0,0 +1,134 @@
+//! Synthetic, deterministic fixtures roughly shaped like JDK and Android SDK classes, so benchmarks don't depend on
+//! whatever JDKs or SDKs happen to be installed.
+
+#![allow(dead_code)] // Not every bench uses every fixture
+
+use jreflection::jar;
+
+use std::collections::*;
+use std::path::PathBuf;
+
+
+
+/// Representative generic signatures, as found in `rt.jar`.
+pub const SIGNATURES : &[&str] = &[
+    "<E:Ljava/lang/Object;>Ljava/util/AbstractList<TE;>;Ljava/util/List<TE;>;Ljava/util/RandomAccess;Ljava/lang/Cloneable;Ljava/io/Serializable;",
+    "<K:Ljava/lang/Object;V:Ljava/lang/Object;>Ljava/util/AbstractMap<TK;TV;>;Ljava/util/Map<TK;TV;>;Ljava/lang/Cloneable;Ljava/io/Serializable;",
+    "<T:Ljava/lang/Object;>(Ljava/util/Collection<+TT;>;Ljava/util/Comparator<-TT;>;)TT;",
+    "<T::Ljava/lang/Comparable<-TT;>;>(Ljava/util/List<TT;>;)V",
+    "(Ljava/util/function/Function<-TT;+Ljava/util/stream/Stream<+TR;>;>;)Ljava/util/stream/Stream<TR;>;",
+    "Ljava/util/Map<Ljava/lang/String;Ljava/util/List<Ljava/lang/Integer;>;>;",
+    "Ljava/util/HashMap<TK;TV;>.Node<TK;TV;>;",
+    "<X:Ljava/lang/Throwable;>(Ljava/util/function/Supplier<+TX;>;)TT;^TX;",
+];
+
+/// `count` synthetic classes, as `(JNI path, .class bytes)`, each with a handful of generic fields and methods with code.
+pub fn classes(count: usize) -> Vec<(String, Vec<u8>)> {
+    (0..count).map(|i| {
+        let path = format!("java/synthetic/p{}/Class{}", i % 16, i);
+        let bytes = class(&path, i);
+        (path, bytes)
+    }).collect()
+}
+
+/// Write `count` synthetic classes (and a few resources) into a jar under the temp directory, returning its path.
+pub fn jar(name: &str, count: usize) -> PathBuf {
+    let dir = std::env::temp_dir().join("jreflection-benches");
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(format!("{}.jar", name));
+    let mut jar = jar::Writer::create(&path).unwrap();
+    jar.add_manifest(b"Manifest-Version: 1.0\r\n".to_vec()).unwrap();
+    for (class, bytes) in classes(count) { jar.add_class(&class, bytes).unwrap(); }
+    for i in 0..(count / 50) { jar.add_resource(&format!("res/strings{}.properties", i), vec![b'x'; 512]).unwrap(); }
+    jar.finish().unwrap();
+    path
+}
+
+fn class(path: &str, seed: usize) -> Vec<u8> {
+    let mut c = Constants::default();
+    let this        = c.class(path);
+    let super_class = c.class("java/lang/Object");
+    let iface       = c.class(if seed % 2 == 0 { "java/util/RandomAccess" } else { "java/io/Serializable" });
+    let signature   = c.utf8("Signature");
+    let code        = c.utf8("Code");
+    let init        = c.methodref("java/lang/Object", "<init>", "()V");
+    let size        = c.interface_methodref("java/util/List", "size", "()I");
+    let out         = c.fieldref("java/lang/System", "out", "Ljava/io/PrintStream;");
+
+    let mut fields = Vec::new();
+    for f in 0..4 {
+        let name = c.utf8(&format!("field{}", f));
+        let desc = c.utf8(if f % 2 == 0 { "Ljava/util/List;" } else { "I" });
+        let sig  = c.utf8("Ljava/util/List<Ljava/lang/String;>;");
+        fields.push([be16(0x0002), be16(name), be16(desc), be16(1), be16(signature), be32(2), be16(sig)].concat());
+    }
+
+    let mut methods = Vec::new();
+    for m in 0..(4 + seed % 8) {
+        let name = c.utf8(if m == 0 { "<init>".to_owned() } else { format!("method{}", m) }.as_str());
+        let desc = c.utf8(if m == 0 { "()V" } else { "(Ljava/util/List;I)Ljava/lang/String;" });
+        let sig  = c.utf8(SIGNATURES[2 + m % 3]); // method signatures
+        let bytecode = if m == 0 {
+            [vec![0x2A, 0xB7], be16(init), vec![0xB1]].concat()
+        } else {
+            [vec![0xB2], be16(out), vec![0x2B, 0xB9], be16(size), vec![1, 0, 0x57, 0x57, 0x01, 0xB0]].concat()
+        };
+        let code_info = [be16(2), be16(3), be32(bytecode.len() as u32), bytecode, be16(0), be16(0)].concat();
+        let attributes = if m == 0 { 1 } else { 2 };
+        let mut method = [be16(0x0001), be16(name), be16(desc), be16(attributes), be16(code), be32(code_info.len() as u32), code_info].concat();
+        if m != 0 { method.extend([be16(signature), be32(2), be16(sig)].concat()); }
+        methods.push(method);
+    }
+
+    [
+        be32(0xCAFEBABE), be16(0), be16(52),
+        be16(c.count), c.bytes,
+        be16(0x0021), be16(this), be16(super_class),
+        be16(1), be16(iface),
+        be16(fields.len() as u16), fields.concat(),
+        be16(methods.len() as u16), methods.concat(),
+        be16(0),
+    ].concat()
+}
+
+struct Constants {
+    bytes:  Vec<u8>,
+    count:  u16,
+    utf8s:  BTreeMap<String, u16>,
+}
+
+impl Default for Constants {
+    fn default() -> Self { Self { bytes: Vec::new(), count: 1, utf8s: BTreeMap::new() } }
+}
+
+impl Constants {
+    fn push(&mut self, tag: u8, info: &[u8]) -> u16 {
+        self.bytes.push(tag);
+        self.bytes.extend_from_slice(info);
+        self.count += 1;
+        self.count - 1
+    }
+
+    fn utf8(&mut self, s: &str) -> u16 {
+        if let Some(&index) = self.utf8s.get(s) { return index; }
+        let index = self.push(1, &[be16(s.len() as u16), s.as_bytes().to_vec()].concat());
+        self.utf8s.insert(s.to_owned(), index);
+        index
+    }
+
+    fn class(&mut self, name: &str) -> u16 { let n = self.utf8(name); self.push(7, &be16(n)) }
+
+    fn member_ref(&mut self, tag: u8, class: &str, name: &str, descriptor: &str) -> u16 {
+        let c = self.class(class);
+        let (n, d) = (self.utf8(name), self.utf8(descriptor));
+        let nt = self.push(12, &[be16(n), be16(d)].concat());
+        self.push(tag, &[be16(c), be16(nt)].concat())
+    }
+
+    fn fieldref(&mut self, class: &str, name: &str, descriptor: &str) -> u16 { self.member_ref(9, class, name, descriptor) }
+    fn methodref(&mut self, class: &str, name: &str, descriptor: &str) -> u16 { self.member_ref(10, class, name, descriptor) }
+    fn interface_methodref(&mut self, class: &str, name: &str, descriptor: &str) -> u16 { self.member_ref(11, class, name, descriptor) }
+}
+
+fn be16(v: u16) -> Vec<u8> { v.to_be_bytes().to_vec() }
+fn be32(v: u32) -> Vec<u8> { v.to_be_bytes().to_vec() }
@@ -0,0 +1,125 @@
+//! Run with `cargo bench` from the `benches` directory, a separate crate so criterion doesn't raise jreflection's MSRV.
+//! To guard against regressions, save a baseline before your change and compare against it after:
+//!
+//! ```text
+//! git checkout master && cargo bench -- --save-baseline master
+//! git checkout my-branch && cargo bench -- --baseline master
+//! ```
+//!
+//! Benchmarks use synthetic fixtures by default.  Set `JREFLECTION_BENCH_JDK` to a JDK 8 directory to also benchmark
+//! parsing a subset of its real `rt.jar`, and real `android.jar`s are benchmarked if an Android SDK is discovered.
+
+mod fixtures;
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use jreflection::*;
+use jreflection::signature::{ClassSignature, MethodSignature, Type};
+
+use std::collections::*;
+
+
+
+fn parse(c: &mut Criterion) {
+    let classes = fixtures::classes(500);
+    let bytes = classes.iter().map(|(_, b)| b.len() as u64).sum();
+
+    let mut group = c.benchmark_group("parse");
+    group.throughput(Throughput::Bytes(bytes));
+    group.bench_function("synthetic/read", |b| b.iter(|| {
+        for (_, bytes) in classes.iter() { Class::read(&mut &bytes[..]).unwrap(); }
+    }));
+
+    let mut retain_code = ParseOptions::new();
+    retain_code.retain_code(true);
+    group.bench_function("synthetic/code", |b| b.iter(|| {
+        for (_, bytes) in classes.iter() { Class::read_with(&mut &bytes[..], &retain_code).unwrap(); }
+    }));
+
+    group.bench_function("synthetic/peek", |b| b.iter(|| {
+        for (_, bytes) in classes.iter() { Class::peek(&bytes[..]).unwrap(); }
+    }));
+    group.finish();
+
+    if let Some(jdk) = std::env::var_os("JREFLECTION_BENCH_JDK") {
+        let src = Source::from_jdk_dir(jdk).unwrap();
+        let subset = src.classes::<BTreeSet<String>>().unwrap().into_iter().filter(|c| c.starts_with("java/util/")).collect::<Vec<_>>();
+        let subset = subset.iter().map(|c| src.read_class_bytes(c).unwrap()).collect::<Vec<_>>();
+        c.bench_function("parse/rt.jar java/util", |b| b.iter(|| {
+            for bytes in subset.iter() { Class::read(&mut &bytes[..]).unwrap(); }
+        }));
+    }
+}
+
+fn enumerate(c: &mut Criterion) {
+    let path = fixtures::jar("enumerate", 2000);
+    let mut group = c.benchmark_group("enumerate");
+    for &io in [IoStrategy::Buffered, IoStrategy::InMemory].iter() {
+        group.bench_function(format!("synthetic/{:?}", io), |b| b.iter(|| {
+            let src = Source::from_jar_with(&path, io).unwrap();
+            src.classes::<Vec<String>>().unwrap()
+        }));
+    }
+    group.bench_function("synthetic/read_all", |b| b.iter_batched(
+        || Source::from_jar(&path).unwrap(),
+        |src| src.for_each_class(|class| src.read_class(class).map(|_| ())).unwrap(),
+        BatchSize::LargeInput,
+    ));
+    group.finish();
+
+    if let Some(platform) = android::discover_sdk().and_then(|sdk| sdk.platforms()).ok().and_then(|p| p.into_iter().last()) {
+        c.bench_function(&format!("enumerate/android-{}", platform.api_level), |b| b.iter(|| {
+            platform.source().unwrap().classes::<Vec<String>>().unwrap()
+        }));
+    }
+}
+
+fn io_strategy(c: &mut Criterion) {
+    // InMemory vs Buffered at sizes around IoStrategy::AUTO_IN_MEMORY_JAR_LIMIT, reading every class or just ten
+    let mut group = c.benchmark_group("io_strategy");
+    group.sample_size(10);
+    for &classes in [500, 4000, 16000, 64000].iter() {
+        let path = fixtures::jar(&format!("io-strategy-{}", classes), classes);
+        let mb = std::fs::metadata(&path).unwrap().len() as f64 / (1024.0 * 1024.0);
+        for &io in [IoStrategy::Buffered, IoStrategy::InMemory].iter() {
+            group.bench_function(format!("{:.1}MB/read_all/{:?}", mb, io), |b| b.iter(|| {
+                let src = Source::from_jar_with(&path, io).unwrap();
+                src.for_each_class(|class| src.read_class_bytes(class).map(|_| ())).unwrap();
+            }));
+            group.bench_function(format!("{:.1}MB/read_10/{:?}", mb, io), |b| b.iter(|| {
+                let src = Source::from_jar_with(&path, io).unwrap();
+                for i in (0..classes).step_by(classes / 10) { src.read_class_bytes(format!("java/synthetic/p{}/Class{}", i % 16, i)).unwrap(); }
+            }));
+        }
+    }
+    group.finish();
+}
+
+fn scan(c: &mut Criterion) {
+    let classes = fixtures::classes(500);
+
+    // ScanResult::add_source parses straight into the arenas - compare against parsing a Class first
+    let mut group = c.benchmark_group("scan");
+    group.bench_function("synthetic/add_class", |b| b.iter(|| {
+        let mut scan = scan::ScanResult::new();
+        for (_, bytes) in classes.iter() { scan.add_class(&Class::read(&mut &bytes[..]).unwrap()).unwrap(); }
+        scan
+    }));
+    group.bench_function("synthetic/add_class_bytes", |b| b.iter(|| {
+        let mut scan = scan::ScanResult::new();
+        for (_, bytes) in classes.iter() { scan.add_class_bytes(&bytes[..]).unwrap(); }
+        scan
+    }));
+    group.finish();
+}
+
+fn signatures(c: &mut Criterion) {
+    c.bench_function("signature/parse", |b| b.iter(|| {
+        for signature in fixtures::SIGNATURES.iter() {
+            // Try each grammar, as the fixtures mix class, method, and field signatures
+            let _ = ClassSignature::parse(signature).is_ok() || MethodSignature::parse(signature).is_ok() || Type::parse(signature).is_ok();
+        }
+    }));
+}
+
+criterion_group!(benches, parse, enumerate, io_strategy, scan, signatures);
+criterion_main!(benches);
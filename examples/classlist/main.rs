@@ -1,12 +1,13 @@
 use jreflection::*;
 use std::collections::*;
 use std::path::*;
-use std::io::{self, BufWriter, Write};
+use std::io::{self, BufWriter};
 use std::time::Instant;
 
-const CLASSES_PLACEHOLDER : &'static str = "{CLASSES}";
 const TEMPLATE_MD : &'static str = include_str!("template.md");
 const TEMPLATE_HTML : &'static str = include_str!("template.html");
+const TEMPLATE_INDEX_MD : &'static str = include_str!("template-index.md");
+const TEMPLATE_PACKAGE_MD : &'static str = include_str!("template-package.md");
 
 #[derive(Clone, Copy)]
 enum Consistency<T: PartialEq> {
@@ -80,23 +81,33 @@ impl Classes {
     }
 
     pub fn write_markdown_to(&self, path: impl AsRef<Path>) -> io::Result<()> {
-        self.write_template_to(path, TEMPLATE_MD)
+        self.write_template_to(path, TEMPLATE_MD, report::Escape::Html) // The table is inline HTML
     }
 
     pub fn write_html_to(&self, path: impl AsRef<Path>) -> io::Result<()> {
-        self.write_template_to(path, TEMPLATE_HTML)
+        self.write_template_to(path, TEMPLATE_HTML, report::Escape::Html)
     }
 
-    fn write_template_to(&self, path: impl AsRef<Path>, template: &str) -> io::Result<()> {
+    /// One page per package plus an index, so a whole JDK's worth of classes remains navigable (and diffable.)
+    pub fn write_markdown_pages_to(&self, dir: impl AsRef<Path>) -> io::Result<()> {
+        let dir = dir.as_ref();
+        println!("Writing {} classes to {}...", self.0.len(), dir.display());
+        let index = report::Template::parse(TEMPLATE_INDEX_MD)?;
+        let page  = report::Template::parse(TEMPLATE_PACKAGE_MD)?;
+
+        let mut pages = report::Pages::new();
+        for (name, data) in self.0.iter().filter(|(_, data)| data.is_public.into_consistent().unwrap_or(true)) {
+            Self::set_row(pages.page(&name[..name.rfind('/').unwrap_or(0)]).row("CLASSES"), name, data);
+        }
+        pages.write(dir, &index, &page, "md", report::Escape::Html)
+    }
+
+    fn write_template_to(&self, path: impl AsRef<Path>, template: &str, escape: report::Escape) -> io::Result<()> {
         let path = path.as_ref();
         println!("Writing {} classes to {}...", self.0.len(), path.display());
-        let classes_placeholder_index = template.find(CLASSES_PLACEHOLDER).expect("{CLASSES} placeholder missing from template");
-        let (template_pre, template_post) = template.split_at(classes_placeholder_index);
-        let template_post = &template_post[CLASSES_PLACEHOLDER.len()..];
-
-        let mut classes_md = BufWriter::new(std::fs::File::create(path)?);
-        write!(classes_md, "{}", template_pre)?;
+        let template = report::Template::parse(template)?;
 
+        let mut values = report::Values::new();
         let mut public = 0;
         for (name, data) in self.0.iter() {
             if !data.is_public.into_consistent().unwrap_or(true) {
@@ -104,52 +115,54 @@ impl Classes {
             }
 
             public += 1;
-            write!(classes_md, "    <tr><td>{}", name)?;
-            for (col,               max) in [
-                (data.java_jdk,     13),
-                (data.java_jre,      8),
-                (data.aojdk_jdk,    13),
-                (data.aojdk_jre,    13),
-                (data.android_adk,  29),
-            ].iter().copied() {
-                write!(classes_md, "</td><td>")?;
-                Self::write_versions_column(&mut classes_md, col, max)?;
-            }
-            writeln!(classes_md, "</td></tr>")?;
+            Self::set_row(values.row("CLASSES"), name, data);
         }
 
-        write!(classes_md, "{}", template_post)?;
+        template.write(&mut BufWriter::new(std::fs::File::create(path)?), &values, escape)?;
 
         println!("    {} were public", public);
         Ok(())
     }
 
-    fn write_versions_column(md: &mut impl Write, versions: u64, max : u64) -> io::Result<()> {
-        let mut anything = false;
+    fn set_row(row: &mut report::Values, name: &str, data: &Class) {
+        row.set("NAME", name);
+        row.set("PACKAGE", name[..name.rfind('/').unwrap_or(0)].replace('/', "."));
+        for (placeholder,       col,                max) in [
+            ("JAVA_JDK",        data.java_jdk,      13),
+            ("JAVA_JRE",        data.java_jre,       8),
+            ("AOJDK_JDK",       data.aojdk_jdk,     13),
+            ("AOJDK_JRE",       data.aojdk_jre,     13),
+            ("ANDROID_ADK",     data.android_adk,   29),
+        ].iter().copied() {
+            row.set(placeholder, Self::versions_column(col, max));
+        }
+    }
+
+    fn versions_column(versions: u64, max : u64) -> String {
+        let mut column = String::new();
         let mut bit = 0;
         while bit < 64 {
             if (1<<bit) & versions != 0 {
                 // Found a set bit!
-                if anything { write!(md, ", ")? }
-                anything = true;
+                if !column.is_empty() { column.push_str(", "); }
 
                 let start = bit;
                 while (bit < 64) && ((1<<bit) & versions != 0) { bit += 1; }
                 let end = bit; // NOTE: non-inclusive
 
                 if end-1 == start {
-                    write!(md, "{}", start)?;
+                    column.push_str(&format!("{}", start));
                 } else if end - 1 == max {
-                    write!(md, "{}+", start)?;
+                    column.push_str(&format!("{}+", start));
                 } else {
-                    write!(md, "{}-{}", start, end-1)?;
+                    column.push_str(&format!("{}-{}", start, end-1));
                 }
             } else {
                 bit += 1;
             }
         }
-        if !anything { write!(md, " ")?; }
-        Ok(())
+        if column.is_empty() { column.push(' '); }
+        column
     }
 }
 
@@ -172,9 +185,11 @@ fn main() {
     classes.injest_jdk_dir(r"C:\Program Files\Java\jdk1.8.0_231",   |c| c.java_jdk |= 1 <<  8);
     classes.injest_jdk_dir(r"C:\Program Files\Java\jre1.8.0_241",   |c| c.java_jre |= 1 <<  8);
 
-    let adk = PathBuf::from(std::env::var_os("LOCALAPPDATA").expect("%LOCALAPPDATA% not set")).join(r"Android\Sdk\platforms");
-    for sdk in 7..=29 {
-        classes.injest_jar(adk.join(format!("android-{}\\android.jar", sdk)), |c| c.android_adk |= 1 << sdk);
+    let sdk = android::discover_sdk().expect("Unable to find the Android SDK");
+    for platform in sdk.platforms().expect("Unable to enumerate Android SDK platforms") {
+        let api_level = platform.api_level;
+        if api_level >= 64 { continue; } // Doesn't fit in our bitmask
+        classes.injest_jar(&platform.jar, move |c| c.android_adk |= 1 << api_level);
     }
 
     // Fixup
@@ -193,6 +208,7 @@ fn main() {
     // Output
     classes.write_markdown_to("classes.md").expect("Failed to write classes.md");
     classes.write_html_to("classes.html").expect("Failed to write classes.html");
+    classes.write_markdown_pages_to("classes").expect("Failed to write classes/");
     let end = Instant::now();
     println!("Took {:?}", end-start);
 }
@@ -0,0 +1,361 @@
+//! Widening (and narrowing) the access of classes and members when rewriting jars, as configured by Forge-style
+//! access transformers and Fabric-style access wideners.  See [AccessTransformer](struct.AccessTransformer.html).
+
+use crate::io::be::*;
+use crate::transform::{ClassFile, Pass};
+#[cfg(test)] use crate::{testing, transform, Class};
+
+use std::collections::*;
+use std::io::{self, Error, ErrorKind};
+
+
+
+/// The access level of a class or member.  Ordered from most to least restrictive.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Access {
+    /// `private`
+    Private,
+    /// No modifier:  accessible within the same package.
+    Package,
+    /// `protected`
+    Protected,
+    /// `public`
+    Public,
+}
+
+impl Access {
+    /// The access of raw `access_flags`.
+    pub fn from_flags(flags: u16) -> Self {
+        if flags & PUBLIC != 0          { Access::Public }
+        else if flags & PROTECTED != 0  { Access::Protected }
+        else if flags & PRIVATE != 0    { Access::Private }
+        else                            { Access::Package }
+    }
+
+    /// Replace the access bits of raw `access_flags` with this access.
+    pub fn apply(self, flags: u16) -> u16 {
+        flags & !(PUBLIC | PROTECTED | PRIVATE) | match self {
+            Access::Public      => PUBLIC,
+            Access::Protected   => PROTECTED,
+            Access::Private     => PRIVATE,
+            Access::Package     => 0,
+        }
+    }
+}
+
+const PUBLIC    : u16 = 0x0001;
+const PRIVATE   : u16 = 0x0002;
+const PROTECTED : u16 = 0x0004;
+const STATIC    : u16 = 0x0008;
+const FINAL     : u16 = 0x0010;
+
+/// A [Pass](../transform/trait.Pass.html) adjusting the access and final-ness of classes and members, as configured by
+/// [Forge access transformers](https://docs.minecraftforge.net/en/latest/advanced/accesstransformers/) and
+/// [Fabric access wideners](https://fabricmc.net/wiki/tutorial:accesswideners).
+///
+/// Rules are applied in the order they were added.  Classes are adjusted both in their own `access_flags` and in the
+/// `InnerClasses` attributes of any transformed class that lists them, since that's where `javac` looks for the access
+/// of nested classes.  Only declarations change:  call sites (such as `invokespecial`s of formerly private methods)
+/// are left as-is.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use jreflection::*;
+/// let mut transformer = access::AccessTransformer::new();
+/// transformer.add_forge(&std::fs::read_to_string("accesstransformer.cfg").unwrap()).unwrap();
+/// transformer.add_fabric(&std::fs::read_to_string("example.accesswidener").unwrap()).unwrap();
+///
+/// let mut pipeline = transform::Pipeline::new();
+/// pipeline.add(transformer); // Not filtered by classes(), so every class's InnerClasses are updated
+/// pipeline.apply_jar("minecraft.jar", "minecraft-widened.jar").unwrap();
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct AccessTransformer {
+    rules: Vec<Rule>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Rule {
+    class:      String,
+    target:     Target,
+    access:     Option<Access>,
+    widen_only: bool,
+    finality:   Finality,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Target {
+    Class,
+    Field   { name: Option<String>, descriptor: Option<String> },
+    Method  { name: Option<String>, descriptor: Option<String> },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Finality {
+    Keep,
+    Add,
+    Remove,
+    AddIfPrivate, // Fabric makes formerly private methods final, so they can't be accidentally overridden
+}
+
+impl AccessTransformer {
+    /// Create a transformer without any rules.
+    pub fn new() -> Self { Self::default() }
+
+    /// Add the rules of a Forge `accesstransformer.cfg`, such as:
+    ///
+    /// ```text
+    /// public net.minecraft.world.level.Level f_46443_ # isClientSide
+    /// public-f net.minecraft.world.entity.Entity f_19853_
+    /// protected net.minecraft.client.Minecraft m_91279_()V
+    /// public net.minecraft.world.item.Item$Properties
+    /// public net.minecraft.world.level.block.Blocks *
+    /// public net.minecraft.world.level.block.Blocks *()
+    /// ```
+    ///
+    /// The access is set exactly as given (`public`, `protected`, `default`, or `private`), with `-f` removing and
+    /// `+f` adding `final`.  `*` and `*()` match every field and method of the class respectively.
+    pub fn add_forge(&mut self, text: &str) -> io::Result<&mut Self> {
+        for (n, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() { continue; }
+            let invalid = |reason: &str| Error::new(ErrorKind::InvalidData, format!("Invalid access transformer at line {}: {}: {:?}", n + 1, reason, line));
+
+            let words = line.split_whitespace().collect::<Vec<_>>();
+            if words.len() < 2 || words.len() > 3 { return Err(invalid("expected <access> <class> [member]")); }
+            let (modifier, finality) = if words[0].ends_with("-f") {
+                (&words[0][..words[0].len()-2], Finality::Remove)
+            } else if words[0].ends_with("+f") {
+                (&words[0][..words[0].len()-2], Finality::Add)
+            } else {
+                (words[0], Finality::Keep)
+            };
+            let access = match modifier {
+                "public"    => Access::Public,
+                "protected" => Access::Protected,
+                "default"   => Access::Package,
+                "private"   => Access::Private,
+                _           => return Err(invalid("expected public, protected, default, or private")),
+            };
+            let target = match words.get(2) {
+                None        => Target::Class,
+                Some(&"*")  => Target::Field { name: None, descriptor: None },
+                Some(&"*()") => Target::Method { name: None, descriptor: None },
+                Some(member) => match member.find('(') {
+                    Some(paren) => Target::Method { name: Some(member[..paren].to_owned()), descriptor: Some(member[paren..].to_owned()) },
+                    None        => Target::Field { name: Some((*member).to_owned()), descriptor: None },
+                },
+            };
+            self.rules.push(Rule { class: words[1].replace('.', "/"), target, access: Some(access), widen_only: false, finality });
+        }
+        Ok(self)
+    }
+
+    /// Add the rules of a Fabric `.accesswidener` file (v1 or v2), such as:
+    ///
+    /// ```text
+    /// accessWidener v2 named
+    /// accessible class net/minecraft/world/item/Item$Properties
+    /// extendable method net/minecraft/world/entity/Entity tick ()V
+    /// accessible field net/minecraft/world/level/Level isClientSide Z
+    /// mutable field net/minecraft/world/level/Level isClientSide Z
+    /// transitive-accessible method net/minecraft/client/Minecraft getInstance ()Lnet/minecraft/client/Minecraft;
+    /// ```
+    ///
+    /// * `accessible` makes classes, fields, and methods public (and formerly private methods final.)
+    /// * `extendable` makes classes public and non-final, and methods at least protected and non-final.
+    /// * `mutable` makes fields non-final.
+    ///
+    /// The namespace of the header isn't checked:  names must simply match the classes being transformed.
+    pub fn add_fabric(&mut self, text: &str) -> io::Result<&mut Self> {
+        let mut header = false;
+        for (n, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() { continue; }
+            let invalid = |reason: &str| Error::new(ErrorKind::InvalidData, format!("Invalid access widener at line {}: {}: {:?}", n + 1, reason, line));
+
+            let words = line.split_whitespace().collect::<Vec<_>>();
+            if !header {
+                if words.len() != 3 || words[0] != "accessWidener" { return Err(invalid("expected accessWidener <version> <namespace> header")); }
+                if words[1] != "v1" && words[1] != "v2" { return Err(invalid("unsupported version")); }
+                header = true;
+                continue;
+            }
+
+            let directive = if words[0].starts_with("transitive-") { &words[0]["transitive-".len()..] } else { words[0] };
+            let kind = words.get(1).cloned().unwrap_or("");
+            let target = match (kind, words.len()) {
+                ("class",  3) => Target::Class,
+                ("field",  5) => Target::Field  { name: Some(words[3].to_owned()), descriptor: Some(words[4].to_owned()) },
+                ("method", 5) => Target::Method { name: Some(words[3].to_owned()), descriptor: Some(words[4].to_owned()) },
+                ("class", _) | ("field", _) | ("method", _) => return Err(invalid("expected <access> class <class> or <access> field|method <class> <name> <descriptor>")),
+                _ => return Err(invalid("expected class, field, or method")),
+            };
+            let (access, widen_only, finality) = match (directive, &target) {
+                ("accessible", Target::Method { .. })   => (Some(Access::Public), true, Finality::AddIfPrivate),
+                ("accessible", _)                       => (Some(Access::Public), true, Finality::Keep),
+                ("extendable", Target::Field { .. })    => return Err(invalid("fields can't be extendable")),
+                ("extendable", Target::Class)           => (Some(Access::Public), true, Finality::Remove),
+                ("extendable", Target::Method { .. })   => (Some(Access::Protected), true, Finality::Remove),
+                ("mutable", Target::Field { .. })       => (None, true, Finality::Remove),
+                ("mutable", _)                          => return Err(invalid("only fields can be mutable")),
+                _                                       => return Err(invalid("expected accessible, extendable, or mutable")),
+            };
+            self.rules.push(Rule { class: words[2].to_owned(), target, access, widen_only, finality });
+        }
+        if !header { return Err(Error::new(ErrorKind::InvalidData, "Invalid access widener: missing accessWidener header")); }
+        Ok(self)
+    }
+
+    /// The JNI paths of every class with rules, such as `"net/minecraft/world/level/Level"`.
+    pub fn classes(&self) -> BTreeSet<String> { self.rules.iter().map(|rule| rule.class.clone()).collect() }
+
+    fn rules_for<'a>(&'a self, class: &'a str) -> impl Iterator<Item = &'a Rule> + 'a { self.rules.iter().filter(move |rule| rule.class == class) }
+}
+
+impl Rule {
+    fn apply(&self, flags: u16) -> u16 {
+        let mut flags = flags;
+        if let Some(access) = self.access {
+            if !self.widen_only || access > Access::from_flags(flags) { flags = access.apply(flags); }
+        }
+        match self.finality {
+            Finality::Keep          => flags,
+            Finality::Add           => flags | FINAL,
+            Finality::Remove        => flags & !FINAL,
+            Finality::AddIfPrivate  => flags, // Handled by apply_method, which knows the original flags
+        }
+    }
+
+    fn apply_method(&self, flags: u16, name: &str) -> u16 {
+        let private = flags & PRIVATE != 0 && flags & STATIC == 0 && name != "<init>";
+        let flags = self.apply(flags);
+        if self.finality == Finality::AddIfPrivate && private { flags | FINAL } else { flags }
+    }
+
+    fn matches(&self, is_method: bool, name: &str, descriptor: &str) -> bool {
+        let (rule_name, rule_descriptor) = match (&self.target, is_method) {
+            (Target::Field  { name, descriptor }, false) => (name, descriptor),
+            (Target::Method { name, descriptor }, true)  => (name, descriptor),
+            _                                           => return false,
+        };
+        rule_name.iter().all(|n| n == name) && rule_descriptor.iter().all(|d| d == descriptor)
+    }
+}
+
+impl Pass for AccessTransformer {
+    fn apply(&self, class: &mut ClassFile) -> io::Result<()> {
+        let path = class.name()?.to_owned();
+        for rule in self.rules_for(&path) {
+            match rule.target {
+                Target::Class => {
+                    // Class files only have public or package access:  javac emits protected nested classes as public
+                    // and private ones as package-private, recording their real access in InnerClasses.
+                    let flags = rule.apply(class.access_flags);
+                    class.access_flags = if flags & (PUBLIC | PROTECTED) != 0 { flags & !(PROTECTED | PRIVATE) | PUBLIC } else { flags & !(PUBLIC | PROTECTED | PRIVATE) };
+                },
+                _ => {
+                    for (members, is_method) in [(&mut class.fields, false), (&mut class.methods, true)].iter_mut() {
+                        for member in members.iter_mut() {
+                            let name = member.name(&class.constants)?;
+                            if !rule.matches(*is_method, name, member.descriptor(&class.constants)?) { continue; }
+                            member.access_flags = if *is_method { rule.apply_method(member.access_flags, name) } else { rule.apply(member.access_flags) };
+                        }
+                    }
+                },
+            }
+        }
+
+        // Nested classes listed by this class
+        for attribute in class.attributes.iter_mut().filter(|a| a.name == "InnerClasses") {
+            let mut info = &attribute.info[..];
+            let count = read_u2(&mut info)? as usize;
+            io_assert!(attribute.info.len() == 2 + 8 * count, "InnerClasses attribute has {} bytes, expected {}", attribute.info.len(), 2 + 8 * count);
+            for i in 0..count {
+                let entry = 2 + 8 * i;
+                let inner = class.constants.get_class(u16::from_be_bytes([attribute.info[entry], attribute.info[entry+1]]))?;
+                let mut flags = u16::from_be_bytes([attribute.info[entry+6], attribute.info[entry+7]]);
+                for rule in self.rules_for(inner).filter(|rule| rule.target == Target::Class) { flags = rule.apply(flags); }
+                attribute.info[entry+6..entry+8].copy_from_slice(&flags.to_be_bytes());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[test] fn forge() {
+    let mut c = testing::ClassFile::new("com/example/Level", Some("java/lang/Object"));
+    c.flags = 0x0010; // FINAL
+    c.field(0x0012, "isClientSide", "Z", &[]);
+    c.field(0x0002, "other", "I", &[]);
+    c.method(0x0002, "tick", "()V", &[]);
+    c.method(0x0002, "tick", "(I)V", &[]);
+    c.method(0x0001, "open", "()V", &[]);
+    let inner = c.class("com/example/Level$Properties");
+    let outer = c.class("com/example/Level");
+    let name = c.utf8("Properties");
+    c.class_attribute("InnerClasses", &[testing::be16(1), testing::be16(inner), testing::be16(outer), testing::be16(name), testing::be16(0x001A)].concat());
+    let bytes = c.to_bytes();
+
+    let mut transformer = AccessTransformer::new();
+    transformer.add_forge("
+        # Comments and blank lines are ignored
+        public-f com.example.Level                  # the class itself
+        public-f com.example.Level isClientSide
+        protected com.example.Level tick()V
+        private com.example.Level *()
+        public com.example.Level tick()V
+        public+f com.example.Level$Properties
+    ").unwrap();
+    assert_eq!(transformer.classes().into_iter().collect::<Vec<_>>(), &["com/example/Level", "com/example/Level$Properties"]);
+
+    let mut pipeline = transform::Pipeline::new();
+    pipeline.add(transformer);
+    let class = Class::read(&mut &pipeline.apply(&bytes[..]).unwrap()[..]).unwrap();
+    assert_eq!(class.flags.bits(), 0x0001);
+    assert_eq!(class.fields.iter().map(|f| f.flags.bits()).collect::<Vec<_>>(), &[0x0001, 0x0002]);
+    assert_eq!(class.methods.iter().map(|m| m.flags.bits()).collect::<Vec<_>>(), &[0x0001, 0x0002, 0x0002]);
+    assert_eq!(class.inner_classes()[0].flags.bits(), 0x0019); // PUBLIC | STATIC | FINAL
+
+    assert!(AccessTransformer::new().add_forge("public").is_err());
+    assert!(AccessTransformer::new().add_forge("visible com.example.Level").is_err());
+    assert!(AccessTransformer::new().add_forge("public com.example.Level a b").is_err());
+}
+
+#[test] fn fabric() {
+    let mut c = testing::ClassFile::new("com/example/Entity", Some("java/lang/Object"));
+    c.flags = 0x0010; // FINAL (package-private)
+    c.field(0x0012, "id", "I", &[]);
+    c.field(0x0012, "id", "J", &[]);
+    c.method(0x0002, "tick", "()V", &[]);
+    c.method(0x0012, "save", "()V", &[]);
+    c.method(0x0001, "load", "()V", &[]);
+    c.method(0x000A, "create", "()V", &[]);
+    let bytes = c.to_bytes();
+
+    let mut transformer = AccessTransformer::new();
+    transformer.add_fabric("
+        accessWidener   v2  named
+        accessible  field   com/example/Entity id I
+        mutable     field   com/example/Entity id I
+        accessible  method  com/example/Entity tick ()V     # private -> public final
+        extendable  method  com/example/Entity save ()V     # private final -> protected
+        extendable  method  com/example/Entity load ()V     # already public
+        accessible  method  com/example/Entity create ()V   # static, so not final
+        transitive-extendable class com/example/Entity
+    ").unwrap();
+
+    let mut pipeline = transform::Pipeline::new();
+    pipeline.add(transformer);
+    let class = Class::read(&mut &pipeline.apply(&bytes[..]).unwrap()[..]).unwrap();
+    assert_eq!(class.flags.bits(), 0x0001);
+    assert_eq!(class.fields.iter().map(|f| f.flags.bits()).collect::<Vec<_>>(), &[0x0001, 0x0012]);
+    assert_eq!(class.methods.iter().map(|m| m.flags.bits()).collect::<Vec<_>>(), &[0x0011, 0x0004, 0x0001, 0x0009]);
+
+    assert!(AccessTransformer::new().add_fabric("accessible class com/example/Entity").is_err());
+    assert!(AccessTransformer::new().add_fabric("accessWidener v1 named\nmutable method com/example/Entity tick ()V").is_err());
+    assert!(AccessTransformer::new().add_fabric("accessWidener v1 named\nextendable field com/example/Entity id I").is_err());
+    assert!(AccessTransformer::new().add_fabric("accessWidener v1 named\naccessible field com/example/Entity id").is_err());
+    assert!(AccessTransformer::new().add_fabric("accessWidener v3 named").is_err());
+}
@@ -0,0 +1,350 @@
+//! Android SDK discovery and enumeration of installed platforms, and recognition of on-device formats.
+//!
+//! Device framework directories (such as `/system/framework`) contain dex bytecode - in `.jar`s, `.vdex`es, and `.oat`
+//! files - rather than `.class` files, so jreflection can't read their classes.  Opening them fails with an error
+//! naming the [DeviceFormat] found, and [extract_dex] can pull out the embedded `.dex` files for use with dex tools.
+//!
+//! [DeviceFormat]: enum.DeviceFormat.html
+//! [extract_dex]:  fn.extract_dex.html
+
+#[cfg(feature = "jar")] use crate::Source;
+#[cfg(all(test, feature = "jar"))] use crate::testing;
+
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, Error, ErrorKind, Read};
+use std::path::*;
+
+
+
+/// An installed Android SDK, typically found via [discover_sdk].
+///
+/// [discover_sdk]:     fn.discover_sdk.html
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Sdk {
+    path: PathBuf,
+}
+
+/// An installed `platforms/android-*` directory of an Android SDK.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Platform {
+    /// The API level of the platform (e.g. `29` for Android 10.)
+    pub api_level:  u32,
+
+    /// The path to the `android.jar` of the platform.
+    pub jar:        PathBuf,
+}
+
+/// An on-device Android format, containing dex bytecode (or compiled code) instead of Java `.class` files.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DeviceFormat {
+    /// A `.dex` file (magic `dex\n`.)
+    Dex,
+
+    /// A compact dex file (magic `cdex`), as found inside `.vdex` files of Android 10 and 11.
+    CompactDex,
+
+    /// An ahead-of-time compiled `.oat` or `.odex` file:  an ELF file, which may embed `.dex` files (before Android 8.)
+    Oat,
+
+    /// A `.vdex` file (magic `vdex`), which may embed (compact) `.dex` files (Android 8+.)
+    Vdex,
+
+    /// A `.art` boot or app image (magic `art\n`):  preinitialized heap objects, without any dex bytecode.
+    Art,
+
+    /// A `.jar` or `.apk` containing `classes.dex` instead of `.class` files.
+    DexJar,
+}
+
+impl DeviceFormat {
+    /// Recognize a device format from the first few bytes of a file.  `DexJar`s are zips, and can't be recognized
+    /// this way - see [detect_file](#method.detect_file).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jreflection::android::DeviceFormat;
+    /// assert_eq!(DeviceFormat::detect(b"dex\n039\0"),  Some(DeviceFormat::Dex));
+    /// assert_eq!(DeviceFormat::detect(b"vdex027\0"),    Some(DeviceFormat::Vdex));
+    /// assert_eq!(DeviceFormat::detect(b"\xCA\xFE\xBA\xBE"), None);
+    /// ```
+    pub fn detect(header: &[u8]) -> Option<Self> {
+        if      header.starts_with(b"dex\n")     { Some(DeviceFormat::Dex) }
+        else if header.starts_with(b"cdex")     { Some(DeviceFormat::CompactDex) }
+        else if header.starts_with(b"vdex")     { Some(DeviceFormat::Vdex) }
+        else if header.starts_with(b"art\n")    { Some(DeviceFormat::Art) }
+        else                                    { None }
+    }
+
+    /// Recognize the device format of the file at `path`, if it has one.  ELF files are only recognized as
+    /// [Oat](#variant.Oat) with an `.oat` or `.odex` extension.  [DexJar](#variant.DexJar)s are only recognized with
+    /// the `jar` feature.
+    pub fn detect_file(path: impl AsRef<Path>) -> io::Result<Option<Self>> {
+        let path = path.as_ref();
+        let mut header = Vec::with_capacity(8);
+        File::open(path)?.take(8).read_to_end(&mut header)?;
+        if let Some(format) = Self::detect(&header) { return Ok(Some(format)); }
+
+        let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("").to_ascii_lowercase();
+        if header.starts_with(b"\x7FELF") && (extension == "oat" || extension == "odex") { return Ok(Some(DeviceFormat::Oat)); }
+        #[cfg(feature = "jar")]
+        {
+            if header.starts_with(b"PK\x03\x04") {
+                let mut zip = zip::ZipArchive::new(File::open(path)?)?;
+                let dex = zip.by_name("classes.dex").is_ok();
+                if dex && !zip.file_names().any(|name| name.ends_with(".class")) { return Ok(Some(DeviceFormat::DexJar)); }
+            }
+        }
+        Ok(None)
+    }
+
+    /// A short description of the format, such as `"an Android VDEX file"`.
+    pub fn description(self) -> &'static str {
+        match self {
+            DeviceFormat::Dex           => "an Android dex file",
+            DeviceFormat::CompactDex    => "an Android compact dex file",
+            DeviceFormat::Oat           => "an Android OAT file",
+            DeviceFormat::Vdex          => "an Android VDEX file",
+            DeviceFormat::Art           => "an Android ART image",
+            DeviceFormat::DexJar        => "an Android jar of dex bytecode",
+        }
+    }
+
+    /// The error returned when trying to read classes from `path`, which is in this format.
+    pub(crate) fn unsupported(self, path: &Path) -> Error {
+        let hint = match self {
+            DeviceFormat::Art   => "ART images contain no bytecode",
+            _                   => "android::extract_dex_file can extract its .dex files for use with dex tools",
+        };
+        Error::new(ErrorKind::InvalidInput, format!(
+            "{} is {}, not .class files - use an SDK platform's android.jar instead ({})",
+            path.display(), self.description(), hint
+        ))
+    }
+
+    /// Find a file in a device format within `dir` or its immediate subdirectories (such as `framework/arm64/boot.oat`.)
+    pub(crate) fn find_in_dir(dir: &Path, depth: usize) -> Option<(PathBuf, Self)> {
+        let mut paths = fs::read_dir(dir).ok()?.filter_map(|e| e.ok()).map(|e| e.path()).collect::<Vec<_>>();
+        paths.sort();
+        let (subdirs, files) : (Vec<_>, Vec<_>) = paths.into_iter().partition(|path| path.is_dir());
+        if let Some(found) = files.into_iter().filter_map(|path| Some((path.clone(), Self::detect_file(&path).ok()??))).next() { return Some(found); }
+        if depth == 0 { return None; }
+        subdirs.iter().filter_map(|subdir| Self::find_in_dir(subdir, depth - 1)).next()
+    }
+}
+
+/// Extract the standard `.dex` files embedded in `bytes`, such as the contents of a `.dex`, `.vdex`, or `.oat` file.
+///
+/// Compact dex (`cdex`) files, as found in the `.vdex` files of Android 10 and 11, aren't extracted, as they aren't
+/// self-contained.  Newer `.vdex` files often contain no dex at all, leaving it in the matching `.jar` instead - see
+/// [extract_dex_file](fn.extract_dex_file.html).
+///
+/// # Examples
+///
+/// ```no_run
+/// let vdex = std::fs::read("/system/framework/boot-framework.vdex").unwrap();
+/// for (i, dex) in jreflection::android::extract_dex(&vdex).iter().enumerate() {
+///     std::fs::write(format!("classes{}.dex", i+1), dex).unwrap();
+/// }
+/// ```
+pub fn extract_dex(bytes: &[u8]) -> Vec<&[u8]> {
+    const HEADER_SIZE : usize = 0x70;
+    let mut dexes = Vec::new();
+    let mut offset = 0;
+    while offset + HEADER_SIZE <= bytes.len() {
+        let header = &bytes[offset..];
+        let version = &header[4..8];
+        let le32 = |at: usize| u32::from_le_bytes([header[at], header[at+1], header[at+2], header[at+3]]) as usize;
+        let is_dex = header.starts_with(b"dex\n") && version[..3].iter().all(|b| b.is_ascii_digit()) && version[3] == 0;
+        if is_dex && le32(0x24) == HEADER_SIZE && le32(0x20) >= HEADER_SIZE && le32(0x20) <= header.len() {
+            let file_size = le32(0x20);
+            dexes.push(&header[..file_size]);
+            offset += file_size;
+        } else {
+            offset += 4; // dex files are 4-byte aligned within vdex and oat files
+        }
+    }
+    dexes
+}
+
+/// Extract the standard `.dex` files of the file at `path`:  the `classes*.dex` entries of a [DexJar](enum.DeviceFormat.html#variant.DexJar),
+/// or the dex files embedded in anything else (see [extract_dex](fn.extract_dex.html).)
+pub fn extract_dex_file(path: impl AsRef<Path>) -> io::Result<Vec<Vec<u8>>> {
+    let path = path.as_ref();
+    match DeviceFormat::detect_file(path)? {
+        #[cfg(feature = "jar")]
+        Some(DeviceFormat::DexJar) => {
+            let mut zip = zip::ZipArchive::new(File::open(path)?)?;
+            let mut names = zip.file_names().filter(|n| n.starts_with("classes") && n.ends_with(".dex") && !n.contains('/')).map(String::from).collect::<Vec<_>>();
+            names.sort_by_key(|n| (n.len(), n.clone())); // classes.dex, classes2.dex, ..., classes10.dex
+            let mut dexes = Vec::new();
+            for name in names {
+                let mut dex = Vec::new();
+                zip.by_name(&name)?.read_to_end(&mut dex)?;
+                dexes.push(dex);
+            }
+            Ok(dexes)
+        },
+        Some(DeviceFormat::Art) => Err(DeviceFormat::Art.unsupported(path)),
+        _ => Ok(extract_dex(&fs::read(path)?).into_iter().map(|dex| dex.to_vec()).collect()),
+    }
+}
+
+/// Find the Android SDK.
+///
+/// This checks, in order:
+/// * `%ANDROID_SDK_ROOT%`
+/// * `%ANDROID_HOME%`
+/// * `%LOCALAPPDATA%\Android\Sdk` (the Android Studio default on Windows)
+/// * `~/Library/Android/sdk` (the Android Studio default on OS X)
+/// * `~/Android/Sdk` (the Android Studio default on Linux)
+///
+/// # Examples
+///
+/// ```no_run
+/// let sdk = jreflection::android::discover_sdk().unwrap();
+/// for platform in sdk.platforms().unwrap() {
+///     println!("API {}: {}", platform.api_level, platform.jar.display());
+/// }
+/// ```
+pub fn discover_sdk() -> io::Result<Sdk> {
+    let mut candidates = Vec::new();
+    for var in ["ANDROID_SDK_ROOT", "ANDROID_HOME"].iter().copied() {
+        if let Some(path) = env::var_os(var) {
+            if !path.is_empty() { candidates.push(PathBuf::from(path)); }
+        }
+    }
+    if let Some(local_app_data) = env::var_os("LOCALAPPDATA") {
+        candidates.push(PathBuf::from(local_app_data).join("Android").join("Sdk"));
+    }
+    if let Some(home) = env::var_os("HOME") {
+        let home = PathBuf::from(home);
+        candidates.push(home.join("Library").join("Android").join("sdk"));
+        candidates.push(home.join("Android").join("Sdk"));
+    }
+
+    for candidate in candidates.iter() {
+        if candidate.join("platforms").is_dir() {
+            return Ok(Sdk::new(candidate));
+        }
+    }
+
+    Err(Error::new(ErrorKind::NotFound, format!("Unable to find an Android SDK, searched: {:?}", candidates)))
+}
+
+impl Sdk {
+    /// Use a specific Android SDK directory (e.g. `C:\Users\Name\AppData\Local\Android\Sdk`)
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self { path: path.as_ref().to_owned() }
+    }
+
+    /// The root directory of the Android SDK.
+    pub fn path(&self) -> &Path { self.path.as_path() }
+
+    /// Enumerate the installed platforms containing an `android.jar`, sorted by API level.
+    ///
+    /// The API level is read from `source.properties` (`AndroidVersion.ApiLevel`) if present, otherwise it's parsed
+    /// from the directory name (`android-29`.)  Platforms without a determinable API level are skipped.
+    pub fn platforms(&self) -> io::Result<Vec<Platform>> {
+        let platforms_dir = self.path.join("platforms");
+        let mut platforms = Vec::new();
+        for entry in fs::read_dir(&platforms_dir)? {
+            let dir = entry?.path();
+            let jar = dir.join("android.jar");
+            if !jar.is_file() { continue; }
+
+            let api_level = fs::read_to_string(dir.join("source.properties")).ok().and_then(|p| api_level_from_properties(&p))
+                .or_else(|| dir.file_name().and_then(|n| n.to_str()).and_then(api_level_from_dir_name));
+
+            if let Some(api_level) = api_level {
+                platforms.push(Platform { api_level, jar });
+            }
+        }
+        platforms.sort_by(|a, b| a.api_level.cmp(&b.api_level).then_with(|| a.jar.cmp(&b.jar)));
+        Ok(platforms)
+    }
+
+    /// Get the platform for a specific API level, if installed.
+    pub fn platform(&self, api_level: u32) -> io::Result<Option<Platform>> {
+        Ok(self.platforms()?.into_iter().find(|p| p.api_level == api_level))
+    }
+}
+
+impl Platform {
+    /// Open the `android.jar` of this platform.
+    #[cfg(feature = "jar")]
+    pub fn source(&self) -> io::Result<Source> {
+        Source::from_jar(&self.jar)
+    }
+}
+
+fn api_level_from_properties(properties: &str) -> Option<u32> {
+    for line in properties.lines() {
+        let mut kv = line.splitn(2, '=');
+        let key     = kv.next()?.trim();
+        let value   = kv.next().unwrap_or("").trim();
+        if key == "AndroidVersion.ApiLevel" {
+            return value.parse().ok();
+        }
+    }
+    None
+}
+
+fn api_level_from_dir_name(name: &str) -> Option<u32> {
+    let mut parts = name.splitn(2, '-');
+    match (parts.next(), parts.next()) {
+        (Some("android"), Some(api_level)) => api_level.parse().ok(),
+        _ => None,
+    }
+}
+
+#[test] fn api_levels() {
+    assert_eq!(api_level_from_dir_name("android-29"),   Some(29));
+    assert_eq!(api_level_from_dir_name("android-7"),    Some(7));
+    assert_eq!(api_level_from_dir_name("android-R"),    None);
+    assert_eq!(api_level_from_dir_name("29"),           None);
+
+    assert_eq!(api_level_from_properties("Pkg.Desc=Android SDK Platform R\nAndroidVersion.ApiLevel=29\nAndroidVersion.CodeName=R\n"), Some(29));
+    assert_eq!(api_level_from_properties("Pkg.Revision=1\r\nAndroidVersion.ApiLevel = 30\r\n"), Some(30));
+    assert_eq!(api_level_from_properties("Pkg.Revision=1\n"), None);
+}
+
+#[cfg(feature = "jar")]
+#[test] fn device_formats() {
+    let dir = std::env::temp_dir().join("jreflection-tests").join("android-device");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("arm64")).unwrap();
+
+    let mut dex = vec![0u8; 0x70];
+    dex[..8].copy_from_slice(b"dex\n039\0");
+    dex[0x20..0x24].copy_from_slice(&0x70u32.to_le_bytes());
+    dex[0x24..0x28].copy_from_slice(&0x70u32.to_le_bytes());
+    let vdex = [&b"vdex027\0"[..], &[0u8; 8][..], &dex[..], &b"cdex001\0"[..], &dex[..]].concat();
+    assert_eq!(extract_dex(&vdex), vec![&dex[..], &dex[..]]);
+    assert!(extract_dex(&dex[..0x6C]).is_empty());
+
+    let vdex_path = dir.join("arm64").join("boot-framework.vdex");
+    fs::write(&vdex_path, &vdex).unwrap();
+    assert_eq!(DeviceFormat::detect_file(&vdex_path).unwrap(), Some(DeviceFormat::Vdex));
+    assert_eq!(extract_dex_file(&vdex_path).unwrap(), vec![dex.clone(), dex.clone()]);
+    let err = Source::from_jar(&vdex_path).err().unwrap();
+    assert!(err.to_string().contains("is an Android VDEX file"), "{}", err);
+
+    let oat_path = dir.join("arm64").join("boot.oat");
+    fs::write(&oat_path, b"\x7FELF\x02\x01\x01\0").unwrap();
+    assert_eq!(DeviceFormat::detect_file(&oat_path).unwrap(), Some(DeviceFormat::Oat));
+
+    let jar = testing::write_jar("android-framework", &[("classes.dex", dex.clone()), ("classes2.dex", dex.clone()), ("res/values.xml", Vec::new())]);
+    assert_eq!(DeviceFormat::detect_file(&jar).unwrap(), Some(DeviceFormat::DexJar));
+    assert_eq!(extract_dex_file(&jar).unwrap().len(), 2);
+    let err = Source::from_jar(&jar).err().unwrap();
+    assert!(err.to_string().contains("is an Android jar of dex bytecode"), "{}", err);
+
+    let err = Source::from_jdk_dir(&dir).err().unwrap();
+    assert!(err.to_string().contains("looks like an Android device directory"), "{}", err);
+
+    let classes = testing::write_jar("android-classes", &[("com/example/Foo.class", testing::ClassFile::new("com/example/Foo", Some("java/lang/Object")).to_bytes())]);
+    assert_eq!(DeviceFormat::detect_file(&classes).unwrap(), None);
+    assert!(Source::from_jar(&classes).is_ok());
+}
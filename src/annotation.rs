@@ -0,0 +1,520 @@
+//! [Java SE 7 &sect; 4.7.16](https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.7.16):  Annotations
+//! and their element values, such as `@Retention(RetentionPolicy.RUNTIME)` or `@RequiresApi(api = 26)`.
+
+use crate::type_path;
+use crate::constants::{Constant, Constants};
+use crate::io::be::*;
+#[cfg(test)] use crate::{testing, Class};
+#[cfg(all(test, feature = "code"))] use crate::{signature, ParseOptions};
+
+use std::io::{self, Read};
+
+
+
+/// A single annotation, such as `@Target({ElementType.METHOD, ElementType.FIELD})`.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use jreflection::*;
+/// # let src = Source::from_jdk_dir(r"C:\Program Files\AdoptOpenJDK\jdk-8.0.232.09-hotspot").unwrap();
+/// let deprecated = src.read_class("java/lang/Deprecated").unwrap();
+/// let retention = deprecated.visible_annotations().iter().find(|a| a.descriptor == "Ljava/lang/annotation/Retention;").unwrap();
+/// assert_eq!(retention.element("value"), Some(&annotation::ElementValue::Enum {
+///     descriptor: "Ljava/lang/annotation/RetentionPolicy;".to_owned(),
+///     name:       "RUNTIME".to_owned(),
+/// }));
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Annotation {
+    /// The type descriptor of the annotation, such as `"Ljava/lang/annotation/Retention;"`.
+    pub descriptor: String,
+
+    /// The explicitly specified elements of the annotation, by name, in classfile order.  Elements left at their
+    /// default values aren't included.
+    pub elements:   Vec<(String, ElementValue)>,
+
+    _non_exhaustive: (),
+}
+
+/// [Java SE 8 &sect; 4.7.20](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-4.html#jvms-4.7.20):  An annotation on
+/// a use of a type (JSR 308), such as the `@NonNull` of `List<@NonNull String>` or `throws @Critical IOException`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TypeAnnotation {
+    /// The kind of type use annotated, such as `0x13` for a field's type.  See [Java SE 8 &sect; 4.7.20-A](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-4.html#jvms-4.7.20-400).
+    pub target_type:    u8,
+
+    /// Which type use of that kind is annotated.
+    pub target:         TypeTarget,
+
+    /// Where within the annotated type the annotation applies.  See [TypeUse](../type_path/enum.TypeUse.html).
+    pub path:           type_path::TypePath,
+
+    /// The annotation itself.
+    pub annotation:     Annotation,
+
+    _non_exhaustive: (),
+}
+
+/// [Java SE 8 &sect; 4.7.20.1](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-4.html#jvms-4.7.20.1):  The
+/// `target_info` of a [TypeAnnotation](struct.TypeAnnotation.html), identifying which type use is annotated.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum TypeTarget {
+    /// `0x00`, `0x01`:  The type parameter `index` of a generic class or method.
+    TypeParameter { index: u8 },
+
+    /// `0x10`:  The `extends` (`index` 65535) or the Nth `implements` clause of a class.
+    Supertype { index: u16 },
+
+    /// `0x11`, `0x12`:  The Nth `bound` of type parameter `type_parameter` of a generic class or method.
+    TypeParameterBound { type_parameter: u8, bound: u8 },
+
+    /// `0x13`, `0x14`, `0x15`:  The type of a field, the return type of a method, or the receiver type of a method.
+    Empty,
+
+    /// `0x16`:  The type of formal parameter `index` of a method.
+    FormalParameter { index: u8 },
+
+    /// `0x17`:  The Nth type of a method's `throws` clause.
+    Throws { index: u16 },
+
+    /// `0x40`, `0x41`:  The type of a local (or resource) variable, live in each `(start_pc, length, index)` range.
+    LocalVariable(Vec<(u16, u16, u16)>),
+
+    /// `0x42`:  The type of a `catch` clause, by index into the exception table.
+    Catch { exception_table_index: u16 },
+
+    /// `0x43` - `0x46`:  The type of an `instanceof`, `new`, or method reference expression at bytecode `offset`.
+    Offset { offset: u16 },
+
+    /// `0x47` - `0x4B`:  Type argument `index` of a cast, constructor call, or method call (or reference) at bytecode `offset`.
+    TypeArgument { offset: u16, index: u8 },
+
+    #[doc(hidden)] _NonExhaustive,
+}
+
+/// [Java SE 7 &sect; 4.7.16.1](https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.7.16.1):  The value of an annotation element.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ElementValue {
+    /// A `byte` constant.
+    Byte(i8),
+    /// A `char` constant, as a UTF16 code unit.
+    Char(u16),
+    /// A `double` constant.
+    Double(f64),
+    /// A `float` constant.
+    Float(f32),
+    /// An `int` constant.
+    Int(i32),
+    /// A `long` constant.
+    Long(i64),
+    /// A `short` constant.
+    Short(i16),
+    /// A `boolean` constant.
+    Boolean(bool),
+    /// A `String` constant.
+    String(String),
+    /// An enum constant, such as `RetentionPolicy.RUNTIME`.
+    Enum {
+        /// The type descriptor of the enum, such as `"Ljava/lang/annotation/RetentionPolicy;"`.
+        descriptor: String,
+        /// The name of the enum constant, such as `"RUNTIME"`.
+        name:       String,
+    },
+    /// A class literal, such as `String.class`, as a return descriptor (`"Ljava/lang/String;"`, `"I"`, or `"V"` for `void.class`.)
+    Class(String),
+    /// A nested annotation.
+    Annotation(Annotation),
+    /// An array of values.  Java allows a single value where an array is expected, but it's still stored as an array.
+    Array(Vec<ElementValue>),
+
+    #[doc(hidden)] _NonExhaustive,
+}
+
+impl Annotation {
+    /// Create an annotation of the type `descriptor` (such as `"Ljava/lang/Deprecated;"`) with explicit `elements`.
+    pub fn new(descriptor: String, elements: Vec<(String, ElementValue)>) -> Self { Self { descriptor, elements, _non_exhaustive: () } }
+
+    /// The JNI path of the annotation's type, such as `"java/lang/annotation/Retention"`.
+    pub fn class(&self) -> &str {
+        let d = self.descriptor.as_str();
+        if d.starts_with('L') && d.ends_with(';') { &d[1..d.len()-1] } else { d }
+    }
+
+    /// The value of the explicitly specified element `name`, if any.
+    pub fn element(&self, name: &str) -> Option<&ElementValue> {
+        self.elements.iter().find(|(n, _)| n == name).map(|(_, v)| v)
+    }
+
+    /// Read a `num_annotations` prefixed list of annotations, such as a RuntimeVisibleAnnotations attribute's.
+    pub(crate) fn read_list(read: &mut dyn Read, constants: &Constants) -> io::Result<Vec<Self>> {
+        let mut read = read;
+        let count = read_u2(&mut read)? as usize;
+        let mut annotations = Vec::with_capacity(count);
+        for _ in 0..count { annotations.push(Self::read(read, constants)?); }
+        Ok(annotations)
+    }
+
+    /// Read a `num_parameters` prefixed list of annotation lists, such as a RuntimeVisibleParameterAnnotations attribute's.
+    pub(crate) fn read_parameter_lists(read: &mut dyn Read, constants: &Constants) -> io::Result<Vec<Vec<Self>>> {
+        let mut read = read;
+        let count = read_u1(&mut read)? as usize;
+        let mut parameters = Vec::with_capacity(count);
+        for _ in 0..count { parameters.push(Self::read_list(read, constants)?); }
+        Ok(parameters)
+    }
+
+    pub(crate) fn read(read: &mut dyn Read, constants: &Constants) -> io::Result<Self> {
+        let mut read = read;
+        let descriptor = constants.get_utf8(read_u2(&mut read)?)?.to_owned();
+        let count = read_u2(&mut read)? as usize;
+        let mut elements = Vec::with_capacity(count);
+        for _ in 0..count {
+            let name = constants.get_utf8(read_u2(&mut read)?)?.to_owned();
+            elements.push((name, ElementValue::read(read, constants)?));
+        }
+        Ok(Self::new(descriptor, elements))
+    }
+
+    /// Encode this annotation as an `annotation` structure, adding any constants it needs to `constants`.
+    #[cfg(feature = "writer")]
+    pub(crate) fn write(&self, constants: &mut Constants, out: &mut Vec<u8>) -> io::Result<()> {
+        out.extend_from_slice(&constants.add_utf8(&self.descriptor)?.to_be_bytes());
+        write_u2_len(out, self.elements.len())?;
+        for (name, value) in self.elements.iter() {
+            out.extend_from_slice(&constants.add_utf8(name)?.to_be_bytes());
+            value.write(constants, out)?;
+        }
+        Ok(())
+    }
+}
+
+impl TypeAnnotation {
+    /// Create an `annotation` of the type use identified by `target_type`, `target`, and `path`.
+    pub fn new(target_type: u8, target: TypeTarget, path: type_path::TypePath, annotation: Annotation) -> Self {
+        Self { target_type, target, path, annotation, _non_exhaustive: () }
+    }
+
+    /// Read a `num_annotations` prefixed list of type annotations, such as a RuntimeVisibleTypeAnnotations attribute's.
+    pub(crate) fn read_list(read: &mut dyn Read, constants: &Constants) -> io::Result<Vec<Self>> {
+        let mut read = read;
+        let count = read_u2(&mut read)? as usize;
+        let mut annotations = Vec::with_capacity(count);
+        for _ in 0..count { annotations.push(Self::read(read, constants)?); }
+        Ok(annotations)
+    }
+
+    pub(crate) fn read(read: &mut dyn Read, constants: &Constants) -> io::Result<Self> {
+        let mut read = read;
+        let target_type = read_u1(&mut read)?;
+        let target = match target_type {
+            0x00 | 0x01         => TypeTarget::TypeParameter { index: read_u1(&mut read)? },
+            0x10                => TypeTarget::Supertype { index: read_u2(&mut read)? },
+            0x11 | 0x12         => TypeTarget::TypeParameterBound { type_parameter: read_u1(&mut read)?, bound: read_u1(&mut read)? },
+            0x13 ..= 0x15       => TypeTarget::Empty,
+            0x16                => TypeTarget::FormalParameter { index: read_u1(&mut read)? },
+            0x17                => TypeTarget::Throws { index: read_u2(&mut read)? },
+            0x40 | 0x41         => {
+                let table_length = read_u2(&mut read)? as usize;
+                let mut table = Vec::with_capacity(table_length);
+                for _ in 0..table_length { table.push((read_u2(&mut read)?, read_u2(&mut read)?, read_u2(&mut read)?)); }
+                TypeTarget::LocalVariable(table)
+            },
+            0x42                => TypeTarget::Catch { exception_table_index: read_u2(&mut read)? },
+            0x43 ..= 0x46       => TypeTarget::Offset { offset: read_u2(&mut read)? },
+            0x47 ..= 0x4B       => TypeTarget::TypeArgument { offset: read_u2(&mut read)?, index: read_u1(&mut read)? },
+            other               => return io_data_err!("Unknown type annotation target_type 0x{:02X}", other),
+        };
+        let path = type_path::TypePath::read(read)?;
+        let annotation = Annotation::read(read, constants)?;
+        Ok(Self::new(target_type, target, path, annotation))
+    }
+}
+
+impl ElementValue {
+    pub(crate) fn read(read: &mut dyn Read, constants: &Constants) -> io::Result<Self> {
+        let mut read = read;
+        let tag = read_u1(&mut read)?;
+        let integer = |index: u16| -> io::Result<i32> {
+            match constants.get(index)? {
+                Constant::Integer(value)    => Ok(*value),
+                other                       => io_data_err!("Expected Constant::Integer for element_value tag {:?}, got {:?}", tag as char, other),
+            }
+        };
+        Ok(match tag {
+            b'B' => ElementValue::Byte(integer(read_u2(&mut read)?)? as i8),
+            b'C' => ElementValue::Char(integer(read_u2(&mut read)?)? as u16),
+            b'I' => ElementValue::Int(integer(read_u2(&mut read)?)?),
+            b'S' => ElementValue::Short(integer(read_u2(&mut read)?)? as i16),
+            b'Z' => ElementValue::Boolean(integer(read_u2(&mut read)?)? != 0),
+            b'D' | b'F' | b'J' => match (tag, constants.get(read_u2(&mut read)?)?) {
+                (b'D', Constant::Double(value)) => ElementValue::Double(*value),
+                (b'F', Constant::Float(value))  => ElementValue::Float(*value),
+                (b'J', Constant::Long(value))   => ElementValue::Long(*value),
+                (_, other) => return io_data_err!("Unexpected constant for element_value tag {:?}: {:?}", tag as char, other),
+            },
+            b's' => ElementValue::String(constants.get_utf8(read_u2(&mut read)?)?.to_owned()),
+            b'e' => {
+                let descriptor  = constants.get_utf8(read_u2(&mut read)?)?.to_owned();
+                let name        = constants.get_utf8(read_u2(&mut read)?)?.to_owned();
+                ElementValue::Enum { descriptor, name }
+            },
+            b'c' => ElementValue::Class(constants.get_utf8(read_u2(&mut read)?)?.to_owned()),
+            b'@' => ElementValue::Annotation(Annotation::read(read, constants)?),
+            b'[' => {
+                let count = read_u2(&mut read)? as usize;
+                let mut values = Vec::with_capacity(count);
+                for _ in 0..count { values.push(ElementValue::read(read, constants)?); }
+                ElementValue::Array(values)
+            },
+            tag  => return io_data_err!("Unknown element_value tag {:?}", tag as char),
+        })
+    }
+
+    /// Encode this value as an `element_value` structure, adding any constants it needs to `constants`.
+    #[cfg(feature = "writer")]
+    pub(crate) fn write(&self, constants: &mut Constants, out: &mut Vec<u8>) -> io::Result<()> {
+        let (tag, index) = match self {
+            ElementValue::Byte(value)       => (b'B', constants.add_unique(Constant::Integer(i32::from(*value)))?),
+            ElementValue::Char(value)       => (b'C', constants.add_unique(Constant::Integer(i32::from(*value)))?),
+            ElementValue::Double(value)     => (b'D', constants.add_unique(Constant::Double(*value))?),
+            ElementValue::Float(value)      => (b'F', constants.add_unique(Constant::Float(*value))?),
+            ElementValue::Int(value)        => (b'I', constants.add_unique(Constant::Integer(*value))?),
+            ElementValue::Long(value)       => (b'J', constants.add_unique(Constant::Long(*value))?),
+            ElementValue::Short(value)      => (b'S', constants.add_unique(Constant::Integer(i32::from(*value)))?),
+            ElementValue::Boolean(value)    => (b'Z', constants.add_unique(Constant::Integer(if *value { 1 } else { 0 }))?),
+            ElementValue::String(value)     => (b's', constants.add_utf8(value)?),
+            ElementValue::Class(descriptor) => (b'c', constants.add_utf8(descriptor)?),
+            ElementValue::Enum { descriptor, name } => {
+                out.push(b'e');
+                out.extend_from_slice(&constants.add_utf8(descriptor)?.to_be_bytes());
+                out.extend_from_slice(&constants.add_utf8(name)?.to_be_bytes());
+                return Ok(());
+            },
+            ElementValue::Annotation(annotation) => {
+                out.push(b'@');
+                return annotation.write(constants, out);
+            },
+            ElementValue::Array(values) => {
+                out.push(b'[');
+                write_u2_len(out, values.len())?;
+                for value in values.iter() { value.write(constants, out)?; }
+                return Ok(());
+            },
+            ElementValue::_NonExhaustive => return Err(io::Error::new(io::ErrorKind::InvalidInput, "Can't write element_value")),
+        };
+        out.push(tag);
+        out.extend_from_slice(&index.to_be_bytes());
+        Ok(())
+    }
+}
+
+#[cfg(feature = "writer")]
+fn write_u2_len(out: &mut Vec<u8>, len: usize) -> io::Result<()> {
+    if len > 0xFFFF { return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Too many annotation elements: {}", len))); }
+    out.extend_from_slice(&(len as u16).to_be_bytes());
+    Ok(())
+}
+
+#[test] fn element_values() {
+    use crate::testing::be16;
+
+    let mut c = testing::ClassFile::new("com/example/Foo", Some("java/lang/Object"));
+    let mut info = be16(2);
+    // @Target({ElementType.METHOD}) @com.example.Everything(b = 1, c = 'x', d = 1.5, f = 2.5f, i = 3, j = 4L, s = -5, z = true, str = "hi", cls = void.class, nested = @Nested)
+    info.extend(be16(c.utf8("Ljava/lang/annotation/Target;")));
+    info.extend(be16(1));
+    info.extend(be16(c.utf8("value")));
+    info.push(b'['); info.extend(be16(1));
+    info.push(b'e'); info.extend(be16(c.utf8("Ljava/lang/annotation/ElementType;"))); info.extend(be16(c.utf8("METHOD")));
+
+    info.extend(be16(c.utf8("Lcom/example/Everything;")));
+    info.extend(be16(11));
+    let elements : Vec<(&str, u8, u16)> = vec![
+        ("b",   b'B', c.integer(1)),
+        ("c",   b'C', c.integer('x' as i32)),
+        ("d",   b'D', c.constant(6, &1.5f64.to_bits().to_be_bytes())),
+        ("f",   b'F', c.constant(4, &2.5f32.to_bits().to_be_bytes())),
+        ("i",   b'I', c.integer(3)),
+        ("j",   b'J', c.long(4)),
+        ("s",   b'S', c.integer(-5)),
+        ("z",   b'Z', c.integer(1)),
+        ("str", b's', c.utf8("hi")),
+        ("cls", b'c', c.utf8("V")),
+    ];
+    for (name, tag, index) in elements {
+        info.extend(be16(c.utf8(name)));
+        info.push(tag);
+        info.extend(be16(index));
+    }
+    info.extend(be16(c.utf8("nested")));
+    info.push(b'@'); info.extend(be16(c.utf8("Lcom/example/Nested;"))); info.extend(be16(0));
+    c.class_attribute("RuntimeVisibleAnnotations", &info);
+
+    let class = c.read();
+    let annotations = class.visible_annotations();
+    assert_eq!(annotations.len(), 2);
+    assert_eq!(annotations[0].class(), "java/lang/annotation/Target");
+    assert_eq!(annotations[0].element("value"), Some(&ElementValue::Array(vec![ElementValue::Enum { descriptor: "Ljava/lang/annotation/ElementType;".to_owned(), name: "METHOD".to_owned() }])));
+
+    let everything = &annotations[1];
+    assert_eq!(everything.elements.iter().map(|(n, v)| (n.as_str(), v.clone())).collect::<Vec<_>>(), vec![
+        ("b",       ElementValue::Byte(1)),
+        ("c",       ElementValue::Char('x' as u16)),
+        ("d",       ElementValue::Double(1.5)),
+        ("f",       ElementValue::Float(2.5)),
+        ("i",       ElementValue::Int(3)),
+        ("j",       ElementValue::Long(4)),
+        ("s",       ElementValue::Short(-5)),
+        ("z",       ElementValue::Boolean(true)),
+        ("str",     ElementValue::String("hi".to_owned())),
+        ("cls",     ElementValue::Class("V".to_owned())),
+        ("nested",  ElementValue::Annotation(Annotation::new("Lcom/example/Nested;".to_owned(), Vec::new()))),
+    ]);
+    assert_eq!(everything.element("missing"), None);
+    assert_eq!(class.annotation_types().collect::<Vec<_>>(), &["Ljava/lang/annotation/Target;", "Lcom/example/Everything;"]);
+
+    let mut c = testing::ClassFile::new("com/example/Foo", Some("java/lang/Object"));
+    let mut info = be16(1);
+    info.extend(be16(c.utf8("Lcom/example/Bad;")));
+    info.extend(be16(1));
+    info.extend(be16(c.utf8("value")));
+    info.push(b'I'); info.extend(be16(c.utf8("not an integer")));
+    c.class_attribute("RuntimeVisibleAnnotations", &info);
+    assert!(Class::read(&mut &c.to_bytes()[..]).is_err());
+}
+
+#[test] fn visible_and_invisible() {
+    use crate::testing::be16;
+
+    let mut c = testing::ClassFile::new("com/example/Foo", Some("java/lang/Object"));
+    let nullable    = [be16(1), be16(c.utf8("Ljavax/annotation/Nullable;")), be16(0)].concat();
+    let nullable    = c.attribute("RuntimeInvisibleAnnotations", &nullable);
+    let deprecated  = [be16(1), be16(c.utf8("Ljava/lang/Deprecated;")), be16(0)].concat();
+    let deprecated  = c.attribute("RuntimeVisibleAnnotations", &deprecated);
+    c.method(0x0001, "get", "()Ljava/lang/Object;", &[nullable.clone(), deprecated.clone()]);
+    c.field(0x0001, "value", "Ljava/lang/Object;", &[nullable]);
+    let info = [be16(1), be16(c.utf8("Lcom/google/common/annotations/Beta;")), be16(0)].concat();
+    c.class_attribute("RuntimeInvisibleAnnotations", &info);
+    let class = c.read();
+
+    assert!(class.visible_annotations().is_empty());
+    assert_eq!(class.invisible_annotations()[0].class(), "com/google/common/annotations/Beta");
+    assert_eq!(class.annotations().map(|a| a.class()).collect::<Vec<_>>(), &["com/google/common/annotations/Beta"]);
+
+    let method = &class.methods[0];
+    assert_eq!(method.visible_annotations().len(), 1);
+    assert_eq!(method.invisible_annotations().len(), 1);
+    assert_eq!(method.annotations().map(|a| a.class()).collect::<Vec<_>>(), &["java/lang/Deprecated", "javax/annotation/Nullable"]);
+    assert_eq!(method.annotation_types().collect::<Vec<_>>(), &["Ljava/lang/Deprecated;", "Ljavax/annotation/Nullable;"]);
+
+    let field = &class.fields[0];
+    assert!(field.visible_annotations().is_empty());
+    assert_eq!(field.annotations().map(|a| a.class()).collect::<Vec<_>>(), &["javax/annotation/Nullable"]);
+}
+
+#[test] fn parameters() {
+    use crate::testing::be16;
+
+    let mut c = testing::ClassFile::new("com/example/Foo", Some("java/lang/Object"));
+    // void set(@NonNull String key, int value, @Nullable @Named("v") Object extra)
+    let visible = [
+        vec![3],
+        be16(1), be16(c.utf8("Landroidx/annotation/NonNull;")), be16(0),
+        be16(0),
+        be16(1), be16(c.utf8("Ljavax/inject/Named;")), be16(1), be16(c.utf8("value")), vec![b's'], be16(c.utf8("v")),
+    ].concat();
+    let visible = c.attribute("RuntimeVisibleParameterAnnotations", &visible);
+    let invisible = [vec![3], be16(0), be16(0), be16(1), be16(c.utf8("Ljavax/annotation/Nullable;")), be16(0)].concat();
+    let invisible = c.attribute("RuntimeInvisibleParameterAnnotations", &invisible);
+    c.method(0x0001, "set", "(Ljava/lang/String;ILjava/lang/Object;)V", &[visible, invisible]);
+    c.method(0x0001, "run", "()V", &[]);
+    let class = c.read();
+
+    let set = &class.methods[0];
+    let types = |index| set.parameter_annotations(index).map(|a| a.class()).collect::<Vec<_>>();
+    assert_eq!(types(0), &["androidx/annotation/NonNull"]);
+    assert!(types(1).is_empty());
+    assert_eq!(types(2), &["javax/inject/Named", "javax/annotation/Nullable"]);
+    assert!(types(3).is_empty());
+    assert_eq!(set.visible_parameter_annotations(2)[0].element("value"), Some(&ElementValue::String("v".to_owned())));
+    assert_eq!(set.invisible_parameter_annotations(2).len(), 1);
+    assert!(set.annotations().next().is_none());
+    assert!(class.methods[1].parameter_annotations(0).next().is_none());
+}
+
+#[cfg(feature = "code")]
+#[test] fn type_annotations() {
+    use crate::testing::{be16, be32};
+    use crate::type_path::{TypePath, TypeUse};
+
+    let mut c = testing::ClassFile::new("com/example/Foo", Some("java/lang/Object"));
+    let non_null = c.utf8("Lorg/checkerframework/checker/nullness/qual/NonNull;");
+
+    // List<@NonNull String> names;
+    let signature = be16(c.utf8("Ljava/util/List<Ljava/lang/String;>;"));
+    let signature = c.attribute("Signature", &signature);
+    let field = [be16(1), vec![0x13, 1, 3, 0], be16(non_null), be16(0)].concat();
+    let field = c.attribute("RuntimeVisibleTypeAnnotations", &field);
+    c.field(0x0001, "names", "Ljava/util/List;", &[signature, field]);
+
+    // void load() throws @Critical IOException { @NonNull Object local = ...; }
+    let throws = [be16(1), vec![0x17], be16(0), vec![0], be16(c.utf8("Lcom/example/Critical;")), be16(0)].concat();
+    let throws = c.attribute("RuntimeInvisibleTypeAnnotations", &throws);
+    let local = [be16(1), vec![0x40], be16(1), be16(0), be16(1), be16(1), vec![0], be16(non_null), be16(0)].concat();
+    let local = c.attribute("RuntimeVisibleTypeAnnotations", &local);
+    let code = [be16(1), be16(2), be32(1), vec![0xB1], be16(0), be16(1), local].concat();
+    let code = c.attribute("Code", &code);
+    c.method(0x0001, "load", "()V", &[throws, code]);
+
+    // class Foo<@NonNull T>
+    let class_info = [be16(1), vec![0x00, 0], vec![0], be16(non_null), be16(0)].concat();
+    c.class_attribute("RuntimeVisibleTypeAnnotations", &class_info);
+
+    let mut options = ParseOptions::new();
+    options.retain_code(true);
+    let class = Class::read_with(&mut &c.to_bytes()[..], &options).unwrap();
+
+    assert_eq!(class.type_annotations().map(|a| (a.target_type, a.target.clone())).collect::<Vec<_>>(), &[(0x00, TypeTarget::TypeParameter { index: 0 })]);
+
+    let names = &class.fields[0];
+    let annotation = &names.visible_type_annotations()[0];
+    assert_eq!((annotation.target_type, &annotation.target), (0x13, &TypeTarget::Empty));
+    assert_eq!(annotation.path.to_string(), "0;");
+    assert_eq!(annotation.annotation.class(), "org/checkerframework/checker/nullness/qual/NonNull");
+    let ty = signature::Type::parse(names.signature_str().unwrap()).unwrap();
+    assert_eq!(TypeUse::new(&ty).at(&annotation.path).unwrap().to_string(), "Ljava/lang/String;");
+
+    let load = &class.methods[0];
+    assert!(load.visible_type_annotations().is_empty());
+    assert_eq!(load.invisible_type_annotations()[0].target, TypeTarget::Throws { index: 0 });
+    let local = load.code().unwrap().type_annotations().unwrap();
+    assert_eq!(local.len(), 1);
+    assert_eq!(local[0].target, TypeTarget::LocalVariable(vec![(0, 1, 1)]));
+    assert_eq!(local[0].path, TypePath::root());
+
+    let mut c = testing::ClassFile::new("com/example/Foo", Some("java/lang/Object"));
+    let bad = [be16(1), vec![0x99]].concat();
+    c.class_attribute("RuntimeVisibleTypeAnnotations", &bad);
+    assert!(Class::read(&mut &c.to_bytes()[..]).is_err());
+}
+
+#[test] fn annotation_default() {
+    use crate::testing::be16;
+
+    let mut c = testing::ClassFile::new("com/example/Config", Some("java/lang/Object"));
+    c.flags = 0x2601; // PUBLIC | INTERFACE | ABSTRACT | ANNOTATION
+    c.interface("java/lang/annotation/Annotation");
+    let timeout = [vec![b'J'], be16(c.long(30))].concat();
+    let timeout = c.attribute("AnnotationDefault", &timeout);
+    c.method(0x0401, "timeout", "()J", &[timeout]);
+    let tags = [vec![b'['], be16(2), vec![b's'], be16(c.utf8("a")), vec![b's'], be16(c.utf8("b"))].concat();
+    let tags = c.attribute("AnnotationDefault", &tags);
+    c.method(0x0401, "tags", "()[Ljava/lang/String;", &[tags]);
+    c.method(0x0401, "name", "()Ljava/lang/String;", &[]);
+    let class = c.read();
+
+    assert_eq!(class.methods[0].annotation_default(), Some(&ElementValue::Long(30)));
+    assert_eq!(class.methods[1].annotation_default(), Some(&ElementValue::Array(vec![ElementValue::String("a".to_owned()), ElementValue::String("b".to_owned())])));
+    assert_eq!(class.methods[2].annotation_default(), None);
+}
@@ -1,34 +1,50 @@
-use crate::*;
+//! [Java SE 7 &sect; 4.7](https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.7):  The attributes of
+//! classes, fields, methods, and code, as decoded by jreflection.
+//!
+//! Most attributes are also surfaced through typed accessors, such as [Class::source_file] or [Method::parameters].
+//! [Attribute::decode] is for branching on the attributes themselves, such as the `attributes` of a [Code], or attributes
+//! whose names are listed by [Class::attribute_names].
+//!
+//! [Class::source_file]:       ../struct.Class.html#method.source_file
+//! [Class::attribute_names]:   ../struct.Class.html#method.attribute_names
+//! [Method::parameters]:       ../struct.Method.html#method.parameters
+//! [Attribute::decode]:        enum.Attribute.html#method.decode
+//! [Code]:                     ../code/struct.Code.html
+
+use crate::{bootstrap, class, constants, field, method, module, Class, CustomAttribute, DuplicateAttributes, ParseOptions, Warning, WarningKind};
+use crate::constants::{Constant, Constants};
 use crate::io::be::*;
+#[cfg(feature = "annotations")] use crate::annotation;
+#[cfg(feature = "code")] use crate::code;
 
 use std::io::{self, Read};
+use std::sync::Arc;
 
 
 
-/// https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.7
+/// [Java SE 7 &sect; 4.7](https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.7):  A decoded attribute.
+///
+/// New attributes are added as new JVMS versions introduce them, so matches need a wildcard arm.
 #[allow(non_camel_case_types)]
 #[derive(Clone, Debug)]
-pub(crate) enum Attribute {
+pub enum Attribute {
     /// [Java SE 7 &sect; 4.7.2](https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.7.2)
     ConstantValue(field::Constant),
 
-    /// https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.7.3
-    Code { #[doc(hidden)] __nyi: () },
-
-    /// https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.7.4
-    StackMapTable { #[doc(hidden)] __nyi: () },
+    /// [Java SE 7 &sect; 4.7.3](https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.7.3):  Requires the `code` feature.
+    #[cfg(feature = "code")] Code(code::Code),
 
-    /// https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.7.5
-    Exceptions { #[doc(hidden)] __nyi: () },
+    /// [Java SE 7 &sect; 4.7.5](https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.7.5):  The checked exceptions a method declares it `throws`.
+    Exceptions(Vec<class::IdBuf>),
 
-    /// https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.7.6
-    InnerClasses { #[doc(hidden)] __nyi: () },
+    /// [Java SE 7 &sect; 4.7.6](https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.7.6)
+    InnerClasses(Vec<class::InnerClass>),
 
-    /// https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.7.7
-    EnclosingMethod { #[doc(hidden)] __nyi: () },
+    /// [Java SE 7 &sect; 4.7.7](https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.7.7)
+    EnclosingMethod(class::EnclosingMethod),
 
-    /// https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.7.8
-    Synthetic { #[doc(hidden)] __nyi: () },
+    /// [Java SE 7 &sect; 4.7.8](https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.7.8)
+    Synthetic { #[doc(hidden)] __in_case_of_extension_break_glass: () },
 
     /// [Java SE 7 &sect; 4.3.4](https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.3.4): Signatures
     /// 
@@ -44,54 +60,162 @@ pub(crate) enum Attribute {
     Signature(String),
 
     /// https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.7.10
-    SourceFile { #[doc(hidden)] __nyi: () },
+    /// 
+    /// The name of the source file the class was compiled from, without any directory, such as `"Object.java"`.
+    SourceFile(String),
 
     /// https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.7.11
-    SourceDebugExtension { #[doc(hidden)] __nyi: () },
+    ///
+    /// Extended debug information, usually an [SMAP](../smap/index.html), decoded from "Modified UTF8".
+    SourceDebugExtension(String),
 
-    /// https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.7.12
-    LineNumberTable { #[doc(hidden)] __nyi: () },
+    /// [Java SE 7 &sect; 4.7.15](https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.7.15)
+    Deprecated { #[doc(hidden)] __in_case_of_extension_break_glass: () },
 
-    /// https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.7.13
-    LocalVariableTable { #[doc(hidden)] __nyi: () },
+    /// [Java SE 7 &sect; 4.7.16](https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.7.16)
+    #[cfg(feature = "annotations")] RuntimeVisibleAnnotations(Vec<annotation::Annotation>),
 
-    /// https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.7.14
-    LocalVariableTypeTable { #[doc(hidden)] __nyi: () },
+    /// [Java SE 7 &sect; 4.7.17](https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.7.17)
+    #[cfg(feature = "annotations")] RuntimeInvisibleAnnotations(Vec<annotation::Annotation>),
 
-    /// [Java SE 7 &sect; 4.7.15](https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.7.15)
-    Deprecated { #[doc(hidden)] __in_case_of_extension_break_glass: () },
+    /// [Java SE 7 &sect; 4.7.18](https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.7.18):  The annotations of each parameter
+    #[cfg(feature = "annotations")] RuntimeVisibleParameterAnnotations(Vec<Vec<annotation::Annotation>>),
+
+    /// [Java SE 7 &sect; 4.7.19](https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.7.19):  The annotations of each parameter
+    #[cfg(feature = "annotations")] RuntimeInvisibleParameterAnnotations(Vec<Vec<annotation::Annotation>>),
+
+    /// [Java SE 8 &sect; 4.7.20](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-4.html#jvms-4.7.20)
+    #[cfg(feature = "annotations")] RuntimeVisibleTypeAnnotations(Vec<annotation::TypeAnnotation>),
+
+    /// [Java SE 8 &sect; 4.7.21](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-4.html#jvms-4.7.21)
+    #[cfg(feature = "annotations")] RuntimeInvisibleTypeAnnotations(Vec<annotation::TypeAnnotation>),
+
+    /// [Java SE 7 &sect; 4.7.20](https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.7.20)
+    #[cfg(feature = "annotations")] AnnotationDefault(annotation::ElementValue),
+
+    /// [Java SE 8 &sect; 4.7.23](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-4.html#jvms-4.7.23)
+    BootstrapMethods(Vec<bootstrap::BootstrapMethod>),
+
+    /// [Java SE 8 &sect; 4.7.24](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-4.html#jvms-4.7.24)
+    MethodParameters(Vec<method::Parameter>),
+
+    /// [Java SE 9 &sect; 4.7.25](https://docs.oracle.com/javase/specs/jvms/se9/html/jvms-4.html#jvms-4.7.25)
+    Module(module::Module),
 
-    /// https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.7.16
-    RuntimeVisibleAnnotations { #[doc(hidden)] __nyi: () },
+    /// [Java SE 9 &sect; 4.7.26](https://docs.oracle.com/javase/specs/jvms/se9/html/jvms-4.html#jvms-4.7.26)
+    ModulePackages(Vec<String>),
 
-    /// https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.7.17
-    RuntimeInvisibleAnnotations { #[doc(hidden)] __nyi: () },
+    /// [Java SE 9 &sect; 4.7.27](https://docs.oracle.com/javase/specs/jvms/se9/html/jvms-4.html#jvms-4.7.27)
+    ModuleMainClass(String),
 
-    /// https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.7.18
-    RuntimeVisibleParameterAnnotations { #[doc(hidden)] __nyi: () },
+    /// [Java SE 11 &sect; 4.7.28](https://docs.oracle.com/javase/specs/jvms/se11/html/jvms-4.html#jvms-4.7.28)
+    NestHost(class::IdBuf),
 
-    /// https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.7.19
-    RuntimeInvisibleParameterAnnotations { #[doc(hidden)] __nyi: () },
+    /// [Java SE 11 &sect; 4.7.29](https://docs.oracle.com/javase/specs/jvms/se11/html/jvms-4.html#jvms-4.7.29)
+    NestMembers(Vec<class::IdBuf>),
 
-    /// https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.7.20
-    AnnotationDefault { #[doc(hidden)] __nyi: () },
+    /// [Java SE 16 &sect; 4.7.30](https://docs.oracle.com/javase/specs/jvms/se16/html/jvms-4.html#jvms-4.7.30)
+    Record(Vec<class::RecordComponent>),
 
-    /// https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.7.21
-    BootstrapMethods { #[doc(hidden)] __nyi: () },
+    /// [Java SE 17 &sect; 4.7.31](https://docs.oracle.com/javase/specs/jvms/se17/html/jvms-4.html#jvms-4.7.31)
+    PermittedSubclasses(Vec<class::IdBuf>),
 
-    /// An unrecognized attribute was used!
-    Unknown,
+    /// An attribute decoded by a decoder registered with [ParseOptions::register_attribute](../struct.ParseOptions.html#method.register_attribute)
+    Custom(CustomAttribute),
 
-    #[doc(hidden)] __NonExhaustive,
+    /// An unrecognized attribute was used!  Holds the raw `info` of the attribute, not including the name index or length.
+    ///
+    /// This includes the attributes of a [Code](../code/struct.Code.html) whose meaning depends on the code they belong
+    /// to (`StackMapTable`, `LineNumberTable`, `LocalVariableTable`, and `LocalVariableTypeTable`), which are instead
+    /// decoded by [Code::stack_map_frames](../code/struct.Code.html#method.stack_map_frames), [Code::line_numbers](../code/struct.Code.html#method.line_numbers), etc.
+    Unknown(Vec<u8>),
+
+    #[doc(hidden)] _NonExhaustive,
+}
+
+/// An undecoded attribute, as found in a [Code]'s [attributes](../code/struct.Code.html#structfield.attributes), or the
+/// [unknown_attributes](../struct.Class.html#method.unknown_attributes) of a class, field, or method.
+///
+/// [Code]: ../code/struct.Code.html
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RawAttribute {
+    /// The name of the attribute, such as `"LineNumberTable"`.
+    pub name:   String,
+
+    /// The attribute's `info`, not including the name index or length.
+    pub info:   Vec<u8>,
 }
 
 impl Attribute {
-    pub(crate) fn read(read: &mut impl Read, constants: &Constants) -> io::Result<Self> {
+    /// Decode `attribute` (such as one of a [Code]'s [attributes](../code/struct.Code.html#structfield.attributes))
+    /// using the constants of `class`, the class it was read from.  Unrecognized attributes - and those whose feature
+    /// (`code` or `annotations`) is disabled - decode as [Unknown](#variant.Unknown).
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use jreflection::{*, attributes::Attribute};
+    /// # #[cfg(feature = "code")] {
+    /// # let mut options = ParseOptions::new();
+    /// # options.retain_code(true);
+    /// # let class = Class::read_with(&mut std::fs::File::open("Foo.class").unwrap(), &options).unwrap();
+    /// let code = class.methods[0].code().unwrap();
+    /// for attribute in code.attributes.iter() {
+    ///     match Attribute::decode(&class, attribute).unwrap() {
+    ///         Attribute::Unknown(info) => println!("{}: {} bytes", attribute.name, info.len()),
+    ///         other => println!("{:?}", other),
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    ///
+    /// [Code]: ../code/struct.Code.html
+    pub fn decode(class: &Class, attribute: &RawAttribute) -> io::Result<Self> {
+        #[cfg(feature = "code")] let options = { let mut options = ParseOptions::new(); options.retain_code(true); options };
+        #[cfg(not(feature = "code"))] let options = ParseOptions::new();
+        let mut read = &attribute.info[..];
+        let decoded = Self::read_info(attribute.name.as_str(), attribute.info.len(), &mut read, &class.constants, &options, &mut Vec::new())?;
+        io_assert!(read.is_empty(), "{} attribute has {} trailing bytes", attribute.name, read.len());
+        Ok(decoded.unwrap_or_else(|| Attribute::Unknown(attribute.info.clone())))
+    }
+
+    /// Read an attribute, also returning the name of the attribute (useful for [Attribute::Unknown] attributes.)
+    ///
+    /// `seen` holds the names of the attributes previously read for the same class, field, or method, to warn about duplicates.
+    /// Returns `None` instead of the attribute for a repeated standard attribute ignored per [DuplicateAttributes::FirstWins],
+    /// or an attribute skipped by `read_info`.
+    pub(crate) fn read_named<'c>(read: &mut impl Read, constants: &'c Arc<Constants>, options: &ParseOptions, seen: &mut Vec<&'c str>, warnings: &mut Vec<Warning>) -> io::Result<(&'c str, Option<Self>)> {
+        let (name, attribute) = Self::read_named_impl(read, constants, options, warnings)?;
+        match attribute {
+            Some(Attribute::Custom(_)) | Some(Attribute::Unknown(_))    => Ok((name, attribute)), // Nonstandard attributes may legitimately repeat
+            _ if !seen.contains(&name)                                  => { seen.push(name); Ok((name, attribute)) },
+            _ => match options.duplicate_attributes_policy() {
+                DuplicateAttributes::Error      => io_data_err!("Duplicate {} attribute", name),
+                DuplicateAttributes::Warn       => { warnings.push(Warning::new(WarningKind::DuplicateAttribute(name.to_owned()))); Ok((name, attribute)) },
+                DuplicateAttributes::FirstWins  => { warnings.push(Warning::new(WarningKind::DuplicateAttribute(name.to_owned()))); Ok((name, None)) },
+            },
+        }
+    }
+
+    fn read_named_impl<'c>(read: &mut impl Read, constants: &'c Arc<Constants>, options: &ParseOptions, warnings: &mut Vec<Warning>) -> io::Result<(&'c str, Option<Self>)> {
         let attribute_name_index    = read_u2(read)?;
         let attribute_length        = read_u4(read)? as usize;
 
         let name = constants.get_utf8(attribute_name_index)?;
-        match name {
+        let attribute = Self::read_info(name, attribute_length, read, constants, options, warnings)?;
+        Ok((name, attribute))
+    }
+
+    /// Read the `info` of attribute `name`, or skip it, returning `None`, if it isn't to be retained:  `Code` that
+    /// wasn't requested via [ParseOptions::retain_code], or attributes whose feature is disabled.
+    fn read_info(name: &str, attribute_length: usize, read: &mut impl Read, constants: &Arc<Constants>, options: &ParseOptions, warnings: &mut Vec<Warning>) -> io::Result<Option<Self>> {
+        if options.has_decoder(name) {
+            let info = read_attribute_info(read, name, attribute_length)?;
+            let custom = options.decode_attribute(name, &info[..], constants).expect("has_decoder")?;
+            return Ok(Some(Attribute::Custom(custom)));
+        }
+
+        let attribute = match name {
             "ConstantValue" => {
                 // https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.7.2
                 io_assert!(attribute_length == 2);
@@ -113,35 +237,180 @@ impl Attribute {
                 let constant = constants.get_utf8(signature_index)?;
                 Ok(Attribute::Signature(constant.to_string()))
             },
+            "SourceFile" => {
+                // https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.7.10
+                io_assert!(attribute_length == 2);
+                let sourcefile_index = read_u2(read)?;
+                Ok(Attribute::SourceFile(constants.get_utf8(sourcefile_index)?.to_owned()))
+            },
+            "SourceDebugExtension" => {
+                // https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.7.11
+                let info = read_attribute_info(read, name, attribute_length)?;
+                let text = constants::read_modified_utf8_as_utf8(&info[..]).unwrap_or_else(|| String::from_utf8_lossy(&info[..]).into_owned());
+                Ok(Attribute::SourceDebugExtension(text))
+            },
             "Deprecated" => {
                 // https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.7.15
                 // 
                 // attribute_length should be 0 according to the docs... but if it's ever extended with more info, it'd
-                // presumably be a semi-ignorable message that shouldn't result in an error here, so I just silently
-                // skip error handling here.
+                // presumably be a semi-ignorable message that shouldn't result in an error here, so I just warn.
+                if attribute_length != 0 { warnings.push(Warning::new(WarningKind::NonEmptyAttribute { name: name.to_owned(), length: attribute_length })); }
                 read_ignore(read, attribute_length)?;
                 Ok(Attribute::Deprecated {__in_case_of_extension_break_glass:()})
             },
+            #[cfg(feature = "code")]
+            "Code" if options.retain_code_enabled() || options.local_variable_names_enabled() => {
+                // https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.7.3
+                let info = read_attribute_info(read, name, attribute_length)?;
+                let mut info = &info[..];
+                let code = code::Code::read(&mut info, constants, options)?;
+                io_assert!(info.is_empty(), "Code attribute has {} trailing bytes", info.len());
+                Ok(Attribute::Code(code))
+            },
+            "Synthetic" => {
+                // https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.7.8
+                //
+                // Older compilers emit this attribute instead of ACC_SYNTHETIC.  Like Deprecated, attribute_length
+                // should be 0, but isn't worth erroring over.
+                if attribute_length != 0 { warnings.push(Warning::new(WarningKind::NonEmptyAttribute { name: name.to_owned(), length: attribute_length })); }
+                read_ignore(read, attribute_length)?;
+                Ok(Attribute::Synthetic {__in_case_of_extension_break_glass:()})
+            },
+            "Module" => {
+                // https://docs.oracle.com/javase/specs/jvms/se9/html/jvms-4.html#jvms-4.7.25
+                let info = read_attribute_info(read, name, attribute_length)?;
+                let mut info = &info[..];
+                let module = module::Module::read(&mut info, constants)?;
+                io_assert!(info.is_empty(), "Module attribute has {} trailing bytes", info.len());
+                Ok(Attribute::Module(module))
+            },
+            "ModulePackages" => {
+                // https://docs.oracle.com/javase/specs/jvms/se9/html/jvms-4.html#jvms-4.7.26
+                let info = read_attribute_info(read, name, attribute_length)?;
+                let mut info = &info[..];
+                let packages = module::Module::read_packages(&mut info, constants)?;
+                io_assert!(info.is_empty(), "ModulePackages attribute has {} trailing bytes", info.len());
+                Ok(Attribute::ModulePackages(packages))
+            },
+            "ModuleMainClass" => {
+                // https://docs.oracle.com/javase/specs/jvms/se9/html/jvms-4.html#jvms-4.7.27
+                io_assert!(attribute_length == 2);
+                Ok(Attribute::ModuleMainClass(constants.get_class(read_u2(read)?)?.to_owned()))
+            },
+            "InnerClasses" => {
+                // https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.7.6
+                let info = read_attribute_info(read, name, attribute_length)?;
+                let mut info = &info[..];
+                let classes = class::InnerClass::read_list(&mut info, constants)?;
+                io_assert!(info.is_empty(), "InnerClasses attribute has {} trailing bytes", info.len());
+                Ok(Attribute::InnerClasses(classes))
+            },
+            "EnclosingMethod" => {
+                // https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.7.7
+                io_assert!(attribute_length == 4);
+                Ok(Attribute::EnclosingMethod(class::EnclosingMethod::read(read, constants)?))
+            },
+            "NestHost" => {
+                // https://docs.oracle.com/javase/specs/jvms/se11/html/jvms-4.html#jvms-4.7.28
+                io_assert!(attribute_length == 2);
+                Ok(Attribute::NestHost(class::IdBuf::new(constants.get_class(read_u2(read)?)?.to_owned())))
+            },
+            "NestMembers" | "PermittedSubclasses" | "Exceptions" => {
+                // https://docs.oracle.com/javase/specs/jvms/se11/html/jvms-4.html#jvms-4.7.29
+                // https://docs.oracle.com/javase/specs/jvms/se17/html/jvms-4.html#jvms-4.7.31
+                // https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.7.5
+                let count = read_u2(read)? as usize;
+                io_assert!(attribute_length == 2 + 2 * count, "{} attribute has length {}, expected {} for {} classes", name, attribute_length, 2 + 2 * count, count);
+                let mut classes = Vec::with_capacity(count);
+                for _ in 0..count { classes.push(class::IdBuf::new(constants.get_class(read_u2(read)?)?.to_owned())); }
+                match name {
+                    "NestMembers"   => Ok(Attribute::NestMembers(classes)),
+                    "Exceptions"    => Ok(Attribute::Exceptions(classes)),
+                    _               => Ok(Attribute::PermittedSubclasses(classes)),
+                }
+            },
+            "Record" => {
+                // https://docs.oracle.com/javase/specs/jvms/se16/html/jvms-4.html#jvms-4.7.30
+                let info = read_attribute_info(read, name, attribute_length)?;
+                let mut info = &info[..];
+                let components = class::RecordComponent::read_list(&mut info, constants, options, warnings)?;
+                io_assert!(info.is_empty(), "Record attribute has {} trailing bytes", info.len());
+                Ok(Attribute::Record(components))
+            },
+            #[cfg(feature = "annotations")]
+            "RuntimeVisibleAnnotations" | "RuntimeInvisibleAnnotations" => {
+                // https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.7.16
+                let info = read_attribute_info(read, name, attribute_length)?;
+                let mut info = &info[..];
+                let annotations = annotation::Annotation::read_list(&mut info, constants)?;
+                io_assert!(info.is_empty(), "{} attribute has {} trailing bytes", name, info.len());
+                if name == "RuntimeVisibleAnnotations" { Ok(Attribute::RuntimeVisibleAnnotations(annotations)) } else { Ok(Attribute::RuntimeInvisibleAnnotations(annotations)) }
+            },
+            #[cfg(feature = "annotations")]
+            "RuntimeVisibleParameterAnnotations" | "RuntimeInvisibleParameterAnnotations" => {
+                // https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.7.18
+                let info = read_attribute_info(read, name, attribute_length)?;
+                let mut info = &info[..];
+                let parameters = annotation::Annotation::read_parameter_lists(&mut info, constants)?;
+                io_assert!(info.is_empty(), "{} attribute has {} trailing bytes", name, info.len());
+                if name == "RuntimeVisibleParameterAnnotations" { Ok(Attribute::RuntimeVisibleParameterAnnotations(parameters)) } else { Ok(Attribute::RuntimeInvisibleParameterAnnotations(parameters)) }
+            },
+            #[cfg(feature = "annotations")]
+            "AnnotationDefault" => {
+                // https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.7.20
+                let info = read_attribute_info(read, name, attribute_length)?;
+                let mut info = &info[..];
+                let value = annotation::ElementValue::read(&mut info, constants)?;
+                io_assert!(info.is_empty(), "AnnotationDefault attribute has {} trailing bytes", info.len());
+                Ok(Attribute::AnnotationDefault(value))
+            },
+            "BootstrapMethods" => {
+                // https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-4.html#jvms-4.7.23
+                let info = read_attribute_info(read, name, attribute_length)?;
+                let mut info = &info[..];
+                let methods = bootstrap::BootstrapMethod::read_list(&mut info, constants)?;
+                io_assert!(info.is_empty(), "BootstrapMethods attribute has {} trailing bytes", info.len());
+                Ok(Attribute::BootstrapMethods(methods))
+            },
+            "MethodParameters" => {
+                // https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-4.html#jvms-4.7.24
+                let info = read_attribute_info(read, name, attribute_length)?;
+                let mut info = &info[..];
+                let parameters = method::Parameter::read_list(&mut info, constants)?;
+                io_assert!(info.is_empty(), "MethodParameters attribute has {} trailing bytes", info.len());
+                Ok(Attribute::MethodParameters(parameters))
+            },
+            #[cfg(feature = "annotations")]
+            "RuntimeVisibleTypeAnnotations" | "RuntimeInvisibleTypeAnnotations" => {
+                // https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-4.html#jvms-4.7.20
+                let info = read_attribute_info(read, name, attribute_length)?;
+                let mut info = &info[..];
+                let annotations = annotation::TypeAnnotation::read_list(&mut info, constants)?;
+                io_assert!(info.is_empty(), "{} attribute has {} trailing bytes", name, info.len());
+                if name == "RuntimeVisibleTypeAnnotations" { Ok(Attribute::RuntimeVisibleTypeAnnotations(annotations)) } else { Ok(Attribute::RuntimeInvisibleTypeAnnotations(annotations)) }
+            },
 
-            // Unimplemented attributes
-            "Code"                                  => { read_ignore(read, attribute_length)?; Ok(Attribute::Code                                  {__nyi:()}) },
-            "StackMapTable"                         => { read_ignore(read, attribute_length)?; Ok(Attribute::StackMapTable                         {__nyi:()}) },
-            "Exceptions"                            => { read_ignore(read, attribute_length)?; Ok(Attribute::Exceptions                            {__nyi:()}) },
-            "InnerClasses"                          => { read_ignore(read, attribute_length)?; Ok(Attribute::InnerClasses                          {__nyi:()}) },
-            "EnclosingMethod"                       => { read_ignore(read, attribute_length)?; Ok(Attribute::EnclosingMethod                       {__nyi:()}) },
-            "Synthetic"                             => { read_ignore(read, attribute_length)?; Ok(Attribute::Synthetic                             {__nyi:()}) },
-            "SourceFile"                            => { read_ignore(read, attribute_length)?; Ok(Attribute::SourceFile                            {__nyi:()}) },
-            "SourceDebugExtension"                  => { read_ignore(read, attribute_length)?; Ok(Attribute::SourceDebugExtension                  {__nyi:()}) },
-            "LineNumberTable"                       => { read_ignore(read, attribute_length)?; Ok(Attribute::LineNumberTable                       {__nyi:()}) },
-            "LocalVariableTable"                    => { read_ignore(read, attribute_length)?; Ok(Attribute::LocalVariableTable                    {__nyi:()}) },
-            "LocalVariableTypeTable"                => { read_ignore(read, attribute_length)?; Ok(Attribute::LocalVariableTypeTable                {__nyi:()}) },
-            "RuntimeVisibleAnnotations"             => { read_ignore(read, attribute_length)?; Ok(Attribute::RuntimeVisibleAnnotations             {__nyi:()}) },
-            "RuntimeInvisibleAnnotations"           => { read_ignore(read, attribute_length)?; Ok(Attribute::RuntimeInvisibleAnnotations           {__nyi:()}) },
-            "RuntimeVisibleParameterAnnotations"    => { read_ignore(read, attribute_length)?; Ok(Attribute::RuntimeVisibleParameterAnnotations    {__nyi:()}) },
-            "RuntimeInvisibleParameterAnnotations"  => { read_ignore(read, attribute_length)?; Ok(Attribute::RuntimeInvisibleParameterAnnotations  {__nyi:()}) },
-            "AnnotationDefault"                     => { read_ignore(read, attribute_length)?; Ok(Attribute::AnnotationDefault                     {__nyi:()}) },
-            "BootstrapMethods"                      => { read_ignore(read, attribute_length)?; Ok(Attribute::BootstrapMethods                      {__nyi:()}) },
-            _                                       => { read_ignore(read, attribute_length)?; Ok(Attribute::Unknown) },
-        }
+            // Skipped attributes
+            "Code"                                  => { read_ignore(read, attribute_length)?; return Ok(None); },
+            #[cfg(not(feature = "annotations"))]
+            "RuntimeVisibleAnnotations" | "RuntimeInvisibleAnnotations" | "RuntimeVisibleParameterAnnotations" | "RuntimeInvisibleParameterAnnotations" |
+            "RuntimeVisibleTypeAnnotations" | "RuntimeInvisibleTypeAnnotations" | "AnnotationDefault"
+                                                    => { read_ignore(read, attribute_length)?; return Ok(None); },
+            _                                       => {
+                let info = read_attribute_info(read, name, attribute_length)?;
+                Ok(Attribute::Unknown(info))
+            },
+        }?;
+        Ok(Some(attribute))
     }
 }
+
+/// Read the `attribute_length` bytes of the `info` of attribute `name`.  `attribute_length` comes straight from the
+/// (possibly corrupt or hostile) classfile, so this only allocates as the bytes actually arrive instead of trusting it
+/// up front, and rejects attributes that claim more bytes than remain.
+pub(crate) fn read_attribute_info(read: &mut impl Read, name: &str, attribute_length: usize) -> io::Result<Vec<u8>> {
+    let mut info = Vec::new();
+    read.by_ref().take(attribute_length as u64).read_to_end(&mut info)?;
+    io_assert!(info.len() == attribute_length, "{} attribute truncated:  attribute_length is {}, but only {} bytes remain", name, attribute_length, info.len());
+    Ok(info)
+}
@@ -0,0 +1,350 @@
+//! Which versions of which families of sources (JDKs, Android platforms, etc.) contain a given class or member.
+
+use crate::{Class, Source};
+#[cfg(test)] use crate::{class, field, method, Field, Method};
+
+use std::collections::*;
+use std::io::{self, Read, Write};
+
+
+
+/// The versions, per labeled source family, in which a class or member is available.
+///
+/// # Examples
+///
+/// ```
+/// # use jreflection::availability::Versions;
+/// let mut versions = Versions::default();
+/// versions.insert("android", 26);
+/// versions.insert("android", 27);
+/// versions.insert("openjdk", 9);
+/// assert_eq!(versions.min("android"), Some(26));
+/// assert!( versions.contains("openjdk", 9));
+/// assert!(!versions.contains("openjdk", 8));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Versions(BTreeMap<String, BTreeSet<u32>>);
+
+impl Versions {
+    /// Record that something is available in `version` of `family`.
+    pub fn insert(&mut self, family: &str, version: u32) {
+        if let Some(versions) = self.0.get_mut(family) {
+            versions.insert(version);
+        } else {
+            let mut versions = BTreeSet::new();
+            versions.insert(version);
+            self.0.insert(family.to_owned(), versions);
+        }
+    }
+
+    /// Iterate over the families something was available in at least one version of.
+    pub fn families(&self) -> impl Iterator<Item = &str> { self.0.keys().map(|f| f.as_str()) }
+
+    /// Get the versions of `family` something is available in, if any.
+    pub fn get(&self, family: &str) -> Option<&BTreeSet<u32>> { self.0.get(family) }
+
+    /// Check if something is available in `version` of `family`.
+    pub fn contains(&self, family: &str, version: u32) -> bool { self.0.get(family).map(|v| v.contains(&version)).unwrap_or(false) }
+
+    /// The oldest version of `family` something is available in (e.g. for "requires API 26+" annotations.)
+    pub fn min(&self, family: &str) -> Option<u32> { self.0.get(family).and_then(|v| v.iter().next().copied()) }
+
+    /// The newest version of `family` something is available in.
+    pub fn max(&self, family: &str) -> Option<u32> { self.0.get(family).and_then(|v| v.iter().next_back().copied()) }
+
+    /// Iterate over every `(family, version)` pair something is available in.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, u32)> {
+        self.0.iter().flat_map(|(family, versions)| versions.iter().map(move |v| (family.as_str(), *v)))
+    }
+}
+
+
+
+#[derive(Clone, Debug, Default)]
+struct ClassAvailability {
+    versions:   Versions,
+    members:    BTreeMap<String, BTreeMap<String, Versions>>, // name -> descriptor -> versions
+}
+
+/// A matrix of which classes and members are available in which versions of which families of sources.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use jreflection::{android, availability::Availability};
+/// # #[cfg(feature = "jar")] {
+/// let mut availability = Availability::new();
+/// for platform in android::discover_sdk().unwrap().platforms().unwrap() {
+///     availability.add_source("android", platform.api_level, &platform.source().unwrap()).unwrap();
+/// }
+///
+/// let list_of = availability.lookup("java/util/List", "of", "([Ljava/lang/Object;)Ljava/util/List;").unwrap();
+/// println!("requires API {}+", list_of.min("android").unwrap());
+/// # }
+/// ```
+///
+/// Scans can be [exported](#method.write_csv) to CSV, and [imported](#method.read_csv) again later to be
+/// [merged](#method.merge) with newer scans, without keeping every old jar around:
+///
+/// ```no_run
+/// # use jreflection::{Source, availability::Availability};
+/// # fn main() -> std::io::Result<()> {
+/// # #[cfg(feature = "jar")] {
+/// let mut availability = Availability::read_csv(&mut std::fs::File::open("android-1-29.csv")?)?;
+/// availability.add_source("android", 30, &Source::from_jar("android-30/android.jar")?)?;
+/// availability.write_csv(&mut std::fs::File::create("android-1-30.csv")?)?;
+///
+/// let versions = availability.class("android/app/ActivityGroup").unwrap();
+/// println!("added in API {:?}, removed in API {:?}", versions.min("android"), availability.removed_in("android", versions));
+/// # }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Availability {
+    classes: BTreeMap<String, ClassAvailability>,
+    scanned: Versions,
+}
+
+impl Availability {
+    /// Create an empty availability matrix.
+    pub fn new() -> Self { Self::default() }
+
+    /// Record every class (and every member of every class) of `source` as being available in `version` of `family`.
+    pub fn add_source(&mut self, family: &str, version: u32, source: &Source) -> io::Result<()> {
+        self.scanned.insert(family, version);
+        source.for_each_class(|name|{
+            let class = source.read_class(name)?;
+            self.add_class(family, version, &class);
+            Ok(())
+        })
+    }
+
+    /// Record `class` (and all of its members) as being available in `version` of `family`.
+    pub fn add_class(&mut self, family: &str, version: u32, class: &Class) {
+        self.scanned.insert(family, version);
+        let entry = self.classes.entry(class.path.as_str().to_owned()).or_default();
+        entry.versions.insert(family, version);
+
+        let members = class.fields.iter().map(|f| (f.name.as_str(), f.descriptor_str()))
+            .chain(class.methods.iter().map(|m| (m.name.as_str(), m.descriptor_str())));
+        for (name, descriptor) in members {
+            entry.members.entry(name.to_owned()).or_default()
+                .entry(descriptor.to_owned()).or_default()
+                .insert(family, version);
+        }
+    }
+
+    /// Iterate over the paths of all classes that were available in at least one version of one family.
+    pub fn classes(&self) -> impl Iterator<Item = &str> { self.classes.keys().map(|c| c.as_str()) }
+
+    /// Get the versions a class is available in, given a JNI path such as `"java/util/List"`.
+    pub fn class(&self, class: &str) -> Option<&Versions> {
+        self.classes.get(class).map(|c| &c.versions)
+    }
+
+    /// Get the versions a field or method is available in, given a JNI class path, a member name, and a descriptor.
+    ///
+    /// Fields and methods share a namespace here, but can't collide, since only method descriptors contain `(`.
+    pub fn lookup(&self, class: &str, name: &str, descriptor: &str) -> Option<&Versions> {
+        self.classes.get(class)?.members.get(name)?.get(descriptor)
+    }
+
+    /// Iterate over the `(name, descriptor, versions)` of all members ever available in a given class.
+    pub fn members<'a>(&'a self, class: &str) -> impl Iterator<Item = (&'a str, &'a str, &'a Versions)> {
+        self.classes.get(class).into_iter().flat_map(|c| c.members.iter().flat_map(|(name, descriptors)|{
+            descriptors.iter().map(move |(descriptor, versions)| (name.as_str(), descriptor.as_str(), versions))
+        }))
+    }
+
+    /// The versions of each family that were scanned, whether or not they contained anything.
+    pub fn scanned(&self) -> &Versions { &self.scanned }
+
+    /// The first scanned version of `family` after the newest one `versions` (from [class] or [lookup]) includes, if
+    /// any - that is, when something was removed.  `None` if it's still available in the newest scanned version, or
+    /// was never available at all.
+    ///
+    /// [class]:    #method.class
+    /// [lookup]:   #method.lookup
+    pub fn removed_in(&self, family: &str, versions: &Versions) -> Option<u32> {
+        let newest = versions.max(family)?;
+        self.scanned.get(family)?.range(newest+1..).next().copied()
+    }
+
+    /// Merge another matrix - such as one [imported](#method.read_csv) from a previous scan - into this one.
+    pub fn merge(&mut self, other: &Availability) {
+        for (family, version) in other.scanned.iter() { self.scanned.insert(family, version); }
+        for (path, other) in other.classes.iter() {
+            let entry = self.classes.entry(path.clone()).or_default();
+            for (family, version) in other.versions.iter() { entry.versions.insert(family, version); }
+            for (name, descriptors) in other.members.iter() {
+                for (descriptor, versions) in descriptors.iter() {
+                    let member = entry.members.entry(name.clone()).or_default().entry(descriptor.clone()).or_default();
+                    for (family, version) in versions.iter() { member.insert(family, version); }
+                }
+            }
+        }
+    }
+
+    /// Export this matrix as CSV, with a `family,version,class,name,descriptor` header row, and a row for each
+    /// version of each class and member.  Class rows have an empty name and descriptor.  Scanned versions without
+    /// any classes get a row with an empty class as well, so [read_csv](#method.read_csv) can round trip them.
+    pub fn write_csv(&self, write: &mut impl Write) -> io::Result<()> {
+        let mut out = String::from("family,version,class,name,descriptor\n");
+        let mut empty = self.scanned.clone();
+        for (path, class) in self.classes.iter() {
+            for (family, version) in class.versions.iter() {
+                if let Some(versions) = empty.0.get_mut(family) { versions.remove(&version); }
+                csv_row(&mut out, family, version, path, "", "");
+            }
+            for (name, descriptors) in class.members.iter() {
+                for (descriptor, versions) in descriptors.iter() {
+                    for (family, version) in versions.iter() { csv_row(&mut out, family, version, path, name, descriptor); }
+                }
+            }
+        }
+        for (family, version) in empty.iter() { csv_row(&mut out, family, version, "", "", ""); }
+        write.write_all(out.as_bytes())
+    }
+
+    /// Import a matrix previously exported with [write_csv](#method.write_csv).  Fails with `InvalidData` if the CSV
+    /// is malformed, or doesn't have the expected columns.
+    pub fn read_csv(read: &mut impl Read) -> io::Result<Self> {
+        let mut text = String::new();
+        read.read_to_string(&mut text)?;
+        let mut rows = csv_rows(&text)?.into_iter();
+
+        let header = rows.next().unwrap_or_default();
+        io_assert!(header == ["family", "version", "class", "name", "descriptor"], "Unexpected availability CSV header {:?}", header);
+
+        let mut availability = Availability::new();
+        for (line, row) in rows.enumerate() {
+            let (family, version, path, name, descriptor) = match &row[..] {
+                [f, v, c, n, d] => (f, v, c, n, d),
+                _ => return io_data_err!("Expected 5 columns in availability CSV row {}, got {}", line + 2, row.len()),
+            };
+            let version = match version.parse() {
+                Ok(version) => version,
+                Err(_)      => return io_data_err!("Invalid version {:?} in availability CSV row {}", version, line + 2),
+            };
+            availability.scanned.insert(family, version);
+            if path.is_empty() { continue; }
+            let class = availability.classes.entry(path.clone()).or_default();
+            if name.is_empty() && descriptor.is_empty() {
+                class.versions.insert(family, version);
+            } else {
+                class.members.entry(name.clone()).or_default().entry(descriptor.clone()).or_default().insert(family, version);
+            }
+        }
+        Ok(availability)
+    }
+}
+
+fn csv_row(out: &mut String, family: &str, version: u32, class: &str, name: &str, descriptor: &str) {
+    for (i, field) in [family, &version.to_string(), class, name, descriptor].iter().enumerate() {
+        if i != 0 { out.push(','); }
+        if field.contains(|ch| ch == ',' || ch == '"' || ch == '\n' || ch == '\r') {
+            out.push('"');
+            out.push_str(&field.replace('"', "\"\""));
+            out.push('"');
+        } else {
+            out.push_str(field);
+        }
+    }
+    out.push('\n');
+}
+
+/// Split RFC 4180 style CSV into rows of fields.  Blank lines are skipped.
+fn csv_rows(text: &str) -> io::Result<Vec<Vec<String>>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut chars = text.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '"' if field.is_empty() => loop {
+                match chars.next() {
+                    Some('"') if chars.peek() == Some(&'"') => { chars.next(); field.push('"'); },
+                    Some('"')   => break,
+                    Some(ch)    => field.push(ch),
+                    None        => return io_data_err!("Unterminated quoted field in CSV"),
+                }
+            },
+            ','     => row.push(std::mem::replace(&mut field, String::new())),
+            '\r'    => {},
+            '\n'    => {
+                row.push(std::mem::replace(&mut field, String::new()));
+                if row.len() > 1 || !row[0].is_empty() { rows.push(std::mem::replace(&mut row, Vec::new())); } else { row.clear(); }
+            },
+            ch      => field.push(ch),
+        }
+    }
+    if !field.is_empty() || !row.is_empty() { row.push(field); rows.push(row); }
+    Ok(rows)
+}
+
+#[test] fn member_availability() {
+    let mut list_v25 = Class { path: class::IdBuf::new("java/util/List".to_string()), ..Class::default() };
+    list_v25.methods.push(Method::new(method::Flags::PUBLIC | method::Flags::ABSTRACT, "size".to_string(), "()I".to_string()).unwrap());
+
+    let mut list_v26 = list_v25.clone();
+    list_v26.methods.push(Method::new(method::Flags::PUBLIC | method::Flags::STATIC, "of".to_string(), "()Ljava/util/List;".to_string()).unwrap());
+
+    let mut availability = Availability::new();
+    availability.add_class("android", 25, &list_v25);
+    availability.add_class("android", 26, &list_v26);
+    availability.add_class("android", 27, &list_v26);
+    availability.add_class("openjdk", 9,  &list_v26);
+
+    let class = availability.class("java/util/List").unwrap();
+    assert_eq!(class.min("android"), Some(25));
+    assert_eq!(class.min("openjdk"), Some(9));
+
+    let of = availability.lookup("java/util/List", "of", "()Ljava/util/List;").unwrap();
+    assert_eq!(of.min("android"), Some(26));
+    assert_eq!(of.max("android"), Some(27));
+    assert_eq!(of.families().collect::<Vec<_>>(), &["android", "openjdk"]);
+
+    assert!(availability.lookup("java/util/List", "of", "()V").is_none());
+    assert!(availability.lookup("java/util/Map",  "of", "()Ljava/util/List;").is_none());
+    assert_eq!(availability.members("java/util/List").count(), 2);
+}
+
+#[test] fn csv_round_trip() {
+    let mut list = Class { path: class::IdBuf::new("java/util/List".to_string()), ..Class::default() };
+    list.methods.push(Method::new(method::Flags::PUBLIC | method::Flags::ABSTRACT, "size".to_string(), "()I".to_string()).unwrap());
+    let mut odd = Class { path: class::IdBuf::new("Odd".to_string()), ..Class::default() };
+    odd.fields.push(Field::new(field::Flags::PUBLIC, "a,\"b\"\nc".to_string(), "I".to_string()).unwrap());
+
+    let mut old = Availability::new();
+    old.add_class("android", 25, &list);
+    old.add_class("android", 25, &odd);
+    old.add_class("android", 26, &list);
+
+    let mut csv = Vec::new();
+    old.write_csv(&mut csv).unwrap();
+    let mut imported = Availability::read_csv(&mut &csv[..]).unwrap();
+    let mut csv2 = Vec::new();
+    imported.write_csv(&mut csv2).unwrap();
+    assert_eq!(csv, csv2);
+    assert_eq!(imported.lookup("Odd", "a,\"b\"\nc", "I").unwrap().min("android"), Some(25));
+
+    let mut new = Availability::new();
+    new.add_class("android", 27, &odd);
+    new.scanned.insert("android", 28); // Scanned, but empty
+    imported.merge(&new);
+
+    let list = imported.class("java/util/List").unwrap();
+    assert_eq!(list.min("android"), Some(25));
+    assert_eq!(imported.removed_in("android", list), Some(27));
+    assert_eq!(imported.removed_in("android", imported.class("Odd").unwrap()), Some(28));
+    assert_eq!(imported.scanned().get("android").unwrap().iter().copied().collect::<Vec<_>>(), &[25, 26, 27, 28]);
+
+    let mut csv = Vec::new();
+    imported.write_csv(&mut csv).unwrap();
+    assert!(String::from_utf8(csv.clone()).unwrap().ends_with("\nandroid,28,,,\n"));
+    assert_eq!(Availability::read_csv(&mut &csv[..]).unwrap().scanned(), imported.scanned());
+
+    assert_eq!(Availability::read_csv(&mut &b"family,version\n"[..]).unwrap_err().kind(), io::ErrorKind::InvalidData);
+    assert_eq!(Availability::read_csv(&mut &b"family,version,class,name,descriptor\nandroid,x,Foo,,\n"[..]).unwrap_err().kind(), io::ErrorKind::InvalidData);
+}
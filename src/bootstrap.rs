@@ -0,0 +1,248 @@
+//! [Java SE 8 &sect; 4.7.23](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-4.html#jvms-4.7.23):  The bootstrap
+//! methods behind `invokedynamic` call sites and dynamically-computed (`CONSTANT_Dynamic`) constants.
+//!
+//! javac uses these for lambdas (`LambdaMetafactory.metafactory`), string concatenation
+//! (`StringConcatFactory.makeConcatWithConstants`), records, and pattern matching `switch`es.  See
+//! [Class::bootstrap_methods](../struct.Class.html#method.bootstrap_methods).
+
+use crate::field;
+use crate::constants::{Constant, Constants};
+use crate::io::be::*;
+#[cfg(test)] use crate::{testing, Class};
+
+use std::io::{self, Read};
+
+
+
+/// An entry of a class's `BootstrapMethods` attribute:  a method handle to call, and the static arguments to call it with.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use jreflection::*;
+/// # #[cfg(feature = "jar")] {
+/// # let src = Source::from_jar("app.jar").unwrap();
+/// let class = src.read_class("com/example/Main").unwrap();
+/// for bootstrap in class.bootstrap_methods() {
+///     if bootstrap.handle.class == "java/lang/invoke/LambdaMetafactory" {
+///         println!("lambda implemented by {:?}", bootstrap.arguments.get(1));
+///     }
+/// }
+/// # }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct BootstrapMethod {
+    /// The bootstrap method itself, such as `java/lang/invoke/LambdaMetafactory.metafactory`.
+    pub handle:     MethodHandle,
+
+    /// The static arguments passed to the bootstrap method, after the lookup, name, and type.
+    pub arguments:  Vec<Argument>,
+
+    _non_exhaustive: (),
+}
+
+/// [Java SE 8 &sect; 4.4.8](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-4.html#jvms-4.4.8):  A resolved `CONSTANT_MethodHandle_info`.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct MethodHandle {
+    /// What the handle does with the referenced member.
+    pub kind:       ReferenceKind,
+
+    /// The class of the referenced member, such as `"java/lang/invoke/LambdaMetafactory"`.
+    pub class:      String,
+
+    /// The name of the referenced member, such as `"metafactory"`.
+    pub name:       String,
+
+    /// The descriptor of the referenced member, such as `"I"` or `"(Ljava/lang/String;)V"`.
+    pub descriptor: String,
+
+    _non_exhaustive: (),
+}
+
+/// [Java SE 8 &sect; 5.4.3.5](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-5.html#jvms-5.4.3.5):  The `reference_kind` of a [MethodHandle](struct.MethodHandle.html).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ReferenceKind {
+    /// `REF_getField` (1)
+    GetField,
+    /// `REF_getStatic` (2)
+    GetStatic,
+    /// `REF_putField` (3)
+    PutField,
+    /// `REF_putStatic` (4)
+    PutStatic,
+    /// `REF_invokeVirtual` (5)
+    InvokeVirtual,
+    /// `REF_invokeStatic` (6)
+    InvokeStatic,
+    /// `REF_invokeSpecial` (7)
+    InvokeSpecial,
+    /// `REF_newInvokeSpecial` (8)
+    NewInvokeSpecial,
+    /// `REF_invokeInterface` (9)
+    InvokeInterface,
+    #[doc(hidden)] _NonExhaustive,
+}
+
+/// A static argument of a [BootstrapMethod](struct.BootstrapMethod.html), resolved through the constant pool.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Argument {
+    /// An `int`, `float`, `long`, `double`, or `String` constant.
+    Constant(field::Constant),
+
+    /// A class, such as `"java/lang/String"` or `"[I"`.
+    Class(String),
+
+    /// A method type descriptor, such as `"(Ljava/lang/Object;)Z"`.
+    MethodType(String),
+
+    /// A method handle, such as the implementation method of a lambda.
+    MethodHandle(MethodHandle),
+
+    /// A dynamically-computed constant (`CONSTANT_Dynamic`), itself computed by another bootstrap method.
+    Dynamic {
+        /// The index of the bootstrap method computing the constant.  See [Class::bootstrap_method](../struct.Class.html#method.bootstrap_method).
+        bootstrap_method:   u16,
+        /// The name of the constant.
+        name:               String,
+        /// The field descriptor of the constant's type.
+        descriptor:         String,
+    },
+
+    #[doc(hidden)] _NonExhaustive,
+}
+
+impl ReferenceKind {
+    /// Convert a raw `reference_kind`, or `None` if it's not in `1 ..= 9`.
+    pub fn from_u8(kind: u8) -> Option<Self> {
+        Some(match kind {
+            1 => ReferenceKind::GetField,
+            2 => ReferenceKind::GetStatic,
+            3 => ReferenceKind::PutField,
+            4 => ReferenceKind::PutStatic,
+            5 => ReferenceKind::InvokeVirtual,
+            6 => ReferenceKind::InvokeStatic,
+            7 => ReferenceKind::InvokeSpecial,
+            8 => ReferenceKind::NewInvokeSpecial,
+            9 => ReferenceKind::InvokeInterface,
+            _ => return None,
+        })
+    }
+}
+
+impl MethodHandle {
+    /// Create a handle that does `kind` with the member `class.name` of type `descriptor`.
+    pub fn new(kind: ReferenceKind, class: String, name: String, descriptor: String) -> Self {
+        Self { kind, class, name, descriptor, _non_exhaustive: () }
+    }
+
+    /// Resolve the `CONSTANT_MethodHandle_info` at `index`.
+    pub(crate) fn resolve(constants: &Constants, index: u16) -> io::Result<Self> {
+        let (reference_kind, reference_index) = match constants.get(index)? {
+            Constant::MethodHandle { reference_kind, reference_index } => (*reference_kind, *reference_index),
+            other => return io_data_err!("Expected a CONSTANT_MethodHandle_info at constant #{}, found a {:?} instead", index, other),
+        };
+        let kind = match ReferenceKind::from_u8(reference_kind) { Some(kind) => kind, None => return io_data_err!("Invalid reference_kind {} at constant #{}", reference_kind, index) };
+        let (class_index, name_and_type_index) = match constants.get(reference_index)? {
+            Constant::Fieldref { class_index, name_and_type_index }             => (*class_index, *name_and_type_index),
+            Constant::Methodref { class_index, name_and_type_index }            => (*class_index, *name_and_type_index),
+            Constant::InterfaceMethodref { class_index, name_and_type_index }   => (*class_index, *name_and_type_index),
+            other => return io_data_err!("Expected a CONSTANT_*ref_info at constant #{}, found a {:?} instead", reference_index, other),
+        };
+        let (name, descriptor) = name_and_type(constants, name_and_type_index)?;
+        Ok(Self::new(kind, constants.get_class(class_index)?.to_owned(), name, descriptor))
+    }
+}
+
+impl Argument {
+    /// Resolve the loadable constant at `index`.
+    pub(crate) fn resolve(constants: &Constants, index: u16) -> io::Result<Self> {
+        Ok(match constants.get(index)? {
+            Constant::Integer(value)            => Argument::Constant(field::Constant::Integer(*value)),
+            Constant::Float(value)              => Argument::Constant(field::Constant::Float(*value)),
+            Constant::Long(value)               => Argument::Constant(field::Constant::Long(*value)),
+            Constant::Double(value)             => Argument::Constant(field::Constant::Double(*value)),
+            Constant::String { string_index }   => Argument::Constant(field::Constant::String(constants.get_utf8_possibly_invalid(*string_index)?.map(String::from))),
+            Constant::Class { name_index }      => Argument::Class(constants.get_utf8(*name_index)?.to_owned()),
+            Constant::MethodType { descriptor_index } => Argument::MethodType(constants.get_utf8(*descriptor_index)?.to_owned()),
+            Constant::MethodHandle { .. }       => Argument::MethodHandle(MethodHandle::resolve(constants, index)?),
+            Constant::Dynamic { bootstrap_method_attr_index, name_and_type_index } => {
+                let (name, descriptor) = name_and_type(constants, *name_and_type_index)?;
+                Argument::Dynamic { bootstrap_method: *bootstrap_method_attr_index, name, descriptor }
+            },
+            other => return io_data_err!("Expected a loadable constant at constant #{}, found a {:?} instead", index, other),
+        })
+    }
+}
+
+impl BootstrapMethod {
+    /// Create a bootstrap method calling `handle` with the static `arguments`.
+    pub fn new(handle: MethodHandle, arguments: Vec<Argument>) -> Self { Self { handle, arguments, _non_exhaustive: () } }
+
+    /// Read a `num_bootstrap_methods` prefixed list of bootstrap methods, as found in a BootstrapMethods attribute.
+    pub(crate) fn read_list(read: &mut dyn Read, constants: &Constants) -> io::Result<Vec<Self>> {
+        let mut read = read;
+        let count = read_u2(&mut read)? as usize;
+        let mut methods = Vec::with_capacity(count);
+        for _ in 0..count {
+            let handle = MethodHandle::resolve(constants, read_u2(&mut read)?)?;
+            let arguments_count = read_u2(&mut read)? as usize;
+            let mut arguments = Vec::with_capacity(arguments_count);
+            for _ in 0..arguments_count { arguments.push(Argument::resolve(constants, read_u2(&mut read)?)?); }
+            methods.push(Self::new(handle, arguments));
+        }
+        Ok(methods)
+    }
+}
+
+fn name_and_type(constants: &Constants, index: u16) -> io::Result<(String, String)> {
+    match constants.get(index)? {
+        Constant::NameAndType { name_index, descriptor_index } => Ok((constants.get_utf8(*name_index)?.to_owned(), constants.get_utf8(*descriptor_index)?.to_owned())),
+        other => io_data_err!("Expected a CONSTANT_NameAndType_info at constant #{}, found a {:?} instead", index, other),
+    }
+}
+
+#[test] fn bootstrap_methods() {
+    use crate::testing::be16;
+
+    let mut c = testing::ClassFile::new("com/example/Main", Some("java/lang/Object"));
+    let metafactory = c.methodref("java/lang/invoke/LambdaMetafactory", "metafactory", "(Ljava/lang/invoke/MethodHandles$Lookup;Ljava/lang/String;Ljava/lang/invoke/MethodType;Ljava/lang/invoke/MethodType;Ljava/lang/invoke/MethodHandle;Ljava/lang/invoke/MethodType;)Ljava/lang/invoke/CallSite;");
+    let metafactory = c.constant(15, &[&[6u8][..], &be16(metafactory)[..]].concat());
+    let erased = c.utf8("()V");
+    let erased = c.constant(16, &be16(erased));
+    let lambda = c.methodref("com/example/Main", "lambda$main$0", "()V");
+    let lambda = c.constant(15, &[&[6u8][..], &be16(lambda)[..]].concat());
+    let condy_nat = c.name_and_type("_", "Ljava/lang/Object;");
+    let condy = c.constant(17, &[be16(0), be16(condy_nat)].concat());
+    let answer = c.integer(42);
+    let class = c.class("java/lang/String");
+    let prefix = c.string("hello ");
+
+    let info = [
+        be16(2),
+        be16(metafactory), be16(3), be16(erased), be16(lambda), be16(erased),
+        be16(metafactory), be16(4), be16(answer), be16(class), be16(prefix), be16(condy),
+    ].concat();
+    c.class_attribute("BootstrapMethods", &info);
+    let class = c.read();
+
+    assert_eq!(class.bootstrap_methods().len(), 2);
+    let lambda = class.bootstrap_method(0).unwrap();
+    assert_eq!((lambda.handle.kind, lambda.handle.class.as_str(), lambda.handle.name.as_str()), (ReferenceKind::InvokeStatic, "java/lang/invoke/LambdaMetafactory", "metafactory"));
+    assert_eq!(lambda.arguments, &[
+        Argument::MethodType("()V".to_owned()),
+        Argument::MethodHandle(MethodHandle::new(ReferenceKind::InvokeStatic, "com/example/Main".to_owned(), "lambda$main$0".to_owned(), "()V".to_owned())),
+        Argument::MethodType("()V".to_owned()),
+    ]);
+    assert_eq!(class.bootstrap_method(1).unwrap().arguments, &[
+        Argument::Constant(field::Constant::Integer(42)),
+        Argument::Class("java/lang/String".to_owned()),
+        Argument::Constant(field::Constant::String(Some("hello ".to_owned()))),
+        Argument::Dynamic { bootstrap_method: 0, name: "_".to_owned(), descriptor: "Ljava/lang/Object;".to_owned() },
+    ]);
+    assert!(class.bootstrap_method(2).is_none());
+
+    let mut c = testing::ClassFile::new("com/example/Main", Some("java/lang/Object"));
+    let not_a_handle = c.integer(1);
+    c.class_attribute("BootstrapMethods", &[be16(1), be16(not_a_handle), be16(0)].concat());
+    assert!(Class::read(&mut &c.to_bytes()[..]).is_err());
+}
@@ -0,0 +1,62 @@
+//! Cooperative cancellation of long running operations.
+
+use std::io;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+
+
+/// A shareable flag for aborting long running operations, such as enumerating or scanning every class of a JDK, from
+/// another thread.
+///
+/// Attach a token to a [Source] with [set_cancellation](struct.Source.html#method.set_cancellation) (or to every
+/// source of a [ClassPath] with [ClassPath::set_cancellation](struct.ClassPath.html#method.set_cancellation)), and
+/// once [cancel](#method.cancel) is called, any operation reading from the source fails with [ErrorKind::Interrupted]
+/// before reading its next class.  This includes [Source::for_each_class], and everything built on top of it.
+///
+/// [Source]:                   struct.Source.html
+/// [ClassPath]:                struct.ClassPath.html
+/// [Source::for_each_class]:   struct.Source.html#method.for_each_class
+/// [ErrorKind::Interrupted]:   https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.Interrupted
+///
+/// # Examples
+///
+/// ```no_run
+/// # use jreflection::*;
+/// let cancel = CancellationToken::new();
+/// let mut src = Source::from_jdk_dir(r"C:\Program Files\AdoptOpenJDK\jdk-8.0.232.09-hotspot").unwrap();
+/// src.set_cancellation(cancel.clone());
+///
+/// std::thread::spawn(move ||{
+///     std::thread::sleep(std::time::Duration::from_millis(100));
+///     cancel.cancel(); // e.g. the user clicked "Stop"
+/// });
+///
+/// match scan::ScanResult::scan(&[src]) {
+///     Err(ref err) if err.kind() == std::io::ErrorKind::Interrupted => println!("cancelled"),
+///     other => { other.unwrap(); },
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    /// Create a new, uncancelled token.
+    pub fn new() -> Self { Self::default() }
+
+    /// Cancel every operation using this token (or any of its clones.)  Can't be undone.
+    pub fn cancel(&self) { self.0.store(true, Ordering::SeqCst); }
+
+    /// Has [cancel](#method.cancel) been called on this token or any of its clones?
+    pub fn is_cancelled(&self) -> bool { self.0.load(Ordering::SeqCst) }
+
+    /// Fail with [ErrorKind::Interrupted](https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.Interrupted) if
+    /// cancelled, for checking the token from your own loops.
+    pub fn check(&self) -> io::Result<()> {
+        if self.is_cancelled() {
+            Err(io::Error::new(io::ErrorKind::Interrupted, "Operation cancelled"))
+        } else {
+            Ok(())
+        }
+    }
+}
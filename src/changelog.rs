@@ -0,0 +1,335 @@
+//! Human readable summaries of API changes between two releases, for release notes.
+
+use crate::{field, filter, method, scan, Source};
+use crate::scan::{ScanResult, ScannedClass};
+#[cfg(all(test, feature = "jar"))] use crate::testing;
+
+use std::collections::*;
+use std::fmt::{self, Display, Formatter};
+use std::io;
+
+
+
+/// The public API changes between two versions of a library, grouped by package.  Display it for Markdown.
+///
+/// Only `public` classes and their `public` and `protected` members are considered.  Members of added or removed
+/// classes aren't listed separately.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use jreflection::*;
+/// # #[cfg(feature = "jar")] {
+/// let old = Source::from_jar("library-1.0.jar").unwrap();
+/// let new = Source::from_jar("library-1.1.jar").unwrap();
+/// let summary = changelog::summarize(&old, &new).unwrap();
+/// println!("{}", summary); // e.g. "**3 classes added, 1 class removed, 12 methods deprecated**" followed by per-package details
+/// # }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Summary {
+    /// The changes per package (JNI path, e.g. `"java/util"`.)  Packages without changes are omitted.
+    pub packages: BTreeMap<String, PackageChanges>,
+}
+
+/// The changes to the API of a single package.  See [Summary](struct.Summary.html).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PackageChanges {
+    /// Classes, by JNI path.
+    pub classes:    Changes,
+
+    /// Fields, as `"java/util/Foo.name:descriptor"`.
+    pub fields:     Changes,
+
+    /// Methods, as `"java/util/Foo.name(descriptor)"`.
+    pub methods:    Changes,
+}
+
+/// What was added, removed, or newly deprecated.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Changes {
+    /// In the new version's API, but not the old one's.  Already deprecated additions are only listed here.
+    pub added:      BTreeSet<String>,
+
+    /// In the old version's API, but not the new one's - including anything that merely stopped being API (e.g. by
+    /// being made private.)
+    pub removed:    BTreeSet<String>,
+
+    /// In both versions' APIs, but only deprecated in the new one.  Anything already deprecated isn't listed again.
+    pub deprecated: BTreeSet<String>,
+}
+
+impl Changes {
+    /// Is nothing added, removed, or deprecated?
+    pub fn is_empty(&self) -> bool { self.added.is_empty() && self.removed.is_empty() && self.deprecated.is_empty() }
+}
+
+impl PackageChanges {
+    /// Is nothing changed?
+    pub fn is_empty(&self) -> bool { self.classes.is_empty() && self.fields.is_empty() && self.methods.is_empty() }
+}
+
+/// Summarize the public API changes from `old` to `new`.
+pub fn summarize(old: &Source, new: &Source) -> io::Result<Summary> {
+    let old = ScanResult::scan(std::slice::from_ref(old))?;
+    let new = ScanResult::scan(std::slice::from_ref(new))?;
+    Ok(Summary::from_scans(&old, &new))
+}
+
+/// Summarize the API changes from `old` to `new`, where `filter` decides what counts as API.
+pub fn summarize_with(old: &Source, new: &Source, filter: &filter::Filter) -> io::Result<Summary> {
+    Ok(Summary::from_apis(&api_of_source(old, filter)?, &api_of_source(new, filter)?))
+}
+
+/// The API of a single class:  whether it's deprecated, and its fields and methods (keyed as in [PackageChanges])
+/// with whether they're deprecated.
+///
+/// [PackageChanges]:   struct.PackageChanges.html
+#[derive(Default)]
+struct ApiClass {
+    deprecated: bool,
+    fields:     BTreeMap<String, bool>,
+    methods:    BTreeMap<String, bool>,
+}
+
+fn api_of_scan(scan: &ScanResult) -> BTreeMap<String, ApiClass> {
+    let api_field   = |f: &scan::ScannedField| f.flags().intersects(field::Flags::PUBLIC | field::Flags::PROTECTED);
+    let api_method  = |m: &scan::ScannedMethod| m.flags().intersects(method::Flags::PUBLIC | method::Flags::PROTECTED) && m.name() != "<clinit>";
+    scan.classes().filter(|c| c.is_public()).map(|class: ScannedClass| {
+        let path = class.path().as_str().to_owned();
+        let api = ApiClass {
+            deprecated: class.deprecated(),
+            fields:     class.fields() .filter(api_field ).map(|f| (format!("{}.{}:{}", path, f.name(), f.descriptor_str()), f.deprecated())).collect(),
+            methods:    class.methods().filter(api_method).map(|m| (format!("{}.{}{}",  path, m.name(), m.descriptor_str()), m.deprecated())).collect(),
+        };
+        (path, api)
+    }).collect()
+}
+
+fn api_of_source(source: &Source, filter: &filter::Filter) -> io::Result<BTreeMap<String, ApiClass>> {
+    let mut classes = BTreeMap::new();
+    source.for_each_class(|path|{
+        let class = source.read_class(path.as_str())?;
+        if !filter.includes_class(&class) { return Ok(()); }
+        let api = ApiClass {
+            deprecated: class.deprecated,
+            fields:     class.fields .iter().filter(|f| filter.includes_field (&class, f)).map(|f| (format!("{}.{}:{}", path, f.name, f.descriptor_str()), f.deprecated)).collect(),
+            methods:    class.methods.iter().filter(|m| filter.includes_method(&class, m)).map(|m| (format!("{}.{}{}",  path, m.name, m.descriptor_str()), m.deprecated)).collect(),
+        };
+        classes.insert(path, api);
+        Ok(())
+    })?;
+    Ok(classes)
+}
+
+impl Summary {
+    /// Summarize the public API changes from `old` to `new`, for when you've already scanned both.
+    pub fn from_scans(old: &ScanResult, new: &ScanResult) -> Self {
+        Self::from_apis(&api_of_scan(old), &api_of_scan(new))
+    }
+
+    fn from_apis(old: &BTreeMap<String, ApiClass>, new: &BTreeMap<String, ApiClass>) -> Self {
+        let mut summary = Self::default();
+        for (path, class) in old.iter() {
+            match new.get(path) {
+                Some(new_class) => {
+                    let package = summary.package(path);
+                    diff(&class.fields,  &new_class.fields,  &mut package.fields);
+                    diff(&class.methods, &new_class.methods, &mut package.methods);
+                },
+                None            => { summary.package(path).classes.removed.insert(path.clone()); },
+            }
+        }
+        for (path, class) in new.iter() {
+            match old.get(path) {
+                None                                                => { summary.package(path).classes.added.insert(path.clone()); },
+                Some(old) if class.deprecated && !old.deprecated    => { summary.package(path).classes.deprecated.insert(path.clone()); },
+                Some(_)                                             => {},
+            }
+        }
+        summary.packages = summary.packages.into_iter().filter(|(_, changes)| !changes.is_empty()).collect();
+        summary
+    }
+
+    fn package(&mut self, class: &str) -> &mut PackageChanges {
+        self.packages.entry(package_of(class).to_owned()).or_default()
+    }
+
+    /// Totals across every package, as `(classes, fields, methods)`.
+    pub fn totals(&self) -> (Counts, Counts, Counts) {
+        let mut totals = (Counts::default(), Counts::default(), Counts::default());
+        for changes in self.packages.values() {
+            totals.0.add(&changes.classes);
+            totals.1.add(&changes.fields);
+            totals.2.add(&changes.methods);
+        }
+        totals
+    }
+}
+
+/// How many things were added, removed, and newly deprecated.  See [Summary::totals](struct.Summary.html#method.totals).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Counts {
+    /// How many things were [added](struct.Changes.html#structfield.added), across every package.
+    pub added:      usize,
+
+    /// How many things were [removed](struct.Changes.html#structfield.removed), across every package.
+    pub removed:    usize,
+
+    /// How many things were [deprecated](struct.Changes.html#structfield.deprecated), across every package.
+    pub deprecated: usize,
+}
+
+impl Counts {
+    fn add(&mut self, changes: &Changes) {
+        self.added      += changes.added.len();
+        self.removed    += changes.removed.len();
+        self.deprecated += changes.deprecated.len();
+    }
+}
+
+fn diff(old: &BTreeMap<String, bool>, new: &BTreeMap<String, bool>, changes: &mut Changes) {
+    for member in old.keys().filter(|m| !new.contains_key(*m)) { changes.removed.insert(member.clone()); }
+    for (member, deprecated) in new.iter() {
+        match old.get(member) {
+            None                        => { changes.added.insert(member.clone()); },
+            Some(false) if *deprecated  => { changes.deprecated.insert(member.clone()); },
+            Some(_)                     => {},
+        }
+    }
+}
+
+fn package_of(class: &str) -> &str { class.rfind('/').map(|slash| &class[..slash]).unwrap_or("") }
+
+/// `"java/util/Map$Entry"` -> `"Map.Entry"`
+fn simple_name(class: &str) -> String { class[class.rfind('/').map(|s| s+1).unwrap_or(0)..].replace('$', ".") }
+
+fn java_type(ty: &method::Type) -> String {
+    let (levels, inner) = match ty {
+        method::Type::Single(inner)             => (0, inner),
+        method::Type::Array { levels, inner }   => (*levels, inner),
+    };
+    let mut name = match inner {
+        field::BasicType::Boolean   => "boolean".to_owned(),
+        field::BasicType::Byte      => "byte".to_owned(),
+        field::BasicType::Char      => "char".to_owned(),
+        field::BasicType::Short     => "short".to_owned(),
+        field::BasicType::Int       => "int".to_owned(),
+        field::BasicType::Long      => "long".to_owned(),
+        field::BasicType::Float     => "float".to_owned(),
+        field::BasicType::Double    => "double".to_owned(),
+        field::BasicType::Void      => "void".to_owned(),
+        field::BasicType::Class(id) => simple_name(id.as_str()),
+    };
+    for _ in 0..levels { name.push_str("[]"); }
+    name
+}
+
+/// `"java/util/List.of(Ljava/lang/Object;)Ljava/util/List;"` -> `"List.of(Object)"`
+fn display_member(member: &str) -> String {
+    let (class, rest) = match member.find('.') { // JNI paths, names, and descriptors never contain '.'s
+        Some(dot)   => (&member[..dot], &member[dot+1..]),
+        None        => return member.to_owned(),
+    };
+    let class = simple_name(class);
+    if let Some(paren) = rest.find('(') {
+        let name = &rest[..paren];
+        let name = if name == "<init>" { class.rsplit('.').next().unwrap_or("") } else { name };
+        let args = match method::Descriptor::new(&rest[paren..]) {
+            Ok(descriptor)  => descriptor.arguments().map(|a| java_type(&a)).collect::<Vec<_>>().join(", "),
+            Err(_)          => rest[paren..].to_owned(),
+        };
+        format!("{}.{}({})", class, name, args)
+    } else {
+        format!("{}.{}", class, rest.split(':').next().unwrap_or(rest))
+    }
+}
+
+fn plural(n: usize, singular: &str, plural: &str) -> String { format!("{} {}", n, if n == 1 { singular } else { plural }) }
+
+fn counts(counts: &Counts, singular: &str, plurals: &str, out: &mut Vec<String>) {
+    if counts.added      != 0 { out.push(format!("{} added",      plural(counts.added,      singular, plurals))); }
+    if counts.removed    != 0 { out.push(format!("{} removed",    plural(counts.removed,    singular, plurals))); }
+    if counts.deprecated != 0 { out.push(format!("{} deprecated", plural(counts.deprecated, singular, plurals))); }
+}
+
+fn list(fmt: &mut Formatter, what: &str, items: &BTreeSet<String>, display: impl Fn(&str) -> String) -> fmt::Result {
+    if items.is_empty() { return Ok(()); }
+    let items = items.iter().map(|i| format!("`{}`", display(i))).collect::<Vec<_>>();
+    writeln!(fmt, "- {}: {}", what, items.join(", "))
+}
+
+impl Display for Summary {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        if self.packages.is_empty() { return writeln!(fmt, "No public API changes."); }
+
+        let (classes, fields, methods) = self.totals();
+        let mut totals = Vec::new();
+        counts(&classes, "class",  "classes",  &mut totals);
+        counts(&fields,  "field",  "fields",   &mut totals);
+        counts(&methods, "method", "methods",  &mut totals);
+        writeln!(fmt, "**{}**", totals.join(", "))?;
+
+        for (package, changes) in self.packages.iter() {
+            writeln!(fmt)?;
+            if package.is_empty() { writeln!(fmt, "### (default package)")?; } else { writeln!(fmt, "### `{}`", package.replace('/', "."))?; }
+            list(fmt, &format!("{} added",      plural(changes.classes.added.len(),      "class",  "classes")), &changes.classes.added,      simple_name)?;
+            list(fmt, &format!("{} removed",    plural(changes.classes.removed.len(),    "class",  "classes")), &changes.classes.removed,    simple_name)?;
+            list(fmt, &format!("{} deprecated", plural(changes.classes.deprecated.len(), "class",  "classes")), &changes.classes.deprecated, simple_name)?;
+            for &(members, singular, plurals) in [(&changes.fields, "field", "fields"), (&changes.methods, "method", "methods")].iter() {
+                list(fmt, &format!("{} added",      plural(members.added.len(),      singular, plurals)), &members.added,      display_member)?;
+                list(fmt, &format!("{} removed",    plural(members.removed.len(),    singular, plurals)), &members.removed,    display_member)?;
+                list(fmt, &format!("{} deprecated", plural(members.deprecated.len(), singular, plurals)), &members.deprecated, display_member)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "jar")]
+#[test] fn summarize_changes() {
+    let mut list_v1 = testing::ClassFile::new("com/example/util/List", Some("java/lang/Object"));
+    list_v1.method(0x0001, "<init>", "()V", &[]);
+    list_v1.method(0x0001, "add", "(Ljava/lang/Object;)Z", &[]);
+    list_v1.method(0x0001, "old", "([I)V", &[]);
+    list_v1.method(0x0002, "secret", "()V", &[]);
+    list_v1.field(0x0001, "size", "I", &[]);
+    let mut list_v2 = testing::ClassFile::new("com/example/util/List", Some("java/lang/Object"));
+    let deprecated = list_v2.attribute("Deprecated", &[]);
+    list_v2.method(0x0001, "<init>", "()V", &[]);
+    list_v2.method(0x0001, "add", "(Ljava/lang/Object;)Z", &[deprecated]);
+    list_v2.method(0x0001, "of", "([Ljava/lang/Object;)Lcom/example/util/List;", &[]);
+    list_v2.method(0x0002, "secret2", "()V", &[]);
+    list_v2.field(0x0001, "size", "I", &[]);
+    let entry   = testing::ClassFile::new("com/example/util/Map$Entry", Some("java/lang/Object")).to_bytes();
+    let gone    = testing::ClassFile::new("com/example/Gone", Some("java/lang/Object")).to_bytes();
+
+    let old = Source::from_jar(testing::write_jar("changelog-old", &[("com/example/util/List.class", list_v1.to_bytes()), ("com/example/Gone.class", gone)])).unwrap();
+    let new = Source::from_jar(testing::write_jar("changelog-new", &[("com/example/util/List.class", list_v2.to_bytes()), ("com/example/util/Map$Entry.class", entry)])).unwrap();
+    let summary = summarize(&old, &new).unwrap();
+
+    assert_eq!(summary.packages.len(), 2);
+    let util = &summary.packages["com/example/util"];
+    assert_eq!(util.classes.added.iter().collect::<Vec<_>>(), &["com/example/util/Map$Entry"]);
+    assert_eq!(util.methods.added.iter().collect::<Vec<_>>(), &["com/example/util/List.of([Ljava/lang/Object;)Lcom/example/util/List;"]);
+    assert_eq!(util.methods.removed.iter().collect::<Vec<_>>(), &["com/example/util/List.old([I)V"]);
+    assert_eq!(util.methods.deprecated.iter().collect::<Vec<_>>(), &["com/example/util/List.add(Ljava/lang/Object;)Z"]);
+    assert!(util.fields.is_empty());
+    assert_eq!(summary.packages["com/example"].classes.removed.len(), 1);
+
+    let markdown = summary.to_string();
+    assert!(markdown.starts_with("**1 class added, 1 class removed, 1 method added, 1 method removed, 1 method deprecated**\n"), "{}", markdown);
+    assert!(markdown.contains("### `com.example.util`\n- 1 class added: `Map.Entry`\n"), "{}", markdown);
+    assert!(markdown.contains("- 1 method added: `List.of(Object[])`\n"), "{}", markdown);
+    assert!(markdown.contains("- 1 method deprecated: `List.add(Object)`\n"), "{}", markdown);
+    assert_eq!(summarize(&old, &old).unwrap().to_string(), "No public API changes.\n");
+    assert_eq!(display_member("java/lang/Object.<init>()V"), "Object.Object()");
+    assert_eq!(display_member("java/lang/Integer.MAX_VALUE:I"), "Integer.MAX_VALUE");
+
+    assert_eq!(summarize_with(&old, &new, &filter::Filter::new()).unwrap(), summary);
+    #[cfg(feature = "annotations")] {
+        let mut filter = filter::Filter::new();
+        filter.include_only_annotated("com.example.PublicApi");
+        assert_eq!(summarize_with(&old, &new, &filter).unwrap().to_string(), "No public API changes.\n");
+    }
+}
@@ -3,12 +3,21 @@
 // https://en.wikipedia.org/wiki/Java_class_file
 // https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html
 
-use crate::*;
+use crate::{attributes, bootstrap, constants, field, fingerprint, method, module, signature, smap, validate, version, CustomAttribute, Field, Method, ParseOptions, Warning};
+use crate::attributes::Attribute;
+use crate::constants::{Constant, Constants};
 use crate::io::be::*;
+#[cfg(feature = "annotations")] use crate::annotation;
+#[cfg(feature = "annotations")] use crate::stability;
+#[cfg(feature = "code")] use crate::code;
+#[cfg(test)] use crate::{testing, DuplicateAttributes, WarningKind};
 
 use bitflags::bitflags;
 
+use std::collections::*;
+use std::fmt::{self, Debug, Display, Formatter};
 use std::io::{self, Read};
+use std::sync::Arc;
 
 
 
@@ -34,6 +43,8 @@ bitflags! {
         const ANNOTATION    = 0x2000;
         /// Declared as an enum type.
         const ENUM          = 0x4000;
+        /// Is a module (`module-info.class`), not a class or interface.  Java 9+.
+        const MODULE        = 0x8000;
     }
 }
 
@@ -43,6 +54,181 @@ impl Flags {
     }
 }
 
+bitflags! {
+    #[derive(Default)]
+    /// [Java SE 7 &sect; 4.7.6](https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.7.6):  InnerClasses::classes[]::inner_class_access_flags values.
+    ///
+    /// These are the flags of the nested class as declared in source, unlike the [Flags] of its own class file, which
+    /// can't be `private`, `protected`, or `static`.
+    ///
+    /// [Flags]:    struct.Flags.html
+    pub struct InnerClassFlags : u16 {
+        /// Marked or implicitly `public` in source.
+        const PUBLIC        = 0x0001;
+        /// Marked `private` in source.
+        const PRIVATE       = 0x0002;
+        /// Marked `protected` in source.
+        const PROTECTED     = 0x0004;
+        /// Marked or implicitly `static` in source.
+        const STATIC        = 0x0008;
+        /// Marked `final` in source.
+        const FINAL         = 0x0010;
+        /// Was an `interface` in source.
+        const INTERFACE     = 0x0200;
+        /// Marked or implicitly `abstract` in source.
+        const ABSTRACT      = 0x0400;
+        /// Declared synthetic; not present in the source code.
+        const SYNTHETIC     = 0x1000;
+        /// Declared as an annotation type.
+        const ANNOTATION    = 0x2000;
+        /// Declared as an enum type.
+        const ENUM          = 0x4000;
+    }
+}
+
+/// [Java SE 7 &sect; 4.7.6](https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.7.6):  An entry of
+/// a class's InnerClasses attribute, describing a nested class and how it relates to its outer class.
+///
+/// Classes list every nested class they declare *or* reference, so not every entry is nested within the class listing it.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct InnerClass {
+    /// The nested class, such as `java/util/Map$Entry`.
+    pub inner:  IdBuf,
+
+    /// The class `inner` is a member of, such as `java/util/Map`.  `None` for local and anonymous classes.
+    pub outer:  Option<IdBuf>,
+
+    /// The simple name of `inner` in source, such as `Entry`.  `None` for anonymous classes.
+    pub name:   Option<String>,
+
+    /// The flags of `inner`, as declared in source.
+    pub flags:  InnerClassFlags,
+
+    _non_exhaustive: (),
+}
+
+impl InnerClass {
+    /// Create an entry for the nested class `inner`.
+    pub fn new(inner: IdBuf, outer: Option<IdBuf>, name: Option<String>, flags: InnerClassFlags) -> Self {
+        Self { inner, outer, name, flags, _non_exhaustive: () }
+    }
+
+    pub(crate) fn read_list(read: &mut impl Read, constants: &Constants) -> io::Result<Vec<Self>> {
+        let count = read_u2(read)? as usize;
+        let mut classes = Vec::with_capacity(count);
+        for _ in 0..count {
+            let inner   = IdBuf::new(constants.get_class(read_u2(read)?)?.to_owned());
+            let outer   = constants.get_optional_class(read_u2(read)?)?.map(|o| IdBuf::new(o.to_owned()));
+            let name    = match read_u2(read)? { 0 => None, index => Some(constants.get_utf8(index)?.to_owned()) };
+            let flags   = InnerClassFlags::from_bits_truncate(read_u2(read)?);
+            classes.push(InnerClass::new(inner, outer, name, flags));
+        }
+        Ok(classes)
+    }
+
+    /// Is `inner` a member of `outer` (as opposed to a local or anonymous class)?
+    pub fn is_member(&self)     -> bool { self.outer.is_some() }
+
+    /// Is `inner` a local class, declared within a method or initializer?
+    pub fn is_local(&self)      -> bool { self.outer.is_none() && self.name.is_some() }
+
+    /// Is `inner` an anonymous class?
+    pub fn is_anonymous(&self)  -> bool { self.name.is_none() }
+}
+
+/// [Java SE 7 &sect; 4.7.7](https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.7.7):  The
+/// innermost class - and method, if any - enclosing a local or anonymous class.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct EnclosingMethod {
+    /// The innermost class enclosing the local or anonymous class, such as `com/example/Outer`.
+    pub class:      IdBuf,
+
+    /// The name of the enclosing method, such as `"run"` or `"<init>"`.  `None` if the class is declared in an
+    /// instance, static, or field initializer instead.
+    pub name:       Option<String>,
+
+    /// The descriptor of the enclosing method, such as `"()V"`.  `None` exactly when [name](#structfield.name) is.
+    pub descriptor: Option<String>,
+
+    _non_exhaustive: (),
+}
+
+impl EnclosingMethod {
+    /// Create an enclosing method of `class`, with the `name` and `descriptor` of the method, if any.
+    pub fn new(class: IdBuf, name: Option<String>, descriptor: Option<String>) -> Self {
+        Self { class, name, descriptor, _non_exhaustive: () }
+    }
+
+    pub(crate) fn read(read: &mut impl Read, constants: &Constants) -> io::Result<Self> {
+        let class = IdBuf::new(constants.get_class(read_u2(read)?)?.to_owned());
+        let (name, descriptor) = match read_u2(read)? {
+            0 => (None, None),
+            index => match constants.get(index)? {
+                Constant::NameAndType { name_index, descriptor_index } => (Some(constants.get_utf8(*name_index)?.to_owned()), Some(constants.get_utf8(*descriptor_index)?.to_owned())),
+                other => return io_data_err!("Expected Constant::NameAndType for EnclosingMethod::method_index, got {:?}", other),
+            },
+        };
+        Ok(EnclosingMethod::new(class, name, descriptor))
+    }
+}
+
+/// [Java SE 16 &sect; 4.7.30](https://docs.oracle.com/javase/specs/jvms/se16/html/jvms-4.html#jvms-4.7.30):  A
+/// component of a record class, such as `x` of `record Point(int x, int y)`.  See [Class::record_components](../struct.Class.html#method.record_components).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RecordComponent {
+    /// The name of the component, and of its accessor method and private field, such as `"x"`.
+    pub name:                       String,
+
+    /// The [type erased] field descriptor of the component, such as `"I"` or `"Ljava/util/List;"`.
+    ///
+    /// [type erased]: https://docs.oracle.com/javase/tutorial/java/generics/erasure.html
+    pub descriptor:                 String,
+
+    /// The generic signature of the component, such as `"Ljava/util/List<Ljava/lang/String;>;"`, if it has one.
+    pub signature:                  Option<String>,
+
+    /// Runtime-visible annotations of the component.
+    #[cfg(feature = "annotations")] pub visible_annotations:        Vec<annotation::Annotation>,
+
+    /// Runtime-invisible annotations of the component.
+    #[cfg(feature = "annotations")] pub invisible_annotations:      Vec<annotation::Annotation>,
+
+    /// Runtime-visible type annotations of the component.
+    #[cfg(feature = "annotations")] pub visible_type_annotations:   Vec<annotation::TypeAnnotation>,
+
+    /// Runtime-invisible type annotations of the component.
+    #[cfg(feature = "annotations")] pub invisible_type_annotations: Vec<annotation::TypeAnnotation>,
+
+    _non_exhaustive:            (),
+}
+
+impl RecordComponent {
+    pub(crate) fn read_list(read: &mut impl Read, constants: &Arc<Constants>, options: &ParseOptions, warnings: &mut Vec<Warning>) -> io::Result<Vec<Self>> {
+        let count = read_u2(read)? as usize;
+        let mut components = Vec::with_capacity(count);
+        for _ in 0..count {
+            let name        = constants.get_utf8(read_u2(read)?)?.to_owned();
+            let descriptor  = constants.get_utf8(read_u2(read)?)?.to_owned();
+            field::Descriptor::from_str(descriptor.as_str())?;
+            let mut component = RecordComponent { name, descriptor, .. Default::default() };
+            let mut seen = Vec::new();
+            for _ in 0..read_u2(read)? {
+                let attribute = match Attribute::read_named(read, constants, options, &mut seen, warnings)?.1 { Some(a) => a, None => continue };
+                match attribute {
+                    Attribute::Signature(s)                         => { component.signature = Some(s); },
+                    #[cfg(feature = "annotations")] Attribute::RuntimeVisibleAnnotations(a)         => { component.visible_annotations.extend(a); },
+                    #[cfg(feature = "annotations")] Attribute::RuntimeInvisibleAnnotations(a)       => { component.invisible_annotations.extend(a); },
+                    #[cfg(feature = "annotations")] Attribute::RuntimeVisibleTypeAnnotations(a)     => { component.visible_type_annotations.extend(a); },
+                    #[cfg(feature = "annotations")] Attribute::RuntimeInvisibleTypeAnnotations(a)   => { component.invisible_type_annotations.extend(a); },
+                    _ => {},
+                }
+            }
+            components.push(component);
+        }
+        Ok(components)
+    }
+}
+
 
 
 /// [Java SE 7 &sect; 4.1](https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.1):  The first few fields of a given ClassFile.
@@ -67,6 +253,18 @@ impl Header {
 
 
 
+/// The names and flags of a class, as read by [Class::peek](struct.Class.html#method.peek).
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ClassHeaderInfo {
+    pub minor_version:  u16,
+    pub major_version:  u16,
+    pub flags:          Flags,
+    pub path:           IdBuf,
+    pub super_path:     Option<IdBuf>,
+
+    _non_exhaustive: (),
+}
+
 #[derive(Clone, Debug, Default)]
 pub struct Class {
     pub flags:      Flags,
@@ -76,14 +274,101 @@ pub struct Class {
     pub fields:     Vec<Field>,
     pub methods:    Vec<Method>,
     pub deprecated: bool,
+    pub(crate) signature: Option<String>,
+    pub(crate) source_file: Option<String>,
+    pub(crate) source_debug_extension: Option<String>,
+    pub(crate) synthetic_attribute: bool,
+    pub(crate) attribute_names: Vec<String>,
+    pub(crate) custom_attributes: Vec<CustomAttribute>,
+    pub(crate) unknown_attributes: Vec<attributes::RawAttribute>,
+    #[cfg(feature = "annotations")] pub(crate) visible_annotations: Vec<annotation::Annotation>,
+    #[cfg(feature = "annotations")] pub(crate) invisible_annotations: Vec<annotation::Annotation>,
+    #[cfg(feature = "annotations")] pub(crate) visible_type_annotations: Vec<annotation::TypeAnnotation>,
+    #[cfg(feature = "annotations")] pub(crate) invisible_type_annotations: Vec<annotation::TypeAnnotation>,
+    pub(crate) module: Option<module::Module>,
+    pub(crate) inner_classes: Vec<InnerClass>,
+    pub(crate) enclosing_method: Option<EnclosingMethod>,
+    pub(crate) nest_host: Option<IdBuf>,
+    pub(crate) nest_members: Vec<IdBuf>,
+    pub(crate) record_components: Option<Vec<RecordComponent>>,
+    pub(crate) permitted_subclasses: Option<Vec<IdBuf>>,
+    pub(crate) bootstrap_methods: Vec<bootstrap::BootstrapMethod>,
+    pub(crate) warnings: Vec<Warning>,
+    pub(crate) minor_version: u16,
+    pub(crate) major_version: u16,
+    pub(crate) constants: Arc<Constants>,
 }
 
 #[allow(dead_code)]
 impl Class {
     /// [Java SE 7 &sect; 4](https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html):  Read a class File.
     pub fn read(read: &mut impl Read) -> io::Result<Self> {
-        let _header     = Header::read(read)?;
-        let constants   = Constants::read(read)?;
+        Self::read_with(read, &ParseOptions::default())
+    }
+
+    /// Read only the version, flags, and names of a class from its raw .class file bytes.
+    ///
+    /// The constant pool is skipped over without decoding anything but the two names needed, and nothing after the
+    /// super class is read at all, making this much cheaper than [read](#method.read) for tools that just need to know
+    /// what a class is called and whether it's public, an interface, etc.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # #[cfg(feature = "jar")] {
+    /// # let src = jreflection::Source::from_jar("app.jar").unwrap();
+    /// let bytes = src.read_class_bytes("com/example/Main").unwrap();
+    /// let header = jreflection::Class::peek(&bytes[..]).unwrap();
+    /// assert_eq!(header.path.as_str(), "com/example/Main");
+    /// # }
+    /// ```
+    pub fn peek(bytes: &[u8]) -> io::Result<ClassHeaderInfo> {
+        let header = Header::read(&mut &bytes[..])?;
+
+        // Find each constant, without decoding (or even copying) any of them
+        let mut raw = constants::RawConstants::new(bytes)?;
+        let mut constants = vec![(0u8, &[][..]); raw.slots() as usize];
+        for constant in &mut raw {
+            let (index, tag, info) = constant?;
+            constants[index as usize] = (tag, info);
+        }
+
+        let mut read = &bytes[raw.offset()..];
+        let flags       = Flags::read(&mut read)?;
+        let this_class  = read_u2(&mut read)?;
+        let super_class = read_u2(&mut read)?;
+
+        let class_name = |index: u16| -> io::Result<IdBuf> {
+            let info = |index: u16, tag: u8| -> io::Result<&[u8]> {
+                match constants.get(index as usize) {
+                    Some(&(t, info)) if t == tag    => Ok(info),
+                    Some(&(t, _))                   => io_data_err!("Expected constant #{} to have tag {}, got {}", index, tag, t),
+                    None                            => io_data_err!("No such constant #{}", index),
+                }
+            };
+            let name_index = info(index, 7)?;
+            let name_index = u16::from_be_bytes([name_index[0], name_index[1]]);
+            let name = &info(name_index, 1)?[2..];
+            match constants::read_modified_utf8_as_utf8(name) {
+                Some(name)  => Ok(IdBuf::new(name)),
+                None        => io_data_err!("Class name at constant {} isn't valid modified UTF8", name_index),
+            }
+        };
+
+        Ok(ClassHeaderInfo {
+            minor_version:  header.minor_version,
+            major_version:  header.major_version.0,
+            flags,
+            path:           class_name(this_class)?,
+            super_path:     if super_class == 0 { None } else { Some(class_name(super_class)?) },
+            _non_exhaustive: (),
+        })
+    }
+
+    /// [Java SE 7 &sect; 4](https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html):  Read a class File, with custom [ParseOptions](struct.ParseOptions.html).
+    pub fn read_with(read: &mut impl Read, options: &ParseOptions) -> io::Result<Self> {
+        let header      = Header::read(read)?;
+        let constants   = Arc::new(Constants::read(read)?);
         let flags       = Flags::read(read)?;
         let path        = IdBuf::new(constants.get_class(read_u2(read)?)?.to_owned());
         let super_path  = constants.get_optional_class(read_u2(read)?)?.map(|s| IdBuf::new(s.to_owned()));
@@ -94,17 +379,67 @@ impl Class {
             interfaces.push(IdBuf::new(constants.get_class(read_u2(read)?)?.to_owned()));
         }
 
-        let fields  = Field::read_list(read, &constants)?;
-        let methods = Method::read_list(read, &constants)?;
+        let mut warnings = Vec::new();
+        let fields  = Field::read_list(read, &constants, options, &mut warnings)?;
+        let methods = Method::read_list(read, &constants, options, &mut warnings)?;
 
         let attributes_count = read_u2(read)?;
         let mut deprecated = false;
+        let mut signature = None;
+        let mut source_file = None;
+        let mut source_debug_extension = None;
+        let mut synthetic_attribute = false;
+        let mut custom_attributes = Vec::new();
+        let mut attribute_names = Vec::new();
+        let mut unknown_attributes = Vec::new();
+        #[cfg(feature = "annotations")] let mut visible_annotations = Vec::new();
+        #[cfg(feature = "annotations")] let mut invisible_annotations = Vec::new();
+        #[cfg(feature = "annotations")] let mut visible_type_annotations = Vec::new();
+        #[cfg(feature = "annotations")] let mut invisible_type_annotations = Vec::new();
+        let mut module = None;
+        let mut module_packages = None;
+        let mut module_main_class = None;
+        let mut inner_classes = Vec::new();
+        let mut enclosing_method = None;
+        let mut nest_host = None;
+        let mut nest_members = Vec::new();
+        let mut record_components = None;
+        let mut permitted_subclasses = None;
+        let mut bootstrap_methods = Vec::new();
+        let mut seen = Vec::new();
         for _ in 0..attributes_count {
-            match Attribute::read(read, &constants)? {
+            let (name, attribute) = Attribute::read_named(read, &constants, options, &mut seen, &mut warnings)?;
+            attribute_names.push(name.to_owned());
+            let attribute = match attribute { Some(a) => a, None => continue }; // An ignored duplicate
+            match attribute {
                 Attribute::Deprecated { .. } => { deprecated = true; },
+                Attribute::Signature(s) => { signature = Some(s); },
+                Attribute::SourceFile(s) => { source_file = Some(s); },
+                Attribute::SourceDebugExtension(s) => { source_debug_extension = Some(s); },
+                Attribute::Synthetic { .. } => { synthetic_attribute = true; },
+                Attribute::Custom(custom) => { custom_attributes.push(custom); },
+                Attribute::Unknown(info) => { unknown_attributes.push(attributes::RawAttribute { name: name.to_owned(), info }); },
+                #[cfg(feature = "annotations")] Attribute::RuntimeVisibleAnnotations(a)   => { visible_annotations.extend(a); },
+                #[cfg(feature = "annotations")] Attribute::RuntimeInvisibleAnnotations(a) => { invisible_annotations.extend(a); },
+                #[cfg(feature = "annotations")] Attribute::RuntimeVisibleTypeAnnotations(a)   => { visible_type_annotations.extend(a); },
+                #[cfg(feature = "annotations")] Attribute::RuntimeInvisibleTypeAnnotations(a) => { invisible_type_annotations.extend(a); },
+                Attribute::Module(m) => { module = Some(m); },
+                Attribute::ModulePackages(p) => { module_packages = Some(p); },
+                Attribute::ModuleMainClass(c) => { module_main_class = Some(c); },
+                Attribute::InnerClasses(c) => { inner_classes = c; },
+                Attribute::EnclosingMethod(m) => { enclosing_method = Some(m); },
+                Attribute::NestHost(h) => { nest_host = Some(h); },
+                Attribute::NestMembers(m) => { nest_members = m; },
+                Attribute::Record(c) => { record_components = Some(c); },
+                Attribute::PermittedSubclasses(c) => { permitted_subclasses = Some(c); },
+                Attribute::BootstrapMethods(m) => { bootstrap_methods = m; },
                 _ => {},
             }
         }
+        if let Some(module) = module.as_mut() {
+            if let Some(packages) = module_packages { module.packages = packages; }
+            module.main_class = module_main_class;
+        }
 
         Ok(Self {
             flags,
@@ -114,23 +449,811 @@ impl Class {
             fields,
             methods,
             deprecated,
+            signature,
+            source_file,
+            source_debug_extension,
+            synthetic_attribute,
+            attribute_names,
+            custom_attributes,
+            unknown_attributes,
+            #[cfg(feature = "annotations")] visible_annotations,
+            #[cfg(feature = "annotations")] invisible_annotations,
+            #[cfg(feature = "annotations")] visible_type_annotations,
+            #[cfg(feature = "annotations")] invisible_type_annotations,
+            module,
+            inner_classes,
+            enclosing_method,
+            nest_host,
+            nest_members,
+            record_components,
+            permitted_subclasses,
+            bootstrap_methods,
+            warnings,
+            minor_version: header.minor_version,
+            major_version: header.major_version.0,
+            constants,
         })
     }
 
+    /// The bootstrap methods of this class's `invokedynamic` call sites and dynamically-computed constants, in
+    /// `bootstrap_method_attr_index` order.  See [BootstrapMethod](bootstrap/struct.BootstrapMethod.html).
+    pub fn bootstrap_methods(&self) -> &[bootstrap::BootstrapMethod] { &self.bootstrap_methods[..] }
+
+    /// The bootstrap method at `bootstrap_method_attr_index` `index`, as referenced by `CONSTANT_InvokeDynamic_info`
+    /// and `CONSTANT_Dynamic_info` constants.
+    pub fn bootstrap_method(&self, index: u16) -> Option<&bootstrap::BootstrapMethod> { self.bootstrap_methods.get(usize::from(index)) }
+
+    /// Recoverable oddities found while parsing this class or its fields and methods, such as duplicate attributes.
+    pub fn warnings(&self) -> &[Warning] { &self.warnings[..] }
+
+    /// The `major_version` of the class file, such as `52` for Java 8.
+    pub fn major_version(&self) -> u16 { self.major_version }
+
+    /// The `minor_version` of the class file, such as `0xFFFF` for classes using preview features.
+    pub fn minor_version(&self) -> u16 { self.minor_version }
+
+    /// Check this class against the structural rules of its classfile version, failing with every violation found.
+    /// See [validate::check](validate/fn.check.html).
+    pub fn validate(&self) -> io::Result<()> {
+        let violations = validate::check(self);
+        if violations.is_empty() { return Ok(()); }
+        io_data_err!("{} violates classfile version {} rules: {}", self.path.as_str(), self.major_version, violations.iter().map(|v| v.to_string()).collect::<Vec<_>>().join("; "))
+    }
+
+    /// The names of the attributes of this class (but not its fields or methods), in classfile order.
+    ///
+    /// This includes attributes jreflection doesn't otherwise understand, such as Scala's `"ScalaSig"`.
+    pub fn attribute_names(&self) -> impl Iterator<Item = &str> { self.attribute_names.iter().map(|n| n.as_str()) }
+
+    /// The attributes of this class decoded by decoders registered with [ParseOptions::register_attribute](struct.ParseOptions.html#method.register_attribute).
+    pub fn custom_attributes(&self) -> &[CustomAttribute] { &self.custom_attributes[..] }
+
+    /// The names of any attributes of this class (but not its fields or methods) that jreflection didn't recognize.
+    pub fn unknown_attribute_names(&self) -> impl Iterator<Item = &str> { self.unknown_attributes.iter().map(|a| a.name.as_str()) }
+
+    /// Any attributes of this class (but not its fields or methods) that jreflection didn't recognize, undecoded, such
+    /// as Scala's `"ScalaSig"`.
+    pub fn unknown_attributes(&self) -> &[attributes::RawAttribute] { &self.unknown_attributes[..] }
+
+    /// The type descriptors of the annotations of this class, visible and invisible at runtime alike, such as `"Lcom/google/common/annotations/Beta;"`.
+    #[cfg(feature = "annotations")]
+    pub fn annotation_types(&self) -> impl Iterator<Item = &str> { self.annotations().map(|a| a.descriptor.as_str()) }
+
+    /// The annotations of this class, visible then invisible at runtime, with their elements.  See [Annotation](annotation/struct.Annotation.html).
+    #[cfg(feature = "annotations")]
+    pub fn annotations(&self) -> impl Iterator<Item = &annotation::Annotation> { self.visible_annotations.iter().chain(self.invisible_annotations.iter()) }
+
+    /// The annotations of this class that are visible at runtime, with their elements.  See [Annotation](annotation/struct.Annotation.html).
+    #[cfg(feature = "annotations")]
+    pub fn visible_annotations(&self) -> &[annotation::Annotation] { &self.visible_annotations[..] }
+
+    /// The annotations of this class that are only retained in the classfile, such as `@Nullable` or `@Beta`.
+    #[cfg(feature = "annotations")]
+    pub fn invisible_annotations(&self) -> &[annotation::Annotation] { &self.invisible_annotations[..] }
+
+    /// The annotations on type uses within this class's declaration (JSR 308), visible then invisible at runtime.  See [TypeAnnotation](annotation/struct.TypeAnnotation.html).
+    #[cfg(feature = "annotations")]
+    pub fn type_annotations(&self) -> impl Iterator<Item = &annotation::TypeAnnotation> { self.visible_type_annotations.iter().chain(self.invisible_type_annotations.iter()) }
+
+    /// The annotations on type uses within this class's declaration that are visible at runtime.
+    #[cfg(feature = "annotations")]
+    pub fn visible_type_annotations(&self) -> &[annotation::TypeAnnotation] { &self.visible_type_annotations[..] }
+
+    /// The annotations on type uses within this class's declaration that are only retained in the classfile.
+    #[cfg(feature = "annotations")]
+    pub fn invisible_type_annotations(&self) -> &[annotation::TypeAnnotation] { &self.invisible_type_annotations[..] }
+
+    /// How stable this class is declared to be by its own annotations.  See [Stability](stability/enum.Stability.html).
+    ///
+    /// This doesn't include annotations on the package (see [stability::of_package](stability/fn.of_package.html)) or
+    /// outer classes.
+    #[cfg(feature = "annotations")]
+    pub fn stability(&self) -> stability::Stability { stability::Stability::of_annotations(self.annotation_types()) }
+
+    /// The module declared by this class, if it's a `module-info.class`.
+    pub fn module(&self) -> Option<&module::Module> { self.module.as_ref() }
+
+    /// The entries of this class's `InnerClasses` attribute:  every nested class this class declares or references,
+    /// including itself if it's nested.  See [InnerClass](class/struct.InnerClass.html).
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # let src = jreflection::Source::from_jdk_dir(r"C:\Program Files\AdoptOpenJDK\jdk-8.0.232.09-hotspot").unwrap();
+    /// let map = src.read_class("java/util/Map").unwrap();
+    /// for inner in map.inner_classes().iter().filter(|c| c.outer.as_ref() == Some(&map.path)) {
+    ///     println!("{} declares {:?}", map.path.as_str(), inner.name); // Some("Entry")
+    /// }
+    /// ```
+    pub fn inner_classes(&self) -> &[InnerClass] { &self.inner_classes[..] }
+
+    /// The `InnerClasses` entry describing this class itself, if it's a nested class.
+    pub fn inner_class_entry(&self) -> Option<&InnerClass> { self.inner_classes.iter().find(|c| c.inner == self.path) }
+
+    /// The class and method enclosing this class, if it's a local or anonymous class.  See [EnclosingMethod](class/struct.EnclosingMethod.html).
+    pub fn enclosing_method(&self) -> Option<&EnclosingMethod> { self.enclosing_method.as_ref() }
+
+    /// The host of the nest this class is a member of, per its `NestHost` attribute (Java 11+.)  `None` for nest hosts
+    /// themselves, and classes compiled for older versions.
+    ///
+    /// Nestmates can access each other's `private` members directly.  Unlike [inner_classes](#method.inner_classes),
+    /// which lists every nested class *referenced*, a class's nest is exactly the top-level class and the classes nested
+    /// within it.
+    pub fn nest_host(&self) -> Option<&IdBuf> { self.nest_host.as_ref() }
+
+    /// The other members of this class's nest, per its `NestMembers` attribute (Java 11+), if it's a nest host.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # let src = jreflection::Source::from_jdk_dir(r"C:\Program Files\AdoptOpenJDK\jdk-11.0.6.10-hotspot").unwrap();
+    /// let map = src.read_class("java/util/HashMap").unwrap();
+    /// assert!(map.nest_members().iter().any(|m| m.as_str() == "java/util/HashMap$Node"));
+    ///
+    /// let node = src.read_class("java/util/HashMap$Node").unwrap();
+    /// assert_eq!(node.nest_host(), Some(&map.path));
+    /// ```
+    pub fn nest_members(&self) -> &[IdBuf] { &self.nest_members[..] }
+
+    /// The components of this record class, in declaration order, per its `Record` attribute (Java 16+.)  Empty if
+    /// this isn't a [record](#method.is_record).
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # #[cfg(feature = "jar")] {
+    /// # let src = jreflection::Source::from_jar("app.jar").unwrap();
+    /// let point = src.read_class("com/example/Point").unwrap(); // record Point(int x, int y)
+    /// assert!(point.is_record());
+    /// assert_eq!(point.record_components().iter().map(|c| c.name.as_str()).collect::<Vec<_>>(), &["x", "y"]);
+    /// # }
+    /// ```
+    pub fn record_components(&self) -> &[RecordComponent] { self.record_components.as_ref().map(|c| &c[..]).unwrap_or(&[]) }
+
+    /// The classes and interfaces permitted to directly extend or implement this `sealed` class or interface, per its
+    /// `PermittedSubclasses` attribute (Java 17+.)  Empty if this isn't [sealed](#method.is_sealed).
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # #[cfg(feature = "jar")] {
+    /// # let src = jreflection::Source::from_jar("app.jar").unwrap();
+    /// let shape = src.read_class("com/example/Shape").unwrap(); // sealed interface Shape permits Circle, Square
+    /// assert!(shape.is_sealed());
+    /// assert_eq!(shape.permitted_subclasses().iter().map(|c| c.as_str()).collect::<Vec<_>>(), &["com/example/Circle", "com/example/Square"]);
+    /// # }
+    /// ```
+    pub fn permitted_subclasses(&self) -> &[IdBuf] { self.permitted_subclasses.as_ref().map(|c| &c[..]).unwrap_or(&[]) }
+
+    /// The first attribute of this class named `name` decoded as a `T` by a registered decoder, if any.
+    pub fn custom_attribute<T: std::any::Any>(&self, name: &str) -> Option<&T> {
+        self.custom_attributes.iter().filter(|a| a.name() == name).filter_map(|a| a.downcast_ref()).next()
+    }
+
+    /// How many fields this class declares (not including inherited fields.)
+    pub fn field_count(&self) -> usize { self.fields.len() }
+
+    /// How many methods this class declares (not including inherited methods, but including constructors and `<clinit>`.)
+    pub fn method_count(&self) -> usize { self.methods.len() }
+
+    /// Does this class declare a `public static void main(String[] args)` entry point?
+    ///
+    /// Only the classic signature is recognized, not the instance or parameterless `main` methods of newer Java
+    /// preview features.  An inherited `main` (which `java` would also accept) doesn't count.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jreflection::*;
+    /// let mut class = Class::default();
+    /// assert!(!class.has_main_method());
+    /// class.methods.push(Method::new(method::Flags::PUBLIC | method::Flags::STATIC, "main".to_string(), "([Ljava/lang/String;)V".to_string()).unwrap());
+    /// assert!(class.has_main_method());
+    /// ```
+    pub fn has_main_method(&self) -> bool {
+        self.methods.iter().any(|m| m.is_public() && m.is_static() && m.name == "main" && m.descriptor_str() == "([Ljava/lang/String;)V")
+    }
+
+    /// Find the method a compiler generated bridge method (`ACC_BRIDGE`) forwards to, such as the
+    /// `compareTo(Ljava/lang/String;)I` a `compareTo(Ljava/lang/Object;)I` bridge exists for.
+    ///
+    /// If the bridge's code was retained (see [ParseOptions::retain_code](struct.ParseOptions.html#method.retain_code))
+    /// and is well formed, the method it actually invokes is used.  Otherwise, the target is the only non-bridge method of this class with
+    /// the same name, staticness, and number of parameters, whose parameter and return types erase compatibly - identical
+    /// primitives, and any reference type where the bridge has a reference type.  Returns `None` if `bridge` isn't a
+    /// bridge, or if no unambiguous target is found.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # let src = jreflection::Source::from_jdk_dir(r"C:\Program Files\AdoptOpenJDK\jdk-8.0.232.09-hotspot").unwrap();
+    /// let string = src.read_class("java/lang/String").unwrap();
+    /// for bridge in string.methods.iter().filter(|m| m.is_bridge()) {
+    ///     let target = string.bridge_target(bridge).unwrap();
+    ///     println!("{}{} -> {}{}", bridge.name, bridge.descriptor_str(), target.name, target.descriptor_str());
+    /// }
+    /// ```
+    pub fn bridge_target(&self, bridge: &Method) -> Option<&Method> {
+        if !bridge.is_bridge() { return None; }
+        let candidates = || self.methods.iter().filter(move |m| !m.is_bridge() && m.name == bridge.name && m.is_static() == bridge.is_static());
+
+        #[cfg(feature = "code")]
+        if let Some(code) = bridge.code() {
+            if let Ok(references) = code.references() { // Malformed code falls back to matching by descriptor
+                for reference in references {
+                    if let code::Reference::Method { class, name, descriptor } = reference {
+                        if class != self.path.as_str() || name != bridge.name { continue; }
+                        if let Some(target) = candidates().find(|m| m.descriptor_str() == descriptor) { return Some(target); }
+                    }
+                }
+            }
+        }
+
+        fn is_reference(t: &method::Type) -> bool {
+            match t {
+                method::Type::Single(method::BasicType::Class(_))   => true,
+                method::Type::Single(_)                             => false,
+                method::Type::Array { .. }                          => true,
+            }
+        }
+        let compatible = |b: method::Type, t: method::Type| b == t || (is_reference(&b) && is_reference(&t));
+
+        let bridge_desc = bridge.descriptor();
+        let mut matches = candidates().filter(|m| {
+            let desc = m.descriptor();
+            desc.arguments().count() == bridge_desc.arguments().count()
+                && desc.arguments().zip(bridge_desc.arguments()).all(|(t, b)| compatible(b, t))
+                && compatible(bridge_desc.return_type(), desc.return_type())
+        });
+        match (matches.next(), matches.next()) {
+            (Some(target), None)    => Some(target),
+            _                       => None, // No target, or ambiguous overloads
+        }
+    }
+
+    /// The constant pool of this class.
+    pub fn constants(&self) -> &Constants { &self.constants }
+
+    /// The JNI paths of every class this class references:  its superclass, its interfaces, classes mentioned by field
+    /// and method descriptors, and classes referenced by the constant pool (e.g. by code.)  Array types are reduced
+    /// to their element class, and this class itself is excluded.
+    ///
+    /// Classes only referenced by attributes such as annotations or generic signatures are not included.
+    pub fn referenced_classes(&self) -> BTreeSet<String> {
+        let mut classes = BTreeSet::new();
+        let mut add_descriptor = |descriptor: &str| for class in descriptor_classes(descriptor) { classes.insert(class.to_owned()); };
+
+        for field in self.fields.iter() { add_descriptor(field.descriptor_str()); }
+        for method in self.methods.iter() { add_descriptor(method.descriptor_str()); }
+        for constant in self.constants.0.iter() {
+            match constant {
+                Constant::Class { name_index } => match self.constants.get_utf8(*name_index) {
+                    Ok(name) if name.starts_with('[')  => add_descriptor(name),
+                    Ok(name)                            => add_descriptor(&format!("L{};", name)),
+                    Err(_)                              => {},
+                },
+                Constant::NameAndType { descriptor_index, .. } | Constant::MethodType { descriptor_index } => {
+                    if let Ok(descriptor) = self.constants.get_utf8(*descriptor_index) { add_descriptor(descriptor); }
+                },
+                _ => {},
+            }
+        }
+
+        classes.extend(self.super_path.iter().chain(self.interfaces.iter()).map(|c| c.as_str().to_owned()));
+        classes.remove(self.path.as_str());
+        classes
+    }
+
+    /// The values of this class's `static final` fields that are known without running any code, by field name.
+    ///
+    /// That's fields with a `ConstantValue` attribute (see [Field::constant]), and fields that `<clinit>` assigns
+    /// simple constant expressions to - such as `static final int FROYO = 8;` in stubs compiled without constant
+    /// folding, or `static final long MASK = 1L << BITS;` - if this class was read with [ParseOptions::retain_code].
+    /// Only the straight-line start of `<clinit>` is evaluated, and fields assigned anything depending on method calls,
+    /// other classes, or control flow are omitted.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use jreflection::*;
+    /// # #[cfg(all(feature = "jar", feature = "code"))] {
+    /// let mut options = ParseOptions::new();
+    /// options.retain_code(true);
+    /// let android = android::discover_sdk().unwrap().platform(29).unwrap().unwrap().source().unwrap();
+    /// let codes = android.read_class_with("android/os/Build$VERSION_CODES", &options).unwrap();
+    /// assert_eq!(codes.static_constants().unwrap()["Q"], field::Constant::Integer(29));
+    /// # }
+    /// ```
+    ///
+    /// [Field::constant]:          field/struct.Field.html#structfield.constant
+    /// [ParseOptions::retain_code]: struct.ParseOptions.html#method.retain_code
+    pub fn static_constants(&self) -> io::Result<BTreeMap<String, field::Constant>> {
+        #[cfg_attr(not(feature = "code"), allow(unused_mut))]
+        let mut values = self.fields.iter().filter(|f| f.is_static()).filter_map(|f| f.constant.clone().map(|c| (f.name.clone(), c))).collect::<BTreeMap<_, _>>();
+        #[cfg(feature = "code")]
+        if let Some(code) = self.methods.iter().find(|m| m.is_static_init()).and_then(|m| m.code()) {
+            code.fold_static_assignments(self.path.as_str(), &mut values)?;
+        }
+        let finals = self.fields.iter().filter(|f| f.is_static() && f.is_final()).map(|f| f.name.as_str()).collect::<BTreeSet<_>>();
+        Ok(values.into_iter().filter(|(name, _)| finals.contains(name.as_str())).collect())
+    }
+
+    /// The name of the source file this class was compiled from, such as `"Object.java"`, if recorded (javac omits it
+    /// with `-g:none`.)  It includes no directories:  combine it with the class's package to find the file.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # #[cfg(feature = "jar")] {
+    /// # let src = jreflection::Source::from_jar("app.jar").unwrap();
+    /// let class = src.read_class("com/example/Main$1").unwrap();
+    /// let package = class.path.as_str().rfind('/').map(|slash| &class.path.as_str()[..=slash]).unwrap_or("");
+    /// if let Some(source_file) = class.source_file() {
+    ///     println!("compiled from {}{}", package, source_file); // com/example/Main.java
+    /// }
+    /// # }
+    /// ```
+    pub fn source_file(&self) -> Option<&str> { self.source_file.as_ref().map(|s| s.as_str()) }
+
+    /// The contents of this class's `SourceDebugExtension` attribute, if it has one.  Kotlin, Groovy, and JSP compilers
+    /// store an [SMAP](smap/index.html) here, mapping lines of this class to lines of the original sources.
+    pub fn source_debug_extension(&self) -> Option<&str> { self.source_debug_extension.as_ref().map(|s| s.as_str()) }
+
+    /// The [SMAP](smap/struct.Smap.html) stored in this class's `SourceDebugExtension` attribute, or `None` if it
+    /// doesn't have one, or it contains something other than an SMAP.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # #[cfg(feature = "jar")] {
+    /// # let src = jreflection::Source::from_jar("app.jar").unwrap();
+    /// let class = src.read_class("com/example/MainKt").unwrap();
+    /// if let Some(smap) = class.smap().unwrap() {
+    ///     let kotlin = smap.default_stratum().unwrap();
+    ///     if let Some((file, line)) = kotlin.input_line(42) {
+    ///         println!("line 42 of {} was line {} of {}", smap.output_file, line, file.path());
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    pub fn smap(&self) -> io::Result<Option<smap::Smap>> {
+        match self.source_debug_extension() {
+            Some(text) if text.starts_with("SMAP") => smap::Smap::parse(text).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    /// The unparsed generic signature of this class, if it has one.
+    pub fn signature_str(&self) -> Option<&str> { self.signature.as_ref().map(|s| s.as_str()) }
+
+    /// The parsed generic signature of this class, if it has one.
+    pub fn signature(&self) -> io::Result<Option<signature::ClassSignature>> { self.signature_str().map(signature::ClassSignature::parse).transpose() }
+
+    /// A hash of the "shape" of this class - flags, member descriptors, member flags, and hierarchy arity - that ignores
+    /// the names of this class, its members, and any non-platform classes it references.
+    ///
+    /// Renaming obfuscators (ProGuard, R8, etc.) leave this unchanged, making it useful for recognizing obfuscated
+    /// copies of known library classes.  Unrelated classes with similar shapes can collide, especially small ones.
+    pub fn structural_fingerprint(&self) -> fingerprint::Fingerprint { fingerprint::of(self) }
+
+    /// The class is declared `public`, and may be accessed from outside its package.
+    ///
+    /// ```
+    /// # use jreflection::{Class, class::Flags};
+    /// let mut class = Class::default();
+    /// class.flags = Flags::PUBLIC;
+    /// assert!(class.is_public());
+    /// ```
     pub fn is_public(&self)         -> bool { self.flags.contains(Flags::PUBLIC) }
+
+    /// The class is declared `static`.  Only set on nested classes, in their own class file's flags if at all - see [InnerClasses](https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.7.6).
+    ///
+    /// ```
+    /// # use jreflection::{Class, class::Flags};
+    /// let mut class = Class::default();
+    /// class.flags = Flags::PUBLIC | Flags::STATIC;
+    /// assert!(class.is_static());
+    /// ```
     pub fn is_static(&self)         -> bool { self.flags.contains(Flags::STATIC) }
+
+    /// The class is declared `final`, and may not be subclassed.
+    ///
+    /// ```
+    /// # use jreflection::{Class, class::Flags};
+    /// let mut class = Class::default();
+    /// class.flags = Flags::PUBLIC | Flags::FINAL;
+    /// assert!(class.is_final());
+    /// ```
     pub fn is_final(&self)          -> bool { self.flags.contains(Flags::FINAL) }
+
+    /// The class uses modern `invokespecial` semantics for superclass methods.  Set by every compiler since JDK 1.0.2.
+    ///
+    /// ```
+    /// # use jreflection::{Class, class::Flags};
+    /// let mut class = Class::default();
+    /// class.flags = Flags::PUBLIC | Flags::SUPER;
+    /// assert!(class.is_super());
+    /// ```
     pub fn is_super(&self)          -> bool { self.flags.contains(Flags::SUPER) }
+
+    /// The class is actually an interface.
+    ///
+    /// ```
+    /// # use jreflection::{Class, class::Flags};
+    /// let mut class = Class::default();
+    /// class.flags = Flags::INTERFACE | Flags::ABSTRACT;
+    /// assert!(class.is_interface());
+    /// ```
     pub fn is_interface(&self)      -> bool { self.flags.contains(Flags::INTERFACE) }
+
+    /// The class is declared `abstract`, and may not be instantiated directly.  Interfaces are always abstract.
+    ///
+    /// ```
+    /// # use jreflection::{Class, class::Flags};
+    /// let mut class = Class::default();
+    /// class.flags = Flags::PUBLIC | Flags::ABSTRACT;
+    /// assert!(class.is_abstract());
+    /// ```
     pub fn is_abstract(&self)       -> bool { self.flags.contains(Flags::ABSTRACT) }
-    pub fn is_synthetic(&self)      -> bool { self.flags.contains(Flags::SYNTHETIC) }
+
+    /// The class is synthetic, and is not present in the source code.
+    /// This checks both the `ACC_SYNTHETIC` flag and the `Synthetic` attribute used by older compilers.
+    ///
+    /// ```
+    /// # use jreflection::{Class, class::Flags};
+    /// let mut class = Class::default();
+    /// class.flags = Flags::SYNTHETIC;
+    /// assert!(class.is_synthetic());
+    /// ```
+    pub fn is_synthetic(&self)      -> bool { self.flags.contains(Flags::SYNTHETIC) || self.synthetic_attribute }
+
+    /// The class is an annotation type, such as `java/lang/Deprecated`.
+    ///
+    /// ```
+    /// # use jreflection::{Class, class::Flags};
+    /// let mut class = Class::default();
+    /// class.flags = Flags::INTERFACE | Flags::ABSTRACT | Flags::ANNOTATION;
+    /// assert!(class.is_annotation());
+    /// ```
     pub fn is_annotation(&self)     -> bool { self.flags.contains(Flags::ANNOTATION) }
+
+    /// The class is an enum type, or the anonymous class of an enum constant with a body.
+    ///
+    /// ```
+    /// # use jreflection::{Class, class::Flags};
+    /// let mut class = Class::default();
+    /// class.flags = Flags::PUBLIC | Flags::FINAL | Flags::ENUM;
+    /// assert!(class.is_enum());
+    /// ```
     pub fn is_enum(&self)           -> bool { self.flags.contains(Flags::ENUM) }
+
+    /// The class file is a module declaration (`module-info.class`), not a class or interface.
+    ///
+    /// ```
+    /// # use jreflection::{Class, class::Flags};
+    /// let mut class = Class::default();
+    /// class.flags = Flags::MODULE;
+    /// assert!(class.is_module());
+    /// ```
+    pub fn is_module(&self)         -> bool { self.flags.contains(Flags::MODULE) }
+
+    /// Is this a record class?  True if it has a `Record` attribute, even if it has no components.
+    pub fn is_record(&self)         -> bool { self.record_components.is_some() }
+
+    /// Is this class or interface declared `sealed`?  True if it has a `PermittedSubclasses` attribute.
+    pub fn is_sealed(&self)         -> bool { self.permitted_subclasses.is_some() }
+}
+
+/// A compact, Java-like declaration, such as `public final class java.lang.String extends java.lang.Object implements
+/// java.io.Serializable`.  The alternate form (`{:#}`) also lists every field and method, one per line.
+///
+/// ```
+/// # use jreflection::{Class, class::{Flags, IdBuf}};
+/// let mut class = Class::default();
+/// class.flags         = Flags::PUBLIC | Flags::INTERFACE | Flags::ABSTRACT;
+/// class.path          = IdBuf::new("java/util/List".to_string());
+/// class.super_path    = Some(IdBuf::new("java/lang/Object".to_string()));
+/// class.interfaces    = vec![IdBuf::new("java/util/Collection".to_string())];
+/// assert_eq!(class.to_string(), "public interface java.util.List extends java.util.Collection");
+/// ```
+impl Display for Class {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        let name = |id: &IdBuf| id.as_str().replace('/', ".");
+        if self.is_module() {
+            write!(fmt, "module {}", self.module().map(|m| m.name.clone()).unwrap_or_else(|| name(&self.path)))?;
+        } else {
+            if self.is_public() { write!(fmt, "public ")?; }
+            if self.is_abstract() && !self.is_interface() { write!(fmt, "abstract ")?; }
+            if self.is_final() { write!(fmt, "final ")?; }
+            let kind = if self.is_annotation() { "@interface" } else if self.is_interface() { "interface" } else if self.is_enum() { "enum" } else if self.is_record() { "record" } else { "class" };
+            write!(fmt, "{} {}", kind, name(&self.path))?;
+            if !self.is_interface() {
+                if let Some(super_path) = self.super_path.as_ref() { write!(fmt, " extends {}", name(super_path))?; }
+            }
+            for (i, interface) in self.interfaces.iter().enumerate() {
+                let keyword = if i != 0 { "," } else if self.is_interface() { " extends" } else { " implements" };
+                write!(fmt, "{} {}", keyword, name(interface))?;
+            }
+        }
+
+        if fmt.alternate() {
+            writeln!(fmt, " {{")?;
+            for field  in self.fields.iter()  { writeln!(fmt, "    {};", field)?; }
+            for method in self.methods.iter() { writeln!(fmt, "    {};", method)?; }
+            write!(fmt, "}}")?;
+        }
+        Ok(())
+    }
+}
+
+
+
+/// The classes referenced by a field or method descriptor, e.g. `"(ILjava/lang/String;)[Ljava/lang/Object;"` references `java/lang/String` and `java/lang/Object`.
+pub(crate) fn descriptor_classes(descriptor: &str) -> impl Iterator<Item = &str> {
+    descriptor.split(';').filter_map(|part| part.find('L').map(|start| &part[start+1..])).filter(|class| !class.is_empty())
+}
+
+
+
+#[test] fn attribute_names() {
+    let mut c = testing::ClassFile::new("com/example/Foo", Some("java/lang/Object"));
+    let source_file = c.utf8("Foo.java");
+    c.class_attribute("SourceFile", &testing::be16(source_file));
+    c.class_attribute("ScalaSig", &[5, 0, 0]);
+    c.class_attribute("Deprecated", &[]);
+    let class = c.read();
+    assert_eq!(class.attribute_names().collect::<Vec<_>>(), &["SourceFile", "ScalaSig", "Deprecated"]);
+    assert_eq!(class.source_file(), Some("Foo.java"));
+    assert!(class.deprecated);
+    assert_eq!(testing::ClassFile::new("com/example/Bar", Some("java/lang/Object")).read().source_file(), None);
+}
+
+#[test] fn unknown_attributes() {
+    let mut c = testing::ClassFile::new("com/example/Foo", Some("java/lang/Object"));
+    let kotlin = c.attribute("org.jetbrains.kotlin.Flags", &[1, 2]);
+    c.field (0x0001, "value", "I", &[kotlin.clone()]);
+    c.method(0x0001, "run", "()V", &[kotlin]);
+    c.class_attribute("ScalaSig", &[5, 0, 0]);
+    c.class_attribute("ScalaInlineInfo", &[]);
+    let source_file = c.utf8("Foo.java");
+    let class = c.read();
+
+    let raw = |name: &str, info: &[u8]| attributes::RawAttribute { name: name.to_owned(), info: info.to_vec() };
+    assert_eq!(class.unknown_attributes(), &[raw("ScalaSig", &[5, 0, 0]), raw("ScalaInlineInfo", &[])]);
+    assert_eq!(class.unknown_attribute_names().collect::<Vec<_>>(), &["ScalaSig", "ScalaInlineInfo"]);
+    assert_eq!(class.fields[0].unknown_attributes(), &[raw("org.jetbrains.kotlin.Flags", &[1, 2])]);
+    assert_eq!(class.methods[0].unknown_attributes(), &[raw("org.jetbrains.kotlin.Flags", &[1, 2])]);
+    assert_eq!(class.methods[0].attribute_names().collect::<Vec<_>>(), &["org.jetbrains.kotlin.Flags"]);
+
+    match attributes::Attribute::decode(&class, &class.unknown_attributes()[0]).unwrap() {
+        attributes::Attribute::Unknown(info) => assert_eq!(info, &[5, 0, 0]),
+        other => panic!("expected Unknown, got {:?}", other),
+    }
+    match attributes::Attribute::decode(&class, &raw("SourceFile", &testing::be16(source_file))).unwrap() {
+        attributes::Attribute::SourceFile(name) => assert_eq!(name, "Foo.java"),
+        other => panic!("expected SourceFile, got {:?}", other),
+    }
+    assert!(attributes::Attribute::decode(&class, &raw("SourceFile", &[0, 0])).is_err());
+    match attributes::Attribute::decode(&class, &raw("LineNumberTable", &[0, 0])).unwrap() {
+        attributes::Attribute::Unknown(info) => assert_eq!(info, &[0, 0]), // Needs the Code it belongs to, see Code::line_numbers
+        other => panic!("expected Unknown, got {:?}", other),
+    }
+}
+
+#[test] fn source_debug_extension() {
+    let mut c = testing::ClassFile::new("com/example/MainKt", Some("java/lang/Object"));
+    c.class_attribute("SourceDebugExtension", b"SMAP\nMain.kt\nKotlin\n*S Kotlin\n*F\n+ 1 Main.kt\ncom/example/MainKt\n*L\n1#1,3:1\n*E\n");
+    let class = c.read();
+    assert!(class.source_debug_extension().unwrap().starts_with("SMAP\nMain.kt\n"));
+    let smap = class.smap().unwrap().unwrap();
+    assert_eq!(smap.default_stratum().unwrap().input_line(2).map(|(f, l)| (f.path(), l)), Some(("com/example/MainKt", 2)));
+
+    let mut c = testing::ClassFile::new("com/example/Weird", Some("java/lang/Object"));
+    c.class_attribute("SourceDebugExtension", &[b'a', 0xC0, 0x80, b'b']);
+    let class = c.read();
+    assert_eq!(class.source_debug_extension(), Some("a\u{0}b"));
+    assert_eq!(class.smap().unwrap(), None);
+}
+
+
+#[test] fn synthetic_attribute() {
+    let mut c = testing::ClassFile::new("com/example/Foo$1", Some("java/lang/Object"));
+    let synthetic = c.attribute("Synthetic", &[]);
+    c.field (0x0010, "this$0",  "Lcom/example/Foo;", &[synthetic.clone()]);
+    c.field (0x1010, "val$x",   "I",                 &[]);
+    c.field (0x0000, "y",       "I",                 &[]);
+    c.method(0x0008, "access$000", "()V",            &[synthetic]);
+    c.class_attribute("Synthetic", &[]);
+    let class = c.read();
+    assert!(class.is_synthetic());
+    assert!(!class.flags.contains(Flags::SYNTHETIC));
+    assert_eq!(class.fields.iter().map(|f| f.is_synthetic()).collect::<Vec<_>>(), &[true, true, false]);
+    assert!(class.methods[0].is_synthetic());
+}
+
+#[test] fn inner_classes() {
+    let mut c = testing::ClassFile::new("com/example/Outer$Inner", Some("java/lang/Object"));
+    let entries = [
+        (c.class("com/example/Outer$Inner"),    c.class("com/example/Outer"),   c.utf8("Inner"),    0x000A),
+        (c.class("com/example/Outer$Inner$1"),  0,                              0,                  0x0000),
+        (c.class("com/example/Outer$Inner$1L"), 0,                              c.utf8("L"),        0x0010),
+        (c.class("java/util/Map$Entry"),        c.class("java/util/Map"),       c.utf8("Entry"),    0x0609),
+    ];
+    let mut info = testing::be16(entries.len() as u16);
+    for &(inner, outer, name, flags) in entries.iter() { info.extend([inner, outer, name, flags].iter().flat_map(|v| testing::be16(*v))); }
+    c.class_attribute("InnerClasses", &info);
+    let class = c.read();
+
+    let inner = class.inner_classes();
+    assert_eq!(inner.len(), 4);
+    assert_eq!(class.inner_class_entry(), Some(&inner[0]));
+    assert_eq!(inner[0].outer.as_ref().map(|o| o.as_str()), Some("com/example/Outer"));
+    assert_eq!(inner[0].name.as_ref().map(|n| n.as_str()), Some("Inner"));
+    assert_eq!(inner[0].flags, InnerClassFlags::PRIVATE | InnerClassFlags::STATIC);
+    assert!(inner[0].is_member() && !inner[0].is_local() && !inner[0].is_anonymous());
+    assert!(inner[1].is_anonymous() && !inner[1].is_member());
+    assert!(inner[2].is_local() && !inner[2].is_anonymous());
+    assert_eq!(inner[3].inner.as_str(), "java/util/Map$Entry");
+    assert!(inner[3].flags.contains(InnerClassFlags::INTERFACE));
+
+    assert!(testing::ClassFile::new("com/example/Outer", Some("java/lang/Object")).read().inner_class_entry().is_none());
+}
+
+#[test] fn enclosing_method() {
+    let mut c = testing::ClassFile::new("com/example/Outer$1", Some("java/lang/Object"));
+    let info = [testing::be16(c.class("com/example/Outer")), testing::be16(c.name_and_type("run", "(I)V"))].concat();
+    c.class_attribute("EnclosingMethod", &info);
+    let method = c.read().enclosing_method().cloned().unwrap();
+    assert_eq!(method.class.as_str(), "com/example/Outer");
+    assert_eq!(method.name.as_ref().map(|n| n.as_str()), Some("run"));
+    assert_eq!(method.descriptor.as_ref().map(|d| d.as_str()), Some("(I)V"));
+
+    let mut c = testing::ClassFile::new("com/example/Outer$2", Some("java/lang/Object"));
+    let info = [testing::be16(c.class("com/example/Outer")), testing::be16(0)].concat();
+    c.class_attribute("EnclosingMethod", &info);
+    assert_eq!(c.read().enclosing_method(), Some(&EnclosingMethod::new(IdBuf::new("com/example/Outer".to_owned()), None, None)));
+
+    let mut c = testing::ClassFile::new("com/example/Outer$3", Some("java/lang/Object"));
+    let info = [testing::be16(c.class("com/example/Outer")), testing::be16(c.utf8("run"))].concat();
+    c.class_attribute("EnclosingMethod", &info);
+    assert!(Class::read(&mut &c.to_bytes()[..]).is_err());
+
+    assert!(testing::ClassFile::new("com/example/Outer", Some("java/lang/Object")).read().enclosing_method().is_none());
+}
+
+#[test] fn nestmates() {
+    let mut c = testing::ClassFile::new("com/example/Outer", Some("java/lang/Object"));
+    let info = [testing::be16(2), testing::be16(c.class("com/example/Outer$Inner")), testing::be16(c.class("com/example/Outer$1"))].concat();
+    c.class_attribute("NestMembers", &info);
+    let outer = c.read();
+    assert_eq!(outer.nest_host(), None);
+    assert_eq!(outer.nest_members().iter().map(|m| m.as_str()).collect::<Vec<_>>(), &["com/example/Outer$Inner", "com/example/Outer$1"]);
+
+    let mut c = testing::ClassFile::new("com/example/Outer$Inner", Some("java/lang/Object"));
+    let info = testing::be16(c.class("com/example/Outer"));
+    c.class_attribute("NestHost", &info);
+    let inner = c.read();
+    assert_eq!(inner.nest_host(), Some(&outer.path));
+    assert!(inner.nest_members().is_empty());
+
+    let mut c = testing::ClassFile::new("com/example/Bad", Some("java/lang/Object"));
+    let info = [testing::be16(2), testing::be16(c.class("com/example/Bad$Inner"))].concat();
+    c.class_attribute("NestMembers", &info);
+    assert!(Class::read(&mut &c.to_bytes()[..]).is_err());
+}
+
+#[test] fn record_components() {
+    use crate::testing::be16;
+
+    let mut c = testing::ClassFile::new("com/example/Pair", Some("java/lang/Record"));
+    let (first, second, string, list) = (c.utf8("first"), c.utf8("second"), c.utf8("Ljava/lang/String;"), c.utf8("Ljava/util/List;"));
+    let signature = c.utf8("Ljava/util/List<Ljava/lang/String;>;");
+    let signature = c.attribute("Signature", &be16(signature));
+    let nullable = c.utf8("Ljavax/annotation/Nullable;");
+    let nullable = c.attribute("RuntimeVisibleAnnotations", &[be16(1), be16(nullable), be16(0)].concat());
+    let info = [
+        be16(2),
+        be16(first), be16(string), be16(1), nullable,
+        be16(second), be16(list), be16(1), signature,
+    ].concat();
+    c.class_attribute("Record", &info);
+    let class = c.read();
+    assert!(class.is_record());
+    let components = class.record_components();
+    assert_eq!(components.iter().map(|c| (c.name.as_str(), c.descriptor.as_str())).collect::<Vec<_>>(), &[("first", "Ljava/lang/String;"), ("second", "Ljava/util/List;")]);
+    #[cfg(feature = "annotations")] assert_eq!(components[0].visible_annotations.iter().map(|a| a.descriptor.as_str()).collect::<Vec<_>>(), &["Ljavax/annotation/Nullable;"]);
+    assert_eq!(components[0].signature, None);
+    assert_eq!(components[1].signature.as_ref().map(|s| s.as_str()), Some("Ljava/util/List<Ljava/lang/String;>;"));
+
+    let mut c = testing::ClassFile::new("com/example/Empty", Some("java/lang/Record"));
+    c.class_attribute("Record", &be16(0));
+    let class = c.read();
+    assert!(class.is_record() && class.record_components().is_empty());
+
+    let class = testing::ClassFile::new("com/example/Plain", Some("java/lang/Object")).read();
+    assert!(!class.is_record() && class.record_components().is_empty());
+}
+
+#[test] fn permitted_subclasses() {
+    let mut c = testing::ClassFile::new("com/example/Shape", Some("java/lang/Object"));
+    c.flags = 0x0601; // PUBLIC | INTERFACE | ABSTRACT
+    let info = [testing::be16(2), testing::be16(c.class("com/example/Circle")), testing::be16(c.class("com/example/Square"))].concat();
+    c.class_attribute("PermittedSubclasses", &info);
+    let shape = c.read();
+    assert!(shape.is_sealed());
+    assert_eq!(shape.permitted_subclasses().iter().map(|c| c.as_str()).collect::<Vec<_>>(), &["com/example/Circle", "com/example/Square"]);
+
+    let class = testing::ClassFile::new("com/example/Circle", Some("java/lang/Object")).read();
+    assert!(!class.is_sealed() && class.permitted_subclasses().is_empty());
+
+    let mut c = testing::ClassFile::new("com/example/Bad", Some("java/lang/Object"));
+    c.class_attribute("PermittedSubclasses", &testing::be16(1));
+    assert!(Class::read(&mut &c.to_bytes()[..]).is_err());
+}
+
+#[test] fn display() {
+    let mut c = testing::ClassFile::new("com/example/Foo", Some("java/lang/Object"));
+    c.flags = 0x0001 | 0x0010 | 0x0020;
+    c.interface("java/lang/Runnable");
+    c.interface("java/io/Serializable");
+    c.field (0x0002 | 0x0040, "count", "J", &[]);
+    c.method(0x0001, "<init>", "()V", &[]);
+    c.method(0x0009 | 0x0100, "hash", "([BI)I", &[]);
+    let class = c.read();
+
+    assert_eq!(class.to_string(), "public final class com.example.Foo extends java.lang.Object implements java.lang.Runnable, java.io.Serializable");
+    assert_eq!(format!("{:#}", class), "public final class com.example.Foo extends java.lang.Object implements java.lang.Runnable, java.io.Serializable {
+    private volatile long count;
+    public void <init>();
+    public static native int hash(byte[], int);
+}");
+
+    let dump = format!("{:#?}", class);
+    assert!(dump.contains("path: \"com/example/Foo\""));
+    assert!(dump.contains("#1: Utf8 com/example/Foo"));
 }
 
+#[test] fn custom_attributes() {
+    #[derive(Debug, PartialEq)] struct ScalaSig { major: u8, minor: u8 }
+
+    let mut c = testing::ClassFile::new("com/example/Foo", Some("java/lang/Object"));
+    let constant = c.string("bar");
+    let constant_value = c.attribute("ConstantValue", &testing::be16(constant));
+    let custom = c.attribute("com.example.Custom", &[1, 2, 3]);
+    c.field(0x0019, "FOO", "Ljava/lang/String;", &[constant_value, custom]);
+    c.class_attribute("ScalaSig", &[5, 0, 1]);
+    let bytes = c.to_bytes();
+
+    let mut options = ParseOptions::new();
+    options.register_attribute("ScalaSig", |info, _| Ok(ScalaSig { major: info[0], minor: info[1] }));
+    options.register_attribute("com.example.Custom", |info, _| Ok(info.len()));
+    options.register_attribute("ConstantValue", |info, constants| match constants.get(u16::from_be_bytes([info[0], info[1]]))? {
+        Constant::String { .. } => Ok("string"),
+        _ => Ok("other"),
+    });
+
+    let class = Class::read_with(&mut std::io::Cursor::new(&bytes[..]), &options).unwrap();
+    assert_eq!(class.custom_attribute::<ScalaSig>("ScalaSig"), Some(&ScalaSig { major: 5, minor: 0 }));
+    assert_eq!(class.custom_attribute::<usize>("ScalaSig"), None);
+    assert_eq!(class.attribute_names().collect::<Vec<_>>(), &["ScalaSig"]);
 
+    let field = &class.fields[0];
+    assert_eq!(field.constant, None); // The registered decoder takes priority
+    assert_eq!(field.custom_attributes().iter().map(|a| a.name()).collect::<Vec<_>>(), &["ConstantValue", "com.example.Custom"]);
+    assert_eq!(field.custom_attributes()[0].downcast_ref::<&str>(), Some(&"string"));
+    assert_eq!(field.custom_attributes()[1].downcast_ref::<usize>(), Some(&3));
 
-#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    let class = Class::read(&mut std::io::Cursor::new(&bytes[..])).unwrap();
+    assert!(class.custom_attributes().is_empty());
+    assert!(class.fields[0].constant.is_some());
+
+    options.register_attribute("ScalaSig", |_, _| -> io::Result<()> { io_data_err!("bad ScalaSig") });
+    assert!(Class::read_with(&mut std::io::Cursor::new(&bytes[..]), &options).is_err());
+}
+
+
+
+#[derive(Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct IdBuf(String);
 
 impl IdBuf {
@@ -142,9 +1265,13 @@ impl IdBuf {
 
 // XXX: This should really be `#[repr(transparent)] pub struct Id(str);`, but I've banned unsafe for this lib...
 // Also, patterns apparently can't handle Id::new(...) even when it's a const fn.
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Id<'a>(pub &'a str);
 
+// Just the quoted path, to keep `{:#?}` dumps of classes readable
+impl Debug for IdBuf  { fn fmt(&self, fmt: &mut Formatter) -> fmt::Result { Debug::fmt(&self.0, fmt) } }
+impl Debug for Id<'_> { fn fmt(&self, fmt: &mut Formatter) -> fmt::Result { Debug::fmt(self.0, fmt) } }
+
 impl<'a> Id<'a> {
     pub fn as_str(&self) -> &'a str { self.0 }
     pub fn iter(&self) -> IdIter<'a> { IdIter::new(self.0) }
@@ -218,3 +1345,207 @@ impl<'a> Iterator for IdIter<'a> {
         IdPart::LeafClass("MoreInner"),
     ]);
 }
+
+#[test] fn referenced_classes() {
+    let mut c = testing::ClassFile::new("com/example/Foo", Some("java/lang/Object"));
+    c.interface("java/lang/Runnable");
+    c.field (0x0002, "bar",   "Lcom/example/Bar;",                  &[]);
+    c.method(0x0001, "baz",   "(I[Lcom/example/Baz;)V",             &[]);
+    c.methodref("com/example/Util", "help", "(Lcom/example/Helper;)Ljava/lang/String;");
+    c.class("[[Lcom/example/Element;");
+    c.class("com/example/Foo");
+    let class = c.read();
+    assert_eq!(class.referenced_classes().into_iter().collect::<Vec<_>>(), &[
+        "com/example/Bar",
+        "com/example/Baz",
+        "com/example/Element",
+        "com/example/Helper",
+        "com/example/Util",
+        "java/lang/Object",
+        "java/lang/Runnable",
+        "java/lang/String",
+    ]);
+    assert_eq!(descriptor_classes("(IJ[[D)V").count(), 0);
+}
+
+#[test] fn peek() {
+    let mut c = testing::ClassFile::new("com/example/Main", Some("java/lang/Object"));
+    c.long(42);
+    c.methodref("java/io/PrintStream", "println", "(Ljava/lang/String;)V");
+    c.string("\u{0}snowman \u{2603}");
+    c.method(0x0009, "main", "([Ljava/lang/String;)V", &[]);
+    c.major_version = 55;
+    let bytes = c.to_bytes();
+
+    let header = Class::peek(&bytes[..]).unwrap();
+    let class = Class::read(&mut &bytes[..]).unwrap();
+    assert_eq!(header, ClassHeaderInfo { minor_version: 0, major_version: 55, flags: class.flags, path: class.path, super_path: class.super_path, _non_exhaustive: () });
+
+    let object = testing::ClassFile::new("java/lang/Object", None).to_bytes();
+    assert_eq!(Class::peek(&object[..]).unwrap().super_path, None);
+
+    assert!(Class::peek(&bytes[..20]).is_err());
+    assert!(Class::peek(&bytes[1..]).is_err());
+}
+
+#[cfg(feature = "code")]
+#[test] fn bridge_target() {
+    let mut c = testing::ClassFile::new("com/example/ByLength", Some("java/lang/Object"));
+    c.interface("java/util/Comparator");
+    let compare = c.methodref("com/example/ByLength", "compare", "(Ljava/lang/String;Ljava/lang/String;)I");
+    let string  = c.class("java/lang/String");
+    let bytecode = [
+        vec![0x2A, 0x2B, 0xC0], testing::be16(string),  // aload_0; aload_1; checkcast String
+        vec![0x2C, 0xC0], testing::be16(string),        // aload_2; checkcast String
+        vec![0xB6], testing::be16(compare),             // invokevirtual compare(String, String)
+        vec![0xAC],                                     // ireturn
+    ].concat();
+    let code = [testing::be16(3), testing::be16(3), testing::be32(bytecode.len() as u32), bytecode, testing::be16(0), testing::be16(0)].concat();
+    let code = c.attribute("Code", &code);
+    c.method(0x0001, "compare", "(Ljava/lang/String;Ljava/lang/String;)I", &[]);
+    c.method(0x1041, "compare", "(Ljava/lang/Object;Ljava/lang/Object;)I", &[code]); // PUBLIC | BRIDGE | SYNTHETIC
+    c.method(0x0001, "compare", "(I)I", &[]);
+    let bytes = c.to_bytes();
+
+    let class = Class::read(&mut &bytes[..]).unwrap();
+    let target = class.bridge_target(&class.methods[1]).unwrap();
+    assert_eq!(target.descriptor_str(), "(Ljava/lang/String;Ljava/lang/String;)I");
+    assert!(class.bridge_target(&class.methods[0]).is_none(), "not a bridge");
+
+    // An overload that erases compatibly makes the shape-based match ambiguous, but retained code still resolves it
+    c.method(0x0001, "compare", "(Ljava/lang/Integer;Ljava/lang/Integer;)I", &[]);
+    let bytes = c.to_bytes();
+    let class = Class::read(&mut &bytes[..]).unwrap();
+    assert!(class.bridge_target(&class.methods[1]).is_none());
+
+    let mut options = ParseOptions::new();
+    options.retain_code(true);
+    let class = Class::read_with(&mut &bytes[..], &options).unwrap();
+    assert_eq!(class.bridge_target(&class.methods[1]).unwrap().descriptor_str(), "(Ljava/lang/String;Ljava/lang/String;)I");
+
+    // Truncated code (an invokevirtual missing its operand) can't be followed, but doesn't prevent the shape-based match
+    let mut c = testing::ClassFile::new("com/example/ByLength", Some("java/lang/Object"));
+    let bytecode = [0x2A, 0x2B, 0x2C, 0xB6, 0x00];
+    let code = [testing::be16(3), testing::be16(3), testing::be32(bytecode.len() as u32), bytecode.to_vec(), testing::be16(0), testing::be16(0)].concat();
+    let code = c.attribute("Code", &code);
+    c.method(0x0001, "compare", "(Ljava/lang/String;Ljava/lang/String;)I", &[]);
+    c.method(0x1041, "compare", "(Ljava/lang/Object;Ljava/lang/Object;)I", &[code]);
+    let class = Class::read_with(&mut &c.to_bytes()[..], &options).unwrap();
+    assert!(class.methods[1].code().unwrap().references().is_err());
+    assert_eq!(class.bridge_target(&class.methods[1]).unwrap().descriptor_str(), "(Ljava/lang/String;Ljava/lang/String;)I");
+}
+
+#[cfg(feature = "code")]
+#[test] fn static_constants() {
+    use crate::testing::be16;
+    let mut c = testing::ClassFile::new("com/example/Codes", Some("java/lang/Object"));
+    let field = |c: &mut testing::ClassFile, name: &str, descriptor: &str| { let r = c.fieldref("com/example/Codes", name, descriptor); be16(r) };
+    let (shift, doubled, name, mask, unknown, branchy, late, mutable) = (
+        field(&mut c, "SHIFT", "I"), field(&mut c, "DOUBLED", "I"), field(&mut c, "NAME", "Ljava/lang/String;"), field(&mut c, "MASK", "J"),
+        field(&mut c, "UNKNOWN", "I"), field(&mut c, "BRANCHY", "I"), field(&mut c, "LATE", "I"), field(&mut c, "MUTABLE", "I"),
+    );
+    let other   = be16(c.fieldref("com/example/Other", "VALUE", "I"));
+    let string  = c.string("codes") as u8;
+    let long    = be16(c.long(0xFF));
+    let call    = be16(c.methodref("com/example/Codes", "compute", "(I)I"));
+    let bytecode = [
+        vec![0x04, 0x10, 10, 0x78, 0xB3], shift.clone(),        // SHIFT = 1 << 10
+        vec![0xB2], shift, vec![0x05, 0x68, 0xB3], doubled,     // DOUBLED = SHIFT * 2
+        vec![0x12, string, 0xB3], name,                         // NAME = "codes"
+        vec![0x14], long, vec![0x08, 0x79, 0xB3], mask,         // MASK = 0xFFL << 5
+        vec![0xB2], other, vec![0xB8], call, vec![0xB3], unknown.clone(), // UNKNOWN = compute(Other.VALUE)
+        vec![0x06, 0xB3], branchy.clone(),                      // BRANCHY = 3
+        vec![0x08, 0xB3], mutable,                              // MUTABLE = 5 (not final)
+        vec![0x03, 0x99, 0x00, 0x06],                           // if (0 == 0)
+        vec![0x07, 0xB3], branchy,                              //   BRANCHY = 4
+        vec![0x07, 0xB3], late,                                 // LATE = 4
+        vec![0xB1],                                             // return
+    ].concat();
+    let code = [be16(2), be16(0), testing::be32(bytecode.len() as u32), bytecode, be16(0), be16(0)].concat();
+    let code = c.attribute("Code", &code);
+    let constant = be16(c.integer(7));
+    let constant = c.attribute("ConstantValue", &constant);
+    for field in ["SHIFT", "DOUBLED", "UNKNOWN", "BRANCHY", "LATE"].iter() { c.field(0x0019, field, "I", &[]); }
+    c.field(0x0019, "NAME", "Ljava/lang/String;", &[]);
+    c.field(0x0019, "MASK", "J", &[]);
+    c.field(0x0019, "SEVEN", "I", &[constant]);
+    c.field(0x0009, "MUTABLE", "I", &[]);
+    c.method(0x0008, "<clinit>", "()V", &[code]);
+    let bytes = c.to_bytes();
+
+    let mut options = ParseOptions::new();
+    options.retain_code(true);
+    let constants = Class::read_with(&mut &bytes[..], &options).unwrap().static_constants().unwrap();
+    assert_eq!(constants.keys().map(|k| k.as_str()).collect::<Vec<_>>(), &["DOUBLED", "MASK", "NAME", "SEVEN", "SHIFT"]);
+    assert_eq!(constants["SHIFT"],      field::Constant::Integer(1024));
+    assert_eq!(constants["DOUBLED"],    field::Constant::Integer(2048));
+    assert_eq!(constants["NAME"],       field::Constant::String(Some("codes".to_owned())));
+    assert_eq!(constants["MASK"],       field::Constant::Long(0xFF << 5));
+    assert_eq!(constants["SEVEN"],      field::Constant::Integer(7));
+
+    let without_code = Class::read(&mut &bytes[..]).unwrap().static_constants().unwrap();
+    assert_eq!(without_code.keys().map(|k| k.as_str()).collect::<Vec<_>>(), &["SEVEN"]);
+}
+
+#[test] fn warnings() {
+    let mut c = testing::ClassFile::new("com/example/Foo", Some("java/lang/Object"));
+    let signature = testing::be16(c.utf8("Ljava/lang/Object;"));
+    c.class_attribute("Signature", &signature);
+    c.class_attribute("Signature", &signature);
+    c.class_attribute("ScalaSig", &[]);
+    c.class_attribute("ScalaSig", &[]);
+    let deprecated = c.attribute("Deprecated", &[1, 2, 3]);
+    c.method(0x0001, "run", "()V", &[deprecated]);
+    let synthetic = c.attribute("Synthetic", &[]);
+    c.field(0x0001, "count", "I", &[synthetic.clone(), synthetic]);
+    let class = c.read();
+
+    assert!(class.methods[0].deprecated);
+    assert_eq!(class.warnings(), &[
+        Warning { member: Some("count:I".to_owned()), kind: WarningKind::DuplicateAttribute("Synthetic".to_owned()) },
+        Warning { member: Some("run()V".to_owned()),  kind: WarningKind::NonEmptyAttribute { name: "Deprecated".to_owned(), length: 3 } },
+        Warning { member: None,                       kind: WarningKind::DuplicateAttribute("Signature".to_owned()) },
+    ]);
+    assert_eq!(class.warnings()[1].to_string(), "run()V: Deprecated attribute has 3 unexpected bytes");
+    assert_eq!(class.warnings()[2].to_string(), "duplicate Signature attribute");
+}
+
+#[test] fn duplicate_attributes() {
+    let mut c = testing::ClassFile::new("com/example/Foo", Some("java/lang/Object"));
+    let first  = testing::be16(c.utf8("Lcom/example/First;"));
+    let second = testing::be16(c.utf8("Lcom/example/Second;"));
+    c.class_attribute("Signature", &first);
+    c.class_attribute("Signature", &second);
+    let bytes = c.to_bytes();
+    let read = |policy| { let mut options = ParseOptions::new(); options.duplicate_attributes(policy); Class::read_with(&mut &bytes[..], &options) };
+
+    let warn = read(DuplicateAttributes::Warn).unwrap();
+    assert_eq!(warn.signature_str(), Some("Lcom/example/Second;"));
+    assert_eq!(warn.warnings().len(), 1);
+
+    let first_wins = read(DuplicateAttributes::FirstWins).unwrap();
+    assert_eq!(first_wins.signature_str(), Some("Lcom/example/First;"));
+    assert_eq!(first_wins.warnings(), warn.warnings());
+    assert_eq!(first_wins.attribute_names().collect::<Vec<_>>(), &["Signature", "Signature"]);
+    assert!(first_wins.unknown_attribute_names().next().is_none());
+
+    assert!(read(DuplicateAttributes::Error).is_err());
+}
+
+#[test] fn oversized_attribute_length() {
+    let read = |name: &str| {
+        let mut c = testing::ClassFile::new("com/example/Foo", Some("java/lang/Object"));
+        c.class_attribute(name, &[0, 0]);
+        let mut bytes = c.to_bytes();
+        let length = bytes.len() - 6; // attribute_length, followed by the 2 bytes of info that actually remain
+        bytes[length..length+4].copy_from_slice(&testing::be32(0xFFFF_FFF0));
+        let mut options = ParseOptions::new();
+        options.register_attribute("com.example.Custom", |info, _| Ok(info.len()));
+        Class::read_with(&mut &bytes[..], &options)
+    };
+
+    for name in ["InnerClasses", "Record", "BootstrapMethods", "MethodParameters", "SourceDebugExtension", "com.example.Custom", "ScalaSig"].iter() {
+        assert_eq!(read(name).unwrap_err().kind(), std::io::ErrorKind::InvalidData, "{}", name);
+    }
+    assert_eq!(read("Deprecated").unwrap_err().kind(), std::io::ErrorKind::UnexpectedEof);
+}
@@ -0,0 +1,244 @@
+//! An ordered list of [Source]s, searched in order like a JVM `-classpath`.
+//!
+//! [Source]:   ../struct.Source.html
+
+use crate::{dedup, module, names, CancellationToken, Class, Source};
+#[cfg(feature = "writer")] use crate::jar;
+#[cfg(all(test, feature = "jar"))] use crate::{testing, Hierarchy};
+
+use std::collections::*;
+use std::io::{self, Error, ErrorKind};
+#[cfg(feature = "writer")] use std::path::Path;
+
+
+
+/// An ordered list of [Source]s.  Earlier sources shadow classes of the same name in later sources.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use jreflection::{ClassPath, Source};
+/// # #[cfg(feature = "jar")] {
+/// let mut classpath = ClassPath::new();
+/// classpath.push(Source::from_jar("app.jar").unwrap());
+/// classpath.push(Source::from_jdk_dir(r"C:\Program Files\AdoptOpenJDK\jdk-8.0.232.09-hotspot").unwrap());
+/// let object = classpath.read_class("java/lang/Object").unwrap();
+/// # }
+/// ```
+///
+/// [Source]:   struct.Source.html
+#[derive(Default)]
+pub struct ClassPath {
+    sources: Vec<Source>,
+    modules: Option<module::ModuleGraph>,
+}
+
+/// The classes transitively reachable from a set of root classes.  See [ClassPath::closure](struct.ClassPath.html#method.closure).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Closure {
+    /// The reachable classes found on the classpath, including the roots.
+    pub classes:    BTreeSet<String>,
+
+    /// The reachable classes that couldn't be found on the classpath (typically platform classes, if the JDK isn't on the classpath.)
+    pub missing:    BTreeSet<String>,
+}
+
+impl Closure {
+    /// Copy the jar at `input` to `output`, omitting any classes not in this closure.  See [jar::trim](../jar/fn.trim.html).
+    #[cfg(feature = "writer")]
+    pub fn write_trimmed_jar(&self, input: impl AsRef<Path>, output: impl AsRef<Path>) -> io::Result<jar::TrimStats> {
+        jar::trim(input, output, &self.classes)
+    }
+}
+
+impl ClassPath {
+    /// Create an empty classpath.
+    pub fn new() -> Self { Self::default() }
+
+    /// Create a classpath searching `sources` in order.
+    pub fn from_sources(sources: Vec<Source>) -> Self { Self { sources, modules: None } }
+
+    /// Append a source to the end of the classpath, to be searched after all existing sources.
+    pub fn push(&mut self, source: Source) { self.sources.push(source); }
+
+    /// Abort any operation reading from this classpath's sources once `token` is cancelled.  See [CancellationToken].
+    /// Sources [push](#method.push)ed afterwards aren't affected.
+    ///
+    /// [CancellationToken]:    ../struct.CancellationToken.html
+    pub fn set_cancellation(&mut self, token: CancellationToken) {
+        for source in self.sources.iter_mut() { source.set_cancellation(token.clone()); }
+    }
+
+    /// The sources of this classpath, in search order.
+    pub fn sources(&self) -> &[Source] { &self.sources[..] }
+
+    /// Find the modules of this classpath's sources (see [ModuleGraph::from_sources]), making [is_exported] honor
+    /// JPMS exports as the JDK 9+ runtime does.  Sources [push](#method.push)ed afterwards aren't included.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use jreflection::*;
+    /// let mut classpath = ClassPath::from_sources(vec![Source::from_jdk_dir(r"C:\Program Files\AdoptOpenJDK\jdk-11.0.6.10-hotspot").unwrap()]);
+    /// assert!(classpath.is_exported("sun/misc/Unsafe", None)); // Not module aware yet
+    /// classpath.enable_module_awareness().unwrap();
+    /// assert!(classpath.is_exported("sun/misc/Unsafe", None)); // jdk.unsupported exports sun.misc
+    /// assert!(!classpath.is_exported("jdk/internal/misc/Unsafe", None));
+    /// ```
+    ///
+    /// [ModuleGraph::from_sources]:    module/struct.ModuleGraph.html#method.from_sources
+    /// [is_exported]:                  #method.is_exported
+    pub fn enable_module_awareness(&mut self) -> io::Result<()> {
+        self.modules = Some(module::ModuleGraph::from_sources(&self.sources[..])?);
+        Ok(())
+    }
+
+    /// The modules of this classpath, if [enable_module_awareness](#method.enable_module_awareness) was called.
+    pub fn modules(&self) -> Option<&module::ModuleGraph> { self.modules.as_ref() }
+
+    /// Is the package of `class` exported to code in `module` (`None` for the classpath's unnamed module)?  Always
+    /// true unless [enable_module_awareness](#method.enable_module_awareness) was called.
+    /// See [ModuleGraph::is_exported](module/struct.ModuleGraph.html#method.is_exported).
+    pub fn is_exported(&self, class: &str, module: Option<&str>) -> bool {
+        self.modules.as_ref().map(|m| m.is_exported(class, module)).unwrap_or(true)
+    }
+
+    /// Read class metadata given a JNI path, from the first source containing the class.
+    ///
+    /// Returns an error of kind `NotFound` if no source contains the class.
+    pub fn read_class(&self, path: impl AsRef<str>) -> io::Result<Class> {
+        let path = path.as_ref();
+        let mut first_error = None;
+        for source in self.sources.iter() {
+            match source.read_class(path) {
+                Ok(class) => return Ok(class),
+                Err(ref err) if err.kind() == ErrorKind::NotFound => {},
+                Err(err) => { if first_error.is_none() { first_error = Some(err); } },
+            }
+        }
+        Err(first_error.unwrap_or_else(|| Error::new(ErrorKind::NotFound, format!("No such class on the classpath: {}", path))))
+    }
+
+    /// Enumerate all classes contained within this classpath.  Shadowed classes are only visited once.
+    pub fn for_each_class(&self, mut f: impl FnMut(String) -> io::Result<()>) -> io::Result<()> {
+        let mut visited = BTreeSet::new();
+        for source in self.sources.iter() {
+            source.for_each_class(|class|{
+                if visited.insert(class.clone()) { f(class) } else { Ok(()) }
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Index every class of this classpath by [simple name](names/fn.simple_name.html), to resolve unqualified names
+    /// such as `"ArrayList"`.  See [SimpleNameIndex](names/struct.SimpleNameIndex.html).
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use jreflection::*;
+    /// let classpath = ClassPath::from_sources(vec![Source::from_jdk_dir(r"C:\Program Files\AdoptOpenJDK\jdk-8.0.232.09-hotspot").unwrap()]);
+    /// let index = classpath.simple_name_index().unwrap();
+    /// assert_eq!(index.resolve("ArrayList").unique(), Some("java/util/ArrayList"));
+    /// for (name, paths) in index.ambiguous() {
+    ///     println!("{} is ambiguous: {:?}", name, paths);
+    /// }
+    /// ```
+    pub fn simple_name_index(&self) -> io::Result<names::SimpleNameIndex> {
+        let mut index = names::SimpleNameIndex::new();
+        self.for_each_class(|class|{ index.insert(&class); Ok(()) })?;
+        Ok(index)
+    }
+
+    /// Hash every copy of every class in this classpath - including shadowed copies - to find byte-identical and differing duplicates.
+    pub fn dedup(&self) -> io::Result<dedup::Dedup> { dedup::Dedup::scan(&self.sources[..]) }
+
+    /// Compute the transitive set of classes reachable from `roots` via [Class::referenced_classes].
+    ///
+    /// This is the analysis half of a jar shrinker.  It's conservative in some ways (every referenced class is kept,
+    /// even if only referenced by dead code) and optimistic in others (reflection and `Class.forName` aren't visible.)
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use jreflection::*;
+    /// # #[cfg(feature = "jar")] {
+    /// let classpath = ClassPath::from_sources(vec![Source::from_jar("app.jar").unwrap()]);
+    /// let closure = classpath.closure(&["com/example/Main"]).unwrap();
+    /// println!("{} classes reachable", closure.classes.len());
+    /// # }
+    /// ```
+    ///
+    /// [Class::referenced_classes]:    struct.Class.html#method.referenced_classes
+    pub fn closure(&self, roots: &[impl AsRef<str>]) -> io::Result<Closure> {
+        let mut closure = Closure::default();
+        let mut pending = roots.iter().map(|r| r.as_ref().to_owned()).collect::<Vec<_>>();
+        while let Some(path) = pending.pop() {
+            if closure.classes.contains(&path) || closure.missing.contains(&path) { continue; }
+            match self.read_class(path.as_str()) {
+                Ok(class) => {
+                    pending.extend(class.referenced_classes().into_iter().filter(|c| !closure.classes.contains(c) && !closure.missing.contains(c)));
+                    closure.classes.insert(path);
+                },
+                Err(ref err) if err.kind() == ErrorKind::NotFound => { closure.missing.insert(path); },
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(closure)
+    }
+
+    /// Collect all classes contained within this classpath.  Shadowed classes are only collected once.
+    pub fn classes<C: Default + Extend<String>>(&self) -> io::Result<C> {
+        let mut collection = C::default();
+        self.for_each_class(|class|{
+            collection.extend(Some(class));
+            Ok(())
+        })?;
+        Ok(collection)
+    }
+}
+
+#[cfg(feature = "jar")]
+#[test] fn closure() {
+    let class = |this: &str, refs: &[&str]| {
+        let mut c = testing::ClassFile::new(this, Some("java/lang/Object"));
+        for r in refs.iter() { c.class(r); }
+        (format!("{}.class", this), c.to_bytes())
+    };
+    let (main, main_bytes)      = class("com/example/Main",     &["com/example/Used", "[Lcom/example/Element;"]);
+    let (used, used_bytes)      = class("com/example/Used",     &["com/example/Main"]);
+    let (elem, elem_bytes)      = class("com/example/Element",  &[]);
+    let (unused, unused_bytes)  = class("com/example/Unused",   &["com/example/Used"]);
+    let jar = testing::write_jar("classpath-closure", &[(&main, main_bytes), (&used, used_bytes), (&elem, elem_bytes), (&unused, unused_bytes)]);
+
+    let classpath = ClassPath::from_sources(vec![Source::from_jar(&jar).unwrap()]);
+    let closure = classpath.closure(&["com/example/Main"]).unwrap();
+    assert_eq!(closure.classes.iter().map(|c| c.as_str()).collect::<Vec<_>>(), &["com/example/Element", "com/example/Main", "com/example/Used"]);
+    assert_eq!(closure.missing.iter().map(|c| c.as_str()).collect::<Vec<_>>(), &["java/lang/Object"]);
+
+    let index = classpath.simple_name_index().unwrap();
+    assert_eq!(index.len(), 4);
+    assert_eq!(index.resolve("Used").unique(), Some("com/example/Used"));
+    assert_eq!(index.ambiguous().count(), 0);
+}
+
+#[cfg(feature = "jar")]
+#[test] fn module_awareness() {
+    let info = module::module_info("com.example", &[("com/example/api", &[])], &[]);
+    let api         = testing::ClassFile::new("com/example/api/Api", Some("java/lang/Object")).to_bytes();
+    let internal    = testing::ClassFile::new("com/example/internal/Impl", Some("java/lang/Object")).to_bytes();
+    let jar = testing::write_jar("classpath-module-awareness", &[("module-info.class", info), ("com/example/api/Api.class", api), ("com/example/internal/Impl.class", internal)]);
+
+    let mut classpath = ClassPath::from_sources(vec![Source::from_jar(&jar).unwrap()]);
+    assert!(classpath.modules().is_none());
+    assert!(classpath.is_exported("com/example/internal/Impl", None));
+
+    classpath.enable_module_awareness().unwrap();
+    assert!( classpath.is_exported("com/example/api/Api", None));
+    assert!(!classpath.is_exported("com/example/internal/Impl", None));
+
+    let hierarchy = Hierarchy::new(&classpath);
+    let internal = hierarchy.class("com/example/internal/Impl").unwrap().unwrap();
+    assert!(!hierarchy.is_accessible(&internal, None));
+    assert!( hierarchy.is_accessible(&internal, Some("com.example")));
+}
@@ -0,0 +1,722 @@
+//! [Java SE 7 &sect; 4.7.3](https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.7.3):  Method bytecode, and the references it makes.
+//!
+//! Code is only retained if requested via [ParseOptions::retain_code](../struct.ParseOptions.html#method.retain_code).
+
+use crate::{attributes, field, method, stack_map, CustomAttribute, ParseOptions};
+use crate::constants::{Constant, Constants};
+use crate::io::be::*;
+#[cfg(feature = "annotations")] use crate::annotation;
+#[cfg(test)] use crate::{testing, Class};
+
+use std::collections::*;
+use std::fmt::{self, Debug, Formatter};
+use std::io::{self, Read};
+use std::sync::Arc;
+
+pub use crate::attributes::RawAttribute;
+
+
+
+/// [Java SE 7 &sect; 4.7.3](https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.7.3):  A method's Code attribute.
+#[derive(Clone)]
+pub struct Code {
+    /// The maximum depth of the operand stack.
+    pub max_stack:          u16,
+
+    /// The number of local variable slots, including parameters.
+    pub max_locals:         u16,
+
+    /// The raw bytecode.  See [instructions](#method.instructions).
+    pub bytecode:           Vec<u8>,
+
+    /// The exception handlers, in the order they're searched.
+    pub exception_table:    Vec<ExceptionHandler>,
+
+    /// The attributes of the code itself, such as `LineNumberTable`, `LocalVariableTable`, or `StackMapTable`, undecoded.
+    pub attributes:         Vec<RawAttribute>,
+
+    constants:              Arc<Constants>,
+    custom_attributes:      Vec<CustomAttribute>,
+}
+
+/// [Java SE 7 &sect; 4.7.3](https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.7.3):  An `exception_table` entry.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ExceptionHandler {
+    pub start_pc:   u16,
+    pub end_pc:     u16,
+    pub handler_pc: u16,
+
+    /// The constant pool index of the caught class, or 0 for `finally` handlers.
+    pub catch_type: u16,
+
+    _non_exhaustive: (),
+}
+
+/// [Java SE 8 &sect; 4.7.12](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-4.html#jvms-4.7.12):  A `LineNumberTable` entry.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LineNumber {
+    /// The bytecode offset the line starts at.
+    pub start_pc:       u16,
+
+    /// The source line number, starting at 1.
+    pub line_number:    u16,
+
+    _non_exhaustive: (),
+}
+
+/// [Java SE 8 &sect; 4.7.13](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-4.html#jvms-4.7.13):  A `LocalVariableTable` entry.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LocalVariable {
+    /// The variable is live from bytecode offset `start_pc`...
+    pub start_pc:   u16,
+
+    /// ...for `length` bytes.
+    pub length:     u16,
+
+    /// The name of the variable, such as `"key"`.
+    pub name:       String,
+
+    /// The field descriptor of the variable's type, such as `"Ljava/lang/String;"`.
+    pub descriptor: String,
+
+    /// The local variable slot of the variable.  `long`s and `double`s also use slot `index + 1`.
+    pub index:      u16,
+
+    _non_exhaustive: (),
+}
+
+impl ExceptionHandler {
+    /// Create a handler of `catch_type` (or 0 for `finally`) for `start_pc..end_pc`, jumping to `handler_pc`.
+    pub fn new(start_pc: u16, end_pc: u16, handler_pc: u16, catch_type: u16) -> Self {
+        Self { start_pc, end_pc, handler_pc, catch_type, _non_exhaustive: () }
+    }
+}
+
+impl LineNumber {
+    /// Create an entry for `line_number`, starting at bytecode offset `start_pc`.
+    pub fn new(start_pc: u16, line_number: u16) -> Self { Self { start_pc, line_number, _non_exhaustive: () } }
+}
+
+impl LocalVariable {
+    /// Create an entry for the variable `name` in slot `index`, live from `start_pc` for `length` bytes.
+    pub fn new(start_pc: u16, length: u16, name: String, descriptor: String, index: u16) -> Self {
+        Self { start_pc, length, name, descriptor, index, _non_exhaustive: () }
+    }
+}
+
+/// A single decoded instruction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Instruction<'a> {
+    /// The offset of this instruction from the start of the bytecode.
+    pub offset:     u32,
+
+    /// The opcode, such as `0xB6` for `invokevirtual`.  For `wide` instructions, this is `0xC4`.
+    pub opcode:     u8,
+
+    /// The bytes following the opcode, including any `tableswitch`/`lookupswitch` padding.
+    pub operands:   &'a [u8],
+}
+
+/// An iterator over the [Instruction]s of some bytecode.  See [Code::instructions](struct.Code.html#method.instructions).
+///
+/// [Instruction]:  struct.Instruction.html
+pub struct Instructions<'a> {
+    bytecode:   &'a [u8],
+    offset:     usize,
+}
+
+/// A class, field, or method referenced by bytecode.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Reference {
+    /// A class referenced by `new`, `checkcast`, `instanceof`, `anewarray`, `multianewarray`, or `ldc`.
+    Class(String),
+
+    /// A field referenced by `getfield`, `putfield`, `getstatic`, or `putstatic`.
+    Field { class: String, name: String, descriptor: String },
+
+    /// A method referenced by `invokevirtual`, `invokespecial`, `invokestatic`, or `invokeinterface`.
+    Method { class: String, name: String, descriptor: String },
+}
+
+// Omits the class's constant pool, which every method's code shares
+impl Debug for Code {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        fmt.debug_struct("Code")
+            .field("max_stack",         &self.max_stack)
+            .field("max_locals",        &self.max_locals)
+            .field("bytecode",          &self.bytecode)
+            .field("exception_table",   &self.exception_table)
+            .field("attributes",        &self.attributes)
+            .field("custom_attributes", &self.custom_attributes)
+            .finish()
+    }
+}
+
+impl Code {
+    pub(crate) fn read(read: &mut impl Read, constants: &Arc<Constants>, options: &ParseOptions) -> io::Result<Self> {
+        let max_stack   = read_u2(read)?;
+        let max_locals  = read_u2(read)?;
+        let code_length = read_u4(read)? as usize;
+        io_assert!(code_length < 65536, "Code attribute has code_length {}, expected < 65536", code_length);
+        let mut bytecode = vec![0u8; code_length];
+        read.read_exact(&mut bytecode[..])?;
+
+        let exception_table_length = read_u2(read)? as usize;
+        let mut exception_table = Vec::with_capacity(exception_table_length);
+        for _ in 0..exception_table_length {
+            exception_table.push(ExceptionHandler {
+                start_pc:   read_u2(read)?,
+                end_pc:     read_u2(read)?,
+                handler_pc: read_u2(read)?,
+                catch_type: read_u2(read)?,
+                _non_exhaustive: (),
+            });
+        }
+
+        let attributes_count = read_u2(read)?;
+        let mut attributes = Vec::with_capacity(attributes_count.into());
+        let mut custom_attributes = Vec::new();
+        for _ in 0..attributes_count {
+            let name = constants.get_utf8(read_u2(read)?)?.to_owned();
+            let attribute_length = read_u4(read)? as usize;
+            let info = attributes::read_attribute_info(read, &name, attribute_length)?;
+            if let Some(custom) = options.decode_attribute(&name, &info[..], constants) { custom_attributes.push(custom?); }
+            attributes.push(RawAttribute { name, info });
+        }
+
+        Ok(Self { max_stack, max_locals, bytecode, exception_table, attributes, constants: constants.clone(), custom_attributes })
+    }
+
+    /// The attributes of this code decoded by decoders registered with [ParseOptions::register_attribute](../struct.ParseOptions.html#method.register_attribute).
+    /// They're also included in [attributes](#structfield.attributes), undecoded.
+    pub fn custom_attributes(&self) -> &[CustomAttribute] { &self.custom_attributes[..] }
+
+    /// The first undecoded attribute named `name`, if any.
+    pub fn attribute(&self, name: &str) -> Option<&RawAttribute> { self.attributes.iter().find(|a| a.name == name) }
+
+    /// The annotations on type uses within this code (JSR 308), such as local variable types and casts, visible then
+    /// invisible at runtime.  See [TypeAnnotation](../annotation/struct.TypeAnnotation.html).  Requires the `annotations`
+    /// feature.
+    #[cfg(feature = "annotations")]
+    pub fn type_annotations(&self) -> io::Result<Vec<annotation::TypeAnnotation>> {
+        let mut annotations = Vec::new();
+        for name in ["RuntimeVisibleTypeAnnotations", "RuntimeInvisibleTypeAnnotations"].iter() {
+            for attribute in self.attributes.iter().filter(|a| a.name == *name) {
+                let mut info = &attribute.info[..];
+                annotations.extend(annotation::TypeAnnotation::read_list(&mut info, &self.constants)?);
+                io_assert!(info.is_empty(), "{} attribute has {} trailing bytes", name, info.len());
+            }
+        }
+        Ok(annotations)
+    }
+
+    /// The frames of this code's `StackMapTable` (present from classfile version 50 on, for code with branches or
+    /// exception handlers), in order.  See [stack_map](../stack_map/index.html).
+    pub fn stack_map_frames(&self) -> io::Result<Vec<stack_map::Frame>> {
+        match self.attribute("StackMapTable") {
+            None            => Ok(Vec::new()),
+            Some(attribute) => {
+                let mut info = &attribute.info[..];
+                let frames = stack_map::Frame::read_table(&mut info, &self.constants, self.bytecode.len())?;
+                io_assert!(info.is_empty(), "StackMapTable attribute has {} trailing bytes", info.len());
+                Ok(frames)
+            },
+        }
+    }
+
+    /// The entries of this code's `LineNumberTable`s (present unless compiled with `javac -g:none`), sorted by `start_pc`.
+    pub fn line_numbers(&self) -> io::Result<Vec<LineNumber>> {
+        let mut lines = Vec::new();
+        for attribute in self.attributes.iter().filter(|a| a.name == "LineNumberTable") {
+            let mut info = &attribute.info[..];
+            let count = read_u2(&mut info)?;
+            for _ in 0..count {
+                let start_pc = read_u2(&mut info)?;
+                io_assert!(usize::from(start_pc) < self.bytecode.len(), "LineNumberTable start_pc {} is past the end of {} bytes of code", start_pc, self.bytecode.len());
+                lines.push(LineNumber::new(start_pc, read_u2(&mut info)?));
+            }
+            io_assert!(info.is_empty(), "LineNumberTable attribute has {} trailing bytes", info.len());
+        }
+        lines.sort(); // Multiple tables, or entries, can be in any order
+        Ok(lines)
+    }
+
+    /// The source line of the instruction at bytecode offset `pc`, if known:  the line of the last entry of the
+    /// [line_numbers](#method.line_numbers) starting at or before `pc`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use jreflection::*;
+    /// # #[cfg(feature = "jar")] {
+    /// # let src = Source::from_jar("app.jar").unwrap();
+    /// let mut options = ParseOptions::new();
+    /// options.retain_code(true);
+    /// let class = src.read_class_with("com/example/Main", &options).unwrap();
+    /// for method in class.methods.iter() {
+    ///     let code = match method.code() { Some(code) => code, None => continue };
+    ///     for instruction in code.instructions() {
+    ///         let instruction = instruction.unwrap();
+    ///         if instruction.opcode == 0xBA {
+    ///             println!("invokedynamic in {} at line {:?}", method.name, code.line_of(instruction.offset).unwrap());
+    ///         }
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    pub fn line_of(&self, pc: u32) -> io::Result<Option<u16>> {
+        if pc as usize >= self.bytecode.len() { return Ok(None); }
+        Ok(self.line_numbers()?.iter().rev().find(|l| u32::from(l.start_pc) <= pc).map(|l| l.line_number))
+    }
+
+    /// The entries of this code's `LocalVariableTable`s, present in classes compiled with `javac -g`.
+    pub fn local_variables(&self) -> io::Result<Vec<LocalVariable>> {
+        let mut variables = Vec::new();
+        for attribute in self.attributes.iter().filter(|a| a.name == "LocalVariableTable") {
+            let mut info = &attribute.info[..];
+            let count = read_u2(&mut info)?;
+            for _ in 0..count {
+                variables.push(LocalVariable {
+                    start_pc:   read_u2(&mut info)?,
+                    length:     read_u2(&mut info)?,
+                    name:       self.constants.get_utf8(read_u2(&mut info)?)?.to_owned(),
+                    descriptor: self.constants.get_utf8(read_u2(&mut info)?)?.to_owned(),
+                    index:      read_u2(&mut info)?,
+                    _non_exhaustive: (),
+                });
+            }
+            io_assert!(info.is_empty(), "LocalVariableTable attribute has {} trailing bytes", info.len());
+        }
+        Ok(variables)
+    }
+
+    /// The names of the parameters of `descriptor` according to [local_variables](#method.local_variables):  the
+    /// variables live from offset 0 in each parameter's slot.
+    pub(crate) fn parameter_names(&self, is_static: bool, descriptor: &method::Descriptor) -> io::Result<Vec<Option<String>>> {
+        let variables = self.local_variables()?;
+        let mut slot = if is_static { 0 } else { 1 };
+        let mut names = Vec::new();
+        for parameter in descriptor.arguments() {
+            names.push(variables.iter().find(|v| v.start_pc == 0 && v.index == slot).map(|v| v.name.clone()));
+            slot += match parameter { field::Descriptor::Single(field::BasicType::Long) | field::Descriptor::Single(field::BasicType::Double) => 2, _ => 1 };
+        }
+        Ok(names)
+    }
+
+    /// The constant pool of the class this code belongs to.
+    pub fn constants(&self) -> &Constants { &self.constants }
+
+    /// Iterate over the instructions of this code.
+    pub fn instructions(&self) -> Instructions<'_> { Instructions { bytecode: &self.bytecode[..], offset: 0 } }
+
+    /// Every class, field, and method referenced by this code, in order, with duplicates.
+    ///
+    /// `invokedynamic` call sites (lambdas, string concatenation, etc.) aren't included.
+    pub fn references(&self) -> io::Result<Vec<Reference>> {
+        let mut references = Vec::new();
+        for instruction in self.instructions() {
+            let instruction = instruction?;
+            let index = |len: usize| -> io::Result<u16> {
+                match instruction.operands.len() {
+                    0 => io_data_err!("Expected a constant index for opcode 0x{:02X}", instruction.opcode),
+                    _ if len == 1 => Ok(u16::from(instruction.operands[0])),
+                    _ => Ok(u16::from_be_bytes([instruction.operands[0], instruction.operands[1]])),
+                }
+            };
+            match instruction.opcode {
+                0x12 | 0x13 => { // ldc, ldc_w
+                    let index = index(if instruction.opcode == 0x12 { 1 } else { 2 })?;
+                    if let Constant::Class { .. } = self.constants.get(index)? {
+                        references.push(Reference::Class(self.class_name(index)?));
+                    }
+                },
+                0xB2 ..= 0xB5 => {
+                    let (class, name, descriptor) = self.member_ref(index(2)?)?;
+                    references.push(Reference::Field { class, name, descriptor });
+                },
+                0xB6 ..= 0xB9 => {
+                    let (class, name, descriptor) = self.member_ref(index(2)?)?;
+                    references.push(Reference::Method { class, name, descriptor });
+                },
+                0xBB | 0xBD | 0xC0 | 0xC1 | 0xC5 => references.push(Reference::Class(self.class_name(index(2)?)?)),
+                _ => {},
+            }
+        }
+        Ok(references)
+    }
+
+    /// Fold the constant values the start of this (`<clinit>`) code assigns to static fields of `class` into `values`.
+    ///
+    /// Only straight-line code is evaluated:  constants, simple `int` / `long` / `float` / `double` arithmetic and
+    /// conversions, and static fields of `class` already in `values`.  Calls, allocations, local variables, etc. are
+    /// tracked as unknown values.  Evaluation stops at the first branch or unsupported instruction, after which any
+    /// field of `class` that might still be assigned is removed from `values`.  Fields assigned unknown values are
+    /// also removed.
+    pub(crate) fn fold_static_assignments(&self, class: &str, values: &mut BTreeMap<String, field::Constant>) -> io::Result<()> {
+        use crate::field::Constant as C;
+        let mut stack = Vec::<Option<C>>::new();
+        let mut instructions = self.instructions();
+        for instruction in instructions.by_ref() {
+            let instruction = instruction?;
+            let (opcode, ops) = (instruction.opcode, instruction.operands);
+            let u2 = || u16::from_be_bytes([ops[0], ops[1]]);
+            match opcode {
+                0x00                => {}, // nop
+                0x01                => stack.push(None), // aconst_null
+                0x02 ..= 0x08       => stack.push(Some(C::Integer(i32::from(opcode) - 0x03))),
+                0x09 | 0x0A         => stack.push(Some(C::Long(i64::from(opcode) - 0x09))),
+                0x0B ..= 0x0D       => stack.push(Some(C::Float(f32::from(opcode - 0x0B)))),
+                0x0E | 0x0F         => stack.push(Some(C::Double(f64::from(opcode - 0x0E)))),
+                0x10                => stack.push(Some(C::Integer(i32::from(ops[0] as i8)))),
+                0x11                => stack.push(Some(C::Integer(i32::from(u2() as i16)))),
+                0x12                => stack.push(self.ldc(u16::from(ops[0]))?),
+                0x13 | 0x14         => stack.push(self.ldc(u2())?),
+                0x15 ..= 0x2D       => stack.push(None), // loads
+                0x2E ..= 0x35       => { pop(&mut stack, 2); stack.push(None); }, // array loads
+                0x36 ..= 0x4E       => pop(&mut stack, 1), // stores
+                0x4F ..= 0x56       => pop(&mut stack, 3), // array stores
+                0x57                => pop(&mut stack, 1), // pop
+                0x59                => { let top = stack.last().cloned().unwrap_or(None); stack.push(top); }, // dup
+                0x60 ..= 0x73 | 0x78 ..= 0x83 => {
+                    let b = stack.pop().unwrap_or(None);
+                    let a = stack.pop().unwrap_or(None);
+                    stack.push(match (a, b) { (Some(a), Some(b)) => binary(opcode, a, b), _ => None });
+                },
+                0x74 ..= 0x77 | 0x85 ..= 0x93 => {
+                    let a = stack.pop().unwrap_or(None);
+                    stack.push(a.and_then(|a| unary(opcode, a)));
+                },
+                0xB2 | 0xB3         => {
+                    let (owner, name, _) = self.member_ref(u2())?;
+                    let own = owner == class;
+                    if opcode == 0xB2 {
+                        stack.push(if own { values.get(&name).cloned() } else { None });
+                    } else {
+                        match stack.pop().unwrap_or(None) {
+                            Some(value) if own  => { values.insert(name, value); },
+                            None if own         => { values.remove(&name); },
+                            _                   => {},
+                        }
+                    }
+                },
+                0xB4                => { pop(&mut stack, 1); stack.push(None); }, // getfield
+                0xB5                => pop(&mut stack, 2), // putfield
+                0xB6 ..= 0xBA       => {
+                    let descriptor = if opcode == 0xBA { self.invoke_dynamic_descriptor(u2())? } else { self.member_ref(u2())?.2 };
+                    let descriptor = method::Descriptor::new(descriptor.as_str())?;
+                    let receiver = if opcode == 0xB8 || opcode == 0xBA { 0 } else { 1 };
+                    pop(&mut stack, descriptor.arguments().count() + receiver);
+                    if descriptor.return_type() != method::Type::Single(field::BasicType::Void) { stack.push(None); }
+                },
+                0xBB                => stack.push(None), // new
+                0xBC | 0xBD         => { pop(&mut stack, 1); stack.push(None); }, // newarray, anewarray
+                0xC0                => {}, // checkcast
+                0xC5                => { pop(&mut stack, usize::from(ops[2])); stack.push(None); }, // multianewarray
+                0xB1                => return Ok(()), // return
+                _                   => break,
+            }
+        }
+
+        // Anything after a branch might (or might not) run
+        for instruction in instructions {
+            let instruction = instruction?;
+            if instruction.opcode != 0xB3 { continue; }
+            let (owner, name, _) = self.member_ref(u16::from_be_bytes([instruction.operands[0], instruction.operands[1]]))?;
+            if owner == class { values.remove(&name); }
+        }
+        Ok(())
+    }
+
+    fn ldc(&self, index: u16) -> io::Result<Option<field::Constant>> {
+        Ok(match self.constants.get(index)? {
+            Constant::Integer(value)            => Some(field::Constant::Integer(*value)),
+            Constant::Long(value)               => Some(field::Constant::Long(*value)),
+            Constant::Float(value)              => Some(field::Constant::Float(*value)),
+            Constant::Double(value)             => Some(field::Constant::Double(*value)),
+            Constant::String { string_index }   => Some(field::Constant::String(self.constants.get_utf8_possibly_invalid(*string_index)?.map(String::from))),
+            _                                   => None, // Classes, method handles, etc.
+        })
+    }
+
+    fn invoke_dynamic_descriptor(&self, index: u16) -> io::Result<String> {
+        let name_and_type_index = match self.constants.get(index)? {
+            Constant::InvokeDynamic { name_and_type_index, .. } => *name_and_type_index,
+            other => return io_data_err!("Expected a CONSTANT_InvokeDynamic_info at constant #{}, found a {:?} instead", index, other),
+        };
+        match self.constants.get(name_and_type_index)? {
+            Constant::NameAndType { descriptor_index, .. } => Ok(self.constants.get_utf8(*descriptor_index)?.to_owned()),
+            other => io_data_err!("Expected a CONSTANT_NameAndType_info at constant #{}, found a {:?} instead", name_and_type_index, other),
+        }
+    }
+
+    /// The class name of a CONSTANT_Class_info, with array types reduced to their element class.
+    fn class_name(&self, index: u16) -> io::Result<String> {
+        let name = self.constants.get_class(index)?;
+        let element = name.trim_start_matches('[');
+        if element.len() == name.len() {
+            Ok(name.to_owned())
+        } else if element.starts_with('L') && element.ends_with(';') {
+            Ok(element[1..element.len()-1].to_owned())
+        } else {
+            Ok(name.to_owned()) // Primitive arrays
+        }
+    }
+
+    fn member_ref(&self, index: u16) -> io::Result<(String, String, String)> {
+        let (class_index, name_and_type_index) = match self.constants.get(index)? {
+            Constant::Fieldref { class_index, name_and_type_index } => (*class_index, *name_and_type_index),
+            Constant::Methodref { class_index, name_and_type_index } => (*class_index, *name_and_type_index),
+            Constant::InterfaceMethodref { class_index, name_and_type_index } => (*class_index, *name_and_type_index),
+            other => return io_data_err!("Expected a CONSTANT_*ref_info at constant #{}, found a {:?} instead", index, other),
+        };
+        let (name_index, descriptor_index) = match self.constants.get(name_and_type_index)? {
+            Constant::NameAndType { name_index, descriptor_index } => (*name_index, *descriptor_index),
+            other => return io_data_err!("Expected a CONSTANT_NameAndType_info at constant #{}, found a {:?} instead", name_and_type_index, other),
+        };
+        Ok((self.class_name(class_index)?, self.constants.get_utf8(name_index)?.to_owned(), self.constants.get_utf8(descriptor_index)?.to_owned()))
+    }
+}
+
+fn pop(stack: &mut Vec<Option<field::Constant>>, n: usize) {
+    let len = stack.len().saturating_sub(n);
+    stack.truncate(len);
+}
+
+/// Evaluate a binary arithmetic, shift, or bitwise instruction.  Division by zero (which would throw) is unknown.
+fn binary(opcode: u8, a: field::Constant, b: field::Constant) -> Option<field::Constant> {
+    use crate::field::Constant::*;
+    Some(match (opcode, a, b) {
+        (0x60, Integer(a), Integer(b))  => Integer(a.wrapping_add(b)),
+        (0x61, Long(a), Long(b))        => Long(a.wrapping_add(b)),
+        (0x62, Float(a), Float(b))      => Float(a + b),
+        (0x63, Double(a), Double(b))    => Double(a + b),
+        (0x64, Integer(a), Integer(b))  => Integer(a.wrapping_sub(b)),
+        (0x65, Long(a), Long(b))        => Long(a.wrapping_sub(b)),
+        (0x66, Float(a), Float(b))      => Float(a - b),
+        (0x67, Double(a), Double(b))    => Double(a - b),
+        (0x68, Integer(a), Integer(b))  => Integer(a.wrapping_mul(b)),
+        (0x69, Long(a), Long(b))        => Long(a.wrapping_mul(b)),
+        (0x6A, Float(a), Float(b))      => Float(a * b),
+        (0x6B, Double(a), Double(b))    => Double(a * b),
+        (0x6C, Integer(a), Integer(b))  if b != 0 => Integer(a.wrapping_div(b)),
+        (0x6D, Long(a), Long(b))        if b != 0 => Long(a.wrapping_div(b)),
+        (0x6E, Float(a), Float(b))      => Float(a / b),
+        (0x6F, Double(a), Double(b))    => Double(a / b),
+        (0x70, Integer(a), Integer(b))  if b != 0 => Integer(a.wrapping_rem(b)),
+        (0x71, Long(a), Long(b))        if b != 0 => Long(a.wrapping_rem(b)),
+        (0x72, Float(a), Float(b))      => Float(a % b),
+        (0x73, Double(a), Double(b))    => Double(a % b),
+        (0x78, Integer(a), Integer(b))  => Integer(a.wrapping_shl(b as u32)),
+        (0x79, Long(a), Integer(b))     => Long(a.wrapping_shl(b as u32)),
+        (0x7A, Integer(a), Integer(b))  => Integer(a.wrapping_shr(b as u32)),
+        (0x7B, Long(a), Integer(b))     => Long(a.wrapping_shr(b as u32)),
+        (0x7C, Integer(a), Integer(b))  => Integer((a as u32).wrapping_shr(b as u32) as i32),
+        (0x7D, Long(a), Integer(b))     => Long((a as u64).wrapping_shr(b as u32) as i64),
+        (0x7E, Integer(a), Integer(b))  => Integer(a & b),
+        (0x7F, Long(a), Long(b))        => Long(a & b),
+        (0x80, Integer(a), Integer(b))  => Integer(a | b),
+        (0x81, Long(a), Long(b))        => Long(a | b),
+        (0x82, Integer(a), Integer(b))  => Integer(a ^ b),
+        (0x83, Long(a), Long(b))        => Long(a ^ b),
+        _                               => return None,
+    })
+}
+
+/// Evaluate a negation or conversion instruction.  Float to integer conversions are left unknown.
+fn unary(opcode: u8, a: field::Constant) -> Option<field::Constant> {
+    use crate::field::Constant::*;
+    Some(match (opcode, a) {
+        (0x74, Integer(a))  => Integer(a.wrapping_neg()),
+        (0x75, Long(a))     => Long(a.wrapping_neg()),
+        (0x76, Float(a))    => Float(-a),
+        (0x77, Double(a))   => Double(-a),
+        (0x85, Integer(a))  => Long(i64::from(a)),
+        (0x86, Integer(a))  => Float(a as f32),
+        (0x87, Integer(a))  => Double(f64::from(a)),
+        (0x88, Long(a))     => Integer(a as i32),
+        (0x89, Long(a))     => Float(a as f32),
+        (0x8A, Long(a))     => Double(a as f64),
+        (0x8D, Float(a))    => Double(f64::from(a)),
+        (0x90, Double(a))   => Float(a as f32),
+        (0x91, Integer(a))  => Integer(i32::from(a as i8)),
+        (0x92, Integer(a))  => Integer(i32::from(a as u16)),
+        (0x93, Integer(a))  => Integer(i32::from(a as i16)),
+        _                   => return None,
+    })
+}
+
+impl<'a> Iterator for Instructions<'a> {
+    type Item = io::Result<Instruction<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let offset = self.offset;
+        let opcode = *self.bytecode.get(offset)?;
+        let rest = &self.bytecode[offset+1..];
+
+        let operands = match operands_len(opcode, offset, rest) {
+            Ok(len) if len <= rest.len() => len,
+            Ok(_) => { self.offset = self.bytecode.len(); return Some(io_data_err!("Truncated instruction 0x{:02X} at offset {}", opcode, offset)); },
+            Err(err) => { self.offset = self.bytecode.len(); return Some(Err(err)); },
+        };
+
+        self.offset += 1 + operands;
+        Some(Ok(Instruction { offset: offset as u32, opcode, operands: &rest[..operands] }))
+    }
+}
+
+/// How many bytes of operands follow `opcode` (at `offset`), given the bytes after the opcode (`rest`.)
+fn operands_len(opcode: u8, offset: usize, rest: &[u8]) -> io::Result<usize> {
+    let u4 = |at: usize| -> io::Result<i64> {
+        match rest.get(at..at+4) {
+            Some(b) => Ok(i64::from(i32::from_be_bytes([b[0], b[1], b[2], b[3]]))),
+            None    => io_data_err!("Truncated switch at offset {}", offset),
+        }
+    };
+    let padding = 3 - (offset % 4); // Align to 4 bytes from the start of the code, after the opcode
+
+    Ok(match opcode {
+        0x00 ..= 0x0F => 0,
+        0x10 => 1, 0x11 => 2, 0x12 => 1, 0x13 | 0x14 => 2,
+        0x15 ..= 0x19 => 1,
+        0x1A ..= 0x35 => 0,
+        0x36 ..= 0x3A => 1,
+        0x3B ..= 0x83 => 0,
+        0x84 => 2,
+        0x85 ..= 0x98 => 0,
+        0x99 ..= 0xA8 => 2,
+        0xA9 => 1,
+        0xAA => { // tableswitch
+            let (low, high) = (u4(padding + 4)?, u4(padding + 8)?);
+            io_assert!(low <= high, "tableswitch at offset {} has low {} > high {}", offset, low, high);
+            padding + 12 + 4 * (high - low + 1) as usize
+        },
+        0xAB => { // lookupswitch
+            let npairs = u4(padding + 4)?;
+            io_assert!(npairs >= 0, "lookupswitch at offset {} has negative npairs {}", offset, npairs);
+            padding + 8 + 8 * npairs as usize
+        },
+        0xAC ..= 0xB1 => 0,
+        0xB2 ..= 0xB8 => 2,
+        0xB9 | 0xBA => 4,
+        0xBB => 2, 0xBC => 1, 0xBD => 2,
+        0xBE | 0xBF => 0,
+        0xC0 | 0xC1 => 2,
+        0xC2 | 0xC3 => 0,
+        0xC4 => if rest.first() == Some(&0x84) { 5 } else { 3 }, // wide iinc, or wide load/store/ret
+        0xC5 => 3,
+        0xC6 | 0xC7 => 2,
+        0xC8 | 0xC9 => 4,
+        0xCA | 0xFE | 0xFF => 0, // breakpoint, impdep1, impdep2
+        other => return io_data_err!("Invalid opcode 0x{:02X} at offset {}", other, offset),
+    })
+}
+
+#[test] fn instructions() {
+    let mut c = testing::ClassFile::new("com/example/Foo", Some("java/lang/Object"));
+    let println = c.methodref("java/io/PrintStream", "println", "(Ljava/lang/String;)V");
+    let out     = c.fieldref("java/lang/System", "out", "Ljava/io/PrintStream;");
+    let list    = c.class("[Ljava/util/List;");
+    let bytecode = [
+        vec![0xB2], testing::be16(out),                 // getstatic System.out
+        vec![0x12, 0x00],                               // ldc #0 (placeholder, patched below)
+        vec![0xB6], testing::be16(println),             // invokevirtual println
+        vec![0x03],                                     // iconst_0
+        vec![0xAA, 0x00, 0x00],                         // tableswitch (offset 8, 3 bytes padding)
+        vec![0,0,0,20, 0,0,0,0, 0,0,0,1, 0,0,0,20, 0,0,0,20],
+        vec![0xC4, 0x84, 0x00, 0x01, 0x00, 0x02],       // wide iinc 1, 2
+        vec![0xC0], testing::be16(list),                // checkcast [Ljava/util/List;
+        vec![0xB1],                                     // return
+    ].concat();
+    let string = c.string("hello");
+    let mut bytecode = bytecode;
+    bytecode[4] = string as u8;
+
+    let code = [testing::be16(2), testing::be16(1), testing::be32(bytecode.len() as u32), bytecode.clone(), testing::be16(0), testing::be16(0)].concat();
+    let code = c.attribute("Code", &code);
+    c.method(0x0009, "main", "([Ljava/lang/String;)V", &[code]);
+
+    let bytes = c.to_bytes();
+    let class = Class::read(&mut &bytes[..]).unwrap();
+    assert!(class.methods[0].code().is_none(), "code shouldn't be retained by default");
+
+    let mut options = ParseOptions::new();
+    options.retain_code(true);
+    let class = Class::read_with(&mut &bytes[..], &options).unwrap();
+    let code = class.methods[0].code().unwrap();
+    assert_eq!((code.max_stack, code.max_locals), (2, 1));
+    assert_eq!(code.bytecode, bytecode);
+
+    let opcodes = code.instructions().map(|i| i.map(|i| (i.offset, i.opcode))).collect::<io::Result<Vec<_>>>().unwrap();
+    assert_eq!(opcodes, &[(0, 0xB2), (3, 0x12), (5, 0xB6), (8, 0x03), (9, 0xAA), (32, 0xC4), (38, 0xC0), (41, 0xB1)]);
+
+    assert_eq!(code.references().unwrap(), &[
+        Reference::Field  { class: "java/lang/System".into(), name: "out".into(), descriptor: "Ljava/io/PrintStream;".into() },
+        Reference::Method { class: "java/io/PrintStream".into(), name: "println".into(), descriptor: "(Ljava/lang/String;)V".into() },
+        Reference::Class("java/util/List".into()),
+    ]);
+
+    let truncated = Instructions { bytecode: &[0xB6, 0x00], offset: 0 }.collect::<Vec<_>>();
+    assert_eq!(truncated.len(), 1);
+    assert!(truncated[0].is_err());
+}
+
+#[test] fn nested_attributes() {
+    let mut c = testing::ClassFile::new("com/example/Foo", Some("java/lang/Object"));
+    let line_numbers = [testing::be16(1), testing::be16(0), testing::be16(42)].concat(); // start_pc 0 -> line 42
+    let line_numbers = c.attribute("LineNumberTable", &line_numbers);
+    let code = [testing::be16(0), testing::be16(1), testing::be32(1), vec![0xB1], testing::be16(0), testing::be16(1), line_numbers].concat();
+    let code = c.attribute("Code", &code);
+    c.method(0x0001, "run", "()V", &[code]);
+    let bytes = c.to_bytes();
+
+    let mut options = ParseOptions::new();
+    options.retain_code(true);
+    let class = Class::read_with(&mut &bytes[..], &options).unwrap();
+    let code = class.methods[0].code().unwrap();
+    assert_eq!(code.attributes, vec![RawAttribute { name: "LineNumberTable".to_owned(), info: vec![0, 1, 0, 0, 0, 42] }]);
+    assert!(code.custom_attributes().is_empty());
+
+    options.register_attribute("LineNumberTable", |info, _| Ok(u16::from_be_bytes([info[4], info[5]])));
+    let class = Class::read_with(&mut &bytes[..], &options).unwrap();
+    let code = class.methods[0].code().unwrap();
+    assert_eq!(code.custom_attributes()[0].downcast_ref::<u16>(), Some(&42));
+    assert_eq!(code.attribute("LineNumberTable").unwrap().info.len(), 6);
+    assert!(code.attribute("StackMapTable").is_none());
+}
+
+#[test] fn line_numbers() {
+    use crate::testing::be16;
+
+    let mut c = testing::ClassFile::new("com/example/Foo", Some("java/lang/Object"));
+    let table = |c: &mut testing::ClassFile, entries: &[(u16, u16)]| {
+        let info = [be16(entries.len() as u16), entries.iter().flat_map(|&(pc, line)| [be16(pc), be16(line)].concat()).collect()].concat();
+        c.attribute("LineNumberTable", &info)
+    };
+    let second = table(&mut c, &[(4, 12)]);
+    let first = table(&mut c, &[(1, 11), (0, 10)]);
+    let bytecode = [0x03, 0x3B, 0x84, 0x00, 0x01, 0xB1]; // iconst_0, istore_0, iinc 0 1, return
+    let code = [be16(1), be16(1), testing::be32(bytecode.len() as u32), bytecode.to_vec(), be16(0), be16(2), second, first].concat();
+    let code = c.attribute("Code", &code);
+    c.method(0x0009, "count", "()V", &[code]);
+    let bytes = c.to_bytes();
+
+    let mut options = ParseOptions::new();
+    options.retain_code(true);
+    let class = Class::read_with(&mut &bytes[..], &options).unwrap();
+    let code = class.methods[0].code().unwrap();
+    assert_eq!(code.line_numbers().unwrap(), &[
+        LineNumber::new(0, 10),
+        LineNumber::new(1, 11),
+        LineNumber::new(4, 12),
+    ]);
+    assert_eq!((0..7).map(|pc| code.line_of(pc).unwrap()).collect::<Vec<_>>(), &[Some(10), Some(11), Some(11), Some(11), Some(12), Some(12), None]);
+
+    let mut c = testing::ClassFile::new("com/example/Foo", Some("java/lang/Object"));
+    let past_end = table(&mut c, &[(9, 1)]);
+    let code = [be16(0), be16(0), testing::be32(1), vec![0xB1], be16(0), be16(1), past_end].concat();
+    let code = c.attribute("Code", &code);
+    c.method(0x0009, "run", "()V", &[code]);
+    let bytes = c.to_bytes();
+    let class = Class::read_with(&mut &bytes[..], &options).unwrap();
+    assert!(class.methods[0].code().unwrap().line_numbers().is_err());
+}
@@ -4,16 +4,22 @@ use crate::io::be::*;
 
 use bugsalot::*;
 
+use std::borrow::Cow;
 use std::convert::*;
-use std::io::{self, Read};
+use std::fmt::{self, Debug, Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::io::{self, Read, Write};
 
 
 
 /// [Java SE 7 &sect; 4.4](https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.4):  A CONSTANT_* values.
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Default)]
 pub struct Constants(pub(crate) Vec<Constant>);
 
 /// [Java SE 7 &sect; 4.4](https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.4):  A CONSTANT_* value.  Not ABI compatible with the raw C ABIs but that's fine.
+///
+/// Constants are `Eq` and `Hash`:  `Float`s and `Double`s compare bit-for-bit, like the JVM's own constant pool, so
+/// `NaN`s equal themselves and `0.0` differs from `-0.0`.
 #[derive(Clone, Debug)]
 pub enum Constant {
     /// The constants table (and *only* the constants table) is 1-indexed.  That's just confusing.  Even worse, `Long` and `Double` take up two slots.  So I emit this as a placeholder for those slots.
@@ -50,10 +56,129 @@ pub enum Constant {
     MethodType { descriptor_index: u16 },
     /// [Java SE 7 &sect; 4.4.10](https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.4.10):  A CONSTANT_InvokeDynamic_info, minus the tag.
     InvokeDynamic { bootstrap_method_attr_index: u16, name_and_type_index: u16 },
+    /// [Java SE 11 &sect; 4.4.10](https://docs.oracle.com/javase/specs/jvms/se11/html/jvms-4.html#jvms-4.4.10):  A CONSTANT_Dynamic_info, minus the tag.
+    Dynamic { bootstrap_method_attr_index: u16, name_and_type_index: u16 },
+    /// [Java SE 9 &sect; 4.4.11](https://docs.oracle.com/javase/specs/jvms/se9/html/jvms-4.html#jvms-4.4.11):  A CONSTANT_Module_info, minus the tag.
+    Module { name_index: u16 },
+    /// [Java SE 9 &sect; 4.4.12](https://docs.oracle.com/javase/specs/jvms/se9/html/jvms-4.html#jvms-4.4.12):  A CONSTANT_Package_info, minus the tag.
+    Package { name_index: u16 },
 
     #[doc(hidden)] _NonExhaustive,
 }
 
+/// A compact, `javap -v` like form, such as `Methodref #4.#13` or `Utf8 java/lang/Object`.
+///
+/// ```
+/// # use jreflection::constants::Constant;
+/// assert_eq!(Constant::Methodref { class_index: 4, name_and_type_index: 13 }.to_string(), "Methodref #4.#13");
+/// assert_eq!(Constant::Utf8("java/lang/Object".to_owned()).to_string(), "Utf8 java/lang/Object");
+/// ```
+impl Display for Constant {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        match self {
+            Constant::UnusedPlaceholder                                     => write!(fmt, "(unused)"),
+            Constant::Class { name_index }                                  => write!(fmt, "Class #{}", name_index),
+            Constant::Fieldref { class_index, name_and_type_index }         => write!(fmt, "Fieldref #{}.#{}", class_index, name_and_type_index),
+            Constant::Methodref { class_index, name_and_type_index }        => write!(fmt, "Methodref #{}.#{}", class_index, name_and_type_index),
+            Constant::InterfaceMethodref { class_index, name_and_type_index }
+                                                                            => write!(fmt, "InterfaceMethodref #{}.#{}", class_index, name_and_type_index),
+            Constant::String { string_index }                               => write!(fmt, "String #{}", string_index),
+            Constant::Integer(value)                                        => write!(fmt, "Integer {}", value),
+            Constant::Float(value)                                          => write!(fmt, "Float {}f", value),
+            Constant::Long(value)                                           => write!(fmt, "Long {}l", value),
+            Constant::Double(value)                                         => write!(fmt, "Double {}d", value),
+            Constant::NameAndType { name_index, descriptor_index }          => write!(fmt, "NameAndType #{}:#{}", name_index, descriptor_index),
+            Constant::Utf8(value)                                           => write!(fmt, "Utf8 {}", value),
+            Constant::MispairedUtf16(units)                                 => write!(fmt, "Utf8 (mispaired UTF-16) {:?}", String::from_utf16_lossy(units)),
+            Constant::InvalidModifiedUtf8(bytes)                            => write!(fmt, "Utf8 (invalid) {:?}", String::from_utf8_lossy(bytes)),
+            Constant::MethodHandle { reference_kind, reference_index }      => write!(fmt, "MethodHandle {}:#{}", reference_kind, reference_index),
+            Constant::MethodType { descriptor_index }                       => write!(fmt, "MethodType #{}", descriptor_index),
+            Constant::InvokeDynamic { bootstrap_method_attr_index, name_and_type_index }
+                                                                            => write!(fmt, "InvokeDynamic #{}:#{}", bootstrap_method_attr_index, name_and_type_index),
+            Constant::Dynamic { bootstrap_method_attr_index, name_and_type_index }
+                                                                            => write!(fmt, "Dynamic #{}:#{}", bootstrap_method_attr_index, name_and_type_index),
+            Constant::Module { name_index }                                 => write!(fmt, "Module #{}", name_index),
+            Constant::Package { name_index }                                => write!(fmt, "Package #{}", name_index),
+            Constant::_NonExhaustive                                        => write!(fmt, "(unknown)"),
+        }
+    }
+}
+
+impl PartialEq for Constant {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Constant::UnusedPlaceholder,               Constant::UnusedPlaceholder)                => true,
+            (Constant::Class { name_index: a },         Constant::Class { name_index: b })          => a == b,
+            (Constant::Fieldref { class_index: ac, name_and_type_index: an }, Constant::Fieldref { class_index: bc, name_and_type_index: bn })
+                                                                                                    => (ac, an) == (bc, bn),
+            (Constant::Methodref { class_index: ac, name_and_type_index: an }, Constant::Methodref { class_index: bc, name_and_type_index: bn })
+                                                                                                    => (ac, an) == (bc, bn),
+            (Constant::InterfaceMethodref { class_index: ac, name_and_type_index: an }, Constant::InterfaceMethodref { class_index: bc, name_and_type_index: bn })
+                                                                                                    => (ac, an) == (bc, bn),
+            (Constant::String { string_index: a },      Constant::String { string_index: b })       => a == b,
+            (Constant::Integer(a),                      Constant::Integer(b))                       => a == b,
+            (Constant::Float(a),                        Constant::Float(b))                         => a.to_bits() == b.to_bits(),
+            (Constant::Long(a),                         Constant::Long(b))                          => a == b,
+            (Constant::Double(a),                       Constant::Double(b))                        => a.to_bits() == b.to_bits(),
+            (Constant::NameAndType { name_index: an, descriptor_index: ad }, Constant::NameAndType { name_index: bn, descriptor_index: bd })
+                                                                                                    => (an, ad) == (bn, bd),
+            (Constant::Utf8(a),                         Constant::Utf8(b))                          => a == b,
+            (Constant::MispairedUtf16(a),               Constant::MispairedUtf16(b))                => a == b,
+            (Constant::InvalidModifiedUtf8(a),          Constant::InvalidModifiedUtf8(b))           => a == b,
+            (Constant::MethodHandle { reference_kind: ak, reference_index: ai }, Constant::MethodHandle { reference_kind: bk, reference_index: bi })
+                                                                                                    => (ak, ai) == (bk, bi),
+            (Constant::MethodType { descriptor_index: a }, Constant::MethodType { descriptor_index: b }) => a == b,
+            (Constant::InvokeDynamic { bootstrap_method_attr_index: ab, name_and_type_index: an }, Constant::InvokeDynamic { bootstrap_method_attr_index: bb, name_and_type_index: bn })
+                                                                                                    => (ab, an) == (bb, bn),
+            (Constant::Dynamic { bootstrap_method_attr_index: ab, name_and_type_index: an }, Constant::Dynamic { bootstrap_method_attr_index: bb, name_and_type_index: bn })
+                                                                                                    => (ab, an) == (bb, bn),
+            (Constant::Module { name_index: a },        Constant::Module { name_index: b })         => a == b,
+            (Constant::Package { name_index: a },       Constant::Package { name_index: b })        => a == b,
+            (Constant::_NonExhaustive,                  Constant::_NonExhaustive)                   => true,
+            _                                                                                       => false,
+        }
+    }
+}
+
+impl Eq for Constant {}
+
+impl Hash for Constant {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            Constant::UnusedPlaceholder | Constant::_NonExhaustive                      => {},
+            Constant::Class { name_index } | Constant::Module { name_index } | Constant::Package { name_index }
+                                                                                        => name_index.hash(state),
+            Constant::Fieldref { class_index, name_and_type_index } | Constant::Methodref { class_index, name_and_type_index } | Constant::InterfaceMethodref { class_index, name_and_type_index }
+                                                                                        => (class_index, name_and_type_index).hash(state),
+            Constant::String { string_index }                                           => string_index.hash(state),
+            Constant::Integer(value)                                                    => value.hash(state),
+            Constant::Float(value)                                                      => value.to_bits().hash(state),
+            Constant::Long(value)                                                       => value.hash(state),
+            Constant::Double(value)                                                     => value.to_bits().hash(state),
+            Constant::NameAndType { name_index, descriptor_index }                      => (name_index, descriptor_index).hash(state),
+            Constant::Utf8(text)                                                        => text.hash(state),
+            Constant::MispairedUtf16(units)                                             => units.hash(state),
+            Constant::InvalidModifiedUtf8(bytes)                                        => bytes.hash(state),
+            Constant::MethodHandle { reference_kind, reference_index }                  => (reference_kind, reference_index).hash(state),
+            Constant::MethodType { descriptor_index }                                   => descriptor_index.hash(state),
+            Constant::InvokeDynamic { bootstrap_method_attr_index, name_and_type_index } | Constant::Dynamic { bootstrap_method_attr_index, name_and_type_index }
+                                                                                        => (bootstrap_method_attr_index, name_and_type_index).hash(state),
+        }
+    }
+}
+
+/// One `#index: constant` entry per line with `{:#?}`, skipping the unused slots after `Long`s and `Double`s.
+impl Debug for Constants {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        struct Entry<'a>(usize, &'a Constant);
+        impl Debug for Entry<'_> { fn fmt(&self, fmt: &mut Formatter) -> fmt::Result { write!(fmt, "#{}: {}", self.0, self.1) } }
+
+        let entries = self.0.iter().enumerate().filter(|(_, c)| match c { Constant::UnusedPlaceholder => false, _ => true });
+        fmt.debug_list().entries(entries.map(|(i, c)| Entry(i, c))).finish()
+    }
+}
+
 impl Constants {
     pub fn get(&self, index: u16) -> io::Result<&Constant> {
         let index = index as usize;
@@ -93,6 +218,24 @@ impl Constants {
         }
     }
 
+    pub fn get_module(&self, index: u16) -> io::Result<&str> {
+        match self.get(index)? {
+            Constant::Module { name_index } => Ok(self.get_utf8(*name_index)?),
+            other => io_data_err!("Expected a CONSTANT_Module_info at constant #{}, found a {:?} instead", index, other),
+        }
+    }
+
+    pub fn get_package(&self, index: u16) -> io::Result<&str> {
+        match self.get(index)? {
+            Constant::Package { name_index } => Ok(self.get_utf8(*name_index)?),
+            other => io_data_err!("Expected a CONSTANT_Package_info at constant #{}, found a {:?} instead", index, other),
+        }
+    }
+
+    pub fn get_optional_utf8(&self, index: u16) -> io::Result<Option<&str>> {
+        if index == 0 { Ok(None) } else { self.get_utf8(index).map(Some) }
+    }
+
     pub fn read(read: &mut impl Read) -> io::Result<Self> {
         let count = read_u2(read)?;
         let mut constants = Vec::with_capacity(count as usize);
@@ -128,7 +271,10 @@ impl Constants {
                 1  => read_modified_utf8(read)?,
                 15 => Constant::MethodHandle { reference_kind: read_u1(read)?, reference_index: read_u2(read)? },
                 16 => Constant::MethodType { descriptor_index: read_u2(read)? },
+                17 => Constant::Dynamic { bootstrap_method_attr_index: read_u2(read)?, name_and_type_index: read_u2(read)? },
                 18 => Constant::InvokeDynamic { bootstrap_method_attr_index: read_u2(read)?, name_and_type_index: read_u2(read)? },
+                19 => Constant::Module { name_index: read_u2(read)? },
+                20 => Constant::Package { name_index: read_u2(read)? },
                 _ => { return io_data_err!("Expected CONSTANT_* value reading constant pool, got {:?}", tag); },
             };
             constants.push(constant);
@@ -138,10 +284,187 @@ impl Constants {
         debug_assert_eq!(count as usize, constants.len());
         Ok(Constants(constants))
     }
+
+    /// Write `constant_pool_count` and the constant pool, as found in a class file.
+    pub fn write(&self, write: &mut impl Write) -> io::Result<()> {
+        if self.0.len() > 0xFFFF { return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Too many constants: {} slots", self.0.len()))); }
+        write.write_all(&(self.0.len().max(1) as u16).to_be_bytes())?;
+        for (index, constant) in self.0.iter().enumerate().skip(1) {
+            let (tag, info) = match constant {
+                Constant::UnusedPlaceholder                                         => continue,
+                Constant::Class { name_index }                                      => (7,  name_index.to_be_bytes().to_vec()),
+                Constant::Fieldref { class_index, name_and_type_index }             => (9,  be2x2(*class_index, *name_and_type_index)),
+                Constant::Methodref { class_index, name_and_type_index }            => (10, be2x2(*class_index, *name_and_type_index)),
+                Constant::InterfaceMethodref { class_index, name_and_type_index }   => (11, be2x2(*class_index, *name_and_type_index)),
+                Constant::String { string_index }                                   => (8,  string_index.to_be_bytes().to_vec()),
+                Constant::Integer(value)                                            => (3,  value.to_be_bytes().to_vec()),
+                Constant::Float(value)                                              => (4,  value.to_bits().to_be_bytes().to_vec()),
+                Constant::Long(value)                                               => (5,  value.to_be_bytes().to_vec()),
+                Constant::Double(value)                                             => (6,  value.to_bits().to_be_bytes().to_vec()),
+                Constant::NameAndType { name_index, descriptor_index }              => (12, be2x2(*name_index, *descriptor_index)),
+                Constant::Utf8(text)                                                => (1,  write_modified_utf8(text.encode_utf16())),
+                Constant::MispairedUtf16(units)                                     => (1,  write_modified_utf8(units.iter().cloned())),
+                Constant::InvalidModifiedUtf8(bytes)                                => (1,  bytes.clone()),
+                Constant::MethodHandle { reference_kind, reference_index }          => (15, [&[*reference_kind][..], &reference_index.to_be_bytes()[..]].concat()),
+                Constant::MethodType { descriptor_index }                           => (16, descriptor_index.to_be_bytes().to_vec()),
+                Constant::Dynamic { bootstrap_method_attr_index, name_and_type_index }          => (17, be2x2(*bootstrap_method_attr_index, *name_and_type_index)),
+                Constant::InvokeDynamic { bootstrap_method_attr_index, name_and_type_index }    => (18, be2x2(*bootstrap_method_attr_index, *name_and_type_index)),
+                Constant::Module { name_index }                                     => (19, name_index.to_be_bytes().to_vec()),
+                Constant::Package { name_index }                                    => (20, name_index.to_be_bytes().to_vec()),
+                Constant::_NonExhaustive                                            => return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Can't write constant #{}", index))),
+            };
+            write.write_all(&[tag])?;
+            if tag == 1 {
+                if info.len() > 0xFFFF { return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("CONSTANT_Utf8_info #{} is too long: {} bytes", index, info.len()))); }
+                write.write_all(&(info.len() as u16).to_be_bytes())?;
+            }
+            write.write_all(&info[..])?;
+        }
+        Ok(())
+    }
+
+    /// The index of the first CONSTANT_Utf8_info containing exactly `text`, if any.
+    pub fn find_utf8(&self, text: &str) -> Option<u16> {
+        self.0.iter().position(|c| match c { Constant::Utf8(s) => s == text, _ => false }).map(|i| i as u16)
+    }
+
+    /// Append `constant`, returning its index.  Longs and doubles take up two slots, as usual.
+    pub fn add(&mut self, constant: Constant) -> io::Result<u16> {
+        if self.0.is_empty() { self.0.push(Constant::UnusedPlaceholder); }
+        let index = self.0.len();
+        let wide = match constant { Constant::Long(_) | Constant::Double(_) => true, _ => false };
+        if index + if wide { 2 } else { 1 } > 0xFFFF { return Err(io::Error::new(io::ErrorKind::InvalidInput, "Constant pool is full")); }
+        self.0.push(constant);
+        if wide { self.0.push(Constant::UnusedPlaceholder); }
+        Ok(index as u16)
+    }
+
+    /// The index of a CONSTANT_Utf8_info containing `text`, appending one if there isn't one already.
+    pub fn add_utf8(&mut self, text: &str) -> io::Result<u16> {
+        match self.find_utf8(text) {
+            Some(index) => Ok(index),
+            None        => self.add(Constant::Utf8(text.to_owned())),
+        }
+    }
+
+    /// The index of a CONSTANT_Class_info naming `path` (such as `"java/lang/Object"`), appending one if needed.
+    pub fn add_class(&mut self, path: &str) -> io::Result<u16> {
+        let name_index = self.add_utf8(path)?;
+        self.add_unique(Constant::Class { name_index })
+    }
+
+    /// The index of a CONSTANT_NameAndType_info for `name` and `descriptor`, appending one if needed.
+    pub fn add_name_and_type(&mut self, name: &str, descriptor: &str) -> io::Result<u16> {
+        let (name_index, descriptor_index) = (self.add_utf8(name)?, self.add_utf8(descriptor)?);
+        self.add_unique(Constant::NameAndType { name_index, descriptor_index })
+    }
+
+    /// The index of a constant identical to `constant`, appending it if there isn't one already.  Only "leaf" constants
+    /// (such as numbers, strings, classes, and names and types) are reused - references to members, method handles,
+    /// and dynamic constants are always appended.
+    pub fn add_unique(&mut self, constant: Constant) -> io::Result<u16> {
+        match self.0.iter().position(|c| same(c, &constant)) {
+            Some(index) => Ok(index as u16),
+            None        => self.add(constant),
+        }
+    }
 }
 
 
 
+/// Iterate over the CONSTANT_Utf8_info entries of a raw .class file, as `(index, text)`, without parsing the rest of the
+/// class.
+///
+/// Text that's also valid UTF8 (almost everything - class names, descriptors, most string literals) is borrowed
+/// straight from `class_bytes` without allocating.  Only "Modified UTF8" that differs from real UTF8 (encoded NULs and
+/// supplementary characters) is converted into an owned `String`, and entries that aren't valid Modified UTF8 at all
+/// (such as mispaired surrogates) are converted lossily.
+///
+/// # Examples
+///
+/// ```no_run
+/// # #[cfg(feature = "jar")] {
+/// # let src = jreflection::Source::from_jar("app.jar").unwrap();
+/// src.for_each_class(|class|{
+///     let bytes = src.read_class_bytes(&class)?;
+///     for constant in jreflection::constants::utf8s(&bytes[..])? {
+///         let (_index, text) = constant?;
+///         if text.contains("http://") { println!("{}: {}", class, text); }
+///     }
+///     Ok(())
+/// }).unwrap();
+/// # }
+/// ```
+pub fn utf8s(class_bytes: &[u8]) -> io::Result<Utf8s<'_>> {
+    Ok(Utf8s(RawConstants::new(class_bytes)?))
+}
+
+/// An iterator over the CONSTANT_Utf8_info entries of a raw .class file.  See [utf8s](fn.utf8s.html).
+pub struct Utf8s<'a>(RawConstants<'a>);
+
+impl<'a> Iterator for Utf8s<'a> {
+    type Item = io::Result<(u16, Cow<'a, str>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (index, tag, info) = match self.0.next()? { Ok(c) => c, Err(err) => return Some(Err(err)) };
+            if tag != 1 { continue; }
+            let text = &info[2..];
+            if let Ok(text) = std::str::from_utf8(text) {
+                if !text.contains('\u{0}') { return Some(Ok((index, Cow::Borrowed(text)))); } // Modified UTF8 never contains real NULs, so this matches what Constants would decode
+            }
+            let text = read_modified_utf8_as_utf8(text).unwrap_or_else(|| String::from_utf8_lossy(text).into_owned());
+            return Some(Ok((index, Cow::Owned(text))));
+        }
+    }
+}
+
+/// Walks the constant pool of a raw .class file without decoding it, yielding `(index, tag, info)` for each constant.
+/// `info` excludes the tag, but includes everything else (such as the length prefix of a CONSTANT_Utf8_info.)
+pub(crate) struct RawConstants<'a> {
+    bytes:  &'a [u8],
+    offset: usize,
+    index:  u16,
+    count:  u16,
+}
+
+impl<'a> RawConstants<'a> {
+    pub fn new(class_bytes: &'a [u8]) -> io::Result<Self> {
+        if !class_bytes.starts_with(&[0xCA, 0xFE, 0xBA, 0xBE]) { return io_data_err!("Invalid header magic, not a class file"); }
+        let count = match class_bytes.get(8..10) { Some(c) => u16::from_be_bytes([c[0], c[1]]), None => return io_data_err!("Unexpected end of class file reading constant pool") };
+        Ok(Self { bytes: class_bytes, offset: 10, index: 1, count })
+    }
+
+    /// How many slots the constant pool has, including the unused slot 0 and the second slots of longs and doubles.
+    pub fn slots(&self) -> u16 { self.count }
+
+    /// The offset of the next constant - or the end of the constant pool, once this has been exhausted without error.
+    pub fn offset(&self) -> usize { self.offset }
+}
+
+impl<'a> Iterator for RawConstants<'a> {
+    type Item = io::Result<(u16, u8, &'a [u8])>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.count { return None; }
+        let bytes = self.bytes;
+        let tag = match bytes.get(self.offset) { Some(&tag) => tag, None => { self.count = 0; return Some(io_data_err!("Unexpected end of class file reading constant pool")); } };
+        let (len, slots) = match tag {
+            1                                   => (2 + bytes.get(self.offset+1..self.offset+3).map(|l| u16::from_be_bytes([l[0], l[1]]) as usize).unwrap_or(0), 1),
+            7 | 8 | 16 | 19 | 20                => (2, 1),
+            15                                  => (3, 1),
+            3 | 4 | 9 | 10 | 11 | 12 | 17 | 18  => (4, 1),
+            5 | 6                               => (8, 2),
+            tag                                 => { self.count = 0; return Some(io_data_err!("Expected CONSTANT_* value reading constant pool, got {:?}", tag)); },
+        };
+        let info = match bytes.get(self.offset+1 .. self.offset+1+len) { Some(info) => info, None => { self.count = 0; return Some(io_data_err!("Unexpected end of class file reading constant pool")); } };
+        let index = self.index;
+        self.offset += 1 + len;
+        self.index += slots;
+        Some(Ok((index, tag, info)))
+    }
+}
+
 /// Reads a Java "UTF8" string.  Which is not actually UTF8.  Weirdness:
 /// "\u{0}" is encoded as *two bytes*, neither of which is 0.
 /// "\u{10000}" and above is encoded as *six bytes* - each first encoded as UTF16 surrogate pairs, then encoded as UTF8.
@@ -157,7 +480,7 @@ fn read_modified_utf8(r: &mut impl Read) -> io::Result<Constant> {
     Ok(Constant::InvalidModifiedUtf8(buffer))
 }
 
-fn read_modified_utf8_as_utf8(buffer: &[u8]) -> Option<String> {
+pub(crate) fn read_modified_utf8_as_utf8(buffer: &[u8]) -> Option<String> {
     let mut output = String::new();
     let mut remaining = buffer;
     while !remaining.is_empty() {
@@ -187,6 +510,32 @@ fn read_modified_utf8_as_mispaired_utf16(buffer: &[u8]) -> Option<Vec<u16>> {
     Some(output)
 }
 
+/// Are `a` and `b` identical "leaf" constants?  Floats must match bit-for-bit.
+fn same(a: &Constant, b: &Constant) -> bool {
+    match a {
+        Constant::Class {..} | Constant::String {..} | Constant::Integer(_) | Constant::Float(_) | Constant::Long(_) | Constant::Double(_) |
+        Constant::Utf8(_) | Constant::MethodType {..} | Constant::Module {..} | Constant::Package {..} | Constant::NameAndType {..}
+            => a == b,
+        _   => false,
+    }
+}
+
+fn be2x2(a: u16, b: u16) -> Vec<u8> { [a.to_be_bytes(), b.to_be_bytes()].concat() }
+
+/// Encodes UTF16 code units (which may include mispaired surrogates) as Java "UTF8":  NULs take two bytes, and
+/// surrogates are encoded individually as three bytes each.
+fn write_modified_utf8(units: impl Iterator<Item = u16>) -> Vec<u8> {
+    let mut output = Vec::new();
+    for unit in units {
+        match unit {
+            0x0001 ..= 0x007F   => output.push(unit as u8),
+            0x0000 ..= 0x07FF   => output.extend_from_slice(&[0b11000000 | (unit >> 6) as u8, 0b10000000 | (unit & 0x3F) as u8]),
+            _                   => output.extend_from_slice(&[0b11100000 | (unit >> 12) as u8, 0b10000000 | ((unit >> 6) & 0x3F) as u8, 0b10000000 | (unit & 0x3F) as u8]),
+        }
+    }
+    output
+}
+
 fn read_modified_utf8_point(remaining: &mut &[u8]) -> io::Result<u32> {
     if let Some(&b0) = remaining.get(0) {
         if b0 & 0b10000000 == 0b00000000 {
@@ -244,3 +593,55 @@ fn expect_byte(remaining: &[u8], index: usize, mask: u8, equal: u8) -> io::Resul
         io_data_err!("Incomplete 'UTF8' string - expected more bytes")
     }
 }
+
+#[test] fn utf8_iter() {
+    let mut c = crate::testing::ClassFile::new("com/example/Main", Some("java/lang/Object"));
+    c.long(1);
+    c.string("plain");
+    let nul = c.constant(1, &[0, 4, b'a', 0xC0, 0x80, b'b']);                            // "a\0b"
+    let emoji = c.constant(1, &[0, 6, 0xED, 0xA0, 0xBD, 0xED, 0xB8, 0x80]);                 // U+1F600 as a surrogate pair
+    let bytes = c.to_bytes();
+
+    let all = utf8s(&bytes[..]).unwrap().collect::<io::Result<Vec<_>>>().unwrap();
+    let texts = all.iter().map(|(_, t)| t.as_ref()).collect::<Vec<_>>();
+    assert_eq!(texts, &["com/example/Main", "java/lang/Object", "plain", "a\u{0}b", "\u{1F600}"]);
+    assert!(all.iter().take(3).all(|(_, t)| if let Cow::Borrowed(_) = t { true } else { false }));
+    assert_eq!(all[3].0, nul);
+    assert_eq!(all[4].0, emoji);
+
+    let constants = Constants::read(&mut &bytes[8..]).unwrap();
+    for (index, text) in all.iter() { assert_eq!(constants.get_utf8(*index).unwrap(), text); }
+
+    let mut written = Vec::new();
+    constants.write(&mut written).unwrap();
+    let mut raw = RawConstants::new(&bytes[..]).unwrap();
+    while let Some(c) = raw.next() { c.unwrap(); }
+    assert_eq!(written, &bytes[8 .. raw.offset()]);
+
+    assert!(utf8s(&bytes[1..]).is_err());
+    assert!(utf8s(&bytes[..20]).unwrap().any(|c| c.is_err()));
+}
+
+#[test] fn eq_hash() {
+    let constants = vec![
+        Constant::Float(std::f32::NAN),
+        Constant::Float(0.0),
+        Constant::Float(-0.0),
+        Constant::Double(0.0),
+        Constant::Methodref { class_index: 1, name_and_type_index: 2 },
+        Constant::InterfaceMethodref { class_index: 1, name_and_type_index: 2 },
+        Constant::Utf8("a".to_owned()),
+    ];
+    assert_eq!(Constant::Float(std::f32::NAN), Constant::Float(std::f32::NAN));
+    assert_ne!(Constant::Float(0.0), Constant::Float(-0.0));
+    assert_ne!(Constant::Methodref { class_index: 1, name_and_type_index: 2 }, Constant::InterfaceMethodref { class_index: 1, name_and_type_index: 2 });
+
+    let set = constants.iter().chain(constants.iter()).cloned().collect::<std::collections::HashSet<_>>();
+    assert_eq!(set.len(), constants.len());
+    assert!(set.contains(&Constant::Methodref { class_index: 1, name_and_type_index: 2 }));
+
+    use crate::field::Constant as Value;
+    let fields = vec![Value::Double(std::f64::NAN), Value::Double(-0.0), Value::Double(0.0), Value::String(None)];
+    let set = fields.iter().chain(fields.iter()).cloned().collect::<std::collections::HashSet<_>>();
+    assert_eq!(set.len(), fields.len());
+}
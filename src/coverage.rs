@@ -0,0 +1,234 @@
+//! Which parts of a reference API (e.g. OpenJDK 8) are missing from a subject (e.g. android-29.)
+
+use crate::{desugar, filter, Class, Source};
+#[cfg(test)] use crate::{class, method, Method};
+
+use std::collections::*;
+use std::io::{self, ErrorKind};
+
+
+
+/// A field or method, by name and descriptor.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Member {
+    pub name:       String,
+    pub descriptor: String,
+}
+
+/// The result of [compare]ing a reference API against a subject.
+///
+/// Only the public API of the reference is considered:  public classes, and their public or protected, non-synthetic
+/// members.  A member is considered present if the subject's class declares or inherits it.
+///
+/// [compare]:  fn.compare.html
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Coverage {
+    /// How many public classes the reference has.
+    pub classes:            usize,
+
+    /// How many public or protected members the public classes of the reference have.
+    pub members:            usize,
+
+    /// Public classes of the reference missing entirely from the subject.
+    pub missing_classes:    BTreeSet<String>,
+
+    /// Members of the reference missing from the subject, by class.  Members of missing classes aren't listed here.
+    pub missing_members:    BTreeMap<String, BTreeSet<Member>>,
+}
+
+impl Coverage {
+    /// Is every class and member of the reference available in the subject?
+    pub fn is_complete(&self) -> bool { self.missing_classes.is_empty() && self.missing_members.is_empty() }
+
+    /// Is `class` available in the subject?  Classes outside of the reference's API are assumed to be available.
+    pub fn has_class(&self, class: &str) -> bool { !self.missing_classes.contains(class) }
+
+    /// Is the member of `class` available in the subject?  Members outside of the reference's API are assumed to be available.
+    pub fn has_member(&self, class: &str, name: &str, descriptor: &str) -> bool {
+        self.has_class(class) && !self.missing_members.get(class).map(|m| m.iter().any(|m| m.name == name && m.descriptor == descriptor)).unwrap_or(false)
+    }
+
+    /// Treat everything `config` provides as available, removing it from [missing_classes] and [missing_members].
+    ///
+    /// [missing_classes]:  #structfield.missing_classes
+    /// [missing_members]:  #structfield.missing_members
+    pub fn apply_desugaring(&mut self, config: &desugar::Config) {
+        let missing_classes = std::mem::replace(&mut self.missing_classes, BTreeSet::new());
+        self.missing_classes = missing_classes.into_iter().filter(|c| !config.provides_class(c)).collect();
+
+        let missing_members = std::mem::replace(&mut self.missing_members, BTreeMap::new());
+        for (class, members) in missing_members {
+            let members = members.into_iter().filter(|m| !config.provides_member(&class, &m.name)).collect::<BTreeSet<_>>();
+            if !members.is_empty() { self.missing_members.insert(class, members); }
+        }
+    }
+
+    /// How many members of the reference are missing from the subject, not counting members of missing classes.
+    pub fn missing_member_count(&self) -> usize { self.missing_members.values().map(|m| m.len()).sum() }
+}
+
+/// Compare every class of `reference` against `subject`.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use jreflection::*;
+/// # #[cfg(feature = "jar")] {
+/// let jdk8    = Source::from_jdk_dir(r"C:\Program Files\AdoptOpenJDK\jdk-8.0.232.09-hotspot").unwrap();
+/// let android = android::discover_sdk().unwrap().platform(29).unwrap().unwrap().source().unwrap();
+/// let coverage = coverage::compare(&jdk8, &android).unwrap();
+/// println!("{} of {} JDK 8 classes missing from android-29", coverage.missing_classes.len(), coverage.classes);
+/// assert!(!coverage.has_member("java/util/Optional", "isEmpty", "()Z"));
+/// # }
+/// ```
+pub fn compare(reference: &Source, subject: &Source) -> io::Result<Coverage> {
+    compare_filtered(reference, subject, &filter::Filter::default())
+}
+
+/// Compare every class of `reference` against `subject`, only counting the API of `reference` included by `filter`.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use jreflection::*;
+/// # #[cfg(all(feature = "jar", feature = "annotations"))] {
+/// let mut filter = filter::Filter::new();
+/// filter.exclude_annotated("com.google.common.annotations.Beta");
+/// let coverage = coverage::compare_filtered(&Source::from_jar("guava.jar").unwrap(), &Source::from_jar("guava-lite.jar").unwrap(), &filter).unwrap();
+/// # }
+/// ```
+pub fn compare_filtered(reference: &Source, subject: &Source, filter: &filter::Filter) -> io::Result<Coverage> {
+    let mut classes = Vec::new();
+    reference.for_each_class(|path|{
+        classes.push(reference.read_class(path)?);
+        Ok(())
+    })?;
+
+    compare_with_filter(classes, |path| match subject.read_class(path) {
+        Ok(class) => Ok(Some(class)),
+        Err(ref err) if err.kind() == ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err),
+    }, filter)
+}
+
+/// Compare `reference` classes against a subject, whose classes are looked up by JNI path via `subject`.
+pub fn compare_with(reference: impl IntoIterator<Item = Class>, subject: impl FnMut(&str) -> io::Result<Option<Class>>) -> io::Result<Coverage> {
+    compare_with_filter(reference, subject, &filter::Filter::default())
+}
+
+/// Compare `reference` classes against a subject, whose classes are looked up by JNI path via `subject`, only
+/// counting the API of `reference` included by `filter`.
+pub fn compare_with_filter(reference: impl IntoIterator<Item = Class>, mut subject: impl FnMut(&str) -> io::Result<Option<Class>>, filter: &filter::Filter) -> io::Result<Coverage> {
+    let mut coverage = Coverage::default();
+    let mut cache = BTreeMap::<String, Option<SubjectClass>>::new();
+
+    for class in reference.into_iter() {
+        if !filter.includes_class(&class) { continue; }
+        coverage.classes += 1;
+
+        let api = class.fields.iter().filter(|f| filter.includes_field(&class, f)).map(|f| (f.name.as_str(), f.descriptor_str()))
+            .chain(class.methods.iter().filter(|m| filter.includes_method(&class, m)).map(|m| (m.name.as_str(), m.descriptor_str())))
+            .collect::<Vec<_>>();
+        coverage.members += api.len();
+
+        if lookup(&mut cache, &mut subject, class.path.as_str())?.is_none() {
+            coverage.missing_classes.insert(class.path.as_str().to_owned());
+            continue;
+        }
+
+        for (name, descriptor) in api {
+            let inheritable = !name.starts_with('<');
+            if !has_member(&mut cache, &mut subject, class.path.as_str(), name, descriptor, inheritable)? {
+                coverage.missing_members.entry(class.path.as_str().to_owned()).or_default().insert(Member { name: name.to_owned(), descriptor: descriptor.to_owned() });
+            }
+        }
+    }
+
+    Ok(coverage)
+}
+
+struct SubjectClass {
+    supers:     Vec<String>,
+    members:    BTreeSet<(String, String)>,
+    inherited:  BTreeSet<(String, String)>, // Members subclasses inherit:  not private, nor static interface methods
+}
+
+fn lookup<'c>(cache: &'c mut BTreeMap<String, Option<SubjectClass>>, subject: &mut impl FnMut(&str) -> io::Result<Option<Class>>, path: &str) -> io::Result<Option<&'c SubjectClass>> {
+    if !cache.contains_key(path) {
+        let class = subject(path)?.map(|class| SubjectClass {
+            supers:     class.super_path.iter().chain(class.interfaces.iter()).map(|s| s.as_str().to_owned()).collect(),
+            members:    class.fields.iter().map(|f| (f.name.clone(), f.descriptor_str().to_owned()))
+                .chain(class.methods.iter().map(|m| (m.name.clone(), m.descriptor_str().to_owned()))).collect(),
+            inherited:  class.fields.iter().filter(|f| !f.is_private()).map(|f| (f.name.clone(), f.descriptor_str().to_owned()))
+                .chain(class.methods.iter().filter(|m| !m.is_private() && (!m.is_static() || !class.is_interface())).map(|m| (m.name.clone(), m.descriptor_str().to_owned()))).collect(),
+        });
+        cache.insert(path.to_owned(), class);
+    }
+    Ok(cache[path].as_ref())
+}
+
+fn has_member(cache: &mut BTreeMap<String, Option<SubjectClass>>, subject: &mut impl FnMut(&str) -> io::Result<Option<Class>>, class: &str, name: &str, descriptor: &str, inheritable: bool) -> io::Result<bool> {
+    let mut visited = BTreeSet::new();
+    let mut pending = vec![class.to_owned()];
+    while let Some(path) = pending.pop() {
+        if !visited.insert(path.clone()) { continue; }
+        let class = match lookup(cache, subject, path.as_str())? { Some(c) => c, None => continue };
+        let members = if visited.len() == 1 { &class.members } else { &class.inherited };
+        if members.iter().any(|(n, d)| n == name && d == descriptor) { return Ok(true); }
+        if inheritable { pending.extend(class.supers.iter().cloned()); }
+    }
+    Ok(false)
+}
+
+#[test] fn coverage() {
+    fn class(path: &str, super_path: &str, methods: &[&str]) -> Class {
+        Class {
+            flags:      class::Flags::PUBLIC,
+            path:       class::IdBuf::new(path.to_owned()),
+            super_path: Some(class::IdBuf::new(super_path.to_owned())),
+            methods:    methods.iter().map(|name| Method::new(method::Flags::PUBLIC, (*name).to_owned(), "()V".to_owned()).unwrap()).collect(),
+            .. Default::default()
+        }
+    }
+
+    let reference = vec![
+        class("java/lang/Object",   "",                 &["<init>", "hashCode"]),
+        class("java/util/List",     "java/lang/Object", &["a", "b", "c"]),
+        class("java/util/Optional", "java/lang/Object", &["isEmpty"]),
+        Class { flags: class::Flags::empty(), .. class("java/util/Hidden", "java/lang/Object", &[]) },
+    ];
+    let subject = vec![
+        class("java/lang/Object",   "",                 &["<init>", "hashCode"]),
+        class("java/util/Base",     "java/lang/Object", &["b"]),
+        class("java/util/List",     "java/util/Base",   &["a"]),
+    ];
+
+    let coverage = compare_with(reference, |path| Ok(subject.iter().find(|c| c.path.as_str() == path).cloned())).unwrap();
+    assert_eq!(coverage.classes, 3);
+    assert_eq!(coverage.members, 6);
+    assert_eq!(coverage.missing_classes.iter().map(|c| c.as_str()).collect::<Vec<_>>(), &["java/util/Optional"]);
+    assert_eq!(coverage.missing_member_count(), 1);
+    assert!( coverage.has_member("java/util/List", "a", "()V"));
+    assert!( coverage.has_member("java/util/List", "b", "()V")); // inherited
+    assert!(!coverage.has_member("java/util/List", "c", "()V"));
+    assert!(!coverage.has_member("java/util/Optional", "isEmpty", "()V"));
+    assert!(!coverage.is_complete());
+
+    let mut desugared = coverage.clone();
+    let mut config = desugar::Config::new();
+    config.add_class("java/util/Optional");
+    config.add_member("java/util/List", "c");
+    desugared.apply_desugaring(&config);
+    assert!(desugared.is_complete());
+
+    // Static interface methods aren't inherited
+    let interface = |path: &str, interfaces: &[&str]| Class {
+        flags:      class::Flags::PUBLIC | class::Flags::INTERFACE | class::Flags::ABSTRACT,
+        interfaces: interfaces.iter().map(|i| class::IdBuf::new((*i).to_owned())).collect(),
+        methods:    vec![Method::new(method::Flags::PUBLIC | method::Flags::STATIC, "of".to_owned(), "()V".to_owned()).unwrap()],
+        .. class(path, "java/lang/Object", &[])
+    };
+    let subject = vec![interface("java/util/BaseStream", &[]), Class { methods: Vec::new(), .. interface("java/util/Stream", &["java/util/BaseStream"]) }];
+    let coverage = compare_with(vec![interface("java/util/Stream", &[])], |path| Ok(subject.iter().find(|c| c.path.as_str() == path).cloned())).unwrap();
+    assert!(!coverage.has_member("java/util/Stream", "of", "()V"));
+}
@@ -0,0 +1,159 @@
+//! Content-addressed deduplication of classes across overlapping [Source]s, such as jars with shaded dependencies.
+//!
+//! [Source]:   ../struct.Source.html
+
+use crate::Source;
+
+use std::collections::*;
+use std::fmt::{self, Debug, Display, Formatter};
+use std::io;
+
+
+
+/// A 128-bit [FNV-1a](http://www.isthe.com/chongo/tech/comp/fnv/) hash of a .class file's raw bytes.
+///
+/// This is not a cryptographic hash - it's meant for spotting byte-identical copies, not for defending against
+/// deliberately colliding classes.
+///
+/// # Examples
+///
+/// ```
+/// use jreflection::dedup::ContentHash;
+/// assert_eq!(ContentHash::of(b"").to_string(), "6c62272e07bb014262b821756295c58d");
+/// assert_ne!(ContentHash::of(b"a"), ContentHash::of(b"b"));
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ContentHash(pub u128);
+
+/// A single copy of a class, found in one of the scanned sources.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ClassCopy {
+    /// The index of the source containing this copy, in the order the sources were scanned.
+    pub source: usize,
+
+    /// The hash of this copy's raw .class file bytes.
+    pub hash:   ContentHash,
+}
+
+/// Every copy of every class found in a set of sources, by class name.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use jreflection::*;
+/// # #[cfg(feature = "jar")] {
+/// let classpath = ClassPath::from_sources(vec![
+///     Source::from_jar("app.jar").unwrap(),
+///     Source::from_jar("library.jar").unwrap(),
+/// ]);
+/// let dedup = classpath.dedup().unwrap();
+/// for (class, copies) in dedup.conflicts() {
+///     println!("{} differs between sources {:?}", class, copies.iter().map(|c| c.source).collect::<Vec<_>>());
+/// }
+/// # }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Dedup {
+    classes: BTreeMap<String, Vec<ClassCopy>>,
+}
+
+impl ContentHash {
+    /// Hash `bytes`.
+    pub fn of(bytes: &[u8]) -> Self {
+        const OFFSET_BASIS  : u128 = 0x6c62272e_07bb0142_62b82175_6295c58d;
+        const PRIME         : u128 = 0x00000000_01000000_00000000_0000013B;
+        let mut hash = OFFSET_BASIS;
+        for byte in bytes.iter() {
+            hash ^= u128::from(*byte);
+            hash = hash.wrapping_mul(PRIME);
+        }
+        ContentHash(hash)
+    }
+}
+
+impl Debug   for ContentHash { fn fmt(&self, f: &mut Formatter) -> fmt::Result { write!(f, "ContentHash({})", self) } }
+impl Display for ContentHash { fn fmt(&self, f: &mut Formatter) -> fmt::Result { write!(f, "{:032x}", self.0) } }
+
+impl Dedup {
+    /// Create an empty set of classes.
+    pub fn new() -> Self { Self::default() }
+
+    /// Hash every class of every source in `sources`.  This reads the raw bytes of every class, so it's not cheap.
+    pub fn scan(sources: &[Source]) -> io::Result<Self> {
+        let mut dedup = Self::new();
+        for (index, source) in sources.iter().enumerate() {
+            source.for_each_class(|class|{
+                let bytes = source.read_class_bytes(class.as_str())?;
+                dedup.insert(class, index, &bytes[..]);
+                Ok(())
+            })?;
+        }
+        Ok(dedup)
+    }
+
+    /// Record a copy of the class `name`, found in the source with index `source`.
+    pub fn insert(&mut self, name: impl Into<String>, source: usize, bytes: &[u8]) {
+        self.classes.entry(name.into()).or_default().push(ClassCopy { source, hash: ContentHash::of(bytes) });
+    }
+
+    /// Every class, and all copies of it, by class name.
+    pub fn classes(&self) -> impl Iterator<Item = (&str, &[ClassCopy])> {
+        self.classes.iter().map(|(name, copies)| (name.as_str(), &copies[..]))
+    }
+
+    /// All copies of the class `name`, in scan order.  Empty if the class wasn't found.
+    pub fn copies(&self, name: &str) -> &[ClassCopy] {
+        self.classes.get(name).map(|copies| &copies[..]).unwrap_or(&[])
+    }
+
+    /// Classes found in more than one source (or more than once in the same source.)
+    pub fn duplicates(&self) -> impl Iterator<Item = (&str, &[ClassCopy])> {
+        self.classes().filter(|(_, copies)| copies.len() > 1)
+    }
+
+    /// Duplicated classes where every copy is byte-identical.  These are safe to dedup.
+    pub fn identical(&self) -> impl Iterator<Item = (&str, &[ClassCopy])> {
+        self.duplicates().filter(|(_, copies)| copies.iter().all(|c| c.hash == copies[0].hash))
+    }
+
+    /// Duplicated classes where at least two copies differ.  Whichever source is searched first wins, which may be a bug.
+    pub fn conflicts(&self) -> impl Iterator<Item = (&str, &[ClassCopy])> {
+        self.duplicates().filter(|(_, copies)| copies.iter().any(|c| c.hash != copies[0].hash))
+    }
+
+    /// The first copy of every distinct (class name, content) pair - i.e. what remains after dropping byte-identical copies.
+    pub fn unique(&self) -> impl Iterator<Item = (&str, ClassCopy)> {
+        self.classes().flat_map(|(name, copies)|{
+            let mut seen = BTreeSet::new();
+            copies.iter().filter(move |c| seen.insert(c.hash)).map(move |c| (name, *c))
+        })
+    }
+}
+
+#[test] fn dedup() {
+    let mut dedup = Dedup::new();
+    dedup.insert("com/example/App",                 0, b"app");
+    dedup.insert("com/google/common/base/Strings",  0, b"guava 28");
+    dedup.insert("com/google/common/base/Strings",  1, b"guava 28");
+    dedup.insert("com/google/common/base/Objects",  0, b"guava 28 objects");
+    dedup.insert("com/google/common/base/Objects",  1, b"guava 19 objects");
+    dedup.insert("com/google/common/base/Objects",  2, b"guava 28 objects");
+
+    let names = |i: &mut dyn Iterator<Item = (&str, &[ClassCopy])>| i.map(|(name, _)| name.to_owned()).collect::<Vec<_>>();
+    assert_eq!(names(&mut dedup.duplicates()),  &["com/google/common/base/Objects", "com/google/common/base/Strings"]);
+    assert_eq!(names(&mut dedup.identical()),   &["com/google/common/base/Strings"]);
+    assert_eq!(names(&mut dedup.conflicts()),   &["com/google/common/base/Objects"]);
+
+    let unique = dedup.unique().map(|(name, copy)| (name, copy.source)).collect::<Vec<_>>();
+    assert_eq!(unique, &[
+        ("com/example/App",                 0),
+        ("com/google/common/base/Objects",  0),
+        ("com/google/common/base/Objects",  1),
+        ("com/google/common/base/Strings",  0),
+    ]);
+
+    let copies = dedup.copies("com/google/common/base/Objects");
+    assert_eq!(copies[0].hash, copies[2].hash);
+    assert_ne!(copies[0].hash, copies[1].hash);
+    assert!(dedup.copies("com/example/Missing").is_empty());
+}
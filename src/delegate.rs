@@ -0,0 +1,326 @@
+//! Generation of simple delegating method bodies (load the arguments, invoke a target method, return the result), for
+//! emitting proxy and adapter classes without hand-writing bytecode.
+//!
+//! Arguments and return values are converted between the source and target descriptors where the JVM can do so without
+//! boxing:  identical types are passed as-is, primitives are widened (`int` to `long`, `float` to `double`, etc.), and
+//! references are `checkcast` to the target's type.  Anything else is an error.
+//!
+//! See [Delegate](struct.Delegate.html).
+
+use crate::{class, method};
+use crate::attributes::RawAttribute;
+use crate::constants::{Constant, Constants};
+use crate::field::{BasicType, Descriptor as Type};
+use crate::transform::{ClassFile, Member};
+#[cfg(test)] use crate::{code, testing, Class, ParseOptions};
+
+use std::io::{self, Error, ErrorKind};
+
+
+
+/// How a [Delegate](struct.Delegate.html) invokes its target.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Invoke {
+    /// `invokestatic`, of a static class method.
+    Static,
+    /// `invokevirtual`, of an instance method of a class.
+    Virtual,
+    /// `invokeinterface`, of an instance method of an interface.
+    Interface,
+    /// `invokespecial`, of a superclass method (e.g. `super.run()`) or private method.
+    Special,
+}
+
+/// The object a [Delegate](struct.Delegate.html) invokes a non-static target on.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Receiver {
+    /// `this`, the object the delegating method was called on (the default.)
+    This,
+    /// A field of `this`, such as the `delegate` field of a wrapper.
+    Field { owner: String, name: String, descriptor: String },
+    /// The first argument of the delegating method, which is otherwise not forwarded.  Useful for static adapters.
+    Argument,
+}
+
+/// A method to forward calls to, and how.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use jreflection::*;
+/// use jreflection::delegate::{Delegate, Invoke, Receiver};
+///
+/// let bytes = std::fs::read("ListWrapper.class").unwrap();
+/// let mut class = transform::ClassFile::read(&mut &bytes[..]).unwrap();
+///
+/// // public int size() { return this.inner.size(); }
+/// let mut size = Delegate::new(Invoke::Interface, "java/util/List", "size", "()I");
+/// size.receiver(Receiver::Field { owner: "com/example/ListWrapper".into(), name: "inner".into(), descriptor: "Ljava/util/List;".into() });
+/// size.add_method(&mut class, 0x0001, "size", "()I").unwrap();
+///
+/// // public static long max(int a, int b) { return (long)Math.max(a, b); }
+/// Delegate::new(Invoke::Static, "java/lang/Math", "max", "(JJ)J").add_method(&mut class, 0x0009, "max", "(II)J").unwrap();
+///
+/// std::fs::write("ListWrapper.class", class.to_bytes().unwrap()).unwrap();
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Delegate {
+    invoke:     Invoke,
+    owner:      String,
+    name:       String,
+    descriptor: String,
+    receiver:   Receiver,
+}
+
+impl Delegate {
+    /// Forward to the method `owner.name descriptor` (e.g. `"java/lang/Runnable"`, `"run"`, `"()V"`), invoking it on
+    /// `this` unless it's [static](enum.Invoke.html#variant.Static).
+    pub fn new(invoke: Invoke, owner: impl Into<String>, name: impl Into<String>, descriptor: impl Into<String>) -> Self {
+        Self { invoke, owner: owner.into(), name: name.into(), descriptor: descriptor.into(), receiver: Receiver::This }
+    }
+
+    /// Invoke the target on `receiver` instead of `this`.  Ignored for [static](enum.Invoke.html#variant.Static) targets.
+    pub fn receiver(&mut self, receiver: Receiver) -> &mut Self {
+        self.receiver = receiver;
+        self
+    }
+
+    /// Generate the `Code` attribute of a delegating method with `descriptor`, adding any constants it needs to
+    /// `constants`.
+    pub fn code(&self, constants: &mut Constants, is_static: bool, descriptor: &str) -> io::Result<RawAttribute> {
+        let (source_args, source_return) = parse(descriptor)?;
+        let (target_args, target_return) = parse(self.descriptor.as_str())?;
+
+        let mut code = Vec::new();
+        let mut slot = if is_static { 0 } else { 1 };
+        let mut stack = 0u16;
+        let mut source_args = source_args.into_iter();
+
+        if self.invoke != Invoke::Static {
+            match &self.receiver {
+                Receiver::This => {
+                    if is_static { return invalid(format!("Static method {} can't invoke {} on this", descriptor, self.name)); }
+                    load(&mut code, 0, 4);
+                },
+                Receiver::Field { owner, name, descriptor: field } => {
+                    if is_static { return invalid(format!("Static method {} can't read instance field {}", descriptor, name)); }
+                    load(&mut code, 0, 4);
+                    let fieldref = fieldref(constants, owner, name, field)?;
+                    code.push(0xB4); // getfield
+                    code.extend_from_slice(&fieldref.to_be_bytes());
+                },
+                Receiver::Argument => {
+                    let (ty, raw) = match source_args.next() {
+                        Some(arg)   => arg,
+                        None        => return invalid(format!("Method {} has no argument to invoke {} on", descriptor, self.name)),
+                    };
+                    let owner = format!("L{};", self.owner);
+                    let receiver_type = Type::from_str(owner.as_str())?;
+                    load(&mut code, slot, kind(ty));
+                    slot += size(ty);
+                    convert(&mut code, constants, (ty, raw), (receiver_type, owner.as_str()))?;
+                },
+            }
+            stack += 1;
+        }
+
+        let source_args = source_args.collect::<Vec<_>>();
+        if source_args.len() != target_args.len() {
+            return invalid(format!("Forwarding {} argument(s) of {} to {}{}, which takes {}", source_args.len(), descriptor, self.name, self.descriptor, target_args.len()));
+        }
+        for (source, target) in source_args.into_iter().zip(target_args) {
+            load(&mut code, slot, kind(source.0));
+            slot += size(source.0);
+            convert(&mut code, constants, source, target)?;
+            stack += size(target.0);
+        }
+        if stack > 255 { return invalid(format!("Too many argument slots to invoke {}{}", self.name, self.descriptor)); }
+        let max_stack = stack.max(size(target_return.0)).max(size(source_return.0));
+
+        let class_index = constants.add_class(self.owner.as_str())?;
+        let name_and_type_index = constants.add_name_and_type(self.name.as_str(), self.descriptor.as_str())?;
+        let (opcode, method) = match self.invoke {
+            Invoke::Static      => (0xB8, Constant::Methodref { class_index, name_and_type_index }),
+            Invoke::Virtual     => (0xB6, Constant::Methodref { class_index, name_and_type_index }),
+            Invoke::Special     => (0xB7, Constant::Methodref { class_index, name_and_type_index }),
+            Invoke::Interface   => (0xB9, Constant::InterfaceMethodref { class_index, name_and_type_index }),
+        };
+        code.push(opcode);
+        code.extend_from_slice(&constants.add(method)?.to_be_bytes());
+        if self.invoke == Invoke::Interface { code.extend_from_slice(&[stack as u8, 0]); } // count, 0
+
+        match (source_return.0, target_return.0) {
+            (Type::Single(BasicType::Void), target) => {
+                match size(target) {
+                    0 => {},
+                    1 => code.push(0x57), // pop
+                    _ => code.push(0x58), // pop2
+                }
+                code.push(0xB1); // return
+            },
+            (_, Type::Single(BasicType::Void)) => return invalid(format!("Can't return {} from {}, which returns void", source_return.1, self.name)),
+            (source, _) => {
+                convert(&mut code, constants, target_return, source_return)?;
+                code.push(0xAC + kind(source)); // ireturn ...
+            },
+        }
+
+        if code.len() > 0xFFFF { return invalid(format!("Delegating method {} is too large", descriptor)); }
+        let mut info = Vec::with_capacity(12 + code.len());
+        info.extend_from_slice(&max_stack.to_be_bytes());
+        info.extend_from_slice(&slot.to_be_bytes());
+        info.extend_from_slice(&(code.len() as u32).to_be_bytes());
+        info.extend_from_slice(&code[..]);
+        info.extend_from_slice(&[0, 0, 0, 0]); // exception_table_length, attributes_count
+        Ok(RawAttribute { name: "Code".to_owned(), info })
+    }
+
+    /// Add a delegating method `name descriptor` to `class`, with `access_flags` such as `0x0001` (`ACC_PUBLIC`.)
+    /// Fails if `class` already has such a method.
+    pub fn add_method(&self, class: &mut ClassFile, access_flags: u16, name: &str, descriptor: &str) -> io::Result<()> {
+        for method in class.methods.iter() {
+            if method.name(&class.constants)? == name && method.descriptor(&class.constants)? == descriptor {
+                return invalid(format!("{} already has a method {}{}", class.name()?, name, descriptor));
+            }
+        }
+        let code = self.code(&mut class.constants, access_flags & 0x0008 != 0, descriptor)?;
+        let name_index = class.constants.add_utf8(name)?;
+        let descriptor_index = class.constants.add_utf8(descriptor)?;
+        class.methods.push(Member { access_flags, name_index, descriptor_index, attributes: vec![code] });
+        Ok(())
+    }
+}
+
+type Typed<'a> = (Type<'a>, &'a str);
+
+fn invalid<T>(message: String) -> io::Result<T> { Err(Error::new(ErrorKind::InvalidInput, message)) }
+
+/// Split a method descriptor into its argument and return types, each with the substring of `descriptor` it came from.
+fn parse(descriptor: &str) -> io::Result<(Vec<Typed<'_>>, Typed<'_>)> {
+    method::Descriptor::new(descriptor)?;
+    let end_paren = descriptor.rfind(')').unwrap_or(0); // Already validated by Descriptor::new
+    let mut remaining = &descriptor[1..end_paren];
+    let mut args = Vec::new();
+    while !remaining.is_empty() {
+        let before = remaining;
+        let ty = Type::read_next(&mut remaining)?;
+        args.push((ty, &before[..before.len() - remaining.len()]));
+    }
+    let ret = &descriptor[end_paren+1..];
+    Ok((args, (Type::from_str(ret)?, ret)))
+}
+
+/// The number of local variable or operand stack slots `ty` takes.
+fn size(ty: Type) -> u16 {
+    match ty {
+        Type::Single(BasicType::Void)                           => 0,
+        Type::Single(BasicType::Long)                           => 2,
+        Type::Single(BasicType::Double)                         => 2,
+        _                                                       => 1,
+    }
+}
+
+/// The offset of the `i`, `l`, `f`, `d`, or `a` variant of `iload`/`ireturn`/... for `ty`.
+fn kind(ty: Type) -> u8 {
+    match ty {
+        Type::Single(BasicType::Long)                           => 1,
+        Type::Single(BasicType::Float)                          => 2,
+        Type::Single(BasicType::Double)                         => 3,
+        Type::Single(BasicType::Class(_)) | Type::Array { .. }  => 4,
+        _                                                       => 0,
+    }
+}
+
+fn load(code: &mut Vec<u8>, slot: u16, kind: u8) {
+    match slot {
+        0 ..= 3     => code.push(0x1A + kind * 4 + slot as u8), // iload_0 ...
+        4 ..= 255   => code.extend_from_slice(&[0x15 + kind, slot as u8]),
+        _           => { code.extend_from_slice(&[0xC4, 0x15 + kind]); code.extend_from_slice(&slot.to_be_bytes()); }, // wide
+    }
+}
+
+fn fieldref(constants: &mut Constants, owner: &str, name: &str, descriptor: &str) -> io::Result<u16> {
+    let class_index = constants.add_class(owner)?;
+    let name_and_type_index = constants.add_name_and_type(name, descriptor)?;
+    constants.add(Constant::Fieldref { class_index, name_and_type_index })
+}
+
+/// Convert the value on top of the stack from `from` to `to`.
+fn convert(code: &mut Vec<u8>, constants: &mut Constants, from: Typed, to: Typed) -> io::Result<()> {
+    use self::BasicType::*;
+    if from.1 == to.1 { return Ok(()); }
+    if kind(from.0) == 4 && kind(to.0) == 4 {
+        if to.1 != "Ljava/lang/Object;" {
+            let name = match to.0 { Type::Single(Class(class::Id(name))) => name, _ => to.1 };
+            let class = constants.add_class(name)?;
+            code.push(0xC0); // checkcast
+            code.extend_from_slice(&class.to_be_bytes());
+        }
+        return Ok(());
+    }
+    let int_like = match from.0 { Type::Single(Byte) | Type::Single(Short) | Type::Single(Char) => Type::Single(Int), other => other };
+    let widen : &[u8] = match (int_like, to.0) {
+        (Type::Single(Int),   Type::Single(Int))    => &[], // byte, short, or char
+        (Type::Single(Int),   Type::Single(Long))   => &[0x85], // i2l
+        (Type::Single(Int),   Type::Single(Float))  => &[0x86], // i2f
+        (Type::Single(Int),   Type::Single(Double)) => &[0x87], // i2d
+        (Type::Single(Long),  Type::Single(Float))  => &[0x89], // l2f
+        (Type::Single(Long),  Type::Single(Double)) => &[0x8A], // l2d
+        (Type::Single(Float), Type::Single(Double)) => &[0x8D], // f2d
+        _ => return invalid(format!("Can't convert {} to {} without boxing or narrowing", from.1, to.1)),
+    };
+    code.extend_from_slice(widen);
+    Ok(())
+}
+
+#[test] fn delegate() {
+    let mut c = testing::ClassFile::new("com/example/Wrapper", Some("java/lang/Object"));
+    c.field(0x0012, "inner", "Ljava/util/List;", &[]);
+    let mut class = ClassFile::read(&mut &c.to_bytes()[..]).unwrap();
+
+    let mut size = Delegate::new(Invoke::Interface, "java/util/List", "size", "()I");
+    size.receiver(Receiver::Field { owner: "com/example/Wrapper".into(), name: "inner".into(), descriptor: "Ljava/util/List;".into() });
+    size.add_method(&mut class, 0x0001, "size", "()I").unwrap();
+    assert!(size.add_method(&mut class, 0x0001, "size", "()I").is_err());
+    Delegate::new(Invoke::Static, "java/lang/Math", "max", "(JJ)J").add_method(&mut class, 0x0009, "max", "(II)J").unwrap();
+    Delegate::new(Invoke::Virtual, "java/lang/Object", "toString", "()Ljava/lang/String;").receiver(Receiver::Argument).add_method(&mut class, 0x0009, "describe", "(Ljava/lang/Object;)Ljava/lang/CharSequence;").unwrap();
+    Delegate::new(Invoke::Virtual, "java/lang/String", "indexOf", "(Ljava/lang/String;I)I").receiver(Receiver::Argument).add_method(&mut class, 0x0009, "find", "(Ljava/lang/Object;Ljava/lang/Object;S)V").unwrap();
+    Delegate::new(Invoke::Special, "java/lang/Object", "hashCode", "()I").add_method(&mut class, 0x0001, "hash", "()D").unwrap();
+
+    let mut options = ParseOptions::new();
+    options.retain_code(true);
+    let read = Class::read_with(&mut &class.to_bytes().unwrap()[..], &options).unwrap();
+    let code = |index: usize| {
+        let code = read.methods[index].code().unwrap();
+        (code.max_stack, code.max_locals, code.instructions().map(|i| i.unwrap().opcode).collect::<Vec<_>>())
+    };
+    let references = |index: usize| read.methods[index].code().unwrap().references().unwrap().into_iter().map(|r| match r {
+        code::Reference::Class(class)                   => class,
+        code::Reference::Field { class, name, .. }      => format!("{}.{}", class, name),
+        code::Reference::Method { class, name, .. }     => format!("{}.{}", class, name),
+    }).collect::<Vec<_>>();
+
+    // aload_0, getfield, invokeinterface, ireturn
+    assert_eq!(code(0), (1, 1, vec![0x2A, 0xB4, 0xB9, 0xAC]));
+    assert_eq!(references(0), &["com/example/Wrapper.inner", "java/util/List.size"]);
+    // iload_0, i2l, iload_1, i2l, invokestatic, lreturn
+    assert_eq!(code(1), (4, 2, vec![0x1A, 0x85, 0x1B, 0x85, 0xB8, 0xAD]));
+    // aload_0, invokevirtual, checkcast, areturn
+    assert_eq!(code(2), (1, 1, vec![0x2A, 0xB6, 0xC0, 0xB0]));
+    assert_eq!(references(2), &["java/lang/Object.toString", "java/lang/CharSequence"]);
+    // aload_0, checkcast, aload_1, checkcast, iload_2, invokevirtual, pop, return
+    assert_eq!(code(3), (3, 3, vec![0x2A, 0xC0, 0x2B, 0xC0, 0x1C, 0xB6, 0x57, 0xB1]));
+    assert_eq!(references(3), &["java/lang/String", "java/lang/String", "java/lang/String.indexOf"]);
+    // aload_0, invokespecial, i2d, dreturn
+    assert_eq!(code(4), (2, 1, vec![0x2A, 0xB7, 0x87, 0xAF]));
+
+    // Mismatched arguments, narrowing, boxing, and missing receivers are errors
+    let mut constants = class.constants.clone();
+    let max = Delegate::new(Invoke::Static, "java/lang/Math", "max", "(II)I");
+    assert!(max.code(&mut constants, true, "(I)I").is_err());
+    assert!(max.code(&mut constants, true, "(JJ)J").is_err());
+    assert!(max.code(&mut constants, true, "(II)Ljava/lang/Integer;").is_err());
+    assert!(max.code(&mut constants, true, "(II)V").is_ok());
+    assert!(Delegate::new(Invoke::Virtual, "java/lang/Object", "hashCode", "()I").code(&mut constants, true, "()I").is_err());
+    assert!(Delegate::new(Invoke::Virtual, "java/lang/Object", "hashCode", "()V").code(&mut constants, false, "()I").is_err());
+}
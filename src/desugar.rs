@@ -0,0 +1,153 @@
+//! Awareness of D8 desugaring, which makes some APIs missing from older Android platforms usable anyway.
+//!
+//! D8's core library desugaring (`coreLibraryDesugaringEnabled`) rewrites references to APIs such as `java.time.*`
+//! to a bundled backport, as described by a `desugar_jdk_libs.json` configuration file.  A [Config] built from such
+//! files can be [applied](../coverage/struct.Coverage.html#method.apply_desugaring) to coverage results to avoid false
+//! "missing API" reports.
+//!
+//! Java 8 *language* desugaring (lambdas, default and static interface methods in your own code) doesn't need any
+//! configuration - it rewrites your code, not the APIs it uses.
+//!
+//! [Config]:   struct.Config.html
+
+use crate::json::Value;
+
+use std::collections::*;
+use std::io;
+use std::path::Path;
+
+
+
+/// The classes and members provided by desugaring.
+///
+/// # Examples
+///
+/// ```
+/// # use jreflection::desugar::Config;
+/// let config = Config::parse_json(r#"{
+///     "library_flags": [{
+///         "api_level_below_or_equal": 25,
+///         "rewrite_prefix": { "java.time.": "j$.time." },
+///         "retarget_lib_member": { "java.util.Date#toInstant": "java.util.DesugarDate" }
+///     }]
+/// }"#).unwrap();
+/// assert!( config.provides_class("java/time/Instant"));
+/// assert!( config.provides_member("java/util/Date", "toInstant"));
+/// assert!(!config.provides_member("java/util/Date", "getTime"));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Config {
+    prefixes:   BTreeSet<String>,                   // JNI path prefixes, e.g. "java/time/"
+    classes:    BTreeSet<String>,                   // JNI paths of classes provided in full
+    members:    BTreeMap<String, BTreeSet<String>>, // JNI path -> member names
+}
+
+impl Config {
+    /// Create an empty configuration, providing nothing.
+    pub fn new() -> Self { Self::default() }
+
+    /// Read a D8 `desugar_jdk_libs.json` style configuration file.
+    pub fn from_file(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::parse_json(&std::fs::read_to_string(path)?)
+    }
+
+    /// Parse a D8 `desugar_jdk_libs.json` style configuration.
+    ///
+    /// `rewrite_prefix` keys provide every class under a prefix, `emulate_interface` and `wrapper_conversion` entries
+    /// provide entire classes, and `retarget_lib_member` keys provide individual members.  Flags are read from the
+    /// `library_flags` and `program_flags` sections (regardless of `api_level_below_or_equal`) as well as the top level.
+    /// Unrecognized sections are ignored.
+    pub fn parse_json(json: &str) -> io::Result<Self> {
+        let root = Value::parse(json)?;
+        let mut config = Self::new();
+        let sections = Some(&root).into_iter()
+            .chain(root.get("library_flags").map(|f| f.as_array()).unwrap_or(&[]).iter())
+            .chain(root.get("program_flags").map(|f| f.as_array()).unwrap_or(&[]).iter());
+        for section in sections {
+            for (prefix, _) in section.get("rewrite_prefix").map(|p| p.as_object()).unwrap_or(&[]) {
+                config.add_prefix(&jni(prefix));
+            }
+            for (class, _) in section.get("emulate_interface").map(|p| p.as_object()).unwrap_or(&[]) {
+                config.add_class(&jni(class));
+            }
+            for class in section.get("wrapper_conversion").map(|p| p.as_array()).unwrap_or(&[]) {
+                if let Some(class) = class.as_str() { config.add_class(&jni(class)); }
+            }
+            for (member, _) in section.get("retarget_lib_member").map(|p| p.as_object()).unwrap_or(&[]) {
+                let mut parts = member.splitn(2, '#');
+                match (parts.next(), parts.next()) {
+                    (Some(class), Some(name)) => config.add_member(&jni(class), name),
+                    _ => return io_data_err!("Expected \"class#member\" in retarget_lib_member, got {:?}", member),
+                }
+            }
+        }
+        Ok(config)
+    }
+
+    /// Provide every class whose JNI path starts with `prefix`, such as `"java/time/"`.
+    pub fn add_prefix(&mut self, prefix: &str) { self.prefixes.insert(prefix.to_owned()); }
+
+    /// Provide an entire class (and all of its members), by JNI path.
+    pub fn add_class(&mut self, class: &str) { self.classes.insert(class.to_owned()); }
+
+    /// Provide every overload of the member `name` of `class`.
+    pub fn add_member(&mut self, class: &str, name: &str) { self.members.entry(class.to_owned()).or_default().insert(name.to_owned()); }
+
+    /// Add everything provided by `other` to this configuration.
+    pub fn merge(&mut self, other: &Config) {
+        self.prefixes.extend(other.prefixes.iter().cloned());
+        self.classes.extend(other.classes.iter().cloned());
+        for (class, names) in other.members.iter() {
+            self.members.entry(class.clone()).or_default().extend(names.iter().cloned());
+        }
+    }
+
+    /// Is the class with JNI path `class` provided in full by desugaring?
+    pub fn provides_class(&self, class: &str) -> bool {
+        self.classes.contains(class) || self.prefixes.iter().any(|p| class.starts_with(p.as_str()))
+    }
+
+    /// Is the member `name` of `class` provided by desugaring?
+    pub fn provides_member(&self, class: &str, name: &str) -> bool {
+        self.provides_class(class) || self.members.get(class).map(|names| names.contains(name)).unwrap_or(false)
+    }
+}
+
+/// Convert a dotted Java name (`"java.util.Map$Entry"`) to a JNI path (`"java/util/Map$Entry"`.)
+fn jni(name: &str) -> String { name.replace('.', "/") }
+
+#[test] fn d8_config() {
+    let config = Config::parse_json(r#"{
+        "configuration_format_version": 3,
+        "rewrite_prefix": { "java.util.stream.": "j$.util.stream." },
+        "library_flags": [
+            {
+                "api_level_below_or_equal": 25,
+                "rewrite_prefix": { "java.time.": "j$.time." },
+                "emulate_interface": { "java.util.Map$Entry": "j$.util.Map$Entry" },
+                "retarget_lib_member": { "java.util.Date#toInstant": "java.util.DesugarDate" }
+            }
+        ],
+        "program_flags": [
+            { "api_level_below_or_equal": 23, "wrapper_conversion": ["java.util.function.IntUnaryOperator"] }
+        ],
+        "shrinker_config": ["-keep class j$.** { *; }"]
+    }"#).unwrap();
+
+    assert!( config.provides_class("java/time/LocalDate"));
+    assert!( config.provides_class("java/util/stream/Stream"));
+    assert!( config.provides_class("java/util/Map$Entry"));
+    assert!( config.provides_class("java/util/function/IntUnaryOperator"));
+    assert!(!config.provides_class("java/util/Date"));
+    assert!( config.provides_member("java/util/Date", "toInstant"));
+    assert!( config.provides_member("java/time/Instant", "now"));
+    assert!(!config.provides_member("java/util/Date", "from"));
+
+    let mut merged = Config::new();
+    merged.add_member("java/util/Date", "from");
+    merged.merge(&config);
+    assert!(merged.provides_member("java/util/Date", "from"));
+    assert!(merged.provides_member("java/util/Date", "toInstant"));
+
+    assert!(Config::parse_json(r#"{ "retarget_lib_member": { "java.util.Date": "x" } }"#).is_err());
+}
@@ -0,0 +1,233 @@
+//! Deep links into official API documentation, such as docs.oracle.com and developer.android.com.
+//!
+//! Javadoc's URL scheme has changed over the years:  JDK 8 anchors methods as `#put-K-V-`, later JDKs as `#put(K,V)`
+//! under a per-module directory, and Android as `#put(K,%20V)` without a `.html` extension.  [Site] handles those
+//! details, so reports and generated bindings can link to the right page and member.
+//!
+//! [Site]: struct.Site.html
+
+use crate::{method, module, names, Method};
+use crate::signature::*;
+#[cfg(test)] use crate::testing;
+
+use std::collections::BTreeMap;
+use std::io;
+
+
+
+/// How a documentation site formats its member anchors.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Anchors {
+    /// JDK 8 and 9 javadoc:  `#put-K-V-`, `#valueOf-char:A-`, `#ArrayList-int-`.
+    Javadoc8,
+
+    /// JDK 10+ javadoc:  `#put(K,V)`, `#valueOf(char[])`, `#%3Cinit%3E(int)`.
+    Javadoc10,
+
+    /// developer.android.com:  `#put(K,%20V)`, `#valueOf(char[])`, `#ArrayList(int)`.
+    Android,
+}
+
+/// A documentation site to link to.
+///
+/// # Examples
+///
+/// ```
+/// # use jreflection::*;
+/// let jdk8 = docs::Site::oracle(8);
+/// assert_eq!(jdk8.class_url("java/util/Map$Entry"), "https://docs.oracle.com/javase/8/docs/api/java/util/Map.Entry.html");
+/// assert_eq!(jdk8.field_url("java/lang/Integer", "MAX_VALUE"), "https://docs.oracle.com/javase/8/docs/api/java/lang/Integer.html#MAX_VALUE");
+///
+/// let mut jdk11 = docs::Site::oracle(11);
+/// jdk11.module("java/util", "java.base");
+/// assert_eq!(jdk11.class_url("java/util/Map"), "https://docs.oracle.com/en/java/javase/11/docs/api/java.base/java/util/Map.html");
+///
+/// let android = docs::Site::android();
+/// assert_eq!(android.class_url("android/view/View$OnClickListener"), "https://developer.android.com/reference/android/view/View.OnClickListener");
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Site {
+    base:       String,
+    extension:  &'static str,
+    anchors:    Anchors,
+    modules:    Option<BTreeMap<String, String>>, // package -> module, if the site has per-module directories
+}
+
+impl Site {
+    /// A site rooted at `base` (such as `"https://example.com/javadoc/"`) with pages named `java/util/Map.Entry.html`.
+    pub fn new(base: impl Into<String>, anchors: Anchors) -> Self {
+        let mut base = base.into();
+        if !base.ends_with('/') { base.push('/'); }
+        Self { base, extension: ".html", anchors, modules: None }
+    }
+
+    /// The official docs for Java SE `version` on docs.oracle.com.
+    ///
+    /// JDK 11+ docs are organized by module:  register the modules of packages with [module](#method.module) or
+    /// [modules](#method.modules), or classes in unknown modules will link to the site root.
+    pub fn oracle(version: u32) -> Self {
+        match version {
+            0..=9   => Self::new(format!("https://docs.oracle.com/javase/{}/docs/api/", version), Anchors::Javadoc8),
+            10      => Self::new("https://docs.oracle.com/javase/10/docs/api/", Anchors::Javadoc10),
+            _       => Self { modules: Some(BTreeMap::new()), ..Self::new(format!("https://docs.oracle.com/en/java/javase/{}/docs/api/", version), Anchors::Javadoc10) },
+        }
+    }
+
+    /// The Android SDK reference on developer.android.com.
+    pub fn android() -> Self {
+        Self { extension: "", ..Self::new("https://developer.android.com/reference/", Anchors::Android) }
+    }
+
+    /// Record that `package` (such as `"java/util"`) is documented under `module` (such as `"java.base"`.)  Ignored if
+    /// the site isn't organized by module.
+    pub fn module(&mut self, package: &str, module: &str) -> &mut Self {
+        if let Some(modules) = self.modules.as_mut() { modules.insert(package.replace('.', "/"), module.to_owned()); }
+        self
+    }
+
+    /// Record the module of every package of `graph`.  Ignored if the site isn't organized by module.
+    pub fn modules(&mut self, graph: &module::ModuleGraph) -> &mut Self {
+        for module in graph.modules() {
+            for package in module.packages.iter() { self.module(package, &module.name); }
+        }
+        self
+    }
+
+    /// The page documenting the class at JNI path `class`.  Nested classes get their own pages, such as `Map.Entry.html`.
+    pub fn class_url(&self, class: &str) -> String {
+        let slash = class.rfind('/').map(|s| s+1).unwrap_or(0);
+        let mut url = self.base.clone();
+        if let Some(module) = self.modules.as_ref().and_then(|m| m.get(&class[..slash.saturating_sub(1)])) {
+            url.push_str(module);
+            url.push('/');
+        }
+        url.push_str(&class[..slash]);
+        url.push_str(&source_name(&class[slash..]));
+        url.push_str(self.extension);
+        url
+    }
+
+    /// A link to field `field` of the class at JNI path `class`.
+    pub fn field_url(&self, class: &str, field: &str) -> String {
+        format!("{}#{}", self.class_url(class), field)
+    }
+
+    /// A link to `method` of the class at JNI path `class`.  Uses the method's generic signature if it has one, as
+    /// javadoc anchors mention type variables (`#put(K,V)`) rather than their erasures.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use jreflection::*;
+    /// let src = Source::from_jdk_dir(r"C:\Program Files\AdoptOpenJDK\jdk-8.0.232.09-hotspot").unwrap();
+    /// let map = src.read_class("java/util/Map").unwrap();
+    /// let put = map.methods.iter().find(|m| m.name == "put").unwrap();
+    /// assert_eq!(docs::Site::oracle(8).method_url(map.path.as_str(), put).unwrap(), "https://docs.oracle.com/javase/8/docs/api/java/util/Map.html#put-K-V-");
+    /// ```
+    pub fn method_url(&self, class: &str, method: &Method) -> io::Result<String> {
+        let signature = MethodSignature::parse(method.signature_str().unwrap_or(method.descriptor_str()))?;
+        let varargs = method.flags.contains(method::Flags::VARARGS);
+        let last = signature.parameters.len().wrapping_sub(1);
+        let parameters = signature.parameters.iter().enumerate().map(|(i, p)| self.parameter(p, varargs && i == last)).collect::<Vec<_>>();
+
+        let name = if !method.is_constructor() {
+            method.name.as_str()
+        } else if self.anchors == Anchors::Javadoc10 {
+            "%3Cinit%3E"
+        } else {
+            let slash = class.rfind('/').map(|s| s+1).unwrap_or(0);
+            names::simple_name(class).unwrap_or(&class[slash..])
+        };
+
+        let anchor = match self.anchors {
+            Anchors::Javadoc8   => format!("{}-{}-", name, parameters.join("-")),
+            Anchors::Javadoc10  => format!("{}({})", name, parameters.join(",")),
+            Anchors::Android    => format!("{}({})", name, parameters.join(",%20")),
+        };
+        Ok(format!("{}#{}", self.class_url(class), anchor))
+    }
+
+    fn parameter(&self, ty: &Type, varargs: bool) -> String {
+        match ty {
+            Type::Base(base)            => base_name(*base).to_owned(),
+            Type::Class(class)          => {
+                let path = class.erasure();
+                let slash = path.rfind('/').map(|s| s+1).unwrap_or(0);
+                format!("{}{}", path[..slash].replace('/', "."), source_name(&path[slash..]))
+            },
+            Type::TypeVariable(name)    => name.clone(),
+            Type::Array(element)        => {
+                let suffix = match (varargs, self.anchors) { (true, _) => "...", (false, Anchors::Javadoc8) => ":A", (false, _) => "[]" };
+                format!("{}{}", self.parameter(element, false), suffix)
+            },
+        }
+    }
+}
+
+/// `Map$Entry` -> `Map.Entry`, leaving `$` alone in names that aren't nested classes (such as `$Proxy5`.)
+fn source_name(name: &str) -> String {
+    match names::simple_name(name) {
+        Some(simple) if simple.len() < name.len() && !name.starts_with('$') => name.replace('$', "."),
+        _ => name.to_owned(),
+    }
+}
+
+fn base_name(base: BaseType) -> &'static str {
+    match base {
+        BaseType::Byte      => "byte",
+        BaseType::Char      => "char",
+        BaseType::Double    => "double",
+        BaseType::Float     => "float",
+        BaseType::Int       => "int",
+        BaseType::Long      => "long",
+        BaseType::Short     => "short",
+        BaseType::Boolean   => "boolean",
+        BaseType::Void      => "void",
+    }
+}
+
+#[test] fn urls() {
+    let mut c = testing::ClassFile::new("java/util/Map", Some("java/lang/Object"));
+    let signature = |class: &mut testing::ClassFile, sig: &str| { let info = testing::be16(class.utf8(sig)); class.attribute("Signature", &info) };
+    let put = signature(&mut c, "(TK;TV;)TV;");
+    c.method(0x0401, "put", "(Ljava/lang/Object;Ljava/lang/Object;)Ljava/lang/Object;", &[put]);
+    c.method(0x0009, "valueOf", "([CI)Ljava/lang/String;", &[]);
+    let as_list = signature(&mut c, "<T:Ljava/lang/Object;>([TT;)Ljava/util/List<TT;>;");
+    c.method(0x0089, "asList", "([Ljava/lang/Object;)Ljava/util/List;", &[as_list]);
+    c.method(0x0001, "<init>", "(Ljava/util/Map$Entry;)V", &[]);
+    c.method(0x0001, "size", "()I", &[]);
+    let class = c.read();
+    let urls = |site: &Site| class.methods.iter().map(|m| site.method_url(class.path.as_str(), m).unwrap()).collect::<Vec<_>>();
+
+    assert_eq!(urls(&Site::oracle(8)), &[
+        "https://docs.oracle.com/javase/8/docs/api/java/util/Map.html#put-K-V-",
+        "https://docs.oracle.com/javase/8/docs/api/java/util/Map.html#valueOf-char:A-int-",
+        "https://docs.oracle.com/javase/8/docs/api/java/util/Map.html#asList-T...-",
+        "https://docs.oracle.com/javase/8/docs/api/java/util/Map.html#Map-java.util.Map.Entry-",
+        "https://docs.oracle.com/javase/8/docs/api/java/util/Map.html#size--",
+    ]);
+
+    let mut jdk11 = Site::oracle(11);
+    jdk11.module("java.util", "java.base");
+    assert_eq!(urls(&jdk11), &[
+        "https://docs.oracle.com/en/java/javase/11/docs/api/java.base/java/util/Map.html#put(K,V)",
+        "https://docs.oracle.com/en/java/javase/11/docs/api/java.base/java/util/Map.html#valueOf(char[],int)",
+        "https://docs.oracle.com/en/java/javase/11/docs/api/java.base/java/util/Map.html#asList(T...)",
+        "https://docs.oracle.com/en/java/javase/11/docs/api/java.base/java/util/Map.html#%3Cinit%3E(java.util.Map.Entry)",
+        "https://docs.oracle.com/en/java/javase/11/docs/api/java.base/java/util/Map.html#size()",
+    ]);
+    assert_eq!(jdk11.class_url("javax/swing/JFrame"), "https://docs.oracle.com/en/java/javase/11/docs/api/javax/swing/JFrame.html");
+
+    assert_eq!(urls(&Site::android()), &[
+        "https://developer.android.com/reference/java/util/Map#put(K,%20V)",
+        "https://developer.android.com/reference/java/util/Map#valueOf(char[],%20int)",
+        "https://developer.android.com/reference/java/util/Map#asList(T...)",
+        "https://developer.android.com/reference/java/util/Map#Map(java.util.Map.Entry)",
+        "https://developer.android.com/reference/java/util/Map#size()",
+    ]);
+
+    let custom = Site::new("https://example.com/javadoc", Anchors::Javadoc10);
+    assert_eq!(custom.class_url("Default$Inner"), "https://example.com/javadoc/Default.Inner.html");
+    assert_eq!(custom.class_url("com/example/$Proxy5"), "https://example.com/javadoc/com/example/$Proxy5.html");
+    assert_eq!(custom.field_url("com/example/Foo", "BAR"), "https://example.com/javadoc/com/example/Foo.html#BAR");
+}
@@ -1,12 +1,18 @@
 //! [Java SE 7 &sect; 4.5](https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.5):  Parsing APIs and structures for class fields.
 
-use crate::*;
+use crate::{attributes, class, signature, CustomAttribute, ParseOptions, Warning};
+use crate::attributes::Attribute;
+use crate::constants::Constants;
 use crate::io::be::*;
+#[cfg(feature = "annotations")] use crate::annotation;
+#[cfg(feature = "annotations")] use crate::stability;
 
 use bitflags::bitflags;
 
 use std::fmt::{self, Debug, Display, Formatter};
+use std::hash::{Hash, Hasher};
 use std::io::{self, Read};
+use std::sync::Arc;
 
 
 
@@ -42,13 +48,46 @@ impl Flags {
 }
 
 /// [Java SE 7 &sect; 4.7.2](https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.7.2): The value of compile time constant fields
-#[derive(Clone, Debug, PartialEq, PartialOrd)]
+///
+/// Equality and hashing compare `Float`s and `Double`s bit-for-bit, so `NaN`s equal themselves and `0.0` differs from
+/// `-0.0`.  Ordering still compares them numerically.
+#[derive(Clone, Debug, PartialOrd)]
 pub enum Constant {
     Integer(i32),
     Long(i64),
     Float(f32),
     Double(f64),
     String(Option<String>),
+    #[doc(hidden)] _NonExhaustive,
+}
+
+impl PartialEq for Constant {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Constant::Integer(a),  Constant::Integer(b))   => a == b,
+            (Constant::Long(a),     Constant::Long(b))      => a == b,
+            (Constant::Float(a),    Constant::Float(b))     => a.to_bits() == b.to_bits(),
+            (Constant::Double(a),   Constant::Double(b))    => a.to_bits() == b.to_bits(),
+            (Constant::String(a),   Constant::String(b))    => a == b,
+            _                                               => false,
+        }
+    }
+}
+
+impl Eq for Constant {}
+
+impl Hash for Constant {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            Constant::Integer(value)    => value.hash(state),
+            Constant::Long(value)       => value.hash(state),
+            Constant::Float(value)      => value.to_bits().hash(state),
+            Constant::Double(value)     => value.to_bits().hash(state),
+            Constant::String(value)     => value.hash(state),
+            Constant::_NonExhaustive    => {},
+        }
+    }
 }
 
 impl Display for Constant {
@@ -69,6 +108,7 @@ impl Display for Constant {
 
             Constant::String(Some(value))   => Debug::fmt(value, fmt),
             Constant::String(None)          => write!(fmt, "panic!(\"Java string constant contains invalid 'Modified UTF8'\")"),
+            Constant::_NonExhaustive        => write!(fmt, "panic!(\"unknown Java constant\")"),
         }
     }
 }
@@ -81,6 +121,15 @@ pub struct Field {
     pub flags:      Flags,
     pub name:       String,
     descriptor:     String,
+    signature:      Option<String>,
+    synthetic_attribute:    bool,
+    custom_attributes:      Vec<CustomAttribute>,
+    attribute_names:        Vec<String>,
+    unknown_attributes:     Vec<attributes::RawAttribute>,
+    #[cfg(feature = "annotations")] visible_annotations:    Vec<annotation::Annotation>,
+    #[cfg(feature = "annotations")] invisible_annotations:  Vec<annotation::Annotation>,
+    #[cfg(feature = "annotations")] visible_type_annotations:   Vec<annotation::TypeAnnotation>,
+    #[cfg(feature = "annotations")] invisible_type_annotations: Vec<annotation::TypeAnnotation>,
     pub deprecated: bool,
     pub constant:   Option<Constant>,
     _incomplete:    (),
@@ -95,6 +144,15 @@ impl Field {
             flags,
             name,
             descriptor,
+            signature: None,
+            synthetic_attribute: false,
+            custom_attributes: Vec::new(),
+            attribute_names:    Vec::new(),
+            unknown_attributes: Vec::new(),
+            #[cfg(feature = "annotations")] visible_annotations: Vec::new(),
+            #[cfg(feature = "annotations")] invisible_annotations: Vec::new(),
+            #[cfg(feature = "annotations")] visible_type_annotations: Vec::new(),
+            #[cfg(feature = "annotations")] invisible_type_annotations: Vec::new(),
             deprecated: false,
             constant: None,
             _incomplete: (),
@@ -103,19 +161,156 @@ impl Field {
 
     pub fn descriptor_str(&self) -> &str { self.descriptor.as_str() }
     pub fn descriptor(&self) -> Descriptor { Descriptor::from_str(self.descriptor.as_str()).unwrap() } // Was already validated in Field::new / Field::read_one
-
+    pub fn signature_str(&self) -> Option<&str> { self.signature.as_ref().map(|s| s.as_str()) }
+    pub fn signature(&self) -> io::Result<Option<signature::Type>> { self.signature_str().map(signature::Type::parse).transpose() }
+
+    /// Get the attributes of this field decoded by decoders registered with [ParseOptions::register_attribute](../struct.ParseOptions.html#method.register_attribute).
+    pub fn custom_attributes(&self) -> &[CustomAttribute] { &self.custom_attributes[..] }
+
+    /// The names of the attributes of this field, in classfile order.
+    ///
+    /// This includes attributes jreflection doesn't otherwise understand, such as Kotlin or AspectJ attributes.
+    pub fn attribute_names(&self) -> impl Iterator<Item = &str> { self.attribute_names.iter().map(|n| n.as_str()) }
+
+    /// The names of any attributes of this field that jreflection didn't recognize.
+    pub fn unknown_attribute_names(&self) -> impl Iterator<Item = &str> { self.unknown_attributes.iter().map(|a| a.name.as_str()) }
+
+    /// Any attributes of this field that jreflection didn't recognize, undecoded.
+    pub fn unknown_attributes(&self) -> &[attributes::RawAttribute] { &self.unknown_attributes[..] }
+
+    /// The type descriptors of the annotations of this field, visible and invisible at runtime alike, such as `"Lcom/google/common/annotations/Beta;"`.
+    #[cfg(feature = "annotations")]
+    pub fn annotation_types(&self) -> impl Iterator<Item = &str> { self.annotations().map(|a| a.descriptor.as_str()) }
+
+    /// The annotations of this field, visible then invisible at runtime, with their elements.  See [Annotation](../annotation/struct.Annotation.html).
+    #[cfg(feature = "annotations")]
+    pub fn annotations(&self) -> impl Iterator<Item = &annotation::Annotation> { self.visible_annotations.iter().chain(self.invisible_annotations.iter()) }
+
+    /// The annotations of this field that are visible at runtime, with their elements.  See [Annotation](../annotation/struct.Annotation.html).
+    #[cfg(feature = "annotations")]
+    pub fn visible_annotations(&self) -> &[annotation::Annotation] { &self.visible_annotations[..] }
+
+    /// The annotations of this field that are only retained in the classfile, such as `@Nullable` or `@Beta`.
+    #[cfg(feature = "annotations")]
+    pub fn invisible_annotations(&self) -> &[annotation::Annotation] { &self.invisible_annotations[..] }
+
+    /// The annotations on type uses within this field's declaration (JSR 308), visible then invisible at runtime.  See [TypeAnnotation](../annotation/struct.TypeAnnotation.html).
+    #[cfg(feature = "annotations")]
+    pub fn type_annotations(&self) -> impl Iterator<Item = &annotation::TypeAnnotation> { self.visible_type_annotations.iter().chain(self.invisible_type_annotations.iter()) }
+
+    /// The annotations on type uses within this field's declaration that are visible at runtime.
+    #[cfg(feature = "annotations")]
+    pub fn visible_type_annotations(&self) -> &[annotation::TypeAnnotation] { &self.visible_type_annotations[..] }
+
+    /// The annotations on type uses within this field's declaration that are only retained in the classfile.
+    #[cfg(feature = "annotations")]
+    pub fn invisible_type_annotations(&self) -> &[annotation::TypeAnnotation] { &self.invisible_type_annotations[..] }
+
+    /// How stable this field is declared to be by its own annotations.  See [Stability](../stability/enum.Stability.html).
+    #[cfg(feature = "annotations")]
+    pub fn stability(&self) -> stability::Stability { stability::Stability::of_annotations(self.annotation_types()) }
+
+    /// The field is declared `public`, and may be accessed from outside its package.
+    ///
+    /// ```
+    /// # use jreflection::field::*;
+    /// let field = Field::new(Flags::PUBLIC, "value".to_string(), "I".to_string()).unwrap();
+    /// assert!(field.is_public());
+    /// ```
     pub fn is_public(&self)     -> bool { self.flags.contains(Flags::PUBLIC) }
+
+    /// The field is declared `private`, and may only be accessed within the defining class.
+    ///
+    /// ```
+    /// # use jreflection::field::*;
+    /// let field = Field::new(Flags::PRIVATE, "value".to_string(), "I".to_string()).unwrap();
+    /// assert!(field.is_private());
+    /// ```
     pub fn is_private(&self)    -> bool { self.flags.contains(Flags::PRIVATE) }
+
+    /// The field is declared `protected`, and may be accessed from subclasses.
+    ///
+    /// ```
+    /// # use jreflection::field::*;
+    /// let field = Field::new(Flags::PROTECTED, "value".to_string(), "I".to_string()).unwrap();
+    /// assert!(field.is_protected());
+    /// ```
     pub fn is_protected(&self)  -> bool { self.flags.contains(Flags::PROTECTED) }
+
+    /// The field is declared `static`, and belongs to the class rather than an instance.
+    ///
+    /// ```
+    /// # use jreflection::field::*;
+    /// let field = Field::new(Flags::PUBLIC | Flags::STATIC, "value".to_string(), "I".to_string()).unwrap();
+    /// assert!(field.is_static());
+    /// ```
     pub fn is_static(&self)     -> bool { self.flags.contains(Flags::STATIC) }
+
+    /// The field is declared `final`, and may not be reassigned after initialization.
+    ///
+    /// ```
+    /// # use jreflection::field::*;
+    /// let field = Field::new(Flags::PRIVATE | Flags::FINAL, "value".to_string(), "I".to_string()).unwrap();
+    /// assert!(field.is_final());
+    /// ```
     pub fn is_final(&self)      -> bool { self.flags.contains(Flags::FINAL) }
+
+    /// The field is declared `volatile`, and may not be cached.
+    ///
+    /// ```
+    /// # use jreflection::field::*;
+    /// let field = Field::new(Flags::PRIVATE | Flags::VOLATILE, "value".to_string(), "I".to_string()).unwrap();
+    /// assert!(field.is_volatile());
+    /// ```
     pub fn is_volatile(&self)   -> bool { self.flags.contains(Flags::VOLATILE) }
+
+    /// The field is declared `transient`, and isn't serialized.
+    ///
+    /// ```
+    /// # use jreflection::field::*;
+    /// let field = Field::new(Flags::PRIVATE | Flags::TRANSIENT, "value".to_string(), "I".to_string()).unwrap();
+    /// assert!(field.is_transient());
+    /// ```
     pub fn is_transient(&self)  -> bool { self.flags.contains(Flags::TRANSIENT) }
-    pub fn is_synthetic(&self)  -> bool { self.flags.contains(Flags::SYNTHETIC) }
+
+    /// The field is synthetic (such as an inner class's `this$0`), and is not present in the source code.
+    /// This checks both the `ACC_SYNTHETIC` flag and the `Synthetic` attribute used by older compilers.
+    ///
+    /// ```
+    /// # use jreflection::field::*;
+    /// let field = Field::new(Flags::FINAL | Flags::SYNTHETIC, "value".to_string(), "I".to_string()).unwrap();
+    /// assert!(field.is_synthetic());
+    /// ```
+    pub fn is_synthetic(&self)  -> bool { self.flags.contains(Flags::SYNTHETIC) || self.synthetic_attribute }
+
+    /// The field holds an enum constant.
+    ///
+    /// ```
+    /// # use jreflection::field::*;
+    /// let field = Field::new(Flags::PUBLIC | Flags::STATIC | Flags::FINAL | Flags::ENUM, "value".to_string(), "I".to_string()).unwrap();
+    /// assert!(field.is_enum());
+    /// ```
     pub fn is_enum(&self)       -> bool { self.flags.contains(Flags::ENUM) }
 
+    /// The field is a compile time constant:  `static final`, with a [ConstantValue](https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.7.2).
+    ///
+    /// ```
+    /// # use jreflection::field::*;
+    /// let mut field = Field::new(Flags::PUBLIC | Flags::STATIC | Flags::FINAL, "MAX_VALUE".to_string(), "I".to_string()).unwrap();
+    /// assert!(!field.is_constant());
+    /// field.constant = Some(Constant::Integer(0x7fffffff));
+    /// assert!(field.is_constant());
+    /// ```
     pub fn is_constant(&self)   -> bool { self.is_final() && self.is_static() && self.constant.is_some() }
 
+    /// Try to determine the access keyword for this field.
+    /// One of `Some("public")`, `Some("protected")`, `Some("private")`, or `None` (package private.)
+    ///
+    /// ```
+    /// # use jreflection::field::*;
+    /// let field = Field::new(Flags::PROTECTED, "value".to_string(), "I".to_string()).unwrap();
+    /// assert_eq!(field.access(), Some("protected"));
+    /// ```
     pub fn access(&self) -> Option<&'static str> {
         if      self.is_private()   { Some("private") }
         else if self.is_protected() { Some("protected") }
@@ -123,7 +318,7 @@ impl Field {
         else                        { None }
     }
 
-    pub(crate) fn read_one(read: &mut impl Read, constants: &Constants) -> io::Result<Self> {
+    pub(crate) fn read_one(read: &mut impl Read, constants: &Arc<Constants>, options: &ParseOptions, warnings: &mut Vec<Warning>) -> io::Result<Self> {
         let flags               = Flags::read(read)?;
         let name                = constants.get_utf8(read_u2(read)?)?.to_owned();
         let descriptor          = constants.get_utf8(read_u2(read)?)?.to_owned();
@@ -132,35 +327,86 @@ impl Field {
         Descriptor::from_str(descriptor.as_str())?;
 
         let mut deprecated      = false;
+        let mut signature       = None;
+        let mut synthetic_attribute = false;
+        let mut custom_attributes = Vec::new();
+        let mut attribute_names = Vec::new();
+        let mut unknown_attributes = Vec::new();
+        #[cfg(feature = "annotations")] let mut visible_annotations = Vec::new();
+        #[cfg(feature = "annotations")] let mut invisible_annotations = Vec::new();
+        #[cfg(feature = "annotations")] let mut visible_type_annotations = Vec::new();
+        #[cfg(feature = "annotations")] let mut invisible_type_annotations = Vec::new();
         let mut constant        = None;
+        let first_warning = warnings.len();
+        let mut seen = Vec::new();
         for _ in 0..attributes_count {
-            match Attribute::read(read, constants)? {
+            let (name, attribute) = Attribute::read_named(read, constants, options, &mut seen, warnings)?;
+            attribute_names.push(name.to_owned());
+            let attribute = match attribute { Some(a) => a, None => continue }; // An ignored duplicate
+            match attribute {
                 Attribute::Deprecated { .. }    => { deprecated = true; },
+                Attribute::Signature(value)     => { signature = Some(value); },
+                Attribute::Synthetic { .. }     => { synthetic_attribute = true; },
+                Attribute::Custom(custom)       => { custom_attributes.push(custom); },
                 Attribute::ConstantValue(value) => { constant = Some(value); },
+                Attribute::Unknown(info)        => { unknown_attributes.push(attributes::RawAttribute { name: name.to_owned(), info }); },
+                #[cfg(feature = "annotations")] Attribute::RuntimeVisibleAnnotations(a)     => { visible_annotations.extend(a); },
+                #[cfg(feature = "annotations")] Attribute::RuntimeInvisibleAnnotations(a)   => { invisible_annotations.extend(a); },
+                #[cfg(feature = "annotations")] Attribute::RuntimeVisibleTypeAnnotations(a)     => { visible_type_annotations.extend(a); },
+                #[cfg(feature = "annotations")] Attribute::RuntimeInvisibleTypeAnnotations(a)   => { invisible_type_annotations.extend(a); },
                 _ => {},
             }
         }
 
+        for warning in warnings[first_warning..].iter_mut() { warning.member = Some(format!("{}:{}", name, descriptor)); }
+
         Ok(Self{
             flags,
             name,
             descriptor,
+            signature,
+            synthetic_attribute,
+            custom_attributes,
+            attribute_names,
+            unknown_attributes,
+            #[cfg(feature = "annotations")] visible_annotations,
+            #[cfg(feature = "annotations")] invisible_annotations,
+            #[cfg(feature = "annotations")] visible_type_annotations,
+            #[cfg(feature = "annotations")] invisible_type_annotations,
             deprecated,
             constant,
             _incomplete: (),
         })
     }
 
-    pub(crate) fn read_list(read: &mut impl Read, constants: &Constants) -> io::Result<Vec<Self>> {
+    pub(crate) fn read_list(read: &mut impl Read, constants: &Arc<Constants>, options: &ParseOptions, warnings: &mut Vec<Warning>) -> io::Result<Vec<Self>> {
         let n = read_u2(read)? as usize;
         let mut fields = Vec::with_capacity(n);
         for _ in 0..n {
-            fields.push(Self::read_one(read, constants)?);
+            fields.push(Self::read_one(read, constants, options, warnings)?);
         }
         Ok(fields)
     }
 }
 
+/// A compact, Java-like declaration, such as `public static final int MAX_VALUE`.
+///
+/// ```
+/// # use jreflection::field::*;
+/// let field = Field::new(Flags::PRIVATE | Flags::FINAL, "names".to_string(), "[Ljava/lang/String;".to_string()).unwrap();
+/// assert_eq!(field.to_string(), "private final java.lang.String[] names");
+/// ```
+impl Display for Field {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        if let Some(access) = self.access() { write!(fmt, "{} ", access)?; }
+        if self.is_static()     { write!(fmt, "static ")?; }
+        if self.is_final()      { write!(fmt, "final ")?; }
+        if self.is_volatile()   { write!(fmt, "volatile ")?; }
+        if self.is_transient()  { write!(fmt, "transient ")?; }
+        write!(fmt, "{} {}", self.descriptor(), self.name)
+    }
+}
+
 
 
 /// The [type erased], non-array, value or class portion of a type (e.g. `int` or `java.lang.List`, but not `Object[]`)
@@ -191,6 +437,44 @@ pub enum Descriptor<'a> {
     Array { levels: usize, inner: BasicType<'a> },
 }
 
+/// The Java name of the type, such as `int`, `void`, or `java.util.Map$Entry`.
+impl Display for BasicType<'_> {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        match self {
+            BasicType::Byte         => write!(fmt, "byte"),
+            BasicType::Char         => write!(fmt, "char"),
+            BasicType::Double       => write!(fmt, "double"),
+            BasicType::Float        => write!(fmt, "float"),
+            BasicType::Int          => write!(fmt, "int"),
+            BasicType::Long         => write!(fmt, "long"),
+            BasicType::Class(id)    => write!(fmt, "{}", id.0.replace('/', ".")),
+            BasicType::Short        => write!(fmt, "short"),
+            BasicType::Boolean      => write!(fmt, "boolean"),
+            BasicType::Void         => write!(fmt, "void"),
+        }
+    }
+}
+
+/// The Java name of the type, such as `int[][]` or `java.lang.String`.
+///
+/// ```
+/// # use jreflection::method::Descriptor;
+/// let desc = Descriptor::new("([[ILjava/lang/String;)V").unwrap();
+/// assert_eq!(desc.arguments().map(|arg| arg.to_string()).collect::<Vec<_>>(), &["int[][]", "java.lang.String"]);
+/// ```
+impl Display for Descriptor<'_> {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        match self {
+            Descriptor::Single(inner)           => write!(fmt, "{}", inner),
+            Descriptor::Array { levels, inner } => {
+                write!(fmt, "{}", inner)?;
+                for _ in 0..*levels { write!(fmt, "[]")?; }
+                Ok(())
+            },
+        }
+    }
+}
+
 impl<'a> Descriptor<'a> {
     /// Consume a Descriptor from a string.  Will set `remaining` to parse the *remainder* of the string.
     pub(crate) fn read_next(remaining: &mut &'a str) -> io::Result<Descriptor<'a>> {
@@ -0,0 +1,168 @@
+//! Policies for which classes and members count as a library's API, for API extraction such as
+//! [coverage::compare_filtered](../coverage/fn.compare_filtered.html) and
+//! [changelog::summarize_with](../changelog/fn.summarize_with.html).
+
+use crate::{Class, Field, Method};
+#[cfg(all(test, feature = "annotations"))] use crate::testing;
+
+use std::collections::*;
+
+
+
+/// Which classes and members count as API.
+///
+/// By default, that's `public` classes, and their non-synthetic `public` and `protected` members.  Annotations can
+/// further exclude classes and members (e.g. `@VisibleForTesting`), or restrict the API to only annotated classes
+/// and members (e.g. `@PublicApi`.)  Annotations on a class apply to all of its members.
+///
+/// Annotations can be given as type descriptors (`"Lcom/example/PublicApi;"`), JNI paths (`"com/example/PublicApi"`),
+/// or Java names (`"com.example.PublicApi"` - use `$` for nested annotations, as in `"org.jetbrains.annotations.ApiStatus$Internal"`.)
+///
+/// # Examples
+///
+/// ```no_run
+/// # use jreflection::*;
+/// # #[cfg(all(feature = "jar", feature = "annotations"))] {
+/// let mut filter = filter::Filter::new();
+/// filter.exclude_annotated("com.google.common.annotations.VisibleForTesting");
+/// filter.protected(false);
+///
+/// let old = Source::from_jar("library-1.0.jar").unwrap();
+/// let new = Source::from_jar("library-1.1.jar").unwrap();
+/// println!("{}", changelog::summarize_with(&old, &new, &filter).unwrap());
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Filter {
+    protected:      bool,
+    synthetic:      bool,
+    exclude:        BTreeSet<String>,
+    include_only:   BTreeSet<String>,
+}
+
+impl Default for Filter {
+    fn default() -> Self {
+        Self {
+            protected:      true,
+            synthetic:      false,
+            exclude:        BTreeSet::new(),
+            include_only:   BTreeSet::new(),
+        }
+    }
+}
+
+impl Filter {
+    /// Create the default filter:  `public` classes, and their non-synthetic `public` and `protected` members.
+    pub fn new() -> Self { Self::default() }
+
+    /// Count `protected` members as API (the default), or only `public` ones.
+    pub fn protected(&mut self, include: bool) -> &mut Self {
+        self.protected = include;
+        self
+    }
+
+    /// Count synthetic members (such as bridge methods) as API, or not (the default.)
+    pub fn synthetic(&mut self, include: bool) -> &mut Self {
+        self.synthetic = include;
+        self
+    }
+
+    /// Exclude classes and members annotated with `annotation`.
+    #[cfg(feature = "annotations")]
+    pub fn exclude_annotated(&mut self, annotation: &str) -> &mut Self {
+        self.exclude.insert(descriptor(annotation));
+        self
+    }
+
+    /// Only include classes and members annotated with `annotation` (or any other annotation passed to this method.)
+    ///
+    /// Classes with annotated members are included, but only those members.
+    #[cfg(feature = "annotations")]
+    pub fn include_only_annotated(&mut self, annotation: &str) -> &mut Self {
+        self.include_only.insert(descriptor(annotation));
+        self
+    }
+
+    /// Does `class` have any API?
+    pub fn includes_class(&self, class: &Class) -> bool {
+        if !class.is_public() || self.excluded(&class_annotations(class)) { return false; }
+        self.include_only.is_empty() || self.included(&class_annotations(class))
+            || class.fields .iter().any(|f| self.includes_field (class, f))
+            || class.methods.iter().any(|m| self.includes_method(class, m))
+    }
+
+    /// Is `field` of `class` API?
+    pub fn includes_field(&self, class: &Class, field: &Field) -> bool {
+        let visible = field.is_public() || (self.protected && field.is_protected());
+        visible && (self.synthetic || !field.is_synthetic()) && self.includes_member(class, &field_annotations(field))
+    }
+
+    /// Is `method` of `class` API?  Static initializers never are.
+    pub fn includes_method(&self, class: &Class, method: &Method) -> bool {
+        let visible = method.is_public() || (self.protected && method.is_protected());
+        visible && !method.is_static_init() && (self.synthetic || !method.is_synthetic()) && self.includes_member(class, &method_annotations(method))
+    }
+
+    fn includes_member(&self, class: &Class, annotations: &[&str]) -> bool {
+        let class_annotations = class_annotations(class);
+        class.is_public()
+            && !self.excluded(&class_annotations) && !self.excluded(annotations)
+            && (self.include_only.is_empty() || self.included(&class_annotations) || self.included(annotations))
+    }
+
+    fn excluded(&self, annotations: &[&str]) -> bool { annotations.iter().any(|a| self.exclude.contains(*a)) }
+    fn included(&self, annotations: &[&str]) -> bool { annotations.iter().any(|a| self.include_only.contains(*a)) }
+}
+
+// Without the "annotations" feature, there's nothing to filter by (and no way to ask to.)
+#[cfg(feature = "annotations")]     fn class_annotations (class:  &Class)  -> Vec<&str> { class .annotation_types().collect() }
+#[cfg(feature = "annotations")]     fn field_annotations (field:  &Field)  -> Vec<&str> { field .annotation_types().collect() }
+#[cfg(feature = "annotations")]     fn method_annotations(method: &Method) -> Vec<&str> { method.annotation_types().collect() }
+#[cfg(not(feature = "annotations"))] fn class_annotations (_: &Class)  -> Vec<&str> { Vec::new() }
+#[cfg(not(feature = "annotations"))] fn field_annotations (_: &Field)  -> Vec<&str> { Vec::new() }
+#[cfg(not(feature = "annotations"))] fn method_annotations(_: &Method) -> Vec<&str> { Vec::new() }
+
+/// `"com.example.PublicApi"` or `"com/example/PublicApi"` -> `"Lcom/example/PublicApi;"`
+#[cfg(feature = "annotations")]
+fn descriptor(annotation: &str) -> String {
+    if annotation.starts_with('L') && annotation.ends_with(';') {
+        annotation.to_owned()
+    } else {
+        format!("L{};", annotation.replace('.', "/"))
+    }
+}
+
+#[cfg(feature = "annotations")]
+#[test] fn filter() {
+    use crate::testing::be16;
+    let annotation = |class: &mut testing::ClassFile, ty: &str| {
+        let info = [be16(1), be16(class.utf8(ty)), be16(0)].concat();
+        class.attribute("RuntimeInvisibleAnnotations", &info)
+    };
+    let mut api = testing::ClassFile::new("com/example/Api", Some("java/lang/Object"));
+    let testing_only    = annotation(&mut api, "Lcom/google/common/annotations/VisibleForTesting;");
+    let public_api      = annotation(&mut api, "Lcom/example/PublicApi;");
+    api.method(0x0001, "run", "()V", &[public_api]);
+    api.method(0x0001, "reset", "()V", &[testing_only]);
+    api.method(0x0004, "hook", "()V", &[]);
+    api.method(0x1001, "bridge", "()V", &[]);
+    api.method(0x0008, "<clinit>", "()V", &[]);
+    let api = api.read();
+    let methods = |filter: &Filter| api.methods.iter().filter(|m| filter.includes_method(&api, m)).map(|m| m.name.as_str()).collect::<Vec<_>>();
+
+    let mut filter = Filter::new();
+    assert!(filter.includes_class(&api));
+    assert_eq!(methods(&filter), &["run", "reset", "hook"]);
+    filter.exclude_annotated("com.google.common.annotations.VisibleForTesting").protected(false);
+    assert_eq!(methods(&filter), &["run"]);
+    filter.synthetic(true);
+    assert_eq!(methods(&filter), &["run", "bridge"]);
+
+    let mut filter = Filter::new();
+    filter.include_only_annotated("com/example/PublicApi");
+    assert!(filter.includes_class(&api));
+    assert_eq!(methods(&filter), &["run"]);
+    filter.include_only_annotated("Lcom/example/Other;");
+    let other = testing::ClassFile::new("com/example/Other", Some("java/lang/Object")).read();
+    assert!(!filter.includes_class(&other));
+}
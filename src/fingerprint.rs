@@ -0,0 +1,281 @@
+//! Name-independent structural fingerprints of classes, for matching obfuscated classes against known libraries.
+
+use crate::{class, dedup, Class, Source};
+#[cfg(test)] use crate::{field, method, Field, Method};
+
+use std::collections::*;
+use std::fmt::{self, Debug, Display, Formatter};
+use std::io::{self, BufRead, Write};
+
+
+
+/// Package prefixes of platform classes.  Obfuscators can't rename these, so fingerprints keep their names.
+pub const PLATFORM_PACKAGES : &[&str] = &["java/", "javax/", "android/", "dalvik/", "sun/", "jdk/"];
+
+/// A hash of the "shape" of a class, ignoring the names of the class itself, its members, and any non-platform
+/// classes it references.  See [Class::structural_fingerprint](../class/struct.Class.html#method.structural_fingerprint).
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Fingerprint(pub u128);
+
+impl Debug   for Fingerprint { fn fmt(&self, f: &mut Formatter) -> fmt::Result { write!(f, "Fingerprint({})", self) } }
+impl Display for Fingerprint { fn fmt(&self, f: &mut Formatter) -> fmt::Result { write!(f, "{:032x}", self.0) } }
+
+/// A database of the structural fingerprints of known libraries, for recognizing (possibly obfuscated) copies of them.
+///
+/// Serializes to a simple line based text format:  a `jreflection-fingerprints 1` header, followed by `library <name>`
+/// lines, each followed by the hex fingerprints of that library's classes.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use jreflection::*;
+/// # use jreflection::fingerprint::Database;
+/// # #[cfg(feature = "jar")] {
+/// let mut db = Database::new();
+/// db.add_source("OkHttp 4.9.3", &Source::from_jar("okhttp-4.9.3.jar").unwrap()).unwrap();
+/// db.add_source("Gson 2.10",    &Source::from_jar("gson-2.10.jar").unwrap()).unwrap();
+/// db.write(&mut std::fs::File::create("libraries.fingerprints").unwrap()).unwrap();
+///
+/// let db = Database::read(&mut std::io::BufReader::new(std::fs::File::open("libraries.fingerprints").unwrap())).unwrap();
+/// for m in db.match_source(&Source::from_jar("classes.jar").unwrap()).unwrap() {
+///     if m.confidence >= 0.5 { println!("contains {} ({:.0}% confidence)", m.library, m.confidence * 100.0); }
+/// }
+/// # }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Database {
+    libraries: BTreeMap<String, BTreeSet<Fingerprint>>,
+}
+
+/// How well a set of classes matched one of the libraries of a [Database](struct.Database.html).
+#[derive(Clone, Debug, PartialEq)]
+pub struct LibraryMatch {
+    /// The name of the library, as it was added to the database, such as `"OkHttp 4.9.3"`.
+    pub library:    String,
+
+    /// How many of the library's distinct fingerprints were found.
+    pub matched:    usize,
+
+    /// How many distinct fingerprints the library has.
+    pub total:      usize,
+
+    /// 0.0 ..= 1.0:  The fraction of the library found, with fingerprints shared by several libraries weighted less.
+    pub confidence: f64,
+}
+
+impl Database {
+    /// Create an empty database.
+    pub fn new() -> Self { Self::default() }
+
+    /// Add the fingerprints of every class of `source` to `library`.
+    pub fn add_source(&mut self, library: &str, source: &Source) -> io::Result<()> {
+        source.for_each_class(|path|{
+            let class = source.read_class(path.as_str())?;
+            self.add_class(library, &class);
+            Ok(())
+        })
+    }
+
+    /// Add the fingerprint of `class` to `library`.
+    pub fn add_class(&mut self, library: &str, class: &Class) {
+        self.add_fingerprint(library, class.structural_fingerprint());
+    }
+
+    /// Add a fingerprint to `library`.
+    pub fn add_fingerprint(&mut self, library: &str, fingerprint: Fingerprint) {
+        if let Some(fingerprints) = self.libraries.get_mut(library) {
+            fingerprints.insert(fingerprint);
+        } else {
+            self.libraries.insert(library.to_owned(), Some(fingerprint).into_iter().collect());
+        }
+    }
+
+    /// The names of the libraries in this database, sorted.
+    pub fn libraries(&self) -> impl Iterator<Item = &str> { self.libraries.keys().map(|l| l.as_str()) }
+
+    /// The distinct fingerprints of `library`, if it's in this database.
+    pub fn fingerprints(&self, library: &str) -> Option<&BTreeSet<Fingerprint>> { self.libraries.get(library) }
+
+    /// Read a database previously written by [write](#method.write).
+    pub fn read(read: &mut impl BufRead) -> io::Result<Self> {
+        let mut db = Self::new();
+        let mut library = None;
+        for (index, line) in read.lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if index == 0 {
+                io_assert!(line == "jreflection-fingerprints 1", "Expected fingerprint database header, got {:?}", line);
+            } else if line.is_empty() {
+            } else if line.starts_with("library ") {
+                let name = line["library ".len()..].to_owned();
+                db.libraries.entry(name.clone()).or_default();
+                library = Some(name);
+            } else if let Some(library) = library.as_ref() {
+                match u128::from_str_radix(line, 16) {
+                    Ok(fingerprint) => db.add_fingerprint(library, Fingerprint(fingerprint)),
+                    Err(_) => return io_data_err!("Invalid fingerprint {:?} on line {}", line, index + 1),
+                }
+            } else {
+                return io_data_err!("Fingerprint on line {} precedes any library", index + 1);
+            }
+        }
+        Ok(db)
+    }
+
+    /// Write this database in a form that can be [read](#method.read) back.  Output is sorted, so it diffs well.
+    pub fn write(&self, write: &mut impl Write) -> io::Result<()> {
+        writeln!(write, "jreflection-fingerprints 1")?;
+        for (library, fingerprints) in self.libraries.iter() {
+            writeln!(write, "library {}", library)?;
+            for fingerprint in fingerprints.iter() {
+                writeln!(write, "{}", fingerprint)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Match every class of `source` against this database.  See [match_fingerprints](#method.match_fingerprints).
+    pub fn match_source(&self, source: &Source) -> io::Result<Vec<LibraryMatch>> {
+        let mut fingerprints = BTreeSet::new();
+        source.for_each_class(|path|{
+            fingerprints.insert(source.read_class(path.as_str())?.structural_fingerprint());
+            Ok(())
+        })?;
+        Ok(self.match_fingerprints(&fingerprints))
+    }
+
+    /// Match a set of class fingerprints against this database, returning every library with at least one match,
+    /// most confident first.
+    pub fn match_fingerprints(&self, fingerprints: &BTreeSet<Fingerprint>) -> Vec<LibraryMatch> {
+        let mut libraries_per_fingerprint = BTreeMap::<Fingerprint, usize>::new();
+        for library in self.libraries.values() {
+            for fingerprint in library.iter() {
+                *libraries_per_fingerprint.entry(*fingerprint).or_default() += 1;
+            }
+        }
+
+        let mut matches = Vec::new();
+        for (library, library_fingerprints) in self.libraries.iter() {
+            let mut matched = 0;
+            let mut matched_weight = 0.0;
+            let mut total_weight = 0.0;
+            for fingerprint in library_fingerprints.iter() {
+                let weight = 1.0 / libraries_per_fingerprint[fingerprint] as f64;
+                total_weight += weight;
+                if fingerprints.contains(fingerprint) {
+                    matched += 1;
+                    matched_weight += weight;
+                }
+            }
+            if matched == 0 { continue; }
+            matches.push(LibraryMatch {
+                library:    library.clone(),
+                matched,
+                total:      library_fingerprints.len(),
+                confidence: matched_weight / total_weight,
+            });
+        }
+        matches.sort_by(|a, b| b.confidence.partial_cmp(&a.confidence).unwrap_or(std::cmp::Ordering::Equal).then_with(|| a.library.cmp(&b.library)));
+        matches
+    }
+}
+
+/// Is `path` (a JNI path such as `"java/lang/Object"`) a platform class, whose name survives obfuscation?
+pub fn is_platform_class(path: &str) -> bool {
+    PLATFORM_PACKAGES.iter().any(|p| path.starts_with(p))
+}
+
+/// Replace every non-platform class name in a field or method descriptor with nothing, e.g.
+/// `"(La/b;Ljava/lang/String;)[La/c;"` becomes `"(L;Ljava/lang/String;)[L;"`.
+pub(crate) fn anonymize_descriptor(descriptor: &str) -> String {
+    let mut anonymized = String::with_capacity(descriptor.len());
+    let mut rest = descriptor;
+    while let Some(start) = rest.find('L') {
+        let end = match rest[start..].find(';') { Some(end) => start + end, None => break };
+        let class = &rest[start+1..end];
+        anonymized.push_str(&rest[..=start]);
+        if is_platform_class(class) { anonymized.push_str(class); }
+        anonymized.push(';');
+        rest = &rest[end+1..];
+    }
+    anonymized.push_str(rest);
+    anonymized
+}
+
+pub(crate) fn of(class: &Class) -> Fingerprint {
+    fn class_ref(path: Option<&str>) -> &str {
+        match path {
+            None                                    => "-",
+            Some(path) if is_platform_class(path)   => path,
+            Some(_)                                 => "?",
+        }
+    }
+
+    let mut interfaces = class.interfaces.iter().map(|i| class_ref(Some(i.as_str()))).collect::<Vec<_>>();
+    interfaces.sort();
+
+    let mut members = Vec::new();
+    members.extend(class.fields.iter().map(|f| format!("F {:04x} {}", f.flags.bits(), anonymize_descriptor(f.descriptor_str()))));
+    members.extend(class.methods.iter().map(|m|{
+        let name = if m.name.starts_with('<') { m.name.as_str() } else { "" }; // <init> and <clinit> can't be renamed
+        format!("M {:04x} {}{}", m.flags.bits(), name, anonymize_descriptor(m.descriptor_str()))
+    }));
+    members.sort();
+
+    let flags = class.flags - class::Flags::SUPER; // Set by all modern compilers, ignored by modern JVMs
+    let canonical = format!("{:04x} {} [{}] [{}]", flags.bits(), class_ref(class.super_path.as_ref().map(|s| s.as_str())), interfaces.join(" "), members.join(" "));
+    Fingerprint(dedup::ContentHash::of(canonical.as_bytes()).0)
+}
+
+#[test] fn anonymize() {
+    assert_eq!(anonymize_descriptor("I"),                                       "I");
+    assert_eq!(anonymize_descriptor("(La/b;Ljava/lang/String;)[La/c;"),         "(L;Ljava/lang/String;)[L;");
+    assert_eq!(anonymize_descriptor("(ILandroid/view/View;JLcom/Foo;Z)V"),      "(ILandroid/view/View;JL;Z)V");
+}
+
+#[test] fn structural_fingerprint() {
+    fn class(path: &str, interfaces: &[&str], fields: &[(&str, &str)], methods: &[(&str, &str)]) -> Class {
+        Class {
+            flags:      class::Flags::PUBLIC | class::Flags::SUPER,
+            path:       class::IdBuf::new(path.to_owned()),
+            super_path: Some(class::IdBuf::new("java/lang/Object".to_owned())),
+            interfaces: interfaces.iter().map(|i| class::IdBuf::new((*i).to_owned())).collect(),
+            fields:     fields .iter().map(|(name, desc)| Field ::new(field ::Flags::PRIVATE, (*name).to_owned(), (*desc).to_owned()).unwrap()).collect(),
+            methods:    methods.iter().map(|(name, desc)| Method::new(method::Flags::PUBLIC,  (*name).to_owned(), (*desc).to_owned()).unwrap()).collect(),
+            .. Default::default()
+        }
+    }
+
+    let original    = class("okhttp3/Request",  &["java/io/Closeable", "okhttp3/Call"], &[("url", "Lokhttp3/HttpUrl;"), ("method", "Ljava/lang/String;")], &[("<init>", "()V"), ("url", "()Lokhttp3/HttpUrl;"), ("method", "()Ljava/lang/String;")]);
+    let obfuscated  = class("a/b",              &["a/c", "java/io/Closeable"],          &[("a", "Ljava/lang/String;"), ("b", "La/d;")],                 &[("a", "()Ljava/lang/String;"), ("<init>", "()V"), ("b", "()La/d;")]);
+    let different   = class("a/e",              &["a/c", "java/io/Closeable"],          &[("a", "Ljava/lang/String;"), ("b", "La/d;")],                 &[("a", "()Ljava/lang/Object;"), ("<init>", "()V"), ("b", "()La/d;")]);
+    let constructor = class("a/f",              &["a/c", "java/io/Closeable"],          &[("a", "Ljava/lang/String;"), ("b", "La/d;")],                 &[("a", "()Ljava/lang/String;"), ("c", "()V"), ("b", "()La/d;")]);
+
+    assert_eq!(original.structural_fingerprint(), obfuscated.structural_fingerprint());
+    assert_ne!(original.structural_fingerprint(), different.structural_fingerprint());
+    assert_ne!(original.structural_fingerprint(), constructor.structural_fingerprint());
+}
+
+#[test] fn database() {
+    let mut db = Database::new();
+    for i in 0..4 { db.add_fingerprint("OkHttp 4.9.3", Fingerprint(i)); }
+    for i in 3..5 { db.add_fingerprint("Gson 2.10", Fingerprint(i)); }
+    db.add_fingerprint("Gson 2.10", Fingerprint(0xFFFF_0000_0000_0000_0000_0000_0000_0001));
+
+    let mut bytes = Vec::new();
+    db.write(&mut bytes).unwrap();
+    let text = String::from_utf8(bytes.clone()).unwrap();
+    assert!(text.starts_with("jreflection-fingerprints 1\nlibrary Gson 2.10\n00000000000000000000000000000003\n"));
+    assert_eq!(Database::read(&mut &bytes[..]).unwrap(), db);
+    assert!(Database::read(&mut &b"not a database\n"[..]).is_err());
+    assert!(Database::read(&mut &b"jreflection-fingerprints 1\n00000000000000000000000000000003\n"[..]).is_err());
+
+    let found = [0, 1, 2, 3].iter().map(|i| Fingerprint(*i)).collect();
+    let matches = db.match_fingerprints(&found);
+    assert_eq!(matches.len(), 2);
+    assert_eq!(matches[0].library, "OkHttp 4.9.3");
+    assert_eq!((matches[0].matched, matches[0].total, matches[0].confidence), (4, 4, 1.0));
+    assert_eq!(matches[1].library, "Gson 2.10");
+    assert_eq!((matches[1].matched, matches[1].total), (1, 3));
+    assert!((matches[1].confidence - 0.2).abs() < 1e-9); // 3 is shared by both libraries, so it's only worth half as much
+}
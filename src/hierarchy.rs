@@ -0,0 +1,553 @@
+//! Analysis of class hierarchies (superclasses, superinterfaces, and the members they provide.)
+
+use crate::{class, signature, Class, ClassPath, Field, Method};
+#[cfg(test)] use crate::{method, testing};
+
+use std::cell::RefCell;
+use std::collections::*;
+use std::io::{self, ErrorKind};
+use std::rc::Rc;
+
+
+
+/// A lazily populated, cached view of the class hierarchy of a [ClassPath].
+///
+/// Classes can also be [add]ed directly, to analyze generated or in-memory classes.
+///
+/// [ClassPath]:    struct.ClassPath.html
+/// [add]:          #method.add
+#[derive(Default)]
+pub struct Hierarchy<'cp> {
+    classpath:  Option<&'cp ClassPath>,
+    classes:    RefCell<BTreeMap<String, Rc<Class>>>,
+}
+
+/// Two or more unrelated interfaces provide the same method, at least one of which is a `default` method.
+///
+/// See [Hierarchy::default_method_conflicts](struct.Hierarchy.html#method.default_method_conflicts).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DefaultMethodConflict {
+    /// The name of the conflicting method.
+    pub name:           String,
+
+    /// The descriptor of the conflicting method.
+    pub descriptor:     String,
+
+    /// The maximally-specific interfaces declaring the method, sorted.
+    pub interfaces:     Vec<class::IdBuf>,
+
+    /// If the class (or one of its superclasses) declares the method, resolving the conflict.
+    pub overridden:     bool,
+}
+
+/// A field or method of a class, either declared by it or inherited.  See [Hierarchy::fields] and [Hierarchy::methods].
+///
+/// [Hierarchy::fields]:    struct.Hierarchy.html#method.fields
+/// [Hierarchy::methods]:   struct.Hierarchy.html#method.methods
+#[derive(Clone, Debug)]
+pub struct Member<M> {
+    /// The class or interface declaring the member.
+    pub declaring:  class::IdBuf,
+
+    /// The member itself.
+    pub member:     M,
+}
+
+impl<'cp> Hierarchy<'cp> {
+    /// Create a hierarchy that reads classes from `classpath` on demand.
+    pub fn new(classpath: &'cp ClassPath) -> Self {
+        Self { classpath: Some(classpath), classes: Default::default() }
+    }
+
+    /// Add (or replace) a class, shadowing any class of the same name from the classpath.
+    pub fn add(&self, class: Class) -> Rc<Class> {
+        let class = Rc::new(class);
+        self.classes.borrow_mut().insert(class.path.as_str().to_owned(), class.clone());
+        class
+    }
+
+    /// Get a class by JNI path, or `None` if it couldn't be found.
+    pub fn class(&self, path: &str) -> io::Result<Option<Rc<Class>>> {
+        if let Some(class) = self.classes.borrow().get(path) {
+            return Ok(Some(class.clone()));
+        }
+
+        let classpath = match self.classpath {
+            Some(cp) => cp,
+            None => return Ok(None),
+        };
+
+        match classpath.read_class(path) {
+            Ok(class) => Ok(Some(self.add(class))),
+            Err(ref err) if err.kind() == ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Can code in `module` (`None` for the unnamed module) access `class`?  It must be `public`, and if the
+    /// classpath is [module aware](struct.ClassPath.html#method.enable_module_awareness), in a package exported to
+    /// `module`.
+    pub fn is_accessible(&self, class: &Class, module: Option<&str>) -> bool {
+        class.is_public() && self.classpath.map(|cp| cp.is_exported(class.path.as_str(), module)).unwrap_or(true)
+    }
+
+    /// Get all (found) superclasses of `class`, nearest first.
+    pub fn superclasses(&self, class: &Class) -> io::Result<Vec<Rc<Class>>> {
+        let mut superclasses = Vec::new();
+        let mut next = class.super_path.clone();
+        while let Some(path) = next {
+            match self.class(path.as_str())? {
+                Some(superclass) => {
+                    next = superclass.super_path.clone();
+                    superclasses.push(superclass);
+                },
+                None => break,
+            }
+        }
+        Ok(superclasses)
+    }
+
+    /// Get all (found) interfaces implemented by `class` - directly, via superinterfaces, or via superclasses - without duplicates.
+    pub fn superinterfaces(&self, class: &Class) -> io::Result<Vec<Rc<Class>>> {
+        let mut visited = BTreeSet::new();
+        let mut pending = VecDeque::new();
+        pending.extend(class.interfaces.iter().cloned());
+        for superclass in self.superclasses(class)? {
+            pending.extend(superclass.interfaces.iter().cloned());
+        }
+
+        let mut interfaces = Vec::new();
+        while let Some(path) = pending.pop_front() {
+            if !visited.insert(path.clone()) { continue; }
+            if let Some(interface) = self.class(path.as_str())? {
+                pending.extend(interface.interfaces.iter().cloned());
+                interfaces.push(interface);
+            }
+        }
+        Ok(interfaces)
+    }
+
+    /// The fields of `class`:  those it declares, followed by those it inherits from its superclasses (nearest first),
+    /// then those it inherits from its superinterfaces.
+    ///
+    /// `private` fields, and package-private fields of classes in other packages, aren't inherited.  Inherited fields
+    /// hidden by a nearer field of the same name are omitted.
+    pub fn fields(&self, class: &Class) -> io::Result<Vec<Member<Field>>> {
+        let mut members = class.fields.iter().map(|f| Member { declaring: class.path.clone(), member: f.clone() }).collect::<Vec<_>>();
+        let mut seen = class.fields.iter().map(|f| f.name.clone()).collect::<BTreeSet<_>>();
+        let package = package_of(class.path.as_str());
+        for ancestor in self.superclasses(class)?.into_iter().chain(self.superinterfaces(class)?) {
+            for field in ancestor.fields.iter() {
+                if field.is_private() { continue; }
+                if !field.is_public() && !field.is_protected() && package_of(ancestor.path.as_str()) != package { continue; }
+                if !seen.insert(field.name.clone()) { continue; }
+                members.push(Member { declaring: ancestor.path.clone(), member: field.clone() });
+            }
+        }
+        Ok(members)
+    }
+
+    /// The methods of `class`:  those it declares, followed by those it inherits from its superclasses (nearest
+    /// first), then those it inherits from its superinterfaces.
+    ///
+    /// This follows Java's inheritance rules, rather than naively flattening the hierarchy:
+    ///
+    /// * Constructors, static initializers, and `private` methods (including Java 9+ `private` interface methods) aren't inherited.
+    /// * `static` interface methods aren't inherited - they can only be invoked via their interface - although `static` superclass methods are.
+    /// * Package-private methods of classes in other packages aren't inherited.
+    /// * Inherited methods overridden (or hidden) by a nearer method with the same name and descriptor are omitted.
+    /// * Superinterface methods are inherited from their maximally-specific declarations, so a `default` method
+    ///   overriding an `abstract` declaration in one of its superinterfaces wins regardless of where either interface
+    ///   appears in the hierarchy.  If several unrelated interfaces declare the method, a `default` declaration is
+    ///   preferred (see [default_method_conflicts](#method.default_method_conflicts).)
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use jreflection::*;
+    /// let classpath = ClassPath::from_sources(vec![Source::from_jdk_dir(r"C:\Program Files\AdoptOpenJDK\jdk-11.0.6.10-hotspot").unwrap()]);
+    /// let hierarchy = Hierarchy::new(&classpath);
+    /// let array_list = hierarchy.class("java/util/ArrayList").unwrap().unwrap();
+    /// let methods = hierarchy.methods(&array_list).unwrap();
+    /// assert!( methods.iter().any(|m| m.member.name == "stream"));    // default method of Collection
+    /// assert!(!methods.iter().any(|m| m.member.name == "of"));        // static method of List
+    /// ```
+    pub fn methods(&self, class: &Class) -> io::Result<Vec<Member<Method>>> {
+        let mut members = class.methods.iter().map(|m| Member { declaring: class.path.clone(), member: m.clone() }).collect::<Vec<_>>();
+        let mut seen = class.methods.iter().map(|m| (m.name.clone(), m.descriptor_str().to_owned())).collect::<BTreeSet<_>>();
+        let package = package_of(class.path.as_str());
+        let inherited = |ancestor: &Class, method: &Method| {
+            if method.name.starts_with('<') || method.is_private() { return false; }
+            if method.is_static() && ancestor.is_interface() { return false; }
+            method.is_public() || method.is_protected() || package_of(ancestor.path.as_str()) == package
+        };
+
+        for ancestor in self.superclasses(class)? {
+            for method in ancestor.methods.iter() {
+                if !inherited(&ancestor, method) { continue; }
+                if !seen.insert((method.name.clone(), method.descriptor_str().to_owned())) { continue; }
+                members.push(Member { declaring: ancestor.path.clone(), member: method.clone() });
+            }
+        }
+
+        let interfaces = self.superinterfaces(class)?;
+        let ancestors = self.interface_ancestors(&interfaces)?;
+        for interface in interfaces.iter() {
+            for method in interface.methods.iter() {
+                if !inherited(interface, method) { continue; }
+                if !seen.insert((method.name.clone(), method.descriptor_str().to_owned())) { continue; }
+
+                let declarations = interfaces.iter().filter_map(|i|{
+                    let m = i.methods.iter().find(|m| m.name == method.name && m.descriptor_str() == method.descriptor_str())?;
+                    if inherited(i, m) { Some((&**i, m)) } else { None }
+                }).collect::<Vec<_>>();
+                let maximally_specific = declarations.iter().copied().filter(|(candidate, _)|{
+                    !declarations.iter().any(|(other, _)| ancestors[other.path.as_str()].contains(candidate.path.as_str()))
+                }).collect::<Vec<_>>();
+                let (declaring, method) = maximally_specific.iter().copied().find(|(_, m)| !m.is_abstract())
+                    .or_else(|| maximally_specific.first().copied())
+                    .unwrap_or((&**interface, method)); // Only for cyclic (malformed) hierarchies
+                members.push(Member { declaring: declaring.path.clone(), member: method.clone() });
+            }
+        }
+        Ok(members)
+    }
+
+    /// Heuristically detect "diamond" conflicts between `default` methods inherited by `class`.
+    ///
+    /// A conflict is reported when two or more of the maximally-specific superinterfaces of `class` declare the same
+    /// method, and at least one of those declarations is a `default` method.  Java 8+ requires such classes to
+    /// override the method themselves - [overridden] reports if `class` or one of its superclasses does so.
+    ///
+    /// Interfaces missing from the hierarchy are silently ignored, so results are only as accurate as the classpath.
+    ///
+    /// [overridden]:   struct.DefaultMethodConflict.html#structfield.overridden
+    pub fn default_method_conflicts(&self, class: &Class) -> io::Result<Vec<DefaultMethodConflict>> {
+        let interfaces = self.superinterfaces(class)?;
+
+        // (name, descriptor) -> interfaces declaring the method
+        let mut declarations = BTreeMap::<(&str, &str), Vec<&Class>>::new();
+        for interface in interfaces.iter() {
+            for method in interface.methods.iter() {
+                if method.is_static() || method.is_private() || method.name.starts_with('<') { continue; }
+                declarations.entry((method.name.as_str(), method.descriptor_str())).or_default().push(interface);
+            }
+        }
+
+        let ancestors = self.interface_ancestors(&interfaces)?;
+        let superclasses = self.superclasses(class)?;
+        let mut conflicts = Vec::new();
+        for ((name, descriptor), declaring) in declarations {
+            let maximally_specific = declaring.iter().copied().filter(|candidate|{
+                !declaring.iter().any(|other| ancestors[other.path.as_str()].contains(candidate.path.as_str()))
+            }).collect::<Vec<&Class>>();
+
+            if maximally_specific.len() < 2 { continue; }
+            let any_default = maximally_specific.iter().any(|i| i.methods.iter().any(|m| m.name == name && m.descriptor_str() == descriptor && !m.is_abstract()));
+            if !any_default { continue; }
+
+            let overridden = std::iter::once(class).chain(superclasses.iter().map(|c| &**c)).any(|c|{
+                c.methods.iter().any(|m| m.name == name && m.descriptor_str() == descriptor && !m.is_static())
+            });
+
+            let mut interfaces = maximally_specific.iter().map(|i| i.path.clone()).collect::<Vec<_>>();
+            interfaces.sort();
+            conflicts.push(DefaultMethodConflict {
+                name:       name.to_owned(),
+                descriptor: descriptor.to_owned(),
+                interfaces,
+                overridden,
+            });
+        }
+        Ok(conflicts)
+    }
+
+    /// The (found) superinterfaces of each of `interfaces`, by JNI path.
+    fn interface_ancestors(&self, interfaces: &[Rc<Class>]) -> io::Result<BTreeMap<String, BTreeSet<String>>> {
+        let mut ancestors = BTreeMap::new();
+        for interface in interfaces.iter() {
+            let supers = self.superinterfaces(interface)?.iter().map(|i| i.path.as_str().to_owned()).collect();
+            ancestors.insert(interface.path.as_str().to_owned(), supers);
+        }
+        Ok(ancestors)
+    }
+
+    /// Does `method` of `class` override `ancestor_method` of `ancestor`, one of the superclasses or superinterfaces of `class`?
+    ///
+    /// Unlike comparing descriptors, this follows the generic signatures of the hierarchy between `class` and
+    /// `ancestor`, so `compareTo(Ljava/lang/String;)I` of a `Comparable<String>` implementation overrides
+    /// `Comparable.compareTo(Ljava/lang/Object;)I`, and return types may be covariant.  Identical descriptors (such as
+    /// those of compiler generated bridge methods) always override, as they do for the JVM.  Static, private, and
+    /// constructor methods never override or are overridden, nor are package-private methods from other packages.
+    ///
+    /// Classes missing from the hierarchy are treated as unrelated, so results are only as accurate as the classpath.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use jreflection::*;
+    /// let classpath = ClassPath::from_sources(vec![Source::from_jdk_dir(r"C:\Program Files\AdoptOpenJDK\jdk-8.0.232.09-hotspot").unwrap()]);
+    /// let hierarchy = Hierarchy::new(&classpath);
+    /// let string      = hierarchy.class("java/lang/String").unwrap().unwrap();
+    /// let comparable  = hierarchy.class("java/lang/Comparable").unwrap().unwrap();
+    /// let compare_to  = string.methods.iter().find(|m| m.name == "compareTo" && !m.is_bridge()).unwrap();
+    /// assert!(hierarchy.overrides(&string, compare_to, &comparable, &comparable.methods[0]).unwrap());
+    /// ```
+    pub fn overrides(&self, class: &Class, method: &Method, ancestor: &Class, ancestor_method: &Method) -> io::Result<bool> {
+        if method.name != ancestor_method.name || method.name.starts_with('<') { return Ok(false); }
+        if method.is_static() || ancestor_method.is_static() || ancestor_method.is_private() { return Ok(false); }
+        let package_private = !ancestor_method.is_public() && !ancestor_method.is_protected();
+        if package_private && package_of(class.path.as_str()) != package_of(ancestor.path.as_str()) { return Ok(false); }
+
+        let bindings = match self.supertype_bindings(class, ancestor.path.as_str())? {
+            Some(bindings)  => bindings,
+            None            => return Ok(false),
+        };
+        if method.descriptor_str() == ancestor_method.descriptor_str() { return Ok(true); }
+
+        // Specialize the ancestor's method for `class`, then erase it to compare against `method`'s descriptor.
+        let inherited       = signature::substitute(&generic_signature(ancestor_method)?, &bindings);
+        let class_params    = type_parameters(class);
+        let scopes          = [&inherited.type_parameters[..], &class_params[..]];
+        let parameters      = inherited.parameters.iter().map(|p| erase(p, &scopes, 0).to_string()).collect::<String>();
+        let return_type     = erase(&inherited.return_type, &scopes, 0).to_string();
+
+        let descriptor = method.descriptor_str();
+        let close = descriptor.find(')').unwrap_or(0); // Already validated in Method::new/read_one
+        if descriptor[1..close] != parameters[..] { return Ok(false); }
+        self.is_assignable(&descriptor[close+1..], return_type.as_str())
+    }
+
+    /// The type arguments `class` binds the type parameters of `ancestor` to, or `None` if `ancestor` isn't one of its supertypes.
+    fn supertype_bindings(&self, class: &Class, ancestor: &str) -> io::Result<Option<signature::Bindings>> {
+        let mut visited = BTreeSet::new();
+        let mut pending = supertypes(class).into_iter().map(|s| (s, signature::Bindings::new())).collect::<Vec<_>>();
+        while let Some((reference, outer)) = pending.pop() {
+            let path = reference.erasure();
+            if !visited.insert(path.clone()) { continue; }
+            let supertype = match self.class(path.as_str())? {
+                Some(supertype) => supertype,
+                None            => continue,
+            };
+
+            let params  = type_parameters(&supertype);
+            let args    = reference.segments.last().map(|s| &s.type_arguments[..]).unwrap_or(&[]);
+            let bindings = if args.len() == params.len() {
+                params.iter().map(|p| p.name.clone()).zip(args.iter().map(|a| signature::substitute(a, &outer))).collect::<signature::Bindings>()
+            } else { // Raw type
+                params.iter().map(|p| (p.name.clone(), signature::TypeArgument::Exact(erase(&signature::Type::TypeVariable(p.name.clone()), &[&params[..]], 0)))).collect()
+            };
+
+            if path == ancestor { return Ok(Some(bindings)); }
+            pending.extend(supertypes(&supertype).into_iter().map(|s| (s, bindings.clone())));
+        }
+        Ok(None)
+    }
+
+    /// Can a value of type `from` be returned where `to` is expected?  Both are field descriptors (or `V`.)
+    fn is_assignable(&self, from: &str, to: &str) -> io::Result<bool> {
+        if from == to { return Ok(true); }
+        if to == "Ljava/lang/Object;" { return Ok(from.starts_with('L') || from.starts_with('[')); }
+        if from.starts_with('[') {
+            return if to.starts_with('[') { self.is_assignable(&from[1..], &to[1..]) } else { Ok(to == "Ljava/lang/Cloneable;" || to == "Ljava/io/Serializable;") };
+        }
+        if !from.starts_with('L') || !to.starts_with('L') { return Ok(false); }
+
+        let to = &to[1..to.len()-1];
+        let from = match self.class(&from[1..from.len()-1])? {
+            Some(from)  => from,
+            None        => return Ok(false),
+        };
+        Ok(self.superclasses(&from)?.iter().chain(self.superinterfaces(&from)?.iter()).any(|s| s.path.as_str() == to))
+    }
+}
+
+fn package_of(class: &str) -> &str { class.rfind('/').map(|slash| &class[..slash]).unwrap_or("") }
+
+/// The type parameters of `class`, ignoring malformed signatures (as the JVM does.)
+fn type_parameters(class: &Class) -> Vec<signature::TypeParameter> {
+    class.signature().ok().and_then(|s| s).map(|s| s.type_parameters).unwrap_or_default()
+}
+
+/// The direct supertypes of `class`, with type arguments if it has a (well formed) generic signature.
+fn supertypes(class: &Class) -> Vec<signature::ClassType> {
+    match class.signature().ok().and_then(|s| s) {
+        Some(signature) if class.super_path.is_some()   => std::iter::once(signature.superclass).chain(signature.interfaces).collect(),
+        Some(signature)                                 => signature.interfaces,
+        None                                            => class.super_path.iter().chain(class.interfaces.iter()).map(|p| raw(p.as_str())).collect(),
+    }
+}
+
+/// The generic signature of `method`, or its descriptor if it has none (or a malformed one.)
+fn generic_signature(method: &Method) -> io::Result<signature::MethodSignature> {
+    match method.signature().ok().and_then(|s| s) {
+        Some(signature) => Ok(signature),
+        None            => signature::MethodSignature::parse(method.descriptor_str()),
+    }
+}
+
+fn raw(path: &str) -> signature::ClassType {
+    let (package, name) = match path.rfind('/') {
+        Some(slash) => (&path[..slash], &path[slash+1..]),
+        None        => ("", path),
+    };
+    signature::ClassType { package: package.to_owned(), segments: vec![signature::SimpleClassType { name: name.to_owned(), type_arguments: Vec::new() }] }
+}
+
+/// The [type erasure](https://docs.oracle.com/javase/specs/jls/se8/html/jls-4.html#jls-4.6) of `ty`, looking up type
+/// variables in `scopes` (innermost first.)  Unknown type variables erase to `Object`.
+fn erase(ty: &signature::Type, scopes: &[&[signature::TypeParameter]], depth: usize) -> signature::Type {
+    use crate::signature::Type;
+    match ty {
+        Type::Base(b)           => Type::Base(*b),
+        Type::Array(t)          => Type::Array(Box::new(erase(t, scopes, depth))),
+        Type::Class(c)          => Type::Class(raw(c.erasure().as_str())),
+        Type::TypeVariable(v)   => {
+            let param = scopes.iter().flat_map(|s| s.iter()).find(|p| &p.name == v);
+            match param.and_then(|p| p.class_bound.as_ref().or_else(|| p.interface_bounds.first())) {
+                Some(bound) if depth < 16   => erase(bound, scopes, depth + 1), // Malformed signatures could have cyclic bounds
+                _                           => Type::Class(raw("java/lang/Object")),
+            }
+        },
+    }
+}
+
+#[cfg(test)] fn test_class(path: &str, flags: class::Flags, interfaces: &[&str], methods: &[(&str, method::Flags)]) -> Class {
+    Class {
+        flags,
+        path:       class::IdBuf::new(path.to_owned()),
+        super_path: Some(class::IdBuf::new("java/lang/Object".to_owned())),
+        interfaces: interfaces.iter().map(|i| class::IdBuf::new((*i).to_owned())).collect(),
+        methods:    methods.iter().map(|(name, flags)| Method::new(*flags, (*name).to_owned(), "()V".to_owned()).unwrap()).collect(),
+        .. Default::default()
+    }
+}
+
+#[test] fn default_method_conflicts() {
+    use crate::method::Flags as M;
+    let interface = class::Flags::PUBLIC | class::Flags::INTERFACE | class::Flags::ABSTRACT;
+
+    let hierarchy = Hierarchy::default();
+    hierarchy.add(test_class("A",   interface, &[],     &[("run", M::PUBLIC), ("walk", M::PUBLIC | M::ABSTRACT)]));
+    hierarchy.add(test_class("B",   interface, &[],     &[("run", M::PUBLIC), ("walk", M::PUBLIC | M::ABSTRACT)]));
+    hierarchy.add(test_class("SubA",interface, &["A"],  &[("run", M::PUBLIC)]));
+
+    let ab          = test_class("AB",          class::Flags::PUBLIC, &["A", "B"],      &[]);
+    let ab_override = test_class("ABOverride",  class::Flags::PUBLIC, &["A", "B"],      &[("run", M::PUBLIC)]);
+    let a_suba      = test_class("ASubA",       class::Flags::PUBLIC, &["A", "SubA"],   &[]);
+
+    let conflicts = hierarchy.default_method_conflicts(&ab).unwrap();
+    assert_eq!(conflicts.len(), 1); // "walk" is abstract in both, so not a conflict
+    assert_eq!(conflicts[0].name, "run");
+    assert_eq!(conflicts[0].interfaces, vec![class::IdBuf::new("A".to_owned()), class::IdBuf::new("B".to_owned())]);
+    assert!(!conflicts[0].overridden);
+
+    let conflicts = hierarchy.default_method_conflicts(&ab_override).unwrap();
+    assert_eq!(conflicts.len(), 1);
+    assert!(conflicts[0].overridden);
+
+    // SubA.run overrides A.run, so there's no diamond
+    assert_eq!(hierarchy.default_method_conflicts(&a_suba).unwrap(), vec![]);
+}
+
+#[test] fn overrides() {
+    let signature = |class: &mut testing::ClassFile, signature: &str| {
+        let info = testing::be16(class.utf8(signature));
+        class.attribute("Signature", &info)
+    };
+    let class_signature = |class: &mut testing::ClassFile, signature: &str| {
+        let info = testing::be16(class.utf8(signature));
+        class.class_attribute("Signature", &info);
+    };
+    let hierarchy = Hierarchy::default();
+    let mut number = testing::ClassFile::new("java/lang/Number", Some("java/lang/Object"));
+    number.interface("java/io/Serializable");
+    hierarchy.add(number.read());
+    hierarchy.add(testing::ClassFile::new("java/lang/Integer", Some("java/lang/Number")).read());
+
+    let mut comparable = testing::ClassFile::new("java/lang/Comparable", Some("java/lang/Object"));
+    class_signature(&mut comparable, "<T:Ljava/lang/Object;>Ljava/lang/Object;");
+    let compare_to = signature(&mut comparable, "(TT;)I");
+    comparable.method(0x0401, "compareTo", "(Ljava/lang/Object;)I", &[compare_to]);
+    let comparable = hierarchy.add(comparable.read());
+
+    let mut base = testing::ClassFile::new("com/example/base/Base", Some("java/lang/Object"));
+    class_signature(&mut base, "<E:Ljava/lang/Number;>Ljava/lang/Object;");
+    let get = signature(&mut base, "()TE;");
+    let set = signature(&mut base, "(TE;)V");
+    base.method(0x0001, "get",      "()Ljava/lang/Number;",     &[get]);
+    base.method(0x0001, "set",      "(Ljava/lang/Number;)V",    &[set]);
+    base.method(0x0000, "hidden",   "()V",                      &[]);
+    base.method(0x0001, "list",     "()Ljava/lang/Object;",     &[]);
+    let base = hierarchy.add(base.read());
+
+    let mut sub = testing::ClassFile::new("com/example/Sub", Some("com/example/base/Base"));
+    sub.interface("java/lang/Comparable");
+    class_signature(&mut sub, "Lcom/example/base/Base<Ljava/lang/Integer;>;Ljava/lang/Comparable<Lcom/example/Sub;>;");
+    sub.method(0x0001, "get",       "()Ljava/lang/Integer;",    &[]);
+    sub.method(0x0001, "set",       "(Ljava/lang/Integer;)V",   &[]);
+    sub.method(0x1041, "set",       "(Ljava/lang/Number;)V",    &[]); // bridge
+    sub.method(0x0001, "compareTo", "(Lcom/example/Sub;)I",     &[]);
+    sub.method(0x0001, "compareTo", "(Ljava/lang/Integer;)I",   &[]);
+    sub.method(0x0001, "hidden",    "()V",                      &[]);
+    sub.method(0x0001, "list",      "()[I",                     &[]);
+    let sub = hierarchy.add(sub.read());
+
+    let mut raw = testing::ClassFile::new("com/example/Raw", Some("com/example/base/Base"));
+    raw.method(0x0001, "get",       "()Ljava/lang/Integer;",    &[]);
+    raw.method(0x0001, "set",       "(Ljava/lang/Number;)V",    &[]);
+    raw.method(0x0001, "set",       "(Ljava/lang/Integer;)V",   &[]);
+    let raw = hierarchy.add(raw.read());
+
+    let overrides = |class: &Class, method: usize, ancestor: &Class, ancestor_method: usize| hierarchy.overrides(class, &class.methods[method], ancestor, &ancestor.methods[ancestor_method]).unwrap();
+    assert!( overrides(&sub, 0, &base, 0)); // covariant get
+    assert!( overrides(&sub, 1, &base, 1)); // set(Integer) overrides set(E) for E = Integer
+    assert!( overrides(&sub, 2, &base, 1)); // the bridge has an identical descriptor
+    assert!( overrides(&sub, 3, &comparable, 0));
+    assert!(!overrides(&sub, 4, &comparable, 0));
+    assert!(!overrides(&sub, 5, &base, 2)); // package-private in another package
+    assert!( overrides(&sub, 6, &base, 3)); // arrays are Objects
+    assert!(!overrides(&sub, 0, &base, 1));
+    assert!(!overrides(&base, 0, &sub, 0)); // wrong direction
+
+    assert!( overrides(&raw, 0, &base, 0));
+    assert!( overrides(&raw, 1, &base, 1)); // raw Base erases E to Number
+    assert!(!overrides(&raw, 2, &base, 1));
+}
+
+#[test] fn inherited_members() {
+    use crate::method::Flags as M;
+    let interface = class::Flags::PUBLIC | class::Flags::INTERFACE | class::Flags::ABSTRACT;
+
+    let hierarchy = Hierarchy::default();
+    hierarchy.add(Class { super_path: None, .. test_class("java/lang/Object", class::Flags::PUBLIC, &[], &[("<init>", M::PUBLIC), ("hashCode", M::PUBLIC)]) });
+    hierarchy.add(test_class("a/List", interface, &[], &[("of", M::PUBLIC | M::STATIC), ("helper", M::PRIVATE), ("stream", M::PUBLIC), ("size", M::PUBLIC | M::ABSTRACT)]));
+    hierarchy.add(test_class("a/Base", class::Flags::PUBLIC, &["a/List"], &[("<init>", M::PUBLIC), ("create", M::PUBLIC | M::STATIC), ("internal", M::empty()), ("secret", M::PRIVATE), ("size", M::PUBLIC)]));
+    let sub = test_class("b/Sub", class::Flags::PUBLIC, &[], &[("<init>", M::PUBLIC), ("hashCode", M::PUBLIC)]);
+    let sub = Class { super_path: Some(class::IdBuf::new("a/Base".to_owned())), .. sub };
+
+    let methods = hierarchy.methods(&sub).unwrap().into_iter().map(|m| format!("{}.{}", m.declaring.as_str(), m.member.name)).collect::<Vec<_>>();
+    assert_eq!(methods, &["b/Sub.<init>", "b/Sub.hashCode", "a/Base.create", "a/Base.size", "a/List.stream"]);
+    assert!(hierarchy.fields(&sub).unwrap().is_empty());
+}
+
+#[test] fn inherited_interface_diamond() {
+    use crate::method::Flags as M;
+    let interface = class::Flags::PUBLIC | class::Flags::INTERFACE | class::Flags::ABSTRACT;
+
+    // class C extends S implements I1 { }, class S implements I2 { }, interface I2 extends I1 { default void m() { } }
+    let hierarchy = Hierarchy::default();
+    hierarchy.add(test_class("I1", interface, &[],      &[("m", M::PUBLIC | M::ABSTRACT), ("n", M::PUBLIC | M::ABSTRACT)]));
+    hierarchy.add(test_class("I2", interface, &["I1"],  &[("m", M::PUBLIC)]));
+    hierarchy.add(test_class("S",  class::Flags::PUBLIC, &["I2"], &[]));
+    let c = Class { super_path: Some(class::IdBuf::new("S".to_owned())), .. test_class("C", class::Flags::PUBLIC, &["I1"], &[]) };
+
+    let methods = hierarchy.methods(&c).unwrap().into_iter().map(|m| format!("{}.{}", m.declaring.as_str(), m.member.name)).collect::<Vec<_>>();
+    assert_eq!(methods, &["I2.m", "I1.n"]);
+
+    // Unrelated interfaces:  the default method is preferred over the abstract one
+    hierarchy.add(test_class("I3", interface, &[], &[("m", M::PUBLIC | M::ABSTRACT)]));
+    let d = test_class("D", class::Flags::PUBLIC, &["I3", "I2"], &[]);
+    let methods = hierarchy.methods(&d).unwrap().into_iter().map(|m| format!("{}.{}", m.declaring.as_str(), m.member.name)).collect::<Vec<_>>();
+    assert_eq!(methods, &["I2.m", "I1.n"]);
+}
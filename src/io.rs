@@ -26,7 +26,7 @@ pub mod be {
     pub fn read_i8(r: &mut impl Read) -> Result<i64> { read_u8(r).map(|u| u as i64) }
 }
 
-#[allow(dead_code)]
+#[allow(dead_code, unused_imports)]
 pub mod le {
     use std::io::{Read, Result};
     pub use super::common::*;
@@ -66,10 +66,10 @@ pub mod common {
 
     pub fn read_i1(r: &mut impl Read) -> Result< i8> { read_u1(r).map(|u| u as  i8) }
 
+    /// Skip `bytes` bytes of `read` without buffering them, failing like `read_exact` if fewer remain.
     pub fn read_ignore(read: &mut impl Read, bytes: usize) -> Result<()> {
-        let mut info = Vec::new();
-        info.resize(bytes, 0u8);
-        read.read_exact(&mut info[..])?;
+        let skipped = std::io::copy(&mut read.by_ref().take(bytes as u64), &mut std::io::sink())?;
+        if skipped != bytes as u64 { return Err(std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "failed to fill whole buffer")); }
         Ok(())
     }
 }
@@ -0,0 +1,564 @@
+//! Reading and writing .jar files directly (as opposed to reading classes from them via [Source].)  Writing requires the
+//! `writer` feature.
+//!
+//! [Source]:   ../struct.Source.html
+
+use crate::{Class, EntryNameDecoding, ParseOptions};
+
+use flate2::Crc;
+use flate2::bufread::DeflateDecoder;
+
+#[cfg(feature = "writer")] use std::collections::*;
+#[cfg(feature = "writer")] use std::fs::File;
+use std::io::{self, BufReader, Error, ErrorKind, Read};
+#[cfg(feature = "writer")] use std::io::{BufWriter, Seek, Write};
+#[cfg(feature = "writer")] use std::path::Path;
+
+#[cfg(feature = "writer")] use zip::{CompressionMethod, DateTime, ZipArchive, ZipWriter};
+#[cfg(feature = "writer")] use zip::write::FileOptions;
+
+
+
+/// What [trim] kept and removed.
+///
+/// [trim]: fn.trim.html
+#[cfg(feature = "writer")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TrimStats {
+    /// How many classes were copied to the output jar.
+    pub classes_kept:       usize,
+
+    /// How many classes were omitted from the output jar.
+    pub classes_removed:    usize,
+
+    /// How many resources (including the manifest and directory entries) were copied to the output jar.
+    pub resources_kept:     usize,
+
+    /// How many signature files (`META-INF/*.SF`, `*.RSA`, ...) were omitted, since trimming invalidates them.
+    pub signatures_removed: usize,
+}
+
+/// Writes classes and resources into a reproducible .jar:  entries are sorted (with the manifest first, as
+/// `java.util.jar.JarInputStream` expects), and timestamps and permissions are fixed, so the same inputs always
+/// produce byte-identical output regardless of the order they were added in.
+///
+/// Entries are buffered in memory until [finish](#method.finish) is called.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use jreflection::*;
+/// let src = Source::from_jar("app.jar").unwrap();
+/// let mut jar = jar::Writer::create("repackaged.jar").unwrap();
+/// jar.add_manifest(b"Manifest-Version: 1.0\r\nMain-Class: com.example.Main\r\n".to_vec()).unwrap();
+/// jar.add_class("com/example/Main", src.read_class_bytes("com/example/Main").unwrap()).unwrap();
+/// jar.finish().unwrap();
+/// ```
+#[cfg(feature = "writer")]
+pub struct Writer<W: Write + Seek> {
+    zip:        ZipWriter<W>,
+    entries:    BTreeMap<String, Vec<u8>>,
+    compressed: bool,
+}
+
+#[cfg(feature = "writer")]
+impl Writer<BufWriter<File>> {
+    /// Create (or truncate) the .jar file at `path`.
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self::new(BufWriter::new(File::create(path)?)))
+    }
+}
+
+#[cfg(feature = "writer")]
+impl<W: Write + Seek> Writer<W> {
+    /// Write a .jar to `write`.
+    pub fn new(write: W) -> Self {
+        Self { zip: ZipWriter::new(write), entries: BTreeMap::new(), compressed: true }
+    }
+
+    /// Deflate entries (the default), or store them uncompressed.
+    pub fn set_compressed(&mut self, compressed: bool) { self.compressed = compressed; }
+
+    /// Add the raw .class file bytes of the class with JNI path `path` (e.g. `"com/example/Main"`.)
+    pub fn add_class(&mut self, path: &str, bytes: Vec<u8>) -> io::Result<()> {
+        if !bytes.starts_with(&[0xCA, 0xFE, 0xBA, 0xBE]) { return Err(Error::new(ErrorKind::InvalidInput, format!("Not a class file: {}", path))); }
+        self.add_resource(&format!("{}.class", path), bytes)
+    }
+
+    /// Add `META-INF/MANIFEST.MF`.
+    pub fn add_manifest(&mut self, bytes: Vec<u8>) -> io::Result<()> {
+        self.add_resource(MANIFEST, bytes)
+    }
+
+    /// Add an arbitrary file, such as `"com/example/strings.properties"`.  Errors if the name was already added.
+    pub fn add_resource(&mut self, name: &str, bytes: Vec<u8>) -> io::Result<()> {
+        if self.entries.contains_key(name) { return Err(Error::new(ErrorKind::AlreadyExists, format!("Duplicate jar entry: {}", name))); }
+        self.entries.insert(name.to_owned(), bytes);
+        Ok(())
+    }
+
+    /// Write every entry and the zip directory, returning the underlying writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        let options = FileOptions::default()
+            .compression_method(if self.compressed { CompressionMethod::Deflated } else { CompressionMethod::Stored })
+            .last_modified_time(DateTime::default()) // 1980-01-01 00:00:00, the earliest zip timestamp
+            .unix_permissions(0o644);
+
+        let manifest = self.entries.remove(MANIFEST).map(|bytes| (MANIFEST.to_owned(), bytes));
+        for (name, bytes) in manifest.into_iter().chain(std::mem::replace(&mut self.entries, BTreeMap::new())) {
+            self.zip.start_file(name, options)?;
+            self.zip.write_all(&bytes[..])?;
+        }
+        Ok(self.zip.finish()?)
+    }
+}
+
+#[cfg(feature = "writer")]
+const MANIFEST : &str = "META-INF/MANIFEST.MF";
+
+/// Copy the jar at `input` to `output`, omitting any classes not in `keep` (a set of JNI paths, such as a
+/// [Closure](../classpath/struct.Closure.html)'s `classes`.)
+///
+/// Entries are copied without recompression, so compression settings, timestamps, and the manifest are preserved as-is.
+/// Every resource is kept, except for jar signature files, which would no longer match the trimmed contents.  Versioned
+/// classes of multi-release jars (`META-INF/versions/N/...`) are kept or removed along with the class they version, and
+/// `module-info.class` is always kept.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use jreflection::*;
+/// let classpath = ClassPath::from_sources(vec![Source::from_jar("app.jar").unwrap()]);
+/// let closure = classpath.closure(&["com/example/Main"]).unwrap();
+/// let stats = jar::trim("app.jar", "app-trimmed.jar", &closure.classes).unwrap();
+/// println!("removed {} unreachable classes", stats.classes_removed);
+/// ```
+#[cfg(feature = "writer")]
+pub fn trim(input: impl AsRef<Path>, output: impl AsRef<Path>, keep: &BTreeSet<String>) -> io::Result<TrimStats> {
+    let mut input   = ZipArchive::new(BufReader::new(File::open(input)?))?;
+    let mut output  = ZipWriter::new(BufWriter::new(File::create(output)?));
+    let mut stats   = TrimStats::default();
+
+    for i in 0..input.len() {
+        let entry = input.by_index_raw(i)?;
+        if let Some(class) = class_entry_path(entry.name()) {
+            if class != "module-info" && !keep.contains(class) {
+                stats.classes_removed += 1;
+                continue;
+            }
+            stats.classes_kept += 1;
+        } else if is_signature_file(entry.name()) {
+            stats.signatures_removed += 1;
+            continue;
+        } else {
+            stats.resources_kept += 1;
+        }
+        output.raw_copy_file(entry)?;
+    }
+
+    output.finish()?;
+    Ok(stats)
+}
+
+/// The JNI path of the class a `.class` entry named `name` holds, without any multi-release `META-INF/versions/N/`
+/// prefix:  `"com/example/Main"` for both `"com/example/Main.class"` and `"META-INF/versions/11/com/example/Main.class"`.
+#[cfg(feature = "writer")]
+fn class_entry_path(name: &str) -> Option<&str> {
+    if !name.ends_with(".class") { return None; }
+    let path = &name[..name.len()-".class".len()];
+    if path.starts_with("META-INF/versions/") {
+        let versioned = &path["META-INF/versions/".len()..];
+        if let Some(slash) = versioned.find('/') {
+            if slash > 0 && versioned[..slash].bytes().all(|b| b.is_ascii_digit()) { return Some(&versioned[slash+1..]); }
+        }
+    }
+    Some(path)
+}
+
+/// Is `name` a jar signature file, such as `META-INF/CERT.SF` or `META-INF/CERT.RSA`?
+#[cfg(feature = "writer")]
+pub(crate) fn is_signature_file(name: &str) -> bool {
+    if !name.starts_with("META-INF/") { return false; }
+    let file = &name["META-INF/".len()..];
+    if file.contains('/') { return false; }
+    let upper = file.to_ascii_uppercase();
+    upper.starts_with("SIG-") || [".SF", ".RSA", ".DSA", ".EC"].iter().any(|ext| upper.ends_with(ext))
+}
+
+/// A single entry read by a [StreamReader](struct.StreamReader.html).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StreamEntry {
+    /// The path of the entry within the `.jar`, such as `"java/lang/Object.class"` or `"META-INF/MANIFEST.MF"`.
+    pub name:   String,
+
+    /// The uncompressed contents of the entry.
+    pub bytes:  Vec<u8>,
+}
+
+impl StreamEntry {
+    /// The JNI path of the class this entry contains, if it's a `.class` file (e.g. `"java/lang/Object"`.)
+    pub fn class_path(&self) -> Option<&str> {
+        if self.name.ends_with(".class") { Some(&self.name[..self.name.len()-6]) } else { None }
+    }
+}
+
+/// Reads a .jar front to back from a non-seekable stream, such as stdin or an HTTP response body, by walking the local
+/// file headers instead of reading the central directory at the end of the file.
+///
+/// Compared to a [Source](../struct.Source.html), this has some tradeoffs:
+/// *   Entries can only be read once, in archive order, so random access by class name isn't possible.
+/// *   Entries deleted or replaced by tools that append to archives (rather than rewriting them) will still be seen,
+///     as only the central directory records which entries are live.
+/// *   Stored (uncompressed) entries whose size is only recorded after their data (in a "data descriptor") can't be
+///     delimited, and error.  Deflated entries with data descriptors, as `java.util.jar.JarOutputStream` writes, are fine.
+/// *   Encrypted entries aren't supported.
+/// *   Entries larger than [DEFAULT_MAX_ENTRY_SIZE] once uncompressed error, to bound the memory a "zip bomb" can
+///     consume.  See [set_max_entry_size].
+///
+/// [DEFAULT_MAX_ENTRY_SIZE]:   #associatedconstant.DEFAULT_MAX_ENTRY_SIZE
+/// [set_max_entry_size]:       #method.set_max_entry_size
+///
+/// # Examples
+///
+/// ```no_run
+/// # use jreflection::*;
+/// let stdin = std::io::stdin();
+/// let mut jar = jar::StreamReader::new(stdin.lock());
+/// jar.for_each_class(&ParseOptions::default(), |class| {
+///     println!("{}", class.path.as_str());
+///     Ok(())
+/// }).unwrap();
+/// ```
+pub struct StreamReader<R: Read> {
+    read:           BufReader<R>,
+    decoding:       EntryNameDecoding,
+    max_entry_size: u64,
+    done:           bool,
+}
+
+impl<R: Read> StreamReader<R> {
+    /// The default limit on an entry's uncompressed size.  Far larger than any real class (which the class file format
+    /// caps well below this), while still bounding what a small, maliciously compressed entry can inflate to.
+    pub const DEFAULT_MAX_ENTRY_SIZE : u64 = 256 * 1024 * 1024;
+
+    /// Read a .jar from `read`, starting at its first local file header.
+    pub fn new(read: R) -> Self { Self { read: BufReader::new(read), decoding: EntryNameDecoding::default(), max_entry_size: Self::DEFAULT_MAX_ENTRY_SIZE, done: false } }
+
+    /// Change how entry names are decoded.  See [EntryNameDecoding](../enum.EntryNameDecoding.html).
+    pub fn set_entry_name_decoding(&mut self, decoding: EntryNameDecoding) { self.decoding = decoding; }
+
+    /// Change the limit on an entry's uncompressed size, past which [next_entry](#method.next_entry) errors instead of
+    /// reading further.  Defaults to [DEFAULT_MAX_ENTRY_SIZE](#associatedconstant.DEFAULT_MAX_ENTRY_SIZE).
+    pub fn set_max_entry_size(&mut self, max: u64) { self.max_entry_size = max; }
+
+    /// Read the next entry, or `None` once the central directory (or end of the stream) is reached.
+    pub fn next_entry(&mut self) -> io::Result<Option<StreamEntry>> {
+        if self.done { return Ok(None); }
+
+        let mut signature = [0u8; 4];
+        match self.read.read_exact(&mut signature) {
+            Err(ref err) if err.kind() == ErrorKind::UnexpectedEof => { self.done = true; return Ok(None); },
+            other => other?,
+        }
+        match u32::from_le_bytes(signature) {
+            0x04034B50 => {}, // Local file header
+            0x02014B50 | 0x06054B50 | 0x05054B50 => { self.done = true; return Ok(None); }, // Central directory, end of central directory, or digital signature
+            other => return io_data_err!("Expected a zip local file header, got signature 0x{:08X}", other),
+        }
+
+        let mut header = [0u8; 26];
+        self.read.read_exact(&mut header)?;
+        let u16_at = |i: usize| u16::from_le_bytes([header[i], header[i+1]]);
+        let u32_at = |i: usize| u32::from_le_bytes([header[i], header[i+1], header[i+2], header[i+3]]);
+        let flags           = u16_at(2);
+        let method          = u16_at(4);
+        let mut crc32       = u32_at(10);
+        let mut compressed  = u64::from(u32_at(14));
+        let mut size        = u64::from(u32_at(18));
+        let mut name        = vec![0u8; usize::from(u16_at(22))];
+        let mut extra       = vec![0u8; usize::from(u16_at(24))];
+        self.read.read_exact(&mut name)?;
+        self.read.read_exact(&mut extra)?;
+
+        let zip_name = if flags & (1 << 11) != 0 { String::from_utf8_lossy(&name).into_owned() } else { from_cp437(&name) };
+        let (name, _fallback) = self.decoding.decode(&name, &zip_name);
+        if flags & 1 != 0 { return Err(Error::new(ErrorKind::Other, format!("Encrypted jar entries aren't supported: {}", name))); }
+
+        let zip64 = zip64_sizes(&extra);
+        if let Some((z64_size, z64_compressed)) = zip64 {
+            if size       == 0xFFFF_FFFF { size       = z64_size; }
+            if compressed == 0xFFFF_FFFF { compressed = z64_compressed; }
+        }
+
+        let descriptor = flags & (1 << 3) != 0;
+        let max = self.max_entry_size;
+        if !descriptor && size > max { return io_data_err!("Jar entry {} is {} bytes uncompressed, more than the {} byte limit", name, size, max); }
+
+        // The header's size is only a hint:  start small and let read_to_end grow the buffer as data actually arrives.
+        // Reading one byte past `max` tells an entry that's exactly at the limit from one that's over it.
+        let mut bytes = Vec::with_capacity(if descriptor { 0 } else { size.min(64 * 1024) as usize });
+        let limit = max.saturating_add(1);
+        match (method, descriptor) {
+            (0, false)  => { (&mut self.read).take(compressed).take(limit).read_to_end(&mut bytes)?; },
+            (0, true)   => return Err(Error::new(ErrorKind::Other, format!("Stored jar entries with data descriptors can't be streamed: {}", name))),
+            (8, false)  => { DeflateDecoder::new((&mut self.read).take(compressed)).take(limit).read_to_end(&mut bytes)?; },
+            (8, true)   => { DeflateDecoder::new(&mut self.read).take(limit).read_to_end(&mut bytes)?; }, // Deflate streams end themselves
+            (other, _)  => return Err(Error::new(ErrorKind::Other, format!("Unsupported compression method {} for jar entry: {}", other, name))),
+        }
+        if bytes.len() as u64 > max { return io_data_err!("Jar entry {} is more than the {} byte limit uncompressed", name, max); }
+
+        if descriptor {
+            let mut word = [0u8; 4];
+            self.read.read_exact(&mut word)?;
+            if u32::from_le_bytes(word) == 0x08074B50 { self.read.read_exact(&mut word)?; } // Optional signature
+            crc32 = u32::from_le_bytes(word);
+            let mut sizes = vec![0u8; if zip64.is_some() { 16 } else { 8 }];
+            self.read.read_exact(&mut sizes)?;
+        }
+
+        let mut crc = Crc::new();
+        crc.update(&bytes);
+        io_assert!(crc.sum() == crc32, "CRC mismatch reading jar entry {}", name);
+        Ok(Some(StreamEntry { name, bytes }))
+    }
+
+    /// Parse every class in the rest of the stream, in archive order, skipping resources.
+    pub fn for_each_class(&mut self, options: &ParseOptions, mut f: impl FnMut(Class) -> io::Result<()>) -> io::Result<()> {
+        while let Some(entry) = self.next_entry()? {
+            let path = match entry.class_path() { Some(path) => path, None => continue };
+            let class = Class::read_with(&mut &entry.bytes[..], options).map_err(|e| Error::new(e.kind(), format!("Failed to parse class {:?}: {}", path, e)))?;
+            f(class)?;
+        }
+        Ok(())
+    }
+}
+
+/// The (uncompressed, compressed) sizes from a zip64 extended information extra field, if present.
+fn zip64_sizes(mut extra: &[u8]) -> Option<(u64, u64)> {
+    while extra.len() >= 4 {
+        let id  = u16::from_le_bytes([extra[0], extra[1]]);
+        let len = usize::from(u16::from_le_bytes([extra[2], extra[3]]));
+        let data = extra.get(4..4+len)?;
+        if id == 0x0001 && data.len() >= 16 {
+            let u64_at = |i: usize| { let mut b = [0u8; 8]; b.copy_from_slice(&data[i..i+8]); u64::from_le_bytes(b) };
+            return Some((u64_at(0), u64_at(8)));
+        }
+        extra = &extra[4+len..];
+    }
+    None
+}
+
+/// Decode a zip entry name not flagged as UTF-8, as [code page 437](https://en.wikipedia.org/wiki/Code_page_437).
+pub(crate) fn from_cp437(bytes: &[u8]) -> String {
+    const HIGH : &str = "ÇüéâäàåçêëèïîìÄÅÉæÆôöòûùÿÖÜ¢£¥₧ƒáíóúñÑªº¿⌐¬½¼¡«»░▒▓│┤╡╢╖╕╣║╗╝╜╛┐└┴┬├─┼╞╟╚╔╩╦╠═╬╧╨╤╥╙╘╒╓╫╪┘┌█▄▌▐▀αßΓπΣσµτΦΘΩδ∞φε∩≡±≥≤⌠⌡÷≈°∙·√ⁿ²■\u{a0}";
+    bytes.iter().map(|&b| if b < 0x80 { b as char } else { HIGH.chars().nth(usize::from(b - 0x80)).unwrap() }).collect()
+}
+
+#[cfg(feature = "writer")]
+#[test] fn stream_reader() {
+    use flate2::write::DeflateEncoder;
+
+    // A jar as zip::ZipWriter writes it:  sizes in the local headers
+    let object = crate::testing::ClassFile::new("java/lang/Object", None).to_bytes();
+    let mut jar = Writer::new(io::Cursor::new(Vec::new()));
+    jar.add_manifest(b"Manifest-Version: 1.0\r\n".to_vec()).unwrap();
+    jar.add_class("java/lang/Object", object.clone()).unwrap();
+    let bytes = jar.finish().unwrap().into_inner();
+
+    let mut stream = StreamReader::new(&bytes[..]); // &[u8] is Read, but not Seek
+    assert_eq!(stream.next_entry().unwrap().unwrap().name, MANIFEST);
+    let entry = stream.next_entry().unwrap().unwrap();
+    assert_eq!((entry.class_path(), &entry.bytes), (Some("java/lang/Object"), &object));
+    assert_eq!(stream.next_entry().unwrap(), None);
+    assert_eq!(stream.next_entry().unwrap(), None);
+
+    // A jar as JarOutputStream writes it:  deflated, with sizes and CRCs in data descriptors after each entry
+    let mut bytes = Vec::new();
+    for (name, contents) in [("java/lang/Object.class", &object[..]), ("caf\u{e9}.txt", &b"coffee"[..])].iter() {
+        let mut deflated = DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+        deflated.write_all(contents).unwrap();
+        let deflated = deflated.finish().unwrap();
+        let mut crc = Crc::new();
+        crc.update(contents);
+        bytes.extend_from_slice(&[b'P', b'K', 3, 4, 20, 0, 0x08, 0x08, 8, 0, 0, 0, 0, 0]); // signature, version, flags (descriptor | utf8), deflate, time, date
+        bytes.extend_from_slice(&[0; 12]); // crc, sizes
+        bytes.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(&[0, 0]);
+        bytes.extend_from_slice(name.as_bytes());
+        bytes.extend_from_slice(&deflated);
+        bytes.extend_from_slice(&[b'P', b'K', 7, 8]);
+        bytes.extend_from_slice(&crc.sum().to_le_bytes());
+        bytes.extend_from_slice(&(deflated.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&(contents.len() as u32).to_le_bytes());
+    }
+    bytes.extend_from_slice(&[b'P', b'K', 1, 2]); // central directory follows
+
+    let mut classes = Vec::new();
+    StreamReader::new(&bytes[..]).for_each_class(&ParseOptions::default(), |class| { classes.push(class.path.as_str().to_owned()); Ok(()) }).unwrap();
+    assert_eq!(classes, &["java/lang/Object"]);
+    let mut stream = StreamReader::new(&bytes[..]);
+    stream.next_entry().unwrap();
+    assert_eq!(stream.next_entry().unwrap().unwrap(), StreamEntry { name: "caf\u{e9}.txt".into(), bytes: b"coffee".to_vec() });
+
+    let corrupt = bytes.len() - 40; // Somewhere in the second entry's data
+    bytes[corrupt] ^= 0xFF;
+    let mut stream = StreamReader::new(&bytes[..]);
+    stream.next_entry().unwrap();
+    assert!(stream.next_entry().is_err());
+
+    assert_eq!(from_cp437(b"caf\x82 \xC3\xA9"), "caf\u{e9} \u{251C}\u{2310}");
+}
+
+#[cfg(feature = "writer")]
+#[test] fn stream_reader_limits() {
+    use flate2::write::DeflateEncoder;
+
+    // A single deflated entry, with `size` claimed in its local header (or deferred to a data descriptor if `None`)
+    let entry = |contents: &[u8], size: Option<u32>| {
+        let mut deflated = DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+        deflated.write_all(contents).unwrap();
+        let deflated = deflated.finish().unwrap();
+        let mut crc = Crc::new();
+        crc.update(contents);
+        let mut bytes = vec![b'P', b'K', 3, 4, 20, 0, if size.is_some() { 0 } else { 0x08 }, 0, 8, 0, 0, 0, 0, 0];
+        bytes.extend_from_slice(&crc.sum().to_le_bytes());
+        bytes.extend_from_slice(&(deflated.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&size.unwrap_or(0).to_le_bytes());
+        bytes.extend_from_slice(&[8, 0, 0, 0]);
+        bytes.extend_from_slice(b"bomb.txt");
+        bytes.extend_from_slice(&deflated);
+        if size.is_none() {
+            bytes.extend_from_slice(&crc.sum().to_le_bytes());
+            bytes.extend_from_slice(&(deflated.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(&(contents.len() as u32).to_le_bytes());
+        }
+        bytes
+    };
+    let read = |bytes: &[u8], max: u64| {
+        let mut stream = StreamReader::new(bytes);
+        stream.set_max_entry_size(max);
+        stream.next_entry().map(|entry| entry.unwrap().bytes.len())
+    };
+
+    let zeros = vec![0u8; 1024 * 1024];
+    for &size in [None, Some(zeros.len() as u32)].iter() {
+        let bytes = entry(&zeros, size);
+        assert!(bytes.len() < 10 * 1024);
+        assert_eq!(read(&bytes, zeros.len() as u64).unwrap(), zeros.len());
+        assert_eq!(read(&bytes, zeros.len() as u64 - 1).unwrap_err().kind(), ErrorKind::InvalidData);
+        assert_eq!(read(&bytes, 1000).unwrap_err().kind(), ErrorKind::InvalidData);
+    }
+
+    // A header claiming a huge size is rejected up front, and otherwise only a hint
+    let lying = entry(b"tiny", Some(0xFFFF_FFF0));
+    assert_eq!(read(&lying, StreamReader::<&[u8]>::DEFAULT_MAX_ENTRY_SIZE).unwrap_err().kind(), ErrorKind::InvalidData);
+    assert_eq!(read(&lying, u64::max_value()).unwrap(), 4); // Without a 4 GB allocation
+}
+
+#[cfg(feature = "writer")]
+#[test] fn trim_jar() {
+    use std::io::Read;
+
+    let dir = std::env::temp_dir().join("jreflection-tests");
+    std::fs::create_dir_all(&dir).unwrap();
+    let input   = dir.join("jar-trim-input.jar");
+    let output  = dir.join("jar-trim-output.jar");
+
+    let stored = FileOptions::default().compression_method(CompressionMethod::Stored);
+    let mut zip = ZipWriter::new(File::create(&input).unwrap());
+    zip.start_file("META-INF/MANIFEST.MF",      FileOptions::default()).unwrap(); zip.write_all(b"Manifest-Version: 1.0\r\n").unwrap();
+    zip.start_file("META-INF/CERT.SF",          FileOptions::default()).unwrap(); zip.write_all(b"Signature-Version: 1.0\r\n").unwrap();
+    zip.start_file("META-INF/CERT.RSA",         FileOptions::default()).unwrap(); zip.write_all(b"...").unwrap();
+    zip.start_file("com/example/Main.class",    stored).unwrap();                 zip.write_all(b"main").unwrap();
+    zip.start_file("com/example/Unused.class",  FileOptions::default()).unwrap(); zip.write_all(b"unused").unwrap();
+    zip.start_file("com/example/strings.txt",   FileOptions::default()).unwrap(); zip.write_all(b"hello").unwrap();
+    zip.finish().unwrap();
+
+    let keep = Some("com/example/Main".to_owned()).into_iter().collect();
+    let stats = trim(&input, &output, &keep).unwrap();
+    assert_eq!(stats, TrimStats { classes_kept: 1, classes_removed: 1, resources_kept: 2, signatures_removed: 2 });
+
+    let mut zip = ZipArchive::new(File::open(&output).unwrap()).unwrap();
+    assert_eq!(zip.file_names().collect::<BTreeSet<_>>(), ["META-INF/MANIFEST.MF", "com/example/Main.class", "com/example/strings.txt"].iter().copied().collect());
+    let mut main = zip.by_name("com/example/Main.class").unwrap();
+    assert_eq!(main.compression(), CompressionMethod::Stored);
+    let mut contents = Vec::new();
+    main.read_to_end(&mut contents).unwrap();
+    assert_eq!(contents, b"main");
+}
+
+#[cfg(feature = "writer")]
+#[test] fn trim_modular_jar() {
+    let dir = std::env::temp_dir().join("jreflection-tests");
+    let input = crate::testing::write_jar("jar-trim-modular-input", &[
+        ("module-info.class",           b"module".to_vec()),
+        ("com/example/Main.class",      b"main".to_vec()),
+        ("com/example/Unused.class",    b"unused".to_vec()),
+    ]);
+    let output = dir.join("jar-trim-modular-output.jar");
+
+    let keep = Some("com/example/Main".to_owned()).into_iter().collect();
+    let stats = trim(&input, &output, &keep).unwrap();
+    assert_eq!(stats, TrimStats { classes_kept: 2, classes_removed: 1, resources_kept: 0, signatures_removed: 0 });
+    let zip = ZipArchive::new(File::open(&output).unwrap()).unwrap();
+    assert_eq!(zip.file_names().collect::<BTreeSet<_>>(), ["module-info.class", "com/example/Main.class"].iter().copied().collect());
+}
+
+#[cfg(feature = "writer")]
+#[test] fn trim_multi_release_jar() {
+    let dir = std::env::temp_dir().join("jreflection-tests");
+    let input = crate::testing::write_jar("jar-trim-multi-release-input", &[
+        ("META-INF/MANIFEST.MF",                            b"Manifest-Version: 1.0\r\nMulti-Release: true\r\n".to_vec()),
+        ("com/example/Main.class",                          b"main".to_vec()),
+        ("com/example/Unused.class",                        b"unused".to_vec()),
+        ("META-INF/versions/9/module-info.class",           b"module".to_vec()),
+        ("META-INF/versions/11/com/example/Main.class",     b"main 11".to_vec()),
+        ("META-INF/versions/11/com/example/Unused.class",   b"unused 11".to_vec()),
+        ("META-INF/versions/latest/com/example/Main.class", b"not versioned".to_vec()),
+    ]);
+    let output = dir.join("jar-trim-multi-release-output.jar");
+
+    let keep = ["com/example/Main", "META-INF/versions/latest/com/example/Main"].iter().map(|c| c.to_string()).collect();
+    let stats = trim(&input, &output, &keep).unwrap();
+    assert_eq!(stats, TrimStats { classes_kept: 4, classes_removed: 2, resources_kept: 1, signatures_removed: 0 });
+    let zip = ZipArchive::new(File::open(&output).unwrap()).unwrap();
+    assert_eq!(zip.file_names().collect::<BTreeSet<_>>(), [
+        "META-INF/MANIFEST.MF",
+        "com/example/Main.class",
+        "META-INF/versions/9/module-info.class",
+        "META-INF/versions/11/com/example/Main.class",
+        "META-INF/versions/latest/com/example/Main.class",
+    ].iter().copied().collect());
+
+    assert_eq!(class_entry_path("META-INF/versions/11/com/example/Main.class"), Some("com/example/Main"));
+    assert_eq!(class_entry_path("META-INF/versions//com/example/Main.class"),   Some("META-INF/versions//com/example/Main"));
+    assert_eq!(class_entry_path("META-INF/versions/11/strings.txt"),            None);
+}
+
+#[cfg(feature = "writer")]
+#[test] fn deterministic_writer() {
+    let class = |n: u8| vec![0xCA, 0xFE, 0xBA, 0xBE, n];
+    let write = |reverse: bool| {
+        let mut jar = Writer::new(io::Cursor::new(Vec::new()));
+        let mut entries = vec![("com/example/B", class(2)), ("com/example/A", class(1)), ("a/Z", class(3))];
+        if reverse { entries.reverse(); }
+        if reverse { jar.add_resource("a/strings.txt", b"hello".to_vec()).unwrap(); }
+        for (path, bytes) in entries { jar.add_class(path, bytes).unwrap(); }
+        jar.add_manifest(b"Manifest-Version: 1.0\r\n".to_vec()).unwrap();
+        if !reverse { jar.add_resource("a/strings.txt", b"hello".to_vec()).unwrap(); }
+        jar.finish().unwrap().into_inner()
+    };
+
+    let bytes = write(false);
+    assert_eq!(bytes, write(true));
+
+    let zip = ZipArchive::new(io::Cursor::new(bytes)).unwrap();
+    let mut names = (0..zip.len()).map(|i| zip.clone().by_index(i).unwrap().name().to_owned()).collect::<Vec<_>>();
+    assert_eq!(names.remove(0), "META-INF/MANIFEST.MF");
+    assert_eq!(names, &["a/Z.class", "a/strings.txt", "com/example/A.class", "com/example/B.class"]);
+
+    let mut jar = Writer::new(io::Cursor::new(Vec::new()));
+    assert_eq!(jar.add_class("a/B", b"not a class".to_vec()).unwrap_err().kind(), ErrorKind::InvalidInput);
+    jar.add_resource("a.txt", Vec::new()).unwrap();
+    assert_eq!(jar.add_resource("a.txt", Vec::new()).unwrap_err().kind(), ErrorKind::AlreadyExists);
+}
@@ -0,0 +1,507 @@
+//! Java source-like declarations of classes, for generated stubs and reports.
+//!
+//! Types are written with simple names wherever an `import` makes that unambiguous, so `java.util.List<java.lang.String>`
+//! reads as `List<String>`, with `import java.util.List;` collected at the top.  See [Imports](struct.Imports.html).
+
+use crate::{field, filter, method, names, Class, Method, Source};
+use crate::signature::*;
+#[cfg(test)] use crate::testing;
+
+use std::collections::*;
+use std::fmt::Write;
+use std::io;
+use std::path::Path;
+
+
+
+/// The import-collection pass for rendering declarations:  which classes to `import`, and how to name each class type.
+///
+/// [add_type](#method.add_type) every type a declaration uses first, then render them with [type_name](#method.type_name).
+/// Classes are named by their simple name if that's unambiguous, and fully qualified otherwise.  Classes in
+/// `java.lang` or the same package as the declared class are named simply without being imported.  Nested classes are
+/// named via their outermost class, so `java/util/Map$Entry` is written `Map.Entry` and imports `java.util.Map`.
+///
+/// # Examples
+///
+/// ```
+/// # use jreflection::{java::Imports, signature::Type};
+/// let string_list = Type::parse("Ljava/util/List<Ljava/lang/String;>;").unwrap();
+/// let awt_list    = Type::parse("Ljava/awt/List;").unwrap();
+/// let entry       = Type::parse("Ljava/util/Map$Entry;").unwrap();
+/// let widget      = Type::parse("Lcom/example/Widget;").unwrap();
+///
+/// let mut imports = Imports::new("com/example/Foo");
+/// for ty in [&string_list, &awt_list, &entry, &widget].iter() { imports.add_type(ty); }
+///
+/// assert_eq!(imports.imports(), &["java.util.Map"]);
+/// assert_eq!(imports.type_name(&string_list), "java.util.List<String>"); // Ambiguous with java.awt.List
+/// assert_eq!(imports.type_name(&awt_list),    "java.awt.List");
+/// assert_eq!(imports.type_name(&entry),       "Map.Entry");
+/// assert_eq!(imports.type_name(&widget),      "Widget");
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Imports {
+    owner:  String,                                 // The outermost class being declared, such as "com/example/Foo"
+    names:  BTreeMap<String, BTreeSet<String>>,     // simple name -> outermost classes
+}
+
+impl Imports {
+    /// Start collecting imports for a declaration of the class at JNI path `class`.
+    pub fn new(class: &str) -> Self {
+        let owner = outermost(class).to_owned();
+        let mut imports = Self { owner: owner.clone(), names: BTreeMap::new() };
+        imports.add_class(&owner);
+        imports
+    }
+
+    /// Record that the declaration uses the class at JNI path `class`.
+    pub fn add_class(&mut self, class: &str) {
+        let outer = outermost(class);
+        self.names.entry(simple(outer).to_owned()).or_default().insert(outer.to_owned());
+    }
+
+    /// Record that the declaration uses `ty`, and every type nested within it.
+    pub fn add_type(&mut self, ty: &Type) {
+        match ty {
+            Type::Base(_) | Type::TypeVariable(_)   => {},
+            Type::Array(element)                    => self.add_type(element),
+            Type::Class(class)                      => {
+                self.add_class(&class.erasure());
+                for argument in class.segments.iter().flat_map(|s| s.type_arguments.iter()) {
+                    match argument {
+                        TypeArgument::Any => {},
+                        TypeArgument::Exact(ty) | TypeArgument::Extends(ty) | TypeArgument::Super(ty) => self.add_type(ty),
+                    }
+                }
+            },
+        }
+    }
+
+    /// Record that the declaration uses the bounds of `type_parameters`.
+    pub fn add_type_parameters(&mut self, type_parameters: &[TypeParameter]) {
+        for p in type_parameters.iter() {
+            for bound in p.class_bound.iter().chain(p.interface_bounds.iter()) { self.add_type(bound); }
+        }
+    }
+
+    /// The classes to import, as dotted Java names such as `"java.util.List"`, sorted.
+    pub fn imports(&self) -> Vec<String> {
+        let own_package = package(&self.owner);
+        let mut imports = self.names.iter()
+            .filter(|(_, classes)| classes.len() == 1)
+            .flat_map(|(_, classes)| classes.iter())
+            .filter(|class| { let p = package(class); p != own_package && p != "java/lang" && !p.is_empty() })
+            .map(|class| class.replace('/', "."))
+            .collect::<Vec<_>>();
+        imports.sort();
+        imports
+    }
+
+    /// The name to write the class at JNI path `class` as, such as `"List"`, `"Map.Entry"`, or `"java.awt.List"`.
+    pub fn class_name(&self, class: &str) -> String {
+        let outer = outermost(class);
+        let name = simple(outer);
+        let unambiguous = outer == self.owner || (simple(&self.owner) != name && self.names.get(name).map(|c| c.len() <= 1).unwrap_or(true));
+        let outer = if unambiguous { name.to_owned() } else { outer.replace('/', ".") };
+        format!("{}{}", outer, class[outermost(class).len()..].replace('$', "."))
+    }
+
+    /// Write `ty` in Java syntax, such as `"Map.Entry<K, V>[]"`.
+    pub fn type_name(&self, ty: &Type) -> String {
+        match ty {
+            Type::Base(base)        => base_name(*base).to_owned(),
+            Type::TypeVariable(v)   => v.clone(),
+            Type::Array(element)    => format!("{}[]", self.type_name(element)),
+            Type::Class(class)      => {
+                let mut name = self.class_name(&class.erasure());
+                let arguments = class.segments.iter().rev().find(|s| !s.type_arguments.is_empty()).map(|s| &s.type_arguments[..]).unwrap_or(&[]);
+                if !arguments.is_empty() {
+                    name.push('<');
+                    for (i, argument) in arguments.iter().enumerate() {
+                        if i > 0 { name.push_str(", "); }
+                        match argument {
+                            TypeArgument::Any           => name.push('?'),
+                            TypeArgument::Exact(ty)     => name.push_str(&self.type_name(ty)),
+                            TypeArgument::Extends(ty)   => { name.push_str("? extends "); name.push_str(&self.type_name(ty)); },
+                            TypeArgument::Super(ty)     => { name.push_str("? super "); name.push_str(&self.type_name(ty)); },
+                        }
+                    }
+                    name.push('>');
+                }
+                name
+            },
+        }
+    }
+
+    /// Write `type_parameters` in Java syntax, such as `"<T extends Comparable<? super T>>"`, or `""` if there are none.
+    pub fn type_parameters(&self, type_parameters: &[TypeParameter]) -> String {
+        if type_parameters.is_empty() { return String::new(); }
+        let mut out = String::from("<");
+        for (i, p) in type_parameters.iter().enumerate() {
+            if i > 0 { out.push_str(", "); }
+            out.push_str(&p.name);
+            let bounds = p.class_bound.iter().chain(p.interface_bounds.iter()).filter(|b| !is_object(b)).collect::<Vec<_>>();
+            for (i, bound) in bounds.into_iter().enumerate() {
+                out.push_str(if i == 0 { " extends " } else { " & " });
+                out.push_str(&self.type_name(bound));
+            }
+        }
+        out.push('>');
+        out
+    }
+}
+
+/// Render a Java source-like stub of `class`:  its package, the minimal imports, its declaration, and the
+/// declarations of its fields and methods (without bodies.)  Only members included by `filter` are rendered.
+///
+/// Parameter names aren't recorded in class files, so parameters are named `arg0`, `arg1`, etc.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use jreflection::*;
+/// let src = Source::from_jdk_dir(r"C:\Program Files\AdoptOpenJDK\jdk-8.0.232.09-hotspot").unwrap();
+/// let array_list = src.read_class("java/util/ArrayList").unwrap();
+/// println!("{}", java::render_class(&array_list, &filter::Filter::new()).unwrap());
+/// // package java.util;
+/// //
+/// // import java.io.Serializable;
+/// // import java.util.function.Consumer;
+/// // ...
+/// //
+/// // public class ArrayList<E> extends AbstractList<E> implements List<E>, RandomAccess, Cloneable, Serializable {
+/// //     public ArrayList(int arg0);
+/// //     ...
+/// // }
+/// ```
+pub fn render_class(class: &Class, filter: &filter::Filter) -> io::Result<String> {
+    let path = class.path.as_str();
+    let mut imports = Imports::new(path);
+
+    // Parse every type first, so imports are known before anything is named
+    let class_signature = match class.signature()? {
+        Some(signature) => signature,
+        None => ClassSignature {
+            type_parameters:    Vec::new(),
+            superclass:         class_type(class.super_path.as_ref().map(|s| s.as_str()).unwrap_or("java/lang/Object"))?,
+            interfaces:         class.interfaces.iter().map(|i| class_type(i.as_str())).collect::<io::Result<_>>()?,
+        },
+    };
+    let superclass = Some(&class_signature.superclass).filter(|_| !class.is_interface() && !class.is_enum())
+        .filter(|s| s.erasure() != "java/lang/Object").cloned().map(Type::Class);
+    let interfaces = class_signature.interfaces.iter().filter(|i| !(class.is_annotation() && i.erasure() == "java/lang/annotation/Annotation"))
+        .cloned().map(Type::Class).collect::<Vec<_>>();
+    imports.add_type_parameters(&class_signature.type_parameters[..]);
+    for ty in superclass.iter().chain(interfaces.iter()) { imports.add_type(ty); }
+
+    let mut fields = Vec::new();
+    for field in class.fields.iter().filter(|f| filter.includes_field(class, f)) {
+        let ty = Type::parse(field.signature_str().unwrap_or(field.descriptor_str()))?;
+        imports.add_type(&ty);
+        fields.push((field, ty));
+    }
+
+    let mut methods = Vec::new();
+    for method in class.methods.iter().filter(|m| filter.includes_method(class, m)) {
+        let signature = MethodSignature::parse(method.signature_str().unwrap_or(method.descriptor_str()))?;
+        imports.add_type_parameters(&signature.type_parameters[..]);
+        for ty in signature.parameters.iter().chain(Some(&signature.return_type)).chain(signature.throws.iter()) { imports.add_type(ty); }
+        methods.push((method, signature));
+    }
+
+    // Render
+    let mut out = String::new();
+    if !package(path).is_empty() { let _ = writeln!(out, "package {};\n", package(path).replace('/', ".")); }
+    let import_list = imports.imports();
+    for import in import_list.iter() { let _ = writeln!(out, "import {};", import); }
+    if !import_list.is_empty() { out.push('\n'); }
+
+    let name = names::simple_name(path).unwrap_or(&path[path.rfind('/').map(|s| s+1).unwrap_or(0)..]);
+    let kind = if class.is_annotation() { "@interface" } else if class.is_interface() { "interface" } else if class.is_enum() { "enum" } else { "class" };
+    if class.is_public()                                                            { out.push_str("public "); }
+    if class.is_abstract() && !class.is_interface()                                 { out.push_str("abstract "); }
+    if class.is_final() && !class.is_enum()                                         { out.push_str("final "); }
+    let _ = write!(out, "{} {}{}", kind, name, imports.type_parameters(&class_signature.type_parameters[..]));
+    if let Some(superclass) = superclass.as_ref() { let _ = write!(out, " extends {}", imports.type_name(superclass)); }
+    for (i, interface) in interfaces.iter().enumerate() {
+        let keyword = if i > 0 { ", " } else if class.is_interface() { " extends " } else { " implements " };
+        let _ = write!(out, "{}{}", keyword, imports.type_name(interface));
+    }
+    out.push_str(" {\n");
+
+    for (field, ty) in fields.iter() {
+        out.push_str("    ");
+        if field.is_public()    { out.push_str("public "); }
+        if field.is_protected() { out.push_str("protected "); }
+        if field.is_private()   { out.push_str("private "); }
+        if field.is_static()    { out.push_str("static "); }
+        if field.is_final()     { out.push_str("final "); }
+        if field.flags.contains(field::Flags::TRANSIENT) { out.push_str("transient "); }
+        if field.flags.contains(field::Flags::VOLATILE)  { out.push_str("volatile "); }
+        let _ = write!(out, "{} {}", imports.type_name(ty), field.name);
+        if let Some(constant) = field.constant.as_ref() { let _ = write!(out, " = {}", literal(constant, ty)); }
+        out.push_str(";\n");
+    }
+
+    for (method, signature) in methods.iter() {
+        out.push_str("    ");
+        let flags = method.flags;
+        if flags.contains(method::Flags::PUBLIC)        { out.push_str("public "); }
+        if flags.contains(method::Flags::PROTECTED)     { out.push_str("protected "); }
+        if flags.contains(method::Flags::PRIVATE)       { out.push_str("private "); }
+        if flags.contains(method::Flags::ABSTRACT) && !class.is_interface() { out.push_str("abstract "); }
+        if class.is_interface() && !flags.intersects(method::Flags::ABSTRACT | method::Flags::STATIC | method::Flags::PRIVATE) { out.push_str("default "); }
+        if flags.contains(method::Flags::STATIC)        { out.push_str("static "); }
+        if flags.contains(method::Flags::FINAL)         { out.push_str("final "); }
+        if flags.contains(method::Flags::SYNCRONIZED)   { out.push_str("synchronized "); }
+        if flags.contains(method::Flags::NATIVE)        { out.push_str("native "); }
+        if flags.contains(method::Flags::STRICT)        { out.push_str("strictfp "); }
+        let type_parameters = imports.type_parameters(&signature.type_parameters[..]);
+        if !type_parameters.is_empty() { let _ = write!(out, "{} ", type_parameters); }
+        if method.is_constructor() {
+            out.push_str(name);
+        } else {
+            let _ = write!(out, "{} {}", imports.type_name(&signature.return_type), method.name);
+        }
+        out.push('(');
+        let varargs = flags.contains(method::Flags::VARARGS);
+        let names = parameter_names(method, signature.parameters.len());
+        for (i, parameter) in signature.parameters.iter().enumerate() {
+            if i > 0 { out.push_str(", "); }
+            match parameter {
+                Type::Array(element) if varargs && i + 1 == signature.parameters.len() => { let _ = write!(out, "{}... {}", imports.type_name(element), names[i]); },
+                parameter => { let _ = write!(out, "{} {}", imports.type_name(parameter), names[i]); },
+            }
+        }
+        out.push(')');
+        for (i, thrown) in signature.throws.iter().enumerate() {
+            let _ = write!(out, "{}{}", if i == 0 { " throws " } else { ", " }, imports.type_name(thrown));
+        }
+        out.push_str(";\n");
+    }
+
+    out.push_str("}\n");
+    Ok(out)
+}
+
+/// What [write_stubs] wrote and skipped.
+///
+/// [write_stubs]: fn.write_stubs.html
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StubStats {
+    /// How many `.java` stubs were written.
+    pub written:    usize,
+
+    /// How many classes were skipped because the filter excluded them.
+    pub filtered:   usize,
+
+    /// How many nested, local, or anonymous classes were skipped, since they don't get a `.java` file of their own.
+    pub nested:     usize,
+}
+
+/// Write a `.java` stub (see [render_class](fn.render_class.html)) for every top level class of `source` included by
+/// `filter`, to `dir/com/example/Foo.java` and so on.  Nested classes are skipped.
+///
+/// Paired with a trimmed .jar (see [jar::trim](../jar/fn.trim.html)), this gives documentation tools and IDEs something
+/// familiar to attach javadoc to.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use jreflection::*;
+/// # #[cfg(feature = "jar")] {
+/// let src = Source::from_jar("library.jar").unwrap();
+/// let stats = java::write_stubs(&src, &filter::Filter::new(), "stubs").unwrap();
+/// println!("Wrote {} stubs", stats.written);
+/// # }
+/// ```
+pub fn write_stubs(source: &Source, filter: &filter::Filter, dir: impl AsRef<Path>) -> io::Result<StubStats> {
+    let dir = dir.as_ref();
+    let mut stats = StubStats::default();
+    source.for_each_class(|path| {
+        if names::simple_name(&path) != Some(simple(&path)) { stats.nested += 1; return Ok(()); }
+        let class = source.read_class(&path)?;
+        if !filter.includes_class(&class) { stats.filtered += 1; return Ok(()); }
+        let stub = render_class(&class, filter)?;
+        let file = dir.join(format!("{}.java", path));
+        if let Some(parent) = file.parent() { std::fs::create_dir_all(parent)?; }
+        std::fs::write(file, stub)?;
+        stats.written += 1;
+        Ok(())
+    })?;
+    Ok(stats)
+}
+
+fn class_type(path: &str) -> io::Result<ClassType> {
+    match Type::parse(&format!("L{};", path))? {
+        Type::Class(class)  => Ok(class),
+        other               => io_data_err!("Expected a class type for {:?}, got {}", path, other),
+    }
+}
+
+fn is_object(ty: &Type) -> bool { match ty { Type::Class(c) => c.erasure() == "java/lang/Object", _ => false } }
+
+/// The outermost class of a (possibly nested) class, such as `java/util/Map` for `java/util/Map$Entry`.
+fn outermost(class: &str) -> &str {
+    let slash = class.rfind('/').map(|s| s+1).unwrap_or(0);
+    match names::simple_name(class) {
+        Some(_) => class[slash..].find('$').filter(|&d| d > 0).map(|d| &class[..slash+d]).unwrap_or(class),
+        None    => class, // Not nested in a way Java source could name
+    }
+}
+
+fn package(class: &str) -> &str { &class[..class.rfind('/').unwrap_or(0)] }
+fn simple(class: &str) -> &str { &class[class.rfind('/').map(|s| s+1).unwrap_or(0)..] }
+
+/// The source names of `count` parameters of `method`, falling back on `MethodParameters` alone if the count differs from its descriptor's.
+fn parameter_names(method: &Method, count: usize) -> Vec<String> {
+    let names = method.parameter_names();
+    if names.len() == count { return names; }
+    let mut parameters = method.parameters().iter().collect::<Vec<_>>();
+    if parameters.len() != count { parameters.retain(|p| !p.flags.intersects(method::ParameterFlags::SYNTHETIC | method::ParameterFlags::MANDATED)); } // Generic signatures omit these
+    (0..count).map(|i| match parameters.get(i).filter(|_| parameters.len() == count).and_then(|p| p.name.as_ref()) {
+        Some(name)  => name.clone(),
+        None        => format!("arg{}", i),
+    }).collect()
+}
+
+fn base_name(base: BaseType) -> &'static str {
+    match base {
+        BaseType::Byte      => "byte",
+        BaseType::Char      => "char",
+        BaseType::Double    => "double",
+        BaseType::Float     => "float",
+        BaseType::Int       => "int",
+        BaseType::Long      => "long",
+        BaseType::Short     => "short",
+        BaseType::Boolean   => "boolean",
+        BaseType::Void      => "void",
+    }
+}
+
+/// A Java literal for a field's `ConstantValue`.
+fn literal(constant: &field::Constant, ty: &Type) -> String {
+    use crate::field::Constant as C;
+    match (constant, ty) {
+        (C::Integer(value), Type::Base(BaseType::Boolean))         => (*value != 0).to_string(),
+        (C::Integer(value), Type::Base(BaseType::Char))            => match std::char::from_u32(*value as u32) {
+            Some(ch) if !ch.is_control() && ch != '\'' && ch != '\\'  => format!("'{}'", ch),
+            _                                                   => format!("'\\u{:04x}'", *value as u16),
+        },
+        (C::Integer(value), _)                                     => value.to_string(),
+        (C::Long(value), _)                                        => format!("{}L", value),
+        (C::Float(value), _) if value.is_nan()                     => "0.0f / 0.0f".to_owned(),
+        (C::Float(value), _) if value.is_infinite()                => format!("{}1.0f / 0.0f", if *value < 0.0 { "-" } else { "" }),
+        (C::Float(value), _)                                       => format!("{:?}f", value),
+        (C::Double(value), _) if value.is_nan()                    => "0.0 / 0.0".to_owned(),
+        (C::Double(value), _) if value.is_infinite()               => format!("{}1.0 / 0.0", if *value < 0.0 { "-" } else { "" }),
+        (C::Double(value), _)                                      => format!("{:?}", value),
+        (C::String(Some(value)), _)                             => {
+            let mut out = String::from("\"");
+            for ch in value.chars() {
+                match ch {
+                    '"'                     => out.push_str("\\\""),
+                    '\\'                    => out.push_str("\\\\"),
+                    '\n'                    => out.push_str("\\n"),
+                    '\r'                    => out.push_str("\\r"),
+                    '\t'                    => out.push_str("\\t"),
+                    ch if ch.is_control()   => { let _ = write!(out, "\\u{:04x}", ch as u32); },
+                    ch                      => out.push(ch),
+                }
+            }
+            out.push('"');
+            out
+        },
+        (C::String(None), _)                                    => "null /* invalid modified UTF8 */".to_owned(),
+        (C::_NonExhaustive, _)                                  => "null /* unknown constant */".to_owned(),
+    }
+}
+
+#[test] fn render() {
+    let mut c = testing::ClassFile::new("com/example/Foo", Some("com/example/Base"));
+    c.interface("java/io/Serializable");
+    c.interface("com/example/Foo$Listener");
+    let signature = |class: &mut testing::ClassFile, sig: &str| { let info = testing::be16(class.utf8(sig)); class.attribute("Signature", &info) };
+    let list = signature(&mut c, "Ljava/util/List<Ljava/lang/String;>;");
+    c.field(0x0001, "names", "Ljava/util/List;", &[list]);
+    let max = c.integer(42);
+    let max = c.attribute("ConstantValue", &testing::be16(max));
+    c.field(0x0019, "MAX", "I", &[max]);
+    let yes = c.integer(1);
+    let yes = c.attribute("ConstantValue", &testing::be16(yes));
+    c.field(0x0019, "YES", "Z", &[yes]);
+    c.field(0x0002, "hidden", "Ljava/util/Set;", &[]);
+    c.method(0x0001, "<init>", "()V", &[]);
+    let generic = signature(&mut c, "<T::Ljava/lang/Comparable<-TT;>;>(Ljava/util/Map$Entry<TT;Ljava/lang/Integer;>;[Ljava/awt/List;)TT;^Ljava/io/IOException;");
+    c.method(0x0009, "pick", "(Ljava/util/Map$Entry;[Ljava/awt/List;)Ljava/lang/Comparable;", &[generic]);
+    let (pattern, args) = (c.utf8("pattern"), c.utf8("args"));
+    let names = c.attribute("MethodParameters", &[vec![2], testing::be16(pattern), testing::be16(0x0010), testing::be16(args), testing::be16(0)].concat());
+    c.method(0x0081, "format", "(Ljava/lang/String;[Ljava/lang/Object;)Lcom/example/Foo$Listener;", &[names]);
+    c.method(0x0401, "run", "(Lcom/other/Base;)V", &[]);
+    let class = c.read();
+
+    assert_eq!(render_class(&class, &filter::Filter::new()).unwrap(), "\
+package com.example;
+
+import java.io.IOException;
+import java.io.Serializable;
+import java.util.Map;
+
+public class Foo extends com.example.Base implements Serializable, Foo.Listener {
+    public java.util.List<String> names;
+    public static final int MAX = 42;
+    public static final boolean YES = true;
+    public Foo();
+    public static <T extends Comparable<? super T>> T pick(Map.Entry<T, Integer> arg0, java.awt.List[] arg1) throws IOException;
+    public Foo.Listener format(String pattern, Object... args);
+    public abstract void run(com.other.Base arg0);
+}
+");
+
+    let mut i = testing::ClassFile::new("com/example/Api", Some("java/lang/Object"));
+    i.flags = 0x0601; // PUBLIC | INTERFACE | ABSTRACT
+    i.interface("java/lang/Runnable");
+    i.method(0x0401, "size", "()I", &[]);
+    i.method(0x0001, "isEmpty", "()Z", &[]);
+    i.method(0x0009, "of", "()Lcom/example/Api;", &[]);
+    assert_eq!(render_class(&i.read(), &filter::Filter::new()).unwrap(), "\
+package com.example;
+
+public interface Api extends Runnable {
+    public int size();
+    public default boolean isEmpty();
+    public static Api of();
+}
+");
+}
+
+#[cfg(feature = "jar")]
+#[test] fn stubs() {
+    let mut foo = testing::ClassFile::new("com/example/Foo", Some("java/lang/Object"));
+    foo.method(0x0001, "run", "()V", &[]);
+    let mut inner = testing::ClassFile::new("com/example/Foo$Inner", Some("java/lang/Object"));
+    inner.method(0x0001, "run", "()V", &[]);
+    let mut anonymous = testing::ClassFile::new("com/example/Foo$1", Some("java/lang/Object"));
+    anonymous.method(0x0001, "run", "()V", &[]);
+    let mut hidden = testing::ClassFile::new("com/example/Hidden", Some("java/lang/Object"));
+    hidden.flags = 0x0020; // SUPER, not PUBLIC
+    let jar = testing::write_jar("java-stubs", &[
+        ("com/example/Foo.class",       foo.to_bytes()),
+        ("com/example/Foo$Inner.class", inner.to_bytes()),
+        ("com/example/Foo$1.class",     anonymous.to_bytes()),
+        ("com/example/Hidden.class",    hidden.to_bytes()),
+    ]);
+
+    let dir = std::env::temp_dir().join("jreflection-tests").join("java-stubs");
+    let _ = std::fs::remove_dir_all(&dir);
+    let stats = write_stubs(&Source::from_jar(jar).unwrap(), &filter::Filter::new(), &dir).unwrap();
+    assert_eq!(stats, StubStats { written: 1, filtered: 1, nested: 2 });
+    assert_eq!(std::fs::read_to_string(dir.join("com/example/Foo.java")).unwrap(), "\
+package com.example;
+
+public class Foo {
+    public void run();
+}
+");
+    assert!(!dir.join("com/example/Hidden.java").exists());
+}
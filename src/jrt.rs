@@ -0,0 +1,98 @@
+//! `jrt:/` paths, as used by the JDK's [jrt filesystem](https://openjdk.java.net/jeps/220) to name the contents of
+//! a runtime image, such as `jrt:/java.base/java/lang/Object.class`.
+//!
+//! See [Source::read_jrt](../struct.Source.html#method.read_jrt) and [JImage::jrt_uri](../struct.JImage.html#method.jrt_uri).
+
+use std::fmt::{self, Display, Formatter};
+use std::io::{self, Error, ErrorKind};
+
+
+
+/// A resource of a module of a runtime image, named the way the JDK's jrt filesystem names it.
+///
+/// # Examples
+///
+/// ```
+/// # use jreflection::jrt::JrtPath;
+/// let object = JrtPath::parse("jrt:/java.base/java/lang/Object.class").unwrap();
+/// assert_eq!(object.module, "java.base");
+/// assert_eq!(object.path, "java/lang/Object.class");
+/// assert_eq!(object.class(), Some("java/lang/Object"));
+///
+/// // jrt filesystem paths name the same resources
+/// assert_eq!(JrtPath::parse("/modules/java.base/java/lang/Object.class").unwrap(), object);
+/// assert_eq!(JrtPath::parse("/packages/java.lang/java.base/java/lang/Object.class").unwrap(), object);
+///
+/// assert_eq!(JrtPath::for_class("java.sql", "java/sql/Driver").to_string(), "jrt:/java.sql/java/sql/Driver.class");
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct JrtPath {
+    /// The module containing the resource, such as `"java.base"`.
+    pub module: String,
+
+    /// The path of the resource within its module, such as `"java/lang/Object.class"`, or empty for the module itself.
+    pub path:   String,
+}
+
+impl JrtPath {
+    /// Parse a `jrt:/module/path` URI, or a path of the jrt filesystem:  `/modules/module/path` or
+    /// `/packages/package/module/path`.
+    pub fn parse(uri: &str) -> io::Result<Self> {
+        let invalid = |reason: &str| Error::new(ErrorKind::InvalidInput, format!("Invalid jrt path {:?}: {}", uri, reason));
+
+        let (mut parts, packages) = if uri.starts_with("jrt:/") {
+            (uri["jrt:/".len()..].splitn(2, '/'), false)
+        } else if uri.starts_with("/modules/") {
+            (uri["/modules/".len()..].splitn(2, '/'), false)
+        } else if uri.starts_with("/packages/") {
+            let rest = &uri["/packages/".len()..];
+            let slash = rest.find('/').ok_or_else(|| invalid("expected /packages/<package>/<module>"))?;
+            (rest[slash+1..].splitn(2, '/'), true)
+        } else {
+            return Err(invalid("expected jrt:/<module>/<path>, /modules/<module>/<path>, or /packages/<package>/<module>/<path>"));
+        };
+
+        let module = parts.next().unwrap_or("");
+        let path = parts.next().unwrap_or("").trim_end_matches('/');
+        if module.is_empty() { return Err(invalid(if packages { "missing module after package" } else { "missing module" })); }
+        if path.split('/').any(|component| component == "." || component == "..") { return Err(invalid("relative path components aren't allowed")); }
+        Ok(Self { module: module.to_owned(), path: path.to_owned() })
+    }
+
+    /// The path of the `.class` file of the class at JNI path `class` (such as `"java/lang/Object"`) in `module`.
+    pub fn for_class(module: impl Into<String>, class: &str) -> Self {
+        Self { module: module.into(), path: format!("{}.class", class) }
+    }
+
+    /// The JNI path of the class this is the `.class` file of, such as `"java/lang/Object"`, if it is one.
+    pub fn class(&self) -> Option<&str> {
+        if self.path.ends_with(".class") && !self.path.ends_with("module-info.class") {
+            Some(&self.path[..self.path.len() - ".class".len()])
+        } else {
+            None
+        }
+    }
+}
+
+impl Display for JrtPath {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        if self.path.is_empty() { write!(f, "jrt:/{}", self.module) } else { write!(f, "jrt:/{}/{}", self.module, self.path) }
+    }
+}
+
+#[test] fn parse() {
+    let parse = |uri| JrtPath::parse(uri).map(|p| (p.module, p.path)).map_err(|_| ());
+    let ok = |module: &str, path: &str| Ok((module.to_owned(), path.to_owned()));
+    assert_eq!(parse("jrt:/java.base/java/lang/Object.class"),                  ok("java.base", "java/lang/Object.class"));
+    assert_eq!(parse("jrt:/java.desktop/javax/swing/plaf/metal/icons/"),        ok("java.desktop", "javax/swing/plaf/metal/icons"));
+    assert_eq!(parse("jrt:/java.base"),                                         ok("java.base", ""));
+    assert_eq!(parse("/modules/jdk.jfr/jdk/jfr/Event.class"),                   ok("jdk.jfr", "jdk/jfr/Event.class"));
+    assert_eq!(parse("/packages/jdk.jfr/jdk.jfr/jdk/jfr/Event.class"),          ok("jdk.jfr", "jdk/jfr/Event.class"));
+    assert_eq!(parse("jrt:/"),                                                  Err(()));
+    assert_eq!(parse("/packages/java.lang"),                                    Err(()));
+    assert_eq!(parse("jrt:/java.base/../java.sql/java/sql/Driver.class"),       Err(()));
+    assert_eq!(parse("java/lang/Object.class"),                                 Err(()));
+
+    assert_eq!(JrtPath::parse("jrt:/java.base/module-info.class").unwrap().class(), None);
+    assert_eq!(JrtPath::parse("jrt:/java.base/").unwrap().to_string(), "jrt:/java.base");
+}
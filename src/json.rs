@@ -0,0 +1,206 @@
+//! A minimal JSON parser, just enough to read configuration files (e.g. D8's `desugar_jdk_libs.json`) without
+//! pulling in serde.
+
+use std::io;
+use std::str::CharIndices;
+
+
+
+/// How deeply arrays and objects may nest before parsing fails, instead of overflowing the stack.
+const MAX_DEPTH : usize = 128;
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    pub fn parse(text: &str) -> io::Result<Self> {
+        let mut parser = Parser { text, pos: 0, depth: 0 };
+        let value = parser.value()?;
+        parser.whitespace();
+        if parser.pos != text.len() { return io_data_err!("Unexpected trailing characters at offset {} parsing JSON", parser.pos); }
+        Ok(value)
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Object(members) => members.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> { match self { Value::String(s) => Some(s.as_str()), _ => None } }
+    pub fn as_array(&self) -> &[Value] { match self { Value::Array(a) => &a[..], _ => &[] } }
+    pub fn as_object(&self) -> &[(String, Value)] { match self { Value::Object(o) => &o[..], _ => &[] } }
+}
+
+struct Parser<'t> {
+    text:   &'t str,
+    pos:    usize,
+    depth:  usize,
+}
+
+impl<'t> Parser<'t> {
+    fn rest(&self) -> &'t str { &self.text[self.pos..] }
+    fn peek(&self) -> Option<char> { self.rest().chars().next() }
+
+    fn whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn expect(&mut self, token: &str) -> io::Result<()> {
+        self.whitespace();
+        if !self.rest().starts_with(token) { return io_data_err!("Expected {:?} at offset {} parsing JSON", token, self.pos); }
+        self.pos += token.len();
+        Ok(())
+    }
+
+    fn value(&mut self) -> io::Result<Value> {
+        self.whitespace();
+        match self.peek() {
+            Some('n') => { self.expect("null")?; Ok(Value::Null) },
+            Some('t') => { self.expect("true")?; Ok(Value::Bool(true)) },
+            Some('f') => { self.expect("false")?; Ok(Value::Bool(false)) },
+            Some('"') => Ok(Value::String(self.string()?)),
+            Some('[') => self.nested(Self::array),
+            Some('{') => self.nested(Self::object),
+            Some(ch) if ch == '-' || ch.is_ascii_digit() => {
+                let len = self.rest().find(|ch: char| !(ch.is_ascii_digit() || "+-.eE".contains(ch))).unwrap_or(self.rest().len());
+                let number = &self.rest()[..len];
+                let number = match number.parse() { Ok(n) => n, Err(_) => return io_data_err!("Invalid number {:?} at offset {} parsing JSON", number, self.pos) };
+                self.pos += len;
+                Ok(Value::Number(number))
+            },
+            _ => io_data_err!("Expected a value at offset {} parsing JSON", self.pos),
+        }
+    }
+
+    fn nested(&mut self, parse: impl FnOnce(&mut Self) -> io::Result<Value>) -> io::Result<Value> {
+        if self.depth >= MAX_DEPTH { return io_data_err!("Arrays and objects nested more than {} deep at offset {} parsing JSON", MAX_DEPTH, self.pos); }
+        self.depth += 1;
+        let value = parse(self);
+        self.depth -= 1;
+        value
+    }
+
+    fn array(&mut self) -> io::Result<Value> {
+        self.expect("[")?;
+        let mut values = Vec::new();
+        self.whitespace();
+        if self.peek() == Some(']') { self.pos += 1; return Ok(Value::Array(values)); }
+        loop {
+            values.push(self.value()?);
+            self.whitespace();
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                Some(']') => { self.pos += 1; return Ok(Value::Array(values)); },
+                _ => return io_data_err!("Expected ',' or ']' at offset {} parsing JSON", self.pos),
+            }
+        }
+    }
+
+    fn object(&mut self) -> io::Result<Value> {
+        self.expect("{")?;
+        let mut members = Vec::new();
+        self.whitespace();
+        if self.peek() == Some('}') { self.pos += 1; return Ok(Value::Object(members)); }
+        loop {
+            self.whitespace();
+            let key = self.string()?;
+            self.expect(":")?;
+            members.push((key, self.value()?));
+            self.whitespace();
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                Some('}') => { self.pos += 1; return Ok(Value::Object(members)); },
+                _ => return io_data_err!("Expected ',' or '}}' at offset {} parsing JSON", self.pos),
+            }
+        }
+    }
+
+    fn string(&mut self) -> io::Result<String> {
+        self.expect("\"")?;
+        let mut s = String::new();
+        let mut chars = self.rest().char_indices();
+        while let Some((i, ch)) = chars.next() {
+            match ch {
+                '"' => { self.pos += i + 1; return Ok(s); },
+                '\\' => match chars.next().map(|(_, ch)| ch) {
+                    Some('"')   => s.push('"'),
+                    Some('\\')  => s.push('\\'),
+                    Some('/')   => s.push('/'),
+                    Some('b')   => s.push('\u{8}'),
+                    Some('f')   => s.push('\u{C}'),
+                    Some('n')   => s.push('\n'),
+                    Some('r')   => s.push('\r'),
+                    Some('t')   => s.push('\t'),
+                    Some('u')   => {
+                        let code = match hex4(&mut chars)? {
+                            high @ 0xD800 ..= 0xDBFF => match (chars.next(), chars.next()) {
+                                (Some((_, '\\')), Some((_, 'u'))) => match hex4(&mut chars)? {
+                                    low @ 0xDC00 ..= 0xDFFF => 0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00),
+                                    low                     => return io_data_err!("High surrogate \\u{:04X} followed by \\u{:04X} instead of a low surrogate parsing JSON", high, low),
+                                },
+                                _ => return io_data_err!("Unpaired high surrogate \\u{:04X} parsing JSON", high),
+                            },
+                            low @ 0xDC00 ..= 0xDFFF => return io_data_err!("Unpaired low surrogate \\u{:04X} parsing JSON", low),
+                            code                    => code,
+                        };
+                        match std::char::from_u32(code) {
+                            Some(ch)    => s.push(ch),
+                            None        => return io_data_err!("Invalid \\u escape {:X} parsing JSON", code),
+                        }
+                    },
+                    other => return io_data_err!("Invalid escape {:?} parsing JSON", other),
+                },
+                ch => s.push(ch),
+            }
+        }
+        io_data_err!("Unterminated string parsing JSON")
+    }
+}
+
+/// Read the 4 hex digits of a `\uXXXX` escape.
+fn hex4(chars: &mut CharIndices) -> io::Result<u32> {
+    let hex = (0..4).filter_map(|_| chars.next().map(|(_, ch)| ch)).collect::<String>();
+    if hex.len() != 4 || !hex.chars().all(|ch| ch.is_ascii_hexdigit()) { return io_data_err!("Invalid \\u escape {:?} parsing JSON", hex); }
+    Ok(u32::from_str_radix(&hex, 16).unwrap()) // Just validated
+}
+
+#[test] fn parse() {
+    let value = Value::parse(r#" { "a": [1, -2.5e1, true, false, null], "b": { "c\"A": "d" }, "e": [] } "#).unwrap();
+    assert_eq!(value.get("a").unwrap().as_array(), &[Value::Number(1.0), Value::Number(-25.0), Value::Bool(true), Value::Bool(false), Value::Null]);
+    assert_eq!(value.get("b").unwrap().get("c\"A").unwrap().as_str(), Some("d"));
+    assert_eq!(value.get("e").unwrap().as_array(), &[]);
+    assert!(value.get("f").is_none());
+
+    assert!(Value::parse("{").is_err());
+    assert!(Value::parse("[1 2]").is_err());
+    assert!(Value::parse("\"abc").is_err());
+    assert!(Value::parse("1 1").is_err());
+}
+
+#[test] fn unicode_escapes() {
+    assert_eq!(Value::parse(r#""caf\u00e9 \uD83D\uDE00""#).unwrap().as_str(), Some("caf\u{e9} \u{1F600}"));
+    assert_eq!(Value::parse(r#""\ud83d\ude00""#).unwrap().as_str(), Some("\u{1F600}"));
+    assert!(Value::parse(r#""\uD83D""#).is_err());         // Unpaired high surrogate
+    assert!(Value::parse(r#""\uD83Dx""#).is_err());
+    assert!(Value::parse(r#""\uD83D\u0041""#).is_err());   // High surrogate followed by a non-surrogate
+    assert!(Value::parse(r#""\uDE00\uD83D""#).is_err());   // Low surrogate first
+    assert!(Value::parse(r#""\u+041""#).is_err());
+    assert!(Value::parse(r#""\u00"#).is_err());
+}
+
+#[test] fn nesting_depth() {
+    let nested = |depth: usize| format!("{}1{}", "[".repeat(depth), "]".repeat(depth));
+    assert!(Value::parse(&nested(MAX_DEPTH)).is_ok());
+    assert!(Value::parse(&nested(MAX_DEPTH + 1)).is_err());
+    assert!(Value::parse(&"{\"a\":".repeat(100_000)).is_err()); // Not a stack overflow
+}
@@ -1,18 +1,102 @@
-#![forbid(unsafe_code)]
+#![cfg_attr(not(feature = "mmap"), forbid(unsafe_code))]
+#![cfg_attr(feature = "mmap", deny(unsafe_code))] // Only memory mapping needs unsafe; see Source::from_jar_mmap
+
+//! **J**ava **Reflection**:  static reflection APIs for analyzing jars and jimage files.
+//!
+//! # Layout
+//!
+//! jreflection's public API is organized as:
+//!
+//! * The crate root, for the entry points:  [Source], [ClassPath], [Class], [Field], [Method], and [ParseOptions], plus
+//!   the types they take or return directly, such as [IoStrategy] or [Warning].
+//! * One module per classfile structure ([class], [field], [method], [code], [constants], [attributes], [module], ...)
+//!   for the types that only make sense in their context, such as each of their `Flags` and `Descriptor`s.
+//! * One module per analysis or rewrite ([hierarchy], [scan], [report], [transform], [shading], ...)
+//! * The [prelude], which re-exports the entry points and the most common per-structure types, for glob importing.
+//!
+//! Nothing else is re-exported from the crate root, and modules import what they use from each other by name - so
+//! the root only grows when a new entry point does.  Some modules require cargo features (`code`, `annotations`,
+//! `writer`, ...):  see the Readme.
+//!
+//! [Source]:           struct.Source.html
+//! [ClassPath]:        struct.ClassPath.html
+//! [Class]:            struct.Class.html
+//! [Field]:            struct.Field.html
+//! [Method]:           struct.Method.html
+//! [ParseOptions]:     struct.ParseOptions.html
+//! [IoStrategy]:       enum.IoStrategy.html
+//! [Warning]:          struct.Warning.html
+//! [class]:            class/index.html
+//! [field]:            field/index.html
+//! [method]:           method/index.html
+//! [code]:             code/index.html
+//! [constants]:        constants/index.html
+//! [attributes]:       attributes/index.html
+//! [module]:           module/index.html
+//! [hierarchy]:        hierarchy/index.html
+//! [scan]:             scan/index.html
+//! [report]:           report/index.html
+//! [transform]:        transform/index.html
+//! [shading]:          shading/index.html
+//! [prelude]:          prelude/index.html
 
 #[macro_use] mod io;
 
-    mod attributes;
+#[cfg(feature = "writer")] pub mod access;
+pub mod android;
+#[cfg(feature = "annotations")] pub mod annotation;
+pub mod attributes;
+pub mod availability;
+pub mod bootstrap;
+    mod cancel;
+pub mod changelog;
 pub mod class;
-mod constants;
+pub mod classpath;
+#[cfg(feature = "code")] pub mod code;
+pub mod constants;
+pub mod coverage;
+pub mod dedup;
+#[cfg(feature = "writer")] pub mod delegate;
+pub mod desugar;
+pub mod docs;
 pub mod field;
+pub mod filter;
+pub mod fingerprint;
+pub mod hierarchy;
+#[cfg(feature = "jar")] pub mod jar;
+pub mod java;
+pub mod jrt;
+    mod json;
 pub mod method;
-    mod src;
+pub mod module;
+pub mod names;
+pub mod naming;
+    mod options;
+#[cfg(feature = "rayon")] pub mod parallel;
+pub mod prelude;
+#[cfg(feature = "report")] pub mod report;
+#[cfg(feature = "code")] pub mod requirements;
+pub mod scan;
+#[cfg(feature = "writer")] pub mod shading;
+pub mod signature;
+pub mod smap;
+#[cfg(feature = "annotations")] pub mod stability;
+#[cfg(feature = "code")] pub mod stack_map;
+#[cfg(feature = "jar")] pub mod storage;
+#[cfg(feature = "writer")] pub mod transform;
+#[cfg(feature = "annotations")] pub mod type_path;
+pub mod validate;
+mod src;
+#[cfg(test)] mod testing;
     mod version;
 
-    use attributes::Attribute;
+pub use cancel::CancellationToken;
 pub use class::Class;
-use constants::{Constant, Constants};
+pub use classpath::ClassPath;
 pub use field::Field;
-pub use src::Source;
+pub use hierarchy::Hierarchy;
+pub use src::{EntryNameDecoding, ExportLayout, IoStrategy, SkipReport, Source};
+#[cfg(feature = "jar")] pub use src::{Jar, JarEntry};
+#[cfg(feature = "jimage")] pub use src::{JImage, JImageEntry};
 pub use method::Method;
+pub use options::{CustomAttribute, DuplicateAttributes, ParseOptions, Warning, WarningKind};
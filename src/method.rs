@@ -1,13 +1,21 @@
 //! [Java SE 7 &sect; 4.6](https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.6):  Parsing APIs and structures for class methods.
 
-use crate::*;
+use crate::{attributes, class, signature, CustomAttribute, ParseOptions, Warning};
+use crate::attributes::Attribute;
+use crate::constants::Constants;
 use crate::io::be::*;
-pub use field::BasicType;
-pub use field::Descriptor as Type;
+#[cfg(feature = "annotations")] use crate::annotation;
+#[cfg(feature = "annotations")] use crate::stability;
+#[cfg(feature = "code")] use crate::code;
+#[cfg(test)] use crate::{testing, Class};
+pub use crate::field::BasicType;
+pub use crate::field::Descriptor as Type;
 
 use bitflags::bitflags;
 
+use std::fmt::{self, Display, Formatter};
 use std::io::{self, Read};
+use std::sync::Arc;
 
 
 
@@ -49,6 +57,49 @@ impl Flags {
     }
 }
 
+bitflags! {
+    #[derive(Default)]
+    /// [Java SE 8 &sect; 4.7.24](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-4.html#jvms-4.7.24):  MethodParameters::parameters::access_flags values.
+    pub struct ParameterFlags : u16 {
+        /// Declared `final`.
+        const FINAL         = 0x0010;
+        /// Not present in the source code, such as the captured variables of a local class's constructor.
+        const SYNTHETIC     = 0x1000;
+        /// Implicitly declared by the language, such as the outer instance of an inner class's constructor.
+        const MANDATED      = 0x8000;
+    }
+}
+
+/// [Java SE 8 &sect; 4.7.24](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-4.html#jvms-4.7.24):  A
+/// parameter of a method, as recorded by the MethodParameters attribute of classes compiled with `javac -parameters`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Parameter {
+    /// The name of the parameter, such as `"key"`, or `None` if the compiler recorded flags without a name.
+    pub name:   Option<String>,
+
+    /// The flags of the parameter.
+    pub flags:  ParameterFlags,
+
+    _non_exhaustive: (),
+}
+
+impl Parameter {
+    /// Create a parameter named `name` (if recorded) with `flags`.
+    pub fn new(name: Option<String>, flags: ParameterFlags) -> Self { Self { name, flags, _non_exhaustive: () } }
+
+    /// Read a `parameters_count` prefixed list of parameters, as found in a MethodParameters attribute.
+    pub(crate) fn read_list(read: &mut impl Read, constants: &Constants) -> io::Result<Vec<Self>> {
+        let count = read_u1(read)? as usize;
+        let mut parameters = Vec::with_capacity(count);
+        for _ in 0..count {
+            let name = match read_u2(read)? { 0 => None, index => Some(constants.get_utf8(index)?.to_owned()) };
+            let flags = ParameterFlags::from_bits_truncate(read_u2(read)?);
+            parameters.push(Self::new(name, flags));
+        }
+        Ok(parameters)
+    }
+}
+
 
 
 /// [Java SE 7 &sect; 4.6](https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.6):  method_info, minus the trailing attributes
@@ -61,6 +112,22 @@ pub struct Method {
     pub name:       String,
 
     descriptor:     String,
+    signature:      Option<String>,
+    synthetic_attribute:    bool,
+    custom_attributes:      Vec<CustomAttribute>,
+    attribute_names:        Vec<String>,
+    unknown_attributes:     Vec<attributes::RawAttribute>,
+    #[cfg(feature = "annotations")] visible_annotations:    Vec<annotation::Annotation>,
+    #[cfg(feature = "annotations")] invisible_annotations:  Vec<annotation::Annotation>,
+    #[cfg(feature = "annotations")] visible_type_annotations:   Vec<annotation::TypeAnnotation>,
+    #[cfg(feature = "annotations")] invisible_type_annotations: Vec<annotation::TypeAnnotation>,
+    #[cfg(feature = "annotations")] visible_parameter_annotations:      Vec<Vec<annotation::Annotation>>,
+    #[cfg(feature = "annotations")] invisible_parameter_annotations:    Vec<Vec<annotation::Annotation>>,
+    #[cfg(feature = "annotations")] annotation_default:     Option<annotation::ElementValue>,
+    parameters:             Vec<Parameter>,
+    exceptions:             Vec<class::IdBuf>,
+    local_parameter_names:  Vec<Option<String>>,
+    #[cfg(feature = "code")] code:                   Option<code::Code>,
 
     /// Indicates if this method is [`@Deprecated`]
     /// 
@@ -80,11 +147,27 @@ impl Method {
     /// let m = Method::new(Flags::PUBLIC | Flags::STATIC, "method".to_string(), desc).unwrap();
     /// ```
     pub fn new(flags: Flags, name: String, descriptor: String) -> io::Result<Self> {
-        method::Descriptor::new(descriptor.as_str())?;
+        Descriptor::new(descriptor.as_str())?;
         Ok(Self {
             flags,
             name,
             descriptor,
+            signature: None,
+            synthetic_attribute: false,
+            custom_attributes: Vec::new(),
+            attribute_names:    Vec::new(),
+            unknown_attributes: Vec::new(),
+            #[cfg(feature = "annotations")] visible_annotations: Vec::new(),
+            #[cfg(feature = "annotations")] invisible_annotations: Vec::new(),
+            #[cfg(feature = "annotations")] visible_type_annotations: Vec::new(),
+            #[cfg(feature = "annotations")] invisible_type_annotations: Vec::new(),
+            #[cfg(feature = "annotations")] visible_parameter_annotations: Vec::new(),
+            #[cfg(feature = "annotations")] invisible_parameter_annotations: Vec::new(),
+            #[cfg(feature = "annotations")] annotation_default: None,
+            parameters: Vec::new(),
+            exceptions: Vec::new(),
+            local_parameter_names: Vec::new(),
+            #[cfg(feature = "code")] code: None,
             deprecated: false,
             _incomplete: (),
         })
@@ -111,51 +194,292 @@ impl Method {
     /// ```
     pub fn descriptor(&self) -> Descriptor { Descriptor::new(self.descriptor.as_str()).unwrap() } // Already validated in new/read_one
 
+    /// Get the unparsed generic signature of this method, if it has one.
+    pub fn signature_str(&self) -> Option<&str> { self.signature.as_ref().map(|s| s.as_str()) }
+
+    /// Get the parsed generic signature of this method, if it has one.
+    ///
+    /// Signatures aren't validated while reading classes (the JVM itself ignores malformed signatures), so this can fail.
+    pub fn signature(&self) -> io::Result<Option<signature::MethodSignature>> { self.signature_str().map(signature::MethodSignature::parse).transpose() }
+
+    /// Get the component type of the trailing array parameter of a variable arguments (`ACC_VARARGS`) method, or `None`
+    /// if this method doesn't take variable arguments.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use jreflection::{class::Id, method::*};
+    /// let format = Method::new(Flags::PUBLIC | Flags::STATIC | Flags::VARARGS, "format".to_string(), "(Ljava/lang/String;[Ljava/lang/Object;)Ljava/lang/String;".to_string()).unwrap();
+    /// assert_eq!(format.varargs_component(), Some(Type::Single(BasicType::Class(Id("java/lang/Object")))));
+    ///
+    /// let array = Method::new(Flags::PUBLIC | Flags::STATIC, "copy".to_string(), "([Ljava/lang/Object;)V".to_string()).unwrap();
+    /// assert_eq!(array.varargs_component(), None);
+    /// ```
+    pub fn varargs_component(&self) -> Option<Type<'_>> {
+        if !self.is_varargs() { return None; }
+        match self.descriptor().arguments().last()? {
+            Type::Array { levels: 1, inner }    => Some(Type::Single(inner)),
+            Type::Array { levels, inner }       => Some(Type::Array { levels: levels - 1, inner }),
+            Type::Single(_)                     => None, // javac never emits this, but ACC_VARARGS on a non-array is merely odd, not invalid
+        }
+    }
+
+    /// Get the attributes of this method decoded by decoders registered with [ParseOptions::register_attribute](../struct.ParseOptions.html#method.register_attribute).
+    pub fn custom_attributes(&self) -> &[CustomAttribute] { &self.custom_attributes[..] }
+
+    /// The names of the attributes of this method, in classfile order.
+    ///
+    /// This includes attributes jreflection doesn't otherwise understand, such as Kotlin or AspectJ attributes.
+    pub fn attribute_names(&self) -> impl Iterator<Item = &str> { self.attribute_names.iter().map(|n| n.as_str()) }
+
+    /// The names of any attributes of this method that jreflection didn't recognize.
+    pub fn unknown_attribute_names(&self) -> impl Iterator<Item = &str> { self.unknown_attributes.iter().map(|a| a.name.as_str()) }
+
+    /// Any attributes of this method that jreflection didn't recognize, undecoded.
+    pub fn unknown_attributes(&self) -> &[attributes::RawAttribute] { &self.unknown_attributes[..] }
+
+    /// The type descriptors of the annotations of this method, visible and invisible at runtime alike, such as `"Lcom/google/common/annotations/Beta;"`.
+    #[cfg(feature = "annotations")]
+    pub fn annotation_types(&self) -> impl Iterator<Item = &str> { self.annotations().map(|a| a.descriptor.as_str()) }
+
+    /// The annotations of this method, visible then invisible at runtime, with their elements.  See [Annotation](../annotation/struct.Annotation.html).
+    #[cfg(feature = "annotations")]
+    pub fn annotations(&self) -> impl Iterator<Item = &annotation::Annotation> { self.visible_annotations.iter().chain(self.invisible_annotations.iter()) }
+
+    /// The annotations of this method that are visible at runtime, with their elements.  See [Annotation](../annotation/struct.Annotation.html).
+    #[cfg(feature = "annotations")]
+    pub fn visible_annotations(&self) -> &[annotation::Annotation] { &self.visible_annotations[..] }
+
+    /// The annotations of this method that are only retained in the classfile, such as `@Nullable` or `@Beta`.
+    #[cfg(feature = "annotations")]
+    pub fn invisible_annotations(&self) -> &[annotation::Annotation] { &self.invisible_annotations[..] }
+
+    /// The annotations on type uses within this method's declaration (JSR 308), visible then invisible at runtime.  See [TypeAnnotation](../annotation/struct.TypeAnnotation.html).
+    #[cfg(feature = "annotations")]
+    pub fn type_annotations(&self) -> impl Iterator<Item = &annotation::TypeAnnotation> { self.visible_type_annotations.iter().chain(self.invisible_type_annotations.iter()) }
+
+    /// The annotations on type uses within this method's declaration that are visible at runtime.
+    #[cfg(feature = "annotations")]
+    pub fn visible_type_annotations(&self) -> &[annotation::TypeAnnotation] { &self.visible_type_annotations[..] }
+
+    /// The annotations on type uses within this method's declaration that are only retained in the classfile.
+    #[cfg(feature = "annotations")]
+    pub fn invisible_type_annotations(&self) -> &[annotation::TypeAnnotation] { &self.invisible_type_annotations[..] }
+
+    /// The annotations of parameter `index` of this method, visible then invisible at runtime, such as `@NonNull`.
+    ///
+    /// Note that compilers may omit synthetic parameters - such as the outer instance passed to inner class
+    /// constructors - from parameter annotations, so `index` might not line up with the descriptor's parameters for
+    /// such methods.
+    #[cfg(feature = "annotations")]
+    pub fn parameter_annotations(&self, index: usize) -> impl Iterator<Item = &annotation::Annotation> {
+        self.visible_parameter_annotations(index).iter().chain(self.invisible_parameter_annotations(index).iter())
+    }
+
+    /// The annotations of parameter `index` of this method that are visible at runtime.
+    #[cfg(feature = "annotations")]
+    pub fn visible_parameter_annotations(&self, index: usize) -> &[annotation::Annotation] {
+        self.visible_parameter_annotations.get(index).map(|a| &a[..]).unwrap_or(&[])
+    }
+
+    /// The annotations of parameter `index` of this method that are only retained in the classfile.
+    #[cfg(feature = "annotations")]
+    pub fn invisible_parameter_annotations(&self, index: usize) -> &[annotation::Annotation] {
+        self.invisible_parameter_annotations.get(index).map(|a| &a[..]).unwrap_or(&[])
+    }
+
+    /// The default value of this element of an annotation interface, if it has one, such as `30` for
+    /// `long timeout() default 30;`.  See [ElementValue](../annotation/enum.ElementValue.html).
+    #[cfg(feature = "annotations")]
+    pub fn annotation_default(&self) -> Option<&annotation::ElementValue> { self.annotation_default.as_ref() }
+
+    /// The parameters recorded by this method's MethodParameters attribute, or an empty slice if it has none (the
+    /// class wasn't compiled with `javac -parameters`.)  See [Parameter](struct.Parameter.html).
+    ///
+    /// Unlike [parameter_annotations](#method.parameter_annotations), these include synthetic and mandated parameters,
+    /// and so line up with the parameters of the [descriptor](#method.descriptor).
+    pub fn parameters(&self) -> &[Parameter] { &self.parameters[..] }
+
+    /// The real name of parameter `index` of the method's descriptor, such as `"key"`, if it was recorded by a
+    /// MethodParameters attribute.  See also [parameter_names](#method.parameter_names).
+    pub fn parameter_name(&self, index: usize) -> Option<&str> { self.parameters.get(index).and_then(|p| p.name.as_ref()).map(|n| n.as_str()) }
+
+    /// The best available name of each parameter of the method's descriptor:  from the MethodParameters attribute if
+    /// the method has one, then from the `LocalVariableTable` if recovered via [ParseOptions::local_variable_names](../struct.ParseOptions.html#method.local_variable_names),
+    /// and otherwise synthesized as `arg0`, `arg1`, ...
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use jreflection::*;
+    /// # #[cfg(all(feature = "jar", feature = "code"))] {
+    /// # let src = Source::from_jar("app.jar").unwrap();
+    /// let mut options = ParseOptions::new();
+    /// options.local_variable_names(true);
+    /// let class = src.read_class_with("com/example/Main", &options).unwrap();
+    /// for method in class.methods.iter() {
+    ///     println!("{}({})", method.name, method.parameter_names().join(", "));
+    /// }
+    /// # }
+    /// ```
+    pub fn parameter_names(&self) -> Vec<String> {
+        let count = self.descriptor().arguments().count();
+        let recorded = if self.parameters.len() == count { &self.parameters[..] } else { &[] };
+        (0..count).map(|i| {
+            recorded.get(i).and_then(|p| p.name.clone())
+                .or_else(|| self.local_parameter_names.get(i).and_then(|n| n.clone()))
+                .unwrap_or_else(|| format!("arg{}", i))
+        }).collect()
+    }
+
+    /// The checked exceptions this method declares it `throws`, as recorded by its Exceptions attribute, such as
+    /// `java/io/IOException`.  Unchecked exceptions may also be listed, if the source listed them.
+    pub fn exceptions(&self) -> &[class::IdBuf] { &self.exceptions[..] }
+
+    /// How stable this method is declared to be by its own annotations.  See [Stability](../stability/enum.Stability.html).
+    #[cfg(feature = "annotations")]
+    pub fn stability(&self) -> stability::Stability { stability::Stability::of_annotations(self.annotation_types()) }
+
+    /// Get the bytecode of this method, if it has any and it was retained via [ParseOptions::retain_code](../struct.ParseOptions.html#method.retain_code).
+    #[cfg(feature = "code")]
+    pub fn code(&self) -> Option<&code::Code> { self.code.as_ref() }
+
     /// The method is declared `public`, and may be called from outside its package.
+    ///
+    /// ```
+    /// # use jreflection::method::*;
+    /// let method = Method::new(Flags::PUBLIC, "method".to_string(), "()V".to_string()).unwrap();
+    /// assert!(method.is_public());
+    /// ```
     pub fn is_public        (&self) -> bool { self.flags.contains(Flags::PUBLIC         ) }
 
     /// The method is declared `private`, and may only be called within the defining class.
+    ///
+    /// ```
+    /// # use jreflection::method::*;
+    /// let method = Method::new(Flags::PRIVATE, "method".to_string(), "()V".to_string()).unwrap();
+    /// assert!(method.is_private());
+    /// ```
     pub fn is_private       (&self) -> bool { self.flags.contains(Flags::PRIVATE        ) }
 
     /// The method is declared `protected`, and may be called from subclasses.
+    ///
+    /// ```
+    /// # use jreflection::method::*;
+    /// let method = Method::new(Flags::PROTECTED, "method".to_string(), "()V".to_string()).unwrap();
+    /// assert!(method.is_protected());
+    /// ```
     pub fn is_protected     (&self) -> bool { self.flags.contains(Flags::PROTECTED      ) }
 
     /// The method is declared `static`, and may be called without a class instance.
+    ///
+    /// ```
+    /// # use jreflection::method::*;
+    /// let method = Method::new(Flags::PUBLIC | Flags::STATIC, "method".to_string(), "()V".to_string()).unwrap();
+    /// assert!(method.is_static());
+    /// ```
     pub fn is_static        (&self) -> bool { self.flags.contains(Flags::STATIC         ) }
 
     /// The method is declared `final`, and may not be overridden.
+    ///
+    /// ```
+    /// # use jreflection::method::*;
+    /// let method = Method::new(Flags::PUBLIC | Flags::FINAL, "method".to_string(), "()V".to_string()).unwrap();
+    /// assert!(method.is_final());
+    /// ```
     pub fn is_final         (&self) -> bool { self.flags.contains(Flags::FINAL          ) }
 
     /// The method is declared `syncronized`, indicating it is wrapped by a monitor.
+    ///
+    /// ```
+    /// # use jreflection::method::*;
+    /// let method = Method::new(Flags::PUBLIC | Flags::SYNCRONIZED, "method".to_string(), "()V".to_string()).unwrap();
+    /// assert!(method.is_syncronized());
+    /// ```
     pub fn is_syncronized   (&self) -> bool { self.flags.contains(Flags::SYNCRONIZED    ) }
 
     /// The method is a bridge method, generated by the compiler.
+    ///
+    /// ```
+    /// # use jreflection::method::*;
+    /// let method = Method::new(Flags::PUBLIC | Flags::BRIDGE | Flags::SYNTHETIC, "method".to_string(), "()V".to_string()).unwrap();
+    /// assert!(method.is_bridge());
+    /// ```
     pub fn is_bridge        (&self) -> bool { self.flags.contains(Flags::BRIDGE         ) }
 
     /// The method takes a variable number of arguments.
+    ///
+    /// ```
+    /// # use jreflection::method::*;
+    /// let method = Method::new(Flags::PUBLIC | Flags::VARARGS, "method".to_string(), "([Ljava/lang/Object;)V".to_string()).unwrap();
+    /// assert!(method.is_varargs());
+    /// ```
     pub fn is_varargs       (&self) -> bool { self.flags.contains(Flags::VARARGS        ) }
 
     /// The method is declared `native`, and is implemented in a language other than Java.
+    ///
+    /// ```
+    /// # use jreflection::method::*;
+    /// let method = Method::new(Flags::PUBLIC | Flags::NATIVE, "method".to_string(), "()V".to_string()).unwrap();
+    /// assert!(method.is_native());
+    /// ```
     pub fn is_native        (&self) -> bool { self.flags.contains(Flags::NATIVE         ) }
 
     /// The method is declared `abstract` and has no implementation.
+    ///
+    /// ```
+    /// # use jreflection::method::*;
+    /// let method = Method::new(Flags::PUBLIC | Flags::ABSTRACT, "method".to_string(), "()V".to_string()).unwrap();
+    /// assert!(method.is_abstract());
+    /// ```
     pub fn is_abstract      (&self) -> bool { self.flags.contains(Flags::ABSTRACT       ) }
 
     /// The method is declared `strictfp`, and uses strict floating-point mode.
+    ///
+    /// ```
+    /// # use jreflection::method::*;
+    /// let method = Method::new(Flags::PUBLIC | Flags::STRICT, "method".to_string(), "()V".to_string()).unwrap();
+    /// assert!(method.is_strict());
+    /// ```
     pub fn is_strict        (&self) -> bool { self.flags.contains(Flags::STRICT         ) }
 
     /// The method is synthetic, and is not present in the source code.
-    pub fn is_synthetic     (&self) -> bool { self.flags.contains(Flags::SYNTHETIC      ) }
+    /// This checks both the `ACC_SYNTHETIC` flag and the `Synthetic` attribute used by older compilers.
+    ///
+    /// ```
+    /// # use jreflection::method::*;
+    /// let method = Method::new(Flags::PRIVATE | Flags::STATIC | Flags::SYNTHETIC, "method".to_string(), "()V".to_string()).unwrap();
+    /// assert!(method.is_synthetic());
+    /// ```
+    pub fn is_synthetic     (&self) -> bool { self.flags.contains(Flags::SYNTHETIC      ) || self.synthetic_attribute }
 
     /// The method is actually a constructor.
+    ///
+    /// ```
+    /// # use jreflection::method::*;
+    /// let method = Method::new(Flags::PUBLIC, "<init>".to_string(), "()V".to_string()).unwrap();
+    /// assert!(method.is_constructor());
+    /// ```
     pub fn is_constructor   (&self) -> bool { self.name == "<init>" }
 
     /// The method is a static class constructor, automatically invoked by the runtime.
+    ///
+    /// ```
+    /// # use jreflection::method::*;
+    /// let method = Method::new(Flags::STATIC, "<clinit>".to_string(), "()V".to_string()).unwrap();
+    /// assert!(method.is_static_init());
+    /// ```
     pub fn is_static_init   (&self) -> bool { self.name == "<clinit>" }
 
     /// Try to determine the access keyword for this method.
     /// One of `Some("public")`, `Some("protected")`, `Some("private")`, or `None`.
     /// It's not super clear which should be used if multiple flags are used.
+    ///
+    /// ```
+    /// # use jreflection::method::*;
+    /// let method = Method::new(Flags::PRIVATE, "method".to_string(), "()V".to_string()).unwrap();
+    /// assert_eq!(method.access(), Some("private"));
+    /// ```
     pub fn access(&self) -> Option<&'static str> {
         if      self.is_private()   { Some("private") }
         else if self.is_protected() { Some("protected") }
@@ -163,41 +487,128 @@ impl Method {
         else                        { None }
     }
 
-    pub(crate) fn read_one(read: &mut impl Read, constants: &Constants) -> io::Result<Self> {
+    pub(crate) fn read_one(read: &mut impl Read, constants: &Arc<Constants>, options: &ParseOptions, warnings: &mut Vec<Warning>) -> io::Result<Self> {
         let flags               = Flags::read(read)?;
         let name                = constants.get_utf8(read_u2(read)?)?.to_owned();
         let descriptor          = constants.get_utf8(read_u2(read)?)?.to_owned();
         let attributes_count    = read_u2(read)? as usize;
 
-        method::Descriptor::new(descriptor.as_str())?;
+        Descriptor::new(descriptor.as_str())?;
 
         let mut deprecated      = false;
+        let mut signature       = None;
+        let mut synthetic_attribute = false;
+        let mut custom_attributes = Vec::new();
+        let mut attribute_names = Vec::new();
+        let mut unknown_attributes = Vec::new();
+        #[cfg(feature = "annotations")] let mut visible_annotations = Vec::new();
+        #[cfg(feature = "annotations")] let mut invisible_annotations = Vec::new();
+        #[cfg(feature = "annotations")] let mut visible_type_annotations = Vec::new();
+        #[cfg(feature = "annotations")] let mut invisible_type_annotations = Vec::new();
+        #[cfg(feature = "annotations")] let mut visible_parameter_annotations = Vec::new();
+        #[cfg(feature = "annotations")] let mut invisible_parameter_annotations = Vec::new();
+        #[cfg(feature = "annotations")] let mut annotation_default = None;
+        let mut parameters      = Vec::new();
+        let mut exceptions      = Vec::new();
+        #[cfg(feature = "code")] let mut code = None;
+        let first_warning = warnings.len();
+        let mut seen = Vec::new();
         for _ in 0..attributes_count {
-            match Attribute::read(read, constants)? {
+            let (name, attribute) = Attribute::read_named(read, constants, options, &mut seen, warnings)?;
+            attribute_names.push(name.to_owned());
+            let attribute = match attribute { Some(a) => a, None => continue }; // An ignored duplicate
+            match attribute {
+                #[cfg(feature = "code")] Attribute::Code(c) => { code = Some(c); },
                 Attribute::Deprecated { .. } => { deprecated = true; },
+                Attribute::Signature(value) => { signature = Some(value); },
+                Attribute::Synthetic { .. } => { synthetic_attribute = true; },
+                Attribute::Custom(custom) => { custom_attributes.push(custom); },
+                Attribute::Unknown(info) => { unknown_attributes.push(attributes::RawAttribute { name: name.to_owned(), info }); },
+                #[cfg(feature = "annotations")] Attribute::RuntimeVisibleAnnotations(a)   => { visible_annotations.extend(a); },
+                #[cfg(feature = "annotations")] Attribute::RuntimeInvisibleAnnotations(a) => { invisible_annotations.extend(a); },
+                #[cfg(feature = "annotations")] Attribute::RuntimeVisibleTypeAnnotations(a)   => { visible_type_annotations.extend(a); },
+                #[cfg(feature = "annotations")] Attribute::RuntimeInvisibleTypeAnnotations(a) => { invisible_type_annotations.extend(a); },
+                #[cfg(feature = "annotations")] Attribute::RuntimeVisibleParameterAnnotations(p)    => { visible_parameter_annotations = p; },
+                #[cfg(feature = "annotations")] Attribute::RuntimeInvisibleParameterAnnotations(p)  => { invisible_parameter_annotations = p; },
+                #[cfg(feature = "annotations")] Attribute::AnnotationDefault(value)                 => { annotation_default = Some(value); },
+                Attribute::MethodParameters(p)                      => { parameters = p; },
+                Attribute::Exceptions(e)                            => { exceptions = e; },
                 _ => {},
             }
         }
 
+        for warning in warnings[first_warning..].iter_mut() { warning.member = Some(format!("{}{}", name, descriptor)); }
+
+        #[cfg(feature = "code")] let mut local_parameter_names = Vec::new();
+        #[cfg(not(feature = "code"))] let local_parameter_names = Vec::new();
+        #[cfg(feature = "code")]
+        if options.local_variable_names_enabled() {
+            if let Some(code) = code.as_ref() { local_parameter_names = code.parameter_names(flags.contains(Flags::STATIC), &Descriptor::new(descriptor.as_str())?)?; }
+            if !options.retain_code_enabled() { code = None; }
+        }
+
         Ok(Self{
             flags,
             name,
             descriptor,
+            signature,
+            synthetic_attribute,
+            custom_attributes,
+            attribute_names,
+            unknown_attributes,
+            #[cfg(feature = "annotations")] visible_annotations,
+            #[cfg(feature = "annotations")] invisible_annotations,
+            #[cfg(feature = "annotations")] visible_type_annotations,
+            #[cfg(feature = "annotations")] invisible_type_annotations,
+            #[cfg(feature = "annotations")] visible_parameter_annotations,
+            #[cfg(feature = "annotations")] invisible_parameter_annotations,
+            #[cfg(feature = "annotations")] annotation_default,
+            parameters,
+            exceptions,
+            local_parameter_names,
+            #[cfg(feature = "code")] code,
             deprecated,
             _incomplete:    (),
         })
     }
 
-    pub(crate) fn read_list(read: &mut impl Read, constants: &Constants) -> io::Result<Vec<Self>> {
+    pub(crate) fn read_list(read: &mut impl Read, constants: &Arc<Constants>, options: &ParseOptions, warnings: &mut Vec<Warning>) -> io::Result<Vec<Self>> {
         let n = read_u2(read)? as usize;
         let mut methods = Vec::with_capacity(n);
         for _ in 0..n {
-            methods.push(Self::read_one(read, constants)?);
+            methods.push(Self::read_one(read, constants, options, warnings)?);
         }
         Ok(methods)
     }
 }
 
+/// A compact, Java-like declaration, such as `public static void main(java.lang.String[])`.
+///
+/// Constructors and static initializers keep their JVM names, as the class name isn't known here.
+///
+/// ```
+/// # use jreflection::method::*;
+/// let method = Method::new(Flags::PUBLIC | Flags::SYNCRONIZED, "get".to_string(), "(I)Ljava/lang/Object;".to_string()).unwrap();
+/// assert_eq!(method.to_string(), "public synchronized java.lang.Object get(int)");
+/// let method = Method::new(Flags::PUBLIC, "<init>".to_string(), "()V".to_string()).unwrap();
+/// assert_eq!(method.to_string(), "public void <init>()");
+/// ```
+impl Display for Method {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        if let Some(access) = self.access() { write!(fmt, "{} ", access)?; }
+        if self.is_static()         { write!(fmt, "static ")?; }
+        if self.is_abstract()       { write!(fmt, "abstract ")?; }
+        if self.is_final()          { write!(fmt, "final ")?; }
+        if self.is_syncronized()    { write!(fmt, "synchronized ")?; }
+        if self.is_native()         { write!(fmt, "native ")?; }
+        if self.is_strict()         { write!(fmt, "strictfp ")?; }
+        let descriptor = self.descriptor();
+        write!(fmt, "{} {}(", descriptor.return_type(), self.name)?;
+        write_arguments(fmt, &descriptor)?;
+        write!(fmt, ")")
+    }
+}
+
 
 
 /// [Java SE 7 &sect; 4.3.3](https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.3.3): A method descriptor ([type erased] argument/return data)
@@ -227,6 +638,29 @@ pub struct Descriptor<'a> {
     end_paren:  usize,
 }
 
+/// The Java types of the method, such as `(int, double[], java.lang.Thread) -> java.lang.Object`.
+///
+/// ```
+/// # use jreflection::method::Descriptor;
+/// let desc = Descriptor::new("(I[DLjava/lang/Thread;)Ljava/lang/Object;").unwrap();
+/// assert_eq!(desc.to_string(), "(int, double[], java.lang.Thread) -> java.lang.Object");
+/// ```
+impl Display for Descriptor<'_> {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        write!(fmt, "(")?;
+        write_arguments(fmt, self)?;
+        write!(fmt, ") -> {}", self.return_type())
+    }
+}
+
+fn write_arguments(fmt: &mut Formatter, descriptor: &Descriptor) -> fmt::Result {
+    for (i, arg) in descriptor.arguments().enumerate() {
+        if i != 0 { write!(fmt, ", ")?; }
+        write!(fmt, "{}", arg)?;
+    }
+    Ok(())
+}
+
 impl<'a> Descriptor<'a> {
     /// Parses/validates the method descriptor
     /// 
@@ -284,7 +718,7 @@ impl<'a> Descriptor<'a> {
     /// assert_eq!(args[1], Type::Array { levels: 1, inner: BasicType::Double });
     /// assert_eq!(args[2], Type::Single(BasicType::Class(Id("java/lang/Thread"))));
     /// ```
-    pub fn arguments(&self) -> ArgumentsIter { ArgumentsIter(&self.string[1..self.end_paren]) }
+    pub fn arguments(&self) -> ArgumentsIter<'a> { ArgumentsIter(&self.string[1..self.end_paren]) }
 }
 
 /// Implementation of [Descriptor]::[arguments]
@@ -351,3 +785,89 @@ impl<'a> Iterator for ArgumentsIter<'a> {
     assert_eq!(d.next(), None);
     assert_eq!(d.next(), None);
 }
+
+#[test] fn varargs_component() {
+    let method = |flags, desc: &str| Method::new(flags, "m".to_string(), desc.to_string()).unwrap();
+    assert_eq!(method(Flags::VARARGS, "([I)V").varargs_component(),                     Some(Type::Single(BasicType::Int)));
+    assert_eq!(method(Flags::VARARGS, "(I[[Ljava/lang/String;)V").varargs_component(),  Some(Type::Array { levels: 1, inner: BasicType::Class(class::Id("java/lang/String")) }));
+    assert_eq!(method(Flags::VARARGS, "(I)V").varargs_component(),                      None);
+    assert_eq!(method(Flags::VARARGS, "()V").varargs_component(),                       None);
+    assert_eq!(method(Flags::PUBLIC,  "([I)V").varargs_component(),                     None);
+}
+
+#[test] fn parameters() {
+    use crate::testing::be16;
+
+    let mut c = testing::ClassFile::new("com/example/Outer$Inner", Some("java/lang/Object"));
+    let (this, key) = (c.utf8("this$0"), c.utf8("key"));
+    let info = [vec![3], be16(this), be16(0x8010), be16(key), be16(0x0010), be16(0), be16(0)].concat();
+    let names = c.attribute("MethodParameters", &info);
+    c.method(0x0001, "<init>", "(Lcom/example/Outer;Ljava/lang/String;I)V", &[names]);
+    c.method(0x0001, "run", "()V", &[]);
+    let class = c.read();
+
+    let init = &class.methods[0];
+    assert_eq!(init.parameters(), &[
+        Parameter::new(Some("this$0".to_owned()), ParameterFlags::FINAL | ParameterFlags::MANDATED),
+        Parameter::new(Some("key".to_owned()), ParameterFlags::FINAL),
+        Parameter::new(None, ParameterFlags::empty()),
+    ]);
+    assert_eq!((0..4).map(|i| init.parameter_name(i)).collect::<Vec<_>>(), &[Some("this$0"), Some("key"), None, None]);
+    assert!(class.methods[1].parameters().is_empty());
+
+    assert_eq!(init.parameter_names(), &["this$0", "key", "arg2"]);
+
+    let mut c = testing::ClassFile::new("com/example/Bad", Some("java/lang/Object"));
+    let names = c.attribute("MethodParameters", &[vec![1], be16(0)].concat());
+    c.method(0x0001, "run", "(I)V", &[names]);
+    assert!(Class::read(&mut &c.to_bytes()[..]).is_err());
+}
+
+#[cfg(feature = "code")]
+#[test] fn local_variable_parameter_names() {
+    use crate::testing::be16;
+
+    let mut c = testing::ClassFile::new("com/example/Debug", Some("java/lang/Object"));
+    let names = ["this", "millis", "unit", "extra"].iter().map(|n| c.utf8(n)).collect::<Vec<_>>();
+    let (obj, long, string) = (c.utf8("Ljava/lang/Object;"), c.utf8("J"), c.utf8("Ljava/lang/String;"));
+    let lvt = |c: &mut testing::ClassFile, entries: &[(u16, u16, u16, u16)]| {
+        let mut info = be16(entries.len() as u16);
+        for &(start_pc, name, descriptor, index) in entries { info.extend([be16(start_pc), be16(1), be16(name), be16(descriptor), be16(index)].concat()); }
+        let lvt = c.attribute("LocalVariableTable", &info);
+        c.attribute("Code", &[be16(4), be16(4), testing::be32(1), vec![0xB1], be16(0), be16(1), lvt].concat())
+    };
+    let code = lvt(&mut c, &[(0, names[0], obj, 0), (0, names[1], long, 1), (0, names[2], string, 3), (1, names[3], string, 4)]);
+    c.method(0x0001, "sleep", "(JLjava/lang/String;I)V", &[code]);
+    let code = lvt(&mut c, &[(0, names[2], string, 0)]);
+    c.method(0x0009, "parse", "(Ljava/lang/String;)V", &[code]);
+
+    let bytes = c.to_bytes();
+    let mut options = ParseOptions::new();
+    assert_eq!(Class::read_with(&mut &bytes[..], &options).unwrap().methods[0].parameter_names(), &["arg0", "arg1", "arg2"]);
+    options.local_variable_names(true);
+    let class = Class::read_with(&mut &bytes[..], &options).unwrap();
+    assert_eq!(class.methods[0].parameter_names(), &["millis", "unit", "arg2"]);
+    assert_eq!(class.methods[1].parameter_names(), &["unit"]);
+    assert!(class.methods[0].code().is_none());
+    options.retain_code(true);
+    let class = Class::read_with(&mut &bytes[..], &options).unwrap();
+    assert_eq!(class.methods[0].code().unwrap().local_variables().unwrap().len(), 4);
+}
+
+#[test] fn exceptions() {
+    use crate::testing::be16;
+
+    let mut c = testing::ClassFile::new("com/example/Io", Some("java/lang/Object"));
+    let (io, interrupted) = (c.class("java/io/IOException"), c.class("java/lang/InterruptedException"));
+    let throws = c.attribute("Exceptions", &[be16(2), be16(io), be16(interrupted)].concat());
+    c.method(0x0001, "read", "()I", &[throws]);
+    c.method(0x0001, "close", "()V", &[]);
+    let class = c.read();
+    assert_eq!(class.methods[0].exceptions().iter().map(|e| e.as_str()).collect::<Vec<_>>(), &["java/io/IOException", "java/lang/InterruptedException"]);
+    assert!(class.methods[1].exceptions().is_empty());
+
+    let mut c = testing::ClassFile::new("com/example/Bad", Some("java/lang/Object"));
+    let throws = c.attribute("Exceptions", &[be16(2), be16(0)].concat());
+    c.method(0x0001, "read", "()I", &[throws]);
+    assert!(Class::read(&mut &c.to_bytes()[..]).is_err());
+}
@@ -0,0 +1,397 @@
+//! [Java SE 9 &sect; 4.7.25](https://docs.oracle.com/javase/specs/jvms/se9/html/jvms-4.html#jvms-4.7.25):  Module
+//! declarations (`module-info.class`), and which packages modules export to whom.
+
+use crate::Source;
+use crate::constants::Constants;
+use crate::io::be::*;
+#[cfg(any(feature = "jar", feature = "jimage"))] use crate::Class;
+#[cfg(all(test, feature = "jar"))] use crate::testing;
+
+use std::collections::*;
+use std::io::{self, Read};
+
+
+
+/// A module declaration, as read from the `Module` (and `ModulePackages` / `ModuleMainClass`) attributes of a
+/// `module-info.class`.
+///
+/// Packages and classes use JNI paths (`java/lang`, `java/lang/Object`.)
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Module {
+    /// The name of the module, e.g. `"java.base"`.
+    pub name:       String,
+
+    /// Declared `open`:  every package is opened for deep reflection.
+    pub open:       bool,
+
+    /// The version of the module, if recorded.
+    pub version:    Option<String>,
+
+    /// The modules this module depends on.
+    pub requires:   Vec<Requires>,
+
+    /// The packages this module exports, to everyone or to specific modules.
+    pub exports:    Vec<Exports>,
+
+    /// The packages this module opens for deep reflection, to everyone or to specific modules.
+    pub opens:      Vec<Exports>,
+
+    /// The services (classes) this module uses.
+    pub uses:       Vec<String>,
+
+    /// The services this module provides implementations of.
+    pub provides:   Vec<Provides>,
+
+    /// Every package of the module, if listed by a `ModulePackages` attribute (typically only for JDK modules.)
+    pub packages:   Vec<String>,
+
+    /// The main class of the module (such as `"com/example/Main"`), if recorded by a `ModuleMainClass` attribute (e.g.
+    /// by `jar --main-class`.)
+    pub main_class: Option<String>,
+
+    _non_exhaustive: (),
+}
+
+/// A `requires` directive of a [Module](struct.Module.html).
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Requires {
+    /// The name of the required module.
+    pub module:         String,
+
+    /// Declared `transitive`:  modules requiring this module implicitly require `module` too.
+    pub transitive:     bool,
+
+    /// Declared `static`:  only required at compile time.
+    pub static_phase:   bool,
+
+    /// `ACC_MANDATED`:  implicitly declared, like every module's dependency on `java.base`.
+    pub mandated:       bool,
+
+    /// The version of `module` compiled against, if recorded.
+    pub version:        Option<String>,
+
+    _non_exhaustive: (),
+}
+
+/// An `exports` or `opens` directive of a [Module](struct.Module.html).
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Exports {
+    /// The exported (or opened) package.
+    pub package:    String,
+
+    /// The modules the package is exported (or opened) to, or empty if exported to everyone.
+    pub to:         Vec<String>,
+
+    _non_exhaustive: (),
+}
+
+/// A `provides` directive of a [Module](struct.Module.html).
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Provides {
+    /// The service interface or class.
+    pub service:    String,
+
+    /// The implementations provided.
+    pub with:       Vec<String>,
+
+    _non_exhaustive: (),
+}
+
+impl Exports {
+    /// Create a directive exporting (or opening) `package` to the modules `to`, or to everyone if `to` is empty.
+    pub fn new(package: String, to: Vec<String>) -> Self { Self { package, to, _non_exhaustive: () } }
+}
+
+impl Provides {
+    /// Create a directive providing the implementations `with` of `service`.
+    pub fn new(service: String, with: Vec<String>) -> Self { Self { service, with, _non_exhaustive: () } }
+}
+
+impl Module {
+    pub(crate) fn read(read: &mut impl Read, constants: &Constants) -> io::Result<Self> {
+        let name    = constants.get_module(read_u2(read)?)?.to_owned();
+        let flags   = read_u2(read)?;
+        let version = constants.get_optional_utf8(read_u2(read)?)?.map(|v| v.to_owned());
+
+        let mut requires = Vec::new();
+        for _ in 0..read_u2(read)? {
+            let module  = constants.get_module(read_u2(read)?)?.to_owned();
+            let flags   = read_u2(read)?;
+            let version = constants.get_optional_utf8(read_u2(read)?)?.map(|v| v.to_owned());
+            requires.push(Requires { module, transitive: flags & 0x0020 != 0, static_phase: flags & 0x0040 != 0, mandated: flags & 0x8000 != 0, version, _non_exhaustive: () });
+        }
+
+        let read_exports = |read: &mut dyn Read| -> io::Result<Vec<Exports>> {
+            let mut read = read;
+            let mut exports = Vec::new();
+            for _ in 0..read_u2(&mut read)? {
+                let package = constants.get_package(read_u2(&mut read)?)?.to_owned();
+                let _flags  = read_u2(&mut read)?;
+                let mut to  = Vec::new();
+                for _ in 0..read_u2(&mut read)? { to.push(constants.get_module(read_u2(&mut read)?)?.to_owned()); }
+                exports.push(Exports::new(package, to));
+            }
+            Ok(exports)
+        };
+        let exports = read_exports(read)?;
+        let opens   = read_exports(read)?;
+
+        let mut uses = Vec::new();
+        for _ in 0..read_u2(read)? { uses.push(constants.get_class(read_u2(read)?)?.to_owned()); }
+
+        let mut provides = Vec::new();
+        for _ in 0..read_u2(read)? {
+            let service  = constants.get_class(read_u2(read)?)?.to_owned();
+            let mut with = Vec::new();
+            for _ in 0..read_u2(read)? { with.push(constants.get_class(read_u2(read)?)?.to_owned()); }
+            provides.push(Provides::new(service, with));
+        }
+
+        Ok(Self { name, open: flags & 0x0020 != 0, version, requires, exports, opens, uses, provides, packages: Vec::new(), main_class: None, _non_exhaustive: () })
+    }
+
+    pub(crate) fn read_packages(read: &mut impl Read, constants: &Constants) -> io::Result<Vec<String>> {
+        let mut packages = Vec::new();
+        for _ in 0..read_u2(read)? { packages.push(constants.get_package(read_u2(read)?)?.to_owned()); }
+        Ok(packages)
+    }
+
+    /// Does this module export `package` to `module`?  `None` means code outside of any named module (the classpath.)
+    pub fn exports_package(&self, package: &str, module: Option<&str>) -> bool {
+        self.exports.iter().any(|e| e.package == package && (e.to.is_empty() || e.to.iter().any(|to| Some(to.as_str()) == module)))
+    }
+}
+
+
+
+/// Which packages belong to which modules, for JPMS accessibility checks.
+///
+/// Classes in packages that don't belong to any known module (e.g. from plain `.jar`s on the classpath) are treated as
+/// being in the unnamed module, which exports everything.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use jreflection::*;
+/// let jdk = Source::from_jdk_dir(r"C:\Program Files\AdoptOpenJDK\jdk-11.0.6.10-hotspot").unwrap();
+/// let modules = module::ModuleGraph::from_sources(std::slice::from_ref(&jdk)).unwrap();
+/// assert_eq!(modules.module_of("java/lang/Object"), Some("java.base"));
+/// assert!( modules.is_exported("java/lang/Object", None));
+/// assert!(!modules.is_exported("jdk/internal/misc/Unsafe", None));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ModuleGraph {
+    modules:    BTreeMap<String, Module>,
+    packages:   BTreeMap<String, String>, // package -> module
+}
+
+impl ModuleGraph {
+    /// Create an empty graph, where every class is in the unnamed module.
+    pub fn new() -> Self { Self::default() }
+
+    /// Find the modules of `sources`.  Earlier sources win if a module or package is found more than once.
+    pub fn from_sources(sources: &[Source]) -> io::Result<Self> {
+        let mut graph = Self::new();
+        for source in sources.iter() { graph.add_source(source)?; }
+        Ok(graph)
+    }
+
+    /// Add the modules of `source`:  every module of a jimage, or the module of a modular `.jar` (which contains every
+    /// package of the `.jar`.)  Plain `.jar`s add nothing.
+    pub fn add_source(&mut self, source: &Source) -> io::Result<()> {
+        #[cfg(feature = "jar")]
+        {
+            if let Some(jar) = source.as_jar() {
+                let bytes = match jar.read_entry("module-info.class") {
+                    Ok(bytes) => bytes,
+                    Err(ref err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+                    Err(err) => return Err(err),
+                };
+                let module = Self::read_module_info(&bytes[..])?;
+                let mut packages = module.packages.clone();
+                source.for_each_class(|class|{
+                    if let Some(package) = package_of(&class) { packages.push(package.to_owned()); }
+                    Ok(())
+                })?;
+                self.add(module, packages);
+                return Ok(());
+            }
+        }
+
+        if let Some((base, patch)) = source.as_overlay() {
+            self.add_source(base)?;
+            self.add_source(patch)?;
+        } else {
+            #[cfg(feature = "jimage")]
+            {
+                if let Some(jimage) = source.as_jimage() {
+                    let mut packages = BTreeMap::<String, BTreeSet<String>>::new();
+                    for (module, path) in jimage.resources()? {
+                        if !path.ends_with(".class") { continue; }
+                        if let Some(package) = package_of(&path) { packages.entry(module).or_default().insert(package.to_owned()); }
+                    }
+                    for name in jimage.modules()? {
+                        let bytes = jimage.read_resource(&name, "module-info.class")?;
+                        let module = Self::read_module_info(&bytes[..])?;
+                        let packages = packages.remove(&name).unwrap_or_default();
+                        self.add(module, packages);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(any(feature = "jar", feature = "jimage"))]
+    fn read_module_info(bytes: &[u8]) -> io::Result<Module> {
+        let class = Class::read(&mut &bytes[..])?;
+        match class.module() {
+            Some(module)    => Ok(module.clone()),
+            None            => io_data_err!("module-info.class of {:?} is missing its Module attribute", class.path.as_str()),
+        }
+    }
+
+    /// Add `module`, containing `packages` in addition to any listed in [Module::packages].  Ignored if a module of
+    /// the same name was already added, and packages already belonging to another module stay there.
+    ///
+    /// [Module::packages]: struct.Module.html#structfield.packages
+    pub fn add(&mut self, module: Module, packages: impl IntoIterator<Item = String>) {
+        if self.modules.contains_key(&module.name) { return; }
+        for package in module.packages.iter().cloned().chain(packages) {
+            self.packages.entry(package).or_insert_with(|| module.name.clone());
+        }
+        self.modules.insert(module.name.clone(), module);
+    }
+
+    /// Get a module by name.
+    pub fn module(&self, name: &str) -> Option<&Module> { self.modules.get(name) }
+
+    /// Every known module, by name.
+    pub fn modules(&self) -> impl Iterator<Item = &Module> { self.modules.values() }
+
+    /// The name of the module containing `class` (a JNI path), or `None` for the unnamed module.
+    pub fn module_of(&self, class: &str) -> Option<&str> {
+        package_of(class).and_then(|package| self.packages.get(package)).map(|module| module.as_str())
+    }
+
+    /// Is the package of `class` accessible to code in `module` (`None` for the unnamed module, i.e. the classpath)?
+    ///
+    /// True for classes in the unnamed module, classes in `module` itself, and classes in packages exported to
+    /// `module`.  This ignores whether `class` itself is `public` - see
+    /// [Hierarchy::is_accessible](../struct.Hierarchy.html#method.is_accessible) for that.
+    pub fn is_exported(&self, class: &str, module: Option<&str>) -> bool {
+        let owner = match self.module_of(class) {
+            Some(owner) => owner,
+            None        => return true,
+        };
+        if module == Some(owner) { return true; }
+        match self.modules.get(owner) {
+            Some(owner) => owner.exports_package(package_of(class).unwrap_or(""), module),
+            None        => true,
+        }
+    }
+}
+
+fn package_of(class: &str) -> Option<&str> {
+    class.rfind('/').map(|slash| &class[..slash])
+}
+
+#[cfg(all(test, feature = "jar"))] pub(crate) fn module_info(name: &str, exports: &[(&str, &[&str])], packages: &[&str]) -> Vec<u8> {
+    use crate::testing::*;
+    let mut c = ClassFile::new("module-info", None);
+    let module = |c: &mut ClassFile, name: &str| { let n = c.utf8(name); c.constant(19, &be16(n)) };
+    let package = |c: &mut ClassFile, name: &str| { let n = c.utf8(name); c.constant(20, &be16(n)) };
+    let mut info = vec![be16(module(&mut c, name)), be16(0), be16(0), be16(0), be16(exports.len() as u16)];
+    for (package_name, to) in exports.iter() {
+        info.push(be16(package(&mut c, package_name)));
+        info.push(be16(0));
+        info.push(be16(to.len() as u16));
+        for to in to.iter() { info.push(be16(module(&mut c, to))); }
+    }
+    info.extend(vec![be16(0), be16(0), be16(0)]); // opens, uses, provides
+    c.class_attribute("Module", &info.concat());
+    let mut listed = vec![be16(packages.len() as u16)];
+    for p in packages.iter() { listed.push(be16(package(&mut c, p))); }
+    c.class_attribute("ModulePackages", &listed.concat());
+    c.to_bytes()
+}
+
+#[cfg(feature = "jar")]
+#[test] fn module_graph() {
+    let info = module_info("com.example", &[("com/example/api", &[]), ("com/example/spi", &["com.example.plugin"])], &["com/example/listed"]);
+    let module = ModuleGraph::read_module_info(&info[..]).unwrap();
+    assert_eq!(module.name, "com.example");
+    assert_eq!(module.packages, &["com/example/listed"]);
+    assert!( module.exports_package("com/example/api", None));
+    assert!(!module.exports_package("com/example/spi", None));
+    assert!( module.exports_package("com/example/spi", Some("com.example.plugin")));
+
+    let class = |path: &str| (format!("{}.class", path), testing::ClassFile::new(path, Some("java/lang/Object")).to_bytes());
+    let entries = vec![("module-info.class".to_owned(), info), class("com/example/api/Api"), class("com/example/spi/Spi"), class("com/example/internal/Impl")];
+    let entries = entries.iter().map(|(n, b)| (n.as_str(), b.clone())).collect::<Vec<_>>();
+    let modular = Source::from_jar(testing::write_jar("module-graph", &entries[..])).unwrap();
+    let plain   = Source::from_jar(testing::write_jar("module-graph-plain", &[class("org/example/Lib")].iter().map(|(n, b)| (n.as_str(), b.clone())).collect::<Vec<_>>()[..])).unwrap();
+
+    let graph = ModuleGraph::from_sources(&[modular, plain]).unwrap();
+    assert_eq!(graph.modules().count(), 1);
+    assert_eq!(graph.module_of("com/example/internal/Impl"), Some("com.example"));
+    assert_eq!(graph.module_of("com/example/listed/Listed"), Some("com.example"));
+    assert_eq!(graph.module_of("org/example/Lib"), None);
+    assert!( graph.is_exported("com/example/api/Api", None));
+    assert!(!graph.is_exported("com/example/internal/Impl", None));
+    assert!( graph.is_exported("com/example/internal/Impl", Some("com.example")));
+    assert!(!graph.is_exported("com/example/spi/Spi", Some("com.other")));
+    assert!( graph.is_exported("com/example/spi/Spi", Some("com.example.plugin")));
+    assert!( graph.is_exported("org/example/Lib", Some("com.example")));
+}
+
+#[test] fn directives() {
+    use crate::testing::*;
+    let mut c = ClassFile::new("module-info", None);
+    let module = |c: &mut ClassFile, name: &str| { let n = c.utf8(name); c.constant(19, &be16(n)) };
+    let package = |c: &mut ClassFile, name: &str| { let n = c.utf8(name); c.constant(20, &be16(n)) };
+    let name    = module(&mut c, "com.example");
+    let version = c.utf8("1.0");
+    let base    = module(&mut c, "java.base");
+    let base_v  = c.utf8("17");
+    let sql     = module(&mut c, "java.sql");
+    let logging = module(&mut c, "java.logging");
+    let api     = package(&mut c, "com/example/api");
+    let model   = package(&mut c, "com/example/model");
+    let driver  = c.class("java/sql/Driver");
+    let impl_   = c.class("com/example/impl/ExampleDriver");
+    let info = [
+        be16(name), be16(0x0020), be16(version),                                            // open module com.example @ 1.0
+        be16(3),
+        be16(base), be16(0x8000), be16(base_v),                                             //     requires mandated java.base
+        be16(sql), be16(0x0020), be16(0),                                                   //     requires transitive java.sql
+        be16(logging), be16(0x0040), be16(0),                                               //     requires static java.logging
+        be16(1), be16(api), be16(0), be16(0),                                               //     exports com.example.api
+        be16(1), be16(model), be16(0), be16(1), be16(sql),                                  //     opens com.example.model to java.sql
+        be16(1), be16(driver),                                                              //     uses java.sql.Driver
+        be16(1), be16(driver), be16(1), be16(impl_),                                        //     provides java.sql.Driver with ...
+    ].concat();
+    c.class_attribute("Module", &info);
+    let packages = [be16(2), be16(api), be16(model)].concat();
+    c.class_attribute("ModulePackages", &packages);
+    let main = c.class("com/example/Main");
+    c.class_attribute("ModuleMainClass", &be16(main));
+
+    let class = c.read();
+    let module = class.module().unwrap();
+    assert_eq!(module.name, "com.example");
+    assert!(module.open);
+    assert_eq!(module.version.as_ref().map(|v| v.as_str()), Some("1.0"));
+    assert_eq!(module.requires.iter().map(|r| (r.module.as_str(), r.mandated, r.transitive, r.static_phase)).collect::<Vec<_>>(), &[
+        ("java.base",       true,  false, false),
+        ("java.sql",        false, true,  false),
+        ("java.logging",    false, false, true ),
+    ]);
+    assert_eq!(module.requires[0].version.as_ref().map(|v| v.as_str()), Some("17"));
+    assert_eq!(module.exports, &[Exports::new("com/example/api".to_owned(), vec![])]);
+    assert_eq!(module.opens, &[Exports::new("com/example/model".to_owned(), vec!["java.sql".to_owned()])]);
+    assert_eq!(module.uses, &["java/sql/Driver"]);
+    assert_eq!(module.provides, &[Provides::new("java/sql/Driver".to_owned(), vec!["com/example/impl/ExampleDriver".to_owned()])]);
+    assert_eq!(module.packages, &["com/example/api", "com/example/model"]);
+    assert_eq!(module.main_class.as_ref().map(|c| c.as_str()), Some("com/example/Main"));
+}
@@ -0,0 +1,310 @@
+//! Structured breakdowns of compiler generated binary class names, such as `com/foo/Bar$Baz$1$$Lambda$5`.
+//!
+//! For Rust-friendly names of Java members, see [naming](../naming/index.html) instead.
+
+use std::collections::*;
+use std::fmt::{self, Display, Formatter};
+
+
+
+/// A class's JNI path, broken down into its package, outermost class, and nested classes.  Display it to get the
+/// original JNI path back.
+///
+/// # Examples
+///
+/// ```
+/// # use jreflection::names::{self, Segment};
+/// let name = names::demangle("com/foo/Bar$Baz$1$$Lambda$5");
+/// assert_eq!(name.package,                "com/foo");
+/// assert_eq!(name.outer,                  "Bar");
+/// assert_eq!(name.outer_class(),          "com/foo/Bar");
+/// assert_eq!(name.member_classes().collect::<Vec<_>>(), &["Baz"]);
+/// assert_eq!(name.anonymous_index(),      Some(1));
+/// assert_eq!(name.lambda_index(),         Some(5));
+/// assert_eq!(name.segments[1],            Segment::Anonymous(1));
+/// assert_eq!(name.to_string(),            "com/foo/Bar$Baz$1$$Lambda$5");
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Name {
+    /// The package, such as `"com/foo"`, or `""` for the default package.
+    pub package:    String,
+
+    /// The simple name of the outermost class, such as `"Bar"`.
+    pub outer:      String,
+
+    /// The classes nested within [outer](#structfield.outer), outermost first.
+    pub segments:   Vec<Segment>,
+}
+
+/// A single nested class within a [Name](struct.Name.html).
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Segment {
+    /// A member (or otherwise named) class, such as the `Baz` of `Bar$Baz`.
+    Member(String),
+
+    /// An anonymous class, such as the `1` of `Bar$1`.
+    Anonymous(u32),
+
+    /// A local class, such as the `1Local` of `Bar$1Local`.
+    Local { index: u32, name: String },
+
+    /// A lambda proxy class spun by `LambdaMetafactory`, such as the `$Lambda$5` of `Bar$$Lambda$5`, or the
+    /// `$Lambda$5/0x0000000800c02040` of a hidden class.  Newer JDKs omit the index.
+    Lambda { index: Option<u32>, hidden: Option<String> },
+}
+
+/// Break down the JNI path of a class.  Any path can be demangled, and displays back to the original path.
+///
+/// Names are split on `$`s, as Java compilers do for nested classes, so classes that merely have a `$` in their name
+/// are reported as having (inaccurate) nested [Member](enum.Segment.html#variant.Member) segments.  Consult the
+/// [inner_classes](../struct.Class.html#method.inner_classes) of the class to be certain.
+///
+/// # Examples
+///
+/// ```
+/// # use jreflection::names::{self, Segment};
+/// let name = names::demangle("java/util/HashMap$KeySet");
+/// assert_eq!(name.segments, vec![Segment::Member("KeySet".to_owned())]);
+///
+/// let name = names::demangle("Main$1Local");
+/// assert_eq!(name.segments, vec![Segment::Local { index: 1, name: "Local".to_owned() }]);
+/// assert_eq!(name.anonymous_index(), None);
+/// ```
+pub fn demangle(path: &str) -> Name {
+    let (rest, lambda) = match path.find("$$Lambda") {
+        Some(start) => (&path[..start], Some(lambda(&path[start+8..]))),
+        None        => (path, None),
+    };
+    let (package, simple) = match rest.rfind('/') {
+        Some(slash) => (&rest[..slash], &rest[slash+1..]),
+        None        => ("", rest),
+    };
+
+    let mut tokens = simple.split('$');
+    let outer = tokens.next().unwrap_or("").to_owned();
+    let mut segments = tokens.map(segment).collect::<Vec<_>>();
+    segments.extend(lambda);
+    Name { package: package.to_owned(), outer, segments }
+}
+
+/// The name a class is referred to by in Java source (and imported by), such as `"Entry"` for `java/util/Map$Entry`.
+/// `None` for anonymous, local, and lambda proxy classes (and classes nested within them), which can't be imported.
+///
+/// # Examples
+///
+/// ```
+/// # use jreflection::names;
+/// assert_eq!(names::simple_name("java/util/ArrayList"),  Some("ArrayList"));
+/// assert_eq!(names::simple_name("java/util/Map$Entry"),  Some("Entry"));
+/// assert_eq!(names::simple_name("Main$1"),               None);
+/// assert_eq!(names::simple_name("Main$1Local$Inner"),    None);
+/// ```
+pub fn simple_name(path: &str) -> Option<&str> {
+    let simple = &path[path.rfind('/').map(|s| s+1).unwrap_or(0)..];
+    if path.contains("$$Lambda") || simple.split('$').skip(1).any(|s| s.starts_with(|ch: char| ch.is_ascii_digit())) { return None; }
+    let simple = &simple[simple.rfind('$').map(|s| s+1).unwrap_or(0)..];
+    if simple.is_empty() { None } else { Some(simple) }
+}
+
+/// The result of resolving a simple name (such as `"List"`) to the JNI paths of the classes it could refer to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Resolution {
+    /// No class has that simple name.
+    NotFound,
+
+    /// Exactly one class has that simple name.
+    Unique(String),
+
+    /// Several classes have that simple name (such as `java/awt/List` and `java/util/List`), sorted by path.
+    Ambiguous(Vec<String>),
+}
+
+impl Resolution {
+    /// Resolve to `candidates`, which needn't be sorted or deduplicated.
+    pub fn from_candidates(candidates: impl IntoIterator<Item = String>) -> Self {
+        let mut candidates = candidates.into_iter().collect::<Vec<_>>();
+        candidates.sort();
+        candidates.dedup();
+        match candidates.len() {
+            0 => Resolution::NotFound,
+            1 => Resolution::Unique(candidates.pop().unwrap()),
+            _ => Resolution::Ambiguous(candidates),
+        }
+    }
+
+    /// The JNI path of the class, if the name resolved to exactly one.
+    pub fn unique(&self) -> Option<&str> { match self { Resolution::Unique(path) => Some(path.as_str()), _ => None } }
+
+    /// Every candidate class, sorted by path.
+    pub fn candidates(&self) -> &[String] {
+        match self {
+            Resolution::NotFound            => &[],
+            Resolution::Unique(path)        => std::slice::from_ref(path),
+            Resolution::Ambiguous(paths)    => &paths[..],
+        }
+    }
+}
+
+/// An index from [simple names](fn.simple_name.html) to the JNI paths of classes, to resolve unqualified names like
+/// an IDE's import assistance does.  See also [ClassPath::simple_name_index](../struct.ClassPath.html#method.simple_name_index).
+///
+/// # Examples
+///
+/// ```
+/// # use jreflection::names::{Resolution, SimpleNameIndex};
+/// let mut index = SimpleNameIndex::new();
+/// index.insert("java/util/ArrayList");
+/// index.insert("java/util/List");
+/// index.insert("java/awt/List");
+/// index.insert("java/util/Map$Entry");
+/// assert_eq!(index.resolve("ArrayList"), Resolution::Unique("java/util/ArrayList".to_owned()));
+/// assert_eq!(index.resolve("Entry").unique(), Some("java/util/Map$Entry"));
+/// assert_eq!(index.resolve("List").candidates(), &["java/awt/List", "java/util/List"]);
+/// assert_eq!(index.resolve("HashMap"), Resolution::NotFound);
+/// assert_eq!(index.ambiguous().map(|(name, _)| name).collect::<Vec<_>>(), &["List"]);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SimpleNameIndex {
+    names: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl SimpleNameIndex {
+    /// Create an empty index.
+    pub fn new() -> Self { Self::default() }
+
+    /// Index the class at `path`.  Classes without a [simple name](fn.simple_name.html) are ignored.
+    pub fn insert(&mut self, path: &str) {
+        if let Some(simple) = simple_name(path) {
+            self.names.entry(simple.to_owned()).or_default().insert(path.to_owned());
+        }
+    }
+
+    /// Resolve a simple name, such as `"ArrayList"`, to the classes it could refer to.
+    pub fn resolve(&self, simple_name: &str) -> Resolution {
+        Resolution::from_candidates(self.names.get(simple_name).into_iter().flat_map(|paths| paths.iter().cloned()))
+    }
+
+    /// Iterate over every simple name referring to more than one class, along with those classes' paths.
+    pub fn ambiguous(&self) -> impl Iterator<Item = (&str, &BTreeSet<String>)> {
+        self.names.iter().filter(|(_, paths)| paths.len() > 1).map(|(name, paths)| (name.as_str(), paths))
+    }
+
+    /// How many distinct simple names are indexed.
+    pub fn len(&self) -> usize { self.names.len() }
+
+    /// Are no names indexed?
+    pub fn is_empty(&self) -> bool { self.names.is_empty() }
+}
+
+impl Name {
+    /// The JNI path of the outermost class, such as `"com/foo/Bar"`.
+    pub fn outer_class(&self) -> String {
+        if self.package.is_empty() { self.outer.clone() } else { format!("{}/{}", self.package, self.outer) }
+    }
+
+    /// The JNI path of the class enclosing this one, if this is a nested class.
+    pub fn enclosing_class(&self) -> Option<String> {
+        if self.segments.is_empty() { return None; }
+        let mut enclosing = self.clone();
+        enclosing.segments.pop();
+        Some(enclosing.to_string())
+    }
+
+    /// The names of the [Member](enum.Segment.html#variant.Member) classes nested within the outermost class, outermost first.
+    pub fn member_classes(&self) -> impl Iterator<Item = &str> {
+        self.segments.iter().filter_map(|s| match s { Segment::Member(name) => Some(name.as_str()), _ => None })
+    }
+
+    /// The index of the innermost anonymous class of this name, if any.
+    pub fn anonymous_index(&self) -> Option<u32> {
+        self.segments.iter().rev().filter_map(|s| match s { Segment::Anonymous(index) => Some(*index), _ => None }).next()
+    }
+
+    /// The index of the lambda proxy class this name is for, if any (and if the JDK that spun it included one.)
+    pub fn lambda_index(&self) -> Option<u32> {
+        self.segments.iter().filter_map(|s| match s { Segment::Lambda { index, .. } => *index, _ => None }).next()
+    }
+
+    /// Is this the name of an anonymous class, local class, or lambda proxy class, or nested within one?
+    pub fn is_anonymous(&self) -> bool {
+        self.segments.iter().any(|s| match s { Segment::Member(_) => false, _ => true })
+    }
+}
+
+impl Display for Name {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        if !self.package.is_empty() { write!(fmt, "{}/", self.package)?; }
+        fmt.write_str(&self.outer)?;
+        for segment in self.segments.iter() { write!(fmt, "{}", segment)?; }
+        Ok(())
+    }
+}
+
+impl Display for Segment {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        match self {
+            Segment::Member(name)               => write!(fmt, "${}", name),
+            Segment::Anonymous(index)           => write!(fmt, "${}", index),
+            Segment::Local { index, name }      => write!(fmt, "${}{}", index, name),
+            Segment::Lambda { index, hidden }   => {
+                fmt.write_str("$$Lambda")?;
+                if let Some(index)  = index  { write!(fmt, "${}", index)?; }
+                if let Some(hidden) = hidden { write!(fmt, "/{}", hidden)?; }
+                Ok(())
+            },
+        }
+    }
+}
+
+fn segment(token: &str) -> Segment {
+    let digits = token.find(|ch: char| !ch.is_ascii_digit()).unwrap_or(token.len());
+    match index(&token[..digits]) {
+        Some(index) if digits == token.len()    => Segment::Anonymous(index),
+        Some(index)                             => Segment::Local { index, name: token[digits..].to_owned() },
+        None                                    => Segment::Member(token.to_owned()),
+    }
+}
+
+/// Parse what follows `$$Lambda`, such as `"$5/0x0000000800c02040"`.
+fn lambda(rest: &str) -> Segment {
+    let (rest, hidden) = match rest.find('/') {
+        Some(slash) => (&rest[..slash], Some(rest[slash+1..].to_owned())),
+        None        => (rest, None),
+    };
+    let index = if rest.starts_with('$') { index(&rest[1..]) } else { None };
+    match index {
+        Some(_)                     => Segment::Lambda { index, hidden },
+        None if rest.is_empty()     => Segment::Lambda { index, hidden },
+        None                        => Segment::Member(format!("$Lambda{}{}", rest, hidden.map(|h| format!("/{}", h)).unwrap_or_default())), // Not a lambda after all, but round trip anyways
+    }
+}
+
+/// Parse a canonical decimal index (no sign, no leading zeros), so displaying it round trips.
+fn index(digits: &str) -> Option<u32> {
+    if digits.is_empty() || (digits.starts_with('0') && digits.len() > 1) || !digits.bytes().all(|b| b.is_ascii_digit()) { return None; }
+    digits.parse().ok()
+}
+
+#[test] fn demangle_names() {
+    for path in [
+        "com/foo/Bar", "com/foo/Bar$Baz$1$$Lambda$5", "Main$1Local$2", "com/foo/Bar$$Lambda$12/0x0000000800c02040",
+        "com/foo/Bar$$Lambda/0x0000000800c02040", "com/foo/Bar$$Lambdas", "$Proxy12", "Foo$", "Foo$$Bar", "Foo$01", "a/b/c$99999999999",
+    ].iter() {
+        assert_eq!(demangle(path).to_string(), *path);
+    }
+
+    let name = demangle("com/foo/Bar$$Lambda$12/0x0000000800c02040");
+    assert_eq!(name.package, "com/foo");
+    assert_eq!(name.segments, vec![Segment::Lambda { index: Some(12), hidden: Some("0x0000000800c02040".to_owned()) }]);
+    assert!(name.is_anonymous());
+
+    let name = demangle("Main$Inner$1Local$2");
+    assert_eq!(name.outer_class(), "Main");
+    assert_eq!(name.member_classes().collect::<Vec<_>>(), &["Inner"]);
+    assert_eq!(name.anonymous_index(), Some(2));
+    assert_eq!(name.lambda_index(), None);
+    assert_eq!(name.enclosing_class().unwrap(), "Main$Inner$1Local");
+    assert_eq!(demangle("Main").enclosing_class(), None);
+    assert!(!demangle("java/util/Map$Entry").is_anonymous());
+    assert_eq!(demangle("Foo$01").segments, vec![Segment::Member("01".to_owned())]);
+}
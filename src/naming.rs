@@ -0,0 +1,352 @@
+//! Rust-friendly names for Java members, for code generators such as JNI binding generators.
+//!
+//! Every generator needs to turn `getURLConnection` into `get_url_connection`, avoid emitting `fn type()`, and give each
+//! of `StringBuilder.append`'s dozen overloads its own name.  These helpers do so deterministically, so regenerating
+//! bindings against the same classes always produces the same names.
+
+use crate::{field, method, Class, Field, Method};
+#[cfg(test)] use crate::class;
+
+use std::collections::*;
+use std::io::{self, BufRead, Write};
+
+
+
+/// Reserved Rust keywords, including those reserved for future use, that can't be used as plain identifiers.
+pub const RUST_KEYWORDS : &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate", "do", "dyn", "else",
+    "enum", "extern", "false", "final", "fn", "for", "if", "impl", "in", "let", "loop", "macro", "match", "mod", "move",
+    "mut", "override", "priv", "pub", "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true",
+    "try", "type", "typeof", "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+/// Is `name` a reserved Rust keyword?
+pub fn is_keyword(name: &str) -> bool { RUST_KEYWORDS.contains(&name) }
+
+/// Convert a Java `camelCase` or `PascalCase` name to `snake_case`.  Acronyms are kept together, and characters that
+/// aren't valid in Rust identifiers (such as `$` or `<`) become underscores.
+///
+/// # Examples
+///
+/// ```
+/// # use jreflection::naming::snake_case;
+/// assert_eq!(snake_case("toString"),          "to_string");
+/// assert_eq!(snake_case("getURLConnection"),  "get_url_connection");
+/// assert_eq!(snake_case("MAX_VALUE"),         "max_value");
+/// assert_eq!(snake_case("access$000"),        "access_000");
+/// ```
+pub fn snake_case(name: &str) -> String {
+    let chars = name.chars().collect::<Vec<_>>();
+    let mut out = String::with_capacity(name.len() + 4);
+    for (i, &ch) in chars.iter().enumerate() {
+        let prev = if i > 0 { Some(chars[i-1]) } else { None };
+        let next = chars.get(i+1).copied();
+        if !ch.is_alphanumeric() && ch != '_' {
+            if !out.is_empty() && !out.ends_with('_') { out.push('_'); }
+            continue;
+        }
+        if ch.is_uppercase() && !out.is_empty() && !out.ends_with('_') {
+            let after_lower     = prev.map(|p| p.is_lowercase() || p.is_numeric()).unwrap_or(false);
+            let acronym_end     = prev.map(|p| p.is_uppercase()).unwrap_or(false) && next.map(|n| n.is_lowercase()).unwrap_or(false);
+            if after_lower || acronym_end { out.push('_'); }
+        }
+        out.extend(ch.to_lowercase());
+    }
+    while out.len() > 1 && out.ends_with('_') && !name.ends_with('_') { out.pop(); }
+    if out.is_empty() { out.push('_'); }
+    out
+}
+
+/// Make `name` usable as a Rust identifier if it's a keyword: most keywords become raw identifiers (`r#type`), while
+/// those that can't be raw identifiers (`self`, `Self`, `super`, `crate`) get a trailing underscore instead.
+///
+/// # Examples
+///
+/// ```
+/// # use jreflection::naming::escape_keyword;
+/// assert_eq!(escape_keyword("type"),  "r#type");
+/// assert_eq!(escape_keyword("self"),  "self_");
+/// assert_eq!(escape_keyword("size"),  "size");
+/// ```
+pub fn escape_keyword(name: &str) -> String {
+    match name {
+        "self" | "Self" | "super" | "crate" | "_"   => format!("{}_", name),
+        name if is_keyword(name)                    => format!("r#{}", name),
+        name                                        => name.to_owned(),
+    }
+}
+
+/// A `snake_case`, keyword-escaped Rust identifier for a single Java member name.
+pub fn rust_ident(name: &str) -> String { escape_keyword(&snake_case(name)) }
+
+/// Unique Rust identifiers for each of `fields`, in the same order.  Fields whose names only differ in case (rare, but
+/// legal) are disambiguated with numeric suffixes, in descriptor order.
+pub fn field_names<'a>(fields: impl IntoIterator<Item = &'a Field>) -> Vec<String> {
+    let fields = fields.into_iter().collect::<Vec<_>>();
+    let names = fields.iter().map(|f| rust_ident(&f.name)).collect();
+    let keys = fields.iter().map(|f| (f.name.as_str(), f.descriptor_str())).collect::<Vec<_>>();
+    deduplicate(names, &keys)
+}
+
+/// Unique Rust identifiers for each of `methods`, in the same order.
+///
+/// Constructors (`<init>`) are named `new`.  Overloaded methods are suffixed with their parameter types
+/// (`append_char`, `append_char_array`, `append_string`), except for a parameterless overload, which keeps the bare
+/// name.  Any names that still collide get numeric suffixes, in descriptor order, so the result doesn't depend on the
+/// order methods appear in the class file.
+///
+/// # Examples
+///
+/// ```
+/// # use jreflection::*;
+/// let methods = [
+///     Method::new(method::Flags::PUBLIC, "<init>".into(), "()V".into()).unwrap(),
+///     Method::new(method::Flags::PUBLIC, "append".into(), "(C)Ljava/lang/StringBuilder;".into()).unwrap(),
+///     Method::new(method::Flags::PUBLIC, "append".into(), "([C)Ljava/lang/StringBuilder;".into()).unwrap(),
+///     Method::new(method::Flags::PUBLIC, "append".into(), "(Ljava/lang/String;)Ljava/lang/StringBuilder;".into()).unwrap(),
+///     Method::new(method::Flags::PUBLIC, "toString".into(), "()Ljava/lang/String;".into()).unwrap(),
+///     Method::new(method::Flags::PUBLIC, "type".into(), "()I".into()).unwrap(),
+/// ];
+/// assert_eq!(naming::method_names(methods.iter()), &[
+///     "new", "append_char", "append_char_array", "append_string", "to_string", "r#type",
+/// ]);
+/// ```
+pub fn method_names<'a>(methods: impl IntoIterator<Item = &'a Method>) -> Vec<String> {
+    let methods = methods.into_iter().collect::<Vec<_>>();
+    let bases = methods.iter().map(|m| if m.name == "<init>" { "new".to_owned() } else { snake_case(&m.name) }).collect::<Vec<_>>();
+
+    let mut overloads = BTreeMap::<&str, usize>::new();
+    for base in bases.iter() { *overloads.entry(base.as_str()).or_default() += 1; }
+
+    let names = methods.iter().zip(bases.iter()).map(|(method, base)| {
+        let mut name = base.clone();
+        if overloads[base.as_str()] > 1 {
+            for arg in method.descriptor().arguments() {
+                name.push('_');
+                name.push_str(&type_name(&arg));
+            }
+        }
+        escape_keyword(&name)
+    }).collect();
+    let keys = methods.iter().map(|m| (m.name.as_str(), m.descriptor_str())).collect::<Vec<_>>();
+    deduplicate(names, &keys)
+}
+
+/// A short `snake_case` name for a type, for use in overload suffixes: `int`, `string`, `entry` (for `java/util/Map$Entry`), `byte_array`, etc.
+pub fn type_name(ty: &method::Type) -> String {
+    let (levels, inner) = match ty {
+        method::Type::Single(inner)             => (0, inner),
+        method::Type::Array { levels, inner }   => (*levels, inner),
+    };
+    let mut name = match inner {
+        field::BasicType::Boolean   => "boolean".to_owned(),
+        field::BasicType::Byte      => "byte".to_owned(),
+        field::BasicType::Char      => "char".to_owned(),
+        field::BasicType::Short     => "short".to_owned(),
+        field::BasicType::Int       => "int".to_owned(),
+        field::BasicType::Long      => "long".to_owned(),
+        field::BasicType::Float     => "float".to_owned(),
+        field::BasicType::Double    => "double".to_owned(),
+        field::BasicType::Void      => "void".to_owned(),
+        field::BasicType::Class(id) => snake_case(id.as_str().rsplit(|c| c == '/' || c == '$').next().unwrap_or("")),
+    };
+    for _ in 0..levels { name.push_str("_array"); }
+    name
+}
+
+/// The Rust identifiers chosen for the members of a set of classes, which can be saved alongside generated bindings and
+/// re-loaded when regenerating them, so that existing identifiers stay stable even as new releases of a library add
+/// overloads or otherwise change which names [method_names] would pick.
+///
+/// Serializes to a simple line based text format:  a `jreflection-names 1` header, followed by `class <path>` lines, each
+/// followed by `<name> <descriptor> <ident>` lines for that class's members.  Entries can be edited by hand to rename
+/// members, and will be preserved.
+///
+/// [method_names]:     fn.method_names.html
+///
+/// # Examples
+///
+/// ```no_run
+/// # use jreflection::*;
+/// # use jreflection::naming::NameMap;
+/// # use std::fs::File;
+/// # use std::io::BufReader;
+/// # #[cfg(feature = "jar")] {
+/// let mut names = match File::open("names.txt") {
+///     Ok(file) => NameMap::read(&mut BufReader::new(file)).unwrap(),
+///     Err(_) => NameMap::new(),
+/// };
+/// let class = Source::from_jar("library.jar").unwrap().read_class("com/example/Foo").unwrap();
+/// names.add_class(&class);
+/// for method in class.methods.iter() {
+///     println!("fn {}()", names.get(class.path.as_str(), &method.name, method.descriptor_str()).unwrap());
+/// }
+/// names.write(&mut File::create("names.txt").unwrap()).unwrap();
+/// # }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct NameMap {
+    classes: BTreeMap<String, BTreeMap<(String, String), String>>,
+}
+
+impl NameMap {
+    /// Create an empty name map.
+    pub fn new() -> Self { Self::default() }
+
+    /// Choose identifiers for any fields and methods of `class` that don't have them yet.  Members that already have
+    /// identifiers keep them, and new members are given identifiers that don't collide with them.
+    pub fn add_class(&mut self, class: &Class) {
+        let members = self.classes.entry(class.path.as_str().to_owned()).or_default();
+        let fields  = class.fields.iter().map(|f| (f.name.as_str(), f.descriptor_str()));
+        let methods = class.methods.iter().map(|m| (m.name.as_str(), m.descriptor_str()));
+        add_members(members, fields .zip(field_names (class.fields .iter())), |d| !d.starts_with('('));
+        add_members(members, methods.zip(method_names(class.methods.iter())), |d|  d.starts_with('('));
+    }
+
+    /// Set the identifier for a member, replacing any previous choice.
+    pub fn insert(&mut self, class: &str, name: &str, descriptor: &str, ident: &str) {
+        self.classes.entry(class.to_owned()).or_default().insert((name.to_owned(), descriptor.to_owned()), ident.to_owned());
+    }
+
+    /// The identifier chosen for a member, if any.
+    pub fn get(&self, class: &str, name: &str, descriptor: &str) -> Option<&str> {
+        // Avoid allocating a (String, String) key just to look it up
+        self.classes.get(class)?.iter().find(|((n, d), _)| n == name && d == descriptor).map(|(_, ident)| ident.as_str())
+    }
+
+    /// The classes with recorded identifiers, sorted.
+    pub fn classes(&self) -> impl Iterator<Item = &str> { self.classes.keys().map(|c| c.as_str()) }
+
+    /// Read a name map previously written by [write](#method.write).
+    pub fn read(read: &mut impl BufRead) -> io::Result<Self> {
+        let mut map = Self::new();
+        let mut class = None;
+        for (index, line) in read.lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if index == 0 {
+                io_assert!(line == "jreflection-names 1", "Expected name map header, got {:?}", line);
+            } else if line.is_empty() {
+            } else if line.starts_with("class ") {
+                let path = line["class ".len()..].to_owned();
+                map.classes.entry(path.clone()).or_default();
+                class = Some(path);
+            } else if let Some(class) = class.as_ref() {
+                let mut parts = line.rsplitn(3, ' ');
+                match (parts.next(), parts.next(), parts.next()) {
+                    (Some(ident), Some(descriptor), Some(name)) => map.insert(class, name, descriptor, ident),
+                    _ => return io_data_err!("Expected `<name> <descriptor> <ident>` on line {}, got {:?}", index + 1, line),
+                }
+            } else {
+                return io_data_err!("Member on line {} precedes any class", index + 1);
+            }
+        }
+        Ok(map)
+    }
+
+    /// Write this name map in a form that can be [read](#method.read) back.  Output is sorted, so it diffs well.
+    pub fn write(&self, write: &mut impl Write) -> io::Result<()> {
+        writeln!(write, "jreflection-names 1")?;
+        for (class, members) in self.classes.iter() {
+            writeln!(write, "class {}", class)?;
+            for ((name, descriptor), ident) in members.iter() {
+                writeln!(write, "{} {} {}", name, descriptor, ident)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn add_members<'a>(members: &mut BTreeMap<(String, String), String>, fresh: impl Iterator<Item = ((&'a str, &'a str), String)>, same_namespace: impl Fn(&str) -> bool) {
+    let mut used = members.iter().filter(|((_, d), _)| same_namespace(d)).map(|(_, ident)| ident.clone()).collect::<BTreeSet<_>>();
+    let mut fresh = fresh.filter(|((n, d), _)| !members.contains_key(&((*n).to_owned(), (*d).to_owned()))).collect::<Vec<_>>();
+    fresh.sort();
+    for ((name, descriptor), ident) in fresh {
+        let ident = if used.contains(&ident) { (2..).map(|n| format!("{}_{}", ident, n)).find(|n| !used.contains(n)).unwrap() } else { ident };
+        used.insert(ident.clone());
+        members.insert((name.to_owned(), descriptor.to_owned()), ident);
+    }
+}
+
+/// Append `_2`, `_3`, ... to any `names` that collide, keeping the first name (ordered by `keys`) unsuffixed.
+fn deduplicate(mut names: Vec<String>, keys: &[(&str, &str)]) -> Vec<String> {
+    let mut order = (0..names.len()).collect::<Vec<_>>();
+    order.sort_by_key(|&i| (names[i].clone(), keys[i]));
+
+    let mut used = BTreeSet::new();
+    let mut collided = Vec::new();
+    for i in order {
+        if !used.insert(names[i].clone()) { collided.push(i); }
+    }
+    for i in collided {
+        let name = (2..).map(|n| format!("{}_{}", names[i], n)).find(|n| !used.contains(n)).unwrap();
+        used.insert(name.clone());
+        names[i] = name;
+    }
+    names
+}
+
+#[test] fn naming() {
+    assert_eq!(snake_case("URLDecoder"),    "url_decoder");
+    assert_eq!(snake_case("HTTPServer2"),   "http_server2");
+    assert_eq!(snake_case("getX"),          "get_x");
+    assert_eq!(snake_case("<clinit>"),      "clinit");
+    assert_eq!(snake_case("_private"),      "_private");
+    assert_eq!(snake_case("trailing_"),     "trailing_");
+    assert_eq!(snake_case("$"),             "_");
+    assert_eq!(rust_ident("Self"),          "self_");
+    assert_eq!(rust_ident("match"),         "r#match");
+
+    let method = |name: &str, desc: &str| Method::new(method::Flags::PUBLIC, name.to_owned(), desc.to_owned()).unwrap();
+    let methods = [
+        method("valueOf",   "(I)Ljava/lang/String;"),
+        method("valueOf",   "(Ljava/util/Map$Entry;)Ljava/lang/String;"),
+        method("getURL",    "()Ljava/net/URL;"),
+        method("getUrl",    "()Ljava/lang/String;"),
+        method("value_of_int", "()V"),
+        method("value",     "([[J)V"),
+    ];
+    let names = method_names(methods.iter());
+    assert_eq!(names, &["value_of_int", "value_of_entry", "get_url", "get_url_2", "value_of_int_2", "value"]);
+
+    let mut reversed = methods.iter().rev().collect::<Vec<_>>();
+    let mut reversed_names = method_names(reversed.drain(..));
+    reversed_names.reverse();
+    assert_eq!(names, reversed_names, "names shouldn't depend on member order");
+
+    let field = |name: &str, desc: &str| Field::new(field::Flags::PUBLIC, name.to_owned(), desc.to_owned()).unwrap();
+    let fields = [field("fooBar", "I"), field("FOO_BAR", "I"), field("in", "Z")];
+    assert_eq!(field_names(fields.iter()), &["foo_bar_2", "foo_bar", "r#in"]);
+}
+
+#[test] fn name_map() {
+    let method = |name: &str, desc: &str| Method::new(method::Flags::PUBLIC, name.to_owned(), desc.to_owned()).unwrap();
+    let mut v1 = Class { path: class::IdBuf::new("com/example/Foo".to_owned()), .. Default::default() };
+    v1.fields.push(Field::new(field::Flags::PUBLIC, "type".to_owned(), "I".to_owned()).unwrap());
+    v1.methods.push(method("append", "(C)V"));
+
+    let mut names = NameMap::new();
+    names.add_class(&v1);
+    assert_eq!(names.get("com/example/Foo", "type", "I"), Some("r#type"));
+    assert_eq!(names.get("com/example/Foo", "append", "(C)V"), Some("append"));
+    names.insert("com/example/Foo", "type", "I", "kind");
+
+    let mut text = Vec::new();
+    names.write(&mut text).unwrap();
+    assert_eq!(String::from_utf8(text.clone()).unwrap(), "jreflection-names 1\nclass com/example/Foo\nappend (C)V append\ntype I kind\n");
+    let mut names = NameMap::read(&mut &text[..]).unwrap();
+    assert_eq!(names.classes().collect::<Vec<_>>(), &["com/example/Foo"]);
+
+    // A new release adds overloads - existing names must stay put
+    let mut v2 = v1.clone();
+    v2.methods.push(method("append", "(Ljava/lang/String;)V"));
+    v2.methods.push(method("append_string", "()V"));
+    names.add_class(&v2);
+    assert_eq!(names.get("com/example/Foo", "type",          "I"),                      Some("kind"));
+    assert_eq!(names.get("com/example/Foo", "append",        "(C)V"),                   Some("append"));
+    assert_eq!(names.get("com/example/Foo", "append",        "(Ljava/lang/String;)V"),  Some("append_string"));
+    assert_eq!(names.get("com/example/Foo", "append_string", "()V"),                    Some("append_string_2"));
+
+    assert!(NameMap::read(&mut &b"jreflection-names 1\nfoo ()V foo\n"[..]).is_err());
+    assert!(NameMap::read(&mut &b"jreflection-fingerprints 1\n"[..]).is_err());
+}
@@ -0,0 +1,192 @@
+//! Options controlling how classes are parsed.
+
+use crate::constants::Constants;
+
+use std::any::Any;
+use std::collections::*;
+use std::fmt::{self, Debug, Display, Formatter};
+use std::io;
+use std::sync::Arc;
+
+
+
+type Decoder = Arc<dyn Fn(&[u8], &Constants) -> io::Result<Arc<dyn Any + Send + Sync>> + Send + Sync>;
+
+/// Options controlling how classes are parsed.
+///
+/// # Examples
+///
+/// ```
+/// use jreflection::ParseOptions;
+/// use jreflection::constants::Constants;
+///
+/// struct WeaverState { bytes: usize }
+///
+/// let mut options = ParseOptions::new();
+/// options.register_attribute("org.aspectj.weaver.WeaverState", |info: &[u8], _constants: &Constants| Ok(WeaverState { bytes: info.len() }));
+/// ```
+#[derive(Clone, Default)]
+pub struct ParseOptions {
+    decoders:       BTreeMap<String, Decoder>,
+    retain_code:    bool,
+    local_names:    bool,
+    duplicates:     DuplicateAttributes,
+}
+
+/// What to do when a standard attribute (such as `Signature`) appears more than once on the same class, field, or
+/// method, which the JVMS forbids.  See [ParseOptions::duplicate_attributes](struct.ParseOptions.html#method.duplicate_attributes).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DuplicateAttributes {
+    /// Fail to parse the class.
+    Error,
+
+    /// Record a [Warning](struct.Warning.html), and use every occurrence:  the last wins for single valued attributes,
+    /// and list valued attributes (such as annotations) are concatenated.  The default.
+    Warn,
+
+    /// Record a [Warning](struct.Warning.html), and ignore all but the first occurrence.
+    FirstWins,
+}
+
+impl Default for DuplicateAttributes {
+    fn default() -> Self { DuplicateAttributes::Warn }
+}
+
+impl ParseOptions {
+    /// Create the default parse options.
+    pub fn new() -> Self { Self::default() }
+
+    /// Decode attributes named `name` with `decoder`, storing the result as a [CustomAttribute] on the class, field,
+    /// or method the attribute belongs to.
+    ///
+    /// `decoder` is given the raw attribute info (not including the name index or length) and the class's constant
+    /// pool.  Registered decoders take priority over jreflection's own handling of an attribute name.
+    ///
+    /// [CustomAttribute]:  struct.CustomAttribute.html
+    pub fn register_attribute<T: Any + Send + Sync>(&mut self, name: impl Into<String>, decoder: impl Fn(&[u8], &Constants) -> io::Result<T> + Send + Sync + 'static) -> &mut Self {
+        let decoder : Decoder = Arc::new(move |info, constants| -> io::Result<Arc<dyn Any + Send + Sync>> { Ok(Arc::new(decoder(info, constants)?)) });
+        self.decoders.insert(name.into(), decoder);
+        self
+    }
+
+    /// Keep the [Code](code/struct.Code.html) of methods, instead of skipping it (the default.)  Required for
+    /// bytecode analysis such as [requirements::of](requirements/fn.of.html).
+    #[cfg(feature = "code")]
+    pub fn retain_code(&mut self, retain: bool) -> &mut Self {
+        self.retain_code = retain;
+        self
+    }
+
+    /// Recover parameter names from the `LocalVariableTable`s of methods (present in classes compiled with `javac -g`),
+    /// for use by [Method::parameter_names](method/struct.Method.html#method.parameter_names) when a method has no
+    /// MethodParameters attribute.  Off by default, as it requires decoding the Code of every method.
+    #[cfg(feature = "code")]
+    pub fn local_variable_names(&mut self, recover: bool) -> &mut Self {
+        self.local_names = recover;
+        self
+    }
+
+    /// Control what happens when the same standard attribute appears more than once on a class, field, or method.
+    /// Defaults to [DuplicateAttributes::Warn](enum.DuplicateAttributes.html#variant.Warn).
+    pub fn duplicate_attributes(&mut self, policy: DuplicateAttributes) -> &mut Self {
+        self.duplicates = policy;
+        self
+    }
+
+    #[cfg(feature = "code")]
+    pub(crate) fn retain_code_enabled(&self) -> bool { self.retain_code }
+
+    #[cfg(feature = "code")]
+    pub(crate) fn local_variable_names_enabled(&self) -> bool { self.local_names }
+
+    pub(crate) fn duplicate_attributes_policy(&self) -> DuplicateAttributes { self.duplicates }
+
+    pub(crate) fn decode_attribute(&self, name: &str, info: &[u8], constants: &Constants) -> Option<io::Result<CustomAttribute>> {
+        let decoder = self.decoders.get(name)?;
+        Some(decoder(info, constants).map(|value| CustomAttribute { name: name.to_owned(), value }))
+    }
+
+    pub(crate) fn has_decoder(&self, name: &str) -> bool { self.decoders.contains_key(name) }
+}
+
+impl Debug for ParseOptions {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("ParseOptions")
+            .field("decoders", &self.decoders.keys().collect::<Vec<_>>())
+            .field("retain_code", &self.retain_code)
+            .field("local_names", &self.local_names)
+            .field("duplicates", &self.duplicates)
+            .finish()
+    }
+}
+
+
+
+/// A recoverable oddity found while parsing a class, which didn't fail the parse.  See [Class::warnings].
+///
+/// [Class::warnings]:  struct.Class.html#method.warnings
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Warning {
+    /// The field or method the warning is about, as its name and descriptor (such as `"run()V"` or `"count:I"`), or
+    /// `None` for the class itself.
+    pub member: Option<String>,
+
+    /// What was odd.
+    pub kind:   WarningKind,
+}
+
+/// What was odd about a class.  See [Warning](struct.Warning.html).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WarningKind {
+    /// A standard attribute (such as `"Signature"`) appeared more than once on the same class, field, or method.  See
+    /// [DuplicateAttributes](enum.DuplicateAttributes.html) for which occurrence was used.
+    DuplicateAttribute(String),
+
+    /// An attribute that should be empty (such as `"Deprecated"` or `"Synthetic"`) had `length` bytes of info, which
+    /// were ignored.
+    NonEmptyAttribute { name: String, length: usize },
+
+    #[doc(hidden)] _NonExhaustive,
+}
+
+impl Warning {
+    pub(crate) fn new(kind: WarningKind) -> Self { Self { member: None, kind } }
+}
+
+impl Display for Warning {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        if let Some(member) = self.member.as_ref() { write!(f, "{}: ", member)?; }
+        match &self.kind {
+            WarningKind::DuplicateAttribute(name)               => write!(f, "duplicate {} attribute", name),
+            WarningKind::NonEmptyAttribute { name, length }     => write!(f, "{} attribute has {} unexpected bytes", name, length),
+            WarningKind::_NonExhaustive                         => write!(f, "unknown warning"),
+        }
+    }
+}
+
+
+
+/// An attribute decoded by a decoder registered with [ParseOptions::register_attribute].
+///
+/// [ParseOptions::register_attribute]:     struct.ParseOptions.html#method.register_attribute
+#[derive(Clone)]
+pub struct CustomAttribute {
+    name:   String,
+    value:  Arc<dyn Any + Send + Sync>,
+}
+
+impl CustomAttribute {
+    /// The name of the attribute, such as `"org.aspectj.weaver.WeaverState"`.
+    pub fn name(&self) -> &str { self.name.as_str() }
+
+    /// The decoded value, if it's of type `T`.
+    pub fn downcast_ref<T: Any>(&self) -> Option<&T> { self.value.downcast_ref() }
+}
+
+impl Debug for CustomAttribute {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("CustomAttribute")
+            .field("name", &self.name)
+            .finish()
+    }
+}
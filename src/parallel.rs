@@ -0,0 +1,197 @@
+//! Parse the classes of a [Source] on a [rayon] thread pool.  Requires the `rayon` feature - and whatever newer Rust
+//! rayon itself requires, as rayon doesn't support jreflection's 1.36 MSRV.
+//!
+//! Reading class bytes happens on the calling thread (sources aren't thread safe), while parsing happens on the pool.
+//! A bounded queue between the two provides backpressure:  if parsing falls behind, reading blocks instead of buffering
+//! an entire `.jar` worth of class bytes in memory.
+//!
+//! [Source]:   ../struct.Source.html
+//! [rayon]:    https://docs.rs/rayon/
+
+use crate::{Class, ParseOptions, Source};
+use crate::scan::ScanResult;
+#[cfg(all(test, feature = "jar"))] use crate::testing;
+
+use rayon::{ThreadPool, ThreadPoolBuilder};
+
+use std::io::{self, Error, ErrorKind};
+use std::sync::{Mutex, mpsc};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+
+
+/// Settings for a parallel scan:  which thread pool to use (or how many threads to create one with), how many classes
+/// may be queued for parsing, and how classes are parsed.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use jreflection::*;
+/// # use jreflection::parallel::Scan;
+/// # #[cfg(feature = "jar")] {
+/// let sources = [Source::from_jar("app.jar").unwrap(), Source::from_jar("library.jar").unwrap()];
+///
+/// // Use at most 4 threads, and read at most 32 classes ahead of parsing.
+/// let scan = Scan::new().max_threads(4).queue_depth(32).scan(&sources).unwrap();
+/// println!("{} classes", scan.len());
+///
+/// // Or share an application's existing pool.
+/// let pool = rayon::ThreadPoolBuilder::new().build().unwrap();
+/// Scan::new().pool(&pool).for_each_class(&sources[0], |class| {
+///     println!("{}", class.path.as_str());
+///     Ok(())
+/// }).unwrap();
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Scan<'p> {
+    pool:           Option<&'p ThreadPool>,
+    max_threads:    usize,
+    queue_depth:    usize,
+    options:        ParseOptions,
+}
+
+impl<'p> Default for Scan<'p> {
+    fn default() -> Self {
+        Self {
+            pool:           None,
+            max_threads:    0,
+            queue_depth:    64,
+            options:        ParseOptions::default(),
+        }
+    }
+}
+
+impl<'p> Scan<'p> {
+    /// Scan with a new pool with one thread per core, a queue of 64 classes, and default parse options.
+    pub fn new() -> Self { Self::default() }
+
+    /// Parse on an existing thread pool instead of creating a new one for each scan.
+    pub fn pool(&mut self, pool: &'p ThreadPool) -> &mut Self {
+        self.pool = Some(pool);
+        self
+    }
+
+    /// Parse on at most `threads` threads.  `0` (the default) means one per core, or every thread of [pool](#method.pool).
+    pub fn max_threads(&mut self, threads: usize) -> &mut Self {
+        self.max_threads = threads;
+        self
+    }
+
+    /// Allow at most `depth` (minimum 1) classes to be read but not yet parsed, bounding memory use.
+    pub fn queue_depth(&mut self, depth: usize) -> &mut Self {
+        self.queue_depth = depth.max(1);
+        self
+    }
+
+    /// Parse classes with `options` instead of the default [ParseOptions](../struct.ParseOptions.html).
+    pub fn parse_options(&mut self, options: ParseOptions) -> &mut Self {
+        self.options = options;
+        self
+    }
+
+    /// Parse every class of `source` in parallel, calling `f` (from pool threads, in no particular order) with each.
+    ///
+    /// Stops at the first error from reading, parsing, or `f`, and returns it.
+    pub fn for_each_class(&self, source: &Source, f: impl Fn(Class) -> io::Result<()> + Sync) -> io::Result<()> {
+        let owned;
+        let pool = match self.pool {
+            Some(pool) => pool,
+            None => {
+                owned = ThreadPoolBuilder::new().num_threads(self.max_threads).build().map_err(|err| Error::new(ErrorKind::Other, err))?;
+                &owned
+            },
+        };
+        let workers = match self.max_threads {
+            0       => pool.current_num_threads(),
+            max     => pool.current_num_threads().min(max),
+        };
+
+        let (send, recv) = mpsc::sync_channel::<(String, Vec<u8>)>(self.queue_depth);
+        let recv    = Mutex::new(recv);
+        let failed  = AtomicBool::new(false);
+        let error   = Mutex::new(None);
+        let fail    = |err: Error| {
+            error.lock().unwrap().get_or_insert(err);
+            failed.store(true, Ordering::SeqCst);
+        };
+
+        pool.in_place_scope(|scope|{
+            for _ in 0..workers {
+                scope.spawn(|_|{
+                    loop {
+                        let next = recv.lock().unwrap().recv();
+                        let (path, bytes) = match next {
+                            Ok(next)    => next,
+                            Err(_)      => return, // Reading finished
+                        };
+                        if failed.load(Ordering::SeqCst) { continue; } // Keep draining so reading doesn't block forever
+                        let result = Class::read_with(&mut &bytes[..], &self.options)
+                            .map_err(|err| Error::new(err.kind(), format!("Failed to parse {}: {}", path, err)))
+                            .and_then(&f);
+                        if let Err(err) = result { fail(err); }
+                    }
+                });
+            }
+
+            let read = source.for_each_class(|path|{
+                if failed.load(Ordering::SeqCst) { return Err(Error::new(ErrorKind::Other, "Scan cancelled")); }
+                let bytes = source.read_class_bytes(&path)?;
+                send.send((path, bytes)).map_err(|_| Error::new(ErrorKind::Other, "Scan workers exited early"))
+            });
+            drop(send);
+            if let Err(err) = read { fail(err); }
+        });
+
+        match error.into_inner().unwrap() {
+            Some(err)   => Err(err),
+            None        => Ok(()),
+        }
+    }
+
+    /// Parse every class of `sources` in parallel into a [ScanResult].  As with [ScanResult::scan], the first
+    /// source containing a given class wins, although classes are added in no particular order.
+    ///
+    /// [ScanResult]:       ../scan/struct.ScanResult.html
+    /// [ScanResult::scan]: ../scan/struct.ScanResult.html#method.scan
+    pub fn scan(&self, sources: &[Source]) -> io::Result<ScanResult> {
+        let result = Mutex::new(ScanResult::new());
+        for source in sources.iter() {
+            self.for_each_class(source, |class| result.lock().unwrap().add_class(&class))?;
+        }
+        Ok(result.into_inner().unwrap())
+    }
+}
+
+#[cfg(feature = "jar")]
+#[test] fn parallel_scan() {
+    let mut entries = Vec::new();
+    for i in 0..100 {
+        let path = format!("com/example/Class{}", i);
+        entries.push((format!("{}.class", path), testing::ClassFile::new(&path, Some("java/lang/Object")).to_bytes()));
+    }
+    let entries = entries.iter().map(|(n, b)| (n.as_str(), b.clone())).collect::<Vec<_>>();
+    let source = Source::from_jar(testing::write_jar("parallel-scan", &entries[..])).unwrap();
+
+    let serial = ScanResult::scan(std::slice::from_ref(&source)).unwrap();
+    for &(threads, depth) in [(0, 64), (1, 1), (3, 2)].iter() {
+        let parallel = Scan::new().max_threads(threads).queue_depth(depth).scan(std::slice::from_ref(&source)).unwrap();
+        assert_eq!(parallel.len(), serial.len());
+        assert!(serial.classes().all(|c| parallel.class(c.path().as_str()).is_some()));
+    }
+
+    let pool = ThreadPoolBuilder::new().num_threads(2).build().unwrap();
+    let seen = Mutex::new(0);
+    Scan::new().pool(&pool).for_each_class(&source, |_| { *seen.lock().unwrap() += 1; Ok(()) }).unwrap();
+    assert_eq!(*seen.lock().unwrap(), 100);
+
+    let err = Scan::new().queue_depth(1).for_each_class(&source, |class| {
+        if class.path.as_str() == "com/example/Class50" { Err(Error::new(ErrorKind::InvalidData, "rejected")) } else { Ok(()) }
+    }).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+    let mut bad = entries.clone();
+    bad.push(("com/example/Broken.class", vec![0xCA, 0xFE]));
+    let source = Source::from_jar(testing::write_jar("parallel-scan-broken", &bad[..])).unwrap();
+    assert!(Scan::new().for_each_class(&source, |_| Ok(())).unwrap_err().to_string().contains("com/example/Broken"));
+}
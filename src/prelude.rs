@@ -0,0 +1,27 @@
+//! The commonly used types of jreflection, for glob importing.
+//!
+//! ```no_run
+//! # #[cfg(feature = "jar")] {
+//! use jreflection::prelude::*;
+//!
+//! let mut classpath = ClassPath::new();
+//! classpath.push(Source::from_jar("app.jar").unwrap());
+//! let class = classpath.read_class("com/example/Main").unwrap();
+//! assert!(class.flags.contains(ClassFlags::PUBLIC));
+//! for method in class.methods.iter() {
+//!     let descriptor : MethodDescriptor = method.descriptor();
+//!     if method.flags.contains(MethodFlags::STATIC) && descriptor.return_type() == FieldDescriptor::Single(BasicType::Void) {
+//!         println!("{}{}", method.name, descriptor.as_str());
+//!     }
+//! }
+//! # }
+//! ```
+//!
+//! Per-structure types such as each module's `Flags` and `Descriptor`s are renamed (e.g. to `ClassFlags`) to keep
+//! them apart.  Analyses and rewrites are deliberately left out:  import those by module.  See the
+//! [crate's layout](../index.html#layout) for how the rest of the API is organized.
+
+pub use crate::{CancellationToken, Class, ClassPath, Field, Hierarchy, IoStrategy, Method, ParseOptions, Source};
+pub use crate::class::{Flags as ClassFlags, Id, IdBuf};
+pub use crate::field::{BasicType, Descriptor as FieldDescriptor, Flags as FieldFlags};
+pub use crate::method::{Descriptor as MethodDescriptor, Flags as MethodFlags};
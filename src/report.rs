@@ -0,0 +1,355 @@
+//! A small placeholder engine for customizable HTML/Markdown reports.
+//!
+//! Templates are plain text containing placeholders:
+//!
+//! | Syntax                | Meaning                                                                           |
+//! | --------------------- | --------------------------------------------------------------------------------- |
+//! | `{NAME}`              | The value of `NAME`, escaped for the output format.                               |
+//! | `{!NAME}`             | The value of `NAME`, as-is (for values that are already HTML, Markdown, etc.)     |
+//! | `{#ROWS}...{/ROWS}`   | The enclosed template, repeated for each row of `ROWS`.                           |
+//!
+//! Names are `UPPER_SNAKE_CASE`.  Anything else in braces - such as CSS rules - is left untouched.
+//!
+//! Reports covering an entire JDK can be split into one page per package with [Pages](struct.Pages.html).
+//!
+//! # Examples
+//!
+//! ```
+//! # use jreflection::report::*;
+//! let template = Template::parse("<h1>{TITLE}</h1>\n<ul>\n{#CLASSES}<li>{NAME} ({COUNT} methods)</li>\n{/CLASSES}</ul>").unwrap();
+//!
+//! let mut values = Values::new();
+//! values.set("TITLE", "Classes & Interfaces");
+//! values.row("CLASSES").set("NAME", "java/util/List<E>").set("COUNT", 42);
+//! values.row("CLASSES").set("NAME", "java/lang/Object").set("COUNT", 12);
+//!
+//! assert_eq!(template.render(&values, Escape::Html).unwrap(), "\
+//! <h1>Classes &amp; Interfaces</h1>
+//! <ul>
+//! <li>java/util/List&lt;E&gt; (42 methods)</li>
+//! <li>java/lang/Object (12 methods)</li>
+//! </ul>");
+//! ```
+
+use std::collections::*;
+use std::fmt::Display;
+use std::io::{self, BufWriter, Error, ErrorKind, Write};
+use std::path::Path;
+
+
+
+/// A parsed template.  See the [module documentation](index.html) for the syntax.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Part {
+    Text(String),
+    Value { name: String, raw: bool },
+    Rows { name: String, template: Template },
+}
+
+/// The values to fill a [Template](struct.Template.html)'s placeholders with.
+///
+/// Placeholders within a row that the row doesn't set are looked up in the enclosing rows, and then the top level values.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Values {
+    values: BTreeMap<String, String>,
+    rows:   BTreeMap<String, Vec<Values>>,
+}
+
+/// How to escape values substituted for placeholders.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Escape {
+    /// Substitute values as-is.
+    None,
+
+    /// Escape `&`, `<`, `>`, `"`, and `'` as HTML entities.
+    Html,
+
+    /// Backslash escape characters with special meaning in Markdown, including `|` for tables.
+    Markdown,
+}
+
+/// A report split into one page per package, plus an index page linking to them.
+///
+/// Each page is written to `packages/{dotted.package}.{extension}` (or `packages/default.{extension}` for the default
+/// package), and the index to `index.{extension}`.  Output is sorted by package, so regenerated reports diff cleanly.
+///
+/// Each page's values additionally get, unless already set:
+///
+/// | Placeholder   | Value                                             |
+/// | ------------- | ------------------------------------------------- |
+/// | `{PACKAGE}`   | The dotted package name, such as `java.util`.     |
+/// | `{INDEX}`     | The relative path of the index page.              |
+///
+/// The index page gets a `{TOTAL}` count and a `{#PACKAGES}` row per page, with:
+///
+/// | Placeholder   | Value                                                                 |
+/// | ------------- | --------------------------------------------------------------------- |
+/// | `{PACKAGE}`   | The dotted package name, such as `java.util`.                         |
+/// | `{LINK}`      | The relative path of the package's page.                              |
+/// | `{COUNT}`     | The number of rows in the page's sections, such as classes.           |
+///
+/// # Examples
+///
+/// ```no_run
+/// # use jreflection::report::*;
+/// # fn main() -> std::io::Result<()> {
+/// let index = Template::parse("# Packages\n{#PACKAGES}* [{PACKAGE}]({LINK}) ({COUNT} classes)\n{/PACKAGES}")?;
+/// let page  = Template::parse("# {PACKAGE}\n[Index]({INDEX})\n{#CLASSES}* {NAME}\n{/CLASSES}")?;
+///
+/// let mut pages = Pages::new();
+/// pages.page("java/util").row("CLASSES").set("NAME", "List");
+/// pages.page("java/lang").row("CLASSES").set("NAME", "Object");
+/// pages.write("target/report", &index, &page, "md", Escape::Markdown)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Pages {
+    pages: BTreeMap<String, Values>,
+}
+
+impl Pages {
+    /// Create an empty set of pages.
+    pub fn new() -> Self { Self::default() }
+
+    /// The values of the page for `package` (JNI `java/util` or dotted `java.util` style), creating it if necessary.
+    pub fn page(&mut self, package: &str) -> &mut Values { self.pages.entry(package.replace('/', ".")).or_default() }
+
+    /// The path of the page for `package`, relative to the index page.
+    pub fn link(package: &str, extension: &str) -> String {
+        let package = package.replace('/', ".");
+        format!("packages/{}.{}", if package.is_empty() { "default" } else { package.as_str() }, extension)
+    }
+
+    /// Render the index page to a string.  Fails with [ErrorKind::InvalidInput] if `index` needs values that aren't provided.
+    ///
+    /// [ErrorKind::InvalidInput]:  https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.InvalidInput
+    pub fn render_index(&self, index: &Template, extension: &str, escape: Escape) -> io::Result<String> {
+        let mut values = Values::new();
+        let mut total = 0;
+        let rows = values.rows("PACKAGES");
+        for (package, page) in self.pages.iter() {
+            let count = page.rows.values().map(|rows| rows.len()).sum::<usize>();
+            total += count;
+            let mut row = Values::new();
+            row.set("PACKAGE", package).set("LINK", Self::link(package, extension)).set("COUNT", count);
+            rows.push(row);
+        }
+        values.set("TOTAL", total);
+        index.render(&values, escape)
+    }
+
+    /// Render the page for each package to a string, by dotted package name.
+    pub fn render_pages(&self, page: &Template, extension: &str, escape: Escape) -> io::Result<BTreeMap<String, String>> {
+        let mut pages = BTreeMap::new();
+        for (package, values) in self.pages.iter() {
+            let mut values = values.clone();
+            values.values.entry("PACKAGE".to_owned()).or_insert_with(|| package.clone());
+            values.values.entry("INDEX".to_owned()).or_insert_with(|| format!("../index.{}", extension));
+            pages.insert(package.clone(), page.render(&values, escape)?);
+        }
+        Ok(pages)
+    }
+
+    /// Write the index and every page to `dir`, creating directories as needed.
+    pub fn write(&self, dir: impl AsRef<Path>, index: &Template, page: &Template, extension: &str, escape: Escape) -> io::Result<()> {
+        let dir = dir.as_ref();
+        let index = self.render_index(index, extension, escape)?;
+        let pages = self.render_pages(page, extension, escape)?; // Render everything before writing anything
+        std::fs::create_dir_all(dir.join("packages"))?;
+        for (package, text) in pages.iter() {
+            BufWriter::new(std::fs::File::create(dir.join(Self::link(package, extension)))?).write_all(text.as_bytes())?;
+        }
+        BufWriter::new(std::fs::File::create(dir.join(format!("index.{}", extension)))?).write_all(index.as_bytes())
+    }
+}
+
+impl Values {
+    /// Create an empty set of values.
+    pub fn new() -> Self { Self::default() }
+
+    /// Set the value of placeholder `name`.
+    pub fn set(&mut self, name: &str, value: impl Display) -> &mut Self {
+        self.values.insert(name.to_owned(), value.to_string());
+        self
+    }
+
+    /// Append a new row to `{#name}...{/name}`, returning it for its values to be [set](#method.set).
+    pub fn row(&mut self, name: &str) -> &mut Values {
+        let rows = self.rows.entry(name.to_owned()).or_default();
+        rows.push(Values::new());
+        rows.last_mut().unwrap()
+    }
+
+    /// The rows of `{#name}...{/name}`, for when there might be none (which would otherwise be an error when rendering.)
+    pub fn rows(&mut self, name: &str) -> &mut Vec<Values> { self.rows.entry(name.to_owned()).or_default() }
+}
+
+impl Template {
+    /// Parse a template.  Fails with [ErrorKind::InvalidData] if sections aren't properly nested and closed.
+    ///
+    /// [ErrorKind::InvalidData]:  https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.InvalidData
+    pub fn parse(text: &str) -> io::Result<Self> {
+        let mut rest = text;
+        parse(&mut rest, None)
+    }
+
+    /// The names of every placeholder and section in this template, including nested ones.
+    pub fn placeholders(&self) -> BTreeSet<&str> {
+        let mut names = BTreeSet::new();
+        for part in self.parts.iter() {
+            match part {
+                Part::Text(_)                   => {},
+                Part::Value { name, .. }        => { names.insert(name.as_str()); },
+                Part::Rows { name, template }   => { names.insert(name.as_str()); names.extend(template.placeholders()); },
+            }
+        }
+        names
+    }
+
+    /// Render this template to a string.  Fails with [ErrorKind::InvalidInput] if `values` is missing a placeholder or section.
+    ///
+    /// [ErrorKind::InvalidInput]:  https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.InvalidInput
+    pub fn render(&self, values: &Values, escape: Escape) -> io::Result<String> {
+        let mut out = String::new();
+        self.render_into(&mut out, &mut vec![values], escape)?;
+        Ok(out)
+    }
+
+    /// Render this template to `write`.  Fails with [ErrorKind::InvalidInput] if `values` is missing a placeholder or section.
+    ///
+    /// [ErrorKind::InvalidInput]:  https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.InvalidInput
+    pub fn write(&self, write: &mut impl Write, values: &Values, escape: Escape) -> io::Result<()> {
+        write.write_all(self.render(values, escape)?.as_bytes())
+    }
+
+    fn render_into<'v>(&self, out: &mut String, scopes: &mut Vec<&'v Values>, escape: Escape) -> io::Result<()> {
+        for part in self.parts.iter() {
+            match part {
+                Part::Text(text) => out.push_str(text),
+                Part::Value { name, raw } => {
+                    let value = match scopes.iter().rev().filter_map(|s| s.values.get(name)).next() {
+                        Some(value) => value,
+                        None        => return Err(Error::new(ErrorKind::InvalidInput, format!("No value for template placeholder {{{}}}", name))),
+                    };
+                    if *raw { out.push_str(value) } else { escape_into(out, value, escape) }
+                },
+                Part::Rows { name, template } => {
+                    let rows : &'v [Values] = match scopes.iter().rev().filter_map(|s| s.rows.get(name)).next() {
+                        Some(rows)  => rows,
+                        None        => return Err(Error::new(ErrorKind::InvalidInput, format!("No rows for template section {{#{}}}", name))),
+                    };
+                    for row in rows.iter() {
+                        scopes.push(row);
+                        let result = template.render_into(out, scopes, escape);
+                        scopes.pop();
+                        result?;
+                    }
+                },
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parse until the end of `rest`, or the closing `{/section}`.
+fn parse(rest: &mut &str, section: Option<&str>) -> io::Result<Template> {
+    let mut parts = Vec::new();
+    let mut text = String::new();
+    while let Some(open) = rest.find('{') {
+        text.push_str(&rest[..open]);
+        *rest = &rest[open..];
+        let (sigil, name, len) = match tag(rest) {
+            Some(tag)   => tag,
+            None        => { text.push('{'); *rest = &rest[1..]; continue; },
+        };
+        *rest = &rest[len..];
+        if !text.is_empty() { parts.push(Part::Text(std::mem::replace(&mut text, String::new()))); }
+        match sigil {
+            None        => parts.push(Part::Value { name: name.to_owned(), raw: false }),
+            Some('!')   => parts.push(Part::Value { name: name.to_owned(), raw: true }),
+            Some('#')   => parts.push(Part::Rows { name: name.to_owned(), template: parse(rest, Some(name))? }),
+            _ /* '/' */ => match section {
+                Some(section) if section == name    => return Ok(Template { parts }),
+                Some(section)                       => return io_data_err!("Template section {{#{}}} closed by {{/{}}}", section, name),
+                None                                => return io_data_err!("Template section {{/{}}} closed without being opened", name),
+            },
+        }
+    }
+    if let Some(section) = section { return io_data_err!("Template section {{#{}}} never closed", section); }
+    text.push_str(rest);
+    *rest = "";
+    if !text.is_empty() { parts.push(Part::Text(text)); }
+    Ok(Template { parts })
+}
+
+/// Parse `{NAME}`, `{!NAME}`, `{#NAME}`, or `{/NAME}` at the start of `text` into (sigil, name, length).
+fn tag(text: &str) -> Option<(Option<char>, &str, usize)> {
+    let inner = &text[1..];
+    let sigil = inner.chars().next().filter(|ch| "!#/".contains(*ch));
+    let start = 1 + sigil.map(|_| 1).unwrap_or(0);
+    let end = start + text[start..].find('}')?;
+    let name = &text[start..end];
+    let valid = name.starts_with(|ch: char| ch.is_ascii_uppercase() || ch == '_')
+        && name.chars().all(|ch| ch.is_ascii_uppercase() || ch.is_ascii_digit() || ch == '_');
+    if valid { Some((sigil, name, end + 1)) } else { None }
+}
+
+fn escape_into(out: &mut String, value: &str, escape: Escape) {
+    for ch in value.chars() {
+        match (escape, ch) {
+            (Escape::Html, '&')     => out.push_str("&amp;"),
+            (Escape::Html, '<')     => out.push_str("&lt;"),
+            (Escape::Html, '>')     => out.push_str("&gt;"),
+            (Escape::Html, '"')     => out.push_str("&quot;"),
+            (Escape::Html, '\'')    => out.push_str("&#39;"),
+            (Escape::Markdown, '\\') | (Escape::Markdown, '`') | (Escape::Markdown, '*') | (Escape::Markdown, '_')
+            | (Escape::Markdown, '[') | (Escape::Markdown, ']') | (Escape::Markdown, '<') | (Escape::Markdown, '>')
+            | (Escape::Markdown, '|') | (Escape::Markdown, '#') => { out.push('\\'); out.push(ch); },
+            _                       => out.push(ch),
+        }
+    }
+}
+
+#[test] fn templates() {
+    let template = Template::parse("table { width: 100%; }\n{#PACKAGES}## {NAME}\n{#CLASSES}| {NAME} | {PACKAGE_KIND} | {!LINK} |\n{/CLASSES}{/PACKAGES}{ {lower} {}").unwrap();
+    assert_eq!(template.placeholders().into_iter().collect::<Vec<_>>(), &["CLASSES", "LINK", "NAME", "PACKAGES", "PACKAGE_KIND"]);
+
+    let mut values = Values::new();
+    values.set("PACKAGE_KIND", "api");
+    let util = values.row("PACKAGES");
+    util.set("NAME", "java_util");
+    util.row("CLASSES").set("NAME", "Map|Entry").set("LINK", "[Map](#map)");
+    util.row("CLASSES").set("NAME", "List").set("LINK", "").set("PACKAGE_KIND", "core");
+    values.row("PACKAGES").set("NAME", "empty").rows("CLASSES");
+
+    assert_eq!(template.render(&values, Escape::Markdown).unwrap(), "table { width: 100%; }\n## java\\_util\n| Map\\|Entry | api | [Map](#map) |\n| List | core |  |\n## empty\n{ {lower} {}");
+    assert_eq!(Template::parse("{X}").unwrap().render(&Values::new(), Escape::None).unwrap_err().kind(), ErrorKind::InvalidInput);
+    assert_eq!(Template::parse("{#X}{/X}").unwrap().render(&Values::new(), Escape::None).unwrap_err().kind(), ErrorKind::InvalidInput);
+
+    for invalid in ["{#A}", "{/A}", "{#A}{/B}", "{#A}{#B}{/A}{/B}"].iter() {
+        assert_eq!(Template::parse(invalid).unwrap_err().kind(), ErrorKind::InvalidData, "{}", invalid);
+    }
+}
+
+#[test] fn pages() {
+    let index = Template::parse("{TOTAL}\n{#PACKAGES}{PACKAGE} {LINK} {COUNT}\n{/PACKAGES}").unwrap();
+    let page  = Template::parse("{PACKAGE} {INDEX}:{#CLASSES} {NAME}{/CLASSES}").unwrap();
+
+    let mut pages = Pages::new();
+    pages.page("java/util").row("CLASSES").set("NAME", "List");
+    pages.page("java.util").row("CLASSES").set("NAME", "Map");
+    pages.page("").row("CLASSES").set("NAME", "Main");
+    pages.page("java/lang").rows("CLASSES");
+
+    assert_eq!(pages.render_index(&index, "md", Escape::None).unwrap(), "3\n packages/default.md 1\njava.lang packages/java.lang.md 0\njava.util packages/java.util.md 2\n");
+    let rendered = pages.render_pages(&page, "md", Escape::None).unwrap();
+    assert_eq!(rendered["java.util"], "java.util ../index.md: List Map");
+    assert_eq!(rendered["java.lang"], "java.lang ../index.md:");
+    assert_eq!(Pages::link("", "html"), "packages/default.html");
+}
@@ -0,0 +1,161 @@
+//! Which versions of which source families (JDKs, Android platforms, etc.) a method's bytecode requires.
+
+use crate::Method;
+use crate::availability::{Availability, Versions};
+use crate::code::Reference;
+#[cfg(test)] use crate::{class, method, testing, Class, ParseOptions};
+
+use std::collections::*;
+use std::io::{self, Error, ErrorKind};
+
+
+
+/// The minimum versions required by a method, as computed by [of].
+///
+/// [of]:   fn.of.html
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Requirements {
+    /// The minimum version of each family the method requires, e.g. `{"android": 26}`.
+    pub minimum:        BTreeMap<String, u32>,
+
+    /// The references responsible for each family's `minimum`.
+    pub limiting:       BTreeMap<String, BTreeSet<Reference>>,
+
+    /// References which are available in some families, but not in any version of these families.
+    pub unavailable:    BTreeMap<String, BTreeSet<Reference>>,
+}
+
+impl Requirements {
+    /// The minimum version of `family` required, if the method references anything `family` was recorded as having.
+    pub fn min(&self, family: &str) -> Option<u32> { self.minimum.get(family).copied() }
+
+    /// Is every reference the method makes (that's known to the availability matrix) available in some version of `family`?
+    pub fn is_available(&self, family: &str) -> bool { !self.unavailable.contains_key(family) }
+}
+
+/// Compute the minimum version of each family required by every class, field, and method referenced by `method`'s code.
+///
+/// References to classes unknown to `availability` (such as the method's own application classes) are ignored.  Members
+/// unknown to `availability` (such as inherited methods referenced through a subclass) fall back on their class's
+/// availability.  Abstract and native methods have no requirements.
+///
+/// `method` must have been read with [ParseOptions::retain_code](../struct.ParseOptions.html#method.retain_code), or
+/// this will fail with [ErrorKind::InvalidInput].
+///
+/// [ErrorKind::InvalidInput]:  https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.InvalidInput
+///
+/// # Examples
+///
+/// ```no_run
+/// # use jreflection::*;
+/// # use jreflection::availability::Availability;
+/// # #[cfg(feature = "jar")] {
+/// let mut availability = Availability::new();
+/// for platform in android::discover_sdk().unwrap().platforms().unwrap() {
+///     availability.add_source("android", platform.api_level, &platform.source().unwrap()).unwrap();
+/// }
+///
+/// let mut options = ParseOptions::new();
+/// options.retain_code(true);
+/// let app = Source::from_jar("app.jar").unwrap();
+/// let main = app.read_class_with("com/example/Main", &options).unwrap();
+/// for method in main.methods.iter() {
+///     let requirements = requirements::of(method, &availability).unwrap();
+///     println!("{} requires API {:?}", method.name, requirements.min("android"));
+/// }
+/// # }
+/// ```
+pub fn of(method: &Method, availability: &Availability) -> io::Result<Requirements> {
+    let mut requirements = Requirements::default();
+    let code = match method.code() {
+        Some(code) => code,
+        None if method.is_abstract() || method.is_native() => return Ok(requirements),
+        None => return Err(Error::new(ErrorKind::InvalidInput, format!("Method {}{} has no retained code", method.name, method.descriptor_str()))),
+    };
+
+    let mut known = Vec::<(Reference, &Versions)>::new();
+    for reference in code.references()?.into_iter().collect::<BTreeSet<_>>() {
+        let versions = match &reference {
+            Reference::Class(class) => availability.class(class),
+            Reference::Field  { class, name, descriptor } |
+            Reference::Method { class, name, descriptor } => availability.lookup(class, name, descriptor).or_else(|| availability.class(class)),
+        };
+        if let Some(versions) = versions { known.push((reference, versions)); }
+    }
+
+    let families = known.iter().flat_map(|(_, versions)| versions.families()).map(|f| f.to_owned()).collect::<BTreeSet<_>>();
+    for family in families {
+        for (reference, versions) in known.iter() {
+            let min = match versions.min(&family) {
+                Some(min) => min,
+                None => {
+                    requirements.unavailable.entry(family.clone()).or_default().insert(reference.clone());
+                    continue;
+                },
+            };
+            let minimum = requirements.minimum.entry(family.clone()).or_insert(min);
+            let limiting = requirements.limiting.entry(family.clone()).or_default();
+            if min > *minimum {
+                *minimum = min;
+                limiting.clear();
+            }
+            if min == *minimum { limiting.insert(reference.clone()); }
+        }
+    }
+
+    Ok(requirements)
+}
+
+#[test] fn method_requirements() {
+    let mut c = testing::ClassFile::new("com/example/Main", Some("java/lang/Object"));
+    let init    = c.methodref("java/lang/Object", "<init>", "()V");
+    let now     = c.methodref("java/time/Instant", "now", "()Ljava/time/Instant;");
+    let helper  = c.methodref("com/example/Helper", "help", "()V");
+    let max     = c.fieldref("java/lang/Integer", "MAX_VALUE", "I");
+    let bytecode = [
+        vec![0x2A, 0xB7], testing::be16(init),  // aload_0; invokespecial Object.<init>
+        vec![0xB8], testing::be16(now),         // invokestatic Instant.now
+        vec![0x57],                             // pop
+        vec![0xB8], testing::be16(helper),      // invokestatic Helper.help
+        vec![0xB2], testing::be16(max),         // getstatic Integer.MAX_VALUE
+        vec![0x57, 0xB1],                       // pop; return
+    ].concat();
+    let code = [testing::be16(1), testing::be16(1), testing::be32(bytecode.len() as u32), bytecode, testing::be16(0), testing::be16(0)].concat();
+    let code = c.attribute("Code", &code);
+    c.method(0x0001, "<init>", "()V", &[code]);
+    c.method(0x0401, "run", "()V", &[]);
+
+    let mut options = ParseOptions::new();
+    options.retain_code(true);
+    let main = Class::read_with(&mut &c.to_bytes()[..], &options).unwrap();
+
+    let api = |path: &str, methods: &[(&str, &str)]| Class {
+        path:       class::IdBuf::new(path.to_owned()),
+        methods:    methods.iter().map(|(n, d)| Method::new(method::Flags::PUBLIC, (*n).to_owned(), (*d).to_owned()).unwrap()).collect(),
+        .. Default::default()
+    };
+    let object  = api("java/lang/Object", &[("<init>", "()V")]);
+    let integer = api("java/lang/Integer", &[]); // MAX_VALUE omitted to exercise the class fallback
+    let instant = api("java/time/Instant", &[("now", "()Ljava/time/Instant;")]);
+
+    let mut availability = Availability::new();
+    for version in 1 ..= 30 { availability.add_class("android", version, &object); availability.add_class("android", version, &integer); }
+    for version in 26 ..= 30 { availability.add_class("android", version, &instant); }
+    for version in 1 ..= 8 { availability.add_class("java", version, &object); availability.add_class("java", version, &integer); }
+
+    let requirements = of(&main.methods[0], &availability).unwrap();
+    assert_eq!(requirements.min("android"), Some(26));
+    assert_eq!(requirements.min("java"), Some(1));
+    assert_eq!(requirements.min("ios"), None);
+    let now = Reference::Method { class: "java/time/Instant".into(), name: "now".into(), descriptor: "()Ljava/time/Instant;".into() };
+    assert_eq!(requirements.limiting["android"].iter().collect::<Vec<_>>(), &[&now]);
+    assert_eq!(requirements.limiting["java"].len(), 2);
+    assert!( requirements.is_available("android"));
+    assert!(!requirements.is_available("java"));
+    assert_eq!(requirements.unavailable["java"].iter().collect::<Vec<_>>(), &[&now]);
+
+    assert_eq!(of(&main.methods[1], &availability).unwrap(), Requirements::default());
+
+    let main = Class::read(&mut &c.to_bytes()[..]).unwrap();
+    assert_eq!(of(&main.methods[0], &availability).unwrap_err().kind(), ErrorKind::InvalidInput);
+}
@@ -0,0 +1,530 @@
+//! Compact, arena-allocated snapshots of many classes, for read-only bulk analysis (e.g. of an entire JDK.)
+//!
+//! A [Class] owns a `String` per name, descriptor, and signature, and a `Vec` per member list - hundreds of thousands of
+//! small allocations for a whole JDK, many of them duplicates (`"()V"`, `"Ljava/lang/String;"`, ...)  A [ScanResult]
+//! instead interns every string into a single growing buffer, and stores every class, field, and method in one flat
+//! `Vec` each, borrowing from them on access.
+//!
+//! [ScanResult::scan] and [add_source] never build a [Class] either:  they walk each class's constant pool in place,
+//! intern names, descriptors, and signatures straight out of it, and skip every other attribute undecoded.  Besides
+//! the arenas themselves, that leaves a class's bytes and an index of its constant pool as the only allocations per
+//! class (plus the rare string that needs converting from modified UTF8.)  [add_class] remains for classes that were
+//! already parsed anyways.
+//!
+//! [Class]:            ../struct.Class.html
+//! [ScanResult]:       struct.ScanResult.html
+//! [ScanResult::scan]: struct.ScanResult.html#method.scan
+//! [add_source]:       struct.ScanResult.html#method.add_source
+//! [add_class]:        struct.ScanResult.html#method.add_class
+
+use crate::{class, constants, field, method, names, Class, Source};
+use crate::class::Id;
+use crate::io::be::*;
+#[cfg(test)] use crate::testing;
+
+use std::borrow::Cow;
+use std::collections::*;
+use std::io::{self, Error, ErrorKind};
+use std::ops::Range;
+
+
+
+/// An interned string:  a range of [ScanResult]'s string arena.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Str { start: u32, end: u32 }
+
+#[derive(Clone, Debug)]
+struct ClassEntry {
+    flags:      class::Flags,
+    path:       Str,
+    super_path: Option<Str>,
+    interfaces: Range<u32>, // into ScanResult::interfaces
+    fields:     Range<u32>, // into ScanResult::members
+    methods:    Range<u32>, // into ScanResult::members
+    deprecated: bool,
+}
+
+#[derive(Clone, Debug)]
+struct MemberEntry {
+    flags:      u16,
+    name:       Str,
+    descriptor: Str,
+    signature:  Option<Str>,
+    deprecated: bool,
+}
+
+/// Every class of one or more sources, with all strings and lists allocated from a few shared arenas.
+///
+/// If the same class is added more than once (e.g. from overlapping sources), the first copy wins, like a classpath.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use jreflection::*;
+/// let jdk = Source::from_jdk_dir(r"C:\Program Files\AdoptOpenJDK\jdk-11.0.6.10-hotspot").unwrap();
+/// let scan = scan::ScanResult::scan(&[jdk]).unwrap();
+/// let methods : usize = scan.classes().map(|c| c.methods().count()).sum();
+/// println!("{} classes, {} methods, {} bytes of strings", scan.len(), methods, scan.string_bytes());
+/// let object = scan.class("java/lang/Object").unwrap();
+/// assert!(object.super_path().is_none());
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ScanResult {
+    strings:    String,
+    interned:   HashMap<u64, Str>,      // string hash -> first string with that hash
+    classes:    Vec<ClassEntry>,
+    by_path:    HashMap<u64, u32>,      // path hash -> index into classes
+    by_simple:  HashMap<u64, Vec<u32>>, // simple name hash -> indices into classes
+    interfaces: Vec<Str>,
+    members:    Vec<MemberEntry>,
+}
+
+impl ScanResult {
+    /// Create an empty scan.
+    pub fn new() -> Self { Self::default() }
+
+    /// Scan every class of every source in `sources`.
+    pub fn scan(sources: &[Source]) -> io::Result<Self> {
+        let mut scan = Self::new();
+        for source in sources.iter() { scan.add_source(source)?; }
+        Ok(scan)
+    }
+
+    /// Add every class of `source` not already present.
+    pub fn add_source(&mut self, source: &Source) -> io::Result<()> {
+        source.for_each_class(|path|{
+            if self.class(&path).is_some() { return Ok(()); }
+            let bytes = source.read_class_bytes(&path)?;
+            self.add_class_bytes(&bytes[..])
+        })
+    }
+
+    /// Add the class in the `.class` file `bytes`, unless a class with the same path was already added, without
+    /// building a [Class](../struct.Class.html).  Descriptors are validated, but attributes other than `Signature`
+    /// and `Deprecated` are skipped without being decoded - or even checked.
+    pub fn add_class_bytes(&mut self, bytes: &[u8]) -> io::Result<()> {
+        let interfaces  = self.interfaces.len();
+        let members     = self.members.len();
+        let result = self.add_class_bytes_impl(bytes);
+        if result.is_err() {
+            // Don't leave orphaned entries behind.  Strings interned so far stay, but they're harmless.
+            self.interfaces.truncate(interfaces);
+            self.members.truncate(members);
+        }
+        result
+    }
+
+    /// Add `class`, unless a class with the same path was already added.  Errors if the arena exceeds 4 GB.
+    pub fn add_class(&mut self, class: &Class) -> io::Result<()> {
+        if self.class(class.path.as_str()).is_some() { return Ok(()); }
+
+        let path        = self.intern(class.path.as_str())?;
+        let super_path  = match class.super_path.as_ref() { Some(s) => Some(self.intern(s.as_str())?), None => None };
+
+        let interfaces_start = self.interfaces.len() as u32;
+        for interface in class.interfaces.iter() {
+            let interface = self.intern(interface.as_str())?;
+            self.interfaces.push(interface);
+        }
+
+        let fields_start = self.members.len() as u32;
+        for field in class.fields.iter() {
+            let member = self.member(field.flags.bits(), &field.name, field.descriptor_str(), field.signature_str(), field.deprecated)?;
+            self.members.push(member);
+        }
+        let methods_start = self.members.len() as u32;
+        for method in class.methods.iter() {
+            let member = self.member(method.flags.bits(), &method.name, method.descriptor_str(), method.signature_str(), method.deprecated)?;
+            self.members.push(member);
+        }
+
+        let index = self.classes.len() as u32;
+        self.classes.push(ClassEntry {
+            flags:      class.flags,
+            path,
+            super_path,
+            interfaces: interfaces_start .. self.interfaces.len() as u32,
+            fields:     fields_start .. methods_start,
+            methods:    methods_start .. self.members.len() as u32,
+            deprecated: class.deprecated,
+        });
+        self.by_path.entry(hash(class.path.as_str())).or_insert(index); // On the off chance of a hash collision, class() falls back on a linear search
+        if let Some(simple) = names::simple_name(class.path.as_str()) { self.by_simple.entry(hash(simple)).or_default().push(index); }
+        Ok(())
+    }
+
+    /// How many classes were scanned.
+    pub fn len(&self) -> usize { self.classes.len() }
+
+    /// Were no classes scanned?
+    pub fn is_empty(&self) -> bool { self.classes.is_empty() }
+
+    /// How many bytes of (deduplicated) string data the arena holds.
+    pub fn string_bytes(&self) -> usize { self.strings.len() }
+
+    /// Iterate over every scanned class, in the order they were added.
+    pub fn classes(&self) -> impl Iterator<Item = ScannedClass<'_>> { self.classes.iter().map(move |entry| ScannedClass { scan: self, entry }) }
+
+    /// Get a scanned class by JNI path, such as `"java/lang/Object"`.
+    pub fn class(&self, path: &str) -> Option<ScannedClass<'_>> {
+        let by_hash = self.by_path.get(&hash(path)).map(|&i| &self.classes[i as usize]).filter(|e| self.str(e.path) == path);
+        let entry = match by_hash {
+            Some(entry) => entry,
+            None if self.by_path.contains_key(&hash(path)) => self.classes.iter().find(|e| self.str(e.path) == path)?,
+            None => return None,
+        };
+        Some(ScannedClass { scan: self, entry })
+    }
+
+    /// Iterate over every scanned class with a given [simple name](../names/fn.simple_name.html), such as `"List"`.
+    pub fn classes_named<'s>(&'s self, simple_name: &'s str) -> impl Iterator<Item = ScannedClass<'s>> {
+        self.by_simple.get(&hash(simple_name)).into_iter().flat_map(|indices| indices.iter())
+            .map(move |&i| ScannedClass { scan: self, entry: &self.classes[i as usize] })
+            .filter(move |c| names::simple_name(c.path().as_str()) == Some(simple_name)) // Hash collisions
+    }
+
+    /// Resolve a [simple name](../names/fn.simple_name.html) such as `"ArrayList"` to the scanned classes it could
+    /// refer to, as an IDE's import assistance would.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use jreflection::*;
+    /// # let scan = scan::ScanResult::scan(&[Source::from_jdk_dir(r"C:\Program Files\AdoptOpenJDK\jdk-11.0.6.10-hotspot").unwrap()]).unwrap();
+    /// match scan.resolve_simple_name("List") {
+    ///     names::Resolution::NotFound         => println!("cannot find symbol: List"),
+    ///     names::Resolution::Unique(path)     => println!("import {};", path.replace('/', ".")),
+    ///     names::Resolution::Ambiguous(paths) => println!("List is ambiguous: {:?}", paths), // java/awt/List, java/util/List
+    /// }
+    /// ```
+    pub fn resolve_simple_name(&self, simple_name: &str) -> names::Resolution {
+        names::Resolution::from_candidates(self.classes_named(simple_name).map(|c| c.path().as_str().to_owned()))
+    }
+
+    fn add_class_bytes_impl(&mut self, bytes: &[u8]) -> io::Result<()> {
+        let mut raw = constants::RawConstants::new(bytes)?;
+        let mut constants = vec![(0u8, &[][..]); raw.slots() as usize];
+        for constant in &mut raw {
+            let (index, tag, info) = constant?;
+            constants[index as usize] = (tag, info);
+        }
+        let pool = RawPool(constants);
+
+        let mut read = &bytes[raw.offset()..];
+        let flags       = class::Flags::read(&mut read)?;
+        let class_path  = pool.class(read_u2(&mut read)?)?;
+        if self.class(&class_path).is_some() { return Ok(()); }
+        let super_class = read_u2(&mut read)?;
+
+        let path        = self.intern(&class_path)?;
+        let super_path  = if super_class == 0 { None } else { Some(self.intern(&pool.class(super_class)?)?) };
+
+        let interfaces_start = self.interfaces.len() as u32;
+        for _ in 0..read_u2(&mut read)? {
+            let interface = self.intern(&pool.class(read_u2(&mut read)?)?)?;
+            self.interfaces.push(interface);
+        }
+
+        let fields_start = self.members.len() as u32;
+        self.add_member_bytes(&mut read, &pool, |d| field::Descriptor::from_str(d).map(|_| ()))?;
+        let methods_start = self.members.len() as u32;
+        self.add_member_bytes(&mut read, &pool, |d| method::Descriptor::new(d).map(|_| ()))?;
+
+        let mut deprecated = false;
+        for _ in 0..read_u2(&mut read)? {
+            let (name, _info) = read_raw_attribute(&mut read, &pool)?;
+            if name == "Deprecated" { deprecated = true; }
+        }
+
+        let index = self.classes.len() as u32;
+        self.classes.push(ClassEntry {
+            flags,
+            path,
+            super_path,
+            interfaces: interfaces_start .. self.interfaces.len() as u32,
+            fields:     fields_start .. methods_start,
+            methods:    methods_start .. self.members.len() as u32,
+            deprecated,
+        });
+        self.by_path.entry(hash(&class_path)).or_insert(index);
+        if let Some(simple) = names::simple_name(&class_path) { self.by_simple.entry(hash(simple)).or_default().push(index); }
+        Ok(())
+    }
+
+    fn add_member_bytes<'b>(&mut self, read: &mut &'b [u8], pool: &RawPool<'b>, validate: impl Fn(&str) -> io::Result<()>) -> io::Result<()> {
+        for _ in 0..read_u2(read)? {
+            let flags       = read_u2(read)?;
+            let name        = pool.utf8(read_u2(read)?)?;
+            let descriptor  = pool.utf8(read_u2(read)?)?;
+            validate(&descriptor)?;
+
+            let mut signature   = None;
+            let mut deprecated  = false;
+            for _ in 0..read_u2(read)? {
+                let (name, info) = read_raw_attribute(read, pool)?;
+                match name.as_ref() {
+                    "Signature"     => { io_assert!(info.len() == 2); signature = Some(pool.utf8(u16::from_be_bytes([info[0], info[1]]))?); },
+                    "Deprecated"    => { deprecated = true; },
+                    _               => {},
+                }
+            }
+
+            let member = self.member(flags, &name, &descriptor, signature.as_ref().map(|s| s.as_ref()), deprecated)?;
+            self.members.push(member);
+        }
+        Ok(())
+    }
+
+    fn member(&mut self, flags: u16, name: &str, descriptor: &str, signature: Option<&str>, deprecated: bool) -> io::Result<MemberEntry> {
+        Ok(MemberEntry {
+            flags,
+            name:       self.intern(name)?,
+            descriptor: self.intern(descriptor)?,
+            signature:  match signature { Some(s) => Some(self.intern(s)?), None => None },
+            deprecated,
+        })
+    }
+
+    fn intern(&mut self, s: &str) -> io::Result<Str> {
+        let h = hash(s);
+        if let Some(&existing) = self.interned.get(&h) {
+            if self.str(existing) == s { return Ok(existing); }
+        }
+        let start = self.strings.len();
+        let end = start + s.len();
+        if end > u32::max_value() as usize { return Err(Error::new(ErrorKind::Other, "ScanResult string arena exceeded 4 GB")); }
+        self.strings.push_str(s);
+        let interned = Str { start: start as u32, end: end as u32 };
+        self.interned.entry(h).or_insert(interned);
+        Ok(interned)
+    }
+
+    fn str(&self, s: Str) -> &str { &self.strings[s.start as usize .. s.end as usize] }
+}
+
+/// The constant pool of a class being added by [ScanResult::add_class_bytes], indexed but undecoded.
+struct RawPool<'b>(Vec<(u8, &'b [u8])>);
+
+impl<'b> RawPool<'b> {
+    fn info(&self, index: u16, tag: u8) -> io::Result<&'b [u8]> {
+        match self.0.get(index as usize) {
+            Some(&(t, info)) if t == tag    => Ok(info),
+            Some(&(t, _))                   => io_data_err!("Expected constant #{} to have tag {}, got {}", index, tag, t),
+            None                            => io_data_err!("No such constant #{}", index),
+        }
+    }
+
+    fn utf8(&self, index: u16) -> io::Result<Cow<'b, str>> {
+        let bytes = &self.info(index, 1)?[2..];
+        // Modified UTF8 only differs from UTF8 for '\0' and supplementary characters, whose modified encodings are invalid
+        // UTF8 - so strings that are valid UTF8, without the raw NULs and 4 byte sequences modified UTF8 never contains,
+        // can be borrowed as-is.
+        if !bytes.iter().any(|&b| b == 0 || b >= 0xF0) {
+            if let Ok(s) = std::str::from_utf8(bytes) { return Ok(Cow::Borrowed(s)); }
+        }
+        match constants::read_modified_utf8_as_utf8(bytes) {
+            Some(s) => Ok(Cow::Owned(s)),
+            None    => io_data_err!("Constant #{} isn't valid modified UTF8", index),
+        }
+    }
+
+    fn class(&self, index: u16) -> io::Result<Cow<'b, str>> {
+        let name_index = self.info(index, 7)?;
+        self.utf8(u16::from_be_bytes([name_index[0], name_index[1]]))
+    }
+}
+
+/// Read an attribute's name and `info`, borrowing the latter from the class bytes.
+fn read_raw_attribute<'b>(read: &mut &'b [u8], pool: &RawPool<'b>) -> io::Result<(Cow<'b, str>, &'b [u8])> {
+    let name    = pool.utf8(read_u2(read)?)?;
+    let length  = read_u4(read)? as usize;
+    io_assert!(length <= read.len(), "{} attribute truncated:  attribute_length is {}, but only {} bytes remain", name, length, read.len());
+    let (info, rest) = read.split_at(length);
+    *read = rest;
+    Ok((name, info))
+}
+
+/// 64-bit FNV-1a, good enough for bucketing strings.
+fn hash(s: &str) -> u64 {
+    let mut hash = 0xcbf29ce4_84222325u64;
+    for byte in s.bytes() {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x00000100_000001B3);
+    }
+    hash
+}
+
+
+
+/// A class borrowed from a [ScanResult](struct.ScanResult.html).
+#[derive(Clone, Copy)]
+pub struct ScannedClass<'s> {
+    scan:   &'s ScanResult,
+    entry:  &'s ClassEntry,
+}
+
+impl<'s> ScannedClass<'s> {
+    pub fn flags(&self) -> class::Flags { self.entry.flags }
+    pub fn path(&self) -> Id<'s> { Id(self.scan.str(self.entry.path)) }
+    pub fn super_path(&self) -> Option<Id<'s>> { self.entry.super_path.map(|s| Id(self.scan.str(s))) }
+    pub fn deprecated(&self) -> bool { self.entry.deprecated }
+
+    pub fn interfaces(&self) -> impl Iterator<Item = Id<'s>> {
+        let scan = self.scan;
+        scan.interfaces[range(&self.entry.interfaces)].iter().map(move |s| Id(scan.str(*s)))
+    }
+
+    pub fn fields(&self) -> impl Iterator<Item = ScannedField<'s>> {
+        let scan = self.scan;
+        scan.members[range(&self.entry.fields)].iter().map(move |entry| ScannedField(ScannedMember { scan, entry }))
+    }
+
+    pub fn methods(&self) -> impl Iterator<Item = ScannedMethod<'s>> {
+        let scan = self.scan;
+        scan.members[range(&self.entry.methods)].iter().map(move |entry| ScannedMethod(ScannedMember { scan, entry }))
+    }
+
+    pub fn is_public(&self)         -> bool { self.entry.flags.contains(class::Flags::PUBLIC) }
+    pub fn is_interface(&self)      -> bool { self.entry.flags.contains(class::Flags::INTERFACE) }
+}
+
+/// A field or method borrowed from a [ScanResult](struct.ScanResult.html).
+#[derive(Clone, Copy)]
+pub struct ScannedMember<'s> {
+    scan:   &'s ScanResult,
+    entry:  &'s MemberEntry,
+}
+
+impl<'s> ScannedMember<'s> {
+    pub fn name(&self) -> &'s str { self.scan.str(self.entry.name) }
+    pub fn descriptor_str(&self) -> &'s str { self.scan.str(self.entry.descriptor) }
+    pub fn signature_str(&self) -> Option<&'s str> { self.entry.signature.map(|s| self.scan.str(s)) }
+    pub fn deprecated(&self) -> bool { self.entry.deprecated }
+}
+
+/// A field borrowed from a [ScanResult](struct.ScanResult.html).  Derefs to [ScannedMember](struct.ScannedMember.html).
+#[derive(Clone, Copy)]
+pub struct ScannedField<'s>(ScannedMember<'s>);
+
+impl<'s> ScannedField<'s> {
+    pub fn flags(&self) -> field::Flags { field::Flags::from_bits_truncate(self.0.entry.flags) }
+}
+
+impl<'s> std::ops::Deref for ScannedField<'s> {
+    type Target = ScannedMember<'s>;
+    fn deref(&self) -> &Self::Target { &self.0 }
+}
+
+/// A method borrowed from a [ScanResult](struct.ScanResult.html).  Derefs to [ScannedMember](struct.ScannedMember.html).
+#[derive(Clone, Copy)]
+pub struct ScannedMethod<'s>(ScannedMember<'s>);
+
+impl<'s> ScannedMethod<'s> {
+    pub fn flags(&self) -> method::Flags { method::Flags::from_bits_truncate(self.0.entry.flags) }
+}
+
+impl<'s> std::ops::Deref for ScannedMethod<'s> {
+    type Target = ScannedMember<'s>;
+    fn deref(&self) -> &Self::Target { &self.0 }
+}
+
+fn range(r: &Range<u32>) -> Range<usize> { r.start as usize .. r.end as usize }
+
+#[test] fn scan_result() {
+    let mut list = testing::ClassFile::new("java/util/List", Some("java/lang/Object"));
+    list.flags = 0x0601; // PUBLIC | INTERFACE | ABSTRACT
+    list.interface("java/util/Collection");
+    list.method(0x0401, "size", "()I", &[]);
+    list.method(0x0401, "clear", "()V", &[]);
+
+    let mut array_list = testing::ClassFile::new("java/util/ArrayList", Some("java/util/AbstractList"));
+    array_list.interface("java/util/List");
+    let sig = array_list.utf8("Ljava/util/List<TE;>;");
+    let signature = array_list.attribute("Signature", &testing::be16(sig));
+    array_list.field(0x0002, "size", "I", &[]);
+    array_list.field(0x0002, "elementData", "[Ljava/lang/Object;", &[signature]);
+    array_list.method(0x0001, "clear", "()V", &[]);
+
+    let mut scan = ScanResult::new();
+    scan.add_class(&list.read()).unwrap();
+    scan.add_class(&array_list.read()).unwrap();
+    scan.add_class(&list.read()).unwrap(); // duplicate ignored
+    assert_eq!(scan.len(), 2);
+
+    let list = scan.class("java/util/List").unwrap();
+    assert!(list.is_interface());
+    assert_eq!(list.super_path().unwrap().as_str(), "java/lang/Object");
+    assert_eq!(list.interfaces().map(|i| i.as_str()).collect::<Vec<_>>(), &["java/util/Collection"]);
+    assert_eq!(list.methods().map(|m| (m.name(), m.descriptor_str())).collect::<Vec<_>>(), &[("size", "()I"), ("clear", "()V")]);
+    assert!(list.methods().all(|m| m.flags().contains(method::Flags::ABSTRACT)));
+    assert_eq!(list.fields().count(), 0);
+
+    let array_list = scan.class("java/util/ArrayList").unwrap();
+    assert!(!array_list.is_interface());
+    assert_eq!(array_list.fields().map(|f| f.signature_str()).collect::<Vec<_>>(), &[None, Some("Ljava/util/List<TE;>;")]);
+    assert!(array_list.fields().all(|f| f.flags().contains(field::Flags::PRIVATE)));
+    assert_eq!(scan.classes().map(|c| c.path().as_str()).collect::<Vec<_>>(), &["java/util/List", "java/util/ArrayList"]);
+    assert!(scan.class("java/util/Map").is_none());
+
+    // "clear", "()V", "size", "java/util/List", etc. are only stored once
+    let unique = ["java/util/List", "java/lang/Object", "java/util/Collection", "size", "()I", "clear", "()V",
+        "java/util/ArrayList", "java/util/AbstractList", "I", "elementData", "[Ljava/lang/Object;", "Ljava/util/List<TE;>;"];
+    assert_eq!(scan.string_bytes(), unique.iter().map(|s| s.len()).sum::<usize>());
+
+    assert_eq!(scan.resolve_simple_name("List"), names::Resolution::Unique("java/util/List".to_owned()));
+    assert_eq!(scan.classes_named("ArrayList").map(|c| c.path().as_str()).collect::<Vec<_>>(), &["java/util/ArrayList"]);
+    scan.add_class(&testing::ClassFile::new("java/awt/List", Some("java/lang/Object")).read()).unwrap();
+    scan.add_class(&testing::ClassFile::new("java/awt/List$1", Some("java/lang/Object")).read()).unwrap();
+    assert_eq!(scan.resolve_simple_name("List").candidates(), &["java/awt/List", "java/util/List"]);
+    assert_eq!(scan.resolve_simple_name("Map"), names::Resolution::NotFound);
+    assert_eq!(scan.resolve_simple_name("1"), names::Resolution::NotFound);
+}
+
+#[test] fn add_class_bytes() {
+    let mut list = testing::ClassFile::new("java/util/List", Some("java/lang/Object"));
+    list.flags = 0x0601; // PUBLIC | INTERFACE | ABSTRACT
+    list.interface("java/util/Collection");
+    let deprecated = list.attribute("Deprecated", &[]);
+    let code = list.attribute("Code", &[0xFF; 7]); // Skipped, not parsed
+    list.method(0x0401, "size", "()I", &[deprecated.clone(), code]);
+    list.class_attribute("Deprecated", &[]);
+
+    let mut array_list = testing::ClassFile::new("java/util/ArrayList", Some("java/util/AbstractList"));
+    array_list.interface("java/util/List");
+    let sig = array_list.utf8("Ljava/util/List<TE;>;");
+    let signature = array_list.attribute("Signature", &testing::be16(sig));
+    array_list.field(0x0002, "elementData", "[Ljava/lang/Object;", &[signature]);
+    array_list.method(0x0001, "clear", "()V", &[]);
+
+    let mut object = testing::ClassFile::new("java/lang/Object", None);
+    object.method(0x0001, "<init>", "()V", &[]);
+
+    let mut parsed = ScanResult::new();
+    let mut direct = ScanResult::new();
+    for class in [&list, &array_list, &object].iter() {
+        parsed.add_class(&class.read()).unwrap();
+        direct.add_class_bytes(&class.to_bytes()).unwrap();
+    }
+    direct.add_class_bytes(&list.to_bytes()).unwrap(); // duplicate ignored
+
+    let summary = |scan: &ScanResult| scan.classes().map(|c| format!("{:?} {} {:?} {:?} {} {:?} {:?}",
+        c.flags(), c.path().as_str(), c.super_path().map(|s| s.as_str()), c.interfaces().map(|i| i.as_str()).collect::<Vec<_>>(), c.deprecated(),
+        c.fields().map(|f| (f.flags(), f.name(), f.descriptor_str(), f.signature_str(), f.deprecated())).collect::<Vec<_>>(),
+        c.methods().map(|m| (m.flags(), m.name(), m.descriptor_str(), m.signature_str(), m.deprecated())).collect::<Vec<_>>(),
+    )).collect::<Vec<_>>();
+    assert_eq!(summary(&direct), summary(&parsed));
+    assert_eq!(direct.string_bytes(), parsed.string_bytes());
+    assert!(direct.class("java/util/List").unwrap().deprecated());
+    assert!(direct.class("java/util/List").unwrap().methods().next().unwrap().deprecated());
+
+    // Errors don't leave partial classes behind
+    let mut bad = testing::ClassFile::new("com/example/Bad", Some("java/lang/Object"));
+    bad.field(0x0001, "ok", "I", &[]);
+    bad.method(0x0001, "bad", "not a descriptor", &[]);
+    let bytes = bad.to_bytes();
+    assert!(direct.add_class_bytes(&bytes).is_err());
+    assert!(direct.add_class_bytes(&bytes[..bytes.len()-1]).is_err());
+    assert!(direct.class("com/example/Bad").is_none());
+    assert_eq!(summary(&direct), summary(&parsed));
+    assert_eq!(direct.classes().map(|c| c.fields().count() + c.methods().count()).sum::<usize>(), direct.members.len());
+}
@@ -0,0 +1,452 @@
+//! Shaded (relocated) copies of libraries, such as `com/example/shaded/com/google/common/...`:  heuristic
+//! [detection](struct.Shading.html) of them, and a [Relocator](struct.Relocator.html) pass for producing them.
+
+use crate::{Class, Source};
+use crate::constants::Constant;
+use crate::transform::{ClassFile, Pass};
+#[cfg(test)] use crate::{class, method, testing, transform, Method};
+
+use std::collections::*;
+use std::io;
+
+
+
+/// Root packages of well-known, frequently shaded libraries, and the names of those libraries.
+pub const KNOWN_LIBRARIES : &[(&str, &str)] = &[
+    ("com/google/common/",              "Guava"),
+    ("com/google/gson/",                "Gson"),
+    ("com/google/protobuf/",            "Protocol Buffers"),
+    ("com/google/thirdparty/",          "Guava"),
+    ("com/fasterxml/jackson/",          "Jackson"),
+    ("com/squareup/okhttp/",            "OkHttp 2"),
+    ("okhttp3/",                        "OkHttp"),
+    ("okio/",                           "Okio"),
+    ("io/netty/",                       "Netty"),
+    ("kotlin/",                         "Kotlin standard library"),
+    ("kotlinx/coroutines/",             "kotlinx.coroutines"),
+    ("org/apache/commons/codec/",       "Apache Commons Codec"),
+    ("org/apache/commons/io/",          "Apache Commons IO"),
+    ("org/apache/commons/lang3/",       "Apache Commons Lang"),
+    ("org/apache/http/",                "Apache HttpComponents"),
+    ("org/objectweb/asm/",              "ASM"),
+    ("org/slf4j/",                      "SLF4J"),
+    ("org/yaml/snakeyaml/",             "SnakeYAML"),
+];
+
+/// A group of classes that appear to have been relocated from `package` to `prefix` + `package`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Relocation {
+    /// The package prefix classes were relocated under, such as `"com/example/shaded/"`.
+    pub prefix:     String,
+
+    /// The original root package of the relocated classes, such as `"com/google/common/"`.
+    pub package:    String,
+
+    /// The name of the library, if `package` is one of the [KNOWN_LIBRARIES](constant.KNOWN_LIBRARIES.html).
+    pub library:    Option<&'static str>,
+
+    /// The (relocated) paths of the classes, sorted.
+    pub classes:    Vec<String>,
+
+    /// How many relocated classes are structurally identical to an unrelocated copy that was also scanned.
+    pub verified:   usize,
+
+    /// How many relocated classes differ from an unrelocated copy that was also scanned (e.g. different library versions.)
+    pub mismatched: usize,
+}
+
+/// Accumulates classes (typically from a fat jar and its unshaded dependencies) to search for relocated libraries.
+///
+/// Relocations of [KNOWN_LIBRARIES] are detected by name alone.  Relocations of other packages are only reported if
+/// at least one relocated class is structurally identical to an unrelocated copy also added to the analysis - that
+/// is, identical after stripping the relocation prefix from its name, superclass, interfaces, and member descriptors.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use jreflection::*;
+/// let mut shading = shading::Shading::new();
+/// shading.add_source(&Source::from_jar("app-all.jar").unwrap()).unwrap();
+/// for relocation in shading.relocations() {
+///     println!("{} relocated to {}{} ({} classes)", relocation.library.unwrap_or("?"), relocation.prefix, relocation.package, relocation.classes.len());
+/// }
+/// ```
+///
+/// [KNOWN_LIBRARIES]:  constant.KNOWN_LIBRARIES.html
+#[derive(Clone, Debug, Default)]
+pub struct Shading {
+    classes: BTreeMap<String, Vec<String>>, // path -> shape tokens
+}
+
+impl Shading {
+    /// Create an empty analysis.
+    pub fn new() -> Self { Self::default() }
+
+    /// Add every class of `source` to the analysis.
+    pub fn add_source(&mut self, source: &Source) -> io::Result<()> {
+        source.for_each_class(|path|{
+            let class = source.read_class(path.as_str())?;
+            self.add_class(&class);
+            Ok(())
+        })
+    }
+
+    /// Add a single class to the analysis.
+    pub fn add_class(&mut self, class: &Class) {
+        let mut shape = Vec::new();
+        shape.push(format!("{:04x} {}", class.flags.bits(), class.super_path.as_ref().map(|s| s.as_str()).unwrap_or("")));
+        shape.extend(class.interfaces.iter().map(|i| format!("I {}", i.as_str())));
+        shape.extend(class.fields .iter().map(|f| format!("F {:04x} {} {}", f.flags.bits(), f.name, f.descriptor_str())));
+        shape.extend(class.methods.iter().map(|m| format!("M {:04x} {} {}", m.flags.bits(), m.name, m.descriptor_str())));
+        shape[1..].sort();
+        self.classes.insert(class.path.as_str().to_owned(), shape);
+    }
+
+    /// Detect relocated packages, sorted by prefix and then package.
+    pub fn relocations(&self) -> Vec<Relocation> {
+        let mut relocations = BTreeMap::<(String, String), Relocation>::new();
+        for (path, shape) in self.classes.iter() {
+            let (prefix, package, library) = match self.relocation_of(path) { Some(r) => r, None => continue };
+            let relocation = relocations.entry((prefix.to_owned(), package.clone())).or_insert_with(|| Relocation {
+                prefix:     prefix.to_owned(),
+                package,
+                library,
+                classes:    Vec::new(),
+                verified:   0,
+                mismatched: 0,
+            });
+            relocation.classes.push(path.clone());
+            if let Some(original) = self.classes.get(&path[prefix.len()..]) {
+                if unrelocate(shape, prefix) == *original {
+                    relocation.verified += 1;
+                } else {
+                    relocation.mismatched += 1;
+                }
+            }
+        }
+        relocations.into_iter().map(|(_, r)| r).filter(|r| r.library.is_some() || r.verified > 0).collect()
+    }
+
+    /// Find the shortest (prefix, package, library) that `path` could have been relocated from.
+    fn relocation_of<'p>(&self, path: &'p str) -> Option<(&'p str, String, Option<&'static str>)> {
+        for (slash, _) in path.match_indices('/') {
+            let (prefix, suffix) = path.split_at(slash + 1);
+            if let Some((package, library)) = KNOWN_LIBRARIES.iter().find(|(package, _)| suffix.starts_with(package)) {
+                return Some((prefix, (*package).to_owned(), Some(library)));
+            }
+            if suffix.contains('/') && self.classes.contains_key(suffix) {
+                let package_end = suffix.match_indices('/').take(2).last().map(|(i, _)| i + 1).unwrap_or(0);
+                return Some((prefix, suffix[..package_end].to_owned(), None));
+            }
+        }
+        None
+    }
+}
+
+/// A [Pass](../transform/trait.Pass.html) relocating packages, such as `com.google` to `shaded.com.google`, the way
+/// shading plugins do.
+///
+/// Every reference to a relocated class is rewritten:  class constants, field and method descriptors, generic
+/// signatures, annotations, and anything else the constant pool names classes with.  When applied with
+/// [Pipeline::apply_jar](../transform/struct.Pipeline.html#method.apply_jar), the classes and resources of relocated
+/// packages are moved as well.  String constants are left alone unless [strings](#method.strings) is enabled, as are the
+/// contents of resources (including `META-INF/services` files.)
+///
+/// # Examples
+///
+/// ```no_run
+/// # use jreflection::*;
+/// let mut relocator = shading::Relocator::new();
+/// relocator.relocate("com.google", "com.example.shaded.com.google").strings(true);
+///
+/// let mut pipeline = transform::Pipeline::new();
+/// pipeline.add(relocator);
+/// pipeline.apply_jar("app-all.jar", "app-shaded.jar").unwrap();
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Relocator {
+    rules:      Vec<(String, String)>, // (from, to) packages such as "com/google/", longest first
+    strings:    bool,
+}
+
+impl Relocator {
+    /// Create a relocator that doesn't relocate anything yet.
+    pub fn new() -> Self { Self::default() }
+
+    /// Relocate the classes and resources of package `from` and its subpackages to `to`, such as `"com.google"` to
+    /// `"shaded.com.google"`.  Packages may also be given as JNI paths (`"com/google"`.)  If packages overlap, the
+    /// longest matching `from` wins.  Relocating the default package (an empty `from`) is ignored.
+    pub fn relocate(&mut self, from: &str, to: &str) -> &mut Self {
+        let package = |package: &str| {
+            let package = package.trim_end_matches(|c| c == '.' || c == '/').replace('.', "/");
+            if package.is_empty() { package } else { format!("{}/", package) }
+        };
+        let from = package(from);
+        if !from.is_empty() {
+            self.rules.push((from, package(to)));
+            self.rules.sort_by_key(|rule| std::cmp::Reverse(rule.0.len()));
+        }
+        self
+    }
+
+    /// Also relocate string constants naming relocated classes or resources, such as `"com.google.gson.Gson"` (for
+    /// `Class.forName`) or `"com/google/common/version.properties"`.  Off by default, since such strings may just as
+    /// well refer to something outside the jar.
+    pub fn strings(&mut self, relocate: bool) -> &mut Self {
+        self.strings = relocate;
+        self
+    }
+
+    /// The relocated JNI path of the class or resource at `path` (such as `"com/google/gson/Gson"`), if it's in a
+    /// relocated package.
+    pub fn relocate_path(&self, path: &str) -> Option<String> {
+        self.rules.iter().find(|(from, _)| path.starts_with(from.as_str())).map(|(from, to)| format!("{}{}", to, &path[from.len()..]))
+    }
+
+    /// Relocate the class types of a field or method descriptor, or of a generic signature.  `None` if `text` isn't
+    /// one, or doesn't reference any relocated classes.
+    fn relocate_descriptor(&self, text: &str) -> Option<String> {
+        let mut relocated = String::with_capacity(text.len());
+        let mut pos = 0;
+        if text.starts_with('<') { self.relocate_type_parameters(text, &mut pos, &mut relocated)?; }
+        while pos < text.len() {
+            match text.as_bytes()[pos] {
+                b'(' | b')' | b'^' | b'V' => { relocated.push(char::from(text.as_bytes()[pos])); pos += 1; },
+                _ => self.relocate_type(text, &mut pos, &mut relocated)?,
+            }
+        }
+        if relocated == text { None } else { Some(relocated) }
+    }
+
+    /// Relocate the `<T:Ljava/lang/Object;...>` formal type parameters starting at `text[*pos]`.
+    fn relocate_type_parameters(&self, text: &str, pos: &mut usize, out: &mut String) -> Option<()> {
+        let bytes = text.as_bytes();
+        out.push('<');
+        *pos += 1;
+        while *bytes.get(*pos)? != b'>' {
+            let colon = *pos + text[*pos..].find(':')?;
+            out.push_str(&text[*pos..colon]);
+            *pos = colon;
+            while bytes.get(*pos) == Some(&b':') {
+                out.push(':');
+                *pos += 1;
+                if bytes.get(*pos) != Some(&b':') { self.relocate_type(text, pos, out)?; } // Class bounds may be empty
+            }
+        }
+        out.push('>');
+        *pos += 1;
+        Some(())
+    }
+
+    /// Relocate the single (possibly generic) type starting at `text[*pos]`.
+    fn relocate_type(&self, text: &str, pos: &mut usize, out: &mut String) -> Option<()> {
+        let bytes = text.as_bytes();
+        let name_end = |start: usize| text[start..].find(|c| c == ';' || c == '<' || c == '.').map(|end| start + end);
+        match *bytes.get(*pos)? {
+            b'B' | b'C' | b'D' | b'F' | b'I' | b'J' | b'S' | b'Z' => {
+                out.push(char::from(bytes[*pos]));
+                *pos += 1;
+            },
+            b'[' => {
+                out.push('[');
+                *pos += 1;
+                self.relocate_type(text, pos, out)?;
+            },
+            b'T' => {
+                let end = *pos + text[*pos..].find(';')? + 1;
+                out.push_str(&text[*pos..end]);
+                *pos = end;
+            },
+            b'L' => {
+                let end = name_end(*pos + 1)?;
+                let name = &text[*pos + 1 .. end];
+                out.push('L');
+                out.push_str(self.relocate_path(name).as_ref().map(|n| n.as_str()).unwrap_or(name));
+                *pos = end;
+                loop {
+                    match *bytes.get(*pos)? {
+                        b';' => {
+                            out.push(';');
+                            *pos += 1;
+                            break;
+                        },
+                        b'<' => {
+                            out.push('<');
+                            *pos += 1;
+                            while *bytes.get(*pos)? != b'>' {
+                                match bytes[*pos] {
+                                    b'*'        => { out.push('*'); *pos += 1; },
+                                    b'+' | b'-' => { out.push(char::from(bytes[*pos])); *pos += 1; self.relocate_type(text, pos, out)?; },
+                                    _           => self.relocate_type(text, pos, out)?,
+                                }
+                            }
+                            out.push('>');
+                            *pos += 1;
+                        },
+                        _ => { // '.' followed by the simple name of an inner class, which isn't relocated
+                            let end = name_end(*pos + 1)?;
+                            out.push_str(&text[*pos..end]);
+                            *pos = end;
+                        },
+                    }
+                }
+            },
+            _ => return None,
+        }
+        Some(())
+    }
+
+    /// Relocate a string constant naming a class (in JNI or dotted form) or resource (with or without a leading `/`.)
+    fn relocate_string(&self, text: &str) -> Option<String> {
+        if text.starts_with('/') { return self.relocate_path(&text[1..]).map(|path| format!("/{}", path)); }
+        if let Some(path) = self.relocate_path(text).or_else(|| self.relocate_descriptor(text)) { return Some(path); }
+        let dotted = text.replace('.', "/");
+        self.rules.iter().find(|(from, _)| dotted.starts_with(from.as_str())).map(|(from, to)| format!("{}{}", to.replace('/', "."), &text[from.len()..]))
+    }
+}
+
+impl Pass for Relocator {
+    fn apply(&self, class: &mut ClassFile) -> io::Result<()> {
+        let constants = &mut class.constants;
+
+        // String constants may share their CONSTANT_Utf8_info with class names or descriptors, so remember them first
+        let strings = constants.0.iter().enumerate().filter_map(|(index, constant)| match constant {
+            Constant::String { string_index } => Some((index, *string_index)),
+            _ => None,
+        }).map(|(index, string_index)| Ok((index, constants.get_utf8(string_index)?.to_owned()))).collect::<io::Result<Vec<_>>>()?;
+
+        for constant in constants.0.iter_mut() {
+            if let Constant::Utf8(text) = constant {
+                if let Some(relocated) = self.relocate_path(text).or_else(|| self.relocate_descriptor(text)) { *text = relocated; }
+            }
+        }
+
+        for (index, original) in strings {
+            let wanted = if self.strings { self.relocate_string(&original).unwrap_or(original) } else { original };
+            let string_index = match constants.0[index] { Constant::String { string_index } => string_index, _ => unreachable!() };
+            if constants.get_utf8(string_index)? == wanted { continue; }
+            let string_index = constants.add_utf8(&wanted)?;
+            constants.0[index] = Constant::String { string_index };
+        }
+        Ok(())
+    }
+
+    fn rename_entry(&self, name: &str) -> Option<String> { self.relocate_path(name) }
+}
+
+fn unrelocate(shape: &[String], prefix: &str) -> Vec<String> {
+    let descriptor_prefix = format!("L{}", prefix);
+    let path_prefix = format!(" {}", prefix);
+    let mut shape = shape.iter().map(|token| token.replace(descriptor_prefix.as_str(), "L").replace(path_prefix.as_str(), " ")).collect::<Vec<_>>();
+    shape[1..].sort(); // Renaming may reorder interfaces and members
+    shape
+}
+
+#[test] fn relocations() {
+    fn class(path: &str, super_path: &str, methods: &[(&str, &str)]) -> Class {
+        Class {
+            path:       class::IdBuf::new(path.to_owned()),
+            super_path: Some(class::IdBuf::new(super_path.to_owned())),
+            methods:    methods.iter().map(|(name, desc)| Method::new(method::Flags::PUBLIC, (*name).to_owned(), (*desc).to_owned()).unwrap()).collect(),
+            .. Default::default()
+        }
+    }
+
+    let mut shading = Shading::new();
+    shading.add_class(&class("com/example/App",                                 "java/lang/Object", &[]));
+    shading.add_class(&class("com/example/shaded/com/google/common/base/Joiner","java/lang/Object", &[("on", "(C)Lcom/example/shaded/com/google/common/base/Joiner;")]));
+    shading.add_class(&class("com/example/shaded/com/google/common/base/Ascii", "java/lang/Object", &[]));
+    shading.add_class(&class("com/google/common/base/Joiner",                   "java/lang/Object", &[("on", "(C)Lcom/google/common/base/Joiner;")]));
+    shading.add_class(&class("com/google/common/base/Ascii",                    "java/lang/Object", &[("toLowerCase", "(C)C")]));
+    shading.add_class(&class("com/example/libs/org/foo/Util",                   "java/lang/Object", &[("run", "(Lcom/example/libs/org/foo/Util;)V")]));
+    shading.add_class(&class("org/foo/Util",                                    "java/lang/Object", &[("run", "(Lorg/foo/Util;)V")]));
+    shading.add_class(&class("com/example/Util",                                "java/lang/Object", &[])); // Not a relocation of org/foo/Util
+
+    let relocations = shading.relocations();
+    assert_eq!(relocations, vec![
+        Relocation {
+            prefix:     "com/example/libs/".to_owned(),
+            package:    "org/foo/".to_owned(),
+            library:    None,
+            classes:    vec!["com/example/libs/org/foo/Util".to_owned()],
+            verified:   1,
+            mismatched: 0,
+        },
+        Relocation {
+            prefix:     "com/example/shaded/".to_owned(),
+            package:    "com/google/common/".to_owned(),
+            library:    Some("Guava"),
+            classes:    vec!["com/example/shaded/com/google/common/base/Ascii".to_owned(), "com/example/shaded/com/google/common/base/Joiner".to_owned()],
+            verified:   1,
+            mismatched: 1,
+        },
+    ]);
+}
+
+#[test] fn relocator() {
+    use crate::testing::be16;
+
+    let mut c = testing::ClassFile::new("com/google/gson/Gson", Some("java/lang/Object"));
+    c.interface("com/google/gson/internal/Excluder");
+    c.field(0x0002, "excluder", "Lcom/google/gson/internal/Excluder;", &[]);
+    c.field(0x0002, "unrelated", "Lorg/Lcom/google/gson/Gson;", &[]);
+    c.methodref("com/google/gson/Gson", "fromJson", "(ZLcom/google/gson/JsonElement;[[Lcom/google/gson/Gson;)V");
+    c.methodref("com/google/gson/JsonElement", "getAsString", "()Ljava/lang/String;");
+    let signature = c.utf8("<T:Ljava/lang/Object;L::Lcom/google/gson/JsonElement;>(TT;Ljava/util/Map<+Lcom/google/gson/Gson;*>.Entry<[Lcom/google/gson/Gson;>;)Ljava/util/List<Lcom/google/gson/JsonElement;>;^Lcom/google/gson/JsonParseException;");
+    let signature = c.attribute("Signature", &be16(signature));
+    c.method(0x0001, "toJson", "(Lcom/google/gson/JsonElement;)Ljava/lang/String;", &[signature]);
+    c.string("com.google.gson.Gson");
+    c.string("com/google/gson/Gson");
+    c.string("/com/google/gson/version.properties");
+    c.string("hello");
+    let bytes = c.to_bytes();
+
+    let mut relocator = Relocator::new();
+    relocator.relocate("com.google", "shaded.com.google").relocate("com/google/gson/internal/", "internal").relocate("", "ignored");
+    assert_eq!(relocator.relocate_path("com/google/gson/Gson.class"), Some("shaded/com/google/gson/Gson.class".to_owned()));
+    assert_eq!(relocator.relocate_path("com/google/gson/internal/Excluder"), Some("internal/Excluder".to_owned()));
+    assert_eq!(relocator.relocate_path("com/googleapis/Api"), None);
+
+    let strings = |relocator: Relocator| {
+        let mut pipeline = transform::Pipeline::new();
+        pipeline.add(relocator);
+        let class = ClassFile::read(&mut &pipeline.apply(&bytes[..]).unwrap()[..]).unwrap();
+        let strings = class.constants.0.iter().filter_map(|c| match c {
+            Constant::String { string_index } => Some(class.constants.get_utf8(*string_index).unwrap().to_owned()),
+            _ => None,
+        }).collect::<Vec<_>>();
+        (Class::read(&mut &class.to_bytes().unwrap()[..]).unwrap(), strings)
+    };
+
+    let (class, unrelocated) = strings(relocator.clone());
+    assert_eq!(class.path.as_str(), "shaded/com/google/gson/Gson");
+    assert_eq!(class.interfaces.iter().map(|i| i.as_str()).collect::<Vec<_>>(), &["internal/Excluder"]);
+    assert_eq!(class.fields.iter().map(|f| f.descriptor_str()).collect::<Vec<_>>(), &["Linternal/Excluder;", "Lorg/Lcom/google/gson/Gson;"]);
+    assert_eq!(class.methods[0].descriptor_str(), "(Lshaded/com/google/gson/JsonElement;)Ljava/lang/String;");
+    assert_eq!(class.methods[0].signature_str(), Some("<T:Ljava/lang/Object;L::Lshaded/com/google/gson/JsonElement;>(TT;Ljava/util/Map<+Lshaded/com/google/gson/Gson;*>.Entry<[Lshaded/com/google/gson/Gson;>;)Ljava/util/List<Lshaded/com/google/gson/JsonElement;>;^Lshaded/com/google/gson/JsonParseException;"));
+    assert_eq!(relocator.relocate_descriptor("(ZLcom/google/gson/JsonElement;[[Lcom/google/gson/Gson;)V"), Some("(ZLshaded/com/google/gson/JsonElement;[[Lshaded/com/google/gson/Gson;)V".to_owned()));
+    assert_eq!(relocator.relocate_descriptor("<init>"), None);
+    assert_eq!(relocator.relocate_descriptor("Lcom/google/gson/Gson"), None);
+    assert_eq!(unrelocated, &["com.google.gson.Gson", "com/google/gson/Gson", "/com/google/gson/version.properties", "hello"]);
+
+    relocator.strings(true);
+    let (_, relocated) = strings(relocator.clone());
+    assert_eq!(relocated, &["shaded.com.google.gson.Gson", "shaded/com/google/gson/Gson", "/shaded/com/google/gson/version.properties", "hello"]);
+
+    // Jars
+    let input = testing::write_jar("relocator-input", &[
+        ("META-INF/MANIFEST.MF",                b"Manifest-Version: 1.0\r\n".to_vec()),
+        ("com/google/gson/Gson.class",          bytes.clone()),
+        ("com/google/gson/version.properties",  b"version=2.8".to_vec()),
+        ("com/example/Main.class",              testing::ClassFile::new("com/example/Main", Some("java/lang/Object")).to_bytes()),
+    ]);
+    let output = input.with_file_name("relocator-output.jar");
+    let mut pipeline = transform::Pipeline::new();
+    pipeline.add(relocator).only(|class| class.starts_with("com/google/"));
+    let stats = pipeline.apply_jar(&input, &output).unwrap();
+    assert_eq!((stats.classes_transformed, stats.classes_copied, stats.resources_copied, stats.entries_renamed), (1, 1, 2, 2));
+    let src = Source::from_jar(&output).unwrap();
+    assert_eq!(src.read_class("shaded/com/google/gson/Gson").unwrap().path.as_str(), "shaded/com/google/gson/Gson");
+    assert!(src.read_class("com/example/Main").is_ok());
+    assert_eq!(src.as_jar().unwrap().read_entry("shaded/com/google/gson/version.properties").unwrap(), b"version=2.8");
+}
@@ -0,0 +1,567 @@
+//! [Java SE 7 &sect; 4.3.4](https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.3.4):  Parsing APIs and structures for generic signatures.
+
+use std::collections::*;
+use std::fmt::{self, Display, Formatter};
+use std::io;
+
+
+
+/// A primitive type, or `void`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum BaseType {
+    Byte,
+    Char,
+    Double,
+    Float,
+    Int,
+    Long,
+    Short,
+    Boolean,
+    Void, // Only valid as a method return type.
+}
+
+/// A (possibly generic) type, such as `int`, `java.util.List<E>`, `T`, or `T[]`.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Type {
+    /// A primitive type or `void`.
+    Base(BaseType),
+    /// A class or interface type, such as `java.util.Map<K, V>.Entry<K, V>`.
+    Class(ClassType),
+    /// A type variable, such as `T`.
+    TypeVariable(String),
+    /// An array of another type.
+    Array(Box<Type>),
+}
+
+/// A class or interface type, such as `java.util.Map<K, V>.Entry<K, V>`.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ClassType {
+    /// The package of the type, such as `"java/util"`, or `""` for the default package.
+    pub package:    String,
+
+    /// The outermost class, followed by any inner classes, such as `[Map<K, V>, Entry<K, V>]`.
+    pub segments:   Vec<SimpleClassType>,
+}
+
+/// A single class name, and the type arguments applied to it, such as `Entry<K, V>`.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SimpleClassType {
+    pub name:           String,
+    pub type_arguments: Vec<TypeArgument>,
+}
+
+/// A type argument, such as the `String` of `List<String>` or the `? extends Number` of `List<? extends Number>`.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum TypeArgument {
+    /// `?`
+    Any,
+    /// `T`
+    Exact(Type),
+    /// `? extends T`
+    Extends(Type),
+    /// `? super T`
+    Super(Type),
+}
+
+/// A generic type parameter declaration, such as `T extends Comparable<T>`.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TypeParameter {
+    pub name:               String,
+    pub class_bound:        Option<Type>,
+    pub interface_bounds:   Vec<Type>,
+}
+
+/// [Java SE 7 &sect; 4.3.4](https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.3.4):  The generic signature of a class.
+///
+/// # Example
+///
+/// ```
+/// use jreflection::signature::*;
+/// let sig = ClassSignature::parse("<E:Ljava/lang/Object;>Ljava/util/AbstractList<TE;>;Ljava/util/List<TE;>;").unwrap();
+/// assert_eq!(sig.type_parameters[0].name, "E");
+/// assert_eq!(sig.superclass.erasure(), "java/util/AbstractList");
+/// assert_eq!(sig.interfaces[0].to_string(), "Ljava/util/List<TE;>;");
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ClassSignature {
+    pub type_parameters:    Vec<TypeParameter>,
+    pub superclass:         ClassType,
+    pub interfaces:         Vec<ClassType>,
+}
+
+/// [Java SE 7 &sect; 4.3.4](https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.3.4):  The generic signature of a method.
+///
+/// # Example
+///
+/// ```
+/// use jreflection::signature::*;
+/// let sig = MethodSignature::parse("<T:Ljava/lang/Object;>([TT;)TT;^Ljava/io/IOException;").unwrap();
+/// assert_eq!(sig.type_parameters[0].name, "T");
+/// assert_eq!(sig.parameters, vec![Type::Array(Box::new(Type::TypeVariable("T".to_string())))]);
+/// assert_eq!(sig.return_type, Type::TypeVariable("T".to_string()));
+/// assert_eq!(sig.throws.len(), 1);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct MethodSignature {
+    pub type_parameters:    Vec<TypeParameter>,
+    pub parameters:         Vec<Type>,
+    pub return_type:        Type,
+    pub throws:             Vec<Type>,
+}
+
+/// Type variable bindings, as produced by [ClassSignature::bind](struct.ClassSignature.html#method.bind).
+pub type Bindings = BTreeMap<String, TypeArgument>;
+
+
+
+impl Type {
+    /// Parse a field signature (or any other single type signature), such as `"Ljava/util/List<TE;>;"`
+    pub fn parse(signature: &str) -> io::Result<Self> {
+        let mut p = Parser::new(signature);
+        let t = p.java_type(false)?;
+        p.end()?;
+        Ok(t)
+    }
+}
+
+impl ClassType {
+    /// The [type erased] JNI path of this class, such as `"java/util/Map$Entry"`.
+    ///
+    /// [type erased]: https://docs.oracle.com/javase/tutorial/java/generics/erasure.html
+    pub fn erasure(&self) -> String {
+        let mut path = self.package.clone();
+        if !path.is_empty() { path.push('/'); }
+        for (i, segment) in self.segments.iter().enumerate() {
+            if i > 0 { path.push('$'); }
+            path.push_str(segment.name.as_str());
+        }
+        path
+    }
+}
+
+impl ClassSignature {
+    /// Parse a class signature, such as `"<E:Ljava/lang/Object;>Ljava/util/AbstractList<TE;>;Ljava/util/List<TE;>;"`
+    pub fn parse(signature: &str) -> io::Result<Self> {
+        let mut p = Parser::new(signature);
+        let type_parameters = p.type_parameters()?;
+        let superclass = p.class_type()?;
+        let mut interfaces = Vec::new();
+        while !p.rest.is_empty() { interfaces.push(p.class_type()?); }
+        Ok(Self { type_parameters, superclass, interfaces })
+    }
+
+    /// Bind the type parameters of this class to concrete type arguments.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use jreflection::signature::*;
+    /// let array_list = ClassSignature::parse("<E:Ljava/lang/Object;>Ljava/util/AbstractList<TE;>;Ljava/util/List<TE;>;").unwrap();
+    /// let bindings = array_list.bind(&[TypeArgument::Exact(Type::parse("Ljava/lang/String;").unwrap())]).unwrap();
+    ///
+    /// let get = MethodSignature::parse("(I)TE;").unwrap();
+    /// assert_eq!(substitute(&get, &bindings).to_string(), "(I)Ljava/lang/String;");
+    /// ```
+    pub fn bind(&self, type_arguments: &[TypeArgument]) -> io::Result<Bindings> {
+        if type_arguments.len() != self.type_parameters.len() {
+            return io_data_err!("Expected {} type arguments, got {}", self.type_parameters.len(), type_arguments.len());
+        }
+        Ok(self.type_parameters.iter().map(|p| p.name.clone()).zip(type_arguments.iter().cloned()).collect())
+    }
+}
+
+impl MethodSignature {
+    /// Parse a method signature, such as `"<T:Ljava/lang/Object;>([TT;)TT;"`
+    pub fn parse(signature: &str) -> io::Result<Self> {
+        let mut p = Parser::new(signature);
+        let type_parameters = p.type_parameters()?;
+        p.expect('(')?;
+        let mut parameters = Vec::new();
+        while !p.eat(')') { parameters.push(p.java_type(false)?); }
+        let return_type = p.java_type(true)?;
+        let mut throws = Vec::new();
+        while p.eat('^') {
+            match p.java_type(false)? {
+                t @ Type::Class(_) | t @ Type::TypeVariable(_) => throws.push(t),
+                other => return io_data_err!("Expected a class or type variable after '^' in method signature {:?}, got {}", signature, other),
+            }
+        }
+        p.end()?;
+        Ok(Self { type_parameters, parameters, return_type, throws })
+    }
+}
+
+
+
+/// Types that can have type variables substituted for type arguments.  See [substitute](fn.substitute.html).
+pub trait Substitute : Sized {
+    #[doc(hidden)] fn substitute_with(&self, bindings: &Bindings) -> Self;
+}
+
+/// Replace the type variables of `value` with the type arguments they're bound to.
+///
+/// Unbound type variables are left as-is.  Method type parameters shadow class type parameters of the same name.
+/// When a type variable bound to a wildcard appears outside of a type argument (e.g. as a return type), it's replaced
+/// with the wildcard's upper bound (`? extends Number` becomes `Number`, `?` and `? super T` become `Object`.)
+///
+/// # Example
+///
+/// ```
+/// use jreflection::signature::*;
+/// let map = ClassSignature::parse("<K:Ljava/lang/Object;V:Ljava/lang/Object;>Ljava/lang/Object;").unwrap();
+/// let bindings = map.bind(&[
+///     TypeArgument::Exact(Type::parse("Ljava/lang/String;").unwrap()),
+///     TypeArgument::Extends(Type::parse("Ljava/lang/Number;").unwrap()),
+/// ]).unwrap();
+///
+/// let entry_set = MethodSignature::parse("()Ljava/util/Set<Ljava/util/Map$Entry<TK;TV;>;>;").unwrap();
+/// assert_eq!(substitute(&entry_set, &bindings).to_string(), "()Ljava/util/Set<Ljava/util/Map$Entry<Ljava/lang/String;+Ljava/lang/Number;>;>;");
+///
+/// let get = MethodSignature::parse("(Ljava/lang/Object;)TV;").unwrap();
+/// assert_eq!(substitute(&get, &bindings).to_string(), "(Ljava/lang/Object;)Ljava/lang/Number;");
+/// ```
+pub fn substitute<S: Substitute>(value: &S, bindings: &Bindings) -> S {
+    value.substitute_with(bindings)
+}
+
+impl Substitute for Type {
+    fn substitute_with(&self, bindings: &Bindings) -> Self {
+        match self {
+            Type::Base(b)           => Type::Base(*b),
+            Type::Class(c)          => Type::Class(c.substitute_with(bindings)),
+            Type::Array(t)          => Type::Array(Box::new(t.substitute_with(bindings))),
+            Type::TypeVariable(v)   => match bindings.get(v) {
+                None                                => Type::TypeVariable(v.clone()),
+                Some(TypeArgument::Exact(t))        => t.clone(),
+                Some(TypeArgument::Extends(t))      => t.clone(),
+                Some(TypeArgument::Super(_))        => Type::object(),
+                Some(TypeArgument::Any)             => Type::object(),
+            },
+        }
+    }
+}
+
+impl Substitute for ClassType {
+    fn substitute_with(&self, bindings: &Bindings) -> Self {
+        ClassType {
+            package:    self.package.clone(),
+            segments:   self.segments.iter().map(|s| SimpleClassType {
+                name:           s.name.clone(),
+                type_arguments: s.type_arguments.iter().map(|a| a.substitute_with(bindings)).collect(),
+            }).collect(),
+        }
+    }
+}
+
+impl Substitute for TypeArgument {
+    fn substitute_with(&self, bindings: &Bindings) -> Self {
+        match self {
+            TypeArgument::Any => TypeArgument::Any,
+            TypeArgument::Exact(Type::TypeVariable(v)) if bindings.contains_key(v) => bindings[v].clone(),
+            TypeArgument::Extends(Type::TypeVariable(v)) if bindings.contains_key(v) => match &bindings[v] {
+                TypeArgument::Exact(t) | TypeArgument::Extends(t) => TypeArgument::Extends(t.clone()),
+                _ => TypeArgument::Any,
+            },
+            TypeArgument::Super(Type::TypeVariable(v)) if bindings.contains_key(v) => match &bindings[v] {
+                TypeArgument::Exact(t) | TypeArgument::Super(t) => TypeArgument::Super(t.clone()),
+                _ => TypeArgument::Any,
+            },
+            TypeArgument::Exact(t)      => TypeArgument::Exact(t.substitute_with(bindings)),
+            TypeArgument::Extends(t)    => TypeArgument::Extends(t.substitute_with(bindings)),
+            TypeArgument::Super(t)      => TypeArgument::Super(t.substitute_with(bindings)),
+        }
+    }
+}
+
+impl Substitute for TypeParameter {
+    fn substitute_with(&self, bindings: &Bindings) -> Self {
+        TypeParameter {
+            name:               self.name.clone(),
+            class_bound:        self.class_bound.as_ref().map(|b| b.substitute_with(bindings)),
+            interface_bounds:   self.interface_bounds.iter().map(|b| b.substitute_with(bindings)).collect(),
+        }
+    }
+}
+
+impl Substitute for MethodSignature {
+    fn substitute_with(&self, bindings: &Bindings) -> Self {
+        let mut bindings = bindings.clone();
+        for shadowing in self.type_parameters.iter() { bindings.remove(&shadowing.name); }
+        let bindings = &bindings;
+
+        MethodSignature {
+            type_parameters:    self.type_parameters.iter().map(|p| p.substitute_with(bindings)).collect(),
+            parameters:         self.parameters.iter().map(|p| p.substitute_with(bindings)).collect(),
+            return_type:        self.return_type.substitute_with(bindings),
+            throws:             self.throws.iter().map(|t| t.substitute_with(bindings)).collect(),
+        }
+    }
+}
+
+impl Type {
+    fn object() -> Self {
+        Type::Class(ClassType { package: "java/lang".to_owned(), segments: vec![SimpleClassType { name: "Object".to_owned(), type_arguments: Vec::new() }] })
+    }
+}
+
+
+
+impl Display for BaseType {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(match self {
+            BaseType::Byte      => "B",
+            BaseType::Char      => "C",
+            BaseType::Double    => "D",
+            BaseType::Float     => "F",
+            BaseType::Int       => "I",
+            BaseType::Long      => "J",
+            BaseType::Short     => "S",
+            BaseType::Boolean   => "Z",
+            BaseType::Void      => "V",
+        })
+    }
+}
+
+impl Display for Type {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Type::Base(b)           => write!(f, "{}", b),
+            Type::Class(c)          => write!(f, "{}", c),
+            Type::TypeVariable(v)   => write!(f, "T{};", v),
+            Type::Array(t)          => write!(f, "[{}", t),
+        }
+    }
+}
+
+impl Display for ClassType {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str("L")?;
+        if !self.package.is_empty() { write!(f, "{}/", self.package)?; }
+        for (i, segment) in self.segments.iter().enumerate() {
+            if i > 0 { f.write_str(".")?; }
+            write!(f, "{}", segment)?;
+        }
+        f.write_str(";")
+    }
+}
+
+impl Display for SimpleClassType {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(self.name.as_str())?;
+        if !self.type_arguments.is_empty() {
+            f.write_str("<")?;
+            for arg in self.type_arguments.iter() { write!(f, "{}", arg)?; }
+            f.write_str(">")?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for TypeArgument {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            TypeArgument::Any           => f.write_str("*"),
+            TypeArgument::Exact(t)      => write!(f, "{}", t),
+            TypeArgument::Extends(t)    => write!(f, "+{}", t),
+            TypeArgument::Super(t)      => write!(f, "-{}", t),
+        }
+    }
+}
+
+fn fmt_type_parameters(f: &mut Formatter, type_parameters: &[TypeParameter]) -> fmt::Result {
+    if type_parameters.is_empty() { return Ok(()); }
+    f.write_str("<")?;
+    for p in type_parameters.iter() {
+        write!(f, "{}:", p.name)?;
+        if let Some(b) = p.class_bound.as_ref() { write!(f, "{}", b)?; }
+        for b in p.interface_bounds.iter() { write!(f, ":{}", b)?; }
+    }
+    f.write_str(">")
+}
+
+impl Display for ClassSignature {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        fmt_type_parameters(f, &self.type_parameters[..])?;
+        write!(f, "{}", self.superclass)?;
+        for i in self.interfaces.iter() { write!(f, "{}", i)?; }
+        Ok(())
+    }
+}
+
+impl Display for MethodSignature {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        fmt_type_parameters(f, &self.type_parameters[..])?;
+        f.write_str("(")?;
+        for p in self.parameters.iter() { write!(f, "{}", p)?; }
+        write!(f, "){}", self.return_type)?;
+        for t in self.throws.iter() { write!(f, "^{}", t)?; }
+        Ok(())
+    }
+}
+
+
+
+struct Parser<'a> {
+    original:   &'a str,
+    rest:       &'a str,
+}
+
+impl<'a> Parser<'a> {
+    fn new(signature: &'a str) -> Self { Self { original: signature, rest: signature } }
+
+    fn peek(&self) -> Option<char> { self.rest.chars().next() }
+
+    fn eat(&mut self, ch: char) -> bool {
+        if self.peek() == Some(ch) {
+            self.rest = &self.rest[ch.len_utf8()..];
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, ch: char) -> io::Result<()> {
+        if self.eat(ch) { Ok(()) } else { io_data_err!("Expected {:?} at {:?} parsing signature {:?}", ch, self.rest, self.original) }
+    }
+
+    fn end(&self) -> io::Result<()> {
+        if self.rest.is_empty() { Ok(()) } else { io_data_err!("Unexpected trailing {:?} parsing signature {:?}", self.rest, self.original) }
+    }
+
+    fn identifier(&mut self) -> io::Result<&'a str> {
+        let end = self.rest.find(|ch| ".;[/<>:".contains(ch)).unwrap_or(self.rest.len());
+        if end == 0 { return io_data_err!("Expected an identifier at {:?} parsing signature {:?}", self.rest, self.original); }
+        let (id, rest) = self.rest.split_at(end);
+        self.rest = rest;
+        Ok(id)
+    }
+
+    fn type_parameters(&mut self) -> io::Result<Vec<TypeParameter>> {
+        let mut params = Vec::new();
+        if !self.eat('<') { return Ok(params); }
+        loop {
+            let name = self.identifier()?.to_owned();
+            self.expect(':')?;
+            let class_bound = match self.peek() {
+                Some(':') | Some('>') => None,
+                _ => Some(self.reference_type()?),
+            };
+            let mut interface_bounds = Vec::new();
+            while self.eat(':') { interface_bounds.push(self.reference_type()?); }
+            params.push(TypeParameter { name, class_bound, interface_bounds });
+            if self.eat('>') { break; }
+        }
+        Ok(params)
+    }
+
+    fn java_type(&mut self, allow_void: bool) -> io::Result<Type> {
+        let base = match self.peek() {
+            Some('B') => BaseType::Byte,
+            Some('C') => BaseType::Char,
+            Some('D') => BaseType::Double,
+            Some('F') => BaseType::Float,
+            Some('I') => BaseType::Int,
+            Some('J') => BaseType::Long,
+            Some('S') => BaseType::Short,
+            Some('Z') => BaseType::Boolean,
+            Some('V') if allow_void => BaseType::Void,
+            _ => return self.reference_type(),
+        };
+        self.rest = &self.rest[1..];
+        Ok(Type::Base(base))
+    }
+
+    fn reference_type(&mut self) -> io::Result<Type> {
+        match self.peek() {
+            Some('L') => Ok(Type::Class(self.class_type()?)),
+            Some('T') => {
+                self.rest = &self.rest[1..];
+                let name = self.identifier()?.to_owned();
+                self.expect(';')?;
+                Ok(Type::TypeVariable(name))
+            },
+            Some('[') => {
+                self.rest = &self.rest[1..];
+                Ok(Type::Array(Box::new(self.java_type(false)?)))
+            },
+            _ => io_data_err!("Expected a type at {:?} parsing signature {:?}", self.rest, self.original),
+        }
+    }
+
+    fn class_type(&mut self) -> io::Result<ClassType> {
+        self.expect('L')?;
+        let mut package = String::new();
+        let mut name = self.identifier()?;
+        while self.eat('/') {
+            if !package.is_empty() { package.push('/'); }
+            package.push_str(name);
+            name = self.identifier()?;
+        }
+
+        let mut segments = Vec::new();
+        loop {
+            let mut type_arguments = Vec::new();
+            if self.eat('<') {
+                while !self.eat('>') {
+                    type_arguments.push(match self.peek() {
+                        Some('*') => { self.rest = &self.rest[1..]; TypeArgument::Any },
+                        Some('+') => { self.rest = &self.rest[1..]; TypeArgument::Extends(self.reference_type()?) },
+                        Some('-') => { self.rest = &self.rest[1..]; TypeArgument::Super(self.reference_type()?) },
+                        _ => TypeArgument::Exact(self.reference_type()?),
+                    });
+                }
+                if type_arguments.is_empty() { return io_data_err!("Expected type arguments between '<' and '>' parsing signature {:?}", self.original); }
+            }
+            segments.push(SimpleClassType { name: name.to_owned(), type_arguments });
+            if !self.eat('.') { break; }
+            name = self.identifier()?;
+        }
+        self.expect(';')?;
+        Ok(ClassType { package, segments })
+    }
+}
+
+#[test] fn signature_round_trip() {
+    for sig in [
+        "<E:Ljava/lang/Object;>Ljava/util/Collections$UnmodifiableSet<TE;>;Ljava/util/SortedSet<TE;>;Ljava/io/Serializable;",
+        "Ljava/lang/Object;Ljava/security/PrivilegedExceptionAction<Ljava/lang/Boolean;>;",
+        "<T::Ljava/lang/Comparable<-TT;>;>Ljava/lang/Object;",
+        "<K:Ljava/lang/Object;V:Ljava/lang/Object;>Ljava/util/AbstractMap<TK;TV;>;Ljava/util/Map<TK;TV;>;",
+        "Ljava/lang/Object;Lcom/example/Outer<TT;>.Inner<*>;",
+    ].iter().copied() {
+        assert_eq!(ClassSignature::parse(sig).unwrap().to_string(), sig);
+    }
+
+    for sig in [
+        "(Lorg/graalvm/compiler/virtual/phases/ea/PartialEscapeBlockState<TT;>;)V",
+        "<T:Ljava/lang/Object;>([TT;)[TT;",
+        "(Ljava/util/Collection<+TE;>;)Z^Ljava/io/IOException;^TX;",
+        "()V",
+    ].iter().copied() {
+        assert_eq!(MethodSignature::parse(sig).unwrap().to_string(), sig);
+    }
+
+    let inner = ClassSignature::parse("Ljava/lang/Object;Lcom/example/Outer<TT;>.Inner<*>;").unwrap();
+    assert_eq!(inner.interfaces[0].erasure(), "com/example/Outer$Inner");
+    assert_eq!(inner.interfaces[0].segments.len(), 2);
+
+    assert!(ClassSignature::parse("").is_err());
+    assert!(ClassSignature::parse("Ljava/lang/Object").is_err());
+    assert!(MethodSignature::parse("(I)").is_err());
+    assert!(MethodSignature::parse("(V)V").is_err());
+    assert!(Type::parse("Ljava/util/List<>;").is_err());
+}
+
+#[test] fn substitution() {
+    let array_list = ClassSignature::parse("<E:Ljava/lang/Object;>Ljava/util/AbstractList<TE;>;Ljava/util/List<TE;>;").unwrap();
+    let bindings = array_list.bind(&[TypeArgument::Exact(Type::parse("Ljava/lang/String;").unwrap())]).unwrap();
+    assert!(array_list.bind(&[]).is_err());
+
+    assert_eq!(substitute(&array_list.interfaces[0], &bindings).to_string(), "Ljava/util/List<Ljava/lang/String;>;");
+    assert_eq!(substitute(&MethodSignature::parse("(I)TE;").unwrap(), &bindings).to_string(), "(I)Ljava/lang/String;");
+    assert_eq!(substitute(&MethodSignature::parse("(Ljava/util/Collection<+TE;>;)Z").unwrap(), &bindings).to_string(), "(Ljava/util/Collection<+Ljava/lang/String;>;)Z");
+
+    // Method type parameters shadow class type parameters
+    assert_eq!(substitute(&MethodSignature::parse("<E:Ljava/lang/Object;>(TE;)TE;").unwrap(), &bindings).to_string(), "<E:Ljava/lang/Object;>(TE;)TE;");
+    assert_eq!(substitute(&MethodSignature::parse("<T:Ljava/lang/Object;>([TT;)[TT;").unwrap(), &bindings).to_string(), "<T:Ljava/lang/Object;>([TT;)[TT;");
+}
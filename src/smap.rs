@@ -0,0 +1,263 @@
+//! [JSR-45](https://jcp.org/en/jsr/detail?id=45) source maps (SMAPs), as stored in `SourceDebugExtension` attributes
+//! by Kotlin (inline functions), Groovy, and JSP compilers, mapping lines of the generated class back to lines of the
+//! original sources.  See [Class::smap](../struct.Class.html#method.smap).
+//!
+//! ```text
+//! SMAP
+//! Main.kt                             output file
+//! Kotlin                              default stratum
+//! *S Kotlin                           stratum
+//! *F                                  files:  "id name", or "+ id name" followed by a path
+//! + 1 Main.kt
+//! com/example/MainKt
+//! + 2 Strings.kt
+//! kotlin/text/StringsKt__StringsKt
+//! *L                                  lines:  "input[#file][,repeat]:output[,increment]"
+//! 1#1,12:1
+//! 97#2,4:13
+//! *E
+//! ```
+
+use std::io::{self, Error, ErrorKind};
+
+
+
+/// A parsed SMAP.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Smap {
+    /// The name of the generated source, such as `"Main.kt"`.
+    pub output_file:        String,
+
+    /// The id of the stratum debuggers should use by default, such as `"Kotlin"` or `"JSP"`.
+    pub default_stratum:    String,
+
+    /// The strata of the SMAP, in order.  Embedded SMAPs (`*O` ... `*C`) and vendor sections (`*V`) are skipped.
+    pub strata:             Vec<Stratum>,
+}
+
+/// A "stratum" of an [Smap](struct.Smap.html):  one view of which source lines the class's lines came from.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Stratum {
+    /// The id of the stratum, such as `"Kotlin"`, `"KotlinDebug"`, or `"JSP"`.
+    pub id:     String,
+
+    /// The input sources lines are mapped from.
+    pub files:  Vec<File>,
+
+    /// The line mappings, in order.
+    pub lines:  Vec<LineMapping>,
+}
+
+/// A source file of a [Stratum](struct.Stratum.html)'s `*F` section.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct File {
+    /// The id [LineMapping](struct.LineMapping.html)s refer to this file by.
+    pub id:     u32,
+
+    /// The name of the file, such as `"Strings.kt"`.
+    pub name:   String,
+
+    /// The path of the file, if given, such as `"kotlin/text/StringsKt__StringsKt"`.
+    pub path:   Option<String>,
+}
+
+/// A `*L` line of a [Stratum](struct.Stratum.html):  `repeat_count` input lines, each mapped to
+/// `output_increment` output lines.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LineMapping {
+    /// The first line of the input source.
+    pub input_start:        u32,
+
+    /// The [File](struct.File.html) id of the input source.  If omitted, the previous line's file is implied.
+    pub file_id:            u32,
+
+    /// How many consecutive input lines are mapped (1 if omitted.)
+    pub repeat_count:       u32,
+
+    /// The first line of the output (the class's `LineNumberTable`s) `input_start` maps to.
+    pub output_start:       u32,
+
+    /// How many output lines each input line maps to (1 if omitted.)
+    pub output_increment:   u32,
+}
+
+impl Smap {
+    /// Parse the text of an SMAP, such as the contents of a `SourceDebugExtension` attribute.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jreflection::smap::Smap;
+    /// let smap = Smap::parse("SMAP\nMain.kt\nKotlin\n*S Kotlin\n*F\n+ 1 Main.kt\ncom/example/MainKt\n+ 2 Strings.kt\nkotlin/text/StringsKt\n*L\n1#1,12:1\n97#2,4:13\n*E\n").unwrap();
+    /// let kotlin = smap.default_stratum().unwrap();
+    /// let (file, line) = kotlin.input_line(14).unwrap();
+    /// assert_eq!((file.name.as_str(), line), ("Strings.kt", 98));
+    /// ```
+    pub fn parse(text: &str) -> io::Result<Self> {
+        let invalid = |line: usize, reason: &str| Error::new(ErrorKind::InvalidData, format!("Invalid SMAP at line {}: {}", line + 1, reason));
+
+        let mut lines = text.lines().map(|line| line.trim_end()).enumerate().peekable();
+        match lines.next() { Some((_, "SMAP")) => {}, _ => return Err(invalid(0, "expected SMAP header")) }
+        let output_file     = lines.next().map(|(_, l)| l.to_owned()).ok_or_else(|| invalid(1, "expected output file name"))?;
+        let default_stratum = lines.next().map(|(_, l)| l.to_owned()).ok_or_else(|| invalid(2, "expected default stratum id"))?;
+
+        let mut strata = Vec::<Stratum>::new();
+        let mut stratum_ended = true;
+        while let Some((n, line)) = lines.next() {
+            if line.is_empty() { continue; }
+            if !line.starts_with('*') { return Err(invalid(n, "expected a section header")); }
+            let rest = &line[1..];
+            let split = rest.char_indices().nth(1).map_or(rest.len(), |(i, _)| i);
+            let (section, argument) = (&rest[..split], rest[split..].trim());
+            match section {
+                "S" => {
+                    if argument.is_empty() { return Err(invalid(n, "expected a stratum id")); }
+                    strata.push(Stratum { id: argument.to_owned(), files: Vec::new(), lines: Vec::new() });
+                    stratum_ended = false;
+                },
+                "F" | "L" => {
+                    let stratum = match strata.last_mut() { Some(s) if !stratum_ended => s, _ => return Err(invalid(n, "expected *S before section")) };
+                    let mut file_id = 0;
+                    while let Some(&(n, line)) = lines.peek() {
+                        if line.starts_with('*') { break; }
+                        lines.next();
+                        if line.is_empty() { continue; }
+                        if section == "F" {
+                            let (with_path, line) = if line.starts_with('+') { (true, line[1..].trim_start()) } else { (false, line) };
+                            let mut parts = line.splitn(2, ' ');
+                            let id = parts.next().and_then(|id| id.parse().ok()).ok_or_else(|| invalid(n, "expected a file id"))?;
+                            let name = parts.next().unwrap_or("").trim().to_owned();
+                            let path = if with_path { Some(lines.next().map(|(_, l)| l.to_owned()).ok_or_else(|| invalid(n + 1, "expected a file path"))?) } else { None };
+                            stratum.files.push(File { id, name, path });
+                        } else {
+                            let mapping = LineMapping::parse(line, file_id).ok_or_else(|| invalid(n, "expected input[#file][,repeat]:output[,increment]"))?;
+                            file_id = mapping.file_id;
+                            stratum.lines.push(mapping);
+                        }
+                    }
+                },
+                "E" => { stratum_ended = true; },
+                "O" => {
+                    // Skip embedded SMAPs, which can themselves nest
+                    let mut depth = 1;
+                    while depth > 0 {
+                        match lines.next() {
+                            Some((_, l)) if l.starts_with("*O") => depth += 1,
+                            Some((_, l)) if l.starts_with("*C") => depth -= 1,
+                            Some(_)                             => {},
+                            None                                => return Err(invalid(n, "unterminated embedded SMAP")),
+                        }
+                    }
+                },
+                _ => {
+                    // *V vendor sections, and future section types, which "must be ignored"
+                    while let Some(&(_, l)) = lines.peek() {
+                        if l.starts_with('*') { break; }
+                        lines.next();
+                    }
+                },
+            }
+        }
+
+        Ok(Self { output_file, default_stratum, strata })
+    }
+
+    /// The stratum with the id `id`, if any.
+    pub fn stratum(&self, id: &str) -> Option<&Stratum> { self.strata.iter().find(|s| s.id == id) }
+
+    /// The stratum debuggers use by default, if it's present.
+    pub fn default_stratum(&self) -> Option<&Stratum> { self.stratum(&self.default_stratum) }
+}
+
+impl Stratum {
+    /// The file with the id `id`, if any.
+    pub fn file(&self, id: u32) -> Option<&File> { self.files.iter().find(|f| f.id == id) }
+
+    /// The input file and line that output line `line` (as found in a `LineNumberTable`) was generated from, if mapped.
+    pub fn input_line(&self, line: u32) -> Option<(&File, u32)> {
+        self.lines.iter().find_map(|mapping| {
+            let offset = line.checked_sub(mapping.output_start)?;
+            let index = match offset.checked_div(mapping.output_increment) {
+                Some(index)             => index,
+                None if offset == 0     => 0, // An increment of 0 maps input lines to output_start alone
+                None                    => return None,
+            };
+            if index >= mapping.repeat_count { return None; }
+            Some((self.file(mapping.file_id)?, mapping.input_start + index))
+        })
+    }
+}
+
+impl File {
+    /// The path of the file if given, or else its name.
+    pub fn path(&self) -> &str { self.path.as_ref().map_or(self.name.as_str(), |p| p.as_str()) }
+}
+
+impl LineMapping {
+    fn parse(line: &str, previous_file_id: u32) -> Option<Self> {
+        let colon = line.find(':')?;
+        let (input, output) = (&line[..colon], &line[colon+1..]);
+        let (input, repeat_count) = split_number(input, ',', 1)?;
+        let (input_start, file_id) = split_number(input, '#', previous_file_id)?;
+        let (output_start, output_increment) = split_number(output, ',', 1)?;
+        Some(Self { input_start: input_start.parse().ok()?, file_id, repeat_count, output_start: output_start.parse().ok()?, output_increment })
+    }
+}
+
+/// Split `"a<separator>b"` into `("a", b)`, or `"a"` into `("a", default)`.
+fn split_number(text: &str, separator: char, default: u32) -> Option<(&str, u32)> {
+    match text.find(separator) {
+        Some(i) => Some((&text[..i], text[i+1..].trim().parse().ok()?)),
+        None    => Some((text, default)),
+    }
+}
+
+#[test] fn kotlin() {
+    let smap = Smap::parse(concat!(
+        "SMAP\r\n",
+        "Main.kt\r\n",
+        "Kotlin\r\n",
+        "*S Kotlin\r\n",
+        "*F\r\n",
+        "+ 1 Main.kt\r\n",
+        "com/example/MainKt\r\n",
+        "+ 2 _Collections.kt\r\n",
+        "kotlin/collections/CollectionsKt___CollectionsKt\r\n",
+        "*L\r\n",
+        "1#1,10:1\r\n",
+        "1549#2,2:11\r\n",
+        "20:13,3\r\n",
+        "*E\r\n",
+        "*S KotlinDebug\r\n",
+        "*F\r\n",
+        "+ 1 Main.kt\r\n",
+        "com/example/MainKt\r\n",
+        "*L\r\n",
+        "5#1:11,2\r\n",
+        "*E\r\n",
+    )).unwrap();
+
+    assert_eq!((smap.output_file.as_str(), smap.default_stratum.as_str()), ("Main.kt", "Kotlin"));
+    assert_eq!(smap.strata.iter().map(|s| s.id.as_str()).collect::<Vec<_>>(), &["Kotlin", "KotlinDebug"]);
+    let kotlin = smap.default_stratum().unwrap();
+    assert_eq!(kotlin.files[1], File { id: 2, name: "_Collections.kt".to_owned(), path: Some("kotlin/collections/CollectionsKt___CollectionsKt".to_owned()) });
+    assert_eq!(kotlin.lines[2], LineMapping { input_start: 20, file_id: 2, repeat_count: 1, output_start: 13, output_increment: 3 });
+
+    let line = |stratum: &Stratum, line| stratum.input_line(line).map(|(file, line)| (file.name.clone(), line));
+    assert_eq!(line(kotlin, 1),  Some(("Main.kt".to_owned(), 1)));
+    assert_eq!(line(kotlin, 10), Some(("Main.kt".to_owned(), 10)));
+    assert_eq!(line(kotlin, 12), Some(("_Collections.kt".to_owned(), 1550)));
+    assert_eq!(line(kotlin, 15), Some(("_Collections.kt".to_owned(), 20)));
+    assert_eq!(line(kotlin, 16), None);
+    assert_eq!(line(smap.stratum("KotlinDebug").unwrap(), 12), Some(("Main.kt".to_owned(), 5)));
+
+    // JSP-style, with an unprefixed file, a vendor section, and an embedded SMAP
+    let jsp = Smap::parse("SMAP\nindex_jsp.java\nJSP\n*O Inner\nSMAP\nx\ny\n*C Inner\n*S JSP\n*V\nvendor data\n*F\n0 index.jsp\n*L\n1,5:10,2\n*E\n").unwrap();
+    let stratum = jsp.default_stratum().unwrap();
+    assert_eq!(stratum.files, &[File { id: 0, name: "index.jsp".to_owned(), path: None }]);
+    assert_eq!(stratum.input_line(13).map(|(f, l)| (f.path(), l)), Some(("index.jsp", 2)));
+
+    assert!(Smap::parse("").is_err());
+    assert!(Smap::parse("SMAP\nMain.kt\nKotlin\n*F\n1 Main.kt\n").is_err());
+    assert!(Smap::parse("SMAP\nMain.kt\nKotlin\n*S Kotlin\n*L\nbogus\n").is_err());
+}
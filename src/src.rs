@@ -1,24 +1,185 @@
 //! Sources of JVM metadata such as .jars, jimage files, etc.
 
-use crate::Class;
-use zip::ZipArchive;
+use crate::{android, CancellationToken, Class, ParseOptions};
+#[cfg(all(feature = "jimage", feature = "writer"))] use crate::jar;
+#[cfg(feature = "jar")] use zip::ZipArchive;
+#[cfg(feature = "jar")] use zip::result::ZipError;
 use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+#[cfg(feature = "jar")] use std::collections::HashMap;
 use std::default::Default;
-use std::fs::File;
-use std::ffi::*;
-use std::io::{BufReader, Cursor, Error, ErrorKind, Result};
+#[cfg(any(feature = "jar", feature = "jimage"))] use std::fs::File;
+#[cfg(feature = "jimage")] use std::ffi::*;
+use std::fmt::{self, Debug, Formatter};
+use std::io::{Error, ErrorKind, Result};
+#[cfg(any(feature = "jar", feature = "jimage"))] use std::io::{BufReader, Read};
+#[cfg(feature = "jar")] use std::io::{Cursor, Seek, SeekFrom};
 use std::iter::Extend;
 use std::path::*;
+use std::sync::Arc;
+
+/// How [Source::export_modules](struct.Source.html#method.export_modules) lays out the exported resources.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportLayout {
+    /// Write each resource to `{dir}/{module}/{path}`, like `jimage extract`.
+    Directories,
+
+    /// Write each module to a deterministic `{dir}/{module}.jar` (see [jar::Writer](jar/struct.Writer.html).)  Requires the `writer` feature.
+    Jars,
+}
+
+/// How a [Source](struct.Source.html) reads the file backing it.
+///
+/// This only affects `.jar` sources:  jimage `modules` files are always read by the JDK's own `jimage` library, which
+/// memory maps them itself.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IoStrategy {
+    /// Pick a strategy based on the file:  jars smaller than [AUTO_IN_MEMORY_JAR_LIMIT] (16 MB) are read fully into
+    /// memory (a single sequential read, then no further IO), larger jars are streamed with [Buffered].
+    ///
+    /// [AUTO_IN_MEMORY_JAR_LIMIT]: #associatedconstant.AUTO_IN_MEMORY_JAR_LIMIT
+    /// [Buffered]:                 #variant.Buffered
+    Auto,
+
+    /// Read the file on demand through a `BufReader`.  Uses little memory, but seeks for every class read, which adds
+    /// up for many small reads on spinning disks.
+    Buffered,
+
+    /// Read the entire file into memory up front.  Best for many small jars, or when every class will be read anyway.
+    ///
+    /// To memory map a jar instead, see [Source::from_jar_mmap](struct.Source.html#method.from_jar_mmap).
+    InMemory,
+}
+
+impl Default for IoStrategy {
+    fn default() -> Self { IoStrategy::Auto }
+}
+
+impl IoStrategy {
+    /// The size below which [Auto](#variant.Auto) reads a `.jar` fully into memory.
+    ///
+    /// This isn't a speed crossover:  with the file in the OS's cache, the `io_strategy` benchmark (see `benches/`)
+    /// finds [InMemory](#variant.InMemory) 1.3-2x faster than [Buffered](#variant.Buffered) at every size measured
+    /// (0.3 to 38 MB), whether reading every class or just ten of them.  Instead, it caps how much memory `Auto`
+    /// commits per `Source`:  typical library jars fit, while the likes of JDK 8's `rt.jar` (~60 MB) or large "fat"
+    /// jars are streamed rather than held in memory for as long as their `Source` lives.
+    ///
+    /// There's no jimage counterpart:  `modules` files are always memory mapped by the JDK's `jimage` library.
+    pub const AUTO_IN_MEMORY_JAR_LIMIT : u64 = 16 * 1024 * 1024;
+}
+
+type NameDecoder = Arc<dyn Fn(&[u8]) -> Option<String> + Send + Sync>;
+
+/// How a `.jar` [Source](struct.Source.html) decodes entry names (and thus class names.)  See
+/// [Source::set_entry_name_decoding](struct.Source.html#method.set_entry_name_decoding).
+///
+/// The zip format only has a flag for marking names as UTF-8, otherwise names are supposedly CP437.  In practice, old
+/// Java tools wrote UTF-8 names without setting the flag, and some archivers wrote names in the system codepage, such
+/// as Shift-JIS.
+#[derive(Clone)]
+pub enum EntryNameDecoding {
+    /// Decode names as the zip format says to:  UTF-8 if flagged as such, CP437 otherwise.
+    Zip,
+
+    /// Decode names as UTF-8 when they're valid UTF-8 (flagged or not), falling back on CP437 otherwise.
+    Utf8OrCp437,
+
+    /// Decode names as UTF-8 when they're valid UTF-8 (flagged or not), and with a custom decoder (e.g. for Shift-JIS)
+    /// otherwise.  If the decoder returns `None`, falls back on lossy UTF-8 decoding.
+    Custom(NameDecoder),
+}
+
+impl Default for EntryNameDecoding {
+    fn default() -> Self { EntryNameDecoding::Zip }
+}
+
+impl Debug for EntryNameDecoding {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        match self {
+            EntryNameDecoding::Zip          => write!(fmt, "Zip"),
+            EntryNameDecoding::Utf8OrCp437  => write!(fmt, "Utf8OrCp437"),
+            EntryNameDecoding::Custom(_)    => write!(fmt, "Custom(..)"),
+        }
+    }
+}
+
+#[cfg(feature = "jar")]
+impl EntryNameDecoding {
+    /// Decode an entry name, given its raw bytes and zip's own decoding.  Returns `true` if a fallback was used.
+    pub(crate) fn decode(&self, raw: &[u8], zip: &str) -> (String, bool) {
+        if let EntryNameDecoding::Zip = self { return (zip.to_owned(), false); }
+        if let Ok(utf8) = std::str::from_utf8(raw) { return (utf8.to_owned(), false); }
+        match self {
+            EntryNameDecoding::Custom(decode)   => (decode(raw).unwrap_or_else(|| String::from_utf8_lossy(raw).into_owned()), true),
+            _                                   => (zip.to_owned(), true),
+        }
+    }
+}
 
 enum SourceInt {
-    Jar(Jar),
+    #[cfg(feature = "jar")]
+    Jar(Box<Jar>),
+    #[cfg(feature = "jimage")]
     JImage(JImage),
+    Overlay { base: Box<Source>, patch: Box<Source> },
 }
 
 /// Represents a source of class data, typically a `.jar` or `modules` (jimage-format) file.
-pub struct Source(SourceInt);
+pub struct Source {
+    int:        SourceInt,
+    cancel:     Option<CancellationToken>,
+    skipped:    Option<RefCell<SkipReport>>,
+}
+
+/// Content a [Source] skipped over instead of reporting as classes, as collected once enabled by
+/// [Source::set_skip_reporting](struct.Source.html#method.set_skip_reporting).
+///
+/// [Source]:   struct.Source.html
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SkipReport {
+    /// Non-class entries (images, `.properties` files, `META-INF/MANIFEST.MF`, etc.) skipped when enumerating classes.
+    pub resources:          BTreeSet<String>,
+
+    /// Entry names that weren't valid UTF-8, as decoded by the fallback of the source's [EntryNameDecoding], with their
+    /// raw bytes, for checking for mojibake.  Always empty for [EntryNameDecoding::Zip].
+    ///
+    /// [EntryNameDecoding]:        enum.EntryNameDecoding.html
+    /// [EntryNameDecoding::Zip]:   enum.EntryNameDecoding.html#variant.Zip
+    pub fallback_names:     BTreeMap<String, Vec<u8>>,
+
+    /// Modules whose `module-info` class was skipped when enumerating classes, as it describes a module rather than a class.
+    pub module_infos:       BTreeSet<String>,
+
+    /// Attributes jreflection didn't recognize in classes read from the source, by attribute name, and the classes
+    /// (including via their fields or methods) they were found in.
+    pub unknown_attributes: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl SkipReport {
+    /// Was nothing skipped?
+    pub fn is_empty(&self) -> bool { self.resources.is_empty() && self.fallback_names.is_empty() && self.module_infos.is_empty() && self.unknown_attributes.is_empty() }
+
+    /// Record the unknown attributes of `class`, and of its fields and methods.
+    pub fn add_class(&mut self, class: &Class) {
+        let fields  = class.fields .iter().flat_map(|f| f.unknown_attribute_names());
+        let methods = class.methods.iter().flat_map(|m| m.unknown_attribute_names());
+        for name in class.unknown_attribute_names().chain(fields).chain(methods) {
+            self.unknown_attributes.entry(name.to_owned()).or_default().insert(class.path.as_str().to_owned());
+        }
+    }
+
+    /// Merge another report into this one.
+    pub fn extend(&mut self, other: SkipReport) {
+        self.resources.extend(other.resources);
+        self.fallback_names.extend(other.fallback_names);
+        self.module_infos.extend(other.module_infos);
+        for (name, classes) in other.unknown_attributes { self.unknown_attributes.entry(name).or_default().extend(classes); }
+    }
+}
 
 impl Source {
+    fn new(int: SourceInt) -> Self { Self { int, cancel: None, skipped: None } }
+
     /// Open a specific `.jar` file
     /// 
     /// # Examples
@@ -33,8 +194,97 @@ impl Source {
     ///     let src = Source::from_jar(path).unwrap();
     /// }
     /// ```
+    #[cfg(feature = "jar")]
     pub fn from_jar(path: impl AsRef<Path>) -> Result<Self> {
-        Ok(Self(SourceInt::Jar(Jar::open(path)?)))
+        Self::from_jar_with(path, IoStrategy::default())
+    }
+
+    /// Open a specific `.jar` file, reading it with a specific [IoStrategy](enum.IoStrategy.html)
+    ///
+    /// Android device files (`.dex`, `.oat`, `.vdex`, `.art`, and jars of dex bytecode) fail with an error naming the
+    /// format.  See [android::DeviceFormat](android/enum.DeviceFormat.html).
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use jreflection::{IoStrategy, Source};
+    /// let huge = Source::from_jar_with("huge.jar", IoStrategy::Buffered).unwrap();
+    /// let tiny = Source::from_jar_with("tiny.jar", IoStrategy::InMemory).unwrap();
+    /// ```
+    #[cfg(feature = "jar")]
+    pub fn from_jar_with(path: impl AsRef<Path>, io: IoStrategy) -> Result<Self> {
+        let path = path.as_ref();
+        Self::from_opened_jar(path, Jar::open(path, io))
+    }
+
+    /// Open a specific `.jar` file by memory mapping it, letting the OS page it in on demand.  Requires the `mmap`
+    /// feature.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified or truncated - by this process or any other - while the returned `Source` is
+    /// alive.  Doing so is undefined behavior, as it changes memory the `Source` is reading out from under it.  Only
+    /// map files you control, such as a JDK or SDK installation nothing else writes to.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use jreflection::Source;
+    /// // Safe as long as nothing rewrites rt.jar while `src` is alive
+    /// let src = unsafe { Source::from_jar_mmap(r"C:\Program Files\Java\jre1.8.0_241\lib\rt.jar") }.unwrap();
+    /// ```
+    #[cfg(feature = "mmap")]
+    #[allow(unsafe_code)]
+    pub unsafe fn from_jar_mmap(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let map = memmap2::Mmap::map(&File::open(path)?)?; // Sound given the caller upholds the contract documented above
+        Self::from_opened_jar(path, Jar::from_reader(JarReader::Mmap(Cursor::new(map))))
+    }
+
+    #[cfg(feature = "jar")]
+    fn from_opened_jar(path: &Path, jar: Result<Jar>) -> Result<Self> {
+        let jar = jar.map_err(|err| match android::DeviceFormat::detect_file(path) {
+            Ok(Some(format))    => format.unsupported(path),
+            _                   => err,
+        })?;
+        if jar.is_dex_only() { return Err(android::DeviceFormat::DexJar.unsupported(path)); }
+        Ok(Self::new(SourceInt::Jar(Box::new(jar))))
+    }
+
+    /// Open a remote `.jar` over HTTP(S), without downloading all of it.  Requires the `http` feature.
+    ///
+    /// The server must support range requests:  the central directory at the end of the jar is fetched first, then
+    /// each class is fetched as it's read, in 64 KB blocks (the most recent 4 MB of which are cached.)  Enumerating
+    /// classes only needs the central directory, but reading every class of a jar downloads nearly all of it, in many
+    /// small requests - download the jar instead if you'll read most of it.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use jreflection::Source;
+    /// let src = Source::from_jar_url("https://repo1.maven.org/maven2/com/google/guava/guava/31.1-jre/guava-31.1-jre.jar").unwrap();
+    /// let classes = src.classes::<Vec<String>>().unwrap();
+    /// let cache = src.read_class("com/google/common/cache/Cache").unwrap();
+    /// ```
+    #[cfg(feature = "http")]
+    pub fn from_jar_url(url: &str) -> Result<Self> {
+        Self::from_jar_storage(crate::storage::Http::open(url)?)
+    }
+
+    /// Open a `.jar` stored in arbitrary random access storage, such as a blob store.  See [storage](storage/index.html).
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use jreflection::*;
+    /// # #[cfg(feature = "http")] {
+    /// let token = std::env::var("GCS_ACCESS_TOKEN").ok();
+    /// let src = Source::from_jar_storage(storage::Http::gcs("my-artifacts", "releases/app-1.0.jar", token.as_ref().map(|t| t.as_str())).unwrap()).unwrap();
+    /// # }
+    /// ```
+    #[cfg(feature = "jar")]
+    pub fn from_jar_storage(storage: impl crate::storage::ReadAt + 'static) -> Result<Self> {
+        Ok(Self::new(SourceInt::Jar(Box::new(Jar::from_reader(JarReader::Storage(crate::storage::BlockReader::new(Box::new(storage))?))?))))
     }
 
     /// Open a JDK or JRE directory
@@ -53,30 +303,193 @@ impl Source {
     /// }
     /// ```
     pub fn from_jdk_dir(path: impl AsRef<Path>) -> Result<Self> {
+        Self::from_jdk_dir_with(path, IoStrategy::default())
+    }
+
+    /// Open a JDK or JRE directory, reading `rt.jar` (if that's what's found) with a specific [IoStrategy](enum.IoStrategy.html)
+    pub fn from_jdk_dir_with(path: impl AsRef<Path>, io: IoStrategy) -> Result<Self> {
         let path = path.as_ref();
         let modules = path.join("lib").join("modules");
-        if modules.exists() {
-            let jimage = path.join("bin").join(jimage::Library::NAME);
-            if jimage.exists() {
-                return Self::from_jimage_modules(jimage, modules);
-            } else {
-                return Err(Error::new(ErrorKind::InvalidInput, format!("JDK or JRE contains lib/modules, but no bin/{} to read it with: {}", jimage::Library::NAME, path.display())));
+        #[cfg(feature = "jimage")]
+        {
+            if modules.exists() {
+                // Windows JDKs ship the library in bin/, unix JDKs in lib/
+                match ["bin", "lib"].iter().map(|dir| path.join(dir).join(jimage::Library::NAME)).find(|jimage| jimage.exists()) {
+                    Some(jimage) => return Self::from_jimage_modules(jimage, modules),
+                    None => return Err(Error::new(ErrorKind::InvalidInput, format!("JDK or JRE contains lib/modules, but no bin/{0} or lib/{0} to read it with: {1}", jimage::Library::NAME, path.display()))),
+                }
+            }
+        }
+        #[cfg(not(feature = "jimage"))]
+        {
+            if modules.exists() {
+                return Err(Error::new(ErrorKind::InvalidInput, format!("JDK or JRE contains lib/modules, which requires jreflection's \"jimage\" feature to read: {}", path.display())));
             }
         }
 
-        let rt_jar = path.join("jre").join("lib").join("rt.jar");
-        if rt_jar.exists() {
-            return Self::from_jar(rt_jar);
+        for rt_jar in [path.join("jre").join("lib").join("rt.jar"), path.join("lib").join("rt.jar")].iter() {
+            if rt_jar.exists() {
+                #[cfg(feature = "jar")]
+                return Self::from_jar_with(rt_jar, io);
+                #[cfg(not(feature = "jar"))]
+                {
+                    let _ = io;
+                    return Err(Error::new(ErrorKind::InvalidInput, format!("JDK or JRE contains rt.jar, which requires jreflection's \"jar\" feature to read: {}", path.display())));
+                }
+            }
         }
 
-        let rt_jar = path.join("lib").join("rt.jar");
-        if rt_jar.exists() {
-            return Self::from_jar(rt_jar);
+        if let Some((file, format)) = android::DeviceFormat::find_in_dir(path, 2) {
+            return Err(Error::new(ErrorKind::InvalidInput, format!("{} looks like an Android device directory rather than a JDK or JRE: {}", path.display(), format.unsupported(&file))));
         }
 
         Err(Error::new(ErrorKind::InvalidInput, format!("Unable to find lib/modules, jre/lib/rt.jar, or lib/rt.jar in: {}", path.display())))
     }
 
+    /// Combine two sources, with classes in `patch` shadowing classes of the same name in `base` - like
+    /// `-Xbootclasspath/p:` or `--patch-module` - to see what an API would look like with `patch` applied.
+    ///
+    /// Each source keeps its own cancellation and skip reporting settings, in addition to any set on the overlay.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use jreflection::*;
+    /// # #[cfg(feature = "jar")] {
+    /// let jdk     = Source::from_jdk_dir(r"C:\Program Files\AdoptOpenJDK\jdk-8.0.232.09-hotspot").unwrap();
+    /// let patch   = Source::from_jar("string-patch.jar").unwrap();
+    /// let patched = Source::overlay(jdk, patch);
+    /// let string  = patched.read_class("java/lang/String").unwrap(); // From string-patch.jar
+    /// let object  = patched.read_class("java/lang/Object").unwrap(); // From the JDK
+    /// # }
+    /// ```
+    pub fn overlay(base: Source, patch: Source) -> Self {
+        Self::new(SourceInt::Overlay { base: Box::new(base), patch: Box::new(patch) })
+    }
+
+    /// The `(base, patch)` sources of an [overlay](#method.overlay), if this source is one.
+    pub fn as_overlay(&self) -> Option<(&Source, &Source)> {
+        match &self.int {
+            SourceInt::Overlay { base, patch }  => Some((base, patch)),
+            #[cfg(any(feature = "jar", feature = "jimage"))]
+            _                                   => None,
+        }
+    }
+
+    /// Abort any operation reading from this source once `token` is cancelled.  See [CancellationToken].
+    ///
+    /// [CancellationToken]:    struct.CancellationToken.html
+    pub fn set_cancellation(&mut self, token: CancellationToken) { self.cancel = Some(token); }
+
+    fn check_cancelled(&self) -> Result<()> {
+        match self.cancel.as_ref() {
+            Some(token) => token.check(),
+            None        => Ok(()),
+        }
+    }
+
+    /// Change how entry names of a `.jar` source are decoded (see [EntryNameDecoding]).  Has no effect on jimage
+    /// sources, whose names are always UTF-8.  Applies to both layers of an [overlay](#method.overlay).
+    ///
+    /// [EntryNameDecoding]:    enum.EntryNameDecoding.html
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use jreflection::*;
+    /// # #[cfg(feature = "jar")] {
+    /// let mut src = Source::from_jar("legacy.jar").unwrap();
+    /// src.set_entry_name_decoding(EntryNameDecoding::Utf8OrCp437);
+    /// src.set_skip_reporting(true);
+    /// let classes = src.classes::<Vec<String>>().unwrap();
+    /// for (name, raw) in src.skip_report().fallback_names.iter() {
+    ///     eprintln!("warning: {:?} isn't UTF-8, decoded as {:?}", raw, name);
+    /// }
+    /// # }
+    /// ```
+    #[cfg(any(feature = "jar", feature = "jimage"))]
+    pub fn set_entry_name_decoding(&mut self, decoding: EntryNameDecoding) {
+        match &mut self.int {
+            #[cfg(feature = "jar")]
+            SourceInt::Jar(jar)                 => jar.set_name_decoding(decoding),
+            #[cfg(feature = "jimage")]
+            SourceInt::JImage(_)                => {},
+            SourceInt::Overlay { base, patch }  => {
+                patch.set_entry_name_decoding(decoding.clone());
+                base.set_entry_name_decoding(decoding);
+            },
+        }
+    }
+
+    /// Start (or stop) collecting a [SkipReport] of everything skipped by [for_each_class](#method.for_each_class) (and
+    /// everything built on it), and of unknown attributes in classes read via [read_class](#method.read_class) and friends.
+    /// Disabled by default, as it costs a little extra time and memory.
+    ///
+    /// [SkipReport]:   struct.SkipReport.html
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use jreflection::*;
+    /// # #[cfg(feature = "jar")] {
+    /// let mut src = Source::from_jar("app.jar").unwrap();
+    /// src.set_skip_reporting(true);
+    /// let scan = scan::ScanResult::scan(std::slice::from_ref(&src)).unwrap();
+    /// let skipped = src.skip_report();
+    /// for resource in skipped.resources.iter() { println!("resource: {}", resource); }
+    /// for (attribute, classes) in skipped.unknown_attributes.iter() { println!("unknown attribute {} in {} classes", attribute, classes.len()); }
+    /// # }
+    /// ```
+    pub fn set_skip_reporting(&mut self, enabled: bool) {
+        self.skipped = if enabled { Some(self.skipped.take().unwrap_or_default()) } else { None };
+    }
+
+    /// Everything skipped so far, if enabled via [set_skip_reporting](#method.set_skip_reporting), or an empty report otherwise.
+    pub fn skip_report(&self) -> SkipReport {
+        self.skipped.as_ref().map(|r| r.borrow().clone()).unwrap_or_default()
+    }
+
+    /// The underlying `.jar`, if this source is one, for `.jar` specific functionality such as listing every entry.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # let src = jreflection::Source::from_jar("app.jar").unwrap();
+    /// if let Some(jar) = src.as_jar() {
+    ///     let manifest = jar.read_entry("META-INF/MANIFEST.MF").unwrap();
+    ///     for entry in jar.entries().unwrap() {
+    ///         println!("{}: {} -> {} bytes", entry.name, entry.size, entry.compressed_size);
+    ///     }
+    /// }
+    /// ```
+    #[cfg(feature = "jar")]
+    pub fn as_jar(&self) -> Option<&Jar> {
+        match &self.int {
+            #[cfg(feature = "jar")]
+            SourceInt::Jar(jar) => Some(jar),
+            _                   => None,
+        }
+    }
+
+    /// The underlying jimage, if this source is one, for jimage specific functionality such as module information.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # let src = jreflection::Source::from_jdk_dir(r"C:\Program Files\AdoptOpenJDK\jdk-11.0.5.10-hotspot").unwrap();
+    /// if let Some(jimage) = src.as_jimage() {
+    ///     assert_eq!(jimage.module_of("java/lang/Object").unwrap(), "java.base");
+    ///     println!("modules: {:?}", jimage.modules().unwrap());
+    /// }
+    /// ```
+    #[cfg(feature = "jimage")]
+    pub fn as_jimage(&self) -> Option<&JImage> {
+        match &self.int {
+            SourceInt::JImage(jimage)   => Some(jimage),
+            _                           => None,
+        }
+    }
+
     /// Read class metadata given a JNI path
     /// 
     /// # Examples
@@ -86,13 +499,128 @@ impl Source {
     /// let object = src.read_class("java/lang/Object").unwrap();
     /// ```
     pub fn read_class(&self, path: impl AsRef<str>) -> Result<Class> {
+        self.read_class_with(path, &ParseOptions::default())
+    }
+
+    /// Read class metadata given a JNI path, with custom [ParseOptions](struct.ParseOptions.html)
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # #[cfg(feature = "jar")] {
+    /// # let src = jreflection::Source::from_jar("aspectjrt.jar").unwrap();
+    /// let mut options = jreflection::ParseOptions::new();
+    /// options.register_attribute("org.aspectj.weaver.WeaverVersion", |info, _| Ok(info.to_vec()));
+    /// let class = src.read_class_with("org/aspectj/lang/Aspects", &options).unwrap();
+    /// let version = class.custom_attribute::<Vec<u8>>("org.aspectj.weaver.WeaverVersion");
+    /// # }
+    /// ```
+    pub fn read_class_with(&self, path: impl AsRef<str>, options: &ParseOptions) -> Result<Class> {
+        self.read_class_and_bytes(path, options).map(|(class, _bytes)| class)
+    }
+
+    /// Read the raw, unparsed .class file bytes given a JNI path
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # let src = jreflection::Source::from_jdk_dir(r"C:\Program Files\AdoptOpenJDK\jdk-8.0.232.09-hotspot").unwrap();
+    /// let bytes = src.read_class_bytes("java/lang/Object").unwrap();
+    /// assert_eq!(&bytes[..4], &[0xCA, 0xFE, 0xBA, 0xBE]);
+    /// ```
+    pub fn read_class_bytes(&self, path: impl AsRef<str>) -> Result<Vec<u8>> {
         let path = path.as_ref();
-        match &self.0 {
-            SourceInt::Jar(jar)     => jar.read_class(path),
-            SourceInt::JImage(img)  => img.read_class(path),
+        self.check_cancelled()?;
+        match &self.int {
+            #[cfg(feature = "jar")]
+            SourceInt::Jar(jar)     => jar.read_class_bytes(path),
+            #[cfg(feature = "jimage")]
+            SourceInt::JImage(img)  => img.read_class_bytes(path),
+            SourceInt::Overlay { base, patch } => match patch.read_class_bytes(path) {
+                Err(ref err) if err.kind() == ErrorKind::NotFound => base.read_class_bytes(path),
+                other => other,
+            },
+        }
+    }
+
+    /// Read the bytes of a resource named by a `jrt:/` URI (such as `"jrt:/java.base/java/lang/Object.class"`) or a
+    /// jrt filesystem path (such as `"/modules/java.base/java/lang/Object.class"`), as used by Java's own `jrt:`
+    /// filesystem.  Requires a jimage (JDK 9+ `lib/modules`) source, or an overlay of one.
+    ///
+    /// Like `--patch-module`, a `.jar` overlaid on a jimage patches classes of a single module:  the module it declares
+    /// if it's a modular `.jar`, or else the module the jimage places each class's package in.  Its classes are only
+    /// used for paths within that module.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # let src = jreflection::Source::from_jdk_dir(r"C:\Program Files\AdoptOpenJDK\jdk-11.0.6.10-hotspot").unwrap();
+    /// let object = src.read_jrt("jrt:/java.base/java/lang/Object.class").unwrap();
+    /// assert_eq!(object, src.read_class_bytes("java/lang/Object").unwrap());
+    /// ```
+    pub fn read_jrt(&self, uri: &str) -> Result<Vec<u8>> {
+        self.check_cancelled()?;
+        match &self.int {
+            #[cfg(feature = "jar")]
+            SourceInt::Jar(_)       => Err(Error::new(ErrorKind::InvalidInput, format!("Can't read {}:  jrt paths require a jimage source, not a jar", uri))),
+            #[cfg(feature = "jimage")]
+            SourceInt::JImage(img)  => img.read_jrt(uri),
+            #[cfg(not(feature = "jimage"))]
+            SourceInt::Overlay { .. } => Err(Error::new(ErrorKind::InvalidInput, format!("Can't read {}:  jrt paths require a jimage source", uri))),
+            #[cfg(feature = "jimage")]
+            SourceInt::Overlay { base, patch } => {
+                if base.base_jimage().is_none() {
+                    return Err(Error::new(ErrorKind::InvalidInput, format!("Can't read {}:  jrt paths require an overlay of a jimage source", uri)));
+                }
+                let jrt = crate::jrt::JrtPath::parse(uri)?;
+                let patched = match (&patch.int, jrt.class()) {
+                    #[cfg(feature = "jar")]
+                    (SourceInt::Jar(jar), Some(class)) => match patch_module(jar, base, class)? {
+                        Some(ref module) if *module == jrt.module   => patch.read_class_bytes(class),
+                        _                                           => Err(Error::new(ErrorKind::NotFound, format!("{} isn't patched", uri))),
+                    },
+                    #[cfg(feature = "jar")]
+                    (SourceInt::Jar(_), None)           => Err(Error::new(ErrorKind::NotFound, format!("{} isn't a class", uri))),
+                    _                                   => patch.read_jrt(uri),
+                };
+                match patched {
+                    Err(ref err) if err.kind() == ErrorKind::NotFound => base.read_jrt(uri),
+                    other => other,
+                }
+            },
         }
     }
 
+    /// The jimage this source is, or is an overlay (or overlay of overlays) on top of.
+    #[cfg(feature = "jimage")]
+    fn base_jimage(&self) -> Option<&JImage> {
+        match &self.int {
+            #[cfg(feature = "jar")]
+            SourceInt::Jar(_)               => None,
+            SourceInt::JImage(img)          => Some(img),
+            SourceInt::Overlay { base, .. } => base.base_jimage(),
+        }
+    }
+
+    /// Read class metadata given a JNI path, alongside the raw .class file bytes it was parsed from.
+    ///
+    /// This only looks up the class once, so tools that hash, archive, or re-emit classes don't pay for a second lookup.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # let src = jreflection::Source::from_jdk_dir(r"C:\Program Files\AdoptOpenJDK\jdk-8.0.232.09-hotspot").unwrap();
+    /// let options = jreflection::ParseOptions::default();
+    /// let (object, bytes) = src.read_class_and_bytes("java/lang/Object", &options).unwrap();
+    /// ```
+    pub fn read_class_and_bytes(&self, path: impl AsRef<str>, options: &ParseOptions) -> Result<(Class, Vec<u8>)> {
+        let path = path.as_ref();
+        let bytes = self.read_class_bytes(path)?;
+        let class = Class::read_with(&mut &bytes[..], options).map_err(|e| Error::new(e.kind(), format!("Failed to parse class {:?}: {}", path, e)))?;
+        if let Some(skipped) = self.skipped.as_ref() { skipped.borrow_mut().add_class(&class); }
+        Ok((class, bytes))
+    }
+
     /// Enumerate all classes contained within this source
     /// 
     /// # Examples
@@ -109,12 +637,35 @@ impl Source {
     /// }).is_err();
     /// ```
     pub fn for_each_class(&self, mut f: impl FnMut(String) -> Result<()>) -> Result<()> {
-        match &self.0 {
-            SourceInt::Jar(jar)     => jar.for_each_class(|c| f(c.into())),
-            SourceInt::JImage(img)  => img.for_each_class(|c| f(c.into())),
+        self.for_each_class_dyn(&mut f)
+    }
+
+    // Not generic, as overlays recurse
+    fn for_each_class_dyn(&self, f: &mut dyn FnMut(String) -> Result<()>) -> Result<()> {
+        self.check_cancelled()?;
+        match &self.int {
+            #[cfg(feature = "jar")]
+            SourceInt::Jar(jar)     => self.collect_skips(|skipped| jar.for_each_class(skipped, |c| { self.check_cancelled()?; f(c) })),
+            #[cfg(feature = "jimage")]
+            SourceInt::JImage(img)  => self.collect_skips(|skipped| img.for_each_class(skipped, |c| { self.check_cancelled()?; f(c) })),
+            SourceInt::Overlay { base, patch } => {
+                let mut patched = HashSet::new();
+                patch.for_each_class_dyn(&mut |c| { self.check_cancelled()?; patched.insert(c.clone()); f(c) }).and_then(|()|{
+                    base.for_each_class_dyn(&mut |c| { self.check_cancelled()?; if patched.contains(&c) { Ok(()) } else { f(c) } })
+                })
+            },
         }
     }
 
+    #[cfg(any(feature = "jar", feature = "jimage"))]
+    fn collect_skips(&self, enumerate: impl FnOnce(Option<&mut SkipReport>) -> Result<()>) -> Result<()> {
+        // Collect skips locally, as enumeration may read classes, which also records skips
+        let mut skipped = self.skipped.as_ref().map(|_| SkipReport::default());
+        let result = enumerate(skipped.as_mut());
+        if let (Some(report), Some(skipped)) = (self.skipped.as_ref(), skipped) { report.borrow_mut().extend(skipped); }
+        result
+    }
+
     /// Collect all classes contained within this source
     /// 
     /// # Examples
@@ -134,32 +685,357 @@ impl Source {
         Ok(collection)
     }
 
+    /// Find every class with a `public static void main(String[])` method (see [Class::has_main_method](struct.Class.html#method.has_main_method)), by JNI path.
+    ///
+    /// This parses every class, so it's not cheap for large sources.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # #[cfg(feature = "jar")] {
+    /// # let src = jreflection::Source::from_jar("app.jar").unwrap();
+    /// for class in src.find_main_classes().unwrap() {
+    ///     println!("Main-Class: {}", class.replace('/', "."));
+    /// }
+    /// # }
+    /// ```
+    pub fn find_main_classes(&self) -> Result<Vec<String>> {
+        let mut mains = Vec::new();
+        self.for_each_class(|name|{
+            if self.read_class(&name)?.has_main_method() { mains.push(name); }
+            Ok(())
+        })?;
+        Ok(mains)
+    }
+
+    /// Find classes with names similar to `query`, best matches first, for "quick open" style searches.
+    ///
+    /// Matching is case-insensitive, and `query` may use `.` or `/` as package separators.  Classes are ranked by:
+    /// 1.  Simple names matching `query` exactly (`"arraylist"` finds `java/util/ArrayList` first.)
+    /// 2.  Simple names starting with `query`.
+    /// 3.  Simple names containing `query`.
+    /// 4.  Paths containing `query` (`"util.array"` finds `java/util/ArrayList`.)
+    /// 5.  Simple names within a small edit distance of `query`, to forgive typos (`"arrylist"`.)
+    ///
+    /// Ties are broken by edit distance, then by path.  Classes that don't match at all aren't returned.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # let src = jreflection::Source::from_jdk_dir(r"C:\Program Files\AdoptOpenJDK\jdk-8.0.232.09-hotspot").unwrap();
+    /// for class in src.find_class_fuzzy("arraylist").unwrap().into_iter().take(10) {
+    ///     println!("{}", class);
+    /// }
+    /// ```
+    pub fn find_class_fuzzy(&self, query: &str) -> Result<Vec<String>> {
+        let query = query.to_lowercase().replace('.', "/");
+        let max_distance = std::cmp::max(1, query.chars().count() / 3);
+        let mut matches = Vec::new();
+        self.for_each_class(|path|{
+            let lower = path.to_lowercase();
+            let simple = &lower[lower.rfind('/').map(|s| s+1).unwrap_or(0)..];
+            let distance = edit_distance(simple, &query);
+            let rank = if simple == query                   { 0 }
+                else if simple.starts_with(query.as_str())  { 1 }
+                else if simple.contains(query.as_str())     { 2 }
+                else if lower.contains(query.as_str())      { 3 }
+                else if distance <= max_distance            { 4 }
+                else                                        { return Ok(()) };
+            matches.push((rank, distance, path));
+            Ok(())
+        })?;
+        matches.sort();
+        Ok(matches.into_iter().map(|(_, _, path)| path).collect())
+    }
+
+    /// Count how many classes of this source carry each class attribute, by attribute name.
+    ///
+    /// Only the attributes of classes themselves are counted, not those of their fields or methods.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # #[cfg(feature = "jar")] {
+    /// # let src = jreflection::Source::from_jar("scala-library.jar").unwrap();
+    /// for (attribute, classes) in src.attribute_histogram().unwrap() {
+    ///     println!("{:40} {}", attribute, classes);
+    /// }
+    /// # }
+    /// ```
+    pub fn attribute_histogram(&self) -> Result<BTreeMap<String, usize>> {
+        let mut histogram = BTreeMap::<String, usize>::new();
+        self.for_each_class(|name|{
+            let class = self.read_class(name)?;
+            let mut names = class.attribute_names().collect::<Vec<_>>();
+            names.sort();
+            names.dedup();
+            for name in names {
+                *histogram.entry(name.to_owned()).or_default() += 1;
+            }
+            Ok(())
+        })?;
+        Ok(histogram)
+    }
+
+    /// Export every resource (classes, images, properties files, etc.) of a jimage `modules` file, per module.
+    ///
+    /// Returns the number of resources exported, per module.  Errors with `InvalidInput` if this isn't a jimage source.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use jreflection::*;
+    /// let src = Source::from_jdk_dir(r"C:\Program Files\AdoptOpenJDK\jdk-11.0.6.10-hotspot").unwrap();
+    /// for (module, resources) in src.export_modules("jdk-11-jars", ExportLayout::Jars).unwrap() {
+    ///     println!("{}.jar: {} resources", module, resources);
+    /// }
+    /// ```
+    #[cfg(feature = "jimage")]
+    pub fn export_modules(&self, dir: impl AsRef<Path>, layout: ExportLayout) -> Result<BTreeMap<String, usize>> {
+        let img = match &self.int {
+            #[cfg(feature = "jar")]
+            SourceInt::Jar(_)       => return Err(Error::new(ErrorKind::InvalidInput, "export_modules requires a jimage source, not a jar")),
+            SourceInt::Overlay {..} => return Err(Error::new(ErrorKind::InvalidInput, "export_modules requires a jimage source, not an overlay")),
+            SourceInt::JImage(img)  => img,
+        };
+        export_resources(dir.as_ref(), layout, img.resources()?, |module, path| {
+            self.check_cancelled()?;
+            img.read_resource(module, path)
+        })
+    }
+
+    #[cfg(feature = "jimage")]
     fn from_jimage_modules(jimage: impl AsRef<Path>, modules: impl AsRef<Path>) -> Result<Self> {
-        Ok(Self(SourceInt::JImage(JImage::open(jimage, modules)?)))
+        Ok(Self::new(SourceInt::JImage(JImage::open(jimage, modules)?)))
+    }
+}
+
+/// Write `resources` - `(module, path)` pairs, read with `read` - to `dir`.  See [Source::export_modules].
+///
+/// Module names and paths come from the image, so anything that could escape `dir` (absolute paths, `..`, separators
+/// in module names, etc.) is rejected before it's read.
+///
+/// [Source::export_modules]:   struct.Source.html#method.export_modules
+#[cfg(feature = "jimage")]
+fn export_resources(dir: &Path, layout: ExportLayout, resources: Vec<(String, String)>, mut read: impl FnMut(&str, &str) -> Result<Vec<u8>>) -> Result<BTreeMap<String, usize>> {
+    #[cfg(not(feature = "writer"))] {
+        if layout == ExportLayout::Jars { return Err(Error::new(ErrorKind::InvalidInput, "ExportLayout::Jars requires jreflection's \"writer\" feature")); }
+    }
+
+    std::fs::create_dir_all(dir)?;
+    #[cfg(feature = "writer")] let mut jars = BTreeMap::<String, jar::Writer<_>>::new();
+    let mut counts = BTreeMap::<String, usize>::new();
+    for (module, path) in resources {
+        if is_suspicious_export_name(&module) || module.contains('/') || path.split('/').any(is_suspicious_export_name) {
+            return Err(Error::new(ErrorKind::InvalidData, format!("Refusing to export suspicious resource path: {}/{}", module, path)));
+        }
+        let bytes = read(&module, &path)?;
+        match layout {
+            ExportLayout::Directories => {
+                let file = dir.join(&module).join(&path);
+                std::fs::create_dir_all(file.parent().unwrap())?;
+                std::fs::write(file, bytes)?;
+            },
+            #[cfg(feature = "writer")]
+            ExportLayout::Jars => {
+                if !jars.contains_key(&module) { jars.insert(module.clone(), jar::Writer::create(dir.join(format!("{}.jar", module)))?); }
+                jars.get_mut(&module).unwrap().add_resource(&path, bytes)?;
+            },
+            #[cfg(not(feature = "writer"))]
+            ExportLayout::Jars => unreachable!(),
+        }
+        *counts.entry(module).or_default() += 1;
+    }
+    #[cfg(feature = "writer")] for (_, jar) in jars { jar.finish()?; }
+    Ok(counts)
+}
+
+/// Is `name` (a module name, or a single component of a resource path) empty, `.`, `..`, or able to name a drive or
+/// separate directories on some platform?
+#[cfg(feature = "jimage")]
+fn is_suspicious_export_name(name: &str) -> bool {
+    name.is_empty() || name == "." || name == ".." || name.contains(|c| c == '\\' || c == ':' || c == '\0')
+}
+
+#[cfg(feature = "jar")]
+enum JarReader {
+    Buffered(BufReader<File>),
+    InMemory(Cursor<Vec<u8>>),
+    #[cfg(feature = "mmap")] Mmap(Cursor<memmap2::Mmap>),
+    Storage(crate::storage::BlockReader),
+}
+
+#[cfg(feature = "jar")]
+impl JarReader {
+    fn open(path: &Path, io: IoStrategy) -> Result<Self> {
+        let mut file = File::open(path)?;
+        match io {
+            IoStrategy::Auto if file.metadata()?.len() < IoStrategy::AUTO_IN_MEMORY_JAR_LIMIT => Self::open(path, IoStrategy::InMemory),
+            IoStrategy::Auto        => Ok(JarReader::Buffered(BufReader::new(file))),
+            IoStrategy::Buffered    => Ok(JarReader::Buffered(BufReader::new(file))),
+            IoStrategy::InMemory    => {
+                let mut bytes = Vec::new();
+                file.read_to_end(&mut bytes)?;
+                Ok(JarReader::InMemory(Cursor::new(bytes)))
+            },
+        }
     }
 }
 
-struct Jar(RefCell<ZipArchive<BufReader<File>>>);
+#[cfg(feature = "jar")]
+impl Read for JarReader {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        match self {
+            JarReader::Buffered(r)  => r.read(buf),
+            JarReader::InMemory(r)  => r.read(buf),
+            #[cfg(feature = "mmap")]
+            JarReader::Mmap(r)      => r.read(buf),
+            JarReader::Storage(r)   => r.read(buf),
+        }
+    }
+}
+
+#[cfg(feature = "jar")]
+impl Seek for JarReader {
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64> {
+        match self {
+            JarReader::Buffered(r)  => r.seek(pos),
+            JarReader::InMemory(r)  => r.seek(pos),
+            #[cfg(feature = "mmap")]
+            JarReader::Mmap(r)      => r.seek(pos),
+            JarReader::Storage(r)   => r.seek(pos),
+        }
+    }
+}
+
+/// The metadata of a single entry of a `.jar`, as returned by [Jar::entries](struct.Jar.html#method.entries).
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg(feature = "jar")]
+pub struct JarEntry {
+    /// The path of the entry within the `.jar`, such as `"java/lang/Object.class"` or `"META-INF/MANIFEST.MF"`.
+    pub name:               String,
+
+    /// The uncompressed size of the entry, in bytes.
+    pub size:               u64,
+
+    /// The compressed size of the entry, in bytes.
+    pub compressed_size:    u64,
+
+    /// The CRC-32 checksum of the uncompressed entry.
+    pub crc32:              u32,
+
+    /// Is this entry a directory?
+    pub is_dir:             bool,
+}
+
+/// A `.jar` backed [Source], as returned by [Source::as_jar](struct.Source.html#method.as_jar).
+///
+/// [Source]:   struct.Source.html
+#[cfg(feature = "jar")]
+pub struct Jar {
+    zip:        RefCell<ZipArchive<JarReader>>,
+    decoding:   EntryNameDecoding,
+    renamed:    RefCell<Option<HashMap<String, usize>>>, // Entries whose decoded names differ from zip's, built on demand
+}
+
+#[cfg(feature = "jar")]
 impl Jar {
-    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
-        Ok(Self(RefCell::new(ZipArchive::new(BufReader::new(File::open(path)?))?)))
+    pub(crate) fn open(path: impl AsRef<Path>, io: IoStrategy) -> Result<Self> {
+        Self::from_reader(JarReader::open(path.as_ref(), io)?)
     }
 
-    pub fn read_class(&self, path: &str) -> Result<Class> {
-        let mut zip = self.0.borrow_mut();
-        let mut entry = zip.by_name(&format!("{}.class", path))?;
-        Class::read(&mut entry)
+    fn from_reader(reader: JarReader) -> Result<Self> {
+        Ok(Self {
+            zip:        RefCell::new(ZipArchive::new(reader)?),
+            decoding:   EntryNameDecoding::default(),
+            renamed:    RefCell::new(None),
+        })
     }
 
-    pub fn for_each_class(&self, mut f: impl FnMut(String) -> Result<()>) -> Result<()> {
-        let n = self.0.borrow().len();
+    /// Contains `classes.dex` but no `.class` files, as Android framework jars and `.apk`s do.
+    fn is_dex_only(&self) -> bool {
+        let mut zip = self.zip.borrow_mut();
+        let dex = zip.by_name("classes.dex").is_ok();
+        dex && !zip.file_names().any(|name| name.ends_with(".class"))
+    }
+
+    fn set_name_decoding(&mut self, decoding: EntryNameDecoding) {
+        self.decoding = decoding;
+        self.renamed = RefCell::new(None);
+    }
+
+    fn index_of(&self, name: &str) -> Result<Option<usize>> {
+        if let EntryNameDecoding::Zip = self.decoding { return Ok(None); }
+        let mut renamed = self.renamed.borrow_mut();
+        if renamed.is_none() {
+            let mut zip = self.zip.borrow_mut();
+            let mut index = HashMap::new();
+            for i in 0..zip.len() {
+                let entry = zip.by_index(i)?;
+                let (decoded, _) = self.decoding.decode(entry.name_raw(), entry.name());
+                if decoded != entry.name() { index.insert(decoded, i); }
+            }
+            *renamed = Some(index);
+        }
+        Ok(renamed.as_ref().unwrap().get(name).copied())
+    }
+
+    pub(crate) fn read_class_bytes(&self, path: &str) -> Result<Vec<u8>> {
+        self.read_entry(&format!("{}.class", path)).map_err(|err| match err.kind() {
+            ErrorKind::NotFound => Error::new(ErrorKind::NotFound, format!("No such class in jar: {}", path)),
+            _ => err,
+        })
+    }
+
+    /// The metadata of every entry of the `.jar`, classes and resources alike, in archive order.
+    pub fn entries(&self) -> Result<Vec<JarEntry>> {
+        let mut zip = self.zip.borrow_mut();
+        let mut entries = Vec::with_capacity(zip.len());
+        for i in 0..zip.len() {
+            let entry = zip.by_index(i)?;
+            entries.push(JarEntry {
+                name:               self.decoding.decode(entry.name_raw(), entry.name()).0,
+                size:               entry.size(),
+                compressed_size:    entry.compressed_size(),
+                crc32:              entry.crc32(),
+                is_dir:             entry.is_dir(),
+            });
+        }
+        Ok(entries)
+    }
+
+    /// Read any entry of the `.jar` by its full path, such as `"META-INF/MANIFEST.MF"`.
+    pub fn read_entry(&self, name: &str) -> Result<Vec<u8>> {
+        let index = self.index_of(name)?;
+        let mut zip = self.zip.borrow_mut();
+        let entry = match index {
+            Some(index) => zip.by_index(index),
+            None        => zip.by_name(name),
+        };
+        let mut entry = match entry {
+            Ok(entry) => entry,
+            Err(ZipError::FileNotFound) => return Err(Error::new(ErrorKind::NotFound, format!("No such entry in jar: {}", name))),
+            Err(err) => return Err(err.into()),
+        };
+        let mut bytes = Vec::with_capacity(entry.size().min(100_000_000) as usize);
+        entry.read_to_end(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    pub(crate) fn for_each_class(&self, mut skipped: Option<&mut SkipReport>, mut f: impl FnMut(String) -> Result<()>) -> Result<()> {
+        let n = self.zip.borrow().len();
         for i in 0..n {
             let name = {
-                let mut zip = self.0.borrow_mut();
+                let mut zip = self.zip.borrow_mut();
                 let entry = zip.by_index(i)?;
-                let name = entry.name();
+                let (mut name, fallback) = self.decoding.decode(entry.name_raw(), entry.name());
+                if let Some(skipped) = skipped.as_mut() {
+                    if fallback { skipped.fallback_names.insert(name.clone(), entry.name_raw().to_vec()); }
+                    if !name.ends_with(".class") && !entry.is_dir() { skipped.resources.insert(name.clone()); }
+                }
                 if !name.ends_with(".class") { continue; }
-                name[..name.len()-6].to_string()
+                name.truncate(name.len()-6);
+                name
             };
             f(name)?;
         }
@@ -167,16 +1043,88 @@ impl Jar {
     }
 }
 
-struct JImage(jimage::File);
+/// A jimage (JDK 9+ `lib/modules`) backed [Source], as returned by [Source::as_jimage](struct.Source.html#method.as_jimage).
+///
+/// [Source]:   struct.Source.html
+#[cfg(feature = "jimage")]
+pub struct JImage {
+    file:   jimage::File,
+    path:   PathBuf,
+}
+
+/// The metadata of a jimage resource, as returned by [JImage::entries](struct.JImage.html#method.entries).
+#[cfg(feature = "jimage")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct JImageEntry {
+    /// The module containing the resource, such as `"java.base"`.  Empty for image metadata outside of any module.
+    pub module:             String,
+
+    /// The path of the resource within its module, such as `"java/lang/Object.class"`.
+    pub path:               String,
+
+    /// The extension of the resource, such as `"class"`, or empty if it has none.
+    pub extension:          String,
+
+    /// The offset of the resource's (possibly compressed) content from the start of the image file, in bytes.
+    pub offset:             u64,
+
+    /// The size of the resource's content as stored in the image, in bytes.  `0` if it's stored uncompressed.
+    pub compressed_size:    u64,
+
+    /// The uncompressed size of the resource, in bytes.
+    pub size:               u64,
+}
+
+#[cfg(feature = "jimage")]
+impl JImageEntry {
+    /// Is the resource stored compressed?
+    pub fn is_compressed(&self) -> bool { self.compressed_size != 0 }
+
+    /// The number of bytes the resource's content takes up in the image.
+    pub fn stored_size(&self) -> u64 { if self.is_compressed() { self.compressed_size } else { self.size } }
+}
+
+#[cfg(feature = "jimage")]
 impl JImage {
-    pub fn open(jimage: impl AsRef<Path>, modules: impl AsRef<Path>) -> Result<Self> {
+    pub(crate) fn open(jimage: impl AsRef<Path>, modules: impl AsRef<Path>) -> Result<Self> {
         let jimage = jimage::Library::load(jimage.as_ref())?;
-        let modules = jimage.open(modules.as_ref())?;
-        Ok(Self(modules))
+        let file = jimage.open(modules.as_ref())?;
+        Ok(Self { file, path: modules.as_ref().to_owned() })
     }
 
-    pub fn read_class(&self, path: &str) -> Result<Class> {
-        let err = |e: Error| Error::new(e.kind(), format!("Failed to jimage.read_class({:?}): {}", path, e));
+    /// The metadata of every resource of the image - classes, other resources, and `module-info.class`es alike - in
+    /// index order, for analyzing what takes up space in an image.
+    ///
+    /// libjimage doesn't expose this, so it's read from the image's index directly.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use std::collections::BTreeMap;
+    /// # let src = jreflection::Source::from_jdk_dir(r"C:\Program Files\AdoptOpenJDK\jdk-11.0.5.10-hotspot").unwrap();
+    /// let mut sizes = BTreeMap::<String, u64>::new();
+    /// for entry in src.as_jimage().unwrap().entries().unwrap() {
+    ///     *sizes.entry(entry.module).or_default() += entry.stored_size();
+    /// }
+    /// ```
+    pub fn entries(&self) -> Result<Vec<JImageEntry>> {
+        read_jimage_index(&mut BufReader::new(File::open(&self.path)?))
+    }
+
+    /// The name of the module containing the package of `class`, such as `"java.base"` for `"java/lang/Object"`.
+    pub fn module_of(&self, class: &str) -> Result<String> {
+        let package = class.rfind('/').map(|slash| &class[..slash]).unwrap_or("");
+        let package = CString::new(package).map_err(|e| Error::new(ErrorKind::InvalidInput, e))?;
+        Ok(self.file.package_to_module(&package)?.to_string_lossy().into_owned())
+    }
+
+    /// The names of every module with resources in this jimage, sorted.
+    pub fn modules(&self) -> Result<BTreeSet<String>> {
+        Ok(self.resources()?.into_iter().map(|(module, _)| module).collect())
+    }
+
+    pub(crate) fn read_class_bytes(&self, path: &str) -> Result<Vec<u8>> {
+        let err = |e: Error| Error::new(e.kind(), format!("Failed to jimage.read_class_bytes({:?}): {}", path, e));
 
         let slash = path.rfind('/');
         let package = match slash {
@@ -184,31 +1132,60 @@ impl JImage {
             None        => "",
         };
 
-        let path    = CString::new(format!("{}.class", path)).expect("path cannot have any '\\0' characters");
-        let package = CString::new(package).unwrap(); // Shouldn't fail if path didn't fail
+        let package = CString::new(package).expect("path cannot have any '\\0' characters");
+        let module  = self.file.package_to_module(&package).map_err(err)?;
+        self.read_resource_in(module, &format!("{}.class", path)).map_err(err)
+    }
 
-        let module      = self.0.package_to_module(&package).map_err(err)?;
-        let resource    = self.0.find_resource(module, Self::v9(), &path).map_err(err)?;
+    /// The `jrt:/` URI of the `.class` file of `class`, such as `"jrt:/java.base/java/lang/Object.class"` for `"java/lang/Object"`.
+    pub fn jrt_uri(&self, class: &str) -> Result<String> {
+        Ok(crate::jrt::JrtPath::for_class(self.module_of(class)?, class).to_string())
+    }
+
+    /// Read a resource named by a `jrt:/` URI or jrt filesystem path.  See [JrtPath::parse](jrt/struct.JrtPath.html#method.parse).
+    pub fn read_jrt(&self, uri: &str) -> Result<Vec<u8>> {
+        let jrt = crate::jrt::JrtPath::parse(uri)?;
+        if jrt.path.is_empty() { return Err(Error::new(ErrorKind::InvalidInput, format!("{} names a module, not a resource", uri))); }
+        self.read_resource(&jrt.module, &jrt.path)
+    }
+
+    /// Read a resource (path including extension) from a module, by module name.
+    pub fn read_resource(&self, module: &str, path: &str) -> Result<Vec<u8>> {
+        let err = |e: Error| Error::new(e.kind(), format!("Failed to jimage.read_resource({:?}, {:?}): {}", module, path, e));
+        let module = CString::new(module).map_err(|e| err(Error::new(ErrorKind::InvalidInput, e)))?;
+        self.read_resource_in(&module, path).map_err(err)
+    }
+
+    fn read_resource_in(&self, module: &CStr, path: &str) -> Result<Vec<u8>> {
+        let path        = CString::new(path).map_err(|e| Error::new(ErrorKind::InvalidInput, e))?;
+        let resource    = self.file.find_resource(module, Self::v9(), &path)?;
         let size        = resource.size();
 
         let size = if size > 100_000_000 {
-            return Err(err(Error::new(ErrorKind::InvalidData, "exceeds 100MB in size")));
+            return Err(Error::new(ErrorKind::InvalidData, "exceeds 100MB in size"));
         } else {
             size as usize
         };
 
         let mut mem = Vec::new();
         mem.resize(size, 0);
-        assert_eq!(size as u64, resource.get(&mut mem[..]).map_err(err)?);
-        let mut mem = Cursor::new(mem);
-        Class::read(&mut mem).map_err(err)
+        assert_eq!(size as u64, resource.get(&mut mem[..])?);
+        Ok(mem)
     }
 
-    pub fn for_each_class(&self, mut f: impl FnMut(String) -> Result<()>) -> Result<()> {
+    pub(crate) fn for_each_class(&self, mut skipped: Option<&mut SkipReport>, mut f: impl FnMut(String) -> Result<()>) -> Result<()> {
         let mut result = Ok(());
-        self.0.visit(|r|{
+        self.file.visit(|r|{
             if r.extension_cstr().to_bytes() != b"class" {
                 // Possibly a gif, or any number of other resource types
+                if let Some(skipped) = skipped.as_mut() {
+                    let (module, package, name, extension) = (r.module_name_cstr().to_string_lossy(), r.package_cstr().to_string_lossy(), r.name_cstr().to_string_lossy(), r.extension_cstr().to_string_lossy());
+                    let mut path = format!("{}/", module);
+                    if !package.is_empty() { path.push_str(&package); path.push('/'); }
+                    path.push_str(&name);
+                    if !extension.is_empty() { path.push('.'); path.push_str(&extension); }
+                    skipped.resources.insert(path);
+                }
                 return jimage::VisitResult::Continue;
             }
 
@@ -224,6 +1201,7 @@ impl JImage {
 
             if package == "" && name == "module-info" {
                 // Magic metadata nonsense
+                if let Some(skipped) = skipped.as_mut() { skipped.module_infos.insert(r.module_name_cstr().to_string_lossy().into_owned()); }
                 return jimage::VisitResult::Continue;
             }
 
@@ -243,5 +1221,384 @@ impl JImage {
         result
     }
 
+    /// Enumerate (module, path) pairs of every resource, excluding module metadata (`module-info.class`.)
+    pub fn resources(&self) -> Result<Vec<(String, String)>> {
+        let mut resources = Vec::new();
+        let mut result = Ok(());
+        self.file.visit(|r|{
+            let (module, package, name, extension) = match (r.module_name(), r.package(), r.name(), r.extension()) {
+                (Ok(m), Ok(p), Ok(n), Ok(e)) => (m, p, n, e),
+                (Err(e), _, _, _) | (_, Err(e), _, _) | (_, _, Err(e), _) | (_, _, _, Err(e)) => { result = Err(e); return jimage::VisitResult::Cancel; },
+            };
+            if package.is_empty() { return jimage::VisitResult::Continue; } // module-info and other magic metadata nonsense
+
+            let path = if !extension.is_empty() { format!("{}/{}.{}", package, name, extension) } else { format!("{}/{}", package, name) };
+            resources.push((module.to_owned(), path));
+            jimage::VisitResult::Continue
+        });
+        result?;
+        Ok(resources)
+    }
+
     fn v9() -> &'static CStr { CStr::from_bytes_with_nul(b"9.0\0").unwrap() }
 }
+
+/// Read the resource locations of a jimage index.  See `jdk.internal.jimage.BasicImageReader` (and `ImageHeader`,
+/// `ImageLocation`) for the format.
+#[cfg(feature = "jimage")]
+fn read_jimage_index(read: &mut impl Read) -> Result<Vec<JImageEntry>> {
+    let mut header = [0u8; 28];
+    read.read_exact(&mut header[..])?;
+    let big_endian = match header[..4] {
+        [0xCA, 0xFE, 0xDA, 0xDA] => true,
+        [0xDA, 0xDA, 0xFE, 0xCA] => false,
+        _ => return io_data_err!("Invalid jimage header magic"),
+    };
+    let u4 = |bytes: &[u8]| -> u32 {
+        let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+        if big_endian { u32::from_be_bytes(bytes) } else { u32::from_le_bytes(bytes) }
+    };
+    let field = |i: usize| u4(&header[4*i..]) as usize;
+    let (version, table_length, locations_size, strings_size) = (field(1), field(4), field(5), field(6));
+    io_assert!(version >> 16 == 1, "Unsupported jimage version {}.{}", version >> 16, version & 0xFFFF);
+
+    let index_len = table_length * 8 + locations_size + strings_size;
+    let mut index = Vec::new();
+    read.take(index_len as u64).read_to_end(&mut index)?;
+    io_assert!(index.len() == index_len, "jimage index truncated:  expected {} bytes, found {}", index_len, index.len());
+    let offsets     = &index[table_length * 4 .. table_length * 8];
+    let locations   = &index[table_length * 8 .. table_length * 8 + locations_size];
+    let strings     = &index[table_length * 8 + locations_size ..];
+    let index_size  = (header.len() + index.len()) as u64;
+
+    let string = |offset: u64| -> Result<String> {
+        let bytes = match strings.get(offset as usize..) {
+            Some(bytes) => bytes,
+            None        => return io_data_err!("jimage string offset {} out of bounds", offset),
+        };
+        let bytes = &bytes[..bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len())];
+        Ok(crate::constants::read_modified_utf8_as_utf8(bytes).unwrap_or_else(|| String::from_utf8_lossy(bytes).into_owned()))
+    };
+
+    let mut entries = Vec::with_capacity(table_length);
+    for i in 0..table_length {
+        let mut location = match locations.get(u4(&offsets[4*i..]) as usize..) {
+            Some(location)  => location,
+            None            => return io_data_err!("jimage location #{} out of bounds", i),
+        };
+        let mut attributes = [0u64; 8]; // END, MODULE, PARENT, BASE, EXTENSION, OFFSET, COMPRESSED, UNCOMPRESSED
+        while let Some((&byte, rest)) = location.split_first() {
+            let kind = (byte >> 3) as usize;
+            if kind == 0 { break; } // END
+            io_assert!(kind < attributes.len(), "jimage location #{} has unknown attribute kind {}", i, kind);
+            let len = (byte & 0x7) as usize + 1;
+            io_assert!(rest.len() >= len, "jimage location #{} truncated", i);
+            attributes[kind] = rest[..len].iter().fold(0, |value, b| (value << 8) | u64::from(*b));
+            location = &rest[len..];
+        }
+
+        let (parent, base, extension) = (string(attributes[2])?, string(attributes[3])?, string(attributes[4])?);
+        let mut path = parent;
+        if !path.is_empty() { path.push('/'); }
+        path.push_str(&base);
+        if !extension.is_empty() { path.push('.'); path.push_str(&extension); }
+        entries.push(JImageEntry {
+            module:             string(attributes[1])?,
+            path,
+            extension,
+            offset:             index_size + attributes[5],
+            compressed_size:    attributes[6],
+            size:               attributes[7],
+        });
+    }
+    Ok(entries)
+}
+
+/// The module `jar` patches `class` of when overlaid on `base`:  the module a modular `.jar` declares, or else the
+/// module containing the package of `class` in the jimage under `base`, if any.
+#[cfg(all(feature = "jar", feature = "jimage"))]
+fn patch_module(jar: &Jar, base: &Source, class: &str) -> Result<Option<String>> {
+    match jar.read_entry("module-info.class") {
+        Ok(bytes) => match Class::read(&mut &bytes[..])?.module() {
+            Some(module)    => Ok(Some(module.name.clone())),
+            None            => Err(Error::new(ErrorKind::InvalidData, "module-info.class is missing its Module attribute")),
+        },
+        Err(ref err) if err.kind() == ErrorKind::NotFound => Ok(base.base_jimage().and_then(|image| image.module_of(class).ok())),
+        Err(err) => Err(err),
+    }
+}
+
+/// The Levenshtein distance between `a` and `b`, in `char`s.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut prev = (0..=b.len()).collect::<Vec<_>>();
+    let mut next = vec![0; b.len() + 1];
+    for (i, ca) in a.chars().enumerate() {
+        next[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitute = prev[j] + if ca == *cb { 0 } else { 1 };
+            next[j+1] = std::cmp::min(substitute, std::cmp::min(prev[j+1], next[j]) + 1);
+        }
+        std::mem::swap(&mut prev, &mut next);
+    }
+    prev[b.len()]
+}
+
+#[cfg(feature = "jar")]
+#[test] fn entry_name_decoding() {
+    let class = |path: &str| crate::testing::ClassFile::new(path, Some("java/lang/Object")).to_bytes();
+    let path = crate::testing::write_jar("src-entry-names", &[("p/Utf8_XX.class", class("p/A")), ("p/Sjis_XX.class", class("p/B"))]);
+
+    // Patch ASCII placeholders to raw names without the UTF-8 flag, as old tools wrote:  UTF-8 "é", and Shift-JIS "あ"
+    let mut bytes = std::fs::read(&path).unwrap();
+    for (from, to) in [(&b"Utf8_XX"[..], &b"Utf8_\xC3\xA9"[..]), (&b"Sjis_XX"[..], &b"Sjis_\x82\xA0"[..])].iter() {
+        let mut at = 0;
+        while let Some(i) = bytes[at..].windows(from.len()).position(|w| w == *from) {
+            bytes[at+i .. at+i+from.len()].copy_from_slice(to);
+            at += i + 1;
+        }
+    }
+    std::fs::write(&path, bytes).unwrap();
+
+    let mut src = Source::from_jar(&path).unwrap();
+    let classes = src.classes::<Vec<String>>().unwrap();
+    assert_eq!(classes, &["p/Utf8_\u{251C}\u{2310}", "p/Sjis_\u{e9}\u{e1}"], "zip's own CP437 decoding");
+
+    src.set_skip_reporting(true);
+    src.set_entry_name_decoding(EntryNameDecoding::Utf8OrCp437);
+    assert_eq!(src.classes::<Vec<String>>().unwrap(), &["p/Utf8_\u{e9}", "p/Sjis_\u{e9}\u{e1}"]);
+    assert_eq!(src.read_class_bytes("p/Utf8_\u{e9}").unwrap()[..4], [0xCA, 0xFE, 0xBA, 0xBE]);
+    assert_eq!(src.skip_report().fallback_names.values().collect::<Vec<_>>(), &[&b"p/Sjis_\x82\xA0.class".to_vec()]);
+
+    src.set_entry_name_decoding(EntryNameDecoding::Custom(Arc::new(|raw| if raw.ends_with(b"\x82\xA0.class") { Some(format!("{}\u{3042}.class", String::from_utf8_lossy(&raw[..raw.len()-8]))) } else { None })));
+    assert_eq!(src.classes::<Vec<String>>().unwrap(), &["p/Utf8_\u{e9}", "p/Sjis_\u{3042}"]);
+    assert!(src.read_class_bytes("p/Sjis_\u{3042}").is_ok());
+    assert!(src.as_jar().unwrap().entries().unwrap().iter().any(|e| e.name == "p/Sjis_\u{3042}.class"));
+}
+
+#[cfg(feature = "jar")]
+#[test] fn skip_report() {
+    let mut c = crate::testing::ClassFile::new("com/example/Foo", Some("java/lang/Object"));
+    c.class_attribute("ScalaSig", &[5, 0, 0]);
+    let unknown = c.attribute("com.example.Unknown", &[]);
+    c.method(0x0001, "foo", "()V", &[unknown]);
+    let path = crate::testing::write_jar("src-skip-report", &[("com/example/Foo.class", c.to_bytes()), ("res/icon.png", vec![0; 4])]);
+
+    let mut src = Source::from_jar(&path).unwrap();
+    src.for_each_class(|_| Ok(())).unwrap();
+    assert!(src.skip_report().is_empty(), "reporting is disabled by default");
+
+    src.set_skip_reporting(true);
+    src.for_each_class(|class| src.read_class(class).map(|_| ())).unwrap();
+    let report = src.skip_report();
+    assert_eq!(report.resources.iter().collect::<Vec<_>>(), &["res/icon.png"]);
+    assert!(report.module_infos.is_empty());
+    assert_eq!(report.unknown_attributes.keys().collect::<Vec<_>>(), &["ScalaSig", "com.example.Unknown"]);
+    assert!(report.unknown_attributes["ScalaSig"].contains("com/example/Foo"));
+
+    src.set_skip_reporting(false);
+    assert!(src.skip_report().is_empty());
+}
+
+#[cfg(feature = "jar")]
+#[test] fn cancellation() {
+    let object = crate::testing::ClassFile::new("java/lang/Object", None).to_bytes();
+    let path = crate::testing::write_jar("src-cancellation", &[("java/lang/Object.class", object.clone()), ("java/lang/String.class", object)]);
+    let mut src = Source::from_jar(&path).unwrap();
+    let cancel = CancellationToken::new();
+    src.set_cancellation(cancel.clone());
+
+    let mut visited = 0;
+    let err = src.for_each_class(|_|{
+        visited += 1;
+        cancel.cancel();
+        Ok(())
+    }).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Interrupted);
+    assert_eq!(visited, 1);
+    assert_eq!(src.read_class("java/lang/Object").unwrap_err().kind(), ErrorKind::Interrupted);
+}
+
+#[cfg(feature = "jar")]
+#[test] fn jar_entries() {
+    let object = crate::testing::ClassFile::new("java/lang/Object", None).to_bytes();
+    let path = crate::testing::write_jar("src-jar-entries", &[("java/lang/Object.class", object.clone()), ("readme.txt", b"hi".to_vec())]);
+    let src = Source::from_jar(&path).unwrap();
+    #[cfg(feature = "jimage")] assert!(src.as_jimage().is_none());
+
+    let jar = src.as_jar().unwrap();
+    let entries = jar.entries().unwrap();
+    assert_eq!(entries.iter().map(|e| e.name.as_str()).collect::<Vec<_>>(), &["java/lang/Object.class", "readme.txt"]);
+    assert_eq!(entries[0].size, object.len() as u64);
+    assert!(!entries[1].is_dir);
+    assert_eq!(jar.read_entry("readme.txt").unwrap(), b"hi");
+    assert_eq!(jar.read_entry("missing.txt").unwrap_err().kind(), ErrorKind::NotFound);
+    assert_eq!(src.read_class_bytes("java/lang/Missing").unwrap_err().kind(), ErrorKind::NotFound);
+}
+
+#[cfg(feature = "jar")]
+#[test] fn io_strategies() {
+    let object = crate::testing::ClassFile::new("java/lang/Object", None).to_bytes();
+    let path = crate::testing::write_jar("src-io-strategies", &[("java/lang/Object.class", object.clone()), ("readme.txt", b"hi".to_vec())]);
+
+    let check = |src: Source| {
+        assert_eq!(src.classes::<Vec<String>>().unwrap(), &["java/lang/Object"]);
+        assert_eq!(src.read_class_bytes("java/lang/Object").unwrap(), object);
+        assert_eq!(src.read_class_bytes("java/lang/Missing").unwrap_err().kind(), ErrorKind::NotFound);
+    };
+    for &io in [IoStrategy::Auto, IoStrategy::Buffered, IoStrategy::InMemory].iter() { check(Source::from_jar_with(&path, io).unwrap()); }
+    #[cfg(feature = "mmap")] #[allow(unsafe_code)] check(unsafe { Source::from_jar_mmap(&path) }.unwrap()); // Nothing else touches the test's jar
+}
+
+#[cfg(feature = "jar")]
+#[test] fn find_main_classes() {
+    let mut main = crate::testing::ClassFile::new("com/example/Main", Some("java/lang/Object"));
+    main.method(0x0009, "main", "([Ljava/lang/String;)V", &[]);
+    let mut not_static = crate::testing::ClassFile::new("com/example/NotStatic", Some("java/lang/Object"));
+    not_static.method(0x0001, "main", "([Ljava/lang/String;)V", &[]);
+    let mut wrong_args = crate::testing::ClassFile::new("com/example/WrongArgs", Some("java/lang/Object"));
+    wrong_args.method(0x0009, "main", "()V", &[]);
+
+    let path = crate::testing::write_jar("src-find-main-classes", &[
+        ("com/example/Main.class",      main.to_bytes()),
+        ("com/example/NotStatic.class", not_static.to_bytes()),
+        ("com/example/WrongArgs.class", wrong_args.to_bytes()),
+    ]);
+    let src = Source::from_jar(&path).unwrap();
+    assert_eq!(src.find_main_classes().unwrap(), &["com/example/Main"]);
+
+    let main = src.read_class("com/example/Main").unwrap();
+    assert_eq!((main.field_count(), main.method_count()), (0, 1));
+}
+
+#[cfg(feature = "jar")]
+#[test] fn find_class_fuzzy() {
+    let classes = ["java/util/ArrayList", "java/util/Arrays", "java/util/concurrent/CopyOnWriteArrayList", "java/util/List", "java/lang/Object", "com/example/ArrayListUtils"];
+    let entries = classes.iter().map(|c| (format!("{}.class", c), crate::testing::ClassFile::new(c, Some("java/lang/Object")).to_bytes())).collect::<Vec<_>>();
+    let path = crate::testing::write_jar("src-find-class-fuzzy", &entries.iter().map(|(n, b)| (n.as_str(), b.clone())).collect::<Vec<_>>());
+    let src = Source::from_jar(&path).unwrap();
+
+    assert_eq!(src.find_class_fuzzy("arraylist").unwrap(), &["java/util/ArrayList", "com/example/ArrayListUtils", "java/util/concurrent/CopyOnWriteArrayList", "java/util/Arrays"]);
+    assert_eq!(src.find_class_fuzzy("UTIL.array").unwrap(), &["java/util/Arrays", "java/util/ArrayList"]);
+    assert_eq!(src.find_class_fuzzy("arrylist").unwrap(), &["java/util/ArrayList"]);
+    assert_eq!(src.find_class_fuzzy("Lst").unwrap(), &["java/util/List"]);
+    assert!(src.find_class_fuzzy("HashMap").unwrap().is_empty());
+
+    assert_eq!(edit_distance("kitten", "sitting"), 3);
+    assert_eq!(edit_distance("", "abc"), 3);
+}
+
+#[cfg(feature = "jar")]
+#[test] fn overlay() {
+    let object  = crate::testing::ClassFile::new("java/lang/Object", None).to_bytes();
+    let string  = crate::testing::ClassFile::new("java/lang/String", Some("java/lang/Object")).to_bytes();
+    let mut patched = crate::testing::ClassFile::new("java/lang/String", Some("java/lang/Object"));
+    patched.field(0x0001, "patched", "Z", &[]);
+    let patched = patched.to_bytes();
+    let extra   = crate::testing::ClassFile::new("java/lang/Extra", Some("java/lang/Object")).to_bytes();
+    let base    = Source::from_jar(crate::testing::write_jar("src-overlay-base",  &[("java/lang/Object.class", object.clone()), ("java/lang/String.class", string)])).unwrap();
+    let patch   = Source::from_jar(crate::testing::write_jar("src-overlay-patch", &[("java/lang/String.class", patched.clone()), ("java/lang/Extra.class", extra)])).unwrap();
+
+    let src = Source::overlay(base, patch);
+    assert!(src.as_jar().is_none());
+    assert!(src.as_overlay().is_some());
+    assert_eq!(src.read_class_bytes("java/lang/String").unwrap(), patched);
+    assert_eq!(src.read_class_bytes("java/lang/Object").unwrap(), object);
+    assert_eq!(src.read_class_bytes("java/lang/Missing").unwrap_err().kind(), ErrorKind::NotFound);
+
+    let classes = src.classes::<Vec<String>>().unwrap();
+    assert_eq!(classes, &["java/lang/String", "java/lang/Extra", "java/lang/Object"]);
+
+    // jrt paths need a jimage at the base, even for classes the patch has
+    assert_eq!(src.read_jrt("jrt:/java.base/java/lang/String.class").unwrap_err().kind(), ErrorKind::InvalidInput);
+    assert_eq!(src.read_jrt("/modules/java.base/java/lang/Object.class").unwrap_err().kind(), ErrorKind::InvalidInput);
+    assert_eq!(src.read_jrt("java/lang/String.class").unwrap_err().kind(), ErrorKind::InvalidInput);
+    let nested = Source::overlay(src, Source::from_jar(crate::testing::write_jar("src-overlay-nested", &[])).unwrap());
+    assert_eq!(nested.read_jrt("jrt:/java.base/java/lang/String.class").unwrap_err().kind(), ErrorKind::InvalidInput);
+}
+
+#[cfg(feature = "jimage")]
+#[test] fn jimage_index() {
+    // strings: "" @0, "java.base" @1, "java/lang" @11, "Object" @21, "class" @28
+    let strings = b"\0java.base\0java/lang\0Object\0class\0".to_vec();
+    let locations = vec![
+        vec![1<<3, 1, 2<<3, 11, 3<<3, 21, 4<<3, 28, 5<<3, 0, 7<<3 | 1, 0x01, 0x00, 0], // java.base/java/lang/Object.class, 256 bytes, uncompressed
+        vec![1<<3, 1, 3<<3, 21, 5<<3 | 1, 0x01, 0x00, 6<<3, 40, 7<<3, 100, 0],          // java.base/Object, 100 bytes compressed to 40
+    ];
+    let mut offsets = Vec::new();
+    let mut packed = Vec::new();
+    for location in locations.iter() { offsets.push(packed.len() as u32); packed.extend_from_slice(location); }
+
+    for &big_endian in [false, true].iter() {
+        let u4 = |v: u32| if big_endian { v.to_be_bytes() } else { v.to_le_bytes() };
+        let mut image = Vec::new();
+        for v in [0xCAFE_DADA, 1 << 16, 0, 2, 2, packed.len() as u32, strings.len() as u32].iter() { image.extend_from_slice(&u4(*v)); }
+        for _ in 0..2 { image.extend_from_slice(&u4(0)); } // redirect table
+        for offset in offsets.iter() { image.extend_from_slice(&u4(*offset)); }
+        image.extend_from_slice(&packed);
+        image.extend_from_slice(&strings);
+        let index_size = image.len() as u64;
+
+        let entries = read_jimage_index(&mut &image[..]).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0], JImageEntry { module: "java.base".into(), path: "java/lang/Object.class".into(), extension: "class".into(), offset: index_size, compressed_size: 0, size: 256 });
+        assert_eq!(entries[1].path, "Object");
+        assert_eq!(entries[1].offset, index_size + 256);
+        assert!(entries[1].is_compressed());
+        assert_eq!(entries[1].stored_size(), 40);
+    }
+    assert!(read_jimage_index(&mut &[0u8; 28][..]).is_err());
+}
+
+#[cfg(all(feature = "jimage", feature = "writer"))]
+#[test] fn export_resources_layouts() {
+    let dir = std::env::temp_dir().join("jreflection-tests").join("export-resources");
+    let _ = std::fs::remove_dir_all(&dir);
+    let resources = vec![
+        ("java.base".to_owned(), "java/lang/Object.class".to_owned()),
+        ("java.base".to_owned(), "java/lang/uniName.dat".to_owned()),
+        ("java.sql".to_owned(), "java/sql/Driver.class".to_owned()),
+    ];
+    let read = |module: &str, path: &str| Ok(format!("{}/{}", module, path).into_bytes());
+
+    let counts = export_resources(&dir.join("dirs"), ExportLayout::Directories, resources.clone(), read).unwrap();
+    assert_eq!(counts.into_iter().collect::<Vec<_>>(), vec![("java.base".to_owned(), 2), ("java.sql".to_owned(), 1)]);
+    assert_eq!(std::fs::read(dir.join("dirs").join("java.base").join("java/lang/uniName.dat")).unwrap(), b"java.base/java/lang/uniName.dat");
+
+    export_resources(&dir.join("jars"), ExportLayout::Jars, resources, read).unwrap();
+    let jar = Source::from_jar(dir.join("jars").join("java.sql.jar")).unwrap();
+    assert_eq!(jar.read_class_bytes("java/sql/Driver").unwrap(), b"java.sql/java/sql/Driver.class");
+}
+
+#[cfg(feature = "jimage")]
+#[test] fn export_resources_suspicious() {
+    let dir = std::env::temp_dir().join("jreflection-tests").join("export-suspicious");
+    let layouts = if cfg!(feature = "writer") { &[ExportLayout::Directories, ExportLayout::Jars][..] } else { &[ExportLayout::Directories][..] };
+    for (module, path) in [
+        ("..", "evil.class"), (".", "evil.class"), ("", "evil.class"), ("../evil", "evil.class"), ("/evil", "evil.class"),
+        ("C:", "evil.class"), ("java.base\\..", "evil.class"), ("java.base", "../evil.class"), ("java.base", "/evil.class"),
+        ("java.base", "java//evil.class"), ("java.base", "java/./evil.class"), ("java.base", "C:/evil.class"), ("java.base", "..\\evil.class"),
+    ].iter() {
+        for &layout in layouts.iter() {
+            let result = export_resources(&dir, layout, vec![(module.to_string(), path.to_string())], |_, _| panic!("read {}/{}", module, path));
+            assert_eq!(result.unwrap_err().kind(), ErrorKind::InvalidData, "{:?}/{:?}", module, path);
+        }
+    }
+}
+
+#[cfg(feature = "jimage")]
+#[test] fn jimage_index_corrupt() {
+    let image = |table_length: u32, location: &[u8]| {
+        let mut image = Vec::new();
+        for v in [0xCAFE_DADA, 1 << 16, 0, table_length, table_length, location.len() as u32, 1].iter() { image.extend_from_slice(&v.to_le_bytes()); }
+        image.extend_from_slice(&[0u8; 8]); // redirect + offset tables
+        image.extend_from_slice(location);
+        image.push(0); // strings
+        image
+    };
+    assert_eq!(read_jimage_index(&mut &image(1, &[3<<3, 0, 0])[..]).unwrap()[0].path, "");
+    assert_eq!(read_jimage_index(&mut &image(1, &[8<<3, 0, 0])[..]).unwrap_err().kind(), ErrorKind::InvalidData); // kind 8 would index past attributes
+    assert_eq!(read_jimage_index(&mut &image(1, &[31<<3 | 7, 0])[..]).unwrap_err().kind(), ErrorKind::InvalidData);
+    assert_eq!(read_jimage_index(&mut &image(1, &[3<<3 | 1, 0])[..]).unwrap_err().kind(), ErrorKind::InvalidData); // truncated attribute
+    assert_eq!(read_jimage_index(&mut &image(0x1000_0000, &[0])[..]).unwrap_err().kind(), ErrorKind::InvalidData); // length exceeds input
+}
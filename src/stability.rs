@@ -0,0 +1,133 @@
+//! API stability, as declared by common annotations such as JetBrains' `@ApiStatus.Internal` or Guava's `@Beta`.
+
+use crate::{Class, Source};
+#[cfg(all(test, feature = "jar"))] use crate::testing;
+
+use std::io::{self, ErrorKind};
+
+
+
+/// How stable an API is declared to be, from least to most worrying to depend on.
+///
+/// | Annotation                                            | Stability         |
+/// | ----------------------------------------------------- | ----------------- |
+/// | `org.jetbrains.annotations.ApiStatus.Experimental`    | `Experimental`    |
+/// | `com.google.common.annotations.Beta`                  | `Experimental`    |
+/// | `org.jetbrains.annotations.ApiStatus.Internal`        | `Internal`        |
+/// | `androidx.annotation.RestrictTo`                      | `Internal`        |
+/// | `android.support.annotation.RestrictTo`               | `Internal`        |
+///
+/// # Examples
+///
+/// ```
+/// # use jreflection::stability::Stability;
+/// assert_eq!(Stability::of_annotation("Lcom/google/common/annotations/Beta;"), Some(Stability::Experimental));
+/// assert_eq!(Stability::of_annotations(vec!["Ljava/lang/Deprecated;"]), Stability::Stable);
+/// assert!(Stability::Internal > Stability::Experimental);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Stability {
+    /// No stability annotations.
+    Stable,
+
+    /// Public, but may change or be removed without the usual deprecation period.
+    Experimental,
+
+    /// Not intended for use outside of the library (or library group) at all, despite being visible.
+    Internal,
+}
+
+impl Default for Stability {
+    fn default() -> Self { Stability::Stable }
+}
+
+impl Stability {
+    /// The stability declared by a single annotation type descriptor, if it's a recognized stability annotation.
+    pub fn of_annotation(descriptor: &str) -> Option<Self> {
+        match descriptor {
+            "Lorg/jetbrains/annotations/ApiStatus$Experimental;"    => Some(Stability::Experimental),
+            "Lcom/google/common/annotations/Beta;"                  => Some(Stability::Experimental),
+            "Lorg/jetbrains/annotations/ApiStatus$Internal;"        => Some(Stability::Internal),
+            "Landroidx/annotation/RestrictTo;"                      => Some(Stability::Internal),
+            "Landroid/support/annotation/RestrictTo;"               => Some(Stability::Internal),
+            _                                                       => None,
+        }
+    }
+
+    /// The least stable stability declared by any of the annotation type descriptors, or [Stable](#variant.Stable).
+    pub fn of_annotations<'a>(descriptors: impl IntoIterator<Item = &'a str>) -> Self {
+        descriptors.into_iter().filter_map(Self::of_annotation).max().unwrap_or_default()
+    }
+}
+
+/// The stability declared by the annotations of `package` (a JNI path such as `"com/google/common/collect"`) in its
+/// `package-info.class`, or [Stable](enum.Stability.html#variant.Stable) if it has none.
+pub fn of_package(source: &Source, package: &str) -> io::Result<Stability> {
+    let path = if package.is_empty() { "package-info".to_owned() } else { format!("{}/package-info", package) };
+    match source.read_class(&path) {
+        Ok(info)                                            => Ok(info.stability()),
+        Err(ref err) if err.kind() == ErrorKind::NotFound   => Ok(Stability::Stable),
+        Err(err)                                            => Err(err),
+    }
+}
+
+/// The effective stability of `member` of `class`:  the least stable of the member, the class, and its package.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use jreflection::*;
+/// # #[cfg(feature = "jar")] {
+/// let guava = Source::from_jar("guava-31.1-jre.jar").unwrap();
+/// let class = guava.read_class("com/google/common/graph/Graphs").unwrap();
+/// for method in class.methods.iter() {
+///     let stability = stability::of_member(&guava, &class, method.stability()).unwrap();
+///     println!("{}{}: {:?}", method.name, method.descriptor_str(), stability);
+/// }
+/// # }
+/// ```
+pub fn of_member(source: &Source, class: &Class, member: Stability) -> io::Result<Stability> {
+    let path = class.path.as_str();
+    let package = &path[..path.rfind('/').unwrap_or(0)];
+    Ok(member.max(class.stability()).max(of_package(source, package)?))
+}
+
+#[cfg(feature = "jar")]
+#[test] fn stability() {
+    use crate::testing::be16;
+    let mut class = testing::ClassFile::new("com/example/Api", Some("java/lang/Object"));
+    let beta = [be16(1), be16(class.utf8("Lcom/google/common/annotations/Beta;")), be16(0)].concat();
+    let beta = class.attribute("RuntimeInvisibleAnnotations", &beta);
+    let internal = [
+        be16(2),
+        // @RestrictTo({RestrictTo.Scope.LIBRARY_GROUP})
+        be16(class.utf8("Landroidx/annotation/RestrictTo;")), be16(1), be16(class.utf8("value")),
+        vec![b'['], be16(1), vec![b'e'], be16(class.utf8("Landroidx/annotation/RestrictTo$Scope;")), be16(class.utf8("LIBRARY_GROUP")),
+        // @Other(nested = @Nested(1))
+        be16(class.utf8("Lcom/example/Other;")), be16(1), be16(class.utf8("nested")),
+        vec![b'@'], be16(class.utf8("Lcom/example/Nested;")), be16(1), be16(class.utf8("value")), vec![b'I'], be16(class.integer(1)),
+    ].concat();
+    let internal = class.attribute("RuntimeInvisibleAnnotations", &internal);
+    let visible = [be16(1), be16(class.utf8("Ljava/lang/FunctionalInterface;")), be16(0)].concat();
+    let visible = class.attribute("RuntimeVisibleAnnotations", &visible);
+    class.method(0x0001, "beta", "()V", &[beta]);
+    class.method(0x0001, "internal", "()V", &[internal]);
+    class.field(0x0001, "stable", "I", &[visible]);
+    let class = class.read();
+
+    assert_eq!(class.stability(), Stability::Stable);
+    assert_eq!(class.methods[0].stability(), Stability::Experimental);
+    assert_eq!(class.methods[1].stability(), Stability::Internal);
+    assert_eq!(class.methods[1].annotation_types().collect::<Vec<_>>(), &["Landroidx/annotation/RestrictTo;", "Lcom/example/Other;"]);
+    assert_eq!(class.fields[0].stability(), Stability::Stable);
+    assert_eq!(class.fields[0].annotation_types().collect::<Vec<_>>(), &["Ljava/lang/FunctionalInterface;"]);
+
+    let mut info = testing::ClassFile::new("com/example/package-info", Some("java/lang/Object"));
+    let experimental = [be16(1), be16(info.utf8("Lorg/jetbrains/annotations/ApiStatus$Experimental;")), be16(0)].concat();
+    info.class_attribute("RuntimeInvisibleAnnotations", &experimental);
+    let source = Source::from_jar(testing::write_jar("stability", &[("com/example/package-info.class", info.to_bytes())])).unwrap();
+    assert_eq!(of_package(&source, "com/example").unwrap(), Stability::Experimental);
+    assert_eq!(of_package(&source, "com/other").unwrap(), Stability::Stable);
+    assert_eq!(of_member(&source, &class, class.fields[0].stability()).unwrap(), Stability::Experimental);
+    assert_eq!(of_member(&source, &class, class.methods[1].stability()).unwrap(), Stability::Internal);
+}
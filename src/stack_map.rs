@@ -0,0 +1,207 @@
+//! [Java SE 8 &sect; 4.7.4](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-4.html#jvms-4.7.4):  The frames
+//! of `StackMapTable` attributes, which type-checking verifiers (required from classfile version 50/51 on) use to check
+//! the types of locals and the operand stack at branch targets and exception handlers.
+//!
+//! See [Code::stack_map_frames](../code/struct.Code.html#method.stack_map_frames).
+
+use crate::constants::Constants;
+use crate::io::be::*;
+#[cfg(test)] use crate::{testing, Class, ParseOptions};
+
+use std::io::{self, Read};
+
+
+
+/// [Java SE 8 &sect; 4.7.4](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-4.html#jvms-4.7.4):  A
+/// `verification_type_info`:  the type of a local variable or operand stack entry.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum VerificationType {
+    /// `ITEM_Top` (0):  an unusable local, or the second half of a `long` or `double`.
+    Top,
+    /// `ITEM_Integer` (1):  an `int`, `short`, `char`, `byte`, or `boolean`.
+    Integer,
+    /// `ITEM_Float` (2)
+    Float,
+    /// `ITEM_Double` (3):  implicitly followed by a [Top](#variant.Top) in locals (but not on the stack.)
+    Double,
+    /// `ITEM_Long` (4):  implicitly followed by a [Top](#variant.Top) in locals (but not on the stack.)
+    Long,
+    /// `ITEM_Null` (5)
+    Null,
+    /// `ITEM_UninitializedThis` (6):  `this` in a constructor, before the superclass constructor has been called.
+    UninitializedThis,
+    /// `ITEM_Object` (7):  an instance of a class, such as `"java/lang/String"` or `"[I"`.
+    Object(String),
+    /// `ITEM_Uninitialized` (8):  an object created by the `new` instruction at bytecode offset `offset`, whose
+    /// constructor hasn't been called yet.
+    Uninitialized { offset: u16 },
+
+    #[doc(hidden)] _NonExhaustive,
+}
+
+/// [Java SE 8 &sect; 4.7.4](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-4.html#jvms-4.7.4):  A
+/// `stack_map_frame`, describing how the state at `offset` differs from the previous frame's.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Frame {
+    /// The raw `frame_type`, which distinguishes compact from extended encodings (e.g. `same_frame` vs.
+    /// `same_frame_extended`) that [kind](#structfield.kind) doesn't.
+    pub frame_type:     u8,
+
+    /// The bytecode offset this frame applies to, computed from the `offset_delta`s of it and preceding frames.
+    pub offset:         u16,
+
+    /// What changed since the previous frame (or the method's implicit initial frame, for the first frame.)
+    pub kind:           FrameKind,
+
+    _non_exhaustive: (),
+}
+
+/// How a [Frame](struct.Frame.html)'s state differs from the previous frame's.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum FrameKind {
+    /// `same_frame` (0-63) or `same_frame_extended` (251):  The same locals, and an empty stack.
+    Same,
+
+    /// `same_locals_1_stack_item_frame` (64-127) or `same_locals_1_stack_item_frame_extended` (247):  The same locals,
+    /// and a stack of a single entry.
+    SameLocals1StackItem(VerificationType),
+
+    /// `chop_frame` (248-250):  The same locals minus the last 1-3 of them, and an empty stack.
+    Chop(u8),
+
+    /// `append_frame` (252-254):  The same locals plus 1-3 more, and an empty stack.
+    Append(Vec<VerificationType>),
+
+    /// `full_frame` (255):  Entirely new locals and stack.
+    Full {
+        locals: Vec<VerificationType>,
+        stack:  Vec<VerificationType>,
+    },
+
+    #[doc(hidden)] _NonExhaustive,
+}
+
+impl VerificationType {
+    fn read(read: &mut impl Read, constants: &Constants, code_len: usize) -> io::Result<Self> {
+        Ok(match read_u1(read)? {
+            0 => VerificationType::Top,
+            1 => VerificationType::Integer,
+            2 => VerificationType::Float,
+            3 => VerificationType::Double,
+            4 => VerificationType::Long,
+            5 => VerificationType::Null,
+            6 => VerificationType::UninitializedThis,
+            7 => VerificationType::Object(constants.get_class(read_u2(read)?)?.to_owned()),
+            8 => {
+                let offset = read_u2(read)?;
+                io_assert!(usize::from(offset) < code_len, "ITEM_Uninitialized offset {} is past the end of {} bytes of code", offset, code_len);
+                VerificationType::Uninitialized { offset }
+            },
+            tag => return io_data_err!("Unknown verification_type_info tag {}", tag),
+        })
+    }
+
+    fn read_list(read: &mut impl Read, count: usize, constants: &Constants, code_len: usize) -> io::Result<Vec<Self>> {
+        let mut types = Vec::with_capacity(count);
+        for _ in 0..count { types.push(Self::read(read, constants, code_len)?); }
+        Ok(types)
+    }
+
+    /// Does this type take up two local variable slots (`long` and `double`)?
+    pub fn is_wide(&self) -> bool {
+        match self {
+            VerificationType::Double | VerificationType::Long   => true,
+            _                                                   => false,
+        }
+    }
+}
+
+impl Frame {
+    /// Create a frame of `kind` at bytecode `offset`, encoded as `frame_type`.
+    pub fn new(frame_type: u8, offset: u16, kind: FrameKind) -> Self { Self { frame_type, offset, kind, _non_exhaustive: () } }
+
+    /// Read the `number_of_entries` prefixed frames of a StackMapTable attribute, for `code_len` bytes of code.
+    pub(crate) fn read_table(read: &mut impl Read, constants: &Constants, code_len: usize) -> io::Result<Vec<Self>> {
+        let count = read_u2(read)? as usize;
+        let mut frames = Vec::with_capacity(count);
+        let mut previous : Option<u16> = None;
+        for _ in 0..count {
+            let frame_type = read_u1(read)?;
+            let (offset_delta, kind) = match frame_type {
+                0   ..= 63  => (u16::from(frame_type), FrameKind::Same),
+                64  ..= 127 => (u16::from(frame_type - 64), FrameKind::SameLocals1StackItem(VerificationType::read(read, constants, code_len)?)),
+                128 ..= 246 => return io_data_err!("Reserved stack_map_frame frame_type {}", frame_type),
+                247         => (read_u2(read)?, FrameKind::SameLocals1StackItem(VerificationType::read(read, constants, code_len)?)),
+                248 ..= 250 => (read_u2(read)?, FrameKind::Chop(251 - frame_type)),
+                251         => (read_u2(read)?, FrameKind::Same),
+                252 ..= 254 => {
+                    let offset_delta = read_u2(read)?;
+                    (offset_delta, FrameKind::Append(VerificationType::read_list(read, usize::from(frame_type - 251), constants, code_len)?))
+                },
+                255         => {
+                    let offset_delta = read_u2(read)?;
+                    let locals_count = read_u2(read)? as usize;
+                    let locals = VerificationType::read_list(read, locals_count, constants, code_len)?;
+                    let stack_count = read_u2(read)? as usize;
+                    let stack = VerificationType::read_list(read, stack_count, constants, code_len)?;
+                    (offset_delta, FrameKind::Full { locals, stack })
+                },
+            };
+
+            // "the bytecode offset at which a frame applies is calculated by taking the value offset_delta specified
+            // in the frame, and adding offset_delta + 1 to the bytecode offset of the previous frame"
+            let offset = match previous {
+                None            => Some(offset_delta),
+                Some(previous)  => previous.checked_add(offset_delta).and_then(|o| o.checked_add(1)),
+            };
+            let offset = match offset { Some(offset) if usize::from(offset) < code_len => offset, _ => return io_data_err!("StackMapTable frame offset is past the end of {} bytes of code", code_len) };
+            previous = Some(offset);
+            frames.push(Frame::new(frame_type, offset, kind));
+        }
+        Ok(frames)
+    }
+}
+
+#[test] fn frames() {
+    use crate::testing::{be16, be32};
+
+    let mut c = testing::ClassFile::new("com/example/Main", Some("java/lang/Object"));
+    let string = c.class("java/lang/String");
+    let table = [
+        be16(7),
+        vec![3],                                                        // same_frame @ 3
+        vec![64 + 1, 7], be16(string),                                  // same_locals_1_stack_item_frame @ 5
+        vec![247], be16(0), vec![8], be16(0),                           // same_locals_1_stack_item_frame_extended @ 6
+        vec![248], be16(1),                                             // chop_frame (3) @ 8
+        vec![251], be16(0),                                             // same_frame_extended @ 9
+        vec![253], be16(0), vec![1, 4],                                 // append_frame (2) @ 10
+        vec![255], be16(0), be16(3), vec![6, 0, 3], be16(1), vec![5],   // full_frame @ 11
+    ].concat();
+    let stack_map = c.attribute("StackMapTable", &table);
+    let bytecode = vec![0u8; 12];
+    let code = c.attribute("Code", &[be16(2), be16(4), be32(bytecode.len() as u32), bytecode, be16(0), be16(1), stack_map].concat());
+    c.method(0x0001, "run", "()V", &[code]);
+
+    let mut options = ParseOptions::new();
+    options.retain_code(true);
+    let class = Class::read_with(&mut &c.to_bytes()[..], &options).unwrap();
+    let frames = class.methods[0].code().unwrap().stack_map_frames().unwrap();
+    assert_eq!(frames.iter().map(|f| (f.frame_type, f.offset)).collect::<Vec<_>>(), &[(3, 3), (65, 5), (247, 6), (248, 8), (251, 9), (253, 10), (255, 11)]);
+    assert_eq!(frames.iter().map(|f| f.kind.clone()).collect::<Vec<_>>(), &[
+        FrameKind::Same,
+        FrameKind::SameLocals1StackItem(VerificationType::Object("java/lang/String".to_owned())),
+        FrameKind::SameLocals1StackItem(VerificationType::Uninitialized { offset: 0 }),
+        FrameKind::Chop(3),
+        FrameKind::Same,
+        FrameKind::Append(vec![VerificationType::Integer, VerificationType::Long]),
+        FrameKind::Full { locals: vec![VerificationType::UninitializedThis, VerificationType::Top, VerificationType::Double], stack: vec![VerificationType::Null] },
+    ]);
+    assert!(VerificationType::Long.is_wide() && !VerificationType::Integer.is_wide());
+
+    // Reserved frame types, unknown tags, and offsets past the end of the code are errors
+    let read = |table: Vec<u8>| Frame::read_table(&mut &table[..], &class.constants, 4);
+    assert!(read([be16(1), vec![200]].concat()).is_err());
+    assert!(read([be16(1), vec![64, 9]].concat()).is_err());
+    assert!(read([be16(2), vec![2, 2]].concat()).is_err());
+    assert!(read([be16(2), vec![1, 1]].concat()).is_ok());
+}
@@ -0,0 +1,318 @@
+//! Random access storage that `.jar`s can be read from, such as remote blob stores.  See [Source::from_jar_storage].
+//!
+//! [Source::from_jar_storage]:     ../struct.Source.html#method.from_jar_storage
+
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, Error, ErrorKind, Read, Seek, SeekFrom};
+
+
+
+/// Storage that can read bytes at arbitrary offsets, without a shared cursor.
+///
+/// Implement this to read `.jar`s directly out of your own storage (a database blob, a custom object store, etc.)
+/// with [Source::from_jar_storage].  Reads are made in 64 KB blocks, the most recent 4 MB of which are cached, so
+/// implementations don't need to do their own caching.
+///
+/// [Source::from_jar_storage]:     ../struct.Source.html#method.from_jar_storage
+///
+/// # Examples
+///
+/// ```
+/// # use jreflection::storage::ReadAt;
+/// # use std::io;
+/// struct Blob(Vec<u8>);
+///
+/// impl ReadAt for Blob {
+///     fn size(&self) -> io::Result<u64> { Ok(self.0.len() as u64) }
+///     fn read_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<usize> { self.0.read_at(offset, buf) }
+/// }
+/// ```
+pub trait ReadAt {
+    /// The total size of the stored data, in bytes.
+    fn size(&self) -> io::Result<u64>;
+
+    /// Read up to `buf.len()` bytes starting at `offset`, returning how many were read.  Returns `Ok(0)` only at or
+    /// past the end of the data.
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<usize>;
+}
+
+impl ReadAt for [u8] {
+    fn size(&self) -> io::Result<u64> { Ok(self.len() as u64) }
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<usize> {
+        if offset >= self.len() as u64 { return Ok(0); }
+        let src = &self[offset as usize..];
+        let n = src.len().min(buf.len());
+        buf[..n].copy_from_slice(&src[..n]);
+        Ok(n)
+    }
+}
+
+impl ReadAt for Vec<u8> {
+    fn size(&self) -> io::Result<u64> { self[..].size() }
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<usize> { self[..].read_at(offset, buf) }
+}
+
+impl ReadAt for File {
+    fn size(&self) -> io::Result<u64> { Ok(self.metadata()?.len()) }
+
+    #[cfg(unix)]
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<usize> { std::os::unix::fs::FileExt::read_at(self, buf, offset) }
+
+    #[cfg(windows)]
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<usize> { std::os::windows::fs::FileExt::seek_read(self, buf, offset) }
+}
+
+
+
+const BLOCK_SIZE : u64      = 64 * 1024;
+const MAX_BLOCKS : usize    = 64; // 4 MB of cache
+
+/// Adapts [ReadAt] storage to `Read + Seek` for zip reading, caching the most recently used blocks.
+///
+/// Zip readers start at the end of the file (for the central directory) and then jump to individual entries, so only
+/// the tail of the file and the entries actually read are ever fetched.
+pub(crate) struct BlockReader {
+    storage:    Box<dyn ReadAt>,
+    len:        u64,
+    pos:        u64,
+    blocks:     VecDeque<(u64, Vec<u8>)>, // (block index, data), least recently used first
+}
+
+impl BlockReader {
+    pub fn new(storage: Box<dyn ReadAt>) -> io::Result<Self> {
+        let len = storage.size()?;
+        Ok(Self { storage, len, pos: 0, blocks: VecDeque::new() })
+    }
+
+    fn block(&mut self, index: u64) -> io::Result<&[u8]> {
+        if let Some(i) = self.blocks.iter().position(|(b, _)| *b == index) {
+            let block = self.blocks.remove(i).unwrap();
+            self.blocks.push_back(block);
+        } else {
+            let start   = index * BLOCK_SIZE;
+            let len     = (self.len - start).min(BLOCK_SIZE) as usize;
+            let mut data = vec![0u8; len];
+            let mut read = 0;
+            while read < len {
+                match self.storage.read_at(start + read as u64, &mut data[read..])? {
+                    0 => return io_data_err!("Storage ended after {} of {} bytes", start + read as u64, self.len),
+                    n => read += n,
+                }
+            }
+            self.blocks.push_back((index, data));
+            if self.blocks.len() > MAX_BLOCKS { self.blocks.pop_front(); }
+        }
+        Ok(&self.blocks.back().unwrap().1[..])
+    }
+}
+
+impl Read for BlockReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pos >= self.len || buf.is_empty() { return Ok(0); }
+        let offset  = (self.pos % BLOCK_SIZE) as usize;
+        let block   = self.block(self.pos / BLOCK_SIZE)?;
+        let n       = buf.len().min(block.len() - offset);
+        buf[..n].copy_from_slice(&block[offset..offset+n]);
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl Seek for BlockReader {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let pos = match pos {
+            SeekFrom::Start(n)      => Some(n),
+            SeekFrom::End(n)        => (self.len as i64).checked_add(n).filter(|p| *p >= 0).map(|p| p as u64),
+            SeekFrom::Current(n)    => (self.pos as i64).checked_add(n).filter(|p| *p >= 0).map(|p| p as u64),
+        };
+        match pos {
+            Some(pos)   => { self.pos = pos; Ok(pos) },
+            None        => Err(Error::new(ErrorKind::InvalidInput, "Seek before the start of storage")),
+        }
+    }
+}
+
+
+
+/// A remote file read with HTTP(S) range requests.  Requires the `http` feature, and the newer Rust that its `ureq`
+/// dependency requires.
+///
+/// Also covers blob stores with HTTP APIs:  see [s3_unsigned](#method.s3_unsigned) and [gcs](#method.gcs).
+#[cfg(feature = "http")]
+pub struct Http {
+    agent:      ureq::Agent,
+    url:        String,
+    headers:    Vec<(String, String)>,
+    len:        u64,
+}
+
+#[cfg(feature = "http")]
+impl Http {
+    /// Open `url`, which must support range requests.
+    pub fn open(url: &str) -> io::Result<Self> { Self::open_with_headers(url, &[]) }
+
+    /// Open `url`, sending `headers` (such as `Authorization`) with every request.
+    pub fn open_with_headers(url: &str, headers: &[(&str, &str)]) -> io::Result<Self> {
+        let mut http = Self {
+            agent:      ureq::AgentBuilder::new().build(),
+            url:        url.to_owned(),
+            headers:    headers.iter().map(|(k, v)| ((*k).to_owned(), (*v).to_owned())).collect(),
+            len:        0,
+        };
+        let response = http.get(0, 0)?;
+        // e.g. "bytes 0-0/12345"
+        let len = response.header("Content-Range").and_then(|range| range.rsplit('/').next()).and_then(|len| len.trim().parse().ok());
+        http.len = match len {
+            Some(len) => len,
+            None => return Err(Error::new(ErrorKind::InvalidData, format!("Missing or unknown length in Content-Range: {}", url))),
+        };
+        Ok(http)
+    }
+
+    /// Open a public or presigned Amazon S3 object, by URL:  `https://{bucket}.s3.{region}.amazonaws.com/{key}`.
+    ///
+    /// Requests are **not** signed:  there's no AWS Signature Version 4 support, so credentials can't be used here.
+    /// The object must either be public, or `query` must be the query string of a presigned URL for it (as generated
+    /// by e.g. `aws s3 presign`.)  Each `/`-separated segment of `key` is percent-encoded.
+    pub fn s3_unsigned(bucket: &str, region: &str, key: &str, query: Option<&str>) -> io::Result<Self> {
+        Self::open(&s3_url(bucket, region, key, query))
+    }
+
+    /// Open a Google Cloud Storage object, by URL:  `https://storage.googleapis.com/{bucket}/{object}`.
+    ///
+    /// Public objects need no `token`.  Otherwise, pass an OAuth 2 access token (as printed by e.g.
+    /// `gcloud auth print-access-token`), which is sent as a bearer token.  Each `/`-separated segment of `object` is
+    /// percent-encoded.
+    pub fn gcs(bucket: &str, object: &str, token: Option<&str>) -> io::Result<Self> {
+        let url = gcs_url(bucket, object);
+        match token {
+            Some(token) => Self::open_with_headers(&url, &[("Authorization", &format!("Bearer {}", token))]),
+            None        => Self::open(&url),
+        }
+    }
+
+    fn get(&self, start: u64, end: u64) -> io::Result<ureq::Response> {
+        let mut request = self.agent.get(&self.url).set("Range", &format!("bytes={}-{}", start, end));
+        for (name, value) in self.headers.iter() { request = request.set(name, value); }
+        let response = request.call().map_err(|error| match error {
+            ureq::Error::Status(404, _) => Error::new(ErrorKind::NotFound, format!("HTTP 404 Not Found: {}", self.url)),
+            other                       => Error::new(ErrorKind::Other, format!("{}: {}", self.url, other)),
+        })?;
+        if response.status() != 206 {
+            return Err(Error::new(ErrorKind::Other, format!("Server doesn't support range requests (status {}): {}", response.status(), self.url)));
+        }
+        Ok(response)
+    }
+}
+
+#[cfg(feature = "http")]
+fn s3_url(bucket: &str, region: &str, key: &str, query: Option<&str>) -> String {
+    let mut url = format!("https://{}.s3.{}.amazonaws.com/{}", bucket, region, encode_path(key.trim_start_matches('/')));
+    if let Some(query) = query { url.push('?'); url.push_str(query.trim_start_matches('?')); }
+    url
+}
+
+#[cfg(feature = "http")]
+fn gcs_url(bucket: &str, object: &str) -> String {
+    format!("https://storage.googleapis.com/{}/{}", encode_path(bucket), encode_path(object.trim_start_matches('/')))
+}
+
+/// Percent-encode each `/`-separated segment of `path`, leaving only RFC 3986 unreserved characters as-is.
+#[cfg(feature = "http")]
+fn encode_path(path: &str) -> String {
+    let mut encoded = String::with_capacity(path.len());
+    for b in path.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/'  => encoded.push(b as char),
+            _                                                                           => encoded.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    encoded
+}
+
+#[cfg(feature = "http")]
+impl ReadAt for Http {
+    fn size(&self) -> io::Result<u64> { Ok(self.len) }
+
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<usize> {
+        if offset >= self.len || buf.is_empty() { return Ok(0); }
+        let end = (offset + buf.len() as u64).min(self.len) - 1;
+        let mut body = self.get(offset, end)?.into_reader().take(end + 1 - offset);
+        let mut read = 0;
+        loop {
+            match body.read(&mut buf[read..])? {
+                0 => return Ok(read),
+                n => read += n,
+            }
+        }
+    }
+}
+
+#[test] fn block_reader() {
+    let bytes = (0..(3 * BLOCK_SIZE + 7)).map(|i| (i % 251) as u8).collect::<Vec<u8>>();
+    let mut reader = BlockReader::new(Box::new(bytes.clone())).unwrap();
+    reader.seek(SeekFrom::End(-10)).unwrap();
+    let mut tail = Vec::new();
+    reader.read_to_end(&mut tail).unwrap();
+    assert_eq!(tail, &bytes[bytes.len()-10..]);
+
+    reader.seek(SeekFrom::Start(BLOCK_SIZE - 3)).unwrap();
+    let mut straddle = [0u8; 6];
+    reader.read_exact(&mut straddle).unwrap();
+    assert_eq!(straddle, &bytes[BLOCK_SIZE as usize - 3 .. BLOCK_SIZE as usize + 3]);
+    assert!(reader.seek(SeekFrom::Current(-(BLOCK_SIZE as i64) * 2)).is_err());
+
+    let object = crate::testing::ClassFile::new("java/lang/Object", None).to_bytes();
+    let jar = std::fs::read(crate::testing::write_jar("storage-block-reader", &[("java/lang/Object.class", object.clone())])).unwrap();
+    let src = crate::Source::from_jar_storage(jar).unwrap();
+    assert_eq!(src.read_class_bytes("java/lang/Object").unwrap(), object);
+}
+
+#[cfg(feature = "http")]
+#[test] fn http_range_requests() {
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::sync::{Arc, Mutex};
+
+    let object = crate::testing::ClassFile::new("java/lang/Object", None).to_bytes();
+    let padding = vec![7u8; 3 * BLOCK_SIZE as usize]; // Large enough that reading Object shouldn't fetch everything
+    let jar = std::fs::read(crate::testing::write_jar("storage-http", &[("big.bin", padding), ("java/lang/Object.class", object.clone())])).unwrap();
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/test.jar", listener.local_addr().unwrap());
+    let served = Arc::new(Mutex::new(0usize));
+    let (jar, server_served) = (Arc::new(jar), served.clone());
+    std::thread::spawn(move ||{
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut range = None;
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut line = String::new();
+            while { line.clear(); reader.read_line(&mut line).unwrap() > 2 } {
+                let lower = line.to_ascii_lowercase();
+                if lower.starts_with("range: bytes=") {
+                    let mut parts = lower["range: bytes=".len()..].trim().split('-').map(|n| n.parse::<usize>().unwrap());
+                    range = Some((parts.next().unwrap(), parts.next().unwrap()));
+                }
+            }
+            let (start, end) = range.unwrap();
+            let body = &jar[start..=end.min(jar.len()-1)];
+            *server_served.lock().unwrap() += body.len();
+            write!(stream, "HTTP/1.1 206 Partial Content\r\nContent-Range: bytes {}-{}/{}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n", start, start + body.len() - 1, jar.len(), body.len()).unwrap();
+            stream.write_all(body).unwrap();
+        }
+    });
+
+    let src = crate::Source::from_jar_url(&url).unwrap();
+    assert_eq!(src.read_class_bytes("java/lang/Object").unwrap(), object);
+    assert_eq!(src.classes::<Vec<String>>().unwrap(), &["java/lang/Object"]);
+    assert!(*served.lock().unwrap() < 3 * BLOCK_SIZE as usize, "should only download the blocks needed");
+}
+
+#[cfg(feature = "http")]
+#[test] fn blob_store_urls() {
+    assert_eq!(s3_url("bucket", "us-east-1", "/releases/app 1.0+final.jar", None),         "https://bucket.s3.us-east-1.amazonaws.com/releases/app%201.0%2Bfinal.jar");
+    assert_eq!(s3_url("bucket", "us-east-1", "a?b#c.jar", Some("?X-Amz-Expires=60")),      "https://bucket.s3.us-east-1.amazonaws.com/a%3Fb%23c.jar?X-Amz-Expires=60");
+    assert_eq!(gcs_url("my-artifacts", "releases/caf\u{e9}/100%.jar"),                      "https://storage.googleapis.com/my-artifacts/releases/caf%C3%A9/100%25.jar");
+}
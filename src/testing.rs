@@ -0,0 +1,157 @@
+//! Helpers for synthesizing .class files in unit tests.
+
+#![allow(dead_code)] // Not every test uses every helper
+
+use crate::{version, Class};
+
+use std::collections::*;
+use std::io::Cursor;
+#[cfg(feature = "jar")] use std::io::Write;
+#[cfg(feature = "jar")] use std::path::PathBuf;
+
+
+
+/// A minimal .class file assembler.  Attribute contents are supplied as raw bytes.
+pub(crate) struct ClassFile {
+    pub major_version:  u16,
+    pub flags:          u16,
+    constants:          Vec<u8>,
+    constants_count:    u16,
+    utf8s:              BTreeMap<String, u16>,
+    this_class:         u16,
+    super_class:        u16,
+    interfaces:         Vec<u16>,
+    fields:             Vec<u8>,
+    fields_count:       u16,
+    methods:            Vec<u8>,
+    methods_count:      u16,
+    attributes:         Vec<u8>,
+    attributes_count:   u16,
+}
+
+impl ClassFile {
+    pub fn new(this: &str, super_class: Option<&str>) -> Self {
+        let mut c = ClassFile {
+            major_version:      version::Major::JavaSE_8.0,
+            flags:              0x0021, // PUBLIC | SUPER
+            constants:          Vec::new(),
+            constants_count:    1,
+            utf8s:              BTreeMap::new(),
+            this_class:         0,
+            super_class:        0,
+            interfaces:         Vec::new(),
+            fields:             Vec::new(),
+            fields_count:       0,
+            methods:            Vec::new(),
+            methods_count:      0,
+            attributes:         Vec::new(),
+            attributes_count:   0,
+        };
+        c.this_class = c.class(this);
+        c.super_class = super_class.map_or(0, |s| c.class(s));
+        c
+    }
+
+    pub fn constant(&mut self, tag: u8, info: &[u8]) -> u16 {
+        let index = self.constants_count;
+        self.constants.push(tag);
+        self.constants.extend_from_slice(info);
+        self.constants_count += if tag == 5 || tag == 6 { 2 } else { 1 };
+        index
+    }
+
+    pub fn utf8(&mut self, s: &str) -> u16 {
+        if let Some(&index) = self.utf8s.get(s) { return index; }
+        let mut info = be16(s.len() as u16);
+        info.extend_from_slice(s.as_bytes());
+        let index = self.constant(1, &info[..]);
+        self.utf8s.insert(s.to_owned(), index);
+        index
+    }
+
+    pub fn class(&mut self, name: &str) -> u16 { let n = self.utf8(name); self.constant(7, &be16(n)) }
+    pub fn string(&mut self, s: &str) -> u16 { let n = self.utf8(s); self.constant(8, &be16(n)) }
+    pub fn integer(&mut self, i: i32) -> u16 { self.constant(3, &i.to_be_bytes()) }
+    pub fn long(&mut self, l: i64) -> u16 { self.constant(5, &l.to_be_bytes()) }
+
+    pub fn name_and_type(&mut self, name: &str, descriptor: &str) -> u16 {
+        let n = self.utf8(name);
+        let d = self.utf8(descriptor);
+        self.constant(12, &[be16(n), be16(d)].concat())
+    }
+
+    pub fn member_ref(&mut self, tag: u8, class: &str, name: &str, descriptor: &str) -> u16 {
+        let c = self.class(class);
+        let nt = self.name_and_type(name, descriptor);
+        self.constant(tag, &[be16(c), be16(nt)].concat())
+    }
+
+    pub fn fieldref(&mut self, class: &str, name: &str, descriptor: &str) -> u16 { self.member_ref(9, class, name, descriptor) }
+    pub fn methodref(&mut self, class: &str, name: &str, descriptor: &str) -> u16 { self.member_ref(10, class, name, descriptor) }
+
+    pub fn interface(&mut self, name: &str) { let i = self.class(name); self.interfaces.push(i); }
+
+    /// Encode an attribute (name index, length, and info) for use with `field`, `method`, or nested attributes.
+    pub fn attribute(&mut self, name: &str, info: &[u8]) -> Vec<u8> {
+        let n = self.utf8(name);
+        [be16(n), be32(info.len() as u32), info.to_vec()].concat()
+    }
+
+    pub fn field(&mut self, flags: u16, name: &str, descriptor: &str, attributes: &[Vec<u8>]) {
+        let member = self.member(flags, name, descriptor, attributes);
+        self.fields.extend(member);
+        self.fields_count += 1;
+    }
+
+    pub fn method(&mut self, flags: u16, name: &str, descriptor: &str, attributes: &[Vec<u8>]) {
+        let member = self.member(flags, name, descriptor, attributes);
+        self.methods.extend(member);
+        self.methods_count += 1;
+    }
+
+    pub fn class_attribute(&mut self, name: &str, info: &[u8]) {
+        let a = self.attribute(name, info);
+        self.attributes.extend(a);
+        self.attributes_count += 1;
+    }
+
+    fn member(&mut self, flags: u16, name: &str, descriptor: &str, attributes: &[Vec<u8>]) -> Vec<u8> {
+        let n = self.utf8(name);
+        let d = self.utf8(descriptor);
+        [be16(flags), be16(n), be16(d), be16(attributes.len() as u16), attributes.concat()].concat()
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        [
+            be32(0xCAFEBABE), be16(0), be16(self.major_version),
+            be16(self.constants_count), self.constants.clone(),
+            be16(self.flags), be16(self.this_class), be16(self.super_class),
+            be16(self.interfaces.len() as u16), self.interfaces.iter().map(|i| be16(*i)).collect::<Vec<_>>().concat(),
+            be16(self.fields_count), self.fields.clone(),
+            be16(self.methods_count), self.methods.clone(),
+            be16(self.attributes_count), self.attributes.clone(),
+        ].concat()
+    }
+
+    pub fn read(&self) -> Class {
+        Class::read(&mut Cursor::new(self.to_bytes())).unwrap()
+    }
+}
+
+/// Write a .jar containing `entries` to a temporary directory, returning its path.  `name` should be unique per test.
+#[cfg(feature = "jar")]
+pub(crate) fn write_jar(name: &str, entries: &[(&str, Vec<u8>)]) -> PathBuf {
+    let dir = std::env::temp_dir().join("jreflection-tests");
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(format!("{}.jar", name));
+    let mut zip = zip::ZipWriter::new(std::fs::File::create(&path).unwrap());
+    for (name, bytes) in entries.iter() {
+        zip.start_file(*name, zip::write::FileOptions::default()).unwrap();
+        zip.write_all(&bytes[..]).unwrap();
+    }
+    zip.finish().unwrap();
+    path
+}
+
+pub(crate) fn be16(v: u16) -> Vec<u8> { v.to_be_bytes().to_vec() }
+pub(crate) fn be32(v: u32) -> Vec<u8> { v.to_be_bytes().to_vec() }
@@ -0,0 +1,1004 @@
+//! Post-processing of class files:  a [Pipeline] of [Pass]es applied to each class between parsing and re-emitting it.
+//!
+//! Unlike [Class](../struct.Class.html), which decodes a class into a convenient but lossy model, [ClassFile] keeps
+//! the class file structure as-is - constant pool indices, raw flags, and undecoded attributes - so a class that no
+//! pass modifies is re-emitted byte-for-byte, and bytecode referencing the constant pool remains valid.
+//!
+//! [Pipeline]:     struct.Pipeline.html
+//! [Pass]:         trait.Pass.html
+//! [ClassFile]:    struct.ClassFile.html
+
+use crate::{annotation, attributes, class, hierarchy, jar, Class, Hierarchy, Method};
+use crate::constants::{Constant, Constants};
+use crate::attributes::RawAttribute;
+use crate::io::be::*;
+#[cfg(test)] use crate::{testing, ParseOptions, Source};
+
+use zip::{ZipArchive, ZipWriter};
+use zip::write::FileOptions;
+
+use std::borrow::Cow;
+use std::convert::TryFrom;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Error, ErrorKind, Read, Write};
+use std::path::Path;
+
+
+
+/// [Java SE 7 &sect; 4.1](https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.1):  A class file,
+/// as its raw structure.
+///
+/// Attribute names are resolved to strings, and re-added to the constant pool as needed when written, so passes can add
+/// or remove attributes without managing `attribute_name_index`es themselves.
+#[derive(Clone, Debug)]
+pub struct ClassFile {
+    pub minor_version:  u16,
+    pub major_version:  u16,
+    pub constants:      Constants,
+
+    /// The raw `access_flags` of the class.  See [class::Flags](../class/struct.Flags.html) for their meaning.
+    pub access_flags:   u16,
+
+    /// The index of the CONSTANT_Class_info naming this class.
+    pub this_class:     u16,
+
+    /// The index of the CONSTANT_Class_info naming the superclass, or 0 for `java/lang/Object` and `module-info`.
+    pub super_class:    u16,
+
+    /// The indices of the CONSTANT_Class_info naming each directly implemented interface.
+    pub interfaces:     Vec<u16>,
+
+    pub fields:         Vec<Member>,
+    pub methods:        Vec<Member>,
+    pub attributes:     Vec<RawAttribute>,
+}
+
+/// [Java SE 7 &sect; 4.5](https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.5):  A field or method
+/// of a [ClassFile](struct.ClassFile.html), as its raw structure.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Member {
+    /// The raw `access_flags` of the member.  See [field::Flags](../field/struct.Flags.html) and [method::Flags](../method/struct.Flags.html) for their meaning.
+    pub access_flags:       u16,
+
+    /// The index of the CONSTANT_Utf8_info containing the member's name.
+    pub name_index:         u16,
+
+    /// The index of the CONSTANT_Utf8_info containing the member's descriptor.
+    pub descriptor_index:   u16,
+
+    pub attributes:         Vec<RawAttribute>,
+}
+
+impl ClassFile {
+    /// Read a class file.
+    pub fn read(read: &mut impl Read) -> io::Result<Self> {
+        if read_u4(read)? != 0xCAFEBABE { return io_data_err!("Invalid header magic, not a class file"); }
+        let minor_version   = read_u2(read)?;
+        let major_version   = read_u2(read)?;
+        let constants       = Constants::read(read)?;
+        let access_flags    = read_u2(read)?;
+        let this_class      = read_u2(read)?;
+        let super_class     = read_u2(read)?;
+        let interfaces_count = read_u2(read)? as usize;
+        let mut interfaces  = Vec::with_capacity(interfaces_count);
+        for _ in 0..interfaces_count { interfaces.push(read_u2(read)?); }
+        let fields          = Member::read_list(read, &constants)?;
+        let methods         = Member::read_list(read, &constants)?;
+        let attributes      = read_attributes(read, &constants)?;
+        Ok(Self { minor_version, major_version, constants, access_flags, this_class, super_class, interfaces, fields, methods, attributes })
+    }
+
+    /// Write the class file, adding any attribute names missing from the constant pool to the end of it.
+    pub fn write(&self, write: &mut impl Write) -> io::Result<()> {
+        let mut constants = Cow::Borrowed(&self.constants);
+        let mut body = Vec::new();
+        body.extend_from_slice(&self.access_flags.to_be_bytes());
+        body.extend_from_slice(&self.this_class.to_be_bytes());
+        body.extend_from_slice(&self.super_class.to_be_bytes());
+        write_u2_len(&mut body, self.interfaces.len(), "interfaces")?;
+        for interface in self.interfaces.iter() { body.extend_from_slice(&interface.to_be_bytes()); }
+        for members in [&self.fields, &self.methods].iter() {
+            write_u2_len(&mut body, members.len(), "members")?;
+            for member in members.iter() {
+                body.extend_from_slice(&member.access_flags.to_be_bytes());
+                body.extend_from_slice(&member.name_index.to_be_bytes());
+                body.extend_from_slice(&member.descriptor_index.to_be_bytes());
+                write_attributes(&mut body, &member.attributes[..], &mut constants)?;
+            }
+        }
+        write_attributes(&mut body, &self.attributes[..], &mut constants)?;
+
+        write.write_all(&0xCAFEBABEu32.to_be_bytes())?;
+        write.write_all(&self.minor_version.to_be_bytes())?;
+        write.write_all(&self.major_version.to_be_bytes())?;
+        constants.write(write)?;
+        write.write_all(&body[..])
+    }
+
+    /// The class file as bytes.  See [write](#method.write).
+    pub fn to_bytes(&self) -> io::Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        self.write(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// The JNI path of this class, such as `"com/example/Main"`.
+    pub fn name(&self) -> io::Result<&str> { self.constants.get_class(self.this_class) }
+
+    /// The JNI path of the superclass, such as `"java/lang/Object"`, if any.
+    pub fn super_name(&self) -> io::Result<Option<&str>> { self.constants.get_optional_class(self.super_class) }
+
+    /// The first class attribute named `name`, if any.
+    pub fn attribute(&self, name: &str) -> Option<&RawAttribute> { self.attributes.iter().find(|a| a.name == name) }
+}
+
+impl Member {
+    fn read_list(read: &mut impl Read, constants: &Constants) -> io::Result<Vec<Self>> {
+        let count = read_u2(read)? as usize;
+        let mut members = Vec::with_capacity(count);
+        for _ in 0..count {
+            let access_flags        = read_u2(read)?;
+            let name_index          = read_u2(read)?;
+            let descriptor_index    = read_u2(read)?;
+            let attributes          = read_attributes(read, constants)?;
+            members.push(Self { access_flags, name_index, descriptor_index, attributes });
+        }
+        Ok(members)
+    }
+
+    /// The name of this member, such as `"toString"`.
+    pub fn name<'c>(&self, constants: &'c Constants) -> io::Result<&'c str> { constants.get_utf8(self.name_index) }
+
+    /// The descriptor of this member, such as `"()Ljava/lang/String;"`.
+    pub fn descriptor<'c>(&self, constants: &'c Constants) -> io::Result<&'c str> { constants.get_utf8(self.descriptor_index) }
+
+    /// The first attribute named `name`, if any.
+    pub fn attribute(&self, name: &str) -> Option<&RawAttribute> { self.attributes.iter().find(|a| a.name == name) }
+}
+
+fn read_attributes(read: &mut impl Read, constants: &Constants) -> io::Result<Vec<RawAttribute>> {
+    let count = read_u2(read)? as usize;
+    let mut attributes = Vec::with_capacity(count);
+    for _ in 0..count {
+        let name = constants.get_utf8(read_u2(read)?)?.to_owned();
+        let len = read_u4(read)? as usize;
+        let info = attributes::read_attribute_info(read, &name, len)?;
+        attributes.push(RawAttribute { name, info });
+    }
+    Ok(attributes)
+}
+
+fn write_attributes(out: &mut Vec<u8>, attributes: &[RawAttribute], constants: &mut Cow<Constants>) -> io::Result<()> {
+    write_u2_len(out, attributes.len(), "attributes")?;
+    for attribute in attributes.iter() {
+        let name_index = match constants.find_utf8(&attribute.name) {
+            Some(index) => index,
+            None        => constants.to_mut().add_utf8(&attribute.name)?,
+        };
+        let len = u32::try_from(attribute.info.len()).map_err(|_| Error::new(ErrorKind::InvalidInput, format!("{} attribute is too large", attribute.name)))?;
+        out.extend_from_slice(&name_index.to_be_bytes());
+        out.extend_from_slice(&len.to_be_bytes());
+        out.extend_from_slice(&attribute.info[..]);
+    }
+    Ok(())
+}
+
+fn write_u2_len(out: &mut Vec<u8>, len: usize, what: &str) -> io::Result<()> {
+    if len > 0xFFFF { return Err(Error::new(ErrorKind::InvalidInput, format!("Too many {}: {}", what, len))); }
+    out.extend_from_slice(&(len as u16).to_be_bytes());
+    Ok(())
+}
+
+
+
+/// A transformation of a [ClassFile](struct.ClassFile.html).  Implemented for closures taking `&mut ClassFile`.
+pub trait Pass: Send + Sync {
+    fn apply(&self, class: &mut ClassFile) -> io::Result<()>;
+
+    /// The new name of the jar entry `name` (such as `"com/example/Main.class"` or `"com/example/icon.png"`), if this
+    /// pass moves it.  Used by [Pipeline::apply_jar](struct.Pipeline.html#method.apply_jar):  classes are only renamed by
+    /// steps that apply to them, resources by every step.
+    fn rename_entry(&self, _name: &str) -> Option<String> { None }
+}
+
+impl<F: Fn(&mut ClassFile) -> io::Result<()> + Send + Sync> Pass for F {
+    fn apply(&self, class: &mut ClassFile) -> io::Result<()> { self(class) }
+}
+
+type ClassFilter = dyn Fn(&str) -> bool + Send + Sync;
+
+/// A [Pass](trait.Pass.html) registered with a [Pipeline](struct.Pipeline.html), and when to apply it.
+pub struct Step {
+    order:      i32,
+    classes:    Option<Box<ClassFilter>>,
+    pass:       Box<dyn Pass>,
+}
+
+impl Step {
+    /// Run this step before steps with a higher `order`, and after steps with a lower one.  Steps with the same order
+    /// (all of them, by default) run in the order they were added.
+    pub fn order(&mut self, order: i32) -> &mut Self {
+        self.order = order;
+        self
+    }
+
+    /// Only apply this step to classes whose JNI path (such as `"com/example/Main"`) matches `classes`.
+    pub fn only(&mut self, classes: impl Fn(&str) -> bool + Send + Sync + 'static) -> &mut Self {
+        self.classes = Some(Box::new(classes));
+        self
+    }
+
+    fn applies_to(&self, path: &str) -> bool {
+        match self.classes.as_ref() {
+            Some(classes)   => classes(path),
+            None            => true,
+        }
+    }
+}
+
+/// What [Pipeline::apply_jar](struct.Pipeline.html#method.apply_jar) did.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TransformStats {
+    /// How many classes at least one step applied to, and were re-emitted.
+    pub classes_transformed:    usize,
+
+    /// How many classes no step applied to, and were copied as-is.
+    pub classes_copied:         usize,
+
+    /// How many resources (including the manifest and directory entries) were copied as-is.
+    pub resources_copied:       usize,
+
+    /// How many signature files (`META-INF/*.SF`, `*.RSA`, ...) were omitted, since transforming invalidates them.
+    pub signatures_removed:     usize,
+
+    /// How many classes and resources were moved by a pass's [rename_entry](trait.Pass.html#method.rename_entry).
+    pub entries_renamed:        usize,
+}
+
+/// An ordered list of [Pass](trait.Pass.html)es to apply to classes.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use jreflection::*;
+/// let mut pipeline = transform::Pipeline::new();
+/// pipeline.add(transform::remove_members(|name, _descriptor| name.starts_with("debug")))
+///     .only(|class| class.starts_with("com/example/"));
+/// pipeline.add(transform::strip_attributes(&["SourceFile"]));
+/// pipeline.add(|class: &mut transform::ClassFile| {
+///     class.access_flags |= 0x0010; // ACC_FINAL
+///     Ok(())
+/// }).order(-1).only(|class| class == "com/example/Main");
+///
+/// let stats = pipeline.apply_jar("app.jar", "app-release.jar").unwrap();
+/// println!("transformed {} classes", stats.classes_transformed);
+/// ```
+#[derive(Default)]
+pub struct Pipeline {
+    steps: Vec<Step>,
+}
+
+impl Pipeline {
+    /// Create an empty pipeline.
+    pub fn new() -> Self { Self::default() }
+
+    /// Add `pass`, applying to every class at order 0, returning its [Step](struct.Step.html) for further configuration.
+    pub fn add(&mut self, pass: impl Pass + 'static) -> &mut Step {
+        self.steps.push(Step { order: 0, classes: None, pass: Box::new(pass) });
+        self.steps.last_mut().unwrap()
+    }
+
+    /// Apply every step that applies to `class`, in order.  Returns if any steps applied.
+    pub fn apply_to(&self, class: &mut ClassFile) -> io::Result<bool> {
+        let path = class.name()?.to_owned();
+        let mut steps = self.steps.iter().filter(|step| step.applies_to(&path)).collect::<Vec<_>>();
+        steps.sort_by_key(|step| step.order);
+        for step in steps.iter() {
+            step.pass.apply(class).map_err(|err| Error::new(err.kind(), format!("Error transforming {}: {}", path, err)))?;
+        }
+        Ok(!steps.is_empty())
+    }
+
+    /// Transform the raw bytes of a .class file.  Classes no step applies to are returned unmodified.
+    pub fn apply(&self, bytes: &[u8]) -> io::Result<Vec<u8>> {
+        Ok(self.transform(bytes)?.unwrap_or_else(|| bytes.to_vec()))
+    }
+
+    /// Copy the jar at `input` to `output`, transforming its classes.
+    ///
+    /// Classes no step applies to and resources are copied without recompression.  Transformed classes keep their
+    /// original compression method and timestamps.  Jar signature files are omitted, as transformed classes would no
+    /// longer match them.  Entries are moved if a pass [renames](trait.Pass.html#method.rename_entry) them.
+    pub fn apply_jar(&self, input: impl AsRef<Path>, output: impl AsRef<Path>) -> io::Result<TransformStats> {
+        let mut input   = ZipArchive::new(BufReader::new(File::open(input)?))?;
+        let mut output  = ZipWriter::new(BufWriter::new(File::create(output)?));
+        let mut stats   = TransformStats::default();
+
+        for i in 0..input.len() {
+            let (name, transformed) = {
+                let mut entry = input.by_index(i)?;
+                let name = entry.name().to_owned();
+                if !name.ends_with(".class") || name.ends_with("module-info.class") {
+                    (name, None)
+                } else {
+                    let mut bytes = Vec::new();
+                    entry.read_to_end(&mut bytes)?;
+                    let transformed = self.transform(&bytes[..]).map_err(|err| Error::new(err.kind(), format!("{}: {}", name, err)))?;
+                    (name, transformed.map(|bytes| (bytes, entry.compression(), entry.last_modified(), entry.unix_mode())))
+                }
+            };
+
+            let renamed = self.rename_entry(&name);
+            if renamed.is_some() { stats.entries_renamed += 1; }
+
+            if let Some((bytes, compression, modified, mode)) = transformed {
+                let mut options = FileOptions::default().compression_method(compression).last_modified_time(modified);
+                if let Some(mode) = mode { options = options.unix_permissions(mode); }
+                output.start_file(renamed.unwrap_or(name), options)?;
+                output.write_all(&bytes[..])?;
+                stats.classes_transformed += 1;
+                continue;
+            } else if name.ends_with(".class") {
+                stats.classes_copied += 1;
+            } else if jar::is_signature_file(&name) {
+                stats.signatures_removed += 1;
+                continue;
+            } else {
+                stats.resources_copied += 1;
+            }
+            match renamed {
+                Some(renamed)   => output.raw_copy_file_rename(input.by_index_raw(i)?, renamed)?,
+                None            => output.raw_copy_file(input.by_index_raw(i)?)?,
+            }
+        }
+
+        output.finish()?;
+        Ok(stats)
+    }
+
+    /// The new name of jar entry `name`, if any step renames it.
+    fn rename_entry(&self, name: &str) -> Option<String> {
+        if name.ends_with("module-info.class") { return None; }
+        let class = if name.ends_with(".class") { Some(&name[..name.len() - ".class".len()]) } else { None };
+        let mut steps = self.steps.iter().filter(|step| class.iter().all(|class| step.applies_to(class))).collect::<Vec<_>>();
+        steps.sort_by_key(|step| step.order);
+        let mut renamed : Option<String> = None;
+        for step in steps.iter() {
+            if let Some(name) = step.pass.rename_entry(renamed.as_ref().map(|n| n.as_str()).unwrap_or(name)) { renamed = Some(name); }
+        }
+        renamed
+    }
+
+    /// The transformed class, or `None` if no step applies to it.
+    fn transform(&self, bytes: &[u8]) -> io::Result<Option<Vec<u8>>> {
+        let mut class = ClassFile::read(&mut &bytes[..])?;
+        if self.apply_to(&mut class)? { Ok(Some(class.to_bytes()?)) } else { Ok(None) }
+    }
+}
+
+
+
+/// A pass removing every class, field, and method attribute named any of `names`.  Attributes nested within `Code`
+/// attributes are left alone - see [Strip](struct.Strip.html) for removing debug information from those.
+pub fn strip_attributes(names: &[&str]) -> impl Pass {
+    let names = names.iter().map(|name| name.to_string()).collect::<Vec<_>>();
+    move |class: &mut ClassFile| {
+        let keep = |attribute: &RawAttribute| !names.contains(&attribute.name);
+        class.attributes.retain(keep);
+        for member in class.fields.iter_mut().chain(class.methods.iter_mut()) { member.attributes.retain(keep); }
+        Ok(())
+    }
+}
+
+/// A pass removing every field and method for which `predicate(name, descriptor)` is true.
+///
+/// References to removed members, from this or other classes, are left as-is:  it's up to you to only remove members
+/// nothing uses, or to remove the code using them as well.
+pub fn remove_members(predicate: impl Fn(&str, &str) -> bool + Send + Sync) -> impl Pass {
+    move |class: &mut ClassFile| {
+        let constants = &class.constants;
+        for members in [&mut class.fields, &mut class.methods].iter_mut() {
+            let mut error = None;
+            members.retain(|member| match member.name(constants).and_then(|name| Ok(predicate(name, member.descriptor(constants)?))) {
+                Ok(remove)  => !remove,
+                Err(err)    => { error = Some(err); true },
+            });
+            if let Some(err) = error { return Err(err); }
+        }
+        Ok(())
+    }
+}
+
+/// A pass renaming the member `name` (with descriptor `descriptor`) of the class `owner` to `new_name`:  its
+/// declaration when applied to `owner` itself, and field and method references to it when applied to any class.
+///
+/// Only references naming `owner` as the member's class are renamed - references through subclasses (such as
+/// `invokevirtual com/example/Derived.run()V` for a method declared by `com/example/Base`) must be renamed separately.
+/// Overriding methods aren't renamed either.
+pub fn rename_member(owner: &str, name: &str, descriptor: &str, new_name: &str) -> impl Pass {
+    let (owner, name, descriptor, new_name) = (owner.to_owned(), name.to_owned(), descriptor.to_owned(), new_name.to_owned());
+    move |class: &mut ClassFile| {
+        let constants = &mut class.constants;
+        let mut renamed = None;
+        for index in 1 .. constants.0.len() {
+            let (class_index, name_and_type_index) = match constants.0[index] {
+                Constant::Fieldref { class_index, name_and_type_index }             => (class_index, name_and_type_index),
+                Constant::Methodref { class_index, name_and_type_index }            => (class_index, name_and_type_index),
+                Constant::InterfaceMethodref { class_index, name_and_type_index }   => (class_index, name_and_type_index),
+                _                                                                   => continue,
+            };
+            if constants.get_class(class_index)? != owner { continue; }
+            match constants.get(name_and_type_index)? {
+                Constant::NameAndType { name_index, descriptor_index } => if constants.get_utf8(*name_index)? != name || constants.get_utf8(*descriptor_index)? != descriptor { continue; },
+                other => return io_data_err!("Expected a CONSTANT_NameAndType_info at constant #{}, found a {:?} instead", name_and_type_index, other),
+            }
+            let renamed_index = match renamed { Some(index) => index, None => constants.add_name_and_type(&new_name, &descriptor)? };
+            renamed = Some(renamed_index);
+            match constants.0[index] {
+                Constant::Fieldref { ref mut name_and_type_index, .. } | Constant::Methodref { ref mut name_and_type_index, .. } | Constant::InterfaceMethodref { ref mut name_and_type_index, .. } => *name_and_type_index = renamed_index,
+                _ => unreachable!(),
+            }
+        }
+
+        if class.constants.get_class(class.this_class)? == owner {
+            let members = if descriptor.starts_with('(') { &mut class.methods } else { &mut class.fields };
+            for member in members.iter_mut() {
+                if member.name(&class.constants)? == name && member.descriptor(&class.constants)? == descriptor {
+                    member.name_index = class.constants.add_utf8(&new_name)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A pass stripping debug information and/or invisible annotations, for smaller release builds.  Nothing is stripped
+/// by default.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use jreflection::*;
+/// let mut strip = transform::Strip::debug_info();
+/// strip.invisible_annotations(true);
+///
+/// let mut pipeline = transform::Pipeline::new();
+/// pipeline.add(strip);
+/// pipeline.apply_jar("app.jar", "app-release.jar").unwrap();
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Strip {
+    line_numbers:           bool,
+    local_variables:        bool,
+    source_file:            bool,
+    invisible_annotations:  bool,
+}
+
+impl Strip {
+    /// Strip nothing (yet.)
+    pub fn new() -> Self { Self::default() }
+
+    /// Strip everything `javac -g:none` would omit:  line numbers, local variables, and source files.
+    pub fn debug_info() -> Self { Self { line_numbers: true, local_variables: true, source_file: true, invisible_annotations: false } }
+
+    /// Strip `LineNumberTable`s from method code.  Stack traces will lack line numbers.
+    pub fn line_numbers(&mut self, strip: bool) -> &mut Self {
+        self.line_numbers = strip;
+        self
+    }
+
+    /// Strip `LocalVariableTable`s and `LocalVariableTypeTable`s from method code.  Debuggers will lack local
+    /// variable names, as will [Method::parameter_names](../struct.Method.html#method.parameter_names) (unless the
+    /// method also has a `MethodParameters` attribute.)
+    pub fn local_variables(&mut self, strip: bool) -> &mut Self {
+        self.local_variables = strip;
+        self
+    }
+
+    /// Strip `SourceFile` and `SourceDebugExtension` class attributes.  Stack traces will lack file names.
+    pub fn source_file(&mut self, strip: bool) -> &mut Self {
+        self.source_file = strip;
+        self
+    }
+
+    /// Strip `RuntimeInvisibleAnnotations`, `RuntimeInvisibleParameterAnnotations`, and
+    /// `RuntimeInvisibleTypeAnnotations` (`@Retention(CLASS)` annotations, such as most nullability annotations) from
+    /// classes, fields, methods, and method code.  Reflection can't see these anyways, but compilers and static
+    /// analysis using the stripped classes will.
+    pub fn invisible_annotations(&mut self, strip: bool) -> &mut Self {
+        self.invisible_annotations = strip;
+        self
+    }
+
+    fn strips(&self, name: &str) -> bool {
+        match name {
+            "LineNumberTable"                       => self.line_numbers,
+            "LocalVariableTable"
+            | "LocalVariableTypeTable"              => self.local_variables,
+            "SourceFile" | "SourceDebugExtension"   => self.source_file,
+            "RuntimeInvisibleAnnotations"
+            | "RuntimeInvisibleParameterAnnotations"
+            | "RuntimeInvisibleTypeAnnotations"     => self.invisible_annotations,
+            _                                       => false,
+        }
+    }
+}
+
+impl Pass for Strip {
+    fn apply(&self, class: &mut ClassFile) -> io::Result<()> {
+        class.attributes.retain(|a| !self.strips(&a.name));
+        for member in class.fields.iter_mut().chain(class.methods.iter_mut()) {
+            member.attributes.retain(|a| !self.strips(&a.name));
+            for attribute in member.attributes.iter_mut().filter(|a| a.name == "Code") {
+                retain_code_attributes(&mut attribute.info, &class.constants, |name| !self.strips(name))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Remove the attributes of the `Code` attribute `info` for which `keep(name)` is false.
+fn retain_code_attributes(info: &mut Vec<u8>, constants: &Constants, keep: impl Fn(&str) -> bool) -> io::Result<()> {
+    let mut read = &info[..];
+    read_ignore(&mut read, 4)?; // max_stack, max_locals
+    let code_length = read_u4(&mut read)? as usize;
+    read_ignore(&mut read, code_length)?;
+    let exception_table_length = read_u2(&mut read)? as usize;
+    read_ignore(&mut read, 8 * exception_table_length)?;
+    let attributes_offset = info.len() - read.len();
+
+    let count = read_u2(&mut read)?;
+    let mut kept = Vec::new();
+    let mut kept_count = 0u16;
+    for _ in 0..count {
+        let start = info.len() - read.len();
+        let name_index = read_u2(&mut read)?;
+        let len = read_u4(&mut read)? as usize;
+        read_ignore(&mut read, len)?;
+        if keep(constants.get_utf8(name_index)?) {
+            kept.extend_from_slice(&info[start .. info.len() - read.len()]);
+            kept_count += 1;
+        }
+    }
+    io_assert!(read.is_empty());
+    if kept_count == count { return Ok(()); }
+
+    info.truncate(attributes_offset);
+    info.extend_from_slice(&kept_count.to_be_bytes());
+    info.extend_from_slice(&kept[..]);
+    Ok(())
+}
+
+type MemberFilter = dyn Fn(&str, &str) -> bool + Send + Sync;
+
+/// A pass adding an annotation to classes, fields, and/or methods, such as `@Generated` or nullability annotations.
+/// Only the class itself is annotated by default.
+///
+/// Classes and members already annotated with an annotation of the same type are left as-is.  Any constants the
+/// annotation needs are added to the class's constant pool.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use jreflection::*;
+/// use jreflection::annotation::{Annotation, ElementValue};
+///
+/// let mut generated = transform::AddAnnotation::new(Annotation::new(
+///     "Ljavax/annotation/processing/Generated;".to_owned(),
+///     vec![("value".to_owned(), ElementValue::Array(vec![ElementValue::String("my-tool".to_owned())]))],
+/// ));
+/// generated.methods(|name, _descriptor| name.starts_with("get"));
+///
+/// let mut pipeline = transform::Pipeline::new();
+/// pipeline.add(generated).only(|class| class.starts_with("com/example/generated/"));
+/// pipeline.apply_jar("app.jar", "app-annotated.jar").unwrap();
+/// ```
+pub struct AddAnnotation {
+    annotation: annotation::Annotation,
+    visible:    bool,
+    class:      bool,
+    fields:     Option<Box<MemberFilter>>,
+    methods:    Option<Box<MemberFilter>>,
+}
+
+impl AddAnnotation {
+    /// Add `annotation` as a runtime-visible annotation, to the class only.
+    pub fn new(annotation: annotation::Annotation) -> Self {
+        Self { annotation, visible: true, class: true, fields: None, methods: None }
+    }
+
+    /// Add the annotation to `RuntimeVisibleAnnotations` (the default), or `RuntimeInvisibleAnnotations` for
+    /// `@Retention(CLASS)` annotations.
+    pub fn visible(&mut self, visible: bool) -> &mut Self {
+        self.visible = visible;
+        self
+    }
+
+    /// Annotate the class itself (the default.)
+    pub fn class(&mut self, annotate: bool) -> &mut Self {
+        self.class = annotate;
+        self
+    }
+
+    /// Annotate fields for which `predicate(name, descriptor)` is true.
+    pub fn fields(&mut self, predicate: impl Fn(&str, &str) -> bool + Send + Sync + 'static) -> &mut Self {
+        self.fields = Some(Box::new(predicate));
+        self
+    }
+
+    /// Annotate methods for which `predicate(name, descriptor)` is true.
+    pub fn methods(&mut self, predicate: impl Fn(&str, &str) -> bool + Send + Sync + 'static) -> &mut Self {
+        self.methods = Some(Box::new(predicate));
+        self
+    }
+
+    /// Add the annotation to `attributes`, unless an annotation of the same type is already present.
+    fn annotate(&self, attributes: &mut Vec<RawAttribute>, constants: &mut Constants) -> io::Result<()> {
+        for attribute in attributes.iter().filter(|a| a.name == "RuntimeVisibleAnnotations" || a.name == "RuntimeInvisibleAnnotations") {
+            let mut info = &attribute.info[..];
+            if annotation::Annotation::read_list(&mut info, constants)?.iter().any(|a| a.descriptor == self.annotation.descriptor) { return Ok(()); }
+        }
+
+        let name = if self.visible { "RuntimeVisibleAnnotations" } else { "RuntimeInvisibleAnnotations" };
+        let index = match attributes.iter().position(|a| a.name == name) {
+            Some(index) => index,
+            None        => { attributes.push(RawAttribute { name: name.to_owned(), info: vec![0, 0] }); attributes.len() - 1 },
+        };
+        let info = &mut attributes[index].info;
+        io_assert!(info.len() >= 2);
+        let count = u16::from_be_bytes([info[0], info[1]]).checked_add(1).ok_or_else(|| Error::new(ErrorKind::InvalidInput, format!("Too many annotations in {} attribute", name)))?;
+        info[..2].copy_from_slice(&count.to_be_bytes());
+        self.annotation.write(constants, info)
+    }
+}
+
+impl Pass for AddAnnotation {
+    fn apply(&self, class: &mut ClassFile) -> io::Result<()> {
+        if self.class { self.annotate(&mut class.attributes, &mut class.constants)?; }
+        for (members, predicate) in [(&mut class.fields, &self.fields), (&mut class.methods, &self.methods)].iter_mut() {
+            let predicate = match predicate { Some(predicate) => predicate, None => continue };
+            for member in members.iter_mut() {
+                if predicate(member.name(&class.constants)?, member.descriptor(&class.constants)?) {
+                    self.annotate(&mut member.attributes, &mut class.constants)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A pass adding interfaces to the `implements` (or, for interfaces, `extends`) list of classes, such as marker
+/// interfaces or interfaces a proxy or mixin provides the methods of.
+///
+/// Interfaces a class already lists directly are left as-is.  Passes can't consult a [Hierarchy] (which isn't
+/// `Send`), so use [unimplemented] or [apply_checked] to ensure the result doesn't leave abstract methods
+/// unimplemented.
+///
+/// [Hierarchy]:        ../struct.Hierarchy.html
+/// [unimplemented]:    #method.unimplemented
+/// [apply_checked]:    #method.apply_checked
+///
+/// # Examples
+///
+/// ```no_run
+/// # use jreflection::*;
+/// let mut pipeline = transform::Pipeline::new();
+/// pipeline.add(transform::AddInterface::new("java/io/Serializable")).only(|class| class.starts_with("com/example/model/"));
+/// pipeline.apply_jar("app.jar", "app-serializable.jar").unwrap();
+///
+/// // Validated against a classpath
+/// let classpath = ClassPath::from_sources(vec![Source::from_jar("app.jar").unwrap()]);
+/// let hierarchy = Hierarchy::new(&classpath);
+/// let bytes = std::fs::read("Task.class").unwrap();
+/// let mut class = transform::ClassFile::read(&mut &bytes[..]).unwrap();
+/// transform::AddInterface::new("java/lang/Runnable").apply_checked(&mut class, &hierarchy).unwrap();
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AddInterface {
+    interfaces: Vec<String>,
+}
+
+impl AddInterface {
+    /// Add `interface` (a JNI path such as `"java/lang/Runnable"`.)
+    pub fn new(interface: impl Into<String>) -> Self {
+        Self { interfaces: vec![interface.into()] }
+    }
+
+    /// Add `interface` too.
+    pub fn interface(&mut self, interface: impl Into<String>) -> &mut Self {
+        self.interfaces.push(interface.into());
+        self
+    }
+
+    /// The abstract methods `class` would leave unimplemented once the interfaces were added, as resolved by
+    /// [Hierarchy::methods](../struct.Hierarchy.html#method.methods).  Always empty for abstract classes and
+    /// interfaces, which needn't implement anything.  Interfaces missing from `hierarchy` are silently ignored.
+    pub fn unimplemented(&self, class: &ClassFile, hierarchy: &Hierarchy) -> io::Result<Vec<hierarchy::Member<Method>>> {
+        if class::Flags::from_bits_truncate(class.access_flags).intersects(class::Flags::ABSTRACT | class::Flags::INTERFACE) { return Ok(Vec::new()); }
+        let mut class = class.clone();
+        self.apply(&mut class)?;
+        let class = Class::read(&mut &class.to_bytes()?[..])?;
+        Ok(hierarchy.methods(&class)?.into_iter().filter(|m| m.member.is_abstract()).collect())
+    }
+
+    /// Add the interfaces to `class`, or fail without modifying it if that would leave abstract methods
+    /// [unimplemented](#method.unimplemented).
+    pub fn apply_checked(&self, class: &mut ClassFile, hierarchy: &Hierarchy) -> io::Result<()> {
+        let unimplemented = self.unimplemented(class, hierarchy)?;
+        if let Some(method) = unimplemented.first() {
+            return io_data_err!("{} would leave {} abstract method(s) unimplemented, such as {}.{}{}", class.name()?, unimplemented.len(), method.declaring.as_str(), method.member.name, method.member.descriptor_str());
+        }
+        self.apply(class)
+    }
+}
+
+impl Pass for AddInterface {
+    fn apply(&self, class: &mut ClassFile) -> io::Result<()> {
+        for interface in self.interfaces.iter() {
+            let mut present = false;
+            for &index in class.interfaces.iter() { present |= class.constants.get_class(index)? == interface; }
+            if present { continue; }
+            let index = class.constants.add_class(interface)?;
+            class.interfaces.push(index);
+        }
+        Ok(())
+    }
+}
+
+#[test] fn round_trip() {
+    use crate::testing::{be16, be32};
+
+    let mut c = testing::ClassFile::new("com/example/Main", Some("java/lang/Object"));
+    c.interface("java/lang/Runnable");
+    c.long(-1);
+    c.string("caf\u{e9} \u{1F600}");
+    c.constant(1, &[0, 4, b'a', 0xC0, 0x80, b'b']);             // "a\0b"
+    c.constant(1, &[0, 3, 0xED, 0xA0, 0xBD]);                   // lone high surrogate
+    c.constant(1, &[0, 2, 0xFF, 0xFE]);                         // invalid
+    c.methodref("java/lang/Object", "<init>", "()V");
+    let code = c.attribute("Code", &[be16(1), be16(1), be32(1), vec![0xB1], be16(0), be16(0)].concat());
+    c.method(0x0001, "run", "()V", &[code]);
+    let answer = c.integer(42);
+    let constant_value = c.attribute("ConstantValue", &be16(answer));
+    c.field(0x001A, "ANSWER", "I", &[constant_value]);
+    let source_file = c.utf8("Main.java");
+    c.class_attribute("SourceFile", &be16(source_file));
+    let bytes = c.to_bytes();
+
+    let class = ClassFile::read(&mut &bytes[..]).unwrap();
+    assert_eq!(class.name().unwrap(), "com/example/Main");
+    assert_eq!(class.super_name().unwrap(), Some("java/lang/Object"));
+    assert_eq!(class.methods[0].name(&class.constants).unwrap(), "run");
+    assert_eq!(class.fields[0].attribute("ConstantValue").unwrap().info.len(), 2);
+    assert_eq!(class.to_bytes().unwrap(), bytes);
+    assert_eq!(Pipeline::new().apply(&bytes[..]).unwrap(), bytes);
+
+    // New attribute names are added to the constant pool
+    let mut class = class;
+    class.attributes.push(RawAttribute { name: "Synthetic".to_owned(), info: Vec::new() });
+    let modified = class.to_bytes().unwrap();
+    assert!(ClassFile::read(&mut &modified[..]).unwrap().attribute("Synthetic").is_some());
+    assert!(Class::read(&mut &modified[..]).unwrap().is_synthetic());
+}
+
+#[test] fn pipeline() {
+    use std::sync::{Arc, Mutex};
+
+    let mut c = testing::ClassFile::new("com/example/Main", Some("java/lang/Object"));
+    c.field(0x0002, "debugCounter", "I", &[]);
+    c.field(0x0002, "count", "I", &[]);
+    c.method(0x0001, "debugDump", "()V", &[]);
+    let deprecated = c.attribute("Deprecated", &[]);
+    c.method(0x0001, "run", "()V", &[deprecated]);
+    let source_file = c.utf8("Main.java");
+    c.class_attribute("SourceFile", &testing::be16(source_file));
+    let main = c.to_bytes();
+    let mut c = testing::ClassFile::new("com/example/Caller", Some("java/lang/Object"));
+    c.methodref("com/example/Main", "run", "()V");
+    c.methodref("com/example/Other", "run", "()V");
+    let caller = c.to_bytes();
+
+    let order = Arc::new(Mutex::new(Vec::new()));
+    let log = |step: &'static str| { let order = order.clone(); move |_: &mut ClassFile| { order.lock().unwrap().push(step); Ok(()) } };
+    let mut pipeline = Pipeline::new();
+    pipeline.add(log("second"));
+    pipeline.add(log("third")).order(1);
+    pipeline.add(log("first")).order(-1);
+    pipeline.add(log("main only")).only(|class| class == "com/example/Main");
+    pipeline.add(remove_members(|name, _| name.starts_with("debug")));
+    pipeline.add(strip_attributes(&["SourceFile", "Deprecated"]));
+    pipeline.add(rename_member("com/example/Main", "run", "()V", "execute"));
+
+    let class = Class::read(&mut &pipeline.apply(&main[..]).unwrap()[..]).unwrap();
+    assert_eq!(*order.lock().unwrap(), &["first", "second", "main only", "third"]);
+    assert_eq!(class.fields.iter().map(|f| f.name.as_str()).collect::<Vec<_>>(), &["count"]);
+    assert_eq!(class.methods.iter().map(|m| m.name.as_str()).collect::<Vec<_>>(), &["execute"]);
+    assert!(!class.methods[0].deprecated);
+    assert_eq!(class.source_file(), None);
+
+    order.lock().unwrap().clear();
+    let caller = ClassFile::read(&mut &pipeline.apply(&caller[..]).unwrap()[..]).unwrap();
+    assert_eq!(*order.lock().unwrap(), &["first", "second", "third"]);
+    let refs = caller.constants.0.iter().filter_map(|c| match c {
+        Constant::Methodref { class_index, name_and_type_index } => match caller.constants.get(*name_and_type_index).unwrap() {
+            Constant::NameAndType { name_index, .. } => Some((caller.constants.get_class(*class_index).unwrap(), caller.constants.get_utf8(*name_index).unwrap())),
+            _ => None,
+        },
+        _ => None,
+    }).collect::<Vec<_>>();
+    assert_eq!(refs, &[("com/example/Main", "execute"), ("com/example/Other", "run")]);
+
+    // Jars
+    let input = testing::write_jar("transform-input", &[
+        ("META-INF/MANIFEST.MF",        b"Manifest-Version: 1.0\r\n".to_vec()),
+        ("META-INF/CERT.SF",            Vec::new()),
+        ("com/example/Main.class",      main),
+        ("com/example/Other.class",     testing::ClassFile::new("com/example/Other", Some("java/lang/Object")).to_bytes()),
+        ("com/example/strings.txt",     b"hello".to_vec()),
+    ]);
+    let output = input.with_file_name("transform-output.jar");
+    let mut pipeline = Pipeline::new();
+    pipeline.add(remove_members(|name, _| name.starts_with("debug"))).only(|class| class == "com/example/Main");
+    let stats = pipeline.apply_jar(&input, &output).unwrap();
+    assert_eq!(stats, TransformStats { classes_transformed: 1, classes_copied: 1, resources_copied: 2, signatures_removed: 1, entries_renamed: 0 });
+    let src = Source::from_jar(&output).unwrap();
+    assert_eq!(src.read_class("com/example/Main").unwrap().methods.len(), 1);
+    assert!(src.read_class("com/example/Other").is_ok());
+}
+
+#[test] fn strip() {
+    use crate::testing::{be16, be32};
+
+    let mut c = testing::ClassFile::new("com/example/Main", Some("java/lang/Object"));
+    let line_numbers = c.attribute("LineNumberTable", &[be16(1), be16(0), be16(7)].concat());
+    let this = c.utf8("this");
+    let descriptor = c.utf8("Lcom/example/Main;");
+    let local_variables = c.attribute("LocalVariableTable", &[be16(1), be16(0), be16(1), be16(this), be16(descriptor), be16(0)].concat());
+    let stack_map = c.attribute("StackMapTable", &be16(0));
+    let handler = [be16(0), be16(1), be16(1), be16(0)].concat();
+    let code = c.attribute("Code", &[be16(1), be16(1), be32(1), vec![0xB1], be16(1), handler, be16(3), line_numbers, stack_map, local_variables].concat());
+    let nullable = c.utf8("Ljavax/annotation/Nullable;");
+    let invisible = c.attribute("RuntimeInvisibleAnnotations", &[be16(1), be16(nullable), be16(0)].concat());
+    let deprecated = c.utf8("Ljava/lang/Deprecated;");
+    let visible = c.attribute("RuntimeVisibleAnnotations", &[be16(1), be16(deprecated), be16(0)].concat());
+    c.method(0x0001, "run", "()V", &[code, invisible, visible]);
+    let source_file = c.utf8("Main.java");
+    c.class_attribute("SourceFile", &be16(source_file));
+    let bytes = c.to_bytes();
+
+    let strip = |strip: Strip| {
+        let mut pipeline = Pipeline::new();
+        pipeline.add(strip);
+        let mut options = ParseOptions::new();
+        options.retain_code(true);
+        Class::read_with(&mut &pipeline.apply(&bytes[..]).unwrap()[..], &options).unwrap()
+    };
+
+    let class = strip(Strip::new());
+    assert_eq!(class.source_file(), Some("Main.java"));
+    assert_eq!(class.methods[0].code().unwrap().attributes.len(), 3);
+
+    let class = strip(Strip::debug_info());
+    assert_eq!(class.source_file(), None);
+    let code = class.methods[0].code().unwrap();
+    assert_eq!(code.attributes.iter().map(|a| a.name.as_str()).collect::<Vec<_>>(), &["StackMapTable"]);
+    assert_eq!((code.bytecode.as_slice(), code.exception_table.len()), (&[0xB1][..], 1));
+    assert_eq!(class.methods[0].invisible_annotations().len(), 1);
+
+    let mut options = Strip::new();
+    options.invisible_annotations(true);
+    let class = strip(options);
+    assert_eq!(class.source_file(), Some("Main.java"));
+    assert!(class.methods[0].invisible_annotations().is_empty());
+    assert_eq!(class.methods[0].visible_annotations().len(), 1);
+    assert_eq!(class.methods[0].code().unwrap().attributes.len(), 3);
+}
+
+#[test] fn add_annotation() {
+    use crate::annotation::{Annotation, ElementValue};
+    use crate::testing::be16;
+
+    let mut c = testing::ClassFile::new("com/example/Main", Some("java/lang/Object"));
+    c.field(0x0002, "name", "Ljava/lang/String;", &[]);
+    c.field(0x0002, "count", "I", &[]);
+    let deprecated = c.utf8("Ljava/lang/Deprecated;");
+    let deprecated = c.attribute("RuntimeVisibleAnnotations", &[be16(1), be16(deprecated), be16(0)].concat());
+    c.method(0x0001, "getName", "()Ljava/lang/String;", &[deprecated]);
+    c.method(0x0001, "run", "()V", &[]);
+    let bytes = c.to_bytes();
+
+    let everything = Annotation::new("Lcom/example/Everything;".to_owned(), vec![
+        ("b",       ElementValue::Byte(1)),
+        ("c",       ElementValue::Char('x' as u16)),
+        ("d",       ElementValue::Double(1.5)),
+        ("f",       ElementValue::Float(2.5)),
+        ("i",       ElementValue::Int(3)),
+        ("j",       ElementValue::Long(4)),
+        ("s",       ElementValue::Short(-5)),
+        ("z",       ElementValue::Boolean(true)),
+        ("str",     ElementValue::String("hi".to_owned())),
+        ("cls",     ElementValue::Class("V".to_owned())),
+        ("e",       ElementValue::Enum { descriptor: "Ljava/lang/annotation/ElementType;".to_owned(), name: "METHOD".to_owned() }),
+        ("nested",  ElementValue::Annotation(Annotation::new("Lcom/example/Nested;".to_owned(), Vec::new()))),
+        ("array",   ElementValue::Array(vec![ElementValue::Int(3), ElementValue::Int(4)])),
+    ].into_iter().map(|(name, value)| (name.to_owned(), value)).collect());
+    let nullable = Annotation::new("Ljavax/annotation/Nullable;".to_owned(), Vec::new());
+    let deprecated = Annotation::new("Ljava/lang/Deprecated;".to_owned(), Vec::new());
+
+    let mut pipeline = Pipeline::new();
+    pipeline.add(AddAnnotation::new(everything.clone()));
+    let mut add = AddAnnotation::new(nullable.clone());
+    add.visible(false).class(false).fields(|_, descriptor| descriptor.starts_with('L')).methods(|_, descriptor| descriptor.ends_with(';'));
+    pipeline.add(add);
+    let mut add = AddAnnotation::new(deprecated.clone());
+    add.methods(|_, _| true);
+    pipeline.add(add);
+
+    let transformed = pipeline.apply(&bytes[..]).unwrap();
+    let class = Class::read(&mut &transformed[..]).unwrap();
+    assert_eq!(class.visible_annotations(), &[everything, deprecated.clone()]);
+    assert!(class.invisible_annotations().is_empty());
+    assert_eq!(class.fields[0].invisible_annotations(), &[nullable.clone()]);
+    assert!(class.fields[1].invisible_annotations().is_empty());
+    assert_eq!(class.methods[0].visible_annotations(), &[deprecated.clone()]);  // Not duplicated
+    assert_eq!(class.methods[0].invisible_annotations(), &[nullable]);
+    assert_eq!(class.methods[1].visible_annotations(), &[deprecated]);
+
+    // Applying the same annotations again changes nothing
+    assert_eq!(pipeline.apply(&transformed[..]).unwrap(), transformed);
+}
+
+#[test] fn add_interface() {
+    let interface = |name: &str, methods: &[&str]| {
+        let mut c = testing::ClassFile::new(name, Some("java/lang/Object"));
+        c.flags = 0x0601;
+        for method in methods.iter() { c.method(0x0401, method, "()V", &[]); }
+        c.read()
+    };
+    let hierarchy = Hierarchy::default();
+    hierarchy.add(interface("java/lang/Runnable", &["run"]));
+    hierarchy.add(interface("java/io/Closeable", &["close"]));
+
+    let mut c = testing::ClassFile::new("com/example/Task", Some("java/lang/Object"));
+    c.interface("java/lang/Runnable");
+    c.method(0x0001, "run", "()V", &[]);
+    let class = ClassFile::read(&mut &c.to_bytes()[..]).unwrap();
+
+    let mut add = AddInterface::new("java/lang/Runnable");
+    add.interface("java/io/Serializable").interface("java/io/Closeable");
+    let unimplemented = add.unimplemented(&class, &hierarchy).unwrap();
+    assert_eq!(unimplemented.iter().map(|m| (m.declaring.as_str(), m.member.name.as_str())).collect::<Vec<_>>(), &[("java/io/Closeable", "close")]);
+    let mut checked = class.clone();
+    assert!(add.apply_checked(&mut checked, &hierarchy).is_err());
+    assert_eq!(checked.to_bytes().unwrap(), class.to_bytes().unwrap());
+
+    let mut abstract_class = class.clone();
+    abstract_class.access_flags |= 0x0400; // ABSTRACT
+    assert!(add.apply_checked(&mut abstract_class, &hierarchy).is_ok());
+
+    let mut add = AddInterface::new("java/lang/Runnable");
+    add.interface("java/io/Serializable");
+    let mut checked = class.clone();
+    add.apply_checked(&mut checked, &hierarchy).unwrap();
+    let transformed = Class::read(&mut &checked.to_bytes().unwrap()[..]).unwrap();
+    assert_eq!(transformed.interfaces.iter().map(|i| i.as_str()).collect::<Vec<_>>(), &["java/lang/Runnable", "java/io/Serializable"]);
+
+    // Applying the same interfaces again changes nothing
+    let mut pipeline = Pipeline::new();
+    pipeline.add(add);
+    let bytes = checked.to_bytes().unwrap();
+    assert_eq!(pipeline.apply(&bytes[..]).unwrap(), bytes);
+
+    // A default method of a superclass's interface implements an abstract method of a superinterface it extends
+    let mut i2 = testing::ClassFile::new("com/example/I2", Some("java/lang/Object"));
+    i2.flags = 0x0601;
+    i2.interface("java/lang/Runnable");
+    i2.method(0x0001, "run", "()V", &[]);
+    hierarchy.add(i2.read());
+    let mut s = testing::ClassFile::new("com/example/S", Some("java/lang/Object"));
+    s.interface("com/example/I2");
+    hierarchy.add(s.read());
+    let c = testing::ClassFile::new("com/example/C", Some("com/example/S"));
+    let c = ClassFile::read(&mut &c.to_bytes()[..]).unwrap();
+    assert!(AddInterface::new("java/lang/Runnable").unimplemented(&c, &hierarchy).unwrap().is_empty());
+}
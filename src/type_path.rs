@@ -0,0 +1,234 @@
+//! [Java SE 8 &sect; 4.7.20.2](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-4.html#jvms-4.7.20.2):  Navigating type uses by `type_path`.
+//!
+//! Type annotations (JSR 308) identify which part of a type they apply to via a `type_path`: a series of steps into
+//! array component types, nested (inner) types, wildcard bounds, and type arguments.  [TypeUse] exposes the same
+//! structure for [signature::Type]s, so annotations can be correlated with the exact position they apply to.
+//!
+//! [TypeUse]:          enum.TypeUse.html
+//! [signature::Type]:  ../signature/enum.Type.html
+
+use crate::io::be::*;
+use crate::signature::*;
+
+use std::fmt::{self, Display, Formatter};
+use std::io::{self, Read};
+
+
+
+/// A single `type_path` step.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum TypePathStep {
+    /// `type_path_kind` 0:  Deeper in an array type (the component type.)
+    Array,
+    /// `type_path_kind` 1:  Deeper in a nested type (the next inner class.)
+    Nested,
+    /// `type_path_kind` 2:  On the bound of a wildcard type argument.
+    WildcardBound,
+    /// `type_path_kind` 3:  On the Nth type argument of a parameterized type.
+    TypeArgument(u8),
+    #[doc(hidden)] _NonExhaustive,
+}
+
+/// A `type_path`:  The steps from the root of a type to a specific position within it.  An empty path is the root.
+///
+/// Formats/parses in the same notation as ASM's `TypePath`:  `[` for arrays, `.` for nested types, `*` for wildcard
+/// bounds, and `N;` for type arguments.
+///
+/// # Example
+///
+/// ```
+/// use jreflection::type_path::*;
+/// let path = TypePath::parse("0;*").unwrap();
+/// assert_eq!(path.0, vec![TypePathStep::TypeArgument(0), TypePathStep::WildcardBound]);
+/// assert_eq!(path.to_string(), "0;*");
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TypePath(pub Vec<TypePathStep>);
+
+/// A specific position within a [signature::Type](../signature/enum.Type.html), as identified by a [TypePath].
+///
+/// # Example
+///
+/// ```
+/// use jreflection::signature::Type;
+/// use jreflection::type_path::*;
+/// let ty = Type::parse("Ljava/util/Map<Ljava/lang/String;[Ljava/util/List<+Ljava/lang/Number;>;>;").unwrap();
+/// let root = TypeUse::new(&ty);
+/// assert_eq!(root.at(&TypePath::parse("0;").unwrap()).unwrap().to_string(), "Ljava/lang/String;");
+/// assert_eq!(root.at(&TypePath::parse("1;[0;*").unwrap()).unwrap().to_string(), "Ljava/lang/Number;");
+/// assert!(root.at(&TypePath::parse("2;").unwrap()).is_none());
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TypeUse<'a> {
+    /// A primitive, type variable, or array type.
+    Type(&'a Type),
+    /// A class type, positioned on one of its (outermost first) nested segments.
+    Class { class: &'a ClassType, segment: usize },
+    /// A wildcard type argument, such as `?` or `? extends Number`.
+    Wildcard(&'a TypeArgument),
+}
+
+impl TypePath {
+    /// The root of a type.
+    pub fn root() -> Self { Self::default() }
+
+    /// Parse a type path in ASM's `TypePath` notation, such as `"[0;*"`.
+    pub fn parse(path: &str) -> io::Result<Self> {
+        let mut steps = Vec::new();
+        let mut rest = path;
+        while let Some(ch) = rest.chars().next() {
+            rest = &rest[ch.len_utf8()..];
+            match ch {
+                '[' => steps.push(TypePathStep::Array),
+                '.' => steps.push(TypePathStep::Nested),
+                '*' => steps.push(TypePathStep::WildcardBound),
+                '0' ..= '9' => {
+                    let semi = match rest.find(';') { Some(i) => i, None => return io_data_err!("Expected ';' after type argument index parsing type path {:?}", path) };
+                    let index = format!("{}{}", ch, &rest[..semi]);
+                    let index = match index.parse() { Ok(i) => i, Err(_) => return io_data_err!("Invalid type argument index {:?} parsing type path {:?}", index, path) };
+                    steps.push(TypePathStep::TypeArgument(index));
+                    rest = &rest[semi+1..];
+                },
+                other => return io_data_err!("Unexpected {:?} parsing type path {:?}", other, path),
+            }
+        }
+        Ok(TypePath(steps))
+    }
+
+    /// Read a binary `type_path` structure, as found in type annotations.
+    pub(crate) fn read(read: &mut dyn Read) -> io::Result<Self> {
+        let mut read = read;
+        let path_length = read_u1(&mut read)? as usize;
+        let mut steps = Vec::with_capacity(path_length);
+        for _ in 0..path_length {
+            let kind    = read_u1(&mut read)?;
+            let index   = read_u1(&mut read)?;
+            steps.push(match kind {
+                0 => TypePathStep::Array,
+                1 => TypePathStep::Nested,
+                2 => TypePathStep::WildcardBound,
+                3 => TypePathStep::TypeArgument(index),
+                _ => return io_data_err!("Unknown type_path_kind {}", kind),
+            });
+        }
+        Ok(TypePath(steps))
+    }
+
+    /// The number of steps in this path.
+    pub fn len(&self) -> usize { self.0.len() }
+
+    /// Is this path the root of a type?
+    pub fn is_empty(&self) -> bool { self.0.is_empty() }
+}
+
+impl Display for TypePath {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        for step in self.0.iter() {
+            match step {
+                TypePathStep::Array             => f.write_str("[")?,
+                TypePathStep::Nested            => f.write_str(".")?,
+                TypePathStep::WildcardBound     => f.write_str("*")?,
+                TypePathStep::TypeArgument(i)   => write!(f, "{};", i)?,
+                TypePathStep::_NonExhaustive    => f.write_str("?")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'a> TypeUse<'a> {
+    /// The root position of `ty`.  For nested class types, this is the outermost class.
+    pub fn new(ty: &'a Type) -> Self {
+        match ty {
+            Type::Class(class)  => TypeUse::Class { class, segment: 0 },
+            other               => TypeUse::Type(other),
+        }
+    }
+
+    /// Take a single step into this type, if valid for this position.
+    pub fn step(&self, step: TypePathStep) -> Option<TypeUse<'a>> {
+        match (*self, step) {
+            (TypeUse::Type(Type::Array(component)), TypePathStep::Array) => Some(TypeUse::new(component)),
+            (TypeUse::Class { class, segment }, TypePathStep::Nested) if segment + 1 < class.segments.len() => Some(TypeUse::Class { class, segment: segment + 1 }),
+            (TypeUse::Class { class, segment }, TypePathStep::TypeArgument(index)) => {
+                match class.segments[segment].type_arguments.get(index as usize)? {
+                    TypeArgument::Exact(ty) => Some(TypeUse::new(ty)),
+                    wildcard                => Some(TypeUse::Wildcard(wildcard)),
+                }
+            },
+            (TypeUse::Wildcard(TypeArgument::Extends(bound)), TypePathStep::WildcardBound) => Some(TypeUse::new(bound)),
+            (TypeUse::Wildcard(TypeArgument::Super(bound)),   TypePathStep::WildcardBound) => Some(TypeUse::new(bound)),
+            _ => None,
+        }
+    }
+
+    /// Follow a path from this position, or `None` if the path doesn't match the structure of the type.
+    pub fn at(&self, path: &TypePath) -> Option<TypeUse<'a>> {
+        let mut position = *self;
+        for step in path.0.iter() {
+            position = position.step(*step)?;
+        }
+        Some(position)
+    }
+
+    /// The positions reachable in a single step from this position.
+    pub fn children(&self) -> Vec<(TypePathStep, TypeUse<'a>)> {
+        let mut steps = vec![TypePathStep::Array, TypePathStep::Nested, TypePathStep::WildcardBound];
+        if let TypeUse::Class { class, segment } = self {
+            let n = class.segments[*segment].type_arguments.len();
+            steps.extend((0..n.min(256)).map(|i| TypePathStep::TypeArgument(i as u8)));
+        }
+        steps.into_iter().filter_map(|step| self.step(step).map(|child| (step, child))).collect()
+    }
+
+    /// Every position within this type (including this position itself, with an empty path), depth first.
+    pub fn walk(&self) -> Vec<(TypePath, TypeUse<'a>)> {
+        let mut positions = Vec::new();
+        self.walk_into(&mut TypePath::root(), &mut positions);
+        positions
+    }
+
+    fn walk_into(&self, path: &mut TypePath, positions: &mut Vec<(TypePath, TypeUse<'a>)>) {
+        positions.push((path.clone(), *self));
+        for (step, child) in self.children() {
+            path.0.push(step);
+            child.walk_into(path, positions);
+            path.0.pop();
+        }
+    }
+}
+
+impl<'a> Display for TypeUse<'a> {
+    /// Formats the type at this position as a signature.  For class types, nested types outside of this position are omitted.
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            TypeUse::Type(ty)                   => write!(f, "{}", ty),
+            TypeUse::Wildcard(arg)              => write!(f, "{}", arg),
+            TypeUse::Class { class, segment }   => write!(f, "{}", ClassType { package: class.package.clone(), segments: class.segments[..=*segment].to_vec() }),
+        }
+    }
+}
+
+#[test] fn type_use_walk() {
+    let ty = Type::parse("[Lcom/example/Outer<TT;>.Inner<*+[I>;").unwrap();
+    let paths = TypeUse::new(&ty).walk().iter().map(|(path, tu)| format!("{} = {}", path, tu)).collect::<Vec<_>>();
+    assert_eq!(paths, vec![
+        " = [Lcom/example/Outer<TT;>.Inner<*+[I>;",
+        "[ = Lcom/example/Outer<TT;>;",
+        "[. = Lcom/example/Outer<TT;>.Inner<*+[I>;",
+        "[.0; = *",
+        "[.1; = +[I",
+        "[.1;* = [I",
+        "[.1;*[ = I",
+        "[0; = TT;",
+    ]);
+
+    for (path, tu) in TypeUse::new(&ty).walk() {
+        assert_eq!(TypeUse::new(&ty).at(&path), Some(tu));
+        assert_eq!(TypePath::parse(&path.to_string()).unwrap(), path);
+    }
+
+    assert!(TypePath::parse("0").is_err());
+    assert!(TypePath::parse("x").is_err());
+    assert!(TypePath::parse("256;").is_err());
+}
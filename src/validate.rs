@@ -0,0 +1,190 @@
+//! Structural checks of a class against the rules of its own classfile version.
+//!
+//! The JVM accepts different things depending on `major_version`:  `invokedynamic` needs 51+, `jsr`/`ret` are
+//! forbidden from 51 on, non-abstract interface methods need 52+, and so on.  Classes produced by bytecode generators
+//! or transforms can easily break these rules without failing to parse, only failing later when loaded (or worse, when
+//! verified on some other JVM.)  [check] finds such violations up front.
+//!
+//! Checks involving bytecode only run for methods whose [Code](../code/struct.Code.html) was retained - see
+//! [ParseOptions::retain_code](../struct.ParseOptions.html#method.retain_code).
+//!
+//! [check]:    fn.check.html
+
+use crate::Class;
+use crate::constants::Constant;
+#[cfg(all(test, feature = "code"))] use crate::{testing, ParseOptions};
+
+use std::fmt::{self, Display, Formatter};
+
+
+
+/// A rule of the class's classfile version that the class breaks.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Violation {
+    /// The method the violation is in, as its name and descriptor (such as `"run()V"`), or `None` for the class itself.
+    pub member:     Option<String>,
+
+    /// The first `major_version` the rule applies to (for features that were forbidden), or that allows the feature
+    /// (for features that were added.)
+    pub version:    u16,
+
+    /// What's wrong, such as `"invokedynamic requires major_version 51+"`.
+    pub message:    String,
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self.member.as_ref() {
+            Some(member)    => write!(f, "{}: {}", member, self.message),
+            None            => f.write_str(&self.message),
+        }
+    }
+}
+
+/// Check `class` against the structural rules of its classfile version, returning every violation found.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use jreflection::*;
+/// # #[cfg(all(feature = "jar", feature = "code"))] {
+/// let mut options = ParseOptions::new();
+/// options.retain_code(true);
+/// let src = Source::from_jar("generated.jar").unwrap();
+/// let class = src.read_class_with("com/example/Generated", &options).unwrap();
+/// for violation in validate::check(&class) {
+///     eprintln!("{}: {}", class.path.as_str(), violation);
+/// }
+/// # }
+/// ```
+pub fn check(class: &Class) -> Vec<Violation> {
+    let version = class.major_version();
+    let mut violations = Vec::new();
+    let mut violation = |member: Option<String>, since: u16, message: String| violations.push(Violation { member, version: since, message });
+
+    // Constant pool
+    let mut seen = [false; 3];
+    for constant in class.constants.0.iter() {
+        let (slot, since, kind) = match constant {
+            Constant::MethodHandle { .. } | Constant::MethodType { .. } | Constant::InvokeDynamic { .. }    => (0, 51, "CONSTANT_MethodHandle, CONSTANT_MethodType, and CONSTANT_InvokeDynamic"),
+            Constant::Module { .. } | Constant::Package { .. }                                              => (1, 53, "CONSTANT_Module and CONSTANT_Package"),
+            Constant::Dynamic { .. }                                                                        => (2, 55, "CONSTANT_Dynamic"),
+            _                                                                                               => continue,
+        };
+        if version < since && !seen[slot] {
+            seen[slot] = true;
+            violation(None, since, format!("{} constants require major_version {}+", kind, since));
+        }
+    }
+
+    // Class attributes
+    for name in class.attribute_names() {
+        let since = match name {
+            "StackMapTable"                                                     => 50,
+            "BootstrapMethods"                                                  => 51,
+            "MethodParameters"
+            | "RuntimeVisibleTypeAnnotations" | "RuntimeInvisibleTypeAnnotations" => 52,
+            "Module" | "ModulePackages" | "ModuleMainClass"                     => 53,
+            "NestHost" | "NestMembers"                                          => 55,
+            "Record"                                                            => 60,
+            "PermittedSubclasses"                                               => 61,
+            _                                                                   => continue,
+        };
+        if version < since { violation(None, since, format!("{} attribute requires major_version {}+ (it would be ignored)", name, since)); }
+    }
+    if class.is_module() && version < 53 { violation(None, 53, "ACC_MODULE requires major_version 53+".to_owned()); }
+
+    // Methods
+    for method in class.methods.iter() {
+        let member = || Some(format!("{}{}", method.name, method.descriptor_str()));
+        if class.is_interface() && version < 52 && !method.is_static_init() && !(method.is_abstract() && method.is_public()) {
+            violation(member(), 52, "interface methods other than <clinit> must be public abstract before major_version 52".to_owned());
+        }
+
+        // Code, if retained
+        #[cfg(feature = "code")] {
+            let code = match method.code() { Some(code) => code, None => continue };
+            let mut branches = !code.exception_table.is_empty();
+            let (mut invokedynamic, mut subroutines) = (false, false);
+            for instruction in code.instructions() {
+                let opcode = match instruction { Ok(instruction) => instruction.opcode, Err(_) => break }; // Malformed bytecode is the verifier's problem
+                match opcode {
+                    0xA8 | 0xA9 | 0xC9          => { subroutines = true; branches = true; }, // jsr, ret, jsr_w
+                    0x99 ..= 0xA7 | 0xAA | 0xAB | 0xC6 ..= 0xC8 => { branches = true; }, // if*, goto, *switch, ifnull, ifnonnull, goto_w
+                    0xBA                        => { invokedynamic = true; },
+                    _                           => {},
+                }
+            }
+            if invokedynamic && version < 51 { violation(member(), 51, "invokedynamic requires major_version 51+".to_owned()); }
+            if subroutines && version >= 51 { violation(member(), 51, "jsr/ret are forbidden from major_version 51".to_owned()); }
+            if branches && version >= 50 && code.attribute("StackMapTable").is_none() && !(subroutines && version == 50) {
+                violation(member(), 50, "code with branches or exception handlers requires a StackMapTable from major_version 50".to_owned());
+            }
+        }
+    }
+
+    violations
+}
+
+#[cfg(feature = "code")]
+#[test] fn versions() {
+    use crate::testing::{be16, be32};
+
+    let code = |c: &mut testing::ClassFile, bytecode: &[u8], stack_map: bool| {
+        let stack_map = if stack_map { c.attribute("StackMapTable", &be16(0)) } else { Vec::new() };
+        let info = [be16(2), be16(2), be32(bytecode.len() as u32), bytecode.to_vec(), be16(0), be16(if stack_map.is_empty() { 0 } else { 1 }), stack_map].concat();
+        c.attribute("Code", &info)
+    };
+    let check_bytes = |bytes: Vec<u8>| {
+        let mut options = ParseOptions::new();
+        options.retain_code(true);
+        check(&Class::read_with(&mut &bytes[..], &options).unwrap())
+    };
+
+    // Java 6 class with a loop, missing its StackMapTable, and an invokedynamic
+    let mut c = testing::ClassFile::new("com/example/Old", Some("java/lang/Object"));
+    let looping = code(&mut c, &[0xA7, 0x00, 0x00], false); // goto +0
+    c.method(0x0001, "spin", "()V", &[looping]);
+    let indy = code(&mut c, &[0xBA, 0x00, 0x01, 0x00, 0x00, 0xB1], false);
+    c.method(0x0001, "indy", "()V", &[indy]);
+    let straight = code(&mut c, &[0xB1], false); // return
+    c.method(0x0001, "simple", "()V", &[straight]);
+    c.major_version = 50;
+    let violations = check_bytes(c.to_bytes());
+    assert_eq!(violations.iter().map(|v| v.to_string()).collect::<Vec<_>>(), &[
+        "spin()V: code with branches or exception handlers requires a StackMapTable from major_version 50",
+        "indy()V: invokedynamic requires major_version 51+",
+    ]);
+
+    // Java 7 class using jsr, Java 7 interface with a default method, and type annotations
+    let mut c = testing::ClassFile::new("com/example/Api", Some("java/lang/Object"));
+    c.flags = 0x0601; // PUBLIC | INTERFACE | ABSTRACT
+    let jsr = code(&mut c, &[0xA8, 0x00, 0x03, 0xB1], true);
+    c.method(0x0009, "legacy", "()V", &[jsr]);
+    let body = code(&mut c, &[0xB1], false);
+    c.method(0x0001, "fallback", "()V", &[body]);
+    c.method(0x0401, "run", "()V", &[]);
+    c.class_attribute("RuntimeVisibleTypeAnnotations", &be16(0));
+    c.major_version = 51;
+    let violations = check_bytes(c.to_bytes());
+    assert_eq!(violations.iter().map(|v| (v.member.as_ref().map(|m| m.as_str()), v.version)).collect::<Vec<_>>(), &[
+        (None,                  52),
+        (Some("legacy()V"),     52),
+        (Some("legacy()V"),     51),
+        (Some("fallback()V"),   52),
+    ]);
+
+    // The same interface is fine as Java 8 (apart from jsr)
+    c.major_version = 52;
+    let violations = check_bytes(c.to_bytes());
+    assert_eq!(violations.len(), 1);
+    assert_eq!(violations[0].message, "jsr/ret are forbidden from major_version 51");
+
+    // Class::validate
+    let mut c = testing::ClassFile::new("com/example/Fine", Some("java/lang/Object"));
+    c.method(0x0001, "run", "()V", &[]);
+    assert!(c.read().validate().is_ok());
+    c.major_version = 49;
+    c.class_attribute("BootstrapMethods", &be16(0));
+    assert!(c.read().validate().is_err());
+}
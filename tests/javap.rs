@@ -0,0 +1,207 @@
+//! Cross-check jreflection against `javap`, the JDK's own class file disassembler, when a JDK is available.
+//!
+//! A sample of classes from each source is written out as `.class` files and disassembled with `javap -p -s`.  The
+//! names, flags, and member lists javap reports (names, descriptors, and modifiers) must match what jreflection parsed.
+//!
+//! The JDK is found via `%JAVA_HOME%`, or else `javap` on `%PATH%`.  If neither is found, the tests do nothing.
+
+use jreflection::{Class, ExportLayout, Source};
+use std::collections::BTreeMap;
+use std::path::*;
+use std::process::Command;
+
+const SAMPLES : usize = 200;
+const BATCH   : usize = 50;
+
+const MODIFIERS : &[&str] = &["public", "protected", "private", "static", "final", "synchronized", "volatile", "transient", "native", "abstract", "strictfp"];
+
+
+
+#[test] fn jdk_classes() {
+    let jdk = match find_jdk() { Some(jdk) => jdk, None => return };
+    match Source::from_jdk_dir(&jdk) {
+        Ok(src) => cross_check(&jdk, &src),
+        Err(err) => println!("skipping {}: {}", jdk.display(), err),
+    }
+}
+
+#[test] fn jdk_jars() {
+    let jdk = match find_jdk() { Some(jdk) => jdk, None => return };
+    for jar in ["jrt-fs.jar", "tools.jar", "dt.jar"].iter().map(|name| jdk.join("lib").join(name)).filter(|jar| jar.exists()) {
+        cross_check(&jdk, &Source::from_jar(&jar).unwrap_or_else(|err| panic!("error opening {}: {}", jar.display(), err)));
+    }
+}
+
+#[test] fn jdk_skip_report() {
+    let jdk = match find_jdk() { Some(jdk) => jdk, None => return };
+    let mut src = match Source::from_jdk_dir(&jdk) { Ok(src) => src, Err(err) => return println!("skipping {}: {}", jdk.display(), err) };
+    src.set_skip_reporting(true);
+    let classes = src.classes::<Vec<String>>().unwrap();
+    assert!(classes.iter().any(|c| c == "java/lang/Object"));
+    assert!(!classes.iter().any(|c| c.ends_with("/module-info") || c == "module-info"));
+
+    let report = src.skip_report();
+    if jdk.join("lib").join("modules").exists() {
+        assert!(report.module_infos.contains("java.base"), "module_infos: {:?}", report.module_infos);
+        assert!(report.resources.iter().all(|r| !r.ends_with(".class")));
+        assert!(report.resources.iter().any(|r| r.starts_with("java.base/")), "resources: {:?}", report.resources.iter().take(10).collect::<Vec<_>>());
+    }
+}
+
+#[test] fn jdk_resources() {
+    let jdk = match find_jdk() { Some(jdk) => jdk, None => return };
+    let src = match Source::from_jdk_dir(&jdk) { Ok(src) => src, Err(err) => return println!("skipping {}: {}", jdk.display(), err) };
+    let img = match src.as_jimage() { Some(img) => img, None => return };
+
+    let resources = img.resources().unwrap();
+    assert!(resources.iter().any(|(module, path)| module == "java.base" && path == "java/lang/Object.class"));
+    assert!(!resources.iter().any(|(_, path)| path.ends_with("module-info.class")));
+    for (module, path) in resources.iter().filter(|(_, path)| path.ends_with(".class")).step_by(101) {
+        assert_eq!(&img.module_of(&path[..path.len()-6]).unwrap(), module, "{}", path);
+    }
+    assert_eq!(img.modules().unwrap(), resources.iter().map(|(module, _)| module.clone()).collect());
+
+    let jar = Source::from_jar(jdk.join("lib").join("jrt-fs.jar")).unwrap();
+    assert_eq!(jar.export_modules(std::env::temp_dir().join("jreflection-jdk-export"), ExportLayout::Jars).unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+}
+
+#[test] fn jdk_overlay_jrt() {
+    let jdk = match find_jdk() { Some(jdk) => jdk, None => return };
+    let open = || Source::from_jdk_dir(&jdk).unwrap();
+    if open().as_jimage().is_none() { return; }
+    let patch_jar = jdk.join("lib").join("jrt-fs.jar"); // jdk/internal/jrtfs classes, which lib/modules places in java.base
+    let patch = || Source::from_jar(&patch_jar).unwrap();
+    let class = "jdk/internal/jrtfs/JrtFileSystem";
+    let patched = patch().read_class_bytes(class).unwrap();
+
+    let src = Source::overlay(open(), patch());
+    assert_eq!(src.read_jrt(&format!("jrt:/java.base/{}.class", class)).unwrap(), patched);
+    assert_eq!(src.read_jrt(&format!("jrt:/java.sql/{}.class", class)).unwrap_err().kind(), std::io::ErrorKind::NotFound);
+    assert_eq!(src.read_jrt("jrt:/java.base/java/lang/Object.class").unwrap(), open().read_class_bytes("java/lang/Object").unwrap());
+
+    let nested = Source::overlay(src, Source::from_jar(&patch_jar).unwrap());
+    assert_eq!(nested.read_jrt(&format!("jrt:/java.base/{}.class", class)).unwrap(), patched);
+    let jars = Source::overlay(patch(), patch());
+    assert_eq!(jars.read_jrt(&format!("jrt:/java.base/{}.class", class)).unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+}
+
+fn find_jdk() -> Option<PathBuf> {
+    let exe = if cfg!(windows) { "javap.exe" } else { "javap" };
+    let javap = match std::env::var_os("JAVA_HOME") {
+        Some(home)  => PathBuf::from(home).join("bin").join(exe),
+        None        => std::env::split_paths(&std::env::var_os("PATH")?).map(|dir| dir.join(exe)).find(|javap| javap.exists())?,
+    };
+    let jdk = javap.canonicalize().ok()?.parent()?.parent()?.to_owned(); // .../bin/javap -> ...
+    if jdk.join("bin").join(exe).exists() { Some(jdk) } else { None }
+}
+
+/// Disassemble an evenly spaced sample of the classes of `src` with `jdk`'s javap, and compare against `src.read_class`.
+fn cross_check(jdk: &Path, src: &Source) {
+    let mut paths = src.classes::<Vec<String>>().unwrap();
+    paths.retain(|path| !path.ends_with("module-info") && !path.ends_with("package-info"));
+    paths.sort();
+    let step = (paths.len() / SAMPLES).max(1);
+    let paths = paths.into_iter().step_by(step).take(SAMPLES).collect::<Vec<_>>();
+
+    let dir = std::env::temp_dir().join(format!("jreflection-javap-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let mut mismatches = Vec::new();
+    for batch in paths.chunks(BATCH) {
+        let mut javap = Command::new(jdk.join("bin").join("javap"));
+        javap.arg("-p").arg("-s");
+        for (i, path) in batch.iter().enumerate() {
+            let file = dir.join(format!("{}.class", i));
+            std::fs::write(&file, src.read_class_bytes(path).unwrap()).unwrap();
+            javap.arg(file);
+        }
+        let output = javap.output().unwrap_or_else(|err| panic!("error running javap: {}", err));
+        assert!(output.status.success(), "javap failed: {}", String::from_utf8_lossy(&output.stderr));
+        let disassembled = parse_javap(&String::from_utf8_lossy(&output.stdout));
+
+        for path in batch.iter() {
+            let expected = match disassembled.get(path) { Some(lines) => lines, None => { mismatches.push(format!("{}: not disassembled by javap", path)); continue } };
+            let actual = describe(&src.read_class(path).unwrap_or_else(|err| panic!("error reading {}: {}", path, err)));
+            if &actual != expected {
+                mismatches.push(format!("{}:\n    javap:       {:?}\n    jreflection: {:?}", path, expected, actual));
+            }
+        }
+    }
+    let _ = std::fs::remove_dir_all(&dir);
+
+    println!("{}: {} classes cross-checked against javap", jdk.display(), paths.len());
+    assert!(mismatches.is_empty(), "{} of {} classes differ from javap:\n{}", mismatches.len(), paths.len(), mismatches.join("\n"));
+}
+
+/// One line for the class, then one line per member:  `"name descriptor modifiers..."`, in class file order.
+fn describe(class: &Class) -> Vec<String> {
+    let kind = if class.is_interface() { "interface" } else { "class" };
+    let shown = if class.is_interface() { 0x0001 | 0x0010 } else { 0x0001 | 0x0010 | 0x0400 }; // javap doesn't print "abstract interface"
+    let mut lines = vec![line(&class.path.as_str().replace('/', "."), kind, &modifiers(class.flags.bits(), !shown))];
+    for f in class.fields.iter() {
+        let flags = f.flags.bits();
+        lines.push(line(&f.name, f.descriptor_str(), &modifiers(flags, 0x0020 | 0x0100 | 0x0400 | 0x0800)));
+    }
+    for m in class.methods.iter() {
+        let flags = m.flags.bits();
+        lines.push(line(&m.name, m.descriptor_str(), &modifiers(flags, 0x0040 | 0x0080)));
+    }
+    lines
+}
+
+/// Which of [MODIFIERS] `flags` has, ignoring the bits of `ignore` (which mean different things for fields and methods.)
+fn modifiers(flags: u16, ignore: u16) -> Vec<bool> {
+    let flags = flags & !ignore;
+    [0x0001, 0x0004, 0x0002, 0x0008, 0x0010, 0x0020, 0x0040, 0x0080, 0x0100, 0x0400, 0x0800].iter().map(|bit| flags & bit != 0).collect()
+}
+
+fn line(name: &str, descriptor: &str, modifiers: &[bool]) -> String {
+    let mut line = format!("{} {}", name, descriptor);
+    for (modifier, _) in MODIFIERS.iter().zip(modifiers.iter()).filter(|(_, has)| **has) {
+        line.push(' ');
+        line.push_str(modifier);
+    }
+    line
+}
+
+/// Parse `javap -p -s` output into [describe]-style lines, keyed by JNI path.
+fn parse_javap(output: &str) -> BTreeMap<String, Vec<String>> {
+    let mut classes = BTreeMap::new();
+    let mut class = None;
+    let mut lines = output.lines();
+    while let Some(l) = lines.next() {
+        if l.starts_with("Compiled from") || l.trim().is_empty() {
+            continue;
+        } else if l == "}" {
+            class = None;
+        } else if !l.starts_with(' ') {
+            // e.g. "public final class java.util.concurrent.TimeUnit extends java.lang.Enum<java.util.concurrent.TimeUnit> {"
+            let words = l.split(' ').collect::<Vec<_>>();
+            let kind = words.iter().position(|w| *w == "class" || *w == "interface").unwrap_or_else(|| panic!("unexpected javap class line: {}", l));
+            let name = words[kind+1].split('<').next().unwrap();
+            let mods = MODIFIERS.iter().map(|m| words[..kind].contains(m)).collect::<Vec<_>>();
+            let path = name.replace('.', "/");
+            classes.insert(path.clone(), vec![line(name, words[kind], &mods)]);
+            class = Some((path, name.to_owned()));
+        } else if l.starts_with("  ") && !l.starts_with("   ") {
+            // e.g. "  public final void wait(long, int) throws java.lang.InterruptedException;" then "    descriptor: (JI)V"
+            let (path, dotted) = class.as_ref().unwrap_or_else(|| panic!("javap member outside of a class: {}", l));
+            let decl = l.trim().trim_end_matches(';');
+            let descriptor = lines.next().map(|d| d.trim()).filter(|d| d.starts_with("descriptor: ")).unwrap_or_else(|| panic!("javap member without a descriptor: {}", l));
+            let descriptor = &descriptor["descriptor: ".len()..];
+            let (name, before) = if decl == "static {}" {
+                ("<clinit>", "static")
+            } else if let Some(paren) = decl.find('(') {
+                let before = &decl[..paren];
+                let name = before.rsplit(' ').next().unwrap();
+                (if name == dotted { "<init>" } else { name }, before)
+            } else {
+                (decl.rsplit(' ').next().unwrap(), decl)
+            };
+            let words = before.split(' ').collect::<Vec<_>>();
+            let mods = MODIFIERS.iter().map(|m| words.contains(m)).collect::<Vec<_>>();
+            classes.get_mut(path).unwrap().push(line(name, descriptor, &mods));
+        }
+    }
+    classes
+}
@@ -1,4 +1,4 @@
-use jreflection::{Class, Source};
+use jreflection::{android, Class, Source};
 use std::path::*;
 use std::time::*;
 
@@ -24,13 +24,10 @@ use std::time::*;
     }
 }
 
-#[cfg(windows)] #[test] fn local_app_data_android() {
-    let android_platforms = local_app_data().join("Android").join("Sdk").join("platforms");
-    if !android_platforms.exists() { return; }
-    let subdirs = android_platforms.read_dir().unwrap_or_else(|err| panic!("error reading {}: {}", android_platforms.display(), err));
-    for subdir in subdirs.filter_map(|de| de.ok()) {
-        let path = subdir.path().join("android.jar");
-        test_jdk(&path, || Source::from_jar(&path).unwrap());
+#[test] fn android_sdk_platforms() {
+    let sdk = match android::discover_sdk() { Ok(sdk) => sdk, Err(_) => return };
+    for platform in sdk.platforms().unwrap_or_else(|err| panic!("error enumerating platforms of {}: {}", sdk.path().display(), err)) {
+        test_jdk(&platform.jar, || platform.source().unwrap());
     }
 }
 
@@ -39,10 +36,6 @@ use std::time::*;
     PathBuf::from(std::env::var_os(pf).or_else(|| std::env::var_os("ProgramFiles")).expect("%ProgramFiles% not set"))
 }
 
-#[cfg(windows)] fn local_app_data() -> PathBuf {
-    PathBuf::from(std::env::var_os("LOCALAPPDATA").expect("%LOCALAPPDATA% not set"))
-}
-
 fn test_jdk(path: &Path, src_from: impl FnOnce() -> Source) {
     let stdout;
     let _lock;
@@ -55,6 +48,8 @@ fn test_jdk(path: &Path, src_from: impl FnOnce() -> Source) {
     let jdk     = bench("    load jdk:                              ", || src_from());
     let _obj    = bench("    deserialize         java/lang/Object:  ", || jdk.read_class("java/lang/Object").unwrap());
     let _obj2   = bench("    fail to deserialize java/lang/Object2: ", || jdk.read_class("java/lang/Object2").unwrap_err());
+    let bytes   = bench("    read bytes of       java/lang/Object:  ", || jdk.read_class_bytes("java/lang/Object").unwrap());
+    assert_eq!(&bytes[..4], &[0xCA, 0xFE, 0xBA, 0xBE]);
     let classes = bench("    gather all classes of jdk:             ", || jdk.classes::<Vec<String>>().unwrap());
     let classes = bench("    deserialize all classes of jdk:        ", || classes.iter().map(|c| jdk.read_class(&c).unwrap()).collect::<Vec<Class>>());
     let _ = classes;